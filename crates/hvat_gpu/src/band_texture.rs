@@ -0,0 +1,284 @@
+//! Band texture storage formats and packing.
+//!
+//! Each band is stored rescaled to its own value range (`BandRange`), so the
+//! full precision of the texture format is spent on values that actually occur
+//! in the band. The hyperspectral shader maps samples back to the original
+//! values before applying any adjustments.
+
+/// Storage format of the hyperspectral band texture array.
+///
+/// Ordered from most to least precise; `select` picks the first one the
+/// adapter can sample with linear filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandTextureFormat {
+    /// 16-bit unsigned normalized (native, needs `TEXTURE_FORMAT_16BIT_NORM`)
+    Rgba16Unorm,
+    /// 16-bit float (WebGPU and WebGL2)
+    Rgba16Float,
+    /// 8-bit fallback for adapters without filterable 16-bit formats
+    Rgba8Unorm,
+}
+
+impl BandTextureFormat {
+    /// Pick the most precise format the adapter supports for band textures.
+    pub fn select(adapter: &wgpu::Adapter) -> Self {
+        let usable = |format: Self| {
+            let features = adapter.get_texture_format_features(format.texture_format());
+            adapter.features().contains(format.required_features())
+                && features
+                    .allowed_usages
+                    .contains(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST)
+                && features
+                    .flags
+                    .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE)
+        };
+        [Self::Rgba16Unorm, Self::Rgba16Float]
+            .into_iter()
+            .find(|&format| usable(format))
+            .unwrap_or(Self::Rgba8Unorm)
+    }
+
+    /// The wgpu texture format.
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            Self::Rgba16Unorm => wgpu::TextureFormat::Rgba16Unorm,
+            Self::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+            Self::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    /// Device features that must be enabled to use this format.
+    pub fn required_features(self) -> wgpu::Features {
+        match self {
+            Self::Rgba16Unorm => wgpu::Features::TEXTURE_FORMAT_16BIT_NORM,
+            Self::Rgba16Float | Self::Rgba8Unorm => wgpu::Features::empty(),
+        }
+    }
+
+    /// Size of one RGBA texel in bytes.
+    pub fn bytes_per_texel(self) -> u32 {
+        match self {
+            Self::Rgba16Unorm | Self::Rgba16Float => 8,
+            Self::Rgba8Unorm => 4,
+        }
+    }
+
+    /// Get the display name for this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rgba16Unorm => "RGBA16 Unorm",
+            Self::Rgba16Float => "RGBA16 Float",
+            Self::Rgba8Unorm => "RGBA8 Unorm",
+        }
+    }
+
+    /// Write one channel value (0.0-1.0) as little-endian texel bytes.
+    fn write_channel(self, value: f32, out: &mut [u8]) {
+        match self {
+            Self::Rgba16Unorm => {
+                let v = (value * 65535.0).round() as u16;
+                out[..2].copy_from_slice(&v.to_le_bytes());
+            }
+            Self::Rgba16Float => {
+                let v = half::f16::from_f32(value);
+                out[..2].copy_from_slice(&v.to_bits().to_le_bytes());
+            }
+            Self::Rgba8Unorm => out[0] = (value * 255.0).round() as u8,
+        }
+    }
+}
+
+/// Original value range of a band.
+///
+/// Textures store `(value - min) / (max - min)`; the shader decodes samples
+/// with the same range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl BandRange {
+    /// The 0.0-1.0 range (values stored unchanged).
+    pub const UNIT: Self = Self { min: 0.0, max: 1.0 };
+
+    /// Range of the finite values in `values` (`UNIT` if there are none).
+    pub fn of(values: &[f32]) -> Self {
+        let (min, max) = values
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+                (lo.min(v), hi.max(v))
+            });
+        if min <= max {
+            Self { min, max }
+        } else {
+            Self::UNIT
+        }
+    }
+
+    /// Width of the range, never zero so constant bands stay decodable.
+    pub fn span(&self) -> f32 {
+        let span = self.max - self.min;
+        if span > 0.0 {
+            span
+        } else {
+            1.0
+        }
+    }
+
+    /// Map a band value to 0.0-1.0 for storage.
+    pub fn encode(&self, value: f32) -> f32 {
+        let t = (value - self.min) / self.span();
+        if t.is_nan() {
+            0.0
+        } else {
+            t.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Map a stored 0.0-1.0 value back to the band value.
+    pub fn decode(&self, t: f32) -> f32 {
+        self.min + t * self.span()
+    }
+}
+
+impl Default for BandRange {
+    fn default() -> Self {
+        Self::UNIT
+    }
+}
+
+/// How band values are stored in a texture array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandEncoding {
+    /// Texel format of the texture array
+    pub format: BandTextureFormat,
+    /// Original value range of each band (one entry per band)
+    pub ranges: Vec<BandRange>,
+}
+
+impl BandEncoding {
+    /// Encoding for `bands` in `format`, using each band's own value range.
+    pub fn for_bands(bands: &[Vec<f32>], format: BandTextureFormat) -> Self {
+        Self {
+            format,
+            ranges: bands.iter().map(|band| BandRange::of(band)).collect(),
+        }
+    }
+
+    /// Encoding for data already in 0.0-1.0 (e.g. 8-bit RGB images).
+    pub fn unit(num_bands: usize, format: BandTextureFormat) -> Self {
+        Self {
+            format,
+            ranges: vec![BandRange::UNIT; num_bands],
+        }
+    }
+
+    /// Number of bands.
+    pub fn num_bands(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Range of `band`, `UNIT` if out of bounds.
+    pub fn range(&self, band: usize) -> BandRange {
+        self.ranges.get(band).copied().unwrap_or_default()
+    }
+
+    /// Pack up to 4 bands of texture layer `layer_index` into RGBA texels.
+    ///
+    /// Bands whose length differs from `pixel_count` are left zeroed.
+    pub fn pack_layer(&self, bands: &[Vec<f32>], layer_index: u32, pixel_count: usize) -> Vec<u8> {
+        let texel_size = self.format.bytes_per_texel() as usize;
+        let channel_size = texel_size / 4;
+        let mut data = vec![0u8; pixel_count * texel_size];
+
+        let base_band = layer_index as usize * 4;
+        for channel in 0..4 {
+            let band_idx = base_band + channel;
+            let Some(band) = bands.get(band_idx) else {
+                break;
+            };
+            if band.len() != pixel_count {
+                continue;
+            }
+            let range = self.range(band_idx);
+            for (pixel, &value) in band.iter().enumerate() {
+                let offset = pixel * texel_size + channel * channel_size;
+                self.format
+                    .write_channel(range.encode(value), &mut data[offset..]);
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_band_range() {
+        let range = BandRange::of(&[0.25, f32::NAN, 0.5, 0.125]);
+        assert_eq!(
+            range,
+            BandRange {
+                min: 0.125,
+                max: 0.5
+            }
+        );
+        assert_eq!(range.encode(0.5), 1.0);
+        assert_eq!(range.encode(0.3125), 0.5);
+        assert_eq!(range.decode(0.5), 0.3125);
+        assert_eq!(range.encode(f32::NAN), 0.0);
+
+        // Constant and empty bands stay decodable
+        let constant = BandRange::of(&[2.0, 2.0]);
+        assert_eq!(constant.encode(2.0), 0.0);
+        assert_eq!(constant.decode(0.0), 2.0);
+        assert_eq!(BandRange::of(&[]), BandRange::UNIT);
+    }
+
+    #[test]
+    fn test_pack_layer_16bit_keeps_precision() {
+        // 12-bit sensor values stored in u16 and normalized by 65535
+        let bands = vec![(0..4096).map(|v| v as f32 / 65535.0).collect::<Vec<f32>>()];
+        let encoding = BandEncoding::for_bands(&bands, BandTextureFormat::Rgba16Unorm);
+        let data = encoding.pack_layer(&bands, 0, 4096);
+        assert_eq!(data.len(), 4096 * 8);
+
+        let red: Vec<u16> = data
+            .chunks_exact(8)
+            .map(|texel| u16::from_le_bytes([texel[0], texel[1]]))
+            .collect();
+        let mut distinct = red.clone();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 4096);
+        assert_eq!(red[4095], u16::MAX);
+        // Unused channels stay zero
+        assert!(data
+            .chunks_exact(8)
+            .all(|texel| texel[2..].iter().all(|&b| b == 0)));
+    }
+
+    #[test]
+    fn test_pack_layer_float_and_fallback() {
+        let bands = vec![vec![0.0, 0.5, 1.0], vec![10.0, 20.0, 30.0]];
+
+        let encoding = BandEncoding::for_bands(&bands, BandTextureFormat::Rgba16Float);
+        let data = encoding.pack_layer(&bands, 0, 3);
+        let green = |pixel: usize| {
+            half::f16::from_le_bytes([data[pixel * 8 + 2], data[pixel * 8 + 3]]).to_f32()
+        };
+        assert_eq!(encoding.range(1).decode(green(1)), 20.0);
+        assert_eq!(encoding.range(1).decode(green(2)), 30.0);
+
+        let encoding = BandEncoding::unit(2, BandTextureFormat::Rgba8Unorm);
+        let data = encoding.pack_layer(&bands, 0, 3);
+        assert_eq!(&data[..4], &[0, 255, 0, 0]);
+        assert_eq!(&data[4..6], &[128, 255]);
+
+        // Layers past the last band are empty
+        assert!(encoding.pack_layer(&bands, 1, 3).iter().all(|&b| b == 0));
+    }
+}
@@ -53,6 +53,16 @@ pub mod hyperspectral {
     pub const UNIFORM_ADJUSTMENTS_BINDING: u32 = 1;
     /// Binding 2 in group 0: Band selection uniform
     pub const UNIFORM_BAND_SELECTION_BINDING: u32 = 2;
+    /// Binding 3 in group 0: Contrast stretch uniform
+    pub const UNIFORM_STRETCH_BINDING: u32 = 3;
+    /// Binding 4 in group 0: Band-math value ranges uniform
+    pub const UNIFORM_BAND_MATH_BINDING: u32 = 4;
+    /// Binding 5 in group 0: Colormap uniform
+    pub const UNIFORM_COLORMAP_BINDING: u32 = 5;
+    /// Binding 6 in group 0: Colormap lookup texture
+    pub const COLORMAP_TEXTURE_BINDING: u32 = 6;
+    /// Binding 7 in group 0: Colormap sampler
+    pub const COLORMAP_SAMPLER_BINDING: u32 = 7;
 
     /// Group 1: Band texture array
     pub const BAND_TEXTURE_GROUP: u32 = 1;
@@ -84,4 +94,21 @@ mod tests {
         assert_eq!(color::UNIFORM_GROUP, 0);
         assert_eq!(color::UNIFORM_TRANSFORM_BINDING, 0);
     }
+
+    #[test]
+    fn test_hyperspectral_bindings_are_sequential() {
+        assert_eq!(hyperspectral::UNIFORM_GROUP, 0);
+        assert_eq!(hyperspectral::UNIFORM_TRANSFORM_BINDING, 0);
+        assert_eq!(hyperspectral::UNIFORM_ADJUSTMENTS_BINDING, 1);
+        assert_eq!(hyperspectral::UNIFORM_BAND_SELECTION_BINDING, 2);
+        assert_eq!(hyperspectral::UNIFORM_STRETCH_BINDING, 3);
+        assert_eq!(hyperspectral::UNIFORM_BAND_MATH_BINDING, 4);
+        assert_eq!(hyperspectral::UNIFORM_COLORMAP_BINDING, 5);
+        assert_eq!(hyperspectral::COLORMAP_TEXTURE_BINDING, 6);
+        assert_eq!(hyperspectral::COLORMAP_SAMPLER_BINDING, 7);
+
+        assert_eq!(hyperspectral::BAND_TEXTURE_GROUP, 1);
+        assert_eq!(hyperspectral::BAND_TEXTURE_ARRAY_BINDING, 0);
+        assert_eq!(hyperspectral::BAND_SAMPLER_BINDING, 1);
+    }
 }
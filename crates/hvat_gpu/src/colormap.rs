@@ -0,0 +1,162 @@
+//! Colormaps for pseudo-color rendering of a single band or index.
+//!
+//! Each colormap is defined by evenly spaced control colors and interpolated
+//! linearly. The hyperspectral pipeline samples it from a
+//! `COLORMAP_LUT_SIZE` x 1 lookup texture (a 2D texture, as WebGL2 has no 1D
+//! textures).
+
+/// Number of entries in the colormap lookup texture.
+pub const COLORMAP_LUT_SIZE: u32 = 256;
+
+/// Selectable colormaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Colormap {
+    /// Perceptually uniform dark blue - green - yellow
+    #[default]
+    Viridis,
+    /// Perceptually uniform black - purple - orange - light yellow
+    Magma,
+    /// Rainbow dark blue - cyan - yellow - dark red
+    Jet,
+    /// Blue - light gray - red, for values around a midpoint
+    Diverging,
+}
+
+const VIRIDIS: &[[u8; 3]] = &[
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+
+const MAGMA: &[[u8; 3]] = &[
+    [0, 0, 4],
+    [28, 16, 68],
+    [79, 18, 123],
+    [129, 37, 129],
+    [181, 54, 122],
+    [229, 80, 100],
+    [251, 135, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+const JET: &[[u8; 3]] = &[
+    [0, 0, 128],
+    [0, 0, 255],
+    [0, 128, 255],
+    [0, 255, 255],
+    [128, 255, 128],
+    [255, 255, 0],
+    [255, 128, 0],
+    [255, 0, 0],
+    [128, 0, 0],
+];
+
+const DIVERGING: &[[u8; 3]] = &[
+    [59, 76, 192],
+    [98, 130, 234],
+    [141, 176, 254],
+    [184, 208, 249],
+    [221, 221, 221],
+    [245, 196, 173],
+    [244, 154, 123],
+    [222, 96, 77],
+    [180, 4, 38],
+];
+
+impl Colormap {
+    /// All colormaps, in display order.
+    pub fn all() -> &'static [Colormap] {
+        &[
+            Colormap::Viridis,
+            Colormap::Magma,
+            Colormap::Jet,
+            Colormap::Diverging,
+        ]
+    }
+
+    /// Get the display name for this colormap.
+    pub fn name(self) -> &'static str {
+        match self {
+            Colormap::Viridis => "Viridis",
+            Colormap::Magma => "Magma",
+            Colormap::Jet => "Jet",
+            Colormap::Diverging => "Diverging",
+        }
+    }
+
+    fn control_colors(self) -> &'static [[u8; 3]] {
+        match self {
+            Colormap::Viridis => VIRIDIS,
+            Colormap::Magma => MAGMA,
+            Colormap::Jet => JET,
+            Colormap::Diverging => DIVERGING,
+        }
+    }
+
+    /// Color (RGB, 0.0-1.0) at position `t` (clamped to 0.0-1.0).
+    pub fn sample(self, t: f32) -> [f32; 3] {
+        let colors = self.control_colors();
+        let pos = t.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+        let i0 = (pos.floor() as usize).min(colors.len() - 1);
+        let i1 = (i0 + 1).min(colors.len() - 1);
+        let f = pos - i0 as f32;
+        std::array::from_fn(|c| {
+            let a = colors[i0][c] as f32 / 255.0;
+            let b = colors[i1][c] as f32 / 255.0;
+            a + (b - a) * f
+        })
+    }
+
+    /// RGBA8 texels of the lookup texture (`COLORMAP_LUT_SIZE` entries).
+    pub fn lut(self) -> Vec<u8> {
+        (0..COLORMAP_LUT_SIZE)
+            .flat_map(|i| {
+                let [r, g, b] = self.sample(i as f32 / (COLORMAP_LUT_SIZE - 1) as f32);
+                let to_u8 = |v: f32| (v * 255.0).round() as u8;
+                [to_u8(r), to_u8(g), to_u8(b), 255]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_endpoints_and_clamping() {
+        for &colormap in Colormap::all() {
+            let colors = colormap.control_colors();
+            let first = colors[0].map(|v| v as f32 / 255.0);
+            let last = colors[colors.len() - 1].map(|v| v as f32 / 255.0);
+            assert_eq!(colormap.sample(0.0), first);
+            assert_eq!(colormap.sample(1.0), last);
+            assert_eq!(colormap.sample(-1.0), first);
+            assert_eq!(colormap.sample(2.0), last);
+        }
+    }
+
+    #[test]
+    fn test_sample_interpolates() {
+        // Halfway between the first two Jet control colors
+        let [r, g, b] = Colormap::Jet.sample(0.0625);
+        assert_eq!(r, 0.0);
+        assert_eq!(g, 0.0);
+        assert!((b - (128.0 + 255.0) / 2.0 / 255.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_lut() {
+        let lut = Colormap::Viridis.lut();
+        assert_eq!(lut.len(), COLORMAP_LUT_SIZE as usize * 4);
+        assert_eq!(&lut[..4], &[68, 1, 84, 255]);
+        assert_eq!(&lut[lut.len() - 4..], &[253, 231, 37, 255]);
+    }
+}
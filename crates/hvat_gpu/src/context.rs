@@ -2,6 +2,7 @@ use std::sync::Arc;
 use wgpu;
 use winit::window::Window;
 
+use crate::band_texture::BandTextureFormat;
 use crate::config::GpuConfig;
 use crate::error::Result;
 
@@ -31,6 +32,8 @@ pub struct GpuContext {
     pub surface: wgpu::Surface<'static>,
     pub surface_config: wgpu::SurfaceConfiguration,
     pub config: GpuConfig,
+    /// Most precise band texture format supported by the adapter
+    pub band_format: BandTextureFormat,
 }
 
 impl GpuContext {
@@ -139,11 +142,14 @@ impl GpuContext {
         // WebGL doesn't support compute shaders, so we can't use Limits::default()
         let limits = wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
 
+        // 16-bit band textures where available, 8-bit otherwise
+        let band_format = BandTextureFormat::select(&adapter);
+
         // Request device and queue
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("Main Device"),
-                required_features: wgpu::Features::empty(),
+                required_features: band_format.required_features(),
                 required_limits: limits,
                 memory_hints: wgpu::MemoryHints::default(),
                 experimental_features: Default::default(),
@@ -187,6 +193,7 @@ impl GpuContext {
             surface,
             surface_config,
             config,
+            band_format,
         })
     }
 
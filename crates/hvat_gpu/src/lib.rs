@@ -1,19 +1,30 @@
+pub mod band_texture;
 pub mod bindings;
+pub mod colormap;
 pub mod config;
 pub mod context;
 pub mod error;
 pub mod pipeline;
 pub mod texture;
+pub mod tiles;
 pub mod uniform;
 pub mod vertex;
 
+pub use band_texture::{BandEncoding, BandRange, BandTextureFormat};
+pub use colormap::{Colormap, COLORMAP_LUT_SIZE};
 pub use config::{ClearColor, GpuConfig, RenderConfig, TextureConfig};
 pub use context::GpuContext;
 pub use error::{GpuError, Result};
 pub use pipeline::{
     BindGroupLayoutBuilder, ColorPipeline, HyperspectralGpuData, HyperspectralPipeline, Pipeline,
-    PipelineBuilder, TexturePipeline,
+    PipelineBuilder, TexturePipeline, TileQuad,
 };
 pub use texture::Texture;
-pub use uniform::{BandSelectionUniform, ImageAdjustments, TransformUniform};
+pub use tiles::{
+    tile_bands, TileCache, TileKey, TileLayout, TileView, TiledGpuData, TILE_BORDER, TILE_SIZE,
+};
+pub use uniform::{
+    BandMathUniform, BandSelectionUniform, ColormapUniform, ImageAdjustments, StretchUniform,
+    TransformUniform, MAX_BAND_MATH_BANDS, STRETCH_LUT_SIZE,
+};
 pub use vertex::{ColorVertex, Vertex};
@@ -6,14 +6,33 @@
 //!
 //! This design supports an arbitrary number of bands, limited only by GPU texture
 //! array size (typically 2048 layers = 8192 bands on most hardware).
+//!
+//! Bands are stored at 16-bit precision where the adapter allows it (see
+//! `BandTextureFormat`), each rescaled to its own value range.
+//!
+//! Besides the band composite, the pipeline can render a band-math shader:
+//! generated WGSL computing the output channels from arbitrary bands, compiled
+//! together with the compositing shader (see `set_band_math_shader`).
+//!
+//! A single band or index can be shown in pseudo-color: the red channel value
+//! is mapped through a colormap lookup texture (see `set_colormap`).
+//!
+//! Images too large for one texture array are split into tiles (see
+//! `crate::tiles`), which `render_tiles` composites into one target.
 
 use wgpu::util::DeviceExt;
 
 use super::{BindGroupLayoutBuilder, Pipeline, PipelineBuilder};
+use crate::band_texture::BandEncoding;
 use crate::bindings::hyperspectral as bindings;
+use crate::colormap::{Colormap, COLORMAP_LUT_SIZE};
 use crate::config::TextureConfig;
 use crate::context::GpuContext;
-use crate::uniform::{BandSelectionUniform, ImageAdjustments, TransformUniform};
+use crate::error::{GpuError, Result};
+use crate::uniform::{
+    BandMathUniform, BandSelectionUniform, ColormapUniform, ImageAdjustments, StretchUniform,
+    TransformUniform,
+};
 use crate::vertex::Vertex;
 
 /// Hyperspectral image data stored on GPU using a texture array.
@@ -37,6 +56,8 @@ pub struct HyperspectralGpuData {
     pub num_bands: usize,
     /// Number of texture array layers (ceil(num_bands / 4))
     pub num_layers: u32,
+    /// Texel format and original value range of each band
+    pub encoding: BandEncoding,
 }
 
 impl HyperspectralGpuData {
@@ -47,16 +68,16 @@ impl HyperspectralGpuData {
     /// * `texture` - Pre-created and uploaded texture array
     /// * `width` - Image width in pixels
     /// * `height` - Image height in pixels
-    /// * `num_bands` - Number of spectral bands
     /// * `num_layers` - Number of texture array layers
+    /// * `encoding` - How the bands were packed (one range per band)
     /// * `bind_group_layout` - Layout from HyperspectralPipeline
     pub fn from_texture(
         ctx: &GpuContext,
         texture: wgpu::Texture,
         width: u32,
         height: u32,
-        num_bands: usize,
         num_layers: u32,
+        encoding: BandEncoding,
         bind_group_layout: &wgpu::BindGroupLayout,
     ) -> Self {
         // Create view for the entire texture array
@@ -102,16 +123,20 @@ impl HyperspectralGpuData {
             bind_group,
             width,
             height,
-            num_bands,
+            num_bands: encoding.num_bands(),
             num_layers,
+            encoding,
         }
     }
 
     /// Upload hyperspectral band data to GPU.
     ///
+    /// Bands are stored in the context's `band_format`, each rescaled to its
+    /// own value range so no precision is lost to unused parts of the range.
+    ///
     /// # Arguments
     /// * `ctx` - GPU context
-    /// * `bands` - Vector of band data, each band is a Vec<f32> with width*height values (0.0-1.0)
+    /// * `bands` - Vector of band data, each band is a Vec<f32> with width*height values
     /// * `width` - Image width in pixels
     /// * `height` - Image height in pixels
     /// * `bind_group_layout` - Layout from HyperspectralPipeline
@@ -121,6 +146,22 @@ impl HyperspectralGpuData {
         width: u32,
         height: u32,
         bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let encoding = BandEncoding::for_bands(bands, ctx.band_format);
+        Self::from_bands_with_encoding(ctx, bands, width, height, encoding, bind_group_layout)
+    }
+
+    /// Upload band data packed with a given encoding.
+    ///
+    /// Used for parts of a larger image (see `TiledGpuData`), where every
+    /// part must share the value ranges of the whole image.
+    pub fn from_bands_with_encoding(
+        ctx: &GpuContext,
+        bands: &[Vec<f32>],
+        width: u32,
+        height: u32,
+        encoding: BandEncoding,
+        bind_group_layout: &wgpu::BindGroupLayout,
     ) -> Self {
         let num_bands = bands.len();
         let num_layers = ((num_bands + 3) / 4) as u32; // ceil(num_bands / 4)
@@ -145,33 +186,14 @@ impl HyperspectralGpuData {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
+            format: encoding.format.texture_format(),
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
         // Pack bands into RGBA layers and upload each layer
         for layer_idx in 0..num_layers {
-            let base_band = (layer_idx * 4) as usize;
-            let mut rgba_data = vec![0u8; pixel_count * 4];
-
-            // Pack up to 4 bands into this layer's RGBA channels
-            for channel_idx in 0..4 {
-                let band_idx = base_band + channel_idx;
-                if band_idx >= num_bands {
-                    break;
-                }
-
-                let band = &bands[band_idx];
-                if band.len() != pixel_count {
-                    continue; // Skip bands with incorrect size
-                }
-
-                for (pixel_idx, &value) in band.iter().enumerate() {
-                    let byte_value = (value.clamp(0.0, 1.0) * 255.0) as u8;
-                    rgba_data[pixel_idx * 4 + channel_idx] = byte_value;
-                }
-            }
+            let texel_data = encoding.pack_layer(bands, layer_idx, pixel_count);
 
             // Write this layer to the texture array
             ctx.queue.write_texture(
@@ -185,10 +207,10 @@ impl HyperspectralGpuData {
                     },
                     aspect: wgpu::TextureAspect::All,
                 },
-                &rgba_data,
+                &texel_data,
                 wgpu::TexelCopyBufferLayout {
                     offset: 0,
-                    bytes_per_row: Some(4 * width),
+                    bytes_per_row: Some(encoding.format.bytes_per_texel() * width),
                     rows_per_image: Some(height),
                 },
                 wgpu::Extent3d {
@@ -199,55 +221,48 @@ impl HyperspectralGpuData {
             );
         }
 
-        // Create view for the entire texture array
-        let texture_view = texture_array.create_view(&wgpu::TextureViewDescriptor {
-            label: Some("Hyperspectral Band Texture Array View"),
-            dimension: Some(wgpu::TextureViewDimension::D2Array),
-            ..Default::default()
-        });
-
-        // Create sampler with linear filtering
-        let config = TextureConfig::linear();
-        let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Band Sampler"),
-            address_mode_u: config.address_mode_u,
-            address_mode_v: config.address_mode_v,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: config.mag_filter,
-            min_filter: config.min_filter,
-            mipmap_filter: config.mipmap_filter,
-            ..Default::default()
-        });
-
-        // Create bind group
-        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Hyperspectral Band Bind Group"),
-            layout: bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: bindings::BAND_TEXTURE_ARRAY_BINDING,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: bindings::BAND_SAMPLER_BINDING,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
-
-        Self {
+        Self::from_texture(
+            ctx,
             texture_array,
-            texture_view,
-            sampler,
-            bind_group,
             width,
             height,
-            num_bands,
             num_layers,
-        }
+            encoding,
+            bind_group_layout,
+        )
     }
 }
 
+/// Area of a render target drawn from one band texture (see
+/// `HyperspectralPipeline::render_tiles`).
+pub struct TileQuad<'a> {
+    /// Band textures of the area
+    pub bind_group: &'a wgpu::BindGroup,
+    /// Target area in clip space (left, top, right, bottom)
+    pub position: [f32; 4],
+    /// Texture area in texture coordinates (left, top, right, bottom)
+    pub tex_coords: [f32; 4],
+}
+
+/// Source of the shared compositing shader.
+const HYPERSPECTRAL_SHADER: &str = include_str!("../shaders/hyperspectral.wgsl");
+
+/// Color of target areas not covered by the image.
+const BACKGROUND_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 1.0,
+};
+
+/// Entry point appended to band-math shaders.
+const BAND_MATH_ENTRY_POINT: &str = "
+@fragment
+fn fs_band_math(in: VertexOutput) -> @location(0) vec4<f32> {
+    return display_color(band_math(in.tex_coords));
+}
+";
+
 /// Hyperspectral rendering pipeline.
 ///
 /// Composites hyperspectral bands into RGB output on the GPU.
@@ -255,13 +270,21 @@ impl HyperspectralGpuData {
 /// not regenerating textures.
 pub struct HyperspectralPipeline {
     pub render_pipeline: wgpu::RenderPipeline,
+    /// Pipeline of the active band-math shader, used instead of the composite
+    pub band_math_pipeline: Option<wgpu::RenderPipeline>,
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub num_indices: u32,
     pub uniform_buffer: wgpu::Buffer,
     pub adjustments_buffer: wgpu::Buffer,
     pub band_selection_buffer: wgpu::Buffer,
+    pub stretch_buffer: wgpu::Buffer,
+    pub band_math_buffer: wgpu::Buffer,
+    pub colormap_buffer: wgpu::Buffer,
+    /// Colormap lookup texture (`COLORMAP_LUT_SIZE` x 1 RGBA8)
+    pub colormap_texture: wgpu::Texture,
     pub uniform_bind_group: wgpu::BindGroup,
+    pub uniform_bind_group_layout: wgpu::BindGroupLayout,
     pub band_texture_bind_group_layout: wgpu::BindGroupLayout,
 }
 
@@ -271,9 +294,7 @@ impl HyperspectralPipeline {
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("Hyperspectral Shader"),
-                source: wgpu::ShaderSource::Wgsl(
-                    include_str!("../shaders/hyperspectral.wgsl").into(),
-                ),
+                source: wgpu::ShaderSource::Wgsl(HYPERSPECTRAL_SHADER.into()),
             });
 
         // Create uniform buffers
@@ -304,6 +325,55 @@ impl HyperspectralPipeline {
                     usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 });
 
+        let stretch = StretchUniform::new();
+        let stretch_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Hyperspectral Stretch Buffer"),
+                contents: bytemuck::cast_slice(&[stretch]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let band_math_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Band Math Buffer"),
+                contents: bytemuck::cast_slice(&[BandMathUniform::default()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let colormap_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Colormap Buffer"),
+                contents: bytemuck::cast_slice(&[ColormapUniform::default()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let colormap_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Colormap Texture"),
+            size: wgpu::Extent3d {
+                width: COLORMAP_LUT_SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let colormap_view = colormap_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let colormap_sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Colormap Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
         // Create bind group layouts
         let uniform_bind_group_layout = BindGroupLayoutBuilder::new(&ctx.device)
             .with_label("Hyperspectral Uniform Bind Group Layout")
@@ -319,6 +389,26 @@ impl HyperspectralPipeline {
                 bindings::UNIFORM_BAND_SELECTION_BINDING,
                 wgpu::ShaderStages::FRAGMENT,
             )
+            .add_uniform_buffer(
+                bindings::UNIFORM_STRETCH_BINDING,
+                wgpu::ShaderStages::FRAGMENT,
+            )
+            .add_uniform_buffer(
+                bindings::UNIFORM_BAND_MATH_BINDING,
+                wgpu::ShaderStages::FRAGMENT,
+            )
+            .add_uniform_buffer(
+                bindings::UNIFORM_COLORMAP_BINDING,
+                wgpu::ShaderStages::FRAGMENT,
+            )
+            .add_texture_2d(
+                bindings::COLORMAP_TEXTURE_BINDING,
+                wgpu::ShaderStages::FRAGMENT,
+            )
+            .add_sampler(
+                bindings::COLORMAP_SAMPLER_BINDING,
+                wgpu::ShaderStages::FRAGMENT,
+            )
             .build();
 
         // Use texture 2D array for band data
@@ -348,6 +438,26 @@ impl HyperspectralPipeline {
                     binding: bindings::UNIFORM_BAND_SELECTION_BINDING,
                     resource: band_selection_buffer.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: bindings::UNIFORM_STRETCH_BINDING,
+                    resource: stretch_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: bindings::UNIFORM_BAND_MATH_BINDING,
+                    resource: band_math_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: bindings::UNIFORM_COLORMAP_BINDING,
+                    resource: colormap_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: bindings::COLORMAP_TEXTURE_BINDING,
+                    resource: wgpu::BindingResource::TextureView(&colormap_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: bindings::COLORMAP_SAMPLER_BINDING,
+                    resource: wgpu::BindingResource::Sampler(&colormap_sampler),
+                },
             ],
         });
 
@@ -399,17 +509,74 @@ impl HyperspectralPipeline {
                 usage: wgpu::BufferUsages::INDEX,
             });
 
-        Self {
+        let pipeline = Self {
             render_pipeline,
+            band_math_pipeline: None,
             vertex_buffer,
             index_buffer,
             num_indices: indices.len() as u32,
             uniform_buffer,
             adjustments_buffer,
             band_selection_buffer,
+            stretch_buffer,
+            band_math_buffer,
+            colormap_buffer,
+            colormap_texture,
             uniform_bind_group,
+            uniform_bind_group_layout,
             band_texture_bind_group_layout,
-        }
+        };
+        pipeline.set_colormap(ctx, Colormap::default());
+        pipeline
+    }
+
+    /// Render with a band-math shader instead of the band composite.
+    ///
+    /// `band_math_wgsl` must define
+    /// `fn band_math(tex_coords: vec2<f32>) -> vec3<f32>`, returning the
+    /// decoded channel values; it may use `sample_band`, `decode_band` and
+    /// `band_math_range`. The result goes through the same stretch and
+    /// adjustments as the composite. `None` switches back to the composite.
+    ///
+    /// The combined shader is validated with naga before the device sees it.
+    /// Code that fails validation is rejected with an error (instead of
+    /// reaching the device's uncaptured-error handler) and the composite is
+    /// rendered.
+    pub fn set_band_math_shader(
+        &mut self,
+        ctx: &GpuContext,
+        band_math_wgsl: Option<&str>,
+    ) -> Result<()> {
+        self.band_math_pipeline = None;
+        let Some(band_math_wgsl) = band_math_wgsl else {
+            return Ok(());
+        };
+
+        let source = format!(
+            "{}\n{}\n{}",
+            HYPERSPECTRAL_SHADER, band_math_wgsl, BAND_MATH_ENTRY_POINT
+        );
+        validate_wgsl(&source).map_err(GpuError::ShaderCompilation)?;
+        let shader = ctx
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Band Math Shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+
+        let render_pipeline = PipelineBuilder::new(&ctx.device, ctx.surface_config.format)
+            .with_label("Band Math Render Pipeline")
+            .with_shader(&shader, "vs_main", "fs_band_math")
+            .with_vertex_buffer(Vertex::desc())
+            .with_bind_group_layouts(&[
+                &self.uniform_bind_group_layout,
+                &self.band_texture_bind_group_layout,
+            ])
+            .with_blend_state(wgpu::BlendState::REPLACE)
+            .with_cull_mode(Some(wgpu::Face::Back))
+            .build();
+        self.band_math_pipeline = Some(render_pipeline);
+        Ok(())
     }
 
     /// Update transform uniform.
@@ -436,6 +603,50 @@ impl HyperspectralPipeline {
         );
     }
 
+    /// Update the per-channel contrast stretch.
+    pub fn update_stretch(&self, ctx: &GpuContext, stretch: StretchUniform) {
+        ctx.queue
+            .write_buffer(&self.stretch_buffer, 0, bytemuck::cast_slice(&[stretch]));
+    }
+
+    /// Update the value ranges of the bands sampled by the band-math shader.
+    pub fn update_band_math(&self, ctx: &GpuContext, band_math: BandMathUniform) {
+        ctx.queue.write_buffer(
+            &self.band_math_buffer,
+            0,
+            bytemuck::cast_slice(&[band_math]),
+        );
+    }
+
+    /// Update the colormap range and direction (and whether it is used).
+    pub fn update_colormap(&self, ctx: &GpuContext, colormap: ColormapUniform) {
+        ctx.queue
+            .write_buffer(&self.colormap_buffer, 0, bytemuck::cast_slice(&[colormap]));
+    }
+
+    /// Upload the lookup texture of a colormap.
+    pub fn set_colormap(&self, ctx: &GpuContext, colormap: Colormap) {
+        ctx.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.colormap_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &colormap.lut(),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * COLORMAP_LUT_SIZE),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d {
+                width: COLORMAP_LUT_SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
     /// Render hyperspectral image with current band selection.
     pub fn render(
         &self,
@@ -443,17 +654,7 @@ impl HyperspectralPipeline {
         view: &wgpu::TextureView,
         band_bind_group: &wgpu::BindGroup,
     ) {
-        self.render_with_clear(
-            encoder,
-            view,
-            band_bind_group,
-            Some(wgpu::Color {
-                r: 0.1,
-                g: 0.1,
-                b: 0.1,
-                a: 1.0,
-            }),
-        );
+        self.render_with_clear(encoder, view, band_bind_group, Some(BACKGROUND_COLOR));
     }
 
     /// Render hyperspectral image to a render target with optional clear.
@@ -466,6 +667,82 @@ impl HyperspectralPipeline {
         band_bind_group: &wgpu::BindGroup,
         clear_color: Option<wgpu::Color>,
     ) {
+        let mut render_pass = self.begin_render_pass(encoder, view, clear_color);
+        render_pass.set_bind_group(bindings::BAND_TEXTURE_GROUP, band_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+    }
+
+    /// Render several band textures into one target, each into its own area.
+    ///
+    /// The target is cleared first, and quads are drawn in order, so later
+    /// ones cover earlier ones. This is how the visible tiles of a
+    /// `TiledGpuData` are composited.
+    pub fn render_tiles(
+        &self,
+        ctx: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        quads: &[TileQuad<'_>],
+    ) {
+        // Same corner order and winding as the full-screen quad
+        let vertices: Vec<Vertex> = quads
+            .iter()
+            .flat_map(|quad| {
+                let [left, top, right, bottom] = quad.position;
+                let [u0, v0, u1, v1] = quad.tex_coords;
+                [
+                    Vertex {
+                        position: [left, bottom],
+                        tex_coords: [u0, v1],
+                    },
+                    Vertex {
+                        position: [right, bottom],
+                        tex_coords: [u1, v1],
+                    },
+                    Vertex {
+                        position: [right, top],
+                        tex_coords: [u1, v0],
+                    },
+                    Vertex {
+                        position: [left, top],
+                        tex_coords: [u0, v0],
+                    },
+                ]
+            })
+            .collect();
+
+        if vertices.is_empty() {
+            // Only clear the target
+            self.begin_render_pass(encoder, view, Some(BACKGROUND_COLOR));
+            return;
+        }
+        let vertex_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Hyperspectral Tile Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let mut render_pass = self.begin_render_pass(encoder, view, Some(BACKGROUND_COLOR));
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        for (i, quad) in quads.iter().enumerate() {
+            render_pass.set_bind_group(bindings::BAND_TEXTURE_GROUP, quad.bind_group, &[]);
+            render_pass.draw_indexed(0..self.num_indices, (i * 4) as i32, 0..1);
+        }
+    }
+
+    /// Start a render pass into `view` with the active pipeline and uniforms
+    /// bound.
+    fn begin_render_pass<'e>(
+        &self,
+        encoder: &'e mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        clear_color: Option<wgpu::Color>,
+    ) -> wgpu::RenderPass<'e> {
         let load_op = match clear_color {
             Some(color) => wgpu::LoadOp::Clear(color),
             None => wgpu::LoadOp::Load,
@@ -487,12 +764,13 @@ impl HyperspectralPipeline {
             timestamp_writes: None,
         });
 
-        render_pass.set_pipeline(&self.render_pipeline);
+        let pipeline = self
+            .band_math_pipeline
+            .as_ref()
+            .unwrap_or(&self.render_pipeline);
+        render_pass.set_pipeline(pipeline);
         render_pass.set_bind_group(bindings::UNIFORM_GROUP, &self.uniform_bind_group, &[]);
-        render_pass.set_bind_group(bindings::BAND_TEXTURE_GROUP, band_bind_group, &[]);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        render_pass
     }
 
     /// Get the bind group layout for band textures (needed when creating HyperspectralGpuData).
@@ -506,3 +784,51 @@ impl Pipeline for HyperspectralPipeline {
         &self.render_pipeline
     }
 }
+
+/// Parse and validate WGSL the way the device would.
+///
+/// Device errors arrive asynchronously on WebGPU, after the pipeline would
+/// already be in use, so shader code built at runtime is checked here first.
+/// Returns the formatted parse or validation error.
+fn validate_wgsl(source: &str) -> std::result::Result<(), String> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|e| e.emit_to_string(source))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band_math_shader(band_math_wgsl: &str) -> String {
+        format!(
+            "{}\n{}\n{}",
+            HYPERSPECTRAL_SHADER, band_math_wgsl, BAND_MATH_ENTRY_POINT
+        )
+    }
+
+    #[test]
+    fn test_validate_wgsl() {
+        assert!(validate_wgsl(HYPERSPECTRAL_SHADER).is_ok());
+        assert!(validate_wgsl(&band_math_shader(
+            "fn band_math(tex_coords: vec2<f32>) -> vec3<f32> {\n    return vec3<f32>(0.5);\n}"
+        ))
+        .is_ok());
+
+        // Wrong return type and unknown identifiers are rejected
+        let error = validate_wgsl(&band_math_shader(
+            "fn band_math(tex_coords: vec2<f32>) -> vec3<f32> {\n    return 1.0;\n}",
+        ))
+        .unwrap_err();
+        assert!(error.contains("vec3<f32>"), "{}", error);
+        assert!(validate_wgsl(&band_math_shader(
+            "fn band_math(tex_coords: vec2<f32>) -> vec3<f32> {\n    return missing();\n}"
+        ))
+        .is_err());
+    }
+}
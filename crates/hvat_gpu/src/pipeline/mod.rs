@@ -9,7 +9,7 @@ pub mod texture;
 
 pub use builder::{BindGroupLayoutBuilder, PipelineBuilder};
 pub use color::ColorPipeline;
-pub use hyperspectral::{HyperspectralGpuData, HyperspectralPipeline};
+pub use hyperspectral::{HyperspectralGpuData, HyperspectralPipeline, TileQuad};
 pub use texture::TexturePipeline;
 
 /// Common trait for render pipelines.
@@ -10,6 +10,22 @@
 //
 // This supports unlimited bands (limited only by GPU texture array size).
 // The band_selection uniform specifies which bands to use for R, G, B output.
+//
+// Each band is stored rescaled to its own value range (0..1 in the texture);
+// the *_range fields hold the original (min, max) used to decode samples, so
+// adjustments operate on the original values at full texture precision.
+//
+// The stretch uniform maps the decoded values of each channel from [low, high]
+// to 0..1 (optionally through a histogram equalization curve) before the
+// adjustments are applied.
+//
+// Band-math shaders append a generated `band_math(tex_coords) -> vec3<f32>`
+// function and the `fs_band_math` entry point to this source. They sample bands
+// by index and decode them with the ranges in the band_math_slots uniform, where each
+// sampled band has a slot (see `band_math_range`).
+//
+// With the colormap enabled, the red channel value is mapped through the
+// colormap lookup texture instead (pseudo-color for a single band or index).
 
 struct VertexInput {
     @location(0) position: vec2<f32>,
@@ -37,6 +53,39 @@ struct BandSelection {
     green_band: u32,
     blue_band: u32,
     num_bands: u32,
+    red_range: vec2<f32>,
+    green_range: vec2<f32>,
+    blue_range: vec2<f32>,
+    _padding: vec2<f32>,
+}
+
+// Must match STRETCH_LUT_SIZE in uniform.rs
+const STRETCH_LUT_SIZE: u32 = 64u;
+
+struct Stretch {
+    low: vec4<f32>,
+    high: vec4<f32>,
+    // 0 = off, 1 = linear, 2 = equalize
+    mode: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+    lut: array<vec4<f32>, 64>,
+}
+
+// Must match MAX_BAND_MATH_BANDS in uniform.rs (two ranges per vec4)
+struct BandMath {
+    ranges: array<vec4<f32>, 128>,
+}
+
+// Must match COLORMAP_LUT_SIZE in colormap.rs
+const COLORMAP_LUT_SIZE: u32 = 256u;
+
+struct Colormap {
+    low: f32,
+    high: f32,
+    enabled: u32,
+    reversed: u32,
 }
 
 // Group 0: Uniforms
@@ -49,6 +98,21 @@ var<uniform> adjustments: ImageAdjustments;
 @group(0) @binding(2)
 var<uniform> band_selection: BandSelection;
 
+@group(0) @binding(3)
+var<uniform> stretch: Stretch;
+
+@group(0) @binding(4)
+var<uniform> band_math_slots: BandMath;
+
+@group(0) @binding(5)
+var<uniform> colormap: Colormap;
+
+@group(0) @binding(6)
+var colormap_lut: texture_2d<f32>;
+
+@group(0) @binding(7)
+var colormap_sampler: sampler;
+
 // Group 1: Band texture array (packed 4 bands per RGBA layer)
 @group(1) @binding(0)
 var band_texture_array: texture_2d_array<f32>;
@@ -83,6 +147,41 @@ fn sample_band(tex_coords: vec2<f32>, band_index: u32) -> f32 {
     }
 }
 
+// Map a stored 0..1 sample back to the band's original value
+fn decode_band(stored: f32, range: vec2<f32>) -> f32 {
+    return mix(range.x, range.y, stored);
+}
+
+// Original (min, max) of the band in a band-math slot
+fn band_math_range(slot: u32) -> vec2<f32> {
+    let packed = band_math_slots.ranges[slot / 2u];
+    if slot % 2u == 0u {
+        return packed.xy;
+    }
+    return packed.zw;
+}
+
+// Look up a stretched 0..1 value in the equalization curve of one channel
+fn equalize(t: f32, channel: u32) -> f32 {
+    let pos = t * f32(STRETCH_LUT_SIZE - 1u);
+    let i0 = min(u32(floor(pos)), STRETCH_LUT_SIZE - 1u);
+    let i1 = min(i0 + 1u, STRETCH_LUT_SIZE - 1u);
+    return mix(stretch.lut[i0][channel], stretch.lut[i1][channel], fract(pos));
+}
+
+// Apply the per-channel contrast stretch
+fn apply_stretch(rgb: vec3<f32>) -> vec3<f32> {
+    if stretch.mode == 0u {
+        return rgb;
+    }
+    let span = max(stretch.high.xyz - stretch.low.xyz, vec3<f32>(1e-12));
+    let t = clamp((rgb - stretch.low.xyz) / span, vec3<f32>(0.0), vec3<f32>(1.0));
+    if stretch.mode == 1u {
+        return t;
+    }
+    return vec3<f32>(equalize(t.r, 0u), equalize(t.g, 1u), equalize(t.b, 2u));
+}
+
 // Convert RGB to HSV
 fn rgb_to_hsv(rgb: vec3<f32>) -> vec3<f32> {
     let max_c = max(max(rgb.r, rgb.g), rgb.b);
@@ -141,33 +240,71 @@ fn hsv_to_rgb(hsv: vec3<f32>) -> vec3<f32> {
     return rgb + vec3<f32>(m, m, m);
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Sample selected bands for RGB composite
-    let r = sample_band(in.tex_coords, band_selection.red_band);
-    let g = sample_band(in.tex_coords, band_selection.green_band);
-    let b = sample_band(in.tex_coords, band_selection.blue_band);
-
-    var color = vec4<f32>(r, g, b, 1.0);
-
+// Apply brightness, contrast, gamma and hue shift to display values
+fn apply_adjustments(rgb: vec3<f32>) -> vec3<f32> {
     // Apply brightness (additive)
-    color = vec4<f32>(color.rgb + vec3<f32>(adjustments.brightness), color.a);
+    var color = rgb + vec3<f32>(adjustments.brightness);
 
     // Apply contrast (multiplicative around 0.5)
-    color = vec4<f32>((color.rgb - 0.5) * adjustments.contrast + 0.5, color.a);
+    color = (color - 0.5) * adjustments.contrast + 0.5;
 
     // Apply gamma correction
-    color = vec4<f32>(pow(max(color.rgb, vec3<f32>(0.0)), vec3<f32>(1.0 / adjustments.gamma)), color.a);
+    color = pow(max(color, vec3<f32>(0.0)), vec3<f32>(1.0 / adjustments.gamma));
 
     // Apply hue shift
     if abs(adjustments.hue_shift) > 0.001 {
-        var hsv = rgb_to_hsv(color.rgb);
+        var hsv = rgb_to_hsv(color);
         hsv.x = hsv.x + adjustments.hue_shift / 360.0;
         // Wrap hue to 0-1 range
         hsv.x = hsv.x - floor(hsv.x);
-        color = vec4<f32>(hsv_to_rgb(hsv), color.a);
+        color = hsv_to_rgb(hsv);
     }
 
+    return color;
+}
+
+// Position (0..1) of a decoded value in the colormap
+fn colormap_position(value: f32) -> f32 {
+    let span = colormap.high - colormap.low;
+    if abs(span) < 1e-12 {
+        return 0.0;
+    }
+    let t = clamp((value - colormap.low) / span, 0.0, 1.0);
+    if colormap.reversed != 0u {
+        return 1.0 - t;
+    }
+    return t;
+}
+
+// Look up a 0..1 position in the colormap texture (at texel centers)
+fn colormap_color(t: f32) -> vec3<f32> {
+    let size = f32(COLORMAP_LUT_SIZE);
+    let u = (clamp(t, 0.0, 1.0) * (size - 1.0) + 0.5) / size;
+    return textureSampleLevel(colormap_lut, colormap_sampler, vec2<f32>(u, 0.5), 0.0).rgb;
+}
+
+// Stretch, adjust and clamp decoded channel values
+//
+// With the colormap enabled only the red channel is shown: the adjustments
+// apply to its colormap position before the color lookup.
+fn display_color(rgb: vec3<f32>) -> vec4<f32> {
+    if colormap.enabled != 0u {
+        let t = apply_adjustments(vec3<f32>(colormap_position(rgb.r))).r;
+        return vec4<f32>(colormap_color(t), 1.0);
+    }
+
+    let color = apply_adjustments(apply_stretch(rgb));
+
     // Clamp final output
-    return clamp(color, vec4<f32>(0.0), vec4<f32>(1.0));
+    return vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Sample selected bands for RGB composite
+    let r = decode_band(sample_band(in.tex_coords, band_selection.red_band), band_selection.red_range);
+    let g = decode_band(sample_band(in.tex_coords, band_selection.green_band), band_selection.green_range);
+    let b = decode_band(sample_band(in.tex_coords, band_selection.blue_band), band_selection.blue_range);
+
+    return display_color(vec3<f32>(r, g, b));
 }
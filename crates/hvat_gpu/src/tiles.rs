@@ -0,0 +1,681 @@
+//! Tiled pyramid of band textures for images too large for one texture.
+//!
+//! `HyperspectralGpuData::from_bands` puts the whole image into one texture
+//! array, which fails beyond `max_texture_dimension_2d` and keeps every band
+//! of every pixel in GPU memory. `TiledGpuData` instead splits the image into
+//! square tiles at full resolution (level 0) and at each coarser level of a
+//! pyramid, where a pixel of level `n` averages `2^n` x `2^n` image pixels.
+//! Tiles are packed like any band cube (4 bands per layer) and uploaded when
+//! they first become visible, then kept in an LRU cache. The single tile of
+//! the coarsest level stays resident and is drawn under missing tiles.
+//!
+//! Tiles carry a border of `TILE_BORDER` pixels copied from their neighbours
+//! so linear filtering shows no seams, and all tiles share the `BandEncoding`
+//! of the full image.
+
+use std::collections::HashMap;
+
+use crate::band_texture::BandEncoding;
+use crate::context::GpuContext;
+use crate::pipeline::{HyperspectralGpuData, TileQuad};
+
+/// Default tile edge length in pixels (without the border).
+pub const TILE_SIZE: u32 = 256;
+
+/// Pixels copied from neighbouring tiles around each tile.
+pub const TILE_BORDER: u32 = 1;
+
+/// One tile of a pyramid level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileKey {
+    /// Pyramid level (0 = full resolution)
+    pub level: u32,
+    /// Tile column
+    pub x: u32,
+    /// Tile row
+    pub y: u32,
+}
+
+/// Tiles of a level covering part of the image, end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileView {
+    /// Pyramid level of the tiles
+    pub level: u32,
+    /// First tile column
+    pub x0: u32,
+    /// First tile row
+    pub y0: u32,
+    /// Tile column after the last one
+    pub x1: u32,
+    /// Tile row after the last one
+    pub y1: u32,
+}
+
+impl TileView {
+    /// Number of tiles in the view.
+    pub fn len(&self) -> usize {
+        (self.x1 - self.x0) as usize * (self.y1 - self.y0) as usize
+    }
+
+    /// Whether the view has no tiles.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Tiles of the view, row by row.
+    pub fn keys(self) -> impl Iterator<Item = TileKey> {
+        (self.y0..self.y1).flat_map(move |y| {
+            (self.x0..self.x1).map(move |x| TileKey {
+                level: self.level,
+                x,
+                y,
+            })
+        })
+    }
+}
+
+/// Tile grid of an image and its pyramid levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileLayout {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Tile edge length in pixels (without the border)
+    pub tile_size: u32,
+    /// Number of pyramid levels; the last one fits in a single tile
+    pub levels: u32,
+}
+
+impl TileLayout {
+    /// Layout of a `width` x `height` image in tiles of `tile_size` pixels.
+    pub fn new(width: u32, height: u32, tile_size: u32) -> Self {
+        let mut layout = Self {
+            width,
+            height,
+            tile_size: tile_size.max(1),
+            levels: 1,
+        };
+        loop {
+            let (w, h) = layout.level_size(layout.levels - 1);
+            if w.max(h) <= layout.tile_size {
+                return layout;
+            }
+            layout.levels += 1;
+        }
+    }
+
+    /// Coarsest pyramid level.
+    pub fn top_level(&self) -> u32 {
+        self.levels - 1
+    }
+
+    /// View of the single tile of the coarsest level.
+    pub fn overview(&self) -> TileView {
+        TileView {
+            level: self.top_level(),
+            x0: 0,
+            y0: 0,
+            x1: 1,
+            y1: 1,
+        }
+    }
+
+    /// Size of `level` in pixels.
+    pub fn level_size(&self, level: u32) -> (u32, u32) {
+        let scale = 1u64 << level;
+        let shrink = |size: u32| u64::from(size).div_ceil(scale) as u32;
+        (shrink(self.width), shrink(self.height))
+    }
+
+    /// Number of tile columns and rows of `level`.
+    pub fn grid_size(&self, level: u32) -> (u32, u32) {
+        let (w, h) = self.level_size(level);
+        (w.div_ceil(self.tile_size), h.div_ceil(self.tile_size))
+    }
+
+    /// Area of a tile in pixels of its level (x, y, width, height).
+    ///
+    /// Tiles at the right and bottom edge are clipped to the level.
+    pub fn tile_rect(&self, key: TileKey) -> (u32, u32, u32, u32) {
+        let (w, h) = self.level_size(key.level);
+        let x = key.x * self.tile_size;
+        let y = key.y * self.tile_size;
+        (
+            x,
+            y,
+            self.tile_size.min(w.saturating_sub(x)),
+            self.tile_size.min(h.saturating_sub(y)),
+        )
+    }
+
+    /// Finest level whose pixels are at least one screen pixel at `zoom`
+    /// (screen pixels per image pixel).
+    pub fn level_for_zoom(&self, zoom: f32) -> u32 {
+        if zoom >= 1.0 {
+            return 0;
+        }
+        if zoom <= 0.0 {
+            return self.top_level();
+        }
+        ((1.0 / zoom).log2().floor() as u32).min(self.top_level())
+    }
+
+    /// Tiles to draw the image area `rect` (x, y, width, height in image
+    /// pixels) at `zoom`.
+    ///
+    /// Starts at `level_for_zoom` and moves to coarser levels until the view
+    /// fits in a `max_dimension` texture and has at most `max_tiles` tiles.
+    pub fn view(
+        &self,
+        rect: (f32, f32, f32, f32),
+        zoom: f32,
+        max_dimension: u32,
+        max_tiles: usize,
+    ) -> TileView {
+        let mut level = self.level_for_zoom(zoom);
+        loop {
+            let view = self.view_at(level, rect);
+            let (_, _, w, h) = self.view_rect(&view);
+            if level == self.top_level() || (w.max(h) <= max_dimension && view.len() <= max_tiles) {
+                return view;
+            }
+            level += 1;
+        }
+    }
+
+    /// Tiles of `level` overlapping `rect`, at least one.
+    fn view_at(&self, level: u32, rect: (f32, f32, f32, f32)) -> TileView {
+        let (columns, rows) = self.grid_size(level);
+        // Image pixels per tile edge
+        let span = self.tile_size as f32 * (1u64 << level) as f32;
+        let first =
+            |start: f32, count: u32| ((start / span).floor().max(0.0) as u32).min(count - 1);
+        let end = |first: u32, stop: f32, count: u32| {
+            ((stop / span).ceil().max(0.0) as u32).clamp(first + 1, count)
+        };
+        let (x, y, w, h) = rect;
+        let x0 = first(x, columns);
+        let y0 = first(y, rows);
+        TileView {
+            level,
+            x0,
+            y0,
+            x1: end(x0, x + w, columns),
+            y1: end(y0, y + h, rows),
+        }
+    }
+
+    /// Area covered by the tiles of `view` in pixels of its level (x, y,
+    /// width, height).
+    pub fn view_rect(&self, view: &TileView) -> (u32, u32, u32, u32) {
+        let (w, h) = self.level_size(view.level);
+        let x = view.x0 * self.tile_size;
+        let y = view.y0 * self.tile_size;
+        (
+            x,
+            y,
+            (view.x1 * self.tile_size).min(w) - x,
+            (view.y1 * self.tile_size).min(h) - y,
+        )
+    }
+
+    /// Area covered by the tiles of `view` in image pixels (x, y, width,
+    /// height).
+    pub fn view_image_rect(&self, view: &TileView) -> (f32, f32, f32, f32) {
+        let (x, y, w, h) = self.view_rect(view);
+        let scale = (1u64 << view.level) as f32;
+        let x0 = x as f32 * scale;
+        let y0 = y as f32 * scale;
+        let x1 = ((x + w) as f32 * scale).min(self.width as f32);
+        let y1 = ((y + h) as f32 * scale).min(self.height as f32);
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+/// Band values of a tile and its border, downsampled to the tile's level.
+///
+/// `bands` are the full-resolution bands of the image; bands of another
+/// length come out empty. A level pixel is the mean of the finite image
+/// values it covers (NaN if there are none), and border pixels outside the
+/// level repeat its edge. Returns the bands with their width and height.
+pub fn tile_bands(
+    layout: &TileLayout,
+    key: TileKey,
+    bands: &[Vec<f32>],
+) -> (Vec<Vec<f32>>, u32, u32) {
+    let (x, y, w, h) = layout.tile_rect(key);
+    let (level_w, level_h) = layout.level_size(key.level);
+    let tex_w = w + 2 * TILE_BORDER;
+    let tex_h = h + 2 * TILE_BORDER;
+    let scale = 1usize << key.level;
+    let (image_w, image_h) = (layout.width as usize, layout.height as usize);
+
+    // Image pixel span of each texture column and row
+    let spans =
+        |start: u32, count: u32, level_size: u32, image_size: usize| -> Vec<(usize, usize)> {
+            (0..count)
+                .map(|i| {
+                    let p = (start + i).saturating_sub(TILE_BORDER).min(level_size - 1) as usize;
+                    (p * scale, ((p + 1) * scale).min(image_size))
+                })
+                .collect()
+        };
+    let columns = spans(x, tex_w, level_w, image_w);
+    let rows = spans(y, tex_h, level_h, image_h);
+
+    let tile = bands
+        .iter()
+        .map(|band| {
+            if band.len() != image_w * image_h {
+                return Vec::new();
+            }
+            let mut values = Vec::with_capacity(columns.len() * rows.len());
+            for &(y0, y1) in &rows {
+                for &(x0, x1) in &columns {
+                    if scale == 1 {
+                        values.push(band[y0 * image_w + x0]);
+                        continue;
+                    }
+                    let mut sum = 0.0f64;
+                    let mut count = 0u32;
+                    for row in y0..y1 {
+                        for &value in &band[row * image_w + x0..row * image_w + x1] {
+                            if value.is_finite() {
+                                sum += f64::from(value);
+                                count += 1;
+                            }
+                        }
+                    }
+                    values.push(if count > 0 {
+                        (sum / f64::from(count)) as f32
+                    } else {
+                        f32::NAN
+                    });
+                }
+            }
+            values
+        })
+        .collect();
+    (tile, tex_w, tex_h)
+}
+
+/// Least-recently-used cache of tiles.
+pub struct TileCache<T> {
+    entries: HashMap<TileKey, (T, u64)>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl<T> TileCache<T> {
+    /// Create an empty cache holding up to `capacity` tiles (at least one).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    /// Maximum number of tiles.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of cached tiles.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get a tile without marking it as used.
+    pub fn get(&self, key: &TileKey) -> Option<&T> {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    /// Mark a tile as used; returns whether it is cached.
+    pub fn touch(&mut self, key: &TileKey) -> bool {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some((_, used)) => {
+                *used = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add a tile, evicting the least recently used one when full.
+    ///
+    /// Returns the key of the evicted tile.
+    pub fn insert(&mut self, key: TileKey, value: T) -> Option<TileKey> {
+        self.clock += 1;
+        let mut evicted = None;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            evicted = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = evicted {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (value, self.clock));
+        evicted
+    }
+
+    /// Remove all tiles.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Band cube stored as a tiled pyramid on the GPU.
+pub struct TiledGpuData {
+    /// Tile grid and pyramid levels
+    pub layout: TileLayout,
+    /// Value ranges of the full image, shared by all tiles
+    pub encoding: BandEncoding,
+    /// Number of bands
+    pub num_bands: usize,
+    /// The single tile of the coarsest level, always resident
+    overview: HyperspectralGpuData,
+    /// Uploaded tiles of the finer levels
+    tiles: TileCache<HyperspectralGpuData>,
+}
+
+impl TiledGpuData {
+    /// Whether a `width` x `height` image with `num_bands` bands must be
+    /// tiled: it exceeds the texture size limit, or its texture array would
+    /// take more than `max_bytes`.
+    pub fn required(
+        ctx: &GpuContext,
+        width: u32,
+        height: u32,
+        num_bands: usize,
+        max_bytes: u64,
+    ) -> bool {
+        let layers = num_bands.div_ceil(4).max(2) as u64;
+        let bytes = u64::from(width)
+            * u64::from(height)
+            * layers
+            * u64::from(ctx.band_format.bytes_per_texel());
+        width.max(height) > ctx.device.limits().max_texture_dimension_2d || bytes > max_bytes
+    }
+
+    /// Compute the band encoding of the full image and upload its overview.
+    ///
+    /// The tile cache holds as many tiles as fit in `max_bytes`.
+    pub fn new(
+        ctx: &GpuContext,
+        bands: &[Vec<f32>],
+        width: u32,
+        height: u32,
+        tile_size: u32,
+        max_bytes: u64,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let layout = TileLayout::new(width, height, tile_size);
+        let encoding = BandEncoding::for_bands(bands, ctx.band_format);
+        let top = TileKey {
+            level: layout.top_level(),
+            x: 0,
+            y: 0,
+        };
+        let overview = upload_tile(ctx, &layout, top, bands, &encoding, bind_group_layout);
+
+        let side = u64::from(layout.tile_size + 2 * TILE_BORDER);
+        let tile_bytes = side
+            * side
+            * bands.len().div_ceil(4).max(2) as u64
+            * u64::from(encoding.format.bytes_per_texel());
+        Self {
+            layout,
+            num_bands: bands.len(),
+            encoding,
+            overview,
+            tiles: TileCache::new((max_bytes / tile_bytes) as usize),
+        }
+    }
+
+    /// Tiles to draw the image area `rect` at `zoom` (see `TileLayout::view`).
+    pub fn view(&self, rect: (f32, f32, f32, f32), zoom: f32, max_dimension: u32) -> TileView {
+        self.layout
+            .view(rect, zoom, max_dimension, self.tiles.capacity())
+    }
+
+    /// Upload up to `max_uploads` missing tiles of `view` and mark its tiles
+    /// as used.
+    ///
+    /// Returns the number of tiles still missing.
+    pub fn upload(
+        &mut self,
+        ctx: &GpuContext,
+        bands: &[Vec<f32>],
+        view: TileView,
+        max_uploads: usize,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> usize {
+        if view.level == self.layout.top_level() {
+            return 0;
+        }
+        let mut uploads = 0;
+        let mut missing = 0;
+        for key in view.keys() {
+            if self.tiles.touch(&key) {
+                continue;
+            }
+            if uploads < max_uploads {
+                let tile = upload_tile(
+                    ctx,
+                    &self.layout,
+                    key,
+                    bands,
+                    &self.encoding,
+                    bind_group_layout,
+                );
+                self.tiles.insert(key, tile);
+                uploads += 1;
+            } else {
+                missing += 1;
+            }
+        }
+        missing
+    }
+
+    /// Quads drawing the area of `view` into a target covering exactly that
+    /// area.
+    ///
+    /// The overview comes first, then the cached tiles from coarse to fine
+    /// levels, so missing tiles show the best resolution available.
+    pub fn quads<'a>(&'a self, view: TileView) -> Vec<TileQuad<'a>> {
+        let (vx, vy, vw, vh) = self.layout.view_rect(&view);
+        // Target area of a tile of `level` in clip space
+        let position = |key: TileKey| {
+            let (x, y, w, h) = self.layout.tile_rect(key);
+            // Tile pixels per view pixel
+            let scale = (1u64 << (key.level - view.level)) as f32;
+            let to_clip_x = |px: u32| (px as f32 * scale - vx as f32) / vw as f32 * 2.0 - 1.0;
+            let to_clip_y = |py: u32| 1.0 - (py as f32 * scale - vy as f32) / vh as f32 * 2.0;
+            [
+                to_clip_x(x),
+                to_clip_y(y),
+                to_clip_x(x + w),
+                to_clip_y(y + h),
+            ]
+        };
+        let quad = |key: TileKey, data: &'a HyperspectralGpuData| {
+            let (_, _, w, h) = self.layout.tile_rect(key);
+            let border_u = TILE_BORDER as f32 / data.width as f32;
+            let border_v = TILE_BORDER as f32 / data.height as f32;
+            TileQuad {
+                bind_group: &data.bind_group,
+                position: position(key),
+                tex_coords: [
+                    border_u,
+                    border_v,
+                    border_u + w as f32 / data.width as f32,
+                    border_v + h as f32 / data.height as f32,
+                ],
+            }
+        };
+
+        let top = self.layout.top_level();
+        let mut quads = vec![quad(
+            TileKey {
+                level: top,
+                x: 0,
+                y: 0,
+            },
+            &self.overview,
+        )];
+        let (x, y, w, h) = self.layout.view_image_rect(&view);
+        for level in (view.level..top).rev() {
+            let level_view = self.layout.view_at(level, (x, y, w, h));
+            for key in level_view.keys() {
+                if let Some(data) = self.tiles.get(&key) {
+                    quads.push(quad(key, data));
+                }
+            }
+        }
+        quads
+    }
+}
+
+/// Extract a tile from the full-resolution bands and upload it.
+fn upload_tile(
+    ctx: &GpuContext,
+    layout: &TileLayout,
+    key: TileKey,
+    bands: &[Vec<f32>],
+    encoding: &BandEncoding,
+    bind_group_layout: &wgpu::BindGroupLayout,
+) -> HyperspectralGpuData {
+    let (tile, width, height) = tile_bands(layout, key, bands);
+    HyperspectralGpuData::from_bands_with_encoding(
+        ctx,
+        &tile,
+        width,
+        height,
+        encoding.clone(),
+        bind_group_layout,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_levels() {
+        let layout = TileLayout::new(1000, 300, 256);
+        assert_eq!(layout.levels, 3);
+        assert_eq!(layout.level_size(0), (1000, 300));
+        assert_eq!(layout.level_size(1), (500, 150));
+        assert_eq!(layout.level_size(2), (250, 75));
+        assert_eq!(layout.grid_size(0), (4, 2));
+        assert_eq!(layout.grid_size(2), (1, 1));
+
+        let key = TileKey {
+            level: 0,
+            x: 3,
+            y: 1,
+        };
+        assert_eq!(layout.tile_rect(key), (768, 256, 232, 44));
+
+        assert_eq!(layout.view_rect(&layout.overview()), (0, 0, 250, 75));
+
+        // An image smaller than a tile is its own overview
+        assert_eq!(TileLayout::new(100, 50, 256).levels, 1);
+    }
+
+    #[test]
+    fn test_view_selection() {
+        let layout = TileLayout::new(4096, 4096, 256);
+        assert_eq!(layout.level_for_zoom(2.0), 0);
+        assert_eq!(layout.level_for_zoom(0.5), 1);
+        assert_eq!(layout.level_for_zoom(0.3), 1);
+        assert_eq!(layout.level_for_zoom(0.001), layout.top_level());
+        assert_eq!(layout.level_for_zoom(0.0), layout.top_level());
+
+        // 1:1 view of the area 300..800 x 0..200
+        let view = layout.view((300.0, 0.0, 500.0, 200.0), 1.0, 8192, 100);
+        assert_eq!(
+            view,
+            TileView {
+                level: 0,
+                x0: 1,
+                y0: 0,
+                x1: 4,
+                y1: 1,
+            }
+        );
+        assert_eq!(view.len(), 3);
+        assert_eq!(layout.view_rect(&view), (256, 0, 768, 256));
+        assert_eq!(layout.view_image_rect(&view), (256.0, 0.0, 768.0, 256.0));
+
+        // Too many tiles, or too large for a texture: coarser levels
+        assert_eq!(
+            layout.view((300.0, 0.0, 500.0, 200.0), 1.0, 8192, 2).level,
+            1
+        );
+        let whole = (0.0, 0.0, 4096.0, 4096.0);
+        assert_eq!(layout.view(whole, 1.0, 1024, 1000).level, 2);
+
+        // Views off the image still have a tile
+        let view = layout.view((-900.0, -900.0, 100.0, 100.0), 1.0, 8192, 100);
+        assert_eq!(view.len(), 1);
+        assert_eq!((view.x0, view.y0), (0, 0));
+    }
+
+    #[test]
+    fn test_tile_bands_border_and_downsampling() {
+        // 4x2 image, tiles of 2 pixels
+        let layout = TileLayout::new(4, 2, 2);
+        let band: Vec<f32> = vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, f32::NAN];
+
+        let key = TileKey {
+            level: 0,
+            x: 1,
+            y: 0,
+        };
+        let (tile, w, h) = tile_bands(&layout, key, std::slice::from_ref(&band));
+        assert_eq!((w, h), (4, 4));
+        // Border column from the left neighbour, edge rows and column repeated
+        assert_eq!(&tile[0][4..8], &[1.0, 2.0, 3.0, 3.0]);
+        assert_eq!(&tile[0][0..4], &tile[0][4..8]);
+
+        // Level 1 is 2x1: averages of 2x2 blocks, skipping NaN
+        let key = TileKey {
+            level: 1,
+            x: 0,
+            y: 0,
+        };
+        let (tile, w, h) = tile_bands(&layout, key, &[band, vec![0.0; 3]]);
+        assert_eq!((w, h), (4, 3));
+        assert_eq!(&tile[0][4..8], &[2.5, 2.5, 11.0 / 3.0, 11.0 / 3.0]);
+        assert!(tile[1].is_empty());
+    }
+
+    #[test]
+    fn test_tile_cache_lru() {
+        let key = |x| TileKey { level: 0, x, y: 0 };
+        let mut cache = TileCache::new(2);
+        assert_eq!(cache.insert(key(0), "a"), None);
+        assert_eq!(cache.insert(key(1), "b"), None);
+        // Using tile 0 makes tile 1 the oldest
+        assert!(cache.touch(&key(0)));
+        assert_eq!(cache.insert(key(2), "c"), Some(key(1)));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key(0)), Some(&"a"));
+        assert!(!cache.touch(&key(1)));
+        // Replacing a cached tile evicts nothing
+        assert_eq!(cache.insert(key(2), "d"), None);
+        assert_eq!(cache.get(&key(2)), Some(&"d"));
+    }
+}
@@ -2,6 +2,8 @@
 
 use bytemuck::{Pod, Zeroable};
 
+use crate::band_texture::BandEncoding;
+
 /// 4x4 transform matrix for pan/zoom operations.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -95,6 +97,14 @@ pub struct BandSelectionUniform {
     pub blue_band: u32,
     /// Total number of bands (for validation in shader).
     pub num_bands: u32,
+    /// Original value range (min, max) of the red band, used to decode samples.
+    pub red_range: [f32; 2],
+    /// Original value range (min, max) of the green band.
+    pub green_range: [f32; 2],
+    /// Original value range (min, max) of the blue band.
+    pub blue_range: [f32; 2],
+    /// Padding to a multiple of 16 bytes.
+    pub _padding: [f32; 2],
 }
 
 impl BandSelectionUniform {
@@ -104,9 +114,25 @@ impl BandSelectionUniform {
             green_band: green as u32,
             blue_band: blue as u32,
             num_bands: num_bands as u32,
+            red_range: [0.0, 1.0],
+            green_range: [0.0, 1.0],
+            blue_range: [0.0, 1.0],
+            _padding: [0.0; 2],
         }
     }
 
+    /// Set the value ranges of the selected bands from the texture encoding.
+    pub fn with_ranges(mut self, encoding: &BandEncoding) -> Self {
+        let range = |band: u32| {
+            let range = encoding.range(band as usize);
+            [range.min, range.min + range.span()]
+        };
+        self.red_range = range(self.red_band);
+        self.green_range = range(self.green_band);
+        self.blue_range = range(self.blue_band);
+        self
+    }
+
     /// Default RGB selection (bands 0, 1, 2).
     pub fn default_rgb(num_bands: usize) -> Self {
         Self::new(0, 1, 2.min(num_bands.saturating_sub(1)), num_bands)
@@ -118,3 +144,150 @@ impl Default for BandSelectionUniform {
         Self::new(0, 1, 2, 3)
     }
 }
+
+/// Number of samples in the equalization curve of `StretchUniform`.
+pub const STRETCH_LUT_SIZE: usize = 64;
+
+/// Per-channel contrast stretch applied to decoded band values.
+///
+/// Values between `low` and `high` are mapped to 0.0-1.0 before the image
+/// adjustments; with `STRETCH_EQUALIZE` the result is additionally looked up
+/// in the per-channel equalization curve.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct StretchUniform {
+    /// Band value shown as black for the red, green and blue channel (w unused).
+    pub low: [f32; 4],
+    /// Band value shown as white for the red, green and blue channel (w unused).
+    pub high: [f32; 4],
+    /// `STRETCH_OFF`, `STRETCH_LINEAR` or `STRETCH_EQUALIZE`.
+    pub mode: u32,
+    /// Padding to align `lut` to 16 bytes.
+    pub _padding: [u32; 3],
+    /// Equalization curve sampled evenly from `low` to `high` (xyz = r, g, b).
+    pub lut: [[f32; 4]; STRETCH_LUT_SIZE],
+}
+
+impl StretchUniform {
+    /// Band values are shown unchanged.
+    pub const STRETCH_OFF: u32 = 0;
+    /// Linear stretch from `low` to `high`.
+    pub const STRETCH_LINEAR: u32 = 1;
+    /// Linear stretch followed by the equalization curve.
+    pub const STRETCH_EQUALIZE: u32 = 2;
+
+    /// No stretch.
+    pub fn new() -> Self {
+        let mut uniform = Self::zeroed();
+        uniform.high = [1.0; 4];
+        for channel in 0..3 {
+            uniform.set_channel(channel, 0.0, 1.0, None);
+        }
+        uniform
+    }
+
+    /// Set the stretch of one channel (0 = red, 1 = green, 2 = blue).
+    ///
+    /// `lut` is resampled to `STRETCH_LUT_SIZE` entries; `None` uses a
+    /// linear curve.
+    pub fn set_channel(&mut self, channel: usize, low: f32, high: f32, lut: Option<&[f32]>) {
+        self.low[channel] = low;
+        self.high[channel] = high;
+        for (i, entry) in self.lut.iter_mut().enumerate() {
+            let t = i as f32 / (STRETCH_LUT_SIZE - 1) as f32;
+            entry[channel] = match lut {
+                Some(lut) if !lut.is_empty() => {
+                    lut[((t * (lut.len() - 1) as f32).round() as usize).min(lut.len() - 1)]
+                }
+                _ => t,
+            };
+        }
+    }
+}
+
+impl Default for StretchUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maximum number of band samples in a band-math shader.
+pub const MAX_BAND_MATH_BANDS: usize = 256;
+
+/// Value ranges of the bands sampled by a band-math shader.
+///
+/// Generated band-math shaders refer to bands through slots; slot `i` holds
+/// the original (min, max) of its band, two slots per `vec4` (xy, zw).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct BandMathUniform {
+    pub ranges: [[f32; 4]; MAX_BAND_MATH_BANDS / 2],
+}
+
+impl BandMathUniform {
+    /// Ranges of `bands` (the band index of each slot) from the texture encoding.
+    ///
+    /// Slots beyond `MAX_BAND_MATH_BANDS` are ignored.
+    pub fn new(bands: &[usize], encoding: &BandEncoding) -> Self {
+        let mut uniform = Self::zeroed();
+        for (slot, &band) in bands.iter().take(MAX_BAND_MATH_BANDS).enumerate() {
+            let range = encoding.range(band);
+            let offset = (slot % 2) * 2;
+            uniform.ranges[slot / 2][offset] = range.min;
+            uniform.ranges[slot / 2][offset + 1] = range.min + range.span();
+        }
+        uniform
+    }
+}
+
+impl Default for BandMathUniform {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+/// Pseudo-color mapping of the first decoded channel through a colormap.
+///
+/// When enabled, the red channel value is mapped from `low`..`high` to the
+/// colormap lookup texture, replacing the contrast stretch and the
+/// composite of the other channels.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ColormapUniform {
+    /// Value at the start of the colormap.
+    pub low: f32,
+    /// Value at the end of the colormap.
+    pub high: f32,
+    /// Non-zero to render through the colormap.
+    pub enabled: u32,
+    /// Non-zero to run the colormap from `high` to `low`.
+    pub reversed: u32,
+}
+
+impl ColormapUniform {
+    /// Map values from `low` to `high` through the colormap.
+    pub fn new(low: f32, high: f32, reversed: bool) -> Self {
+        Self {
+            low,
+            high,
+            enabled: 1,
+            reversed: reversed as u32,
+        }
+    }
+
+    /// Colormap disabled.
+    pub fn disabled() -> Self {
+        Self {
+            low: 0.0,
+            high: 1.0,
+            enabled: 0,
+            reversed: 0,
+        }
+    }
+}
+
+impl Default for ColormapUniform {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
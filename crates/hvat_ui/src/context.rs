@@ -7,8 +7,8 @@ use crate::state::{
     InteractionMode, NumberInputState, SliderState, TextInputState, TooltipContent,
 };
 use crate::widgets::{
-    AnnotationOverlay, Button, Column, ImagePointerEvent, ImageViewer, NumberInput, Row, Slider,
-    Text, TextInput,
+    AnnotationOverlay, Button, ColorbarLegend, Column, ImagePointerEvent, ImageViewer, NumberInput,
+    Row, Slider, Text, TextInput,
 };
 use hvat_gpu::ImageAdjustments;
 
@@ -376,12 +376,39 @@ impl<'a, M: 'static + Clone> ImageViewerBuilder<'a, M> {
         self
     }
 
+    /// Set the handler for the image pixel under the cursor
+    pub fn on_hover<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Option<(u32, u32)>) -> M + 'static,
+    {
+        self.viewer = self.viewer.on_hover(handler);
+        self
+    }
+
+    /// Set the pixel last reported to `on_hover`
+    pub fn hover_pixel(mut self, pixel: Option<(u32, u32)>) -> Self {
+        self.viewer = self.viewer.hover_pixel(pixel);
+        self
+    }
+
+    /// Set the image area the texture shows, in image pixels
+    pub fn texture_region(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.viewer = self.viewer.texture_region(x, y, width, height);
+        self
+    }
+
     /// Set annotation overlays to draw
     pub fn overlays(mut self, overlays: Vec<AnnotationOverlay>) -> Self {
         self.viewer = self.viewer.overlays(overlays);
         self
     }
 
+    /// Set the colorbar legend drawn in the bottom-left corner
+    pub fn colorbar(mut self, colorbar: ColorbarLegend) -> Self {
+        self.viewer = self.viewer.colorbar(colorbar);
+        self
+    }
+
     /// Set image adjustments (brightness, contrast, gamma, hue shift)
     ///
     /// These adjustments are applied on the GPU for real-time performance.
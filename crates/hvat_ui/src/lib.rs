@@ -38,12 +38,13 @@ pub use widget::{EventResult, Widget};
 pub use widgets::{
     button, col, collapsible, column, dropdown, image_viewer, number_input, row, scrollable,
     slider, text, text_input, tooltip_overlay, tooltip_overlay_with_size, AnnotationOverlay,
-    BaseInputConfig, BorderSides, Collapsible, CollapsibleConfig, ColorPicker, ColorSwatch, Column,
-    ConfirmDialog, ConfirmDialogConfig, ContextMenu, ContextMenuConfig, Dropdown, DropdownConfig,
-    FileTree, FileTreeConfig, FileTreeNode, ImagePointerEvent, MenuItem, NumberInput,
-    NumberInputConfig, OverlayShape, Panel, PointerEventKind, Row, ScrollDirection, Scrollable,
-    ScrollbarConfig, ScrollbarVisibility, Slider, SliderConfig, Text, TextInput, TextInputConfig,
-    TooltipConfig, TooltipOverlay,
+    BaseInputConfig, BorderSides, ChartSeries, Collapsible, CollapsibleConfig, ColorPicker,
+    ColorSwatch, ColorbarLegend, Column, ConfirmDialog, ConfirmDialogConfig, ContextMenu,
+    ContextMenuConfig, Dropdown, DropdownConfig, FileTree, FileTreeConfig, FileTreeNode, Histogram,
+    HistogramConfig, ImagePointerEvent, LineChart, LineChartConfig, MenuItem, NumberInput, NumberInputConfig,
+    OverlayShape, Panel, PointerEventKind, Row, ScrollDirection, Scrollable, ScrollbarConfig,
+    ScrollbarVisibility, Slider, SliderConfig, Text, TextInput, TextInputConfig, TooltipConfig,
+    TooltipOverlay,
 };
 
 // Re-export hvat_gpu types that users need
@@ -378,6 +378,34 @@ impl ImageViewerState {
         }
     }
 
+    /// Part of the image inside the view, in image pixels (x, y, width,
+    /// height).
+    ///
+    /// Uses the sizes cached by the last `sync_with_bounds`; `None` before
+    /// the first layout.
+    pub fn visible_image_rect(&self) -> Option<(f32, f32, f32, f32)> {
+        let (view_w, view_h) = self.cached_view_size?;
+        let (tex_w, tex_h) = self.cached_texture_size?;
+        if view_w <= 0.0 || view_h <= 0.0 || tex_w == 0 || tex_h == 0 {
+            return None;
+        }
+        let (tex_w, tex_h) = (tex_w as f32, tex_h as f32);
+        let zoom = self.effective_zoom();
+        let scale_x = (tex_w / view_w) * zoom;
+        let scale_y = (tex_h / view_h) * zoom;
+
+        // Invert the viewer transform at the view edges (clip space -1 and 1),
+        // flipping y like `ImageViewer::screen_to_image`
+        let to_x =
+            |clip: f32| (((clip - self.pan.0) / scale_x + 1.0) / 2.0 * tex_w).clamp(0.0, tex_w);
+        let to_y = |clip: f32| {
+            ((1.0 - ((clip - self.pan.1) / scale_y + 1.0) / 2.0) * tex_h).clamp(0.0, tex_h)
+        };
+        let (x0, x1) = (to_x(-1.0), to_x(1.0));
+        let (y0, y1) = (to_y(1.0), to_y(-1.0));
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+
     /// Pan by delta in clip space
     pub fn pan_by(&mut self, delta_x: f32, delta_y: f32) {
         self.pan.0 += delta_x;
@@ -954,6 +982,43 @@ impl SliderState {
     }
 }
 
+/// Clip marker of a histogram widget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistogramMarker {
+    /// Value shown as black
+    #[default]
+    Low,
+    /// Value shown as white
+    High,
+}
+
+/// Histogram marker drag interaction state (holds the dragged marker)
+pub type HistogramDragState = DragState<HistogramMarker>;
+
+/// State for histogram widgets
+///
+/// Note: Undo/redo is handled externally via `UndoStack<T>`. Use the `on_undo_point`
+/// callback on the widget to know when to save an undo snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct HistogramState {
+    /// Value at the low clip marker
+    pub low: f32,
+    /// Value at the high clip marker
+    pub high: f32,
+    /// Whether bar heights use a logarithmic scale
+    pub log_scale: bool,
+    /// Marker drag interaction state
+    pub drag: HistogramDragState,
+}
+
+impl HistogramState {
+    /// Set the marker values
+    pub fn set_clips(&mut self, low: f32, high: f32) {
+        self.low = low;
+        self.high = high;
+    }
+}
+
 /// State for number input fields
 ///
 /// Note: Undo/redo is handled externally via `UndoStack<T>`. Use the `on_undo_point`
@@ -1718,6 +1783,24 @@ mod tests {
         assert_eq!(state.pan, (15.0, 10.0));
     }
 
+    #[test]
+    fn image_viewer_state_visible_image_rect() {
+        let mut state = ImageViewerState::new();
+        assert_eq!(state.visible_image_rect(), None);
+
+        // Fitted: the whole image
+        state.sync_with_bounds(400.0, 300.0, 800, 600);
+        assert_eq!(state.visible_image_rect(), Some((0.0, 0.0, 800.0, 600.0)));
+
+        // 1:1, panned a quarter view right and up: the left/lower part
+        state.set_one_to_one();
+        state.pan_by(0.5, 0.5);
+        assert_eq!(
+            state.visible_image_rect(),
+            Some((100.0, 225.0, 400.0, 300.0))
+        );
+    }
+
     // =========================================================================
     // CollapsibleState Tests
     // =========================================================================
@@ -0,0 +1,199 @@
+//! Colorbar legend for pseudo-color images
+//!
+//! Drawn by the image viewer in its bottom-left corner: a horizontal gradient
+//! from `min` to `max` with tick values and an optional label.
+
+use crate::constants::FONT_SIZE_TINY;
+use crate::layout::Bounds;
+use crate::renderer::{Color, Renderer};
+use crate::theme::current_theme;
+
+/// Width of the gradient bar in pixels
+const BAR_WIDTH: f32 = 200.0;
+/// Height of the gradient bar in pixels
+const BAR_HEIGHT: f32 = 12.0;
+/// Distance from the viewer edges
+const MARGIN: f32 = 12.0;
+/// Padding inside the legend background
+const PADDING: f32 = 6.0;
+/// Length of the tick marks below the bar
+const TICK_LENGTH: f32 = 4.0;
+/// Maximum number of tick values
+const MAX_TICKS: usize = 5;
+
+/// Colorbar legend shown over an image
+#[derive(Debug, Clone)]
+pub struct ColorbarLegend {
+    /// Gradient colors, evenly spaced from `min` to `max`
+    pub colors: Vec<Color>,
+    /// Value at the left end of the bar
+    pub min: f32,
+    /// Value at the right end of the bar
+    pub max: f32,
+    /// Text above the bar (e.g. band name)
+    pub label: Option<String>,
+}
+
+impl ColorbarLegend {
+    /// Create a legend for a gradient from `min` to `max`
+    pub fn new(colors: Vec<Color>, min: f32, max: f32) -> Self {
+        Self {
+            colors,
+            min,
+            max,
+            label: None,
+        }
+    }
+
+    /// Set the label shown above the bar
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Draw the legend in the bottom-left corner of `viewer`
+    pub(crate) fn draw(&self, renderer: &mut Renderer, viewer: Bounds) {
+        if self.colors.is_empty() {
+            return;
+        }
+        let theme = current_theme();
+        let label_height = if self.label.is_some() {
+            FONT_SIZE_TINY + 3.0
+        } else {
+            0.0
+        };
+        let height = PADDING * 2.0 + label_height + BAR_HEIGHT + TICK_LENGTH + FONT_SIZE_TINY + 2.0;
+        let background = Bounds::new(
+            viewer.x + MARGIN,
+            viewer.y + viewer.height - MARGIN - height,
+            BAR_WIDTH + PADDING * 4.0,
+            height,
+        );
+        renderer.fill_rect(background, Color::rgba(0.0, 0.0, 0.0, 0.6));
+
+        let bar_x = background.x + PADDING * 2.0;
+        let mut y = background.y + PADDING;
+        if let Some(label) = &self.label {
+            renderer.text(label, bar_x, y, FONT_SIZE_TINY, theme.text_primary);
+            y += label_height;
+        }
+
+        // Gradient as one rectangle per color
+        let step = BAR_WIDTH / self.colors.len() as f32;
+        for (i, color) in self.colors.iter().enumerate() {
+            // Overlap by a pixel to avoid seams
+            let segment = Bounds::new(bar_x + i as f32 * step, y, step + 1.0, BAR_HEIGHT);
+            renderer.fill_rect(segment, *color);
+        }
+        let bar = Bounds::new(bar_x, y, BAR_WIDTH, BAR_HEIGHT);
+        renderer.stroke_rect(bar, theme.border, 1.0);
+
+        // Ticks with values
+        let span = self.max - self.min;
+        let tick_y = y + BAR_HEIGHT;
+        let ticks = nice_ticks(self.min, self.max, MAX_TICKS);
+        let step = match ticks.as_slice() {
+            [first, second, ..] => second - first,
+            _ => span,
+        };
+        for value in ticks {
+            let x = if span != 0.0 {
+                bar_x + (value - self.min) / span * BAR_WIDTH
+            } else {
+                bar_x
+            };
+            renderer.line(x, tick_y, x, tick_y + TICK_LENGTH, theme.text_primary, 1.0);
+            let text = format_tick(value, step);
+            let width = renderer.measure_text_width(&text, FONT_SIZE_TINY);
+            let text_x = (x - width / 2.0)
+                .max(background.x + 2.0)
+                .min(background.x + background.width - width - 2.0);
+            renderer.text(
+                &text,
+                text_x,
+                tick_y + TICK_LENGTH + 1.0,
+                FONT_SIZE_TINY,
+                theme.text_primary,
+            );
+        }
+    }
+}
+
+/// Round tick values (1, 2, 2.5 or 5 times a power of ten apart) between
+/// `min` and `max`, at most `max_ticks` of them.
+///
+/// `max` may be below `min` (reversed axis). An empty range gives one tick.
+pub fn nice_ticks(min: f32, max: f32, max_ticks: usize) -> Vec<f32> {
+    let (low, high) = if min <= max { (min, max) } else { (max, min) };
+    let span = high - low;
+    if !span.is_finite() || !low.is_finite() || max_ticks == 0 {
+        return Vec::new();
+    }
+    if span == 0.0 {
+        return vec![low];
+    }
+
+    // Smallest step that keeps the tick count within the limit
+    let magnitude = 10f32.powf((span / max_ticks as f32).log10().floor());
+    for multiplier in [1.0, 2.0, 2.5, 5.0, 10.0, 20.0, 25.0, 50.0] {
+        let step = multiplier * magnitude;
+        let first = (low / step).ceil() as i64;
+        let last = (high / step).floor() as i64;
+        if last - first < max_ticks as i64 {
+            return (first..=last).map(|i| i as f32 * step).collect();
+        }
+    }
+    vec![low, high]
+}
+
+/// Format a tick value with the decimals needed for the tick spacing.
+fn format_tick(value: f32, step: f32) -> String {
+    let magnitude = value.abs().max(step.abs());
+    if magnitude != 0.0 && !(0.001..100000.0).contains(&magnitude) {
+        return format!("{:.1e}", value);
+    }
+    let decimals = (0..4)
+        .find(|&d| {
+            let scaled = step.abs() * 10f32.powi(d);
+            (scaled - scaled.round()).abs() < 1e-3 * scaled.max(1.0)
+        })
+        .unwrap_or(4) as usize;
+    // Avoid "-0"
+    let value = if value == 0.0 { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nice_ticks() {
+        assert_eq!(nice_ticks(0.0, 1.0, 5), vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(nice_ticks(-1.0, 1.0, 5), vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(nice_ticks(3.0, 97.0, 5), vec![20.0, 40.0, 60.0, 80.0]);
+        // Reversed and empty ranges
+        assert_eq!(nice_ticks(1.0, 0.0, 5), nice_ticks(0.0, 1.0, 5));
+        assert_eq!(nice_ticks(2.0, 2.0, 5), vec![2.0]);
+        assert!(nice_ticks(f32::NAN, 1.0, 5).is_empty());
+    }
+
+    #[test]
+    fn test_nice_ticks_within_limit() {
+        for (min, max) in [(0.0, 65535.0), (-0.37, 0.81), (1e-4, 3e-4), (400.0, 2500.0)] {
+            let ticks = nice_ticks(min, max, 5);
+            assert!(!ticks.is_empty() && ticks.len() <= 5, "{:?}", ticks);
+            assert!(ticks.iter().all(|&t| t >= min - 1e-6 && t <= max + 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_format_tick() {
+        assert_eq!(format_tick(0.5, 0.25), "0.50");
+        assert_eq!(format_tick(0.75, 0.25), "0.75");
+        assert_eq!(format_tick(500.0, 250.0), "500");
+        assert_eq!(format_tick(-0.0, 0.5), "0.0");
+        assert_eq!(format_tick(60000.0, 20000.0), "60000");
+        assert_eq!(format_tick(200000.0, 100000.0), "2.0e5");
+    }
+}
@@ -0,0 +1,530 @@
+//! Histogram widget with draggable clip markers
+//!
+//! Shows value counts as bars between the data minimum and maximum, with a
+//! low and a high marker that can be dragged to set a contrast stretch. The
+//! range outside the markers is shaded and a gamma curve is drawn between
+//! them. A "log" toggle under the plot switches the bar heights to a
+//! logarithmic scale.
+
+use crate::callback::{Callback, SideEffect};
+use crate::constants::{format_number, FONT_SIZE_TINY};
+use crate::event::{Event, MouseButton};
+use crate::layout::{Bounds, Length, Size};
+use crate::renderer::{Color, Renderer};
+use crate::state::{HistogramMarker, HistogramState};
+use crate::theme::current_theme;
+use crate::widget::{EventResult, Widget};
+
+/// Default widget height in pixels
+const DEFAULT_HISTOGRAM_HEIGHT: f32 = 80.0;
+/// Space reserved below the plot area for the marker values
+const VALUE_LABEL_HEIGHT: f32 = FONT_SIZE_TINY + 3.0;
+/// Horizontal distance within which a press grabs a marker
+const MARKER_HIT_DISTANCE: f32 = 6.0;
+/// Size of the marker handles at the top of the plot
+const MARKER_HANDLE_SIZE: f32 = 5.0;
+/// Number of line segments of the gamma curve
+const CURVE_SEGMENTS: usize = 32;
+/// Smallest marker distance as a fraction of the data range
+const MIN_MARKER_GAP: f32 = 1e-3;
+
+/// Configuration for histogram appearance
+#[derive(Debug, Clone)]
+pub struct HistogramConfig {
+    /// Plot area background color
+    pub background_color: Color,
+    /// Plot area border color
+    pub border_color: Color,
+    /// Bar color
+    pub bar_color: Color,
+    /// Shading over the clipped ranges
+    pub clip_color: Color,
+    /// Marker color
+    pub marker_color: Color,
+    /// Marker color while hovered or dragged
+    pub marker_active_color: Color,
+    /// Gamma curve color
+    pub curve_color: Color,
+    /// Label color
+    pub label_color: Color,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        let theme = current_theme();
+        Self {
+            background_color: theme.input_bg,
+            border_color: theme.border,
+            bar_color: theme.text_secondary,
+            clip_color: Color::rgba(0.0, 0.0, 0.0, 0.45),
+            marker_color: theme.text_primary,
+            marker_active_color: theme.accent,
+            curve_color: theme.accent,
+            label_color: theme.text_secondary,
+        }
+    }
+}
+
+/// A histogram widget with low/high clip markers
+pub struct Histogram<M> {
+    /// Value counts in equal-width bins from `min` to `max`
+    counts: Vec<u32>,
+    /// Value at the left edge of the first bin
+    min: f32,
+    /// Value at the right edge of the last bin
+    max: f32,
+    /// Widget state (cloned from external)
+    state: HistogramState,
+    /// Gamma of the previewed curve (1.0 = straight line)
+    gamma: f32,
+    /// Width
+    width: Length,
+    /// Height
+    height: Length,
+    /// Marker under the cursor
+    hovered: Option<HistogramMarker>,
+    /// Configuration
+    config: HistogramConfig,
+    /// Callback for marker and scale changes
+    on_change: Callback<HistogramState, M>,
+    /// Side-effect callback for undo point (called when a marker drag begins)
+    on_undo_point: SideEffect,
+}
+
+impl<M> Histogram<M> {
+    /// Create a histogram of `counts` spanning `min` to `max`
+    pub fn new(counts: Vec<u32>, min: f32, max: f32) -> Self {
+        Self {
+            counts,
+            min,
+            max,
+            state: HistogramState::default(),
+            gamma: 1.0,
+            width: Length::Fill(1.0),
+            height: Length::Fixed(DEFAULT_HISTOGRAM_HEIGHT),
+            hovered: None,
+            config: HistogramConfig::default(),
+            on_change: Callback::none(),
+            on_undo_point: SideEffect::none(),
+        }
+    }
+
+    /// Set the state
+    pub fn state(mut self, state: &HistogramState) -> Self {
+        self.state = state.clone();
+        self
+    }
+
+    /// Set the gamma of the previewed curve
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Set the bar color
+    pub fn bar_color(mut self, color: Color) -> Self {
+        self.config.bar_color = color;
+        self
+    }
+
+    /// Set the width
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    /// Set the height
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// Set the appearance configuration
+    pub fn config(mut self, config: HistogramConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the change handler (markers moved or scale toggled)
+    pub fn on_change<F>(mut self, f: F) -> Self
+    where
+        F: Fn(HistogramState) -> M + 'static,
+    {
+        self.on_change = Callback::new(f);
+        self
+    }
+
+    /// Set the undo point callback, called before a marker drag changes the state
+    pub fn on_undo_point<F>(mut self, callback: F) -> Self
+    where
+        F: Fn() + 'static,
+    {
+        self.on_undo_point = SideEffect::new(callback);
+        self
+    }
+
+    /// Plot area inside the widget bounds
+    fn plot_bounds(&self, bounds: Bounds) -> Bounds {
+        Bounds::new(
+            bounds.x,
+            bounds.y,
+            bounds.width,
+            (bounds.height - VALUE_LABEL_HEIGHT).max(1.0),
+        )
+    }
+
+    /// Area of the log scale toggle, centered below the plot
+    fn log_toggle_bounds(&self, plot: Bounds) -> Bounds {
+        let width = FONT_SIZE_TINY * 2.0;
+        Bounds::new(
+            plot.x + (plot.width - width) / 2.0,
+            plot.y + plot.height,
+            width,
+            VALUE_LABEL_HEIGHT,
+        )
+    }
+
+    /// Data range, widened when empty
+    fn range(&self) -> (f32, f32) {
+        if self.max > self.min {
+            (self.min, self.max)
+        } else {
+            (self.min - 0.5, self.min + 0.5)
+        }
+    }
+
+    /// Screen x of the low and high marker
+    fn marker_xs(&self, plot: Bounds) -> (f32, f32) {
+        let (min, max) = self.range();
+        (
+            value_to_x(self.state.low, min, max, plot),
+            value_to_x(self.state.high, min, max, plot),
+        )
+    }
+
+    /// Marker within grabbing distance of `x`, the closer one if both are
+    fn marker_at(&self, x: f32, plot: Bounds) -> Option<HistogramMarker> {
+        let (low_x, high_x) = self.marker_xs(plot);
+        let low_distance = (x - low_x).abs();
+        let high_distance = (x - high_x).abs();
+        if low_distance.min(high_distance) > MARKER_HIT_DISTANCE {
+            None
+        } else if low_distance < high_distance || (low_distance == high_distance && x < low_x) {
+            Some(HistogramMarker::Low)
+        } else {
+            Some(HistogramMarker::High)
+        }
+    }
+
+    /// Move `marker` to the value at screen `x`
+    ///
+    /// Returns whether the state changed.
+    fn move_marker(&mut self, marker: HistogramMarker, x: f32, plot: Bounds) -> bool {
+        let (min, max) = self.range();
+        let value = x_to_value(x, min, max, plot);
+        let (low, high) =
+            place_marker(marker, value, (self.state.low, self.state.high), (min, max));
+        let changed = low != self.state.low || high != self.state.high;
+        self.state.low = low;
+        self.state.high = high;
+        changed
+    }
+
+    /// Emit a change message
+    fn emit_change(&self) -> EventResult<M> {
+        match self.on_change.call(self.state.clone()) {
+            Some(msg) => EventResult::Message(msg),
+            None => EventResult::Redraw,
+        }
+    }
+}
+
+/// Screen x of `value` in a plot spanning `min` to `max`, clamped to the plot.
+fn value_to_x(value: f32, min: f32, max: f32, plot: Bounds) -> f32 {
+    let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+    plot.x + t * plot.width
+}
+
+/// Value at screen `x` in a plot spanning `min` to `max`, clamped to the range.
+fn x_to_value(x: f32, min: f32, max: f32, plot: Bounds) -> f32 {
+    let t = ((x - plot.x) / plot.width).clamp(0.0, 1.0);
+    min + t * (max - min)
+}
+
+/// Marker values after moving `marker` to `value`.
+///
+/// The value is clamped to `range` and kept at least `MIN_MARKER_GAP` of the
+/// range away from the other marker, so low stays below high.
+fn place_marker(
+    marker: HistogramMarker,
+    value: f32,
+    (low, high): (f32, f32),
+    (min, max): (f32, f32),
+) -> (f32, f32) {
+    let gap = (max - min) * MIN_MARKER_GAP;
+    match marker {
+        HistogramMarker::Low => (value.clamp(min, (high - gap).max(min)), high),
+        HistogramMarker::High => (low, value.clamp((low + gap).min(max), max)),
+    }
+}
+
+/// Bar height as a fraction of the plot height.
+fn bar_fraction(count: u32, max_count: u32, log_scale: bool) -> f32 {
+    if max_count == 0 {
+        0.0
+    } else if log_scale {
+        (count as f32).ln_1p() / (max_count as f32).ln_1p()
+    } else {
+        count as f32 / max_count as f32
+    }
+}
+
+impl<M: Clone + 'static> Widget<M> for Histogram<M> {
+    fn layout(&mut self, available: Size) -> Size {
+        Size::new(
+            self.width.resolve(available.width, available.width),
+            self.height
+                .resolve(available.height, DEFAULT_HISTOGRAM_HEIGHT),
+        )
+    }
+
+    fn draw(&self, renderer: &mut Renderer, bounds: Bounds) {
+        let plot = self.plot_bounds(bounds);
+        renderer.fill_rect(plot, self.config.background_color);
+
+        // Bars
+        let max_count = self.counts.iter().copied().max().unwrap_or(0);
+        if !self.counts.is_empty() {
+            let bar_width = plot.width / self.counts.len() as f32;
+            for (i, &count) in self.counts.iter().enumerate() {
+                let height = bar_fraction(count, max_count, self.state.log_scale) * plot.height;
+                if height <= 0.0 {
+                    continue;
+                }
+                // Overlap by a pixel to avoid seams
+                let bar = Bounds::new(
+                    plot.x + i as f32 * bar_width,
+                    plot.y + plot.height - height,
+                    bar_width + 1.0,
+                    height,
+                );
+                renderer.fill_rect(bar, self.config.bar_color);
+            }
+        }
+
+        // Clipped ranges
+        let (low_x, high_x) = self.marker_xs(plot);
+        if low_x > plot.x {
+            let shade = Bounds::new(plot.x, plot.y, low_x - plot.x, plot.height);
+            renderer.fill_rect(shade, self.config.clip_color);
+        }
+        if high_x < plot.x + plot.width {
+            let shade = Bounds::new(high_x, plot.y, plot.x + plot.width - high_x, plot.height);
+            renderer.fill_rect(shade, self.config.clip_color);
+        }
+
+        // Gamma curve between the markers
+        if high_x > low_x && self.gamma > 0.0 {
+            let bottom = plot.y + plot.height;
+            let mut previous = (low_x, bottom);
+            for i in 1..=CURVE_SEGMENTS {
+                let t = i as f32 / CURVE_SEGMENTS as f32;
+                let point = (
+                    low_x + t * (high_x - low_x),
+                    bottom - t.powf(1.0 / self.gamma) * plot.height,
+                );
+                renderer.line(
+                    previous.0,
+                    previous.1,
+                    point.0,
+                    point.1,
+                    self.config.curve_color,
+                    1.0,
+                );
+                previous = point;
+            }
+        }
+
+        // Markers with handles
+        let dragged = self.state.drag.data().copied();
+        for (marker, x) in [
+            (HistogramMarker::Low, low_x),
+            (HistogramMarker::High, high_x),
+        ] {
+            let color = if dragged == Some(marker) || self.hovered == Some(marker) {
+                self.config.marker_active_color
+            } else {
+                self.config.marker_color
+            };
+            renderer.line(x, plot.y, x, plot.y + plot.height, color, 1.5);
+            let handle = Bounds::new(
+                x - MARKER_HANDLE_SIZE / 2.0,
+                plot.y,
+                MARKER_HANDLE_SIZE,
+                MARKER_HANDLE_SIZE,
+            );
+            renderer.fill_rect(handle, color);
+        }
+
+        renderer.stroke_rect(plot, self.config.border_color, 1.0);
+
+        // Log scale toggle
+        let toggle = self.log_toggle_bounds(plot);
+        let toggle_color = if self.state.log_scale {
+            self.config.marker_active_color
+        } else {
+            self.config.label_color
+        };
+        renderer.text(
+            "log",
+            toggle.x,
+            toggle.y + 2.0,
+            FONT_SIZE_TINY,
+            toggle_color,
+        );
+
+        // Marker values
+        let label_y = plot.y + plot.height + 2.0;
+        let low_label = format_number(self.state.low);
+        let high_label = format_number(self.state.high);
+        let high_width = renderer.measure_text_width(&high_label, FONT_SIZE_TINY);
+        renderer.text(
+            &low_label,
+            plot.x,
+            label_y,
+            FONT_SIZE_TINY,
+            self.config.label_color,
+        );
+        renderer.text(
+            &high_label,
+            plot.x + plot.width - high_width,
+            label_y,
+            FONT_SIZE_TINY,
+            self.config.label_color,
+        );
+    }
+
+    fn on_event(&mut self, event: &Event, bounds: Bounds) -> EventResult<M> {
+        let plot = self.plot_bounds(bounds);
+
+        match event {
+            Event::MouseMove { position, .. } => {
+                let (x, y) = *position;
+
+                if let Some(&marker) = self.state.drag.data() {
+                    if self.move_marker(marker, x, plot) {
+                        return self.emit_change();
+                    }
+                    return EventResult::None;
+                }
+
+                let was_hovered = self.hovered;
+                self.hovered = if plot.contains(x, y) {
+                    self.marker_at(x, plot)
+                } else {
+                    None
+                };
+                if self.hovered != was_hovered {
+                    EventResult::Redraw
+                } else {
+                    EventResult::None
+                }
+            }
+
+            Event::MousePress {
+                button: MouseButton::Left,
+                position,
+                ..
+            } => {
+                let (x, y) = *position;
+                if self.log_toggle_bounds(plot).contains(x, y) {
+                    self.state.log_scale = !self.state.log_scale;
+                    log::debug!("Histogram: log scale = {}", self.state.log_scale);
+                    return self.emit_change();
+                }
+                if !plot.contains(x, y) {
+                    return EventResult::None;
+                }
+
+                // Grab the marker under the cursor, or move the nearer one here
+                let marker = self.marker_at(x, plot).unwrap_or_else(|| {
+                    let (low_x, high_x) = self.marker_xs(plot);
+                    if (x - low_x).abs() <= (x - high_x).abs() {
+                        HistogramMarker::Low
+                    } else {
+                        HistogramMarker::High
+                    }
+                });
+                log::debug!("Histogram: calling on_undo_point (drag start)");
+                self.on_undo_point.emit();
+                self.state.drag.start_drag_with(marker);
+                self.move_marker(marker, x, plot);
+                log::debug!("Histogram: started dragging {:?} marker", marker);
+                self.emit_change()
+            }
+
+            Event::MouseRelease {
+                button: MouseButton::Left,
+                ..
+            } => {
+                if self.state.drag.is_dragging() {
+                    self.state.drag.stop_drag();
+                    log::debug!("Histogram: stopped dragging");
+                    return self.emit_change();
+                }
+                EventResult::None
+            }
+
+            _ => EventResult::None,
+        }
+    }
+
+    fn has_active_drag(&self) -> bool {
+        self.state.drag.is_dragging()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_x_mapping() {
+        let plot = Bounds::new(10.0, 0.0, 100.0, 50.0);
+        assert_eq!(value_to_x(0.0, 0.0, 200.0, plot), 10.0);
+        assert_eq!(value_to_x(50.0, 0.0, 200.0, plot), 35.0);
+        assert_eq!(x_to_value(35.0, 0.0, 200.0, plot), 50.0);
+        // Both directions clamp to the plot and range
+        assert_eq!(value_to_x(-10.0, 0.0, 200.0, plot), 10.0);
+        assert_eq!(x_to_value(500.0, 0.0, 200.0, plot), 200.0);
+    }
+
+    #[test]
+    fn test_place_marker_keeps_order() {
+        let range = (0.0, 100.0);
+        assert_eq!(
+            place_marker(HistogramMarker::Low, 20.0, (10.0, 90.0), range),
+            (20.0, 90.0)
+        );
+        // Low cannot pass high and high cannot pass low
+        let (low, high) = place_marker(HistogramMarker::Low, 95.0, (10.0, 90.0), range);
+        assert!(low < high && high == 90.0);
+        let (low, high) = place_marker(HistogramMarker::High, 5.0, (10.0, 90.0), range);
+        assert!(low < high && low == 10.0);
+        // Values outside the data range are clamped
+        assert_eq!(
+            place_marker(HistogramMarker::High, 150.0, (10.0, 90.0), range),
+            (10.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_bar_fraction() {
+        assert_eq!(bar_fraction(50, 100, false), 0.5);
+        assert_eq!(bar_fraction(100, 100, true), 1.0);
+        assert_eq!(bar_fraction(0, 100, true), 0.0);
+        // Log scale lifts small counts
+        assert!(bar_fraction(10, 1000, true) > 0.3);
+        assert_eq!(bar_fraction(0, 0, false), 0.0);
+    }
+}
@@ -1,5 +1,6 @@
 //! Image viewer widget with pan and zoom
 
+use super::ColorbarLegend;
 use crate::callback::Callback;
 use crate::event::{Event, KeyCode, MouseButton};
 use crate::layout::{Bounds, Length, Size};
@@ -26,6 +27,9 @@ const CONTROL_PADDING: f32 = 8.0;
 /// Control button spacing
 const CONTROL_SPACING: f32 = 4.0;
 
+/// Distance of a rotated box's rotation handle beyond its top edge (screen pixels)
+const ROTATE_HANDLE_OFFSET: f32 = 24.0;
+
 /// The kind of pointer event on the image.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PointerEventKind {
@@ -86,6 +90,14 @@ pub enum OverlayShape {
         width: f32,
         height: f32,
     },
+    /// Oriented box (center, size, rotation in radians) in image coordinates
+    RotatedBox {
+        cx: f32,
+        cy: f32,
+        width: f32,
+        height: f32,
+        angle: f32,
+    },
     /// Point marker (x, y) in image coordinates
     Point { x: f32, y: f32 },
     /// Polygon (vertices) in image coordinates
@@ -93,6 +105,21 @@ pub enum OverlayShape {
         vertices: Vec<(f32, f32)>,
         closed: bool,
     },
+    /// Closed rings (parts and holes) in image coordinates
+    MultiPolygon { rings: Vec<Vec<(f32, f32)>> },
+    /// Open path (vertices) in image coordinates, with a stroke width in image pixels
+    Polyline { vertices: Vec<(f32, f32)>, width: f32 },
+    /// Raster mask as horizontal pixel runs (x, y, length) in image coordinates
+    Mask { runs: Vec<(u32, u32, u32)> },
+    /// Keypoints (x, y, visibility) in image coordinates, with skeleton edges
+    /// as index pairs and an optional instance box (x, y, width, height).
+    /// Visibility follows COCO: 0 = not labeled (hidden), 1 = occluded
+    /// (hollow), 2 = visible (filled).
+    Keypoints {
+        points: Vec<(f32, f32, u8)>,
+        skeleton: Vec<(usize, usize)>,
+        bbox: Option<(f32, f32, f32, f32)>,
+    },
 }
 
 /// An image viewer widget with pan and zoom capabilities
@@ -103,6 +130,8 @@ pub struct ImageViewer<M> {
     texture_width: u32,
     /// Texture height
     texture_height: u32,
+    /// Image area the texture shows (x, y, width, height), None = all of it
+    texture_region: Option<(f32, f32, f32, f32)>,
     /// Current state
     state: ImageViewerState,
     /// Image adjustments (brightness, contrast, gamma, hue) - applied on GPU
@@ -111,6 +140,10 @@ pub struct ImageViewer<M> {
     on_change: Callback<ImageViewerState, M>,
     /// Pointer event handler for annotation tools
     on_pointer: Callback<ImagePointerEvent, M>,
+    /// Handler for the image pixel under the cursor (None = outside the image)
+    on_hover: Callback<Option<(u32, u32)>, M>,
+    /// Last pixel reported to `on_hover`
+    hover_pixel: Option<(u32, u32)>,
     /// Enable panning
     pannable: bool,
     /// Enable zooming
@@ -125,6 +158,8 @@ pub struct ImageViewer<M> {
     overlays: Vec<AnnotationOverlay>,
     /// Interaction mode (View or Annotate)
     interaction_mode: InteractionMode,
+    /// Colorbar legend of a pseudo-color image
+    colorbar: Option<ColorbarLegend>,
     /// Phantom data for message type
     _phantom: PhantomData<M>,
 }
@@ -135,10 +170,13 @@ impl<M> Default for ImageViewer<M> {
             texture_id: None,
             texture_width: 0,
             texture_height: 0,
+            texture_region: None,
             state: ImageViewerState::default(),
             adjustments: ImageAdjustments::default(),
             on_change: Callback::none(),
             on_pointer: Callback::none(),
+            on_hover: Callback::none(),
+            hover_pixel: None,
             pannable: true,
             zoomable: true,
             show_controls: true,
@@ -146,6 +184,7 @@ impl<M> Default for ImageViewer<M> {
             height: Length::fill(),
             overlays: Vec::new(),
             interaction_mode: InteractionMode::default(),
+            colorbar: None,
             _phantom: PhantomData,
         }
     }
@@ -175,6 +214,16 @@ impl<M> ImageViewer<M> {
         self
     }
 
+    /// Set the image area the texture shows, in image pixels.
+    ///
+    /// For images rendered only around the visible area (e.g. tiled images),
+    /// the texture is drawn over that area. `width` and `height` stay the
+    /// full image size, so zoom, pan and overlays work in image coordinates.
+    pub fn texture_region(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.texture_region = Some((x, y, width, height));
+        self
+    }
+
     /// Set the viewer state
     pub fn state(mut self, state: &ImageViewerState) -> Self {
         self.state = state.clone();
@@ -229,18 +278,53 @@ impl<M> ImageViewer<M> {
         self
     }
 
+    /// Set the handler for the image pixel under the cursor
+    ///
+    /// Called with `Some((x, y))` whenever the cursor moves onto a different
+    /// pixel and with `None` when it leaves the image.
+    pub fn on_hover<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Option<(u32, u32)>) -> M + 'static,
+    {
+        self.on_hover = Callback::new(handler);
+        self
+    }
+
+    /// Set the pixel last reported to `on_hover` (avoids repeated messages)
+    pub fn hover_pixel(mut self, pixel: Option<(u32, u32)>) -> Self {
+        self.hover_pixel = pixel;
+        self
+    }
+
     /// Set the interaction mode (View or Annotate)
     pub fn interaction_mode(mut self, mode: InteractionMode) -> Self {
         self.interaction_mode = mode;
         self
     }
 
+    /// Image pixel at a screen position, if the position is over the image.
+    fn pixel_at(&self, screen_x: f32, screen_y: f32, bounds: &Bounds) -> Option<(u32, u32)> {
+        if !bounds.contains(screen_x, screen_y) {
+            return None;
+        }
+        let (x, y) = self.screen_to_image(screen_x, screen_y, bounds);
+        let inside =
+            x >= 0.0 && y >= 0.0 && x < self.texture_width as f32 && y < self.texture_height as f32;
+        inside.then_some((x as u32, y as u32))
+    }
+
     /// Set annotation overlays to draw
     pub fn overlays(mut self, overlays: Vec<AnnotationOverlay>) -> Self {
         self.overlays = overlays;
         self
     }
 
+    /// Set the colorbar legend drawn in the bottom-left corner
+    pub fn colorbar(mut self, colorbar: ColorbarLegend) -> Self {
+        self.colorbar = Some(colorbar);
+        self
+    }
+
     /// Set image adjustments (brightness, contrast, gamma, hue shift)
     ///
     /// These adjustments are applied on the GPU for real-time performance.
@@ -334,12 +418,21 @@ impl<M> ImageViewer<M> {
         let base_scale_x = (self.texture_width as f32 / bounds.width) * zoom;
         let base_scale_y = (self.texture_height as f32 / bounds.height) * zoom;
 
-        TransformUniform::from_transform_xy(
-            self.state.pan.0,
-            self.state.pan.1,
-            base_scale_x,
-            base_scale_y,
-        )
+        let (mut pan_x, mut pan_y) = self.state.pan;
+        let (mut scale_x, mut scale_y) = (base_scale_x, base_scale_y);
+        if let Some((x, y, width, height)) = self.texture_region {
+            // Shrink the quad onto the region's part of the image (UV y is
+            // flipped relative to image coordinates)
+            let (tex_w, tex_h) = (self.texture_width as f32, self.texture_height as f32);
+            let (u0, u1) = (x / tex_w, (x + width) / tex_w);
+            let (v0, v1) = (1.0 - (y + height) / tex_h, 1.0 - y / tex_h);
+            pan_x += (u0 + u1 - 1.0) * scale_x;
+            pan_y += (v0 + v1 - 1.0) * scale_y;
+            scale_x *= u1 - u0;
+            scale_y *= v1 - v0;
+        }
+
+        TransformUniform::from_transform_xy(pan_x, pan_y, scale_x, scale_y)
     }
 
     /// Convert screen position to clip space relative to widget bounds
@@ -647,6 +740,103 @@ impl<M: 'static> Widget<M> for ImageViewer<M> {
                         renderer.stroke_rect(center_bounds, handle_color, 1.0);
                     }
                 }
+                OverlayShape::RotatedBox {
+                    cx,
+                    cy,
+                    width,
+                    height,
+                    angle,
+                } => {
+                    let (sin, cos) = angle.sin_cos();
+                    let (hw, hh) = (width / 2.0, height / 2.0);
+                    // Corners clockwise from the local top-left
+                    let screen_corners: Vec<(f32, f32)> =
+                        [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+                            .iter()
+                            .map(|(lx, ly)| {
+                                self.image_to_screen(
+                                    cx + lx * cos - ly * sin,
+                                    cy + lx * sin + ly * cos,
+                                    &bounds,
+                                )
+                            })
+                            .collect();
+
+                    let strokes: &[(Color, f32)] = if overlay.selected {
+                        // Triple-stroke effect: outer black, inner white, category color in middle
+                        &[
+                            (selection_outer_color, overlay.line_width + 4.0),
+                            (selection_inner_color, overlay.line_width + 2.0),
+                            (color, overlay.line_width),
+                        ]
+                    } else {
+                        &[(color, overlay.line_width)]
+                    };
+                    for (stroke_color, stroke_width) in strokes {
+                        for i in 0..4 {
+                            let (x1, y1) = screen_corners[i];
+                            let (x2, y2) = screen_corners[(i + 1) % 4];
+                            renderer.line(x1, y1, x2, y2, *stroke_color, *stroke_width);
+                        }
+                    }
+
+                    // Draw handles if selected
+                    if overlay.selected {
+                        let handle_size = 6.0;
+                        let draw_handle =
+                            |renderer: &mut Renderer, hx: f32, hy: f32, fill: Color| {
+                                let handle_bounds = Bounds::new(
+                                    hx - handle_size / 2.0,
+                                    hy - handle_size / 2.0,
+                                    handle_size,
+                                    handle_size,
+                                );
+                                renderer.fill_rect(handle_bounds, fill);
+                                renderer.stroke_rect(handle_bounds, handle_color, 1.0);
+                            };
+
+                        // Corner handles (for resize in both directions)
+                        for (hx, hy) in &screen_corners {
+                            draw_handle(renderer, *hx, *hy, Color::WHITE);
+                        }
+
+                        // Edge midpoint handles (for resize in one direction)
+                        for i in 0..4 {
+                            let (x1, y1) = screen_corners[i];
+                            let (x2, y2) = screen_corners[(i + 1) % 4];
+                            draw_handle(
+                                renderer,
+                                (x1 + x2) / 2.0,
+                                (y1 + y2) / 2.0,
+                                Color::rgba(0.9, 0.9, 0.9, 1.0),
+                            );
+                        }
+
+                        // Rotation handle on a stem beyond the top edge
+                        let (top_x, top_y) = (
+                            (screen_corners[0].0 + screen_corners[1].0) / 2.0,
+                            (screen_corners[0].1 + screen_corners[1].1) / 2.0,
+                        );
+                        let (rot_x, rot_y) = (
+                            top_x + ROTATE_HANDLE_OFFSET * sin,
+                            top_y - ROTATE_HANDLE_OFFSET * cos,
+                        );
+                        renderer.line(top_x, top_y, rot_x, rot_y, handle_color, 1.0);
+                        draw_handle(renderer, rot_x, rot_y, handle_color);
+
+                        // Center handle (for move)
+                        let (center_x, center_y) = self.image_to_screen(*cx, *cy, &bounds);
+                        let center_size = 8.0;
+                        let center_bounds = Bounds::new(
+                            center_x - center_size / 2.0,
+                            center_y - center_size / 2.0,
+                            center_size,
+                            center_size,
+                        );
+                        renderer.fill_rect(center_bounds, Color::rgba(1.0, 1.0, 0.5, 0.6));
+                        renderer.stroke_rect(center_bounds, handle_color, 1.0);
+                    }
+                }
                 OverlayShape::Point { x, y } => {
                     let (screen_x, screen_y) = self.image_to_screen(*x, *y, &bounds);
                     log::info!(
@@ -813,12 +1003,283 @@ impl<M: 'static> Widget<M> for ImageViewer<M> {
                         }
                     }
                 }
+                OverlayShape::MultiPolygon { rings } => {
+                    let screen_rings: Vec<Vec<(f32, f32)>> = rings
+                        .iter()
+                        .map(|ring| {
+                            ring.iter()
+                                .map(|(x, y)| self.image_to_screen(*x, *y, &bounds))
+                                .collect()
+                        })
+                        .collect();
+                    // Every ring is closed on itself
+                    let segments: Vec<((f32, f32), (f32, f32))> = screen_rings
+                        .iter()
+                        .filter(|ring| ring.len() >= 2)
+                        .flat_map(|ring| {
+                            (0..ring.len()).map(|i| (ring[i], ring[(i + 1) % ring.len()]))
+                        })
+                        .collect();
+                    if segments.is_empty() {
+                        continue;
+                    }
+
+                    // Triple-stroke effect when selected: outer black, inner white, category color
+                    let strokes: &[(Color, f32)] = if overlay.selected {
+                        &[
+                            (selection_outer_color, overlay.line_width + 4.0),
+                            (selection_inner_color, overlay.line_width + 2.0),
+                            (color, overlay.line_width),
+                        ]
+                    } else {
+                        &[(color, overlay.line_width)]
+                    };
+                    for (stroke_color, stroke_width) in strokes {
+                        for ((x1, y1), (x2, y2)) in &segments {
+                            renderer.line(*x1, *y1, *x2, *y2, *stroke_color, *stroke_width);
+                        }
+                    }
+
+                    // Vertex handles of all rings, and the center handle (for move)
+                    if overlay.selected {
+                        let handle_size = 6.0;
+                        for (sx, sy) in screen_rings.iter().flatten() {
+                            let handle_bounds = Bounds::new(
+                                sx - handle_size / 2.0,
+                                sy - handle_size / 2.0,
+                                handle_size,
+                                handle_size,
+                            );
+                            renderer.fill_rect(handle_bounds, Color::WHITE);
+                            renderer.stroke_rect(handle_bounds, handle_color, 1.0);
+                        }
+
+                        let n = screen_rings.iter().map(Vec::len).sum::<usize>() as f32;
+                        let center_x =
+                            screen_rings.iter().flatten().map(|(x, _)| x).sum::<f32>() / n;
+                        let center_y =
+                            screen_rings.iter().flatten().map(|(_, y)| y).sum::<f32>() / n;
+                        let center_size = 8.0;
+                        let center_bounds = Bounds::new(
+                            center_x - center_size / 2.0,
+                            center_y - center_size / 2.0,
+                            center_size,
+                            center_size,
+                        );
+                        renderer.fill_rect(center_bounds, Color::rgba(1.0, 1.0, 0.5, 0.6));
+                        renderer.stroke_rect(center_bounds, handle_color, 1.0);
+                    }
+                }
+                OverlayShape::Polyline { vertices, width } => {
+                    if vertices.is_empty() {
+                        continue;
+                    }
+
+                    let screen_verts: Vec<(f32, f32)> = vertices
+                        .iter()
+                        .map(|(x, y)| self.image_to_screen(*x, *y, &bounds))
+                        .collect();
+
+                    // Stroke band at the annotated line width (zoom = screen px per image px)
+                    let band_width = width * self.calculate_zoom_for_mode(&bounds);
+                    let band_color = Color::rgba(color.r, color.g, color.b, color.a * 0.35);
+                    let strokes: &[(Color, f32)] = if overlay.selected {
+                        // Triple-stroke effect: outer black, inner white, category color in middle
+                        &[
+                            (band_color, band_width),
+                            (selection_outer_color, overlay.line_width + 4.0),
+                            (selection_inner_color, overlay.line_width + 2.0),
+                            (color, overlay.line_width),
+                        ]
+                    } else {
+                        &[(band_color, band_width), (color, overlay.line_width)]
+                    };
+                    for (stroke_color, stroke_width) in strokes {
+                        for pair in screen_verts.windows(2) {
+                            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                            renderer.line(x1, y1, x2, y2, *stroke_color, *stroke_width);
+                        }
+                    }
+
+                    // Draw vertex handles if selected
+                    if overlay.selected {
+                        let handle_size = 6.0;
+                        for (sx, sy) in &screen_verts {
+                            let handle_bounds = Bounds::new(
+                                sx - handle_size / 2.0,
+                                sy - handle_size / 2.0,
+                                handle_size,
+                                handle_size,
+                            );
+                            renderer.fill_rect(handle_bounds, Color::WHITE);
+                            renderer.stroke_rect(handle_bounds, handle_color, 1.0);
+                        }
+
+                        // Center handle (for move) halfway along the path
+                        let lengths: Vec<f32> = screen_verts
+                            .windows(2)
+                            .map(|p| ((p[1].0 - p[0].0).powi(2) + (p[1].1 - p[0].1).powi(2)).sqrt())
+                            .collect();
+                        let mut remaining = lengths.iter().sum::<f32>() / 2.0;
+                        let mut center = screen_verts[0];
+                        for (pair, length) in screen_verts.windows(2).zip(&lengths) {
+                            if *length > 0.0 && remaining <= *length {
+                                let t = remaining / length;
+                                center = (
+                                    pair[0].0 + t * (pair[1].0 - pair[0].0),
+                                    pair[0].1 + t * (pair[1].1 - pair[0].1),
+                                );
+                                break;
+                            }
+                            remaining -= length;
+                        }
+
+                        let center_size = 8.0;
+                        let center_bounds = Bounds::new(
+                            center.0 - center_size / 2.0,
+                            center.1 - center_size / 2.0,
+                            center_size,
+                            center_size,
+                        );
+                        renderer.fill_rect(center_bounds, Color::rgba(1.0, 1.0, 0.5, 0.6));
+                        renderer.stroke_rect(center_bounds, handle_color, 1.0);
+                    }
+                }
+                OverlayShape::Mask { runs } => {
+                    if runs.is_empty() {
+                        continue;
+                    }
+
+                    // One rectangle per pixel run, batched with the other overlay quads
+                    let fill_color = Color::rgba(color.r, color.g, color.b, color.a * 0.45);
+                    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+                    for (x, y, len) in runs {
+                        let (sx1, sy1) = self.image_to_screen(*x as f32, *y as f32, &bounds);
+                        let (sx2, sy2) =
+                            self.image_to_screen((x + len) as f32, (y + 1) as f32, &bounds);
+                        renderer.fill_rect(Bounds::new(sx1, sy1, sx2 - sx1, sy2 - sy1), fill_color);
+                        min_x = min_x.min(*x);
+                        min_y = min_y.min(*y);
+                        max_x = max_x.max(x + len);
+                        max_y = max_y.max(y + 1);
+                    }
+
+                    // Outline the mask's extent when selected
+                    if overlay.selected {
+                        let (sx1, sy1) = self.image_to_screen(min_x as f32, min_y as f32, &bounds);
+                        let (sx2, sy2) = self.image_to_screen(max_x as f32, max_y as f32, &bounds);
+                        let box_bounds = Bounds::new(sx1, sy1, sx2 - sx1, sy2 - sy1);
+                        // Triple-stroke effect: outer black, inner white, category color in middle
+                        renderer.stroke_rect(
+                            box_bounds,
+                            selection_outer_color,
+                            overlay.line_width + 4.0,
+                        );
+                        renderer.stroke_rect(
+                            box_bounds,
+                            selection_inner_color,
+                            overlay.line_width + 2.0,
+                        );
+                        renderer.stroke_rect(box_bounds, color, overlay.line_width);
+                    }
+                }
+                OverlayShape::Keypoints {
+                    points,
+                    skeleton,
+                    bbox,
+                } => {
+                    // Unlabeled keypoints have no screen position
+                    let screen_points: Vec<Option<(f32, f32)>> = points
+                        .iter()
+                        .map(|(x, y, v)| (*v > 0).then(|| self.image_to_screen(*x, *y, &bounds)))
+                        .collect();
+
+                    // Instance box, faint unless selected
+                    if let Some((x, y, width, height)) = bbox {
+                        let (sx1, sy1) = self.image_to_screen(*x, *y, &bounds);
+                        let (sx2, sy2) = self.image_to_screen(*x + *width, *y + *height, &bounds);
+                        let box_bounds = Bounds::new(sx1, sy1, sx2 - sx1, sy2 - sy1);
+                        if overlay.selected {
+                            // Triple-stroke effect: outer black, inner white, category color
+                            renderer.stroke_rect(
+                                box_bounds,
+                                selection_outer_color,
+                                overlay.line_width + 4.0,
+                            );
+                            renderer.stroke_rect(
+                                box_bounds,
+                                selection_inner_color,
+                                overlay.line_width + 2.0,
+                            );
+                            renderer.stroke_rect(box_bounds, color, overlay.line_width);
+
+                            // Corner and edge midpoint handles (for resize)
+                            let handle_size = 6.0;
+                            let mid_x = (sx1 + sx2) / 2.0;
+                            let mid_y = (sy1 + sy2) / 2.0;
+                            let handles = [
+                                (sx1, sy1),
+                                (sx2, sy1),
+                                (sx2, sy2),
+                                (sx1, sy2),
+                                (mid_x, sy1),
+                                (mid_x, sy2),
+                                (sx1, mid_y),
+                                (sx2, mid_y),
+                            ];
+                            for (hx, hy) in handles {
+                                let handle_bounds = Bounds::new(
+                                    hx - handle_size / 2.0,
+                                    hy - handle_size / 2.0,
+                                    handle_size,
+                                    handle_size,
+                                );
+                                renderer.fill_rect(handle_bounds, Color::WHITE);
+                                renderer.stroke_rect(handle_bounds, handle_color, 1.0);
+                            }
+                        } else {
+                            let box_color = Color::rgba(color.r, color.g, color.b, color.a * 0.5);
+                            renderer.stroke_rect(box_bounds, box_color, 1.0);
+                        }
+                    }
+
+                    // Skeleton edges between labeled keypoints
+                    for (a, b) in skeleton {
+                        if let (Some(Some((x1, y1))), Some(Some((x2, y2)))) =
+                            (screen_points.get(*a), screen_points.get(*b))
+                        {
+                            renderer.line(*x1, *y1, *x2, *y2, color, overlay.line_width);
+                        }
+                    }
+
+                    // Visible keypoints filled, occluded keypoints hollow
+                    let radius = 5.0;
+                    let ring_color = if overlay.selected {
+                        handle_color
+                    } else {
+                        Color::WHITE
+                    };
+                    for (point, (_, _, visibility)) in screen_points.iter().zip(points) {
+                        let Some((sx, sy)) = point else {
+                            continue;
+                        };
+                        renderer.fill_circle(*sx, *sy, radius + 1.0, ring_color);
+                        renderer.fill_circle(*sx, *sy, radius, color);
+                        if *visibility < 2 {
+                            renderer.fill_circle(*sx, *sy, radius - 2.0, selection_outer_color);
+                        }
+                    }
+                }
             }
         }
 
         // Pop clip before drawing controls (they should be visible even at edges)
         renderer.pop_clip();
 
+        if let Some(colorbar) = &self.colorbar {
+            colorbar.draw(renderer, bounds);
+        }
+
         // Draw zoom info - use calculated zoom for current mode/bounds
         let display_zoom = self.calculate_zoom_for_mode(&bounds);
         let zoom_text = format!("{:.0}%", display_zoom * 100.0);
@@ -1069,6 +1530,15 @@ impl<M: 'static> Widget<M> for ImageViewer<M> {
                         .into();
                 }
 
+                // Report the pixel under the cursor when it changes
+                if self.on_hover.is_some() {
+                    let pixel = self.pixel_at(position.0, position.1, &bounds);
+                    if pixel != self.hover_pixel {
+                        self.hover_pixel = pixel;
+                        return self.on_hover.call(pixel).into();
+                    }
+                }
+
                 EventResult::None
             }
 
@@ -0,0 +1,352 @@
+//! Line chart widget for plotting one or more data series
+
+use crate::constants::{FONT_SIZE_SMALL, FONT_SIZE_TINY};
+use crate::layout::{Bounds, Length, Size};
+use crate::renderer::{Color, Renderer};
+use crate::theme::current_theme;
+use crate::widget::Widget;
+
+/// Default chart height in pixels
+const DEFAULT_CHART_HEIGHT: f32 = 160.0;
+/// Space reserved left of the plot area for y-axis labels
+const AXIS_LABEL_WIDTH: f32 = 44.0;
+/// Space reserved below the plot area for x-axis labels
+const AXIS_LABEL_HEIGHT: f32 = 14.0;
+/// Inner padding of the plot area
+const PLOT_PADDING: f32 = 4.0;
+
+/// Configuration for line chart appearance
+#[derive(Debug, Clone)]
+pub struct LineChartConfig {
+    /// Plot area background color
+    pub background_color: Color,
+    /// Plot area border color
+    pub border_color: Color,
+    /// Grid line color
+    pub grid_color: Color,
+    /// Axis label color
+    pub label_color: Color,
+    /// Number of horizontal grid lines between min and max
+    pub grid_lines: usize,
+}
+
+impl Default for LineChartConfig {
+    fn default() -> Self {
+        let theme = current_theme();
+        Self {
+            background_color: theme.input_bg,
+            border_color: theme.border,
+            grid_color: theme.border.darken(0.3),
+            label_color: theme.text_secondary,
+            grid_lines: 3,
+        }
+    }
+}
+
+/// One data series of a line chart
+#[derive(Debug, Clone)]
+pub struct ChartSeries {
+    /// Data points (x, y); non-finite points break the line
+    pub points: Vec<(f32, f32)>,
+    /// Line color
+    pub color: Color,
+    /// Line thickness in pixels
+    pub thickness: f32,
+}
+
+impl ChartSeries {
+    /// Create a series with the default line thickness
+    pub fn new(points: Vec<(f32, f32)>, color: Color) -> Self {
+        Self {
+            points,
+            color,
+            thickness: 1.5,
+        }
+    }
+
+    /// Set the line thickness
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+}
+
+/// A line chart widget
+///
+/// Axis ranges are fitted to the data of all series. The chart is display-only
+/// and does not produce messages.
+pub struct LineChart<M> {
+    /// Series to plot, drawn in order
+    series: Vec<ChartSeries>,
+    /// Label shown under the x axis (e.g. unit)
+    x_label: Option<String>,
+    /// Text shown when there is nothing to plot
+    empty_text: String,
+    /// Width of the chart
+    width: Length,
+    /// Height of the chart
+    height: Length,
+    /// Appearance
+    config: LineChartConfig,
+    _phantom: std::marker::PhantomData<M>,
+}
+
+impl<M> Default for LineChart<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> LineChart<M> {
+    /// Create an empty line chart
+    pub fn new() -> Self {
+        Self {
+            series: Vec::new(),
+            x_label: None,
+            empty_text: "No data".to_string(),
+            width: Length::Fill(1.0),
+            height: Length::Fixed(DEFAULT_CHART_HEIGHT),
+            config: LineChartConfig::default(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Add a data series
+    pub fn series(mut self, series: ChartSeries) -> Self {
+        self.series.push(series);
+        self
+    }
+
+    /// Set the x axis label
+    pub fn x_label(mut self, label: impl Into<String>) -> Self {
+        self.x_label = Some(label.into());
+        self
+    }
+
+    /// Set the text shown when there is no data
+    pub fn empty_text(mut self, text: impl Into<String>) -> Self {
+        self.empty_text = text.into();
+        self
+    }
+
+    /// Set the width
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    /// Set the height
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// Set the appearance configuration
+    pub fn config(mut self, config: LineChartConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+/// Data range (x_min, x_max, y_min, y_max) over all finite points.
+///
+/// Degenerate ranges are widened so every point maps into the plot area.
+fn data_range(series: &[ChartSeries]) -> Option<(f32, f32, f32, f32)> {
+    let mut range: Option<(f32, f32, f32, f32)> = None;
+    for &(x, y) in series.iter().flat_map(|s| &s.points) {
+        if !x.is_finite() || !y.is_finite() {
+            continue;
+        }
+        range = Some(match range {
+            None => (x, x, y, y),
+            Some((x0, x1, y0, y1)) => (x0.min(x), x1.max(x), y0.min(y), y1.max(y)),
+        });
+    }
+    range.map(|(x0, x1, y0, y1)| {
+        let (x0, x1) = widen(x0, x1);
+        let (y0, y1) = widen(y0, y1);
+        (x0, x1, y0, y1)
+    })
+}
+
+/// Widen an empty range around its value.
+fn widen(min: f32, max: f32) -> (f32, f32) {
+    if max > min {
+        (min, max)
+    } else {
+        let pad = (min.abs() * 0.05).max(0.5);
+        (min - pad, max + pad)
+    }
+}
+
+/// Format an axis value compactly.
+fn format_value(value: f32) -> String {
+    let magnitude = value.abs();
+    if magnitude != 0.0 && !(0.01..10000.0).contains(&magnitude) {
+        format!("{:.1e}", value)
+    } else if magnitude >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+impl<M: Clone + 'static> Widget<M> for LineChart<M> {
+    fn layout(&mut self, available: Size) -> Size {
+        Size::new(
+            self.width.resolve(available.width, available.width),
+            self.height.resolve(available.height, DEFAULT_CHART_HEIGHT),
+        )
+    }
+
+    fn draw(&self, renderer: &mut Renderer, bounds: Bounds) {
+        let plot = Bounds::new(
+            bounds.x + AXIS_LABEL_WIDTH,
+            bounds.y,
+            (bounds.width - AXIS_LABEL_WIDTH).max(1.0),
+            (bounds.height - AXIS_LABEL_HEIGHT).max(1.0),
+        );
+        renderer.fill_rect(plot, self.config.background_color);
+        renderer.stroke_rect(plot, self.config.border_color, 1.0);
+
+        let Some((x_min, x_max, y_min, y_max)) = data_range(&self.series) else {
+            renderer.text(
+                &self.empty_text,
+                plot.x + PLOT_PADDING * 2.0,
+                plot.y + plot.height / 2.0 - FONT_SIZE_SMALL / 2.0,
+                FONT_SIZE_SMALL,
+                self.config.label_color,
+            );
+            return;
+        };
+
+        let inner = Bounds::new(
+            plot.x + PLOT_PADDING,
+            plot.y + PLOT_PADDING,
+            plot.width - PLOT_PADDING * 2.0,
+            plot.height - PLOT_PADDING * 2.0,
+        );
+        let to_screen = |x: f32, y: f32| {
+            (
+                inner.x + (x - x_min) / (x_max - x_min) * inner.width,
+                inner.y + inner.height - (y - y_min) / (y_max - y_min) * inner.height,
+            )
+        };
+
+        // Horizontal grid lines with y labels
+        let steps = self.config.grid_lines + 1;
+        for i in 0..=steps {
+            let value = y_min + (y_max - y_min) * i as f32 / steps as f32;
+            let (_, y) = to_screen(x_min, value);
+            if i > 0 && i < steps {
+                renderer.line(
+                    plot.x,
+                    y,
+                    plot.x + plot.width,
+                    y,
+                    self.config.grid_color,
+                    1.0,
+                );
+            }
+            let label = format_value(value);
+            let label_width = renderer.measure_text_width(&label, FONT_SIZE_TINY);
+            renderer.text(
+                &label,
+                plot.x - label_width - 3.0,
+                y - FONT_SIZE_TINY / 2.0,
+                FONT_SIZE_TINY,
+                self.config.label_color,
+            );
+        }
+
+        // X range labels and axis label
+        let label_y = plot.y + plot.height + 2.0;
+        renderer.text(
+            &format_value(x_min),
+            plot.x,
+            label_y,
+            FONT_SIZE_TINY,
+            self.config.label_color,
+        );
+        let max_label = format_value(x_max);
+        let max_width = renderer.measure_text_width(&max_label, FONT_SIZE_TINY);
+        renderer.text(
+            &max_label,
+            plot.x + plot.width - max_width,
+            label_y,
+            FONT_SIZE_TINY,
+            self.config.label_color,
+        );
+        if let Some(x_label) = &self.x_label {
+            let width = renderer.measure_text_width(x_label, FONT_SIZE_TINY);
+            renderer.text(
+                x_label,
+                plot.x + (plot.width - width) / 2.0,
+                label_y,
+                FONT_SIZE_TINY,
+                self.config.label_color,
+            );
+        }
+
+        // Series
+        renderer.push_clip(plot);
+        for series in &self.series {
+            let mut previous: Option<(f32, f32)> = None;
+            for &(x, y) in &series.points {
+                if !x.is_finite() || !y.is_finite() {
+                    previous = None;
+                    continue;
+                }
+                let point = to_screen(x, y);
+                match previous {
+                    Some((px, py)) => {
+                        renderer.line(px, py, point.0, point.1, series.color, series.thickness)
+                    }
+                    // Single points are still visible
+                    None if series.points.len() == 1 => {
+                        renderer.fill_circle(point.0, point.1, series.thickness + 1.0, series.color)
+                    }
+                    None => {}
+                }
+                previous = Some(point);
+            }
+        }
+        renderer.pop_clip();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(points: &[(f32, f32)]) -> ChartSeries {
+        ChartSeries::new(points.to_vec(), Color::WHITE)
+    }
+
+    #[test]
+    fn test_data_range_over_all_series() {
+        let range = data_range(&[
+            series(&[(400.0, 0.1), (500.0, f32::NAN), (600.0, 0.4)]),
+            series(&[(450.0, -0.2), (700.0, 0.3)]),
+        ]);
+        assert_eq!(range, Some((400.0, 700.0, -0.2, 0.4)));
+        assert_eq!(data_range(&[series(&[(1.0, f32::NAN)])]), None);
+        assert_eq!(data_range(&[]), None);
+    }
+
+    #[test]
+    fn test_data_range_widens_single_point() {
+        let (x0, x1, y0, y1) = data_range(&[series(&[(3.0, 100.0)])]).unwrap();
+        assert!(x0 < 3.0 && x1 > 3.0);
+        assert!(y0 < 100.0 && y1 > 100.0);
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(0.0), "0.00");
+        assert_eq!(format_value(0.25), "0.25");
+        assert_eq!(format_value(650.0), "650");
+        assert_eq!(format_value(65535.0), "6.6e4");
+        assert_eq!(format_value(0.0001), "1.0e-4");
+    }
+}
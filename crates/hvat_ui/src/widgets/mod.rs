@@ -4,6 +4,7 @@ mod button;
 mod collapsible;
 mod color_picker;
 mod color_swatch;
+mod colorbar;
 mod column;
 pub mod config;
 mod confirm_dialog;
@@ -12,7 +13,9 @@ mod context_menu;
 mod dropdown;
 mod file_tree;
 mod flex_layout;
+mod histogram;
 mod image_viewer;
+mod line_chart;
 mod number_input;
 pub mod overlay;
 mod panel;
@@ -29,15 +32,18 @@ pub use button::{Button, ButtonStyle};
 pub use collapsible::{Collapsible, CollapsibleConfig};
 pub use color_picker::ColorPicker;
 pub use color_swatch::ColorSwatch;
+pub use colorbar::ColorbarLegend;
 pub use column::Column;
 pub use config::BaseInputConfig;
 pub use confirm_dialog::{ConfirmDialog, ConfirmDialogConfig};
 pub use context_menu::{ContextMenu, ContextMenuConfig, MenuItem};
 pub use dropdown::{Dropdown, DropdownConfig};
 pub use file_tree::{FileTree, FileTreeConfig, FileTreeNode};
+pub use histogram::{Histogram, HistogramConfig};
 pub use image_viewer::{
     AnnotationOverlay, ImagePointerEvent, ImageViewer, OverlayShape, PointerEventKind,
 };
+pub use line_chart::{ChartSeries, LineChart, LineChartConfig};
 pub use number_input::{NumberInput, NumberInputConfig};
 pub use panel::{BorderSides, Panel};
 pub use row::Row;
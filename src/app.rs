@@ -10,12 +10,16 @@
 //! - Band compositing done in fragment shader (instant band changes)
 //! - Image adjustments (brightness, contrast, gamma, hue) also GPU-side
 
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
-use hvat_gpu::{BandSelectionUniform, ImageAdjustments};
+use hvat_gpu::{
+    BandSelectionUniform, Colormap, ColormapUniform, ImageAdjustments, STRETCH_LUT_SIZE,
+    StretchUniform, TiledGpuData,
+};
 use hvat_ui::prelude::*;
 use hvat_ui::{
     Application, Column, Element, Event, FileTreeState, KeyCode, Resources, Row, TickResult,
@@ -25,24 +29,40 @@ use hvat_ui::{
 #[cfg(not(target_arch = "wasm32"))]
 use crate::constants::MAX_IN_FLIGHT_DECODES;
 use crate::constants::{
-    DEFAULT_BRIGHTNESS, DEFAULT_CONTRAST, DEFAULT_GAMMA, DEFAULT_HUE, DEFAULT_RED_BAND,
-    DEFAULT_TEST_BANDS, DEFAULT_TEST_HEIGHT, DEFAULT_TEST_WIDTH, MAX_GPU_PRELOAD_COUNT,
-    UNDO_HISTORY_SIZE,
+    COMPONENT_MAX_SAMPLES, DEFAULT_BRIGHTNESS, DEFAULT_CONTRAST, DEFAULT_GAMMA, DEFAULT_HUE,
+    DEFAULT_RED_BAND, DEFAULT_SIMILARITY_THRESHOLD, DEFAULT_STRETCH_K, DEFAULT_TEST_BANDS,
+    DEFAULT_TEST_HEIGHT, DEFAULT_TEST_WIDTH, MASK_CONTOUR_EPSILON, MAX_DISPLAY_COMPONENTS,
+    MAX_GPU_PRELOAD_COUNT, MAX_LOAD_BAND_STRIDE, MAX_PINNED_SPECTRA, MAX_SIMILARITY_REGIONS,
+    SIMILARITY_CONTOUR_EPSILON, SIMILARITY_MIN_REGION_AREA, TILED_IMAGE_BYTES, UNDO_HISTORY_SIZE,
+};
+use crate::data::{
+    BandExclusion, BandHistogram, BandInfo, BandMathMode, BandMathPreset, BandMathProgram,
+    CalibrationRule, ChannelStretch, ComponentMethod, HyperspectralData, Illuminant, LoadSubset,
+    LoaderOptions, PreprocessStep, PreprocessingChain, RegionStats, SimilarityMap,
+    SimilarityMetric, StretchMode, band_histograms, default_bands, nearest_band, parse_factors,
+    parse_wavelength, project_pattern,
+};
+use crate::format::{
+    AutoSaveManager, CompositeBand, CompositeEntry, ExportOptions, FormatRegistry, ProjectData,
+    SpectralDataset,
 };
-use crate::data::HyperspectralData;
-use crate::format::{AutoSaveManager, ExportOptions, FormatRegistry, ProjectData};
 use crate::keybindings::{KeyBindings, KeybindTarget};
 use crate::message::Message;
 use crate::model::{
-    Annotation, AnnotationShape, AnnotationTool, Category, DrawingState, EditState,
-    HANDLE_HIT_RADIUS, MIN_DRAG_DISTANCE, MIN_POLYGON_VERTICES, POLYGON_CLOSE_THRESHOLD, Tag,
+    Annotation, AnnotationId, AnnotationShape, AnnotationTool, Category, DEFAULT_BRUSH_RADIUS,
+    DEFAULT_POLYLINE_WIDTH, DrawingState, EditState, HANDLE_HIT_RADIUS, KEYPOINT_OCCLUDED,
+    KEYPOINT_VISIBLE, Keypoint, MIN_DRAG_DISTANCE, MIN_POLYGON_VERTICES, MIN_POLYLINE_VERTICES,
+    POLYGON_CLOSE_THRESHOLD, RleMask, Tag, format_skeleton, parse_keypoint_names, parse_skeleton,
 };
 use crate::state::{
-    AppSnapshot, GpuRenderState, GpuTextureCache, ImageDataStore, LoadedImage, ProjectState,
-    SharedGpuPipeline,
+    AnnotationStats, AppSnapshot, ComponentImages, ComponentTextures, GpuBands, GpuRenderState,
+    GpuTextureCache, ImageDataStore, LoadedImage, PinnedSpectrum, ProjectState, SharedGpuPipeline,
+    TrueColorTextures, next_pin_color,
 };
 #[cfg(not(target_arch = "wasm32"))]
-use crate::state::{DecodeResult, NativePreloadState, extract_images_from_zip_file, is_zip_path};
+use crate::state::{
+    ComponentJobs, DecodeResult, NativePreloadState, extract_images_from_zip_file, is_zip_path,
+};
 #[cfg(target_arch = "wasm32")]
 use crate::state::{WasmPreloadState, extract_images_from_zip_bytes, is_zip_file};
 use crate::test_image::generate_test_hyperspectral;
@@ -155,7 +175,8 @@ fn pending_sam2_decode_state() -> &'static std::sync::Mutex<Option<Sam2DecodeRes
 #[cfg(target_arch = "wasm32")]
 mod wasm_folder_picker {
     use crate::state::{
-        LoadedImage, extract_images_from_zip_bytes, is_image_filename, is_zip_file,
+        LoadedImage, extract_images_from_zip_bytes, is_image_filename, is_sidecar_filename,
+        is_zip_file,
     };
     use hvat_ui::read_file_async;
     use std::cell::RefCell;
@@ -253,7 +274,7 @@ mod wasm_folder_picker {
                     if is_zip_file(&name) {
                         log::info!("Found ZIP file: {}", name);
                         zip_files.push(file);
-                    } else if is_image_filename(&name) {
+                    } else if is_image_filename(&name) || is_sidecar_filename(&name) {
                         image_files.push(file);
                     } else {
                         log::debug!("Skipping non-image file: {}", name);
@@ -374,7 +395,8 @@ pub struct HvatApp {
     // Project state (loaded folder with images)
     pub(crate) project: Option<ProjectState>,
 
-    // Hyperspectral data (CPU-side, used for initial upload to GPU)
+    // Hyperspectral data of the displayed image (CPU-side). Used for the GPU
+    // upload and spectrum plots; None after a GPU cache hit until needed.
     hyperspectral: Option<HyperspectralData>,
     pub(crate) num_bands: usize,
     band_selection: (usize, usize, usize), // R, G, B band indices
@@ -422,6 +444,10 @@ pub struct HvatApp {
 
     // Tool selection
     pub(crate) selected_tool: AnnotationTool,
+    /// Line width for new polylines (and the selected polyline)
+    pub(crate) polyline_width_slider: SliderState,
+    /// Brush and eraser radius for painting masks
+    pub(crate) brush_radius_slider: SliderState,
 
     // Note: Annotations are now stored per-image in ImageDataStore
 
@@ -442,6 +468,14 @@ pub struct HvatApp {
     pub(crate) color_picker_category: Option<u32>,
     /// Color picker state (drag tracking)
     pub(crate) color_picker_state: ColorPickerState,
+    /// Text input for the selected category's keypoint names
+    pub(crate) keypoint_names_input: String,
+    /// State for keypoint names text input
+    pub(crate) keypoint_names_input_state: TextInputState,
+    /// Text input for the selected category's keypoint skeleton edges
+    pub(crate) keypoint_skeleton_input: String,
+    /// State for keypoint skeleton text input
+    pub(crate) keypoint_skeleton_input_state: TextInputState,
 
     // Per-image data (tags selection, annotations, etc.)
     pub(crate) image_data_store: ImageDataStore,
@@ -472,6 +506,110 @@ pub struct HvatApp {
     pub(crate) gamma_slider: SliderState,
     pub(crate) hue_slider: SliderState,
 
+    // Automatic contrast stretch (kept across images, recomputed per image)
+    pub(crate) stretch_mode: StretchMode,
+    pub(crate) stretch_k_slider: SliderState,
+    /// Dragged (low, high) bounds of the output channels (`StretchMode::Manual`)
+    pub(crate) manual_stretch: [Option<(f32, f32)>; 3],
+    /// Log scale toggle and marker drag of the channel histograms
+    pub(crate) histogram_states: [HistogramState; 3],
+
+    // Band math display (expressions are kept across images)
+    pub(crate) band_math_collapsed: CollapsibleState,
+    pub(crate) band_math_mode: BandMathMode,
+    /// Expressions of the red, green and blue channel (RGB Math mode)
+    pub(crate) band_math_channels: [String; 3],
+    pub(crate) band_math_channel_states: [TextInputState; 3],
+    /// Expression of the Index mode
+    pub(crate) band_math_index: String,
+    pub(crate) band_math_index_state: TextInputState,
+    /// Compiled applied expressions (None = band composite)
+    pub(crate) band_math_program: Option<BandMathProgram>,
+    /// Whether the pipeline still renders a different program than `band_math_program`
+    band_math_shader_dirty: bool,
+    /// Error of the last applied expressions
+    pub(crate) band_math_error: Option<String>,
+    /// Histograms of the program output of the displayed image (for the stretch)
+    pub(crate) band_math_histograms: Option<(PathBuf, Vec<BandHistogram>)>,
+    /// Saved expressions (shared through the configuration)
+    pub(crate) band_math_presets: Vec<BandMathPreset>,
+    pub(crate) band_math_name: String,
+    pub(crate) band_math_name_state: TextInputState,
+
+    // Pseudo-color display of the red band or index (kept across images)
+    pub(crate) colormap_collapsed: CollapsibleState,
+    /// Selected colormap (None = band composite)
+    pub(crate) colormap: Option<Colormap>,
+    pub(crate) colormap_reversed: bool,
+    /// Entered value range (None = fitted to the data)
+    pub(crate) colormap_range: Option<(f32, f32)>,
+    pub(crate) colormap_min: String,
+    pub(crate) colormap_min_state: TextInputState,
+    pub(crate) colormap_max: String,
+    pub(crate) colormap_max_state: TextInputState,
+    /// Colormap in the lookup texture (None = not uploaded yet)
+    colormap_lut: Option<Colormap>,
+
+    // Principal component composite (method kept across images, components per image)
+    pub(crate) components_collapsed: CollapsibleState,
+    /// Selected method (None = band composite)
+    pub(crate) component_method: Option<ComponentMethod>,
+    /// Component shown in the red, green and blue channel (0-based)
+    pub(crate) component_sliders: [SliderState; 3],
+    /// Failed computation (image, method, error), not retried until either changes
+    pub(crate) component_error: Option<(PathBuf, ComponentMethod, String)>,
+    /// Computations on worker threads; the previous components stay
+    /// displayed until their result is uploaded
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) component_jobs: ComponentJobs,
+
+    // True-color synthesis (illuminant kept across images, images per image)
+    pub(crate) true_color_collapsed: CollapsibleState,
+    /// Selected illuminant (None = band composite)
+    pub(crate) true_color_illuminant: Option<Illuminant>,
+    /// Failed synthesis (image, illuminant, error), not retried until either changes
+    pub(crate) true_color_error: Option<(PathBuf, Illuminant, String)>,
+
+    // Spectral similarity map (reference kept across images, map per image)
+    pub(crate) similarity_collapsed: CollapsibleState,
+    pub(crate) similarity_metric: SimilarityMetric,
+    /// Reference spectrum with a description of where it came from
+    pub(crate) similarity_reference: Option<(String, Vec<f32>)>,
+    /// Whether the next click on the image picks the reference pixel
+    pub(crate) similarity_picking: bool,
+    /// Whether the map is displayed instead of the bands
+    pub(crate) similarity_shown: bool,
+    /// Threshold as a fraction of the value range of the map
+    pub(crate) similarity_threshold_slider: SliderState,
+    /// Map of the displayed image
+    pub(crate) similarity_map: Option<(PathBuf, SimilarityMap)>,
+    /// Failed map computation (image, error), not retried until the image,
+    /// reference or metric changes
+    pub(crate) similarity_error: Option<(PathBuf, String)>,
+    /// Outlines of the regions below the threshold (previewed on the image)
+    pub(crate) similarity_regions: Vec<Vec<(f32, f32)>>,
+    /// Whether the GPU still displays a different map
+    similarity_upload_dirty: bool,
+
+    // Spectral preprocessing (per project, saved in the project file)
+    pub(crate) preprocessing_collapsed: CollapsibleState,
+    pub(crate) preprocessing: PreprocessingChain,
+    /// Bands typed into the exclusion input ("1-5, 1350-1450nm")
+    pub(crate) preprocess_exclude: String,
+    pub(crate) preprocess_exclude_state: TextInputState,
+    /// Savitzky–Golay "window, order" typed into the smoothing input
+    pub(crate) preprocess_smoothing: String,
+    pub(crate) preprocess_smoothing_state: TextInputState,
+    /// Why the last entered step was rejected
+    pub(crate) preprocess_error: Option<String>,
+
+    // Spectrum plot
+    pub(crate) spectrum_collapsed: CollapsibleState,
+    /// Image pixel under the cursor (tracked while the spectrum plot is open)
+    pub(crate) hover_pixel: Option<(u32, u32)>,
+    /// Spectra pinned by the user (may belong to other images)
+    pub(crate) pinned_spectra: Vec<PinnedSpectrum>,
+
     // Undo system
     pub(crate) undo_stack: Rc<RefCell<UndoStack<AppSnapshot>>>,
 
@@ -488,6 +626,7 @@ pub struct HvatApp {
     pub(crate) appearance_section_collapsed: CollapsibleState,
     pub(crate) keybindings_section_collapsed: CollapsibleState,
     pub(crate) folders_section_collapsed: CollapsibleState,
+    pub(crate) loading_section_collapsed: CollapsibleState,
     pub(crate) performance_section_collapsed: CollapsibleState,
     pub(crate) dependencies_collapsed: CollapsibleState,
     /// Collapsed state for each license type in the dependencies view
@@ -508,6 +647,30 @@ pub struct HvatApp {
     pub(crate) capturing_keybind: Option<KeybindTarget>,
     /// Current log level setting
     pub(crate) log_level: crate::config::LogLevel,
+    /// File loading options (e.g. `.npz` cube key, `.npy` layouts)
+    pub(crate) loader_options: LoaderOptions,
+    pub(crate) npz_cube_key_state: TextInputState,
+    /// Pattern for a new `.npy` layout rule (Settings > File Loading)
+    pub(crate) npy_rule_pattern: String,
+    pub(crate) npy_rule_pattern_state: TextInputState,
+    /// Band range typed into Settings > File Loading (applied on focus loss)
+    pub(crate) load_band_range: String,
+    pub(crate) load_band_range_state: TextInputState,
+    pub(crate) load_band_stride_slider: SliderState,
+    /// Calibration rule being entered in Settings > File Loading
+    pub(crate) calibration_pattern: String,
+    pub(crate) calibration_pattern_state: TextInputState,
+    pub(crate) calibration_dark: String,
+    pub(crate) calibration_dark_state: TextInputState,
+    pub(crate) calibration_white: String,
+    pub(crate) calibration_white_state: TextInputState,
+    pub(crate) calibration_gain: String,
+    pub(crate) calibration_gain_state: TextInputState,
+    pub(crate) calibration_offset: String,
+    pub(crate) calibration_offset_state: TextInputState,
+    /// Wavelength(s) typed into the band picker (Band Selection)
+    pub(crate) band_wavelength_input: String,
+    pub(crate) band_wavelength_state: TextInputState,
 
     // Format system
     /// Format registry with all supported formats
@@ -533,6 +696,12 @@ pub struct HvatApp {
     pub(crate) annotations_scroll_state: ScrollState,
     /// Set of category IDs that are hidden (filtered out from display)
     pub(crate) hidden_categories: std::collections::HashSet<u32>,
+    /// Annotation statistics section collapsible state
+    pub(crate) annotation_stats_collapsed: CollapsibleState,
+    /// Annotation statistics band list scroll state
+    pub(crate) annotation_stats_scroll_state: ScrollState,
+    /// Band statistics of the selected annotation (while the section is open)
+    pub(crate) annotation_stats: Option<AnnotationStats>,
 
     // Tooltip system
     /// Tooltip manager for hover-triggered tooltips
@@ -628,6 +797,7 @@ impl HvatApp {
             .preferences
             .gpu_preload_count
             .min(MAX_GPU_PRELOAD_COUNT);
+        let load_subset = config.loader.subset;
 
         Self {
             viewer_state: ImageViewerState::new(),
@@ -684,6 +854,8 @@ impl HvatApp {
             right_scroll_state: ScrollState::default(),
 
             selected_tool: AnnotationTool::default(),
+            polyline_width_slider: SliderState::new(DEFAULT_POLYLINE_WIDTH),
+            brush_radius_slider: SliderState::new(DEFAULT_BRUSH_RADIUS),
 
             categories: config.categories.into_iter().map(|c| c.into()).collect(),
             selected_category: 1,
@@ -694,6 +866,10 @@ impl HvatApp {
             category_name_input_state: TextInputState::default(),
             color_picker_category: None,
             color_picker_state: ColorPickerState::default(),
+            keypoint_names_input: String::new(),
+            keypoint_names_input_state: TextInputState::default(),
+            keypoint_skeleton_input: String::new(),
+            keypoint_skeleton_input_state: TextInputState::default(),
 
             image_data_store: ImageDataStore::new(),
             tags: config.tags.into_iter().map(|t| t.into()).collect(),
@@ -714,6 +890,65 @@ impl HvatApp {
             gamma_slider: SliderState::new(1.0),
             hue_slider: SliderState::new(0.0),
 
+            stretch_mode: StretchMode::None,
+            stretch_k_slider: SliderState::new(DEFAULT_STRETCH_K),
+            manual_stretch: [None; 3],
+            histogram_states: Default::default(),
+
+            band_math_collapsed: CollapsibleState::collapsed(),
+            band_math_mode: BandMathMode::Off,
+            band_math_channels: Default::default(),
+            band_math_channel_states: Default::default(),
+            band_math_index: String::new(),
+            band_math_index_state: TextInputState::default(),
+            band_math_program: None,
+            band_math_shader_dirty: false,
+            band_math_error: None,
+            band_math_histograms: None,
+            band_math_presets: config.band_math,
+            band_math_name: String::new(),
+            band_math_name_state: TextInputState::default(),
+            colormap_collapsed: CollapsibleState::collapsed(),
+            colormap: None,
+            colormap_reversed: false,
+            colormap_range: None,
+            colormap_min: String::new(),
+            colormap_min_state: TextInputState::default(),
+            colormap_max: String::new(),
+            colormap_max_state: TextInputState::default(),
+            colormap_lut: None,
+            components_collapsed: CollapsibleState::collapsed(),
+            component_method: None,
+            component_sliders: [0.0, 1.0, 2.0].map(SliderState::new),
+            component_error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            component_jobs: ComponentJobs::new(),
+            true_color_collapsed: CollapsibleState::collapsed(),
+            true_color_illuminant: None,
+            true_color_error: None,
+            similarity_collapsed: CollapsibleState::collapsed(),
+            similarity_metric: SimilarityMetric::default(),
+            similarity_reference: None,
+            similarity_picking: false,
+            similarity_shown: false,
+            similarity_threshold_slider: SliderState::new(DEFAULT_SIMILARITY_THRESHOLD),
+            similarity_map: None,
+            similarity_error: None,
+            similarity_regions: Vec::new(),
+            similarity_upload_dirty: false,
+
+            preprocessing_collapsed: CollapsibleState::collapsed(),
+            preprocessing: PreprocessingChain::default(),
+            preprocess_exclude: String::new(),
+            preprocess_exclude_state: TextInputState::default(),
+            preprocess_smoothing: "7, 2".to_string(),
+            preprocess_smoothing_state: TextInputState::default(),
+            preprocess_error: None,
+
+            spectrum_collapsed: CollapsibleState::collapsed(),
+            hover_pixel: None,
+            pinned_spectra: Vec::new(),
+
             undo_stack: Rc::new(RefCell::new(UndoStack::new(UNDO_HISTORY_SIZE))),
 
             window_height: 900.0,
@@ -726,6 +961,7 @@ impl HvatApp {
             appearance_section_collapsed: CollapsibleState::expanded(),
             keybindings_section_collapsed: CollapsibleState::collapsed(),
             folders_section_collapsed: CollapsibleState::collapsed(),
+            loading_section_collapsed: CollapsibleState::collapsed(),
             performance_section_collapsed: CollapsibleState::expanded(),
             dependencies_collapsed: CollapsibleState::collapsed(),
             license_collapsed: std::collections::HashMap::new(),
@@ -738,6 +974,25 @@ impl HvatApp {
             keybindings: config.keybindings.to_keybindings(),
             capturing_keybind: None,
             log_level: config.preferences.log_level,
+            loader_options: config.loader,
+            npz_cube_key_state: TextInputState::default(),
+            npy_rule_pattern: String::new(),
+            npy_rule_pattern_state: TextInputState::default(),
+            load_band_range: load_subset.band_range_text(),
+            load_band_range_state: TextInputState::default(),
+            load_band_stride_slider: SliderState::new(load_subset.band_stride as f32),
+            calibration_pattern: String::new(),
+            calibration_pattern_state: TextInputState::default(),
+            calibration_dark: String::new(),
+            calibration_dark_state: TextInputState::default(),
+            calibration_white: String::new(),
+            calibration_white_state: TextInputState::default(),
+            calibration_gain: String::new(),
+            calibration_gain_state: TextInputState::default(),
+            calibration_offset: String::new(),
+            calibration_offset_state: TextInputState::default(),
+            band_wavelength_input: String::new(),
+            band_wavelength_state: TextInputState::default(),
 
             format_registry: FormatRegistry::new(),
             auto_save: AutoSaveManager::new(),
@@ -751,6 +1006,9 @@ impl HvatApp {
             annotations_collapsed: CollapsibleState::expanded(),
             annotations_scroll_state: ScrollState::default(),
             hidden_categories: std::collections::HashSet::new(),
+            annotation_stats_collapsed: CollapsibleState::collapsed(),
+            annotation_stats_scroll_state: ScrollState::default(),
+            annotation_stats: None,
 
             // Tooltip system
             tooltip_manager: TooltipManager::new(),
@@ -806,6 +1064,9 @@ impl HvatApp {
             contrast: self.contrast_slider.value,
             gamma: self.gamma_slider.value,
             hue: self.hue_slider.value,
+            stretch_mode: self.stretch_mode,
+            manual_stretch: self.manual_stretch,
+            stretch_k: self.stretch_k_slider.value,
             annotations: None,
         }
     }
@@ -823,6 +1084,9 @@ impl HvatApp {
             contrast: self.contrast_slider.value,
             gamma: self.gamma_slider.value,
             hue: self.hue_slider.value,
+            stretch_mode: self.stretch_mode,
+            manual_stretch: self.manual_stretch,
+            stretch_k: self.stretch_k_slider.value,
             annotations: Some(crate::state::AnnotationState {
                 image_path: path,
                 annotations: image_data.annotations.clone(),
@@ -842,6 +1106,9 @@ impl HvatApp {
         self.contrast_slider.set_value(snapshot.contrast);
         self.gamma_slider.set_value(snapshot.gamma);
         self.hue_slider.set_value(snapshot.hue);
+        self.stretch_mode = snapshot.stretch_mode;
+        self.manual_stretch = snapshot.manual_stretch;
+        self.stretch_k_slider.set_value(snapshot.stretch_k);
         self.needs_gpu_render = true;
 
         // Restore annotation state if present
@@ -1182,11 +1449,30 @@ impl HvatApp {
     fn any_text_input_focused(&self) -> bool {
         // Text input fields
         self.category_name_input_state.is_focused
+            || self.keypoint_names_input_state.is_focused
+            || self.keypoint_skeleton_input_state.is_focused
             || self.tag_input_state.is_focused
             || self.export_folder_state.is_focused
             || self.import_folder_state.is_focused
+            || self.npz_cube_key_state.is_focused
+            || self.npy_rule_pattern_state.is_focused
+            || self.load_band_range_state.is_focused
+            || self.calibration_pattern_state.is_focused
+            || self.calibration_dark_state.is_focused
+            || self.calibration_white_state.is_focused
+            || self.calibration_gain_state.is_focused
+            || self.calibration_offset_state.is_focused
+            || self.band_wavelength_state.is_focused
+            || self.band_math_channel_states.iter().any(|s| s.is_focused)
+            || self.band_math_index_state.is_focused
+            || self.band_math_name_state.is_focused
+            || self.colormap_min_state.is_focused
+            || self.colormap_max_state.is_focused
+            || self.preprocess_exclude_state.is_focused
+            || self.preprocess_smoothing_state.is_focused
             // Slider text inputs
             || self.gpu_preload_slider.input_focused
+            || self.load_band_stride_slider.input_focused
             || self.red_band_slider.input_focused
             || self.green_band_slider.input_focused
             || self.blue_band_slider.input_focused
@@ -1194,6 +1480,11 @@ impl HvatApp {
             || self.contrast_slider.input_focused
             || self.gamma_slider.input_focused
             || self.hue_slider.input_focused
+            || self.stretch_k_slider.input_focused
+            || self.component_sliders.iter().any(|s| s.input_focused)
+            || self.similarity_threshold_slider.input_focused
+            || self.polyline_width_slider.input_focused
+            || self.brush_radius_slider.input_focused
     }
 
     /// Handle keyboard events for undo/redo, annotation shortcuts, and custom keybindings.
@@ -1248,7 +1539,13 @@ impl HvatApp {
                 match key {
                     KeyCode::Enter => {
                         if session.mask.is_some() {
-                            return Some(Message::SAM2(crate::sam2::SAM2Message::AcceptMask));
+                            // Shift+Enter keeps the mask as a raster instead of a polygon
+                            let message = if modifiers.shift {
+                                crate::sam2::SAM2Message::AcceptMaskAsRaster
+                            } else {
+                                crate::sam2::SAM2Message::AcceptMask
+                            };
+                            return Some(Message::SAM2(message));
                         }
                     }
                     KeyCode::Escape => {
@@ -1275,6 +1572,9 @@ impl HvatApp {
                 KeyCode::Escape => return Some(Message::CancelAnnotation),
                 KeyCode::Delete | KeyCode::Backspace => return Some(Message::DeleteAnnotation),
                 KeyCode::Enter => return Some(Message::FinishPolygon),
+                KeyCode::Tab if self.selected_tool == AnnotationTool::Keypoints => {
+                    return Some(Message::SkipKeypoint);
+                }
                 _ => {}
             }
 
@@ -1299,6 +1599,11 @@ impl HvatApp {
                         }
                     }
                 }
+
+                // P pins the spectrum under the cursor (unless bound above)
+                if *key == KeyCode::P && self.hover_pixel.is_some() {
+                    return Some(Message::PinSpectrum(None));
+                }
             }
         }
         None
@@ -1306,12 +1611,12 @@ impl HvatApp {
 
     /// Build current band selection uniform from state.
     fn band_selection_uniform(&self) -> BandSelectionUniform {
-        BandSelectionUniform {
-            red_band: self.band_selection.0 as u32,
-            green_band: self.band_selection.1 as u32,
-            blue_band: self.band_selection.2 as u32,
-            num_bands: self.num_bands as u32,
-        }
+        BandSelectionUniform::new(
+            self.band_selection.0,
+            self.band_selection.1,
+            self.band_selection.2,
+            self.num_bands,
+        )
     }
 
     /// Build current image adjustments from slider state.
@@ -1324,6 +1629,893 @@ impl HvatApp {
         }
     }
 
+    /// Histogram of the red, green and blue output channel.
+    ///
+    /// With a band-math program these are the histograms of its output
+    /// instead of the selected bands, in component mode those of the selected
+    /// components. Images decoded without histograms (WASM worker) have none.
+    pub(crate) fn channel_histograms(&self) -> [Option<BandHistogram>; 3] {
+        if let Some(map) = self.displayed_similarity() {
+            return std::array::from_fn(|_| Some(map.histogram.clone()));
+        }
+        if let Some(textures) = self.displayed_components() {
+            return self
+                .component_selection(textures)
+                .map(|component| textures.histograms.get(component).cloned());
+        }
+        if let Some(textures) = self.displayed_true_color() {
+            return std::array::from_fn(|channel| textures.histograms.get(channel).cloned());
+        }
+        if let Some(program) = &self.band_math_program {
+            let histograms: &[BandHistogram] = match &self.band_math_histograms {
+                Some((path, histograms)) if *path == self.current_image_path() => {
+                    histograms.as_slice()
+                }
+                _ => &[],
+            };
+            return std::array::from_fn(|channel| {
+                histograms.get(channel.min(program.channels() - 1)).cloned()
+            });
+        }
+        let histograms = self
+            .image_data_store
+            .get(&self.current_image_path())
+            .band_histograms;
+        [
+            self.band_selection.0,
+            self.band_selection.1,
+            self.band_selection.2,
+        ]
+        .map(|band| histograms.get(band).cloned())
+    }
+
+    /// Whether all output channels show the same value (band-math index or
+    /// similarity map), so one histogram covers the display.
+    pub(crate) fn single_channel_display(&self) -> bool {
+        self.displayed_similarity().is_some()
+            || self
+                .band_math_program
+                .as_ref()
+                .is_some_and(|program| program.channels() == 1)
+    }
+
+    /// Compute the stretch of the red, green and blue output channel.
+    ///
+    /// Follows the channel histograms; bands of images decoded without
+    /// histograms fall back to their value range. In manual mode the dragged
+    /// bounds replace the computed ones.
+    pub(crate) fn channel_stretches(&self) -> [Option<ChannelStretch>; 3] {
+        let mode = self.effective_stretch_mode();
+        if mode == StretchMode::None {
+            return [None, None, None];
+        }
+        let histograms = self.channel_histograms();
+        let raw_bands = self.displayed_similarity().is_none()
+            && self.displayed_components().is_none()
+            && self.displayed_true_color().is_none()
+            && self.band_math_program.is_none();
+        let bands = [
+            self.band_selection.0,
+            self.band_selection.1,
+            self.band_selection.2,
+        ];
+        std::array::from_fn(|channel| {
+            if mode == StretchMode::Manual
+                && let Some((low, high)) = self.manual_stretch[channel]
+            {
+                return Some(ChannelStretch {
+                    low,
+                    high,
+                    lut: Vec::new(),
+                });
+            }
+            match &histograms[channel] {
+                Some(hist) => ChannelStretch::compute(
+                    hist,
+                    mode,
+                    self.stretch_k_slider.value,
+                    STRETCH_LUT_SIZE,
+                ),
+                None if raw_bands => self.gpu_state.as_ref().map(|state| {
+                    let range = state.band_data.encoding().range(bands[channel]);
+                    ChannelStretch {
+                        low: range.min,
+                        high: range.max,
+                        lut: Vec::new(),
+                    }
+                }),
+                None => None,
+            }
+        })
+    }
+
+    /// Clip marker values (low, high) of each channel histogram: the current
+    /// stretch, or the histogram range when the stretch is off.
+    pub(crate) fn histogram_clips(&self) -> [Option<(f32, f32)>; 3] {
+        let stretches = self.channel_stretches();
+        let histograms = self.channel_histograms();
+        std::array::from_fn(
+            |channel| match (&stretches[channel], &histograms[channel]) {
+                (Some(stretch), _) => Some((stretch.low, stretch.high)),
+                (None, Some(hist)) if hist.total() > 0 => Some((hist.min, hist.max)),
+                _ => None,
+            },
+        )
+    }
+
+    /// Stretch mode applied to the display.
+    ///
+    /// Component and similarity values have no fixed range, so they are
+    /// always stretched (min/max when the stretch is off).
+    fn effective_stretch_mode(&self) -> StretchMode {
+        if self.stretch_mode == StretchMode::None
+            && (self.displayed_components().is_some() || self.displayed_similarity().is_some())
+        {
+            StretchMode::MinMax
+        } else {
+            self.stretch_mode
+        }
+    }
+
+    /// Build current contrast stretch uniform from state.
+    fn stretch_uniform(&self) -> StretchUniform {
+        let mut uniform = StretchUniform::new();
+        let mode = self.effective_stretch_mode();
+        if mode == StretchMode::None {
+            return uniform;
+        }
+        uniform.mode = if mode == StretchMode::Equalize {
+            StretchUniform::STRETCH_EQUALIZE
+        } else {
+            StretchUniform::STRETCH_LINEAR
+        };
+        for (channel, stretch) in self.channel_stretches().into_iter().enumerate() {
+            if let Some(stretch) = stretch {
+                uniform.set_channel(channel, stretch.low, stretch.high, Some(&stretch.lut));
+            }
+        }
+        uniform
+    }
+
+    /// Value range mapped by the colormap.
+    ///
+    /// The entered range if any, else the stretch of the red channel, else the
+    /// full value range of the red band (or of the band-math output).
+    pub(crate) fn colormap_value_range(&self) -> Option<(f32, f32)> {
+        if self.colormap_range.is_some() {
+            return self.colormap_range;
+        }
+        if let [Some(stretch), _, _] = self.channel_stretches() {
+            return Some((stretch.low, stretch.high));
+        }
+        let finite = |range: (f32, f32)| range.0.is_finite() && range.1.is_finite();
+        if self.band_math_program.is_some() {
+            return match &self.band_math_histograms {
+                Some((path, histograms)) if *path == self.current_image_path() => histograms
+                    .first()
+                    .map(|hist| (hist.min, hist.max))
+                    .filter(|&range| finite(range)),
+                _ => None,
+            };
+        }
+        let band = self.band_selection.0;
+        let histograms = self
+            .image_data_store
+            .get(&self.current_image_path())
+            .band_histograms;
+        match histograms.get(band) {
+            Some(hist) => Some((hist.min, hist.max)).filter(|&range| finite(range)),
+            None => self.gpu_state.as_ref().map(|state| {
+                let range = state.band_data.encoding().range(band);
+                (range.min, range.min + range.span())
+            }),
+        }
+    }
+
+    /// What the colormap shows: the red band, the index, the red expression
+    /// or the red component.
+    pub(crate) fn colormap_source(&self) -> String {
+        if let Some(map) = self.displayed_similarity() {
+            return match map.metric {
+                SimilarityMetric::SpectralAngle => "Spectral angle (rad)".to_string(),
+                SimilarityMetric::Euclidean => "Euclidean distance".to_string(),
+            };
+        }
+        if let Some(textures) = self.displayed_components() {
+            let [red, _, _] = self.component_selection(textures);
+            return format!("{}{}", textures.components.method.prefix(), red + 1);
+        }
+        if self.displayed_true_color().is_some() {
+            return "True color red".to_string();
+        }
+        match &self.band_math_program {
+            Some(program) if program.channels() == 1 => "Index".to_string(),
+            Some(_) => "Red expression".to_string(),
+            None => self
+                .current_band_info()
+                .get(self.band_selection.0)
+                .map(|info| info.describe())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Colormap applied to the display: the selected one, or the default
+    /// for the similarity map.
+    pub(crate) fn displayed_colormap(&self) -> Option<Colormap> {
+        self.colormap
+            .or_else(|| self.displayed_similarity().map(|_| Colormap::default()))
+    }
+
+    /// Build the colormap uniform from state.
+    fn colormap_uniform(&self) -> ColormapUniform {
+        if self.displayed_colormap().is_none() {
+            return ColormapUniform::disabled();
+        }
+        let (low, high) = self.colormap_value_range().unwrap_or((0.0, 1.0));
+        ColormapUniform::new(low, high, self.colormap_reversed)
+    }
+
+    /// Use the entered colormap range; both fields empty fits it to the data.
+    fn submit_colormap_range(&mut self) {
+        let min = self.colormap_min.trim();
+        let max = self.colormap_max.trim();
+        if min.is_empty() && max.is_empty() {
+            self.colormap_range = None;
+            self.needs_gpu_render = true;
+            return;
+        }
+        // A single entered bound keeps the other one from the current range
+        let current = self.colormap_value_range().unwrap_or((0.0, 1.0));
+        let parse = |text: &str, current: f32| {
+            if text.is_empty() {
+                Some(current)
+            } else {
+                text.parse::<f32>().ok().filter(|v| v.is_finite())
+            }
+        };
+        match (parse(min, current.0), parse(max, current.1)) {
+            (Some(low), Some(high)) if low != high => {
+                self.colormap_range = Some((low, high));
+                self.colormap_min = low.to_string();
+                self.colormap_max = high.to_string();
+                self.needs_gpu_render = true;
+            }
+            _ => log::warn!("Invalid colormap range: '{}' - '{}'", min, max),
+        }
+    }
+
+    /// Image area the render target shows, when it does not cover the whole
+    /// image (tiled images).
+    pub(crate) fn render_target_region(&self) -> Option<(f32, f32, f32, f32)> {
+        self.gpu_state.as_ref()?.target_region()
+    }
+
+    /// Component images shown instead of the bands, if any.
+    pub(crate) fn displayed_components(&self) -> Option<&ComponentTextures> {
+        self.component_method?;
+        self.gpu_state.as_ref()?.components.as_ref()
+    }
+
+    /// Components shown in the red, green and blue channel, clamped to the
+    /// computed ones.
+    pub(crate) fn component_selection(&self, textures: &ComponentTextures) -> [usize; 3] {
+        let last = textures.num_components().saturating_sub(1);
+        self.component_sliders
+            .each_ref()
+            .map(|slider| (slider.value as usize).min(last))
+    }
+
+    /// Compute the component images of the displayed image for the selected
+    /// method, or drop them when component mode is off.
+    ///
+    /// Loads the CPU bands if needed. The projection runs on a worker thread
+    /// where threads are available; the previous textures stay until its
+    /// images are uploaded. A failed computation is not retried until the
+    /// image or method changes.
+    fn refresh_components(&mut self, resources: &mut Resources<'_>) {
+        let Some(method) = self.component_method else {
+            if let Some(state) = self.gpu_state.as_mut() {
+                state.components = None;
+            }
+            return;
+        };
+        let Some(state) = &self.gpu_state else {
+            return;
+        };
+        if state
+            .components
+            .as_ref()
+            .is_some_and(|c| c.components.method == method)
+        {
+            return;
+        }
+        let size = (state.width, state.height);
+        let path = self.current_image_path();
+        if matches!(&self.component_error, Some((p, m, _)) if *p == path && *m == method) {
+            return;
+        }
+        let images = if !self.ensure_cpu_bands() {
+            Err("Band data is not available".to_string())
+        } else if self
+            .hyperspectral
+            .as_ref()
+            .is_none_or(|hyper| (hyper.width, hyper.height) != size)
+        {
+            Err("Band data does not match the displayed image".to_string())
+        } else {
+            match self.component_images(&path, method) {
+                Some(images) => images,
+                // Still computing
+                None => return,
+            }
+        };
+        let result = images.and_then(|images| match &self.shared_pipeline {
+            Some(pipeline) => Ok(ComponentTextures::upload(
+                resources.gpu_context(),
+                pipeline,
+                images,
+                size.0,
+                size.1,
+            )),
+            None => Err("GPU pipeline is not available".to_string()),
+        });
+        match result {
+            Ok(textures) => {
+                if let Some(state) = self.gpu_state.as_mut() {
+                    state.components = Some(textures);
+                }
+                self.component_error = None;
+            }
+            Err(e) => {
+                log::warn!("{} of {:?} failed: {}", method.name(), path, e);
+                self.component_error = Some((path, method, e));
+            }
+        }
+    }
+
+    /// Component images of the loaded CPU bands, computed on a worker thread.
+    ///
+    /// Starts the computation on the first call and returns None until
+    /// `poll_component_job` has collected its result.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn component_images(
+        &mut self,
+        path: &Path,
+        method: ComponentMethod,
+    ) -> Option<Result<ComponentImages, String>> {
+        if let Some(images) = self.component_jobs.take_result(path, method) {
+            return Some(images);
+        }
+        if self.component_jobs.is_running(path, method) {
+            return None;
+        }
+        let Some(hyper) = self.hyperspectral.as_ref() else {
+            return Some(Err("Band data is not available".to_string()));
+        };
+
+        // The worker projects its own copy of the bands
+        let bands = hyper.bands.clone();
+        let width = hyper.width;
+        let path = path.to_path_buf();
+        let sender = self.component_jobs.start(path.clone(), method);
+        log::info!(
+            "Computing {} of {:?} in the background",
+            method.name(),
+            path
+        );
+        std::thread::spawn(move || {
+            let images = ComponentImages::compute(
+                &bands,
+                width,
+                method,
+                MAX_DISPLAY_COMPONENTS,
+                COMPONENT_MAX_SAMPLES,
+            );
+            let _ = sender.send((path, method, images));
+        });
+        None
+    }
+
+    /// Component images of the loaded CPU bands, computed in place (no
+    /// worker threads).
+    #[cfg(target_arch = "wasm32")]
+    fn component_images(
+        &mut self,
+        _path: &Path,
+        method: ComponentMethod,
+    ) -> Option<Result<ComponentImages, String>> {
+        let hyper = self.hyperspectral.as_ref()?;
+        Some(ComponentImages::compute(
+            &hyper.bands,
+            hyper.width,
+            method,
+            MAX_DISPLAY_COMPONENTS,
+            COMPONENT_MAX_SAMPLES,
+        ))
+    }
+
+    /// Collect the result of the running component computation and schedule
+    /// its upload. Results of abandoned computations are dropped.
+    ///
+    /// Returns whether a computation is still running.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_component_job(&mut self) -> bool {
+        if self.component_jobs.poll(self.component_method) {
+            self.needs_gpu_render = true;
+        }
+        self.component_jobs.is_busy()
+    }
+
+    /// True-color images shown instead of the bands, if any.
+    pub(crate) fn displayed_true_color(&self) -> Option<&TrueColorTextures> {
+        self.true_color_illuminant?;
+        self.gpu_state.as_ref()?.true_color.as_ref()
+    }
+
+    /// Synthesize the true-color images of the displayed image under the
+    /// selected illuminant, or drop them when true-color mode is off.
+    ///
+    /// Loads the CPU bands if needed. A failed synthesis is not retried
+    /// until the image or illuminant changes.
+    fn refresh_true_color(&mut self, resources: &mut Resources<'_>) {
+        let Some(illuminant) = self.true_color_illuminant else {
+            if let Some(state) = self.gpu_state.as_mut() {
+                state.true_color = None;
+            }
+            return;
+        };
+        let Some(state) = &self.gpu_state else {
+            return;
+        };
+        if state
+            .true_color
+            .as_ref()
+            .is_some_and(|t| t.true_color.illuminant == illuminant)
+        {
+            return;
+        }
+        let path = self.current_image_path();
+        if matches!(&self.true_color_error, Some((p, i, _)) if *p == path && *i == illuminant) {
+            return;
+        }
+        let result = if !self.ensure_cpu_bands() {
+            Err("Band data is not available".to_string())
+        } else {
+            // Bad-band flags may have been edited in the session
+            let band_info = self.current_band_info();
+            match (&self.hyperspectral, &self.shared_pipeline, &self.gpu_state) {
+                (Some(hyper), Some(pipeline), Some(state))
+                    if (hyper.width, hyper.height) == (state.width, state.height) =>
+                {
+                    TrueColorTextures::compute(
+                        resources.gpu_context(),
+                        pipeline,
+                        hyper,
+                        &band_info,
+                        illuminant,
+                    )
+                }
+                _ => Err("Band data does not match the displayed image".to_string()),
+            }
+        };
+        match result {
+            Ok(textures) => {
+                if let Some(state) = self.gpu_state.as_mut() {
+                    state.true_color = Some(textures);
+                }
+                self.true_color_error = None;
+            }
+            Err(e) => {
+                log::warn!("True color of {:?} failed: {}", path, e);
+                self.true_color_error = Some((path, illuminant, e));
+            }
+        }
+    }
+
+    /// Similarity map shown instead of the bands, if any.
+    pub(crate) fn displayed_similarity(&self) -> Option<&SimilarityMap> {
+        if !self.similarity_shown {
+            return None;
+        }
+        self.gpu_state.as_ref()?.similarity.as_ref()?;
+        self.current_similarity_map()
+    }
+
+    /// Similarity map of the displayed image, if computed.
+    pub(crate) fn current_similarity_map(&self) -> Option<&SimilarityMap> {
+        match &self.similarity_map {
+            Some((path, map)) if *path == self.current_image_path() => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Map value at the threshold slider position.
+    pub(crate) fn similarity_threshold(&self) -> Option<f32> {
+        let hist = &self.current_similarity_map()?.histogram;
+        (hist.min.is_finite() && hist.max.is_finite())
+            .then(|| hist.min + (hist.max - hist.min) * self.similarity_threshold_slider.value)
+    }
+
+    /// Use `spectrum` as the similarity reference and recompute the map.
+    fn set_similarity_reference(&mut self, source: String, spectrum: Vec<f32>) {
+        log::info!("Similarity reference: {}", source);
+        self.similarity_reference = Some((source, spectrum));
+        self.similarity_picking = false;
+        self.similarity_map = None;
+        self.similarity_error = None;
+        self.similarity_collapsed = CollapsibleState::expanded();
+    }
+
+    /// Use the mean spectrum of the selected annotation as the reference.
+    fn use_annotation_as_reference(&mut self) {
+        let path = self.current_image_path();
+        let Some(annotation) = self
+            .image_data_store
+            .get(&path)
+            .annotations
+            .into_iter()
+            .find(|a| a.selected)
+        else {
+            log::warn!("Similarity reference: no annotation selected");
+            return;
+        };
+        if !self.ensure_cpu_bands() {
+            return;
+        }
+        let Some(hyper) = self.hyperspectral.as_ref() else {
+            return;
+        };
+        let pixels = annotation.shape.mask_pixels(hyper.width, hyper.height);
+        let stats = RegionStats::compute(&hyper.bands, &pixels);
+        if stats.pixel_count == 0 {
+            log::warn!(
+                "Similarity reference: annotation {} covers no pixels",
+                annotation.id
+            );
+            return;
+        }
+        let source = format!(
+            "Mean of annotation {} ({} pixels)",
+            annotation.id, stats.pixel_count
+        );
+        self.set_similarity_reference(source, stats.mean);
+    }
+
+    /// Use the spectrum of the pixel at (x, y) as the reference.
+    fn use_pixel_as_reference(&mut self, x: u32, y: u32) {
+        if !self.ensure_cpu_bands() {
+            self.similarity_picking = false;
+            return;
+        }
+        match self.pixel_spectrum(x, y) {
+            Some(values) => self.set_similarity_reference(format!("Pixel ({}, {})", x, y), values),
+            None => self.similarity_picking = false,
+        }
+    }
+
+    /// Compute the similarity map of the displayed image if it is missing.
+    ///
+    /// Only runs while the similarity section is open or the map is shown,
+    /// since it may have to load the image bands.
+    fn refresh_similarity_map(&mut self) {
+        if !self.similarity_collapsed.is_expanded && !self.similarity_shown {
+            return;
+        }
+        let Some((_, reference)) = self.similarity_reference.clone() else {
+            return;
+        };
+        let path = self.current_image_path();
+        if let Some((map_path, map)) = &self.similarity_map
+            && *map_path == path
+            && map.metric == self.similarity_metric
+        {
+            return;
+        }
+        if matches!(&self.similarity_error, Some((p, _)) if *p == path) {
+            return;
+        }
+        if !self.ensure_cpu_bands() {
+            self.similarity_error = Some((path, "Band data is not available".to_string()));
+            return;
+        }
+        // Bad-band flags may have been edited in the session
+        let band_info = self.current_band_info();
+        let Some(hyper) = self.hyperspectral.as_ref() else {
+            return;
+        };
+        match SimilarityMap::compute(
+            &hyper.bands,
+            hyper.width,
+            hyper.height,
+            &band_info,
+            &reference,
+            self.similarity_metric,
+        ) {
+            Ok(map) => {
+                log::debug!(
+                    "{} similarity map of {:?}: {:.4} - {:.4}",
+                    self.similarity_metric.name(),
+                    path,
+                    map.histogram.min,
+                    map.histogram.max
+                );
+                self.similarity_map = Some((path, map));
+                self.similarity_error = None;
+            }
+            Err(e) => {
+                log::warn!("Similarity map of {:?}: {}", path, e);
+                self.similarity_map = None;
+                self.similarity_error = Some((path, e));
+            }
+        }
+        self.similarity_upload_dirty = true;
+        self.needs_gpu_render = true;
+        self.update_similarity_regions();
+    }
+
+    /// Trace the regions of the map below the threshold.
+    fn update_similarity_regions(&mut self) {
+        let regions = match (self.current_similarity_map(), self.similarity_threshold()) {
+            (Some(map), Some(threshold)) => map.regions(
+                threshold,
+                SIMILARITY_MIN_REGION_AREA,
+                SIMILARITY_CONTOUR_EPSILON,
+                MAX_SIMILARITY_REGIONS,
+            ),
+            _ => Vec::new(),
+        };
+        self.similarity_regions = regions;
+    }
+
+    /// Upload the similarity map of the displayed image while it is shown,
+    /// or drop it from the GPU.
+    fn refresh_similarity_texture(&mut self, resources: &mut Resources<'_>) {
+        let path = self.current_image_path();
+        let Some(state) = self.gpu_state.as_mut() else {
+            return;
+        };
+        let map = match &self.similarity_map {
+            Some((map_path, map))
+                if self.similarity_shown
+                    && *map_path == path
+                    && (map.width, map.height) == (state.width, state.height) =>
+            {
+                map
+            }
+            _ => {
+                state.similarity = None;
+                return;
+            }
+        };
+        if state.similarity.is_some() && !self.similarity_upload_dirty {
+            return;
+        }
+        let Some(pipeline) = &self.shared_pipeline else {
+            return;
+        };
+        state.similarity = Some(GpuBands::upload(
+            resources.gpu_context(),
+            pipeline,
+            std::slice::from_ref(&map.values),
+            map.width,
+            map.height,
+        ));
+        self.similarity_upload_dirty = false;
+    }
+
+    /// Fit the render target of a tiled image to the visible area, uploading
+    /// the tiles it needs.
+    ///
+    /// Before the viewer has reported its size, the window size stands in
+    /// for it. Keeps `needs_gpu_render` set while tiles are missing, so the
+    /// view sharpens over the next frames.
+    fn refresh_tiles(&mut self, resources: &mut Resources<'_>) {
+        let path = self.current_image_path();
+        let (Some(state), Some(pipeline)) = (self.gpu_state.as_mut(), &self.shared_pipeline) else {
+            return;
+        };
+        let mut viewer = self.viewer_state.clone();
+        let (view_w, view_h) = viewer.cached_view_size.unwrap_or(self.window_size);
+        viewer.sync_with_bounds(view_w, view_h, state.width, state.height);
+        let Some(rect) = viewer.visible_image_rect() else {
+            return;
+        };
+        let bands = self
+            .hyperspectral
+            .as_ref()
+            .filter(|hyper| (hyper.width, hyper.height) == (state.width, state.height))
+            .map(|hyper| hyper.bands.as_slice())
+            .unwrap_or_default();
+        let similarity = match &self.similarity_map {
+            Some((map_path, map)) if *map_path == path => Some(&map.values),
+            _ => None,
+        };
+        match state.update_tiles(
+            resources.gpu_context(),
+            pipeline,
+            rect,
+            viewer.effective_zoom(),
+            bands,
+            similarity,
+        ) {
+            Ok(update) => {
+                if update.target_resized
+                    && let Some(id) = self.texture_id.take()
+                {
+                    resources.unregister_texture(id);
+                }
+                if update.pending {
+                    self.needs_gpu_render = true;
+                }
+            }
+            Err(e) => log::error!("Failed to resize the tile render target: {:?}", e),
+        }
+    }
+
+    /// Turn the regions below the threshold into polygon annotations.
+    fn create_similarity_annotations(&mut self) {
+        if self.similarity_regions.is_empty() || self.current_similarity_map().is_none() {
+            return;
+        }
+        self.push_annotation_undo_point();
+        let path = self.current_image_path();
+        let category = self.selected_category;
+        let image_data = self.image_data_store.get_or_create(&path);
+        for vertices in &self.similarity_regions {
+            let annotation = Annotation::new(
+                image_data.next_annotation_id,
+                AnnotationShape::Polygon {
+                    vertices: vertices.clone(),
+                },
+                category,
+            );
+            image_data.next_annotation_id += 1;
+            image_data.annotations.push(annotation);
+        }
+        self.auto_save.mark_dirty();
+        log::info!(
+            "Created {} polygon annotations from the similarity map",
+            self.similarity_regions.len()
+        );
+    }
+
+    /// Expressions of the current band-math mode.
+    fn band_math_sources(&self) -> Vec<String> {
+        match self.band_math_mode {
+            BandMathMode::Off => Vec::new(),
+            BandMathMode::Rgb => self.band_math_channels.to_vec(),
+            BandMathMode::Index => vec![self.band_math_index.clone()],
+        }
+    }
+
+    /// Compile the expressions of the current band-math mode and display them.
+    ///
+    /// On errors the band composite is displayed until valid expressions
+    /// are applied.
+    fn apply_band_math(&mut self) {
+        let sources = self.band_math_sources();
+        let program = if sources.iter().all(|s| s.trim().is_empty()) {
+            self.band_math_error = None;
+            None
+        } else {
+            match BandMathProgram::compile(&sources, self.num_bands) {
+                Ok(program) => {
+                    self.band_math_error = None;
+                    Some(program)
+                }
+                Err(e) => {
+                    log::warn!("Band math: {}", e);
+                    self.band_math_error = Some(e.message);
+                    None
+                }
+            }
+        };
+        if program != self.band_math_program {
+            self.band_math_program = program;
+            self.band_math_histograms = None;
+            self.band_math_shader_dirty = true;
+            self.needs_gpu_render = true;
+        }
+        // Expressions sample the bands, not the components, the true-color
+        // images or the map
+        if self.band_math_program.is_some() {
+            self.component_method = None;
+            self.true_color_illuminant = None;
+            self.similarity_shown = false;
+        }
+    }
+
+    /// Compute the output histograms of the band-math program for the
+    /// displayed image if they are missing.
+    ///
+    /// Evaluates the expressions on the CPU, loading the bands if needed.
+    fn refresh_band_math_stats(&mut self) {
+        let Some(program) = &self.band_math_program else {
+            return;
+        };
+        let path = self.current_image_path();
+        if self
+            .band_math_histograms
+            .as_ref()
+            .is_some_and(|(p, _)| *p == path)
+        {
+            return;
+        }
+        // A new image may lack bands the expressions refer to
+        if program
+            .expressions
+            .iter()
+            .any(|expr| expr.validate(self.num_bands).is_err())
+        {
+            self.apply_band_math();
+            return;
+        }
+
+        let histograms: Vec<BandHistogram> = if self.ensure_cpu_bands()
+            && let Some(hyper) = &self.hyperspectral
+            && let Some(program) = &self.band_math_program
+        {
+            program
+                .evaluate(&hyper.bands)
+                .iter()
+                .map(|values| BandHistogram::from_values(values))
+                .collect()
+        } else {
+            // Not retried until the image or expressions change
+            Vec::new()
+        };
+        self.band_math_histograms = Some((path, histograms));
+        self.needs_gpu_render = true;
+    }
+
+    /// Save the expressions of the current mode under the entered name,
+    /// replacing saved expressions with the same name.
+    fn save_band_math_preset(&mut self) {
+        let name = self.band_math_name.trim().to_string();
+        let expressions = self.band_math_sources();
+        if name.is_empty() || expressions.iter().all(|e| e.trim().is_empty()) {
+            return;
+        }
+        let preset = BandMathPreset { name, expressions };
+        log::info!("Saved band math '{}'", preset.name);
+        match self
+            .band_math_presets
+            .iter_mut()
+            .find(|p| p.name == preset.name)
+        {
+            Some(existing) => *existing = preset,
+            None => self.band_math_presets.push(preset),
+        }
+        self.band_math_name.clear();
+        self.auto_save_config();
+    }
+
+    /// Load saved expressions into the matching mode and display them.
+    fn load_band_math_preset(&mut self, index: usize) {
+        let Some(preset) = self.band_math_presets.get(index) else {
+            return;
+        };
+        match preset.expressions.as_slice() {
+            [expression] => {
+                self.band_math_mode = BandMathMode::Index;
+                self.band_math_index = expression.clone();
+            }
+            [red, green, blue] => {
+                self.band_math_mode = BandMathMode::Rgb;
+                self.band_math_channels = [red.clone(), green.clone(), blue.clone()];
+            }
+            _ => {
+                log::warn!(
+                    "Band math '{}' has {} expressions, expected 1 or 3",
+                    preset.name,
+                    preset.expressions.len()
+                );
+                return;
+            }
+        }
+        self.apply_band_math();
+    }
+
     /// Initialize GPU pipeline and upload band data.
     fn init_gpu_state(&mut self, resources: &mut Resources<'_>) {
         let Some(ref hyper) = self.hyperspectral else {
@@ -1358,6 +2550,34 @@ impl HvatApp {
 
     /// Render hyperspectral composite to the render target texture.
     fn render_to_texture(&mut self, resources: &mut Resources<'_>) {
+        if self.band_math_shader_dirty
+            && let Some(pipeline) = self.shared_pipeline.as_mut()
+        {
+            self.band_math_shader_dirty = false;
+            if let Err(e) =
+                pipeline.set_band_math(resources.gpu_context(), self.band_math_program.as_ref())
+            {
+                // Show the composite and the error, as for invalid expressions
+                log::error!("Band math: {}", e);
+                self.band_math_error = Some(e.to_string());
+                self.band_math_program = None;
+                self.band_math_histograms = None;
+            }
+        }
+        self.refresh_components(resources);
+        self.refresh_true_color(resources);
+        self.refresh_similarity_texture(resources);
+        self.refresh_tiles(resources);
+        if let Some(pipeline) = &self.shared_pipeline {
+            if let Some(colormap) = self.displayed_colormap()
+                && self.colormap_lut != Some(colormap)
+            {
+                pipeline.set_colormap(resources.gpu_context(), colormap);
+                self.colormap_lut = Some(colormap);
+            }
+            pipeline.update_colormap(resources.gpu_context(), self.colormap_uniform());
+        }
+
         let Some(ref gpu_state) = self.gpu_state else {
             return;
         };
@@ -1366,12 +2586,24 @@ impl HvatApp {
         };
 
         let gpu_ctx = resources.gpu_context();
+        let band_selection = if self.displayed_similarity().is_some() {
+            BandSelectionUniform::new(0, 0, 0, 1)
+        } else if let Some(textures) = self.displayed_components() {
+            let [red, green, blue] = self.component_selection(textures);
+            BandSelectionUniform::new(red, green, blue, textures.num_components())
+        } else if self.displayed_true_color().is_some() {
+            BandSelectionUniform::new(0, 1, 2, 3)
+        } else {
+            self.band_selection_uniform()
+        };
 
         gpu_state.render(
             gpu_ctx,
             pipeline,
-            self.band_selection_uniform(),
+            band_selection,
             self.image_adjustments(),
+            self.stretch_uniform(),
+            self.band_math_program.as_ref(),
         );
 
         // Register render target with UI renderer if not already done
@@ -1382,7 +2614,7 @@ impl HvatApp {
         }
 
         log::debug!(
-            "GPU render: bands ({}, {}, {}), brightness={:.2}, contrast={:.2}, gamma={:.2}, hue={:.0}",
+            "GPU render: bands ({}, {}, {}), brightness={:.2}, contrast={:.2}, gamma={:.2}, hue={:.0}, stretch={:?}",
             self.band_selection.0,
             self.band_selection.1,
             self.band_selection.2,
@@ -1390,6 +2622,7 @@ impl HvatApp {
             self.contrast_slider.value,
             self.gamma_slider.value,
             self.hue_slider.value,
+            self.stretch_mode,
         );
     }
 
@@ -1416,7 +2649,316 @@ impl HvatApp {
             keybindings: KeyBindingsConfig::from(&self.keybindings),
             categories: self.categories.iter().map(CategoryConfig::from).collect(),
             tags: self.tags.iter().map(TagConfig::from).collect(),
+            loader: self.loader_options.clone(),
+            band_math: self.band_math_presets.clone(),
+        }
+    }
+
+    /// Layout rule pattern covering the current project folder.
+    pub(crate) fn project_layout_pattern(&self) -> Option<String> {
+        self.project.as_ref().map(|p| project_pattern(&p.folder))
+    }
+
+    /// Band metadata of the current image, with "Band N" defaults if the
+    /// loader provided none.
+    pub(crate) fn current_band_info(&self) -> Vec<BandInfo> {
+        let info = self
+            .image_data_store
+            .get(&self.current_image_path())
+            .band_info;
+        if info.len() == self.num_bands {
+            info
+        } else {
+            default_bands(self.num_bands)
+        }
+    }
+
+    /// Make the band values of the displayed image available on the CPU.
+    ///
+    /// Images shown from the GPU cache only keep their textures, so the bands
+    /// are loaded again on first use. Returns whether they are available.
+    fn ensure_cpu_bands(&mut self) -> bool {
+        if self.hyperspectral.is_some() {
+            return true;
+        }
+        let Some(project) = self.project.as_ref() else {
+            return false;
+        };
+        let path = self.current_image_path();
+        match project.load_hyperspectral(&path, &self.loader_options, &self.preprocessing) {
+            Ok(hyper) => {
+                log::info!("Loaded CPU band data for {:?}", path);
+                self.hyperspectral = Some(hyper);
+                true
+            }
+            Err(e) => {
+                log::warn!("Failed to load band data for {:?}: {}", path, e);
+                false
+            }
+        }
+    }
+
+    /// Band values of the displayed image at pixel (x, y).
+    pub(crate) fn pixel_spectrum(&self, x: u32, y: u32) -> Option<Vec<f32>> {
+        self.hyperspectral
+            .as_ref()
+            .filter(|h| (h.width, h.height) == self.image_size)
+            .and_then(|h| h.pixel_spectrum(x, y))
+    }
+
+    /// Pin the spectrum at `pixel`, or at the pixel under the cursor.
+    fn pin_spectrum(&mut self, pixel: Option<(u32, u32)>) {
+        let Some((x, y)) = pixel.or(self.hover_pixel) else {
+            return;
+        };
+        if !self.ensure_cpu_bands() {
+            return;
+        }
+        let Some(values) = self.pixel_spectrum(x, y) else {
+            return;
+        };
+        if self.pinned_spectra.len() >= MAX_PINNED_SPECTRA {
+            self.pinned_spectra.remove(0);
+        }
+        let color = next_pin_color(&self.pinned_spectra);
+        self.pinned_spectra.push(PinnedSpectrum {
+            image_path: self.current_image_path(),
+            x,
+            y,
+            values,
+            color,
+        });
+        self.spectrum_collapsed = CollapsibleState::expanded();
+        log::info!("Pinned spectrum at ({}, {})", x, y);
+    }
+
+    /// Recompute the band statistics of the selected annotation if it changed.
+    ///
+    /// Only runs while the statistics section is open, since it may have to
+    /// load the image bands.
+    fn refresh_annotation_stats(&mut self) {
+        if !self.annotation_stats_collapsed.is_expanded {
+            return;
+        }
+        let path = self.current_image_path();
+        let Some(annotation) = self
+            .image_data_store
+            .get(&path)
+            .annotations
+            .into_iter()
+            .find(|a| a.selected)
+        else {
+            self.annotation_stats = None;
+            return;
+        };
+        if let Some(current) = &self.annotation_stats
+            && current.image_path == path
+            && current.shape == annotation.shape
+        {
+            return;
+        }
+        if !self.ensure_cpu_bands() {
+            self.annotation_stats = None;
+            return;
+        }
+        let Some(hyper) = self.hyperspectral.as_ref() else {
+            return;
+        };
+        let pixels = annotation.shape.mask_pixels(hyper.width, hyper.height);
+        let stats = RegionStats::compute(&hyper.bands, &pixels);
+        log::debug!(
+            "Annotation {} statistics: {} pixels",
+            annotation.id,
+            stats.pixel_count
+        );
+        self.annotation_stats = Some(AnnotationStats {
+            image_path: path,
+            shape: annotation.shape,
+            stats,
+        });
+    }
+
+    /// Collect the spectra inside all annotations of the project.
+    ///
+    /// Loads the bands of every annotated image; the displayed image reuses
+    /// its CPU data.
+    fn build_spectral_dataset(&self) -> SpectralDataset {
+        let mut dataset = SpectralDataset::new();
+        let current = self.current_image_path();
+        let (folder, images) = match &self.project {
+            Some(project) => (project.folder.clone(), project.images.clone()),
+            None => (PathBuf::new(), vec![current.clone()]),
+        };
+
+        for path in images {
+            let annotations = self.image_data_store.get(&path).annotations;
+            if annotations.is_empty() {
+                continue;
+            }
+            let name = path
+                .strip_prefix(&folder)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned();
+            let hyper =
+                match &self.hyperspectral {
+                    Some(hyper) if path == current => Cow::Borrowed(hyper),
+                    _ => match self.project.as_ref().map(|p| {
+                        p.load_hyperspectral(&path, &self.loader_options, &self.preprocessing)
+                    }) {
+                        Some(Ok(hyper)) => Cow::Owned(hyper),
+                        Some(Err(e)) => {
+                            dataset.warnings.push(format!("Skipped {}: {}", name, e));
+                            continue;
+                        }
+                        None => continue,
+                    },
+                };
+            dataset.add_image(&name, &hyper, &annotations, &self.categories);
+        }
+
+        for warning in &dataset.warnings {
+            log::warn!("Spectral export: {}", warning);
+        }
+        log::info!(
+            "Collected {} annotations with {} pixels for spectral export",
+            dataset.annotations.len(),
+            dataset.pixel_count()
+        );
+        dataset
+    }
+
+    /// Export per-annotation band statistics (CSV) or per-pixel spectra
+    /// (`.npy` plus a `.index.csv` naming each row).
+    fn export_spectral_data(&self, pixel_spectra: bool) {
+        let default_name = if pixel_spectra {
+            "spectra.npy"
+        } else {
+            "annotation_stats.csv"
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (filter, ext) = if pixel_spectra {
+                ("NumPy", "npy")
+            } else {
+                ("CSV", "csv")
+            };
+            let Some(path) = rfd::FileDialog::new()
+                .set_file_name(default_name)
+                .add_filter(filter, &[ext])
+                .save_file()
+            else {
+                return;
+            };
+            let dataset = self.build_spectral_dataset();
+            let result = if pixel_spectra {
+                dataset
+                    .spectra_npy()
+                    .map_err(|e| e.to_string())
+                    .and_then(|npy| std::fs::write(&path, npy).map_err(|e| e.to_string()))
+                    .and_then(|_| {
+                        std::fs::write(
+                            path.with_extension("index.csv"),
+                            dataset.spectra_index_csv(),
+                        )
+                        .map_err(|e| e.to_string())
+                    })
+            } else {
+                std::fs::write(&path, dataset.stats_csv()).map_err(|e| e.to_string())
+            };
+            match result {
+                Ok(()) => log::info!("Spectral data exported to {:?}", path),
+                Err(e) => log::error!("Failed to export spectral data: {}", e),
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let dataset = self.build_spectral_dataset();
+            if pixel_spectra {
+                match dataset.spectra_npy() {
+                    Ok(npy) => {
+                        self.download_file_wasm(default_name, "application/octet-stream", &npy);
+                        self.download_file_wasm(
+                            "spectra.index.csv",
+                            "text/csv",
+                            dataset.spectra_index_csv().as_bytes(),
+                        );
+                    }
+                    Err(e) => log::error!("Failed to export spectral data: {}", e),
+                }
+            } else {
+                self.download_file_wasm(default_name, "text/csv", dataset.stats_csv().as_bytes());
+            }
+        }
+    }
+
+    /// Select the bands closest to the wavelengths typed into the band picker.
+    ///
+    /// One wavelength selects the same band for all channels, three set R, G, B.
+    fn select_bands_by_wavelength(&mut self) {
+        let input = self.band_wavelength_input.clone();
+        let Some(wavelengths) = input
+            .split(',')
+            .map(parse_wavelength)
+            .collect::<Option<Vec<f32>>>()
+        else {
+            log::warn!("Invalid wavelength '{}'", input);
+            return;
+        };
+        let info = self.current_band_info();
+        let Some(bands) = wavelengths
+            .iter()
+            .map(|&nm| nearest_band(&info, nm))
+            .collect::<Option<Vec<usize>>>()
+        else {
+            log::warn!("Current image has no wavelength metadata");
+            return;
+        };
+        let (red, green, blue) = match bands.as_slice() {
+            [band] => (*band, *band, *band),
+            [red, green, blue] => (*red, *green, *blue),
+            _ => {
+                log::warn!("Enter one wavelength or three (R, G, B), got '{}'", input);
+                return;
+            }
+        };
+
+        self.undo_stack.borrow_mut().push(self.snapshot());
+        self.band_selection = (red, green, blue);
+        self.red_band_slider.set_value(red as f32);
+        self.green_band_slider.set_value(green as f32);
+        self.blue_band_slider.set_value(blue as f32);
+        self.needs_gpu_render = true;
+        log::info!("Bands by wavelength '{}': {:?}", input, self.band_selection);
+    }
+
+    /// Append a validated step to the preprocessing chain.
+    fn add_preprocess_step(&mut self, step: PreprocessStep) {
+        if let Err(e) = step.validate() {
+            self.preprocess_error = Some(e);
+            return;
         }
+        log::info!("Preprocessing step added: {}", step.describe());
+        self.preprocessing.steps.push(step);
+        self.apply_preprocessing_change();
+    }
+
+    /// Save the changed preprocessing chain with the project and reload
+    /// the images with it.
+    fn apply_preprocessing_change(&mut self) {
+        self.preprocess_error = None;
+        self.auto_save.mark_dirty();
+        self.gpu_cache.clear();
+        self.pending_image_load = true;
+    }
+
+    /// Persist changed loader options and re-decode images with them.
+    fn apply_loader_options_change(&mut self) {
+        self.auto_save_config();
+        self.gpu_cache.clear();
+        self.pending_image_load = true;
     }
 
     /// Auto-save configuration to persistent storage.
@@ -1799,6 +3341,11 @@ impl HvatApp {
                 self.gpu_cache.set_preload_count(self.gpu_preload_count);
                 self.log_level = config.preferences.log_level;
                 log::set_max_level(self.log_level.to_level_filter());
+                self.loader_options = config.loader;
+                self.load_band_range = self.loader_options.subset.band_range_text();
+                self.load_band_stride_slider =
+                    SliderState::new(self.loader_options.subset.band_stride as f32);
+                self.band_math_presets = config.band_math;
 
                 // Apply keybindings
                 self.keybindings = config.keybindings.to_keybindings();
@@ -1954,6 +3501,9 @@ impl HvatApp {
             return;
         }
 
+        // Statistics are recomputed from the (re)loaded bands
+        self.annotation_stats = None;
+
         // Reset SAM2 state when switching images - embeddings are image-specific
         #[cfg(feature = "sam2")]
         {
@@ -1977,9 +3527,11 @@ impl HvatApp {
             (self.gpu_state.take(), self.current_gpu_image_path.take())
         {
             // Only cache if it's not the same image we're about to load
-            if old_path != path {
+            if old_path != path
+                && let Some(cached) = old_state.into_cached()
+            {
                 log::debug!("Returning {:?} to GPU cache", old_path);
-                self.gpu_cache.insert(old_path, old_state.into_cached());
+                self.gpu_cache.insert(old_path, cached);
             }
         }
 
@@ -1991,6 +3543,8 @@ impl HvatApp {
             log::info!("*** CACHE HIT *** Using cached GPU data for {:?}", path);
 
             self.num_bands = cached.num_bands;
+            // The cache only holds textures; bands are reloaded when needed
+            self.hyperspectral = None;
             self.reset_band_sliders();
             self.reset_adjustment_sliders();
             self.texture_id = None;
@@ -2036,27 +3590,37 @@ impl HvatApp {
 
         // Not cached - load from disk/memory using unified API
         let hyper_result = if let Some(ref project) = self.project {
-            project
-                .get_image_data(&path)
-                .and_then(|data| HyperspectralData::from_bytes(&data))
+            project.load_hyperspectral(&path, &self.loader_options, &self.preprocessing)
         } else {
             Err("No project loaded".to_string())
         };
 
         match hyper_result {
-            Ok(hyper) => {
+            Ok(mut hyper) => {
                 self.num_bands = hyper.bands.len();
                 self.reset_band_sliders();
                 self.reset_adjustment_sliders();
 
                 self.texture_id = None;
+                let geotransform = hyper.geotransform;
+                let warnings = std::mem::take(&mut hyper.warnings);
+                let band_info = hyper.band_info.clone();
+                let calibration = hyper.calibration.clone();
+                let histograms = band_histograms(&hyper.bands);
                 self.hyperspectral = Some(hyper);
 
                 self.init_gpu_state(resources);
                 self.current_gpu_image_path = Some(path.clone());
-                // Store dimensions in image data for export
+                // Store dimensions and georeferencing in image data for export
                 let image_data = self.image_data_store.get_or_create(&path);
                 image_data.dimensions = Some(self.image_size);
+                if geotransform.is_some() {
+                    image_data.geotransform = geotransform;
+                }
+                image_data.band_info = band_info;
+                image_data.calibration = calibration;
+                image_data.band_histograms = Arc::new(histograms);
+                image_data.load_warnings = warnings;
                 self.render_to_texture(resources);
                 self.pending_preload = true; // Trigger preloading for adjacent images
 
@@ -2149,8 +3713,17 @@ impl HvatApp {
         if let Some(result) = worker_result {
             match result {
                 DecodeResult::Decoded(decoded) => {
-                    // Don't queue if already in cache or already queued
-                    if !self.gpu_cache.contains(&decoded.path)
+                    // Tiled images are not preloaded; don't queue if already
+                    // in cache or already queued
+                    if TiledGpuData::required(
+                        gpu_ctx,
+                        decoded.width,
+                        decoded.height,
+                        decoded.encoding.num_bands(),
+                        TILED_IMAGE_BYTES,
+                    ) {
+                        self.gpu_cache.skip(decoded.path);
+                    } else if !self.gpu_cache.contains(&decoded.path)
                         && !self
                             .wasm_preload
                             .chunked_upload_queue
@@ -2168,9 +3741,9 @@ impl HvatApp {
                             decoded.path,
                             decoded.width,
                             decoded.height,
-                            decoded.num_bands,
                             decoded.num_layers,
                             decoded.layers,
+                            decoded.encoding,
                             &gpu_ctx.device,
                         );
                     }
@@ -2206,8 +3779,8 @@ impl HvatApp {
                 completed.texture,
                 completed.width,
                 completed.height,
-                completed.num_bands,
                 completed.num_layers,
+                completed.encoding,
                 pipeline.band_texture_layout(),
             );
         }
@@ -2237,6 +3810,14 @@ impl HvatApp {
         const MAX_IN_FLIGHT: usize = 3;
         let project = self.project.as_ref().unwrap();
         for path in &to_preload {
+            // The worker decodes at full resolution without processing;
+            // subsets, preprocessed and calibrated images load on demand
+            if !self.loader_options.subset.is_full() || !self.preprocessing.is_empty() {
+                break;
+            }
+            if self.loader_options.calibration_for(path).is_some() {
+                continue;
+            }
             let pending_count = self
                 .wasm_preload
                 .decoder_worker
@@ -2263,7 +3844,10 @@ impl HvatApp {
             if let Ok(data) = project.get_image_data(path) {
                 if let Some(ref mut worker) = self.wasm_preload.decoder_worker {
                     log::debug!("Requesting worker decode for {:?}", path);
-                    worker.request_decode(path.clone(), data);
+                    match data.into_vec() {
+                        Ok(bytes) => worker.request_decode(path.clone(), bytes),
+                        Err(e) => log::warn!("Failed to read {:?}: {}", path, e),
+                    }
                 }
             }
         }
@@ -2326,15 +3910,30 @@ impl HvatApp {
                         img.height,
                         img.num_layers
                     );
-                    native_preload.chunked_upload_queue.queue_prepacked(
-                        img.path,
+                    let image_data = self.image_data_store.get_or_create(&img.path);
+                    image_data.band_info = img.band_info;
+                    image_data.band_histograms = Arc::new(img.histograms);
+                    image_data.load_warnings = img.warnings;
+                    image_data.calibration = img.calibration;
+                    if TiledGpuData::required(
+                        gpu_ctx,
                         img.width,
                         img.height,
-                        img.num_bands,
-                        img.num_layers,
-                        img.layers,
-                        &gpu_ctx.device,
-                    );
+                        img.encoding.num_bands(),
+                        TILED_IMAGE_BYTES,
+                    ) {
+                        self.gpu_cache.skip(img.path);
+                    } else {
+                        native_preload.chunked_upload_queue.queue_prepacked(
+                            img.path,
+                            img.width,
+                            img.height,
+                            img.num_layers,
+                            img.layers,
+                            img.encoding,
+                            &gpu_ctx.device,
+                        );
+                    }
                 }
                 DecodeResult::Error(err) => {
                     log::warn!("Decode error for {:?}: {}", err.path, err.error);
@@ -2356,8 +3955,8 @@ impl HvatApp {
                 completed.texture,
                 completed.width,
                 completed.height,
-                completed.num_bands,
                 completed.num_layers,
+                completed.encoding,
                 pipeline.band_texture_layout(),
             );
         }
@@ -2384,14 +3983,29 @@ impl HvatApp {
                 continue;
             }
 
-            // Load image data and send to decoder
-            if let Some(data) = self
-                .project
-                .as_ref()
-                .and_then(|p| p.get_image_data(path).ok())
-            {
+            // Load image data (plus sidecar files) and send to decoder
+            let Some(project) = self.project.as_ref() else {
+                break;
+            };
+            let processors =
+                match project.processors(path, &self.loader_options, &self.preprocessing) {
+                    Ok(processors) => processors,
+                    Err(e) => {
+                        log::warn!("Not preloading {:?}: {}", path, e);
+                        continue;
+                    }
+                };
+            if let Ok(data) = project.get_image_data(path) {
+                let sidecars = project.sidecar_files(path);
                 log::debug!("Requesting decode for {:?}", path);
-                native_preload.decoder.request_decode(path.clone(), data);
+                native_preload.decoder.request_decode(
+                    path.clone(),
+                    data,
+                    sidecars,
+                    self.loader_options.clone(),
+                    processors,
+                    gpu_ctx.band_format,
+                );
             }
         }
 
@@ -2438,12 +4052,11 @@ impl HvatApp {
         let gpu_ctx = resources.gpu_context();
 
         // Use unified API that works for both WASM (in-memory) and native (filesystem)
-        let hyper_result = self
-            .project
-            .as_ref()
-            .unwrap()
-            .get_image_data(&path)
-            .and_then(|data| HyperspectralData::from_bytes(&data));
+        let hyper_result = self.project.as_ref().unwrap().load_hyperspectral(
+            &path,
+            &self.loader_options,
+            &self.preprocessing,
+        );
 
         match hyper_result {
             Ok(hyper) => {
@@ -2454,6 +4067,14 @@ impl HvatApp {
                     &hyper,
                     pipeline.band_texture_layout(),
                 );
+                let image_data = self.image_data_store.get_or_create(&path);
+                if hyper.geotransform.is_some() {
+                    image_data.geotransform = hyper.geotransform;
+                }
+                image_data.band_histograms = Arc::new(band_histograms(&hyper.bands));
+                image_data.band_info = hyper.band_info;
+                image_data.calibration = hyper.calibration;
+                image_data.load_warnings = hyper.warnings;
             }
             Err(e) => {
                 log::warn!("Failed to load image for preloading {:?}: {}", path, e);
@@ -2474,6 +4095,26 @@ impl HvatApp {
         let x = event.image_x;
         let y = event.image_y;
 
+        // While picking the similarity reference, left clicks pick it
+        // instead of drawing
+        if self.similarity_picking && event.button == MouseButton::Left {
+            if event.kind == PointerEventKind::Click && x >= 0.0 && y >= 0.0 {
+                self.use_pixel_as_reference(x as u32, y as u32);
+            }
+            return;
+        }
+
+        // Alt+click pins the spectrum of the clicked pixel
+        if event.modifiers.alt
+            && event.button == MouseButton::Left
+            && event.kind == PointerEventKind::Click
+        {
+            if x >= 0.0 && y >= 0.0 {
+                self.pin_spectrum(Some((x as u32, y as u32)));
+            }
+            return;
+        }
+
         log::trace!(
             "ImagePointer: tool={:?}, pos=({:.1}, {:.1}), kind={:?}, button={:?}",
             self.selected_tool,
@@ -2487,7 +4128,9 @@ impl HvatApp {
         if event.button == MouseButton::Right && event.kind == PointerEventKind::Click {
             // Check if we clicked on a polygon vertex (for vertex removal - priority action)
             if self.selected_tool == AnnotationTool::Select {
-                if self.try_remove_polygon_vertex(x, y) {
+                if self.try_remove_polygon_vertex(x, y)
+                    || self.try_toggle_keypoint_visibility(x, y)
+                {
                     return;
                 }
             }
@@ -2524,17 +4167,34 @@ impl HvatApp {
             AnnotationTool::Select => {
                 self.handle_select_tool(x, y, event.kind);
             }
-            AnnotationTool::BoundingBox => {
+            AnnotationTool::BoundingBox | AnnotationTool::RotatedBox => {
                 self.handle_bounding_box_draw(x, y, event.kind);
             }
             AnnotationTool::Polygon => {
                 self.handle_polygon_draw(x, y, event.kind);
             }
+            AnnotationTool::Polyline => {
+                self.handle_polyline_draw(x, y, event.kind);
+            }
             AnnotationTool::Point => {
                 if event.kind == PointerEventKind::DragStart {
                     self.create_point_annotation(x, y);
                 }
             }
+            AnnotationTool::Keypoints => {
+                if event.kind == PointerEventKind::DragStart {
+                    // Shift+click places the keypoint as occluded
+                    let visibility = if event.modifiers.shift {
+                        KEYPOINT_OCCLUDED
+                    } else {
+                        KEYPOINT_VISIBLE
+                    };
+                    self.place_keypoint(Keypoint::new(x, y, visibility));
+                }
+            }
+            AnnotationTool::Brush | AnnotationTool::Eraser => {
+                self.handle_brush(x, y, event.kind);
+            }
             #[cfg(feature = "sam2")]
             AnnotationTool::SAM2Segment => {
                 self.handle_sam2_tool(x, y, event.kind, event.modifiers);
@@ -2901,7 +4561,7 @@ impl HvatApp {
                 .annotations
                 .iter()
                 .enumerate()
-                .find(|(_, ann)| ann.selected && ann.shape.is_polygon())
+                .find(|(_, ann)| ann.selected && ann.shape.has_vertices())
                 .map(|(idx, ann)| (idx, ann.id, ann.shape.clone()))
         };
 
@@ -2934,7 +4594,7 @@ impl HvatApp {
                         "Removed vertex {} from polygon {} (now has {} vertices)",
                         vertex_idx,
                         ann_id,
-                        ann.shape.polygon_vertices().map(|v| v.len()).unwrap_or(0)
+                        ann.shape.vertex_count()
                     );
                     return true;
                 }
@@ -2968,7 +4628,7 @@ impl HvatApp {
         None
     }
 
-    /// Handle bounding box drawing.
+    /// Handle bounding box drawing (axis-aligned or rotated, per the selected tool).
     fn handle_bounding_box_draw(&mut self, x: f32, y: f32, kind: hvat_ui::PointerEventKind) {
         use hvat_ui::PointerEventKind;
 
@@ -2992,11 +4652,20 @@ impl HvatApp {
                     ann.selected = false;
                 }
                 // Start new bounding box
-                image_data.drawing_state = DrawingState::BoundingBox {
-                    start_x: x,
-                    start_y: y,
-                    current_x: x,
-                    current_y: y,
+                image_data.drawing_state = if self.selected_tool == AnnotationTool::RotatedBox {
+                    DrawingState::RotatedBox {
+                        start_x: x,
+                        start_y: y,
+                        current_x: x,
+                        current_y: y,
+                    }
+                } else {
+                    DrawingState::BoundingBox {
+                        start_x: x,
+                        start_y: y,
+                        current_x: x,
+                        current_y: y,
+                    }
                 };
                 log::info!("BoundingBox: STARTED at ({:.1}, {:.1})", x, y);
             }
@@ -3006,6 +4675,11 @@ impl HvatApp {
                     current_x,
                     current_y,
                     ..
+                }
+                | DrawingState::RotatedBox {
+                    current_x,
+                    current_y,
+                    ..
                 } = &mut image_data.drawing_state
                 {
                     *current_x = x;
@@ -3123,45 +4797,460 @@ impl HvatApp {
         }
     }
 
-    /// Finalize the current polygon drawing and create an annotation.
+    /// Handle polyline drawing.
+    fn handle_polyline_draw(&mut self, x: f32, y: f32, kind: hvat_ui::PointerEventKind) {
+        use hvat_ui::PointerEventKind;
+
+        // Polyline only responds to DragStart (click to add vertex)
+        if kind != PointerEventKind::DragStart {
+            return;
+        }
+
+        // Clicking the last vertex again finishes the open path
+        let zoom = self.viewer_state.zoom;
+        let close_threshold = POLYGON_CLOSE_THRESHOLD / zoom;
+        let width = self.polyline_width_slider.value;
+
+        let path = self.current_image_path();
+        let image_data = self.image_data_store.get_or_create(&path);
+
+        if let DrawingState::Polyline { vertices, .. } = &image_data.drawing_state {
+            if let Some((last_x, last_y)) = vertices.last() {
+                let dist = ((x - last_x).powi(2) + (y - last_y).powi(2)).sqrt();
+                if vertices.len() >= MIN_POLYLINE_VERTICES && dist < close_threshold {
+                    self.finalize_polygon();
+                    return;
+                }
+            }
+        }
+
+        // Need to re-borrow after potential finalize_polygon call
+        let image_data = self.image_data_store.get_or_create(&path);
+
+        match &mut image_data.drawing_state {
+            DrawingState::Polyline { vertices, .. } => {
+                vertices.push((x, y));
+                log::debug!(
+                    "Polyline: added vertex {} at ({:.1}, {:.1})",
+                    vertices.len(),
+                    x,
+                    y
+                );
+            }
+            _ => {
+                // Deselect all annotations when starting to draw
+                for ann in &mut image_data.annotations {
+                    ann.selected = false;
+                }
+                image_data.drawing_state = DrawingState::Polyline {
+                    vertices: vec![(x, y)],
+                    width,
+                };
+                log::info!("Polyline: started at ({:.1}, {:.1})", x, y);
+            }
+        }
+    }
+
+    /// Handle brush and eraser strokes on mask annotations.
+    ///
+    /// The brush paints into the selected mask, or starts a new one; the
+    /// eraser works on the selected mask, or the mask under the cursor.
+    fn handle_brush(&mut self, x: f32, y: f32, kind: hvat_ui::PointerEventKind) {
+        use hvat_ui::PointerEventKind;
+
+        let path = self.current_image_path();
+        let radius = self.brush_radius_slider.value;
+        let limit = self.image_size;
+
+        match kind {
+            PointerEventKind::DragStart => {
+                let erase = self.selected_tool == AnnotationTool::Eraser;
+                let target = {
+                    let image_data = self.image_data_store.get(&path);
+                    let masks = || {
+                        image_data
+                            .annotations
+                            .iter()
+                            .rev()
+                            .filter(|a| a.shape.is_mask())
+                    };
+                    masks()
+                        .find(|a| a.selected)
+                        .or_else(|| masks().find(|a| erase && a.shape.contains_point(x, y)))
+                        .map(|a| a.id)
+                };
+                if erase && target.is_none() {
+                    log::debug!("Eraser: no mask at ({:.1}, {:.1})", x, y);
+                    return;
+                }
+
+                // The whole stroke is one undo step
+                self.push_annotation_undo_point();
+
+                let image_data = self.image_data_store.get_or_create(&path);
+                let annotation_id = match target {
+                    Some(id) => id,
+                    None => {
+                        // Deselect all annotations; the new mask stays selected
+                        // so later strokes extend it
+                        for ann in &mut image_data.annotations {
+                            ann.selected = false;
+                        }
+                        let id = image_data.next_annotation_id;
+                        let mut annotation = Annotation::new(
+                            id,
+                            AnnotationShape::Mask {
+                                mask: RleMask::default(),
+                                crowd: false,
+                            },
+                            self.selected_category,
+                        );
+                        annotation.selected = true;
+                        image_data.next_annotation_id += 1;
+                        image_data.annotations.push(annotation);
+                        log::info!("Brush: started mask annotation {}", id);
+                        id
+                    }
+                };
+                image_data.drawing_state = DrawingState::Brush {
+                    annotation_id,
+                    last_x: x,
+                    last_y: y,
+                    erase,
+                };
+                Self::paint_mask_stroke(
+                    &mut image_data.annotations,
+                    annotation_id,
+                    (x, y),
+                    (x, y),
+                    radius,
+                    !erase,
+                    limit,
+                );
+                self.auto_save.mark_dirty();
+            }
+            PointerEventKind::DragMove => {
+                let image_data = self.image_data_store.get_or_create(&path);
+                if let DrawingState::Brush {
+                    annotation_id,
+                    last_x,
+                    last_y,
+                    erase,
+                } = &mut image_data.drawing_state
+                {
+                    Self::paint_mask_stroke(
+                        &mut image_data.annotations,
+                        *annotation_id,
+                        (*last_x, *last_y),
+                        (x, y),
+                        radius,
+                        !*erase,
+                        limit,
+                    );
+                    *last_x = x;
+                    *last_y = y;
+                    self.auto_save.mark_dirty();
+                }
+            }
+            PointerEventKind::DragEnd => {
+                let image_data = self.image_data_store.get_or_create(&path);
+                if let DrawingState::Brush { annotation_id, .. } = image_data.drawing_state {
+                    // Drop the mask if the stroke erased all of it
+                    image_data.annotations.retain(|a| {
+                        a.id != annotation_id
+                            || !matches!(&a.shape, AnnotationShape::Mask { mask, .. } if mask.is_empty())
+                    });
+                    log::debug!("Brush: stroke on annotation {} finished", annotation_id);
+                }
+                image_data.drawing_state = DrawingState::Idle;
+            }
+            PointerEventKind::Click => {
+                // A click paints a single dab
+                self.handle_brush(x, y, PointerEventKind::DragStart);
+                self.handle_brush(x, y, PointerEventKind::DragEnd);
+            }
+        }
+    }
+
+    /// Paint (or erase) one stroke segment on the mask annotation with the given ID.
+    fn paint_mask_stroke(
+        annotations: &mut [Annotation],
+        annotation_id: AnnotationId,
+        from: (f32, f32),
+        to: (f32, f32),
+        radius: f32,
+        value: bool,
+        limit: (u32, u32),
+    ) {
+        if let Some(AnnotationShape::Mask { mask, .. }) = annotations
+            .iter_mut()
+            .find(|a| a.id == annotation_id)
+            .map(|a| &mut a.shape)
+        {
+            mask.paint_stroke(from, to, radius, value, limit);
+        }
+    }
+
+    /// Place the next keypoint of the selected category's template, starting a
+    /// new instance if none is in progress. The annotation is created once
+    /// every keypoint has been placed or skipped.
+    fn place_keypoint(&mut self, keypoint: Keypoint) {
+        let count = self
+            .categories
+            .iter()
+            .find(|c| c.id == self.selected_category)
+            .map_or(0, |c| c.keypoints.len());
+        if count == 0 {
+            log::warn!(
+                "Keypoints: category {} has no keypoint template",
+                self.selected_category
+            );
+            return;
+        }
+
+        let path = self.current_image_path();
+        let image_data = self.image_data_store.get_or_create(&path);
+
+        let placed = match &mut image_data.drawing_state {
+            DrawingState::Keypoints {
+                keypoints,
+                count: template_count,
+            } if *template_count == count => {
+                keypoints.push(keypoint);
+                keypoints.len()
+            }
+            _ => {
+                // Deselect all annotations when starting to draw
+                for ann in &mut image_data.annotations {
+                    ann.selected = false;
+                }
+                image_data.drawing_state = DrawingState::Keypoints {
+                    keypoints: vec![keypoint],
+                    count,
+                };
+                log::info!("Keypoints: started instance with {} keypoints", count);
+                1
+            }
+        };
+        log::debug!(
+            "Keypoints: {} keypoint {}/{}",
+            if keypoint.is_labeled() {
+                "placed"
+            } else {
+                "skipped"
+            },
+            placed,
+            count
+        );
+
+        if placed >= count {
+            self.finalize_polygon();
+        }
+    }
+
+    /// Toggle a keypoint of the selected annotation between visible and
+    /// occluded. Returns true if a keypoint was hit.
+    fn try_toggle_keypoint_visibility(&mut self, x: f32, y: f32) -> bool {
+        use crate::model::AnnotationHandle;
+
+        let path = self.current_image_path();
+        let hit_radius = self.scaled_hit_radius();
+
+        // Find the selected keypoints annotation
+        let selected_keypoints_info = {
+            let image_data = self.image_data_store.get(&path);
+            image_data
+                .annotations
+                .iter()
+                .enumerate()
+                .find(|(_, ann)| ann.selected && ann.shape.is_keypoints())
+                .map(|(idx, ann)| (idx, ann.shape.clone()))
+        };
+
+        let Some((ann_idx, shape)) = selected_keypoints_info else {
+            return false;
+        };
+
+        // Hit-test against the annotation's handles - only care about keypoints
+        let Some(AnnotationHandle::Keypoint(kp_idx)) = shape.hit_test_handle(x, y, hit_radius)
+        else {
+            return false;
+        };
+
+        // Push undo point before modifying
+        self.push_annotation_undo_point();
+
+        let image_data = self.image_data_store.get_or_create(&path);
+        if let Some(AnnotationShape::Keypoints { keypoints, .. }) = image_data
+            .annotations
+            .get_mut(ann_idx)
+            .map(|ann| &mut ann.shape)
+        {
+            if let Some(kp) = keypoints.get_mut(kp_idx) {
+                kp.visibility = if kp.visibility == KEYPOINT_VISIBLE {
+                    KEYPOINT_OCCLUDED
+                } else {
+                    KEYPOINT_VISIBLE
+                };
+                log::info!(
+                    "Keypoint {} is now {}",
+                    kp_idx + 1,
+                    if kp.visibility == KEYPOINT_VISIBLE {
+                        "visible"
+                    } else {
+                        "occluded"
+                    }
+                );
+                self.auto_save.mark_dirty();
+            }
+        }
+        true
+    }
+
+    /// Fill the keypoint template inputs from the selected category.
+    fn load_keypoint_template_inputs(&mut self) {
+        let (names, skeleton) = self
+            .categories
+            .iter()
+            .find(|c| c.id == self.selected_category)
+            .map(|c| (c.keypoints.join(", "), format_skeleton(&c.skeleton)))
+            .unwrap_or_default();
+        self.keypoint_names_input = names;
+        self.keypoint_skeleton_input = skeleton;
+    }
+
+    /// Apply the typed keypoint template to the selected category.
+    fn apply_keypoint_template(&mut self) {
+        let names = parse_keypoint_names(&self.keypoint_names_input);
+        let skeleton = match parse_skeleton(&self.keypoint_skeleton_input, names.len()) {
+            Ok(skeleton) => skeleton,
+            Err(e) => {
+                log::warn!("Keypoint template: {}", e);
+                return;
+            }
+        };
+
+        let id = self.selected_category;
+        let Some(cat) = self.categories.iter_mut().find(|c| c.id == id) else {
+            return;
+        };
+        log::info!(
+            "Category '{}' keypoint template: {} keypoints, {} skeleton edges",
+            cat.name,
+            names.len(),
+            skeleton.len()
+        );
+        cat.set_keypoints(names, skeleton);
+
+        // An instance in progress was placed against the old template
+        let path = self.current_image_path();
+        let image_data = self.image_data_store.get_or_create(&path);
+        if matches!(image_data.drawing_state, DrawingState::Keypoints { .. }) {
+            image_data.drawing_state = DrawingState::Idle;
+        }
+
+        self.load_keypoint_template_inputs();
+        self.auto_save.mark_dirty();
+        self.auto_save_config();
+    }
+
+    /// Finalize the current polygon, polyline or keypoint drawing and create an annotation.
     fn finalize_polygon(&mut self) {
         let path = self.current_image_path();
 
-        // Check if we can create a polygon and push undo point first
-        let should_create = {
-            let image_data = self.image_data_store.get(&path);
-            if let DrawingState::Polygon { vertices } = &image_data.drawing_state {
-                vertices.len() >= MIN_POLYGON_VERTICES
-            } else {
-                false
-            }
+        // Check if we can create a shape and push undo point first
+        let shape = {
+            let image_data = self.image_data_store.get(&path);
+            match &image_data.drawing_state {
+                DrawingState::Polygon { .. }
+                | DrawingState::Polyline { .. }
+                | DrawingState::Keypoints { .. } => image_data.drawing_state.to_shape(),
+                _ => None,
+            }
+        };
+
+        if shape.is_some() {
+            // Push undo point before creating annotation
+            self.push_annotation_undo_point();
+        }
+
+        let image_data = self.image_data_store.get_or_create(&path);
+
+        if let Some(shape) = shape {
+            let (kind, vertex_count) = match &shape {
+                AnnotationShape::Polyline { vertices, .. } => ("Polyline", vertices.len()),
+                AnnotationShape::Polygon { vertices } => ("Polygon", vertices.len()),
+                AnnotationShape::Keypoints { keypoints, .. } => (
+                    "Keypoints",
+                    keypoints.iter().filter(|k| k.is_labeled()).count(),
+                ),
+                _ => ("Shape", 0),
+            };
+            let annotation =
+                Annotation::new(image_data.next_annotation_id, shape, self.selected_category);
+            image_data.next_annotation_id += 1;
+            log::info!(
+                "{} created with {} vertices (total: {})",
+                kind,
+                vertex_count,
+                image_data.annotations.len() + 1
+            );
+            image_data.annotations.push(annotation);
+            self.auto_save.mark_dirty();
+        }
+        image_data.drawing_state = DrawingState::Idle;
+    }
+
+    /// Convert an annotation into a raster mask (`to_mask`) or a mask into
+    /// a polygon traced from its contours (a multipolygon if the mask has
+    /// several parts or holes).
+    fn convert_annotation_shape(&mut self, annotation_id: AnnotationId, to_mask: bool) {
+        let path = self.current_image_path();
+        let (width, height) = self.image_size;
+        let Some(shape) = self
+            .image_data_store
+            .get(&path)
+            .annotations
+            .into_iter()
+            .find(|a| a.id == annotation_id)
+            .map(|a| a.shape)
+        else {
+            return;
+        };
+
+        let converted = match (&shape, to_mask) {
+            (_, true) => shape.to_mask(width, height),
+            (AnnotationShape::Mask { mask, .. }, false) => AnnotationShape::from_rings(
+                crate::sam2::extract_mask_contours(mask, MASK_CONTOUR_EPSILON),
+            ),
+            _ => None,
+        };
+        let Some(converted) = converted else {
+            log::warn!(
+                "Cannot convert annotation {} to a {}",
+                annotation_id,
+                if to_mask { "mask" } else { "polygon" }
+            );
+            return;
         };
 
-        if should_create {
-            // Push undo point before creating annotation
-            self.push_annotation_undo_point();
-        }
+        // Push undo point before modifying
+        self.push_annotation_undo_point();
 
         let image_data = self.image_data_store.get_or_create(&path);
-
-        if let DrawingState::Polygon { vertices } = &image_data.drawing_state.clone() {
-            if vertices.len() >= MIN_POLYGON_VERTICES {
-                let shape = AnnotationShape::Polygon {
-                    vertices: vertices.clone(),
-                };
-                let annotation =
-                    Annotation::new(image_data.next_annotation_id, shape, self.selected_category);
-                image_data.next_annotation_id += 1;
-                log::info!(
-                    "Polygon created with {} vertices (total: {})",
-                    vertices.len(),
-                    image_data.annotations.len() + 1
-                );
-                image_data.annotations.push(annotation);
-                self.auto_save.mark_dirty();
-            }
+        if let Some(ann) = image_data
+            .annotations
+            .iter_mut()
+            .find(|a| a.id == annotation_id)
+        {
+            ann.shape = converted;
+            self.auto_save.mark_dirty();
+            log::info!(
+                "Converted annotation {} to a {}",
+                annotation_id,
+                if to_mask { "mask" } else { "polygon" }
+            );
         }
-        image_data.drawing_state = DrawingState::Idle;
     }
 
     /// Create a point annotation.
@@ -3212,7 +5301,7 @@ impl HvatApp {
             self.tags.len()
         );
 
-        let data = ProjectData::from_app_state(
+        let mut data = ProjectData::from_app_state(
             folder,
             &image_paths,
             &self.categories,
@@ -3221,6 +5310,19 @@ impl HvatApp {
             |path| self.get_image_dimensions(path),
         );
 
+        data.metadata.preprocessing = self.preprocessing.clone();
+
+        // Record which bands (and wavelengths) make up the current composite
+        if self.num_bands > 0 {
+            let info = self.current_band_info();
+            let (red, green, blue) = self.band_selection;
+            data.metadata.composite = Some(CompositeEntry {
+                red: CompositeBand::from_band(red, &info),
+                green: CompositeBand::from_band(green, &info),
+                blue: CompositeBand::from_band(blue, &info),
+            });
+        }
+
         log::info!(
             "to_project_data: exported {} images with {} total annotations",
             data.images.len(),
@@ -3301,6 +5403,13 @@ impl HvatApp {
             self.image_data_store = ImageDataStore::new();
         }
 
+        // The preprocessing chain belongs to the project; reload images with it
+        if !merge && data.metadata.preprocessing != self.preprocessing {
+            self.preprocessing = data.metadata.preprocessing.clone();
+            self.gpu_cache.clear();
+            self.pending_image_load = true;
+        }
+
         // Apply categories
         for cat_entry in &data.categories {
             let exists = self.categories.iter().any(|c| c.id == cat_entry.id);
@@ -3334,6 +5443,16 @@ impl HvatApp {
                 .selected_tag_ids
                 .extend(image_entry.tag_ids.clone());
 
+            if image_entry.geotransform.is_some() {
+                image_data.geotransform = image_entry.geotransform;
+            }
+            if !image_entry.bands.is_empty() {
+                image_data.band_info = image_entry.bands.clone();
+            }
+            if image_entry.calibration.is_some() {
+                image_data.calibration = image_entry.calibration.clone();
+            }
+
             // Update next_annotation_id
             if let Some(max_id) = image_data.annotations.iter().map(|a| a.id).max() {
                 image_data.next_annotation_id = max_id + 1;
@@ -3401,7 +5520,7 @@ impl HvatApp {
                         log::info!("SAM2 state: Ready (models already loaded)");
 
                         // Auto-start encoding for current image if one is loaded
-                        if self.project.is_some() && self.hyperspectral.is_some() {
+                        if self.project.is_some() && self.gpu_state.is_some() {
                             log::info!("SAM2: Auto-starting encoding after enable");
                             self.handle_sam2_message_impl(SAM2Message::StartEncoding);
                         }
@@ -3522,7 +5641,7 @@ impl HvatApp {
                 log::info!("SAM2 models loaded successfully");
 
                 // Auto-start encoding for current image if one is loaded
-                if self.project.is_some() && self.hyperspectral.is_some() {
+                if self.project.is_some() && self.gpu_state.is_some() {
                     log::info!("SAM2: Auto-starting encoding after model load");
                     self.handle_sam2_message_impl(SAM2Message::StartEncoding);
                 }
@@ -3561,10 +5680,11 @@ impl HvatApp {
                     // the correct image data (GPU cache path may have skipped updating
                     // self.hyperspectral when switching images)
                     let hyper = if let Some(ref project) = self.project {
-                        match project
-                            .get_image_data(&path)
-                            .and_then(|data| HyperspectralData::from_bytes(&data))
-                        {
+                        match project.load_hyperspectral(
+                            &path,
+                            &self.loader_options,
+                            &self.preprocessing,
+                        ) {
                             Ok(h) => h,
                             Err(e) => {
                                 log::error!("Failed to load image data for SAM2: {}", e);
@@ -3664,10 +5784,11 @@ impl HvatApp {
 
                     // Reload hyperspectral data fresh from project
                     let hyper = if let Some(ref project) = self.project {
-                        match project
-                            .get_image_data(&path)
-                            .and_then(|data| HyperspectralData::from_bytes(&data))
-                        {
+                        match project.load_hyperspectral(
+                            &path,
+                            &self.loader_options,
+                            &self.preprocessing,
+                        ) {
                             Ok(h) => h,
                             Err(e) => {
                                 log::error!("Failed to load image data for SAM2: {}", e);
@@ -3944,52 +6065,9 @@ impl HvatApp {
                 }
             }
 
-            SAM2Message::AcceptMask => {
-                if let SAM2State::Active { session } = &self.sam2_state {
-                    if let Some(mask) = &session.mask {
-                        if !mask.contour.is_empty() {
-                            // Use the session's image path, not current_image_path()
-                            // This ensures the annotation is saved to the correct image
-                            // even if the user navigated away during mask computation
-                            let session_path = session.image_path.clone();
-                            let contour = mask.contour.clone();
-                            let category = self.selected_category;
-
-                            log::info!(
-                                "SAM2 accepting mask as polygon with {} vertices for {:?}",
-                                contour.len(),
-                                session_path
-                            );
-
-                            // Push undo point before creating annotation
-                            self.push_annotation_undo_point();
-
-                            // Create polygon annotation from mask contour
-                            if let Some(image_data) = self.image_data_store.get_mut(&session_path) {
-                                let annotation = crate::model::Annotation::new(
-                                    image_data.next_annotation_id,
-                                    crate::model::AnnotationShape::Polygon { vertices: contour },
-                                    category,
-                                );
-                                image_data.next_annotation_id += 1;
-                                image_data.annotations.push(annotation);
-                                self.auto_save.mark_dirty();
-                            }
+            SAM2Message::AcceptMask => self.accept_sam2_mask(false),
 
-                            // Clear the session prompts and history for next segmentation
-                            if let SAM2State::Active { session } = &mut self.sam2_state {
-                                session.prompts.clear();
-                                session.mask = None;
-                                session.clear_prompts_history();
-                            }
-                        } else {
-                            log::warn!("SAM2 mask has no contour, cannot accept");
-                        }
-                    } else {
-                        log::warn!("SAM2 has no mask to accept");
-                    }
-                }
-            }
+            SAM2Message::AcceptMaskAsRaster => self.accept_sam2_mask(true),
 
             SAM2Message::CancelSession => {
                 if let SAM2State::Active { session } = &mut self.sam2_state {
@@ -4002,6 +6080,72 @@ impl HvatApp {
         }
     }
 
+    /// Accepts the current SAM2 mask as an annotation: a polygon traced
+    /// from its contour, or the raster mask itself when `as_raster` is set.
+    #[cfg(feature = "sam2")]
+    fn accept_sam2_mask(&mut self, as_raster: bool) {
+        use crate::sam2::SAM2State;
+
+        let SAM2State::Active { session } = &self.sam2_state else {
+            return;
+        };
+        let Some(mask) = &session.mask else {
+            log::warn!("SAM2 has no mask to accept");
+            return;
+        };
+
+        // Use the session's image path, not current_image_path()
+        // This ensures the annotation is saved to the correct image
+        // even if the user navigated away during mask computation
+        let session_path = session.image_path.clone();
+        let shape = if as_raster {
+            let rle = mask.to_rle();
+            if rle.is_empty() {
+                log::warn!("SAM2 mask is empty, cannot accept");
+                return;
+            }
+            log::info!(
+                "SAM2 accepting mask as raster with {} pixels for {:?}",
+                rle.area(),
+                session_path
+            );
+            AnnotationShape::Mask {
+                mask: rle,
+                crowd: false,
+            }
+        } else {
+            // Masks with several parts or holes become a multipolygon
+            let Some(shape) = AnnotationShape::from_rings(mask.contours.clone()) else {
+                log::warn!("SAM2 mask has no contour, cannot accept");
+                return;
+            };
+            log::info!(
+                "SAM2 accepting mask as polygon with {} rings for {:?}",
+                mask.contours.len(),
+                session_path
+            );
+            shape
+        };
+        let category = self.selected_category;
+
+        // Push undo point before creating annotation
+        self.push_annotation_undo_point();
+
+        if let Some(image_data) = self.image_data_store.get_mut(&session_path) {
+            let annotation = Annotation::new(image_data.next_annotation_id, shape, category);
+            image_data.next_annotation_id += 1;
+            image_data.annotations.push(annotation);
+            self.auto_save.mark_dirty();
+        }
+
+        // Clear the session prompts and history for next segmentation
+        if let SAM2State::Active { session } = &mut self.sam2_state {
+            session.prompts.clear();
+            session.mask = None;
+            session.clear_prompts_history();
+        }
+    }
+
     /// Runs the SAM2 decoder to compute a new mask from current prompts.
     #[cfg(feature = "sam2")]
     fn run_sam2_decoder(&mut self) {
@@ -4065,10 +6209,10 @@ impl HvatApp {
                 Ok(mask) => {
                     let elapsed = start.elapsed();
                     log::info!(
-                        "SAM2: Mask computed in {:?}, score={:.2}, {} contour vertices",
+                        "SAM2: Mask computed in {:?}, score={:.2}, {} contour rings",
                         elapsed,
                         mask.score,
-                        mask.contour.len()
+                        mask.contours.len()
                     );
 
                     // Update the session with the new mask
@@ -4343,6 +6487,9 @@ impl Application for HvatApp {
             Message::FoldersSectionToggled(state) => {
                 self.folders_section_collapsed = state;
             }
+            Message::LoadingSectionToggled(state) => {
+                self.loading_section_collapsed = state;
+            }
             Message::PerformanceSectionToggled(state) => {
                 self.performance_section_collapsed = state;
             }
@@ -4378,10 +6525,164 @@ impl Application for HvatApp {
                     self.auto_save_config();
                 }
             }
+            Message::NpzCubeKeyChanged(text, state) => {
+                // Only save when focus is lost (not on every keystroke)
+                let was_focused = self.npz_cube_key_state.is_focused;
+                let now_focused = state.is_focused;
+                self.loader_options.npz_cube_key = (!text.is_empty()).then_some(text);
+                self.npz_cube_key_state = state;
+                if was_focused && !now_focused {
+                    self.auto_save_config();
+                }
+            }
+            Message::NpyLayoutChanged(layout) => {
+                log::info!(
+                    "Default .npy layout: {}",
+                    layout.map_or("auto", |l| l.name())
+                );
+                self.loader_options.npy_layout = layout;
+                self.apply_loader_options_change();
+            }
+            Message::ProjectNpyLayoutChanged(layout) => {
+                if let Some(pattern) = self.project_layout_pattern() {
+                    log::info!(
+                        ".npy layout for {}: {}",
+                        pattern,
+                        layout.map_or("default", |l| l.name())
+                    );
+                    self.loader_options.set_npy_layout_rule(&pattern, layout);
+                    self.apply_loader_options_change();
+                }
+            }
+            Message::NpyLayoutRulePatternChanged(text, state) => {
+                self.npy_rule_pattern = text;
+                self.npy_rule_pattern_state = state;
+            }
+            Message::AddNpyLayoutRule(layout) => {
+                let pattern = self.npy_rule_pattern.trim().to_string();
+                if !pattern.is_empty() {
+                    self.loader_options
+                        .set_npy_layout_rule(&pattern, Some(layout));
+                    self.npy_rule_pattern.clear();
+                    self.apply_loader_options_change();
+                }
+            }
+            Message::RemoveNpyLayoutRule(idx) => {
+                if idx < self.loader_options.npy_layout_rules.len() {
+                    self.loader_options.npy_layout_rules.remove(idx);
+                    self.apply_loader_options_change();
+                }
+            }
+            Message::LoadBandRangeChanged(text, state) => {
+                // Only apply when focus is lost (not on every keystroke)
+                let was_focused = self.load_band_range_state.is_focused;
+                let now_focused = state.is_focused;
+                self.load_band_range = text;
+                self.load_band_range_state = state;
+                if was_focused && !now_focused {
+                    match LoadSubset::parse_band_range(&self.load_band_range) {
+                        Ok(bands) if bands != self.loader_options.subset.bands => {
+                            log::info!("Load band range: {:?}", bands);
+                            self.loader_options.subset.bands = bands;
+                            self.apply_loader_options_change();
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            log::warn!("{}", e);
+                            self.load_band_range = self.loader_options.subset.band_range_text();
+                        }
+                    }
+                }
+            }
+            Message::LoadBandStrideChanged(state) => {
+                // Only reload once the drag ends
+                let dragging = state.drag.is_dragging();
+                self.load_band_stride_slider = state;
+                let stride =
+                    (self.load_band_stride_slider.value as usize).clamp(1, MAX_LOAD_BAND_STRIDE);
+                if !dragging && stride != self.loader_options.subset.band_stride {
+                    log::info!("Load band stride: {}", stride);
+                    self.loader_options.subset.band_stride = stride;
+                    self.apply_loader_options_change();
+                }
+            }
+            Message::LoadBinningChanged(binning) => {
+                if binning != self.loader_options.subset.binning {
+                    log::info!("Load binning: {}x{}", binning, binning);
+                    self.loader_options.subset.binning = binning;
+                    self.apply_loader_options_change();
+                }
+            }
+            Message::CalibrationPatternChanged(text, state) => {
+                self.calibration_pattern = text;
+                self.calibration_pattern_state = state;
+            }
+            Message::CalibrationDarkChanged(text, state) => {
+                self.calibration_dark = text;
+                self.calibration_dark_state = state;
+            }
+            Message::CalibrationWhiteChanged(text, state) => {
+                self.calibration_white = text;
+                self.calibration_white_state = state;
+            }
+            Message::CalibrationGainChanged(text, state) => {
+                self.calibration_gain = text;
+                self.calibration_gain_state = state;
+            }
+            Message::CalibrationOffsetChanged(text, state) => {
+                self.calibration_offset = text;
+                self.calibration_offset_state = state;
+            }
+            Message::AddCalibrationRule(for_project) => {
+                let pattern = if for_project {
+                    self.project_layout_pattern()
+                } else {
+                    let pattern = self.calibration_pattern.trim();
+                    (!pattern.is_empty()).then(|| pattern.to_string())
+                };
+                let factors = parse_factors(&self.calibration_gain).and_then(|gain| {
+                    parse_factors(&self.calibration_offset).map(|offset| (gain, offset))
+                });
+                match (pattern, factors) {
+                    (Some(pattern), Ok((gain, offset))) => {
+                        let reference = |text: &str| {
+                            let text = text.trim();
+                            (!text.is_empty()).then(|| text.to_string())
+                        };
+                        let rule = CalibrationRule {
+                            pattern,
+                            dark: reference(&self.calibration_dark),
+                            white: reference(&self.calibration_white),
+                            gain,
+                            offset,
+                        };
+                        log::info!("Calibration rule: {}", rule.describe());
+                        self.loader_options.set_calibration_rule(rule);
+                        self.calibration_pattern.clear();
+                        self.calibration_dark.clear();
+                        self.calibration_white.clear();
+                        self.calibration_gain.clear();
+                        self.calibration_offset.clear();
+                        self.apply_loader_options_change();
+                    }
+                    (None, _) => log::warn!("Calibration rule needs a pattern or a project"),
+                    (_, Err(e)) => log::warn!("{}", e),
+                }
+            }
+            Message::RemoveCalibrationRule(idx) => {
+                if idx < self.loader_options.calibration_rules.len() {
+                    self.loader_options.calibration_rules.remove(idx);
+                    self.apply_loader_options_change();
+                }
+            }
 
             // Image Viewer
             Message::ViewerChanged(state) => {
                 self.viewer_state = state;
+                // Tiled images render only the visible area
+                if self.gpu_state.as_ref().is_some_and(|s| s.is_tiled()) {
+                    self.needs_gpu_render = true;
+                }
             }
 
             // Left Sidebar - Tools
@@ -4396,6 +6697,9 @@ impl Application for HvatApp {
                     ann.selected = false;
                 }
                 self.selected_tool = tool;
+                if tool == AnnotationTool::Keypoints {
+                    self.load_keypoint_template_inputs();
+                }
                 log::info!("Tool selected: {:?}", tool);
             }
 
@@ -4429,6 +6733,7 @@ impl Application for HvatApp {
                 } else {
                     // No annotation selected - change the default category for new annotations
                     self.selected_category = id;
+                    self.load_keypoint_template_inputs();
                     log::info!("Default category changed to: {}", id);
                 }
             }
@@ -4749,6 +7054,210 @@ impl Application for HvatApp {
                 self.needs_gpu_render = true;
                 log::debug!("Blue band: {}", self.band_selection.2);
             }
+            Message::BandWavelengthChanged(text, state) => {
+                self.band_wavelength_input = text;
+                self.band_wavelength_state = state;
+            }
+            Message::BandWavelengthSubmitted => {
+                self.select_bands_by_wavelength();
+            }
+
+            // Right Sidebar - Band Math
+            Message::BandMathToggled(state) => {
+                self.band_math_collapsed = state;
+            }
+            Message::BandMathModeChanged(mode) => {
+                self.band_math_mode = mode;
+                self.apply_band_math();
+            }
+            Message::BandMathChannelChanged(channel, text, state) => {
+                if channel < 3 {
+                    self.band_math_channels[channel] = text;
+                    self.band_math_channel_states[channel] = state;
+                }
+            }
+            Message::BandMathIndexChanged(text, state) => {
+                self.band_math_index = text;
+                self.band_math_index_state = state;
+            }
+            Message::BandMathApply => {
+                self.apply_band_math();
+            }
+            Message::BandMathNameChanged(text, state) => {
+                self.band_math_name = text;
+                self.band_math_name_state = state;
+            }
+            Message::BandMathSave => {
+                self.save_band_math_preset();
+            }
+            Message::BandMathLoad(index) => {
+                self.load_band_math_preset(index);
+            }
+            Message::BandMathDelete(index) => {
+                if index < self.band_math_presets.len() {
+                    let preset = self.band_math_presets.remove(index);
+                    log::info!("Deleted band math '{}'", preset.name);
+                    self.auto_save_config();
+                }
+            }
+
+            // Right Sidebar - Colormap
+            Message::ColormapToggled(state) => {
+                self.colormap_collapsed = state;
+            }
+            Message::ColormapChanged(colormap) => {
+                if colormap != self.colormap {
+                    self.colormap = colormap;
+                    self.needs_gpu_render = true;
+                }
+            }
+            Message::ColormapReverseToggled => {
+                self.colormap_reversed = !self.colormap_reversed;
+                self.needs_gpu_render = true;
+            }
+            Message::ColormapMinChanged(text, state) => {
+                self.colormap_min = text;
+                self.colormap_min_state = state;
+            }
+            Message::ColormapMaxChanged(text, state) => {
+                self.colormap_max = text;
+                self.colormap_max_state = state;
+            }
+            Message::ColormapRangeSubmitted => {
+                self.submit_colormap_range();
+            }
+            Message::ColormapAutoRange => {
+                self.colormap_range = None;
+                self.colormap_min.clear();
+                self.colormap_max.clear();
+                self.needs_gpu_render = true;
+            }
+
+            // Right Sidebar - Principal Components
+            Message::ComponentsToggled(state) => {
+                self.components_collapsed = state;
+            }
+            Message::ComponentMethodChanged(method) => {
+                if method != self.component_method {
+                    self.component_method = method;
+                    if method.is_some() {
+                        self.true_color_illuminant = None;
+                    }
+                    if method.is_some() && self.band_math_mode != BandMathMode::Off {
+                        self.band_math_mode = BandMathMode::Off;
+                        self.apply_band_math();
+                    }
+                    self.needs_gpu_render = true;
+                }
+            }
+            Message::ComponentChanged(channel, state) => {
+                if let Some(slider) = self.component_sliders.get_mut(channel) {
+                    *slider = state;
+                    self.needs_gpu_render = true;
+                }
+            }
+
+            // Right Sidebar - True Color
+            Message::TrueColorToggled(state) => {
+                self.true_color_collapsed = state;
+            }
+            Message::TrueColorIlluminantChanged(illuminant) => {
+                if illuminant != self.true_color_illuminant {
+                    self.true_color_illuminant = illuminant;
+                    if illuminant.is_some() {
+                        self.component_method = None;
+                    }
+                    if illuminant.is_some() && self.band_math_mode != BandMathMode::Off {
+                        self.band_math_mode = BandMathMode::Off;
+                        self.apply_band_math();
+                    }
+                    self.needs_gpu_render = true;
+                }
+            }
+
+            // Right Sidebar - Similarity Map
+            Message::SimilarityToggled(state) => {
+                self.similarity_collapsed = state;
+            }
+            Message::SimilarityMetricChanged(metric) => {
+                self.similarity_metric = metric;
+                self.similarity_error = None;
+            }
+            Message::SimilarityPickPixel => {
+                self.similarity_picking = !self.similarity_picking;
+            }
+            Message::SimilarityUseAnnotation => {
+                self.use_annotation_as_reference();
+            }
+            Message::SimilarityShowToggled => {
+                self.similarity_shown = !self.similarity_shown;
+                if self.similarity_shown && self.band_math_mode != BandMathMode::Off {
+                    self.band_math_mode = BandMathMode::Off;
+                    self.apply_band_math();
+                }
+                self.needs_gpu_render = true;
+            }
+            Message::SimilarityThresholdChanged(state) => {
+                self.similarity_threshold_slider = state;
+                self.update_similarity_regions();
+            }
+            Message::SimilarityCreateAnnotations => {
+                self.create_similarity_annotations();
+            }
+            Message::SimilarityClear => {
+                self.similarity_reference = None;
+                self.similarity_picking = false;
+                self.similarity_shown = false;
+                self.similarity_map = None;
+                self.similarity_error = None;
+                self.similarity_regions.clear();
+                self.needs_gpu_render = true;
+            }
+
+            // Right Sidebar - Preprocessing
+            Message::PreprocessingToggled(state) => {
+                self.preprocessing_collapsed = state;
+            }
+            Message::PreprocessExcludeChanged(text, state) => {
+                self.preprocess_exclude = text;
+                self.preprocess_exclude_state = state;
+            }
+            Message::PreprocessExcludeSubmitted => {
+                match BandExclusion::parse(&self.preprocess_exclude, false) {
+                    Ok(exclusion) => {
+                        self.preprocess_exclude.clear();
+                        self.add_preprocess_step(PreprocessStep::ExcludeBands(exclusion));
+                    }
+                    Err(e) => self.preprocess_error = Some(e),
+                }
+            }
+            Message::PreprocessSmoothingChanged(text, state) => {
+                self.preprocess_smoothing = text;
+                self.preprocess_smoothing_state = state;
+            }
+            Message::PreprocessSmoothingSubmitted => {
+                let values = self
+                    .preprocess_smoothing
+                    .split([',', ' '])
+                    .filter(|s| !s.is_empty())
+                    .map(str::parse)
+                    .collect::<Result<Vec<usize>, _>>();
+                if let Ok(&[window, order]) = values.as_deref() {
+                    self.add_preprocess_step(PreprocessStep::SavitzkyGolay { window, order });
+                } else {
+                    self.preprocess_error =
+                        Some("Enter the smoothing window and order, e.g. 7, 2".to_string());
+                }
+            }
+            Message::AddPreprocessStep(step) => {
+                self.add_preprocess_step(step);
+            }
+            Message::RemovePreprocessStep(idx) => {
+                if idx < self.preprocessing.steps.len() {
+                    self.preprocessing.steps.remove(idx);
+                    self.apply_preprocessing_change();
+                }
+            }
 
             // Right Sidebar - Adjustments
             Message::AdjustmentsToggled(state) => {
@@ -4770,8 +7279,40 @@ impl Application for HvatApp {
                 self.hue_slider = state;
                 self.needs_gpu_render = true;
             }
+            Message::StretchModeChanged(mode) => {
+                if mode != self.stretch_mode {
+                    self.undo_stack.borrow_mut().push(self.snapshot());
+                    self.stretch_mode = mode;
+                    self.needs_gpu_render = true;
+                    log::debug!("Stretch mode: {:?}", mode);
+                }
+            }
+            Message::StretchKChanged(state) => {
+                self.stretch_k_slider = state;
+                self.needs_gpu_render = true;
+            }
+            Message::HistogramChanged(channel, state) => {
+                let clips = (state.low, state.high);
+                if self.histogram_clips()[channel].is_some_and(|current| current != clips) {
+                    if self.stretch_mode != StretchMode::Manual {
+                        // Start from the current clips so the other channels keep their stretch
+                        self.manual_stretch = self.histogram_clips();
+                        self.stretch_mode = StretchMode::Manual;
+                    }
+                    if self.single_channel_display() {
+                        self.manual_stretch = [Some(clips); 3];
+                    } else {
+                        self.manual_stretch[channel] = Some(clips);
+                    }
+                    self.needs_gpu_render = true;
+                }
+                self.histogram_states[channel] = state;
+            }
             Message::ResetAdjustments => {
                 self.reset_adjustment_sliders();
+                self.stretch_mode = StretchMode::None;
+                self.manual_stretch = [None; 3];
+                self.stretch_k_slider.set_value(DEFAULT_STRETCH_K);
                 self.needs_gpu_render = true;
                 log::info!("Adjustments reset");
             }
@@ -4865,6 +7406,12 @@ impl Application for HvatApp {
             Message::AnnotationsScrolled(state) => {
                 self.annotations_scroll_state = state;
             }
+            Message::AnnotationStatsToggled(state) => {
+                self.annotation_stats_collapsed = state;
+            }
+            Message::AnnotationStatsScrolled(state) => {
+                self.annotation_stats_scroll_state = state;
+            }
             Message::ToggleCategoryFilter(category_id) => {
                 if self.hidden_categories.contains(&category_id) {
                     self.hidden_categories.remove(&category_id);
@@ -4916,7 +7463,9 @@ impl Application for HvatApp {
                 // First check if we're drawing a polygon - undo should remove the last vertex
                 let path = self.current_image_path();
                 let image_data = self.image_data_store.get_or_create(&path);
-                if let DrawingState::Polygon { vertices } = &mut image_data.drawing_state {
+                if let DrawingState::Polygon { vertices } | DrawingState::Polyline { vertices, .. } =
+                    &mut image_data.drawing_state
+                {
                     if vertices.len() > 1 {
                         let removed = vertices.pop();
                         log::info!(
@@ -4932,6 +7481,17 @@ impl Application for HvatApp {
                         return;
                     }
                 }
+                if let DrawingState::Keypoints { keypoints, .. } = &mut image_data.drawing_state {
+                    keypoints.pop();
+                    log::info!(
+                        "Keypoints: undo removed keypoint, {} remaining",
+                        keypoints.len()
+                    );
+                    if keypoints.is_empty() {
+                        image_data.drawing_state = DrawingState::Idle;
+                    }
+                    return;
+                }
 
                 // Unified undo - snapshots may contain slider state, annotation state, or both
                 let current = self.snapshot_with_annotations();
@@ -4951,6 +7511,32 @@ impl Application for HvatApp {
                 }
             }
 
+            // Right Sidebar - Spectrum
+            Message::SpectrumToggled(state) => {
+                self.spectrum_collapsed = state;
+                if !state.is_expanded {
+                    self.hover_pixel = None;
+                }
+            }
+            Message::ImageHover(pixel) => {
+                // Load bands when the cursor enters the image, not on every move
+                if pixel.is_some() && self.hover_pixel.is_none() {
+                    self.ensure_cpu_bands();
+                }
+                self.hover_pixel = pixel;
+            }
+            Message::PinSpectrum(pixel) => {
+                self.pin_spectrum(pixel);
+            }
+            Message::RemovePinnedSpectrum(index) => {
+                if index < self.pinned_spectra.len() {
+                    self.pinned_spectra.remove(index);
+                }
+            }
+            Message::ClearPinnedSpectra => {
+                self.pinned_spectra.clear();
+            }
+
             // Annotation Drawing
             Message::ImagePointer(event) => {
                 self.handle_pointer_event(event);
@@ -4983,6 +7569,47 @@ impl Application for HvatApp {
             Message::FinishPolygon => {
                 self.finalize_polygon();
             }
+            Message::PolylineWidthChanged(state) => {
+                let width = state.value;
+                self.polyline_width_slider = state;
+                // Undo point is pushed by the slider when a change starts
+                let path = self.current_image_path();
+                let image_data = self.image_data_store.get_or_create(&path);
+                let mut changed = false;
+                for ann in &mut image_data.annotations {
+                    if let AnnotationShape::Polyline { width: w, .. } = &mut ann.shape {
+                        if ann.selected && *w != width {
+                            *w = width;
+                            changed = true;
+                        }
+                    }
+                }
+                if let DrawingState::Polyline { width: w, .. } = &mut image_data.drawing_state {
+                    *w = width;
+                }
+                if changed {
+                    self.auto_save.mark_dirty();
+                }
+            }
+            Message::BrushRadiusChanged(state) => {
+                self.brush_radius_slider = state;
+            }
+            Message::SkipKeypoint => {
+                if self.selected_tool == AnnotationTool::Keypoints {
+                    self.place_keypoint(Keypoint::unlabeled());
+                }
+            }
+            Message::KeypointNamesChanged(text, state) => {
+                self.keypoint_names_input = text;
+                self.keypoint_names_input_state = state;
+            }
+            Message::KeypointSkeletonChanged(text, state) => {
+                self.keypoint_skeleton_input = text;
+                self.keypoint_skeleton_input_state = state;
+            }
+            Message::ApplyKeypointTemplate => {
+                self.apply_keypoint_template();
+            }
             Message::ChangeSelectedAnnotationCategory(category_id) => {
                 let path = self.current_image_path();
                 let image_data = self.image_data_store.get_or_create(&path);
@@ -5041,6 +7668,14 @@ impl Application for HvatApp {
                 self.export_dialog_open = false;
                 log::info!("Export dialog closed");
             }
+            Message::ExportAnnotationStats => {
+                self.export_dialog_open = false;
+                self.export_spectral_data(false);
+            }
+            Message::ExportPixelSpectra => {
+                self.export_dialog_open = false;
+                self.export_spectral_data(true);
+            }
             Message::ExportAnnotations(format_id) => {
                 log::info!("Export requested in format: {}", format_id);
                 self.export_dialog_open = false;
@@ -5423,6 +8058,15 @@ impl Application for HvatApp {
                     }
                 }
 
+                // Handle shape conversion between masks and polygons
+                if let Some(ann_id) = self.context_menu_annotation_id {
+                    match item_id.as_str() {
+                        "convert_mask" => self.convert_annotation_shape(ann_id, true),
+                        "convert_polygon" => self.convert_annotation_shape(ann_id, false),
+                        _ => {}
+                    }
+                }
+
                 self.context_menu_annotation_id = None;
             }
 
@@ -5460,6 +8104,10 @@ impl Application for HvatApp {
                 Self::handle_sam2_message_impl(self, sam2_msg);
             }
         }
+
+        self.refresh_annotation_stats();
+        self.refresh_band_math_stats();
+        self.refresh_similarity_map();
     }
 
     fn tick_with_resources(&mut self, resources: &mut Resources<'_>) -> TickResult {
@@ -5528,7 +8176,7 @@ impl Application for HvatApp {
                             needs_rebuild = true;
 
                             // Auto-start encoding for current image if one is loaded
-                            if self.project.is_some() && self.hyperspectral.is_some() {
+                            if self.project.is_some() && self.gpu_state.is_some() {
                                 log::info!("SAM2: Auto-starting encoding after native model load");
                                 self.handle_sam2_message_impl(
                                     crate::sam2::SAM2Message::StartEncoding,
@@ -5570,7 +8218,7 @@ impl Application for HvatApp {
                             needs_rebuild = true;
 
                             // Auto-start encoding for current image if one is loaded
-                            if self.project.is_some() && self.hyperspectral.is_some() {
+                            if self.project.is_some() && self.gpu_state.is_some() {
                                 log::info!("SAM2: Auto-starting encoding after WASM model load");
                                 self.handle_sam2_message_impl(
                                     crate::sam2::SAM2Message::StartEncoding,
@@ -5770,6 +8418,12 @@ impl Application for HvatApp {
             }
         }
 
+        // Upload component images once the worker thread finishes
+        #[cfg(not(target_arch = "wasm32"))]
+        let components_computing = self.poll_component_job();
+        #[cfg(target_arch = "wasm32")]
+        let components_computing = false;
+
         // Load new image if pending
         if self.pending_image_load {
             self.pending_image_load = false;
@@ -5779,6 +8433,9 @@ impl Application for HvatApp {
                     needs_rebuild = true;
                 }
             }
+            self.refresh_annotation_stats();
+            self.refresh_band_math_stats();
+            self.refresh_similarity_map();
             // Don't preload in the same tick - let the frame render first
             // Return appropriate result based on what work was done
             // Note: Tooltip timer takes priority if pending
@@ -5795,8 +8452,9 @@ impl Application for HvatApp {
 
         // Re-render to texture if band selection or adjustments changed
         if self.needs_gpu_render {
-            self.render_to_texture(resources);
+            // Cleared first: rendering sets it again while tiles are missing
             self.needs_gpu_render = false;
+            self.render_to_texture(resources);
             needs_rebuild = true;
             // Don't preload in the same tick as a render
             // Note: Tooltip timer takes priority if pending
@@ -5839,8 +8497,8 @@ impl Application for HvatApp {
 
         if needs_rebuild {
             TickResult::NeedsRebuild
-        } else if sam2_loading || sam2_encoding {
-            // SAM2 models are loading or encoding in background, keep polling
+        } else if sam2_loading || sam2_encoding || components_computing {
+            // SAM2 or component work is running in the background, keep polling
             TickResult::ScheduleTick
         } else if tooltip_pending {
             // Tooltip is pending but not visible yet - request idle timer to show it
@@ -58,6 +58,7 @@ impl LogLevel {
     }
 }
 
+use crate::data::{BandMathPreset, LoaderOptions};
 use crate::keybindings::{KeyBindings, MAX_CATEGORY_HOTKEYS};
 use crate::model::{Category, Tag, default_categories, default_tags};
 
@@ -87,6 +88,14 @@ pub struct AppConfig {
     /// Tag definitions (for images)
     #[serde(default = "default_tag_configs")]
     pub tags: Vec<TagConfig>,
+
+    /// File loading options (e.g. `.npz` cube key)
+    #[serde(default)]
+    pub loader: LoaderOptions,
+
+    /// Saved band-math expressions (e.g. vegetation indices)
+    #[serde(default)]
+    pub band_math: Vec<BandMathPreset>,
 }
 
 fn default_tag_configs() -> Vec<TagConfig> {
@@ -148,16 +157,51 @@ pub struct KeyBindingsConfig {
     pub tool_select: KeyCode,
     /// Hotkey for BoundingBox tool
     pub tool_bbox: KeyCode,
+    /// Hotkey for RotatedBox tool
+    #[serde(default = "default_tool_rotated_box")]
+    pub tool_rotated_box: KeyCode,
     /// Hotkey for Polygon tool
     pub tool_polygon: KeyCode,
+    /// Hotkey for Polyline tool
+    #[serde(default = "default_tool_polyline")]
+    pub tool_polyline: KeyCode,
     /// Hotkey for Point tool
     pub tool_point: KeyCode,
+    /// Hotkey for Brush tool
+    #[serde(default = "default_tool_brush")]
+    pub tool_brush: KeyCode,
+    /// Hotkey for Eraser tool
+    #[serde(default = "default_tool_eraser")]
+    pub tool_eraser: KeyCode,
+    /// Hotkey for Keypoints tool
+    #[serde(default = "default_tool_keypoints")]
+    pub tool_keypoints: KeyCode,
 
     /// Hotkeys for category selection (indices 0-9 map to categories 1-10)
     #[serde(default = "default_category_hotkeys")]
     pub category_hotkeys: Vec<Option<KeyCode>>,
 }
 
+fn default_tool_rotated_box() -> KeyCode {
+    KeyCode::W
+}
+
+fn default_tool_polyline() -> KeyCode {
+    KeyCode::Q
+}
+
+fn default_tool_brush() -> KeyCode {
+    KeyCode::B
+}
+
+fn default_tool_eraser() -> KeyCode {
+    KeyCode::X
+}
+
+fn default_tool_keypoints() -> KeyCode {
+    KeyCode::K
+}
+
 fn default_category_hotkeys() -> Vec<Option<KeyCode>> {
     vec![
         Some(KeyCode::Key1),
@@ -178,8 +222,13 @@ impl Default for KeyBindingsConfig {
         Self {
             tool_select: KeyCode::S,
             tool_bbox: KeyCode::E,
+            tool_rotated_box: default_tool_rotated_box(),
             tool_polygon: KeyCode::R,
+            tool_polyline: default_tool_polyline(),
             tool_point: KeyCode::T,
+            tool_brush: default_tool_brush(),
+            tool_eraser: default_tool_eraser(),
+            tool_keypoints: default_tool_keypoints(),
             category_hotkeys: default_category_hotkeys(),
         }
     }
@@ -190,8 +239,13 @@ impl From<&KeyBindings> for KeyBindingsConfig {
         Self {
             tool_select: bindings.tool_select,
             tool_bbox: bindings.tool_bbox,
+            tool_rotated_box: bindings.tool_rotated_box,
             tool_polygon: bindings.tool_polygon,
+            tool_polyline: bindings.tool_polyline,
             tool_point: bindings.tool_point,
+            tool_brush: bindings.tool_brush,
+            tool_eraser: bindings.tool_eraser,
+            tool_keypoints: bindings.tool_keypoints,
             category_hotkeys: bindings.category_hotkeys.to_vec(),
         }
     }
@@ -215,8 +269,13 @@ impl KeyBindingsConfig {
         KeyBindings {
             tool_select: self.tool_select,
             tool_bbox: self.tool_bbox,
+            tool_rotated_box: self.tool_rotated_box,
             tool_polygon: self.tool_polygon,
+            tool_polyline: self.tool_polyline,
             tool_point: self.tool_point,
+            tool_brush: self.tool_brush,
+            tool_eraser: self.tool_eraser,
+            tool_keypoints: self.tool_keypoints,
             category_hotkeys,
         }
     }
@@ -231,6 +290,12 @@ pub struct CategoryConfig {
     pub name: String,
     /// RGB color for the category
     pub color: [u8; 3],
+    /// Keypoint names in placement order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keypoints: Vec<String>,
+    /// Skeleton edges as pairs of 0-based keypoint indices
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skeleton: Vec<(usize, usize)>,
 }
 
 impl From<&Category> for CategoryConfig {
@@ -239,6 +304,8 @@ impl From<&Category> for CategoryConfig {
             id: cat.id,
             name: cat.name.clone(),
             color: cat.color,
+            keypoints: cat.keypoints.clone(),
+            skeleton: cat.skeleton.clone(),
         }
     }
 }
@@ -246,6 +313,7 @@ impl From<&Category> for CategoryConfig {
 impl From<CategoryConfig> for Category {
     fn from(config: CategoryConfig) -> Self {
         Category::new(config.id, &config.name, config.color)
+            .with_keypoints(config.keypoints, config.skeleton)
     }
 }
 
@@ -289,6 +357,8 @@ impl AppConfig {
                 .map(CategoryConfig::from)
                 .collect(),
             tags: default_tags().iter().map(TagConfig::from).collect(),
+            loader: LoaderOptions::default(),
+            band_math: Vec::new(),
         }
     }
 
@@ -73,6 +73,21 @@ pub const HUE_MAX: f32 = 360.0;
 /// Hue slider step size (degrees)
 pub const HUE_STEP: f32 = 1.0;
 
+/// Stretch standard deviation multiplier minimum value
+pub const STRETCH_K_MIN: f32 = 0.5;
+
+/// Stretch standard deviation multiplier maximum value
+pub const STRETCH_K_MAX: f32 = 4.0;
+
+/// Stretch standard deviation multiplier step size
+pub const STRETCH_K_STEP: f32 = 0.1;
+
+/// Height of each channel histogram (including its value labels)
+pub const HISTOGRAM_HEIGHT: f32 = 70.0;
+
+/// Bar colors of the red, green and blue channel histograms (RGB)
+pub const HISTOGRAM_CHANNEL_RGB: [[u8; 3]; 3] = [[210, 90, 90], [90, 180, 90], [90, 130, 220]];
+
 // =============================================================================
 // Default Adjustment Values
 // =============================================================================
@@ -89,6 +104,9 @@ pub const DEFAULT_GAMMA: f32 = 1.0;
 /// Default hue value (no change, in degrees)
 pub const DEFAULT_HUE: f32 = 0.0;
 
+/// Default number of standard deviations for the mean ± kσ stretch
+pub const DEFAULT_STRETCH_K: f32 = 2.0;
+
 // =============================================================================
 // Default Band Selection
 // =============================================================================
@@ -96,6 +114,9 @@ pub const DEFAULT_HUE: f32 = 0.0;
 /// Default red band index
 pub const DEFAULT_RED_BAND: usize = 0;
 
+/// Text color of loader warnings shown under Band Selection (RGB)
+pub const LOAD_WARNING_RGB: [u8; 3] = [230, 160, 50];
+
 // =============================================================================
 // GPU Preloading
 // =============================================================================
@@ -135,6 +156,31 @@ pub const MIN_TEXTURE_LAYERS: u32 = 2;
 #[cfg(not(target_arch = "wasm32"))]
 pub const MAX_IN_FLIGHT_DECODES: usize = 3;
 
+// =============================================================================
+// Tiled Rendering
+// =============================================================================
+
+/// Band texture size (bytes) above which an image is shown as a tiled
+/// pyramid instead of one texture array. Images beyond the GPU texture size
+/// limit are always tiled. Tiled images are not preloaded or GPU cached.
+pub const TILED_IMAGE_BYTES: u64 = 1 << 30;
+
+/// GPU memory (bytes) for the uploaded tiles of one tiled image
+pub const TILE_CACHE_BYTES: u64 = 512 << 20;
+
+/// Tiles uploaded per frame; the view sharpens over the following frames
+pub const TILE_UPLOADS_PER_FRAME: usize = 2;
+
+// =============================================================================
+// Load Subset
+// =============================================================================
+
+/// Largest band stride offered in Settings > File Loading
+pub const MAX_LOAD_BAND_STRIDE: usize = 16;
+
+/// Spatial binning factors offered in Settings > File Loading
+pub const LOAD_BINNING_FACTORS: &[u32] = &[1, 2, 4, 8];
+
 // =============================================================================
 // Right Sidebar Sections
 // =============================================================================
@@ -153,3 +199,88 @@ pub const THUMBNAIL_SPACING: f32 = 4.0;
 
 /// Maximum height for annotations panel collapsible content
 pub const ANNOTATIONS_MAX_HEIGHT: f32 = 300.0;
+
+/// Maximum height for annotation statistics collapsible content
+pub const STATS_BANDS_MAX_HEIGHT: f32 = 350.0;
+
+/// Height of the spectrum plot
+pub const SPECTRUM_CHART_HEIGHT: f32 = 150.0;
+
+/// Maximum number of pinned spectra (oldest is dropped when exceeded)
+pub const MAX_PINNED_SPECTRA: usize = 8;
+
+/// Line colors of pinned spectra (RGB), cycled in pin order
+pub const PINNED_SPECTRUM_COLORS: [[u8; 3]; 8] = [
+    [230, 90, 80],
+    [90, 180, 90],
+    [80, 140, 230],
+    [230, 180, 60],
+    [180, 100, 220],
+    [70, 200, 200],
+    [230, 130, 180],
+    [160, 160, 160],
+];
+
+/// Number of color steps in the colormap legend
+pub const COLORBAR_STEPS: usize = 64;
+
+// ============================================================================
+// Polylines
+// ============================================================================
+
+/// Minimum polyline line width (image pixels)
+pub const POLYLINE_WIDTH_MIN: f32 = 1.0;
+
+/// Maximum polyline line width (image pixels)
+pub const POLYLINE_WIDTH_MAX: f32 = 50.0;
+
+/// Polyline line width slider step (image pixels)
+pub const POLYLINE_WIDTH_STEP: f32 = 0.5;
+
+// ============================================================================
+// Masks
+// ============================================================================
+
+/// Minimum brush/eraser radius (image pixels)
+pub const BRUSH_RADIUS_MIN: f32 = 1.0;
+
+/// Maximum brush/eraser radius (image pixels)
+pub const BRUSH_RADIUS_MAX: f32 = 100.0;
+
+/// Brush/eraser radius slider step (image pixels)
+pub const BRUSH_RADIUS_STEP: f32 = 1.0;
+
+/// Douglas-Peucker tolerance when converting masks to polygons (image pixels)
+pub const MASK_CONTOUR_EPSILON: f32 = 1.0;
+
+// ============================================================================
+// Principal Components
+// ============================================================================
+
+/// Maximum number of pixels the component covariance is estimated from
+pub const COMPONENT_MAX_SAMPLES: usize = 20_000;
+
+/// Number of component images computed and selectable for display
+pub const MAX_DISPLAY_COMPONENTS: usize = 16;
+
+// ============================================================================
+// Spectral Similarity Map
+// ============================================================================
+
+/// Initial threshold (fraction of the map's value range)
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.1;
+
+/// Threshold slider step (fraction of the map's value range)
+pub const SIMILARITY_THRESHOLD_STEP: f32 = 0.005;
+
+/// Smallest region (in pixels) turned into a polygon
+pub const SIMILARITY_MIN_REGION_AREA: usize = 16;
+
+/// Contour simplification tolerance of region polygons (pixels)
+pub const SIMILARITY_CONTOUR_EPSILON: f32 = 1.0;
+
+/// Maximum number of region polygons (largest first)
+pub const MAX_SIMILARITY_REGIONS: usize = 100;
+
+/// Outline color of the previewed regions (RGBA)
+pub const SIMILARITY_REGION_RGBA: [f32; 4] = [1.0, 0.6, 0.1, 0.9];
@@ -0,0 +1,940 @@
+//! Band-math expressions for index and false-color display modes.
+//!
+//! Expressions combine bands with arithmetic, e.g. `(b80-b40)/(b80+b40)` or
+//! `mean(b10..b20)`. Bands are referenced by number, starting at `b1` like the
+//! "Band N" labels. Supported syntax:
+//!
+//! - numbers, bands `bN`, and band ranges `bA..bB` inside reductions
+//! - `+`, `-`, `*`, `/`, `^` with the usual precedence, unary minus, parentheses
+//! - `abs`, `sqrt`, `ln`, `exp` of one expression
+//! - `mean`, `sum`, `min`, `max` of any number of expressions and ranges
+//!
+//! A `BandMathProgram` holds one expression (an index, shown as a single
+//! channel) or three (red, green, blue). It is evaluated per pixel on the GPU
+//! through generated WGSL, and on the CPU by `BandExpr::eval` for statistics
+//! such as the contrast stretch of the result.
+
+use std::fmt::Write;
+
+use hvat_gpu::MAX_BAND_MATH_BANDS;
+use serde::{Deserialize, Serialize};
+
+/// Error in a band-math expression.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message}")]
+pub struct BandMathError {
+    /// Human-readable description, including the position if known
+    pub message: String,
+}
+
+impl BandMathError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    fn at(position: usize, message: impl std::fmt::Display) -> Self {
+        Self::new(format!("{} at position {}", message, position + 1))
+    }
+}
+
+/// Binary arithmetic operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// Function of one argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryFn {
+    Abs,
+    Sqrt,
+    Ln,
+    Exp,
+}
+
+/// Function over any number of values and band ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReduceFn {
+    Mean,
+    Sum,
+    Min,
+    Max,
+}
+
+/// Argument of a reduction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReduceArg {
+    /// A single value
+    Expr(BandExpr),
+    /// All bands from `first` to `last` (0-based, inclusive)
+    Range { first: usize, last: usize },
+}
+
+/// Parsed band-math expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BandExpr {
+    /// Constant
+    Number(f32),
+    /// Value of a band (0-based index)
+    Band(usize),
+    /// Unary minus
+    Neg(Box<BandExpr>),
+    /// Arithmetic on two values
+    Binary(BinaryOp, Box<BandExpr>, Box<BandExpr>),
+    /// Function of one value
+    Unary(UnaryFn, Box<BandExpr>),
+    /// Reduction over values and band ranges
+    Reduce(ReduceFn, Vec<ReduceArg>),
+}
+
+impl BandExpr {
+    /// Parse an expression.
+    pub fn parse(source: &str) -> Result<Self, BandMathError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.chars().count(),
+        };
+        let expr = parser.expr()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some((_, position)) => Err(BandMathError::at(*position, "Unexpected input")),
+        }
+    }
+
+    /// Check that every referenced band exists in an image with `num_bands`.
+    pub fn validate(&self, num_bands: usize) -> Result<(), BandMathError> {
+        match self.max_band() {
+            Some(band) if band >= num_bands => Err(BandMathError::new(format!(
+                "b{} is out of range (image has {} bands)",
+                band + 1,
+                num_bands
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Highest referenced band index.
+    pub fn max_band(&self) -> Option<usize> {
+        match self {
+            BandExpr::Number(_) => None,
+            BandExpr::Band(band) => Some(*band),
+            BandExpr::Neg(inner) | BandExpr::Unary(_, inner) => inner.max_band(),
+            BandExpr::Binary(_, lhs, rhs) => lhs.max_band().max(rhs.max_band()),
+            BandExpr::Reduce(_, args) => args
+                .iter()
+                .filter_map(|arg| match arg {
+                    ReduceArg::Expr(expr) => expr.max_band(),
+                    ReduceArg::Range { last, .. } => Some(*last),
+                })
+                .max(),
+        }
+    }
+
+    /// Evaluate the expression for one pixel, with `band(i)` giving the
+    /// value of band `i`.
+    pub fn eval<F: Fn(usize) -> f32>(&self, band: &F) -> f32 {
+        match self {
+            BandExpr::Number(value) => *value,
+            BandExpr::Band(index) => band(*index),
+            BandExpr::Neg(inner) => -inner.eval(band),
+            BandExpr::Binary(op, lhs, rhs) => {
+                let a = lhs.eval(band);
+                match op {
+                    BinaryOp::Add => a + rhs.eval(band),
+                    BinaryOp::Sub => a - rhs.eval(band),
+                    BinaryOp::Mul => a * rhs.eval(band),
+                    BinaryOp::Div => a / rhs.eval(band),
+                    BinaryOp::Pow => match integer_exponent(rhs) {
+                        Some(n) => a.powi(n),
+                        None => a.powf(rhs.eval(band)),
+                    },
+                }
+            }
+            BandExpr::Unary(func, inner) => {
+                let value = inner.eval(band);
+                match func {
+                    UnaryFn::Abs => value.abs(),
+                    UnaryFn::Sqrt => value.sqrt(),
+                    UnaryFn::Ln => value.ln(),
+                    UnaryFn::Exp => value.exp(),
+                }
+            }
+            BandExpr::Reduce(func, args) => {
+                let mut acc = func.initial();
+                let mut count = 0usize;
+                for arg in args {
+                    match arg {
+                        ReduceArg::Expr(expr) => {
+                            acc = func.combine(acc, expr.eval(band));
+                            count += 1;
+                        }
+                        ReduceArg::Range { first, last } => {
+                            for index in *first..=*last {
+                                acc = func.combine(acc, band(index));
+                            }
+                            count += last - first + 1;
+                        }
+                    }
+                }
+                if *func == ReduceFn::Mean {
+                    acc / count as f32
+                } else {
+                    acc
+                }
+            }
+        }
+    }
+
+    /// Evaluate the expression for every pixel of `bands` (row-major).
+    pub fn eval_image(&self, bands: &[Vec<f32>]) -> Vec<f32> {
+        let pixel_count = bands.first().map_or(0, Vec::len);
+        (0..pixel_count)
+            .map(|pixel| self.eval(&|index| bands[index][pixel]))
+            .collect()
+    }
+}
+
+impl UnaryFn {
+    fn wgsl_name(self) -> &'static str {
+        match self {
+            UnaryFn::Abs => "abs",
+            UnaryFn::Sqrt => "sqrt",
+            UnaryFn::Ln => "log",
+            UnaryFn::Exp => "exp",
+        }
+    }
+}
+
+impl ReduceFn {
+    fn initial(self) -> f32 {
+        match self {
+            ReduceFn::Mean | ReduceFn::Sum => 0.0,
+            ReduceFn::Min => f32::INFINITY,
+            ReduceFn::Max => f32::NEG_INFINITY,
+        }
+    }
+
+    fn combine(self, acc: f32, value: f32) -> f32 {
+        match self {
+            ReduceFn::Mean | ReduceFn::Sum => acc + value,
+            ReduceFn::Min => acc.min(value),
+            ReduceFn::Max => acc.max(value),
+        }
+    }
+
+    /// WGSL literal of the initial value.
+    fn wgsl_initial(self) -> &'static str {
+        match self {
+            ReduceFn::Mean | ReduceFn::Sum => "0.0",
+            ReduceFn::Min => "3.4028235e38",
+            ReduceFn::Max => "-3.4028235e38",
+        }
+    }
+
+    /// WGSL statement folding `value` into `acc`.
+    fn wgsl_combine(self, acc: &str, value: &str) -> String {
+        match self {
+            ReduceFn::Mean | ReduceFn::Sum => format!("{} = {} + {};", acc, acc, value),
+            ReduceFn::Min => format!("{} = min({}, {});", acc, acc, value),
+            ReduceFn::Max => format!("{} = max({}, {});", acc, acc, value),
+        }
+    }
+}
+
+/// Exponent of `x ^ n` when `n` is a whole-number constant.
+///
+/// Whole powers are computed by repeated multiplication so negative bases
+/// work the same on the CPU and GPU (WGSL `pow` is undefined for them).
+fn integer_exponent(expr: &BandExpr) -> Option<i32> {
+    let value = match expr {
+        BandExpr::Number(value) => *value,
+        BandExpr::Neg(inner) => match inner.as_ref() {
+            BandExpr::Number(value) => -*value,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    (value.fract() == 0.0 && value.abs() <= 64.0).then_some(value as i32)
+}
+
+// =============================================================================
+// Tokenizer and parser
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    /// Band number as written (1-based)
+    Band(usize),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+    DotDot,
+}
+
+/// Split the source into tokens with their character positions.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, BandMathError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            // A single dot starts the fraction; ".." is a range
+            if chars.get(i) == Some(&'.') && chars.get(i + 1) != Some(&'.') {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            if matches!(chars.get(i), Some('e' | 'E')) {
+                let mut j = i + 1;
+                if matches!(chars.get(j), Some('+' | '-')) {
+                    j += 1;
+                }
+                if chars.get(j).is_some_and(char::is_ascii_digit) {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            // Literals beyond f32 range parse as infinity, which WGSL can't express
+            let value = text
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| BandMathError::at(start, format!("Invalid number '{}'", text)))?;
+            tokens.push((Token::Number(value), start));
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let token = match text.strip_prefix(['b', 'B']) {
+                Some(digits)
+                    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    let number: usize = digits.parse().map_err(|_| {
+                        BandMathError::at(start, format!("Invalid band '{}'", text))
+                    })?;
+                    if number == 0 {
+                        return Err(BandMathError::at(start, "Band numbers start at b1"));
+                    }
+                    Token::Band(number)
+                }
+                _ => Token::Ident(text.to_lowercase()),
+            };
+            tokens.push((token, start));
+            continue;
+        }
+        let token = match c {
+            '+' | '-' | '*' | '/' | '^' => Token::Op(c),
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                i += 1;
+                Token::DotDot
+            }
+            _ => return Err(BandMathError::at(start, format!("Unexpected '{}'", c))),
+        };
+        tokens.push((token, start));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Function named in an expression.
+enum Function {
+    Unary(UnaryFn),
+    Reduce(ReduceFn),
+}
+
+/// Recursive-descent parser over the token list.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Source length, reported for errors at the end of input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, p)| *p)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), BandMathError> {
+        if self.peek() == Some(&expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(BandMathError::at(
+                self.position(),
+                format!("Expected {}", what),
+            ))
+        }
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<BandExpr, BandMathError> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek() {
+            let op = if *op == '+' {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Sub
+            };
+            self.pos += 1;
+            lhs = BandExpr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    /// term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<BandExpr, BandMathError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek() {
+            let op = if *op == '*' {
+                BinaryOp::Mul
+            } else {
+                BinaryOp::Div
+            };
+            self.pos += 1;
+            lhs = BandExpr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    /// unary := '-' unary | power
+    fn unary(&mut self) -> Result<BandExpr, BandMathError> {
+        if self.peek() == Some(&Token::Op('-')) {
+            self.pos += 1;
+            return Ok(BandExpr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// power := atom ('^' unary)?  (right-associative)
+    fn power(&mut self) -> Result<BandExpr, BandMathError> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Op('^')) {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return Ok(BandExpr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    /// atom := number | band | function '(' args ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<BandExpr, BandMathError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(value)) => Ok(BandExpr::Number(value)),
+            Some(Token::Band(number)) => {
+                if self.peek() == Some(&Token::DotDot) {
+                    return Err(BandMathError::at(
+                        position,
+                        "Band ranges are only allowed in mean, sum, min and max",
+                    ));
+                }
+                Ok(BandExpr::Band(number - 1))
+            }
+            Some(Token::LParen) => {
+                let expr = self.expr()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => self.call(&name, position),
+            Some(_) => Err(BandMathError::at(position, "Expected a value")),
+            None => Err(BandMathError::at(position, "Unexpected end of expression")),
+        }
+    }
+
+    /// Function call after its name.
+    fn call(&mut self, name: &str, position: usize) -> Result<BandExpr, BandMathError> {
+        let func = match name {
+            "abs" => Function::Unary(UnaryFn::Abs),
+            "sqrt" => Function::Unary(UnaryFn::Sqrt),
+            "ln" | "log" => Function::Unary(UnaryFn::Ln),
+            "exp" => Function::Unary(UnaryFn::Exp),
+            "mean" | "avg" => Function::Reduce(ReduceFn::Mean),
+            "sum" => Function::Reduce(ReduceFn::Sum),
+            "min" => Function::Reduce(ReduceFn::Min),
+            "max" => Function::Reduce(ReduceFn::Max),
+            _ => {
+                return Err(BandMathError::at(
+                    position,
+                    format!("Unknown function '{}'", name),
+                ));
+            }
+        };
+        self.expect(Token::LParen, &format!("'(' after {}", name))?;
+
+        let func = match func {
+            Function::Unary(unary) => {
+                let arg = self.expr()?;
+                self.expect(Token::RParen, "')'")?;
+                return Ok(BandExpr::Unary(unary, Box::new(arg)));
+            }
+            Function::Reduce(reduce) => reduce,
+        };
+
+        let mut args = Vec::new();
+        loop {
+            args.push(self.reduce_arg()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => break,
+                _ => {
+                    self.pos -= 1;
+                    return Err(BandMathError::at(self.position(), "Expected ',' or ')'"));
+                }
+            }
+        }
+        Ok(BandExpr::Reduce(func, args))
+    }
+
+    /// Reduction argument: a band range or an expression.
+    fn reduce_arg(&mut self) -> Result<ReduceArg, BandMathError> {
+        let position = self.position();
+        if let Some(Token::Band(first)) = self.peek().cloned()
+            && self.tokens.get(self.pos + 1).map(|(t, _)| t) == Some(&Token::DotDot)
+        {
+            self.pos += 2;
+            let last = match self.next() {
+                Some(Token::Band(last)) => last,
+                _ => {
+                    self.pos -= 1;
+                    return Err(BandMathError::at(
+                        self.position(),
+                        "Expected a band after '..'",
+                    ));
+                }
+            };
+            if last < first {
+                return Err(BandMathError::at(
+                    position,
+                    format!("Empty band range b{}..b{}", first, last),
+                ));
+            }
+            return Ok(ReduceArg::Range {
+                first: first - 1,
+                last: last - 1,
+            });
+        }
+        self.expr().map(ReduceArg::Expr)
+    }
+}
+
+// =============================================================================
+// Programs and WGSL generation
+// =============================================================================
+
+/// Compiled band-math display: one or three expressions with their shader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandMathProgram {
+    /// One expression (single channel) or three (red, green, blue)
+    pub expressions: Vec<BandExpr>,
+    /// Band index of each range slot used by the shader
+    pub bands: Vec<usize>,
+    /// WGSL defining `fn band_math(tex_coords: vec2<f32>) -> vec3<f32>`
+    pub wgsl: String,
+}
+
+impl BandMathProgram {
+    /// Parse, validate and compile one or three expressions.
+    ///
+    /// Errors name the channel of the failing expression.
+    pub fn compile(sources: &[String], num_bands: usize) -> Result<Self, BandMathError> {
+        let names: &[&str] = match sources.len() {
+            1 => &["Index"],
+            3 => &["Red", "Green", "Blue"],
+            n => {
+                return Err(BandMathError::new(format!(
+                    "Expected 1 or 3 expressions, got {}",
+                    n
+                )));
+            }
+        };
+        let mut expressions = Vec::with_capacity(sources.len());
+        for (source, name) in sources.iter().zip(names) {
+            if source.trim().is_empty() {
+                return Err(BandMathError::new(format!("{}: expression is empty", name)));
+            }
+            let expr = BandExpr::parse(source)
+                .and_then(|expr| expr.validate(num_bands).map(|_| expr))
+                .map_err(|e| BandMathError::new(format!("{}: {}", name, e.message)))?;
+            expressions.push(expr);
+        }
+
+        let mut codegen = Codegen::default();
+        let values: Vec<String> = expressions.iter().map(|e| codegen.expr(e)).collect();
+        if codegen.bands.len() > MAX_BAND_MATH_BANDS {
+            return Err(BandMathError::new(format!(
+                "Expressions sample {} bands, at most {} are supported",
+                codegen.bands.len(),
+                MAX_BAND_MATH_BANDS
+            )));
+        }
+
+        let mut wgsl = String::from("fn band_math(tex_coords: vec2<f32>) -> vec3<f32> {\n");
+        for line in &codegen.lines {
+            let _ = writeln!(wgsl, "    {}", line);
+        }
+        let rgb = if values.len() == 1 {
+            format!("vec3<f32>({})", values[0])
+        } else {
+            format!("vec3<f32>({}, {}, {})", values[0], values[1], values[2])
+        };
+        let _ = writeln!(wgsl, "    return {};\n}}", rgb);
+
+        Ok(Self {
+            expressions,
+            bands: codegen.bands,
+            wgsl,
+        })
+    }
+
+    /// Number of output channels (1 or 3).
+    pub fn channels(&self) -> usize {
+        self.expressions.len()
+    }
+
+    /// Evaluate every expression over the whole image on the CPU.
+    pub fn evaluate(&self, bands: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        self.expressions
+            .iter()
+            .map(|expr| expr.eval_image(bands))
+            .collect()
+    }
+}
+
+/// WGSL code generator.
+///
+/// Every band sample gets a slot in the band-math uniform holding its value
+/// range; ranges use consecutive slots so they can be sampled in a loop.
+#[derive(Default)]
+struct Codegen {
+    /// Statements preceding the returned value
+    lines: Vec<String>,
+    /// Band index of each slot
+    bands: Vec<usize>,
+    /// Counter for temporaries
+    temps: usize,
+}
+
+impl Codegen {
+    fn slot(&mut self, band: usize) -> usize {
+        match self.bands.iter().position(|&b| b == band) {
+            Some(slot) => slot,
+            None => {
+                self.bands.push(band);
+                self.bands.len() - 1
+            }
+        }
+    }
+
+    fn temp(&mut self) -> String {
+        self.temps += 1;
+        format!("t{}", self.temps)
+    }
+
+    /// WGSL expression computing `expr`, emitting statements as needed.
+    fn expr(&mut self, expr: &BandExpr) -> String {
+        match expr {
+            BandExpr::Number(value) => wgsl_float(*value),
+            BandExpr::Band(band) => {
+                let slot = self.slot(*band);
+                format!(
+                    "decode_band(sample_band(tex_coords, {}u), band_math_range({}u))",
+                    band, slot
+                )
+            }
+            BandExpr::Neg(inner) => format!("(-{})", self.expr(inner)),
+            BandExpr::Binary(BinaryOp::Pow, base, exponent) => {
+                let base = self.expr(base);
+                match integer_exponent(exponent) {
+                    Some(n) => {
+                        let value = self.temp();
+                        self.lines.push(format!("let {} = {};", value, base));
+                        let product = if n == 0 {
+                            "1.0".to_string()
+                        } else {
+                            vec![value.as_str(); n.unsigned_abs() as usize].join(" * ")
+                        };
+                        if n < 0 {
+                            format!("(1.0 / ({}))", product)
+                        } else {
+                            format!("({})", product)
+                        }
+                    }
+                    None => format!("pow({}, {})", base, self.expr(exponent)),
+                }
+            }
+            BandExpr::Binary(op, lhs, rhs) => {
+                let symbol = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::Pow => unreachable!(),
+                };
+                format!("({} {} {})", self.expr(lhs), symbol, self.expr(rhs))
+            }
+            BandExpr::Unary(func, inner) => format!("{}({})", func.wgsl_name(), self.expr(inner)),
+            BandExpr::Reduce(func, args) => {
+                let acc = self.temp();
+                self.lines
+                    .push(format!("var {} = {};", acc, func.wgsl_initial()));
+                let mut count = 0usize;
+                for arg in args {
+                    match arg {
+                        ReduceArg::Expr(expr) => {
+                            let value = self.expr(expr);
+                            self.lines.push(func.wgsl_combine(&acc, &value));
+                            count += 1;
+                        }
+                        ReduceArg::Range { first, last } => {
+                            let len = last - first + 1;
+                            let slot = self.bands.len();
+                            self.bands.extend(*first..=*last);
+                            let i = self.temp();
+                            let value = format!(
+                                "decode_band(sample_band(tex_coords, {}u + {}), band_math_range({}u + {}))",
+                                first, i, slot, i
+                            );
+                            self.lines.push(format!(
+                                "for (var {} = 0u; {} < {}u; {} = {} + 1u) {{ {} }}",
+                                i,
+                                i,
+                                len,
+                                i,
+                                i,
+                                func.wgsl_combine(&acc, &value)
+                            ));
+                            count += len;
+                        }
+                    }
+                }
+                if *func == ReduceFn::Mean {
+                    format!("({} / {})", acc, wgsl_float(count as f32))
+                } else {
+                    acc
+                }
+            }
+        }
+    }
+}
+
+/// WGSL float literal.
+fn wgsl_float(value: f32) -> String {
+    let text = format!("{:?}", value);
+    if text.contains(['.', 'e']) {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+// =============================================================================
+// Saved expressions
+// =============================================================================
+
+/// Named band-math expression(s) saved in the configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BandMathPreset {
+    /// Display name (e.g. "NDVI")
+    pub name: String,
+    /// One expression (index) or three (red, green, blue)
+    pub expressions: Vec<String>,
+}
+
+/// How the displayed image is computed from the bands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BandMathMode {
+    /// Three bands shown as red, green and blue
+    #[default]
+    Off,
+    /// One expression per output channel
+    Rgb,
+    /// A single expression shown as grayscale
+    Index,
+}
+
+impl BandMathMode {
+    /// All modes, in display order.
+    pub fn all() -> &'static [BandMathMode] {
+        &[BandMathMode::Off, BandMathMode::Rgb, BandMathMode::Index]
+    }
+
+    /// Get the display name for this mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            BandMathMode::Off => "Bands",
+            BandMathMode::Rgb => "RGB Math",
+            BandMathMode::Index => "Index",
+        }
+    }
+
+    /// Number of expressions the mode uses.
+    pub fn channels(self) -> usize {
+        match self {
+            BandMathMode::Off => 0,
+            BandMathMode::Rgb => 3,
+            BandMathMode::Index => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, spectrum: &[f32]) -> f32 {
+        BandExpr::parse(source)
+            .unwrap()
+            .eval(&|band| spectrum[band])
+    }
+
+    #[test]
+    fn test_parse_and_eval() {
+        let spectrum = [0.2, 0.4, 0.6, 0.8];
+        let ndvi = eval("(b4-b2)/(b4+b2)", &spectrum);
+        assert!((ndvi - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(eval("1 + 2 * 3", &spectrum), 7.0);
+        assert_eq!(eval("-2^2", &spectrum), -4.0);
+        assert_eq!(eval("2^3^2", &spectrum), 512.0);
+        assert_eq!(eval("(-2)^3", &spectrum), -8.0);
+        assert_eq!(eval("2 ^ -1", &spectrum), 0.5);
+        assert_eq!(eval("abs(b1 - b2) * 10", &spectrum), 2.0);
+        assert_eq!(eval("sqrt(16) + ln(exp(1))", &spectrum), 5.0);
+        assert_eq!(eval("1.5e1 + .5", &spectrum), 15.5);
+    }
+
+    #[test]
+    fn test_reductions() {
+        let spectrum = [1.0, 2.0, 3.0, 4.0, 10.0];
+        assert_eq!(eval("mean(b1..b4)", &spectrum), 2.5);
+        assert_eq!(eval("mean(b1..b2, b5)", &spectrum), 13.0 / 3.0);
+        assert_eq!(eval("sum(b1..b5)", &spectrum), 20.0);
+        assert_eq!(eval("min(b2..b5, 0.5)", &spectrum), 0.5);
+        assert_eq!(eval("max(b1, b3 * 2)", &spectrum), 6.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |source: &str| BandExpr::parse(source).unwrap_err().message;
+        assert_eq!(err("b1 +"), "Unexpected end of expression at position 5");
+        assert_eq!(err("(b1"), "Expected ')' at position 4");
+        assert_eq!(err("b0"), "Band numbers start at b1 at position 1");
+        assert_eq!(err("foo(b1)"), "Unknown function 'foo' at position 1");
+        assert_eq!(err("b1 $ b2"), "Unexpected '$' at position 4");
+        assert_eq!(err("b1 b2"), "Unexpected input at position 4");
+        assert!(err("b1..b3").contains("only allowed in"));
+        assert!(err("mean(b5..b2)").contains("Empty band range"));
+
+        let expr = BandExpr::parse("mean(b10..b20)").unwrap();
+        assert_eq!(expr.max_band(), Some(19));
+        assert!(expr.validate(20).is_ok());
+        assert_eq!(
+            expr.validate(15).unwrap_err().message,
+            "b20 is out of range (image has 15 bands)"
+        );
+    }
+
+    #[test]
+    fn test_overflowing_literal() {
+        let err = BandExpr::parse("b1*1e39").unwrap_err();
+        assert_eq!(err.message, "Invalid number '1e39' at position 4");
+        assert!(BandExpr::parse(&"9".repeat(50)).is_err());
+        assert!(BandExpr::parse("b1*1e38").is_ok());
+    }
+
+    #[test]
+    fn test_program_compile() {
+        let sources = ["(b3-b1)/(b3+b1)".to_string()];
+        let program = BandMathProgram::compile(&sources, 3).unwrap();
+        assert_eq!(program.channels(), 1);
+        // b3 and b1 each get one slot, in order of appearance
+        assert_eq!(program.bands, vec![2, 0]);
+        assert!(
+            program
+                .wgsl
+                .starts_with("fn band_math(tex_coords: vec2<f32>) -> vec3<f32> {")
+        );
+        assert!(
+            program
+                .wgsl
+                .contains("sample_band(tex_coords, 2u), band_math_range(0u)")
+        );
+
+        let rgb = [
+            "mean(b1..b3)".to_string(),
+            "b2^2".to_string(),
+            "1".to_string(),
+        ];
+        let program = BandMathProgram::compile(&rgb, 3).unwrap();
+        assert_eq!(program.bands, vec![0, 1, 2]);
+        assert!(program.wgsl.contains("for (var t2 = 0u; t2 < 3u;"));
+        assert!(program.wgsl.contains("(t1 / 3.0)"));
+        assert!(program.wgsl.contains("(t3 * t3)"));
+
+        let err = |sources: &[&str]| {
+            let sources: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
+            BandMathProgram::compile(&sources, 3).unwrap_err().message
+        };
+        assert_eq!(
+            err(&["b1", "b4", "b2"]),
+            "Green: b4 is out of range (image has 3 bands)"
+        );
+        assert_eq!(err(&[" "]), "Index: expression is empty");
+        assert!(err(&["b1", "b2"]).starts_with("Expected 1 or 3"));
+    }
+
+    #[test]
+    fn test_program_evaluate() {
+        let bands = vec![vec![0.1, 0.2], vec![0.5, 0.2]];
+        let sources = ["(b2-b1)/(b2+b1)".to_string()];
+        let program = BandMathProgram::compile(&sources, 2).unwrap();
+        let values = program.evaluate(&bands);
+        assert_eq!(values.len(), 1);
+        assert!((values[0][0] - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(values[0][1], 0.0);
+    }
+}
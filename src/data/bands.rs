@@ -0,0 +1,215 @@
+//! Per-band spectral metadata.
+//!
+//! Loaders fill `BandInfo` from whatever the format provides (ENVI header
+//! fields, `.npz` keys, `.npy` sidecar tables). Wavelengths are stored in
+//! nanometers regardless of the units used in the source file.
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata of one spectral band.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BandInfo {
+    /// Band name (e.g. from ENVI `band names`, defaults to "Band N")
+    pub name: String,
+    /// Center wavelength in nanometers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wavelength: Option<f32>,
+    /// Full width at half maximum in nanometers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fwhm: Option<f32>,
+    /// Band flagged as unusable (e.g. water absorption, dead detector)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bad: bool,
+}
+
+impl BandInfo {
+    /// Create metadata with only a name.
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Short description for the UI, e.g. "Band 12 (650.3 nm)".
+    pub fn describe(&self) -> String {
+        let mut text = self.name.clone();
+        match (self.wavelength, self.fwhm) {
+            (Some(wl), Some(fwhm)) => text.push_str(&format!(" ({:.1} nm, FWHM {:.1})", wl, fwhm)),
+            (Some(wl), None) => text.push_str(&format!(" ({:.1} nm)", wl)),
+            _ => {}
+        }
+        if self.bad {
+            text.push_str(" [bad]");
+        }
+        text
+    }
+}
+
+/// Default metadata for `count` bands ("Band 1", "Band 2", ...).
+pub fn default_bands(count: usize) -> Vec<BandInfo> {
+    (1..=count)
+        .map(|b| BandInfo::named(format!("Band {}", b)))
+        .collect()
+}
+
+/// Scale factor from a wavelength unit name to nanometers.
+///
+/// Returns `None` for unknown units.
+pub fn unit_to_nm(units: &str) -> Option<f32> {
+    match units.trim().to_lowercase().as_str() {
+        "nanometers" | "nanometer" | "nm" => Some(1.0),
+        "micrometers" | "micrometer" | "microns" | "micron" | "um" | "µm" => Some(1000.0),
+        "millimeters" | "millimeter" | "mm" => Some(1_000_000.0),
+        _ => None,
+    }
+}
+
+/// Parse a wavelength typed by the user, e.g. "650nm", "650 nm", "0.65um" or "650".
+///
+/// Returns the wavelength in nanometers.
+pub fn parse_wavelength(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(text.len());
+    let (number, units) = text.split_at(split);
+    let value: f32 = number.trim().parse().ok()?;
+    let scale = if units.trim().is_empty() {
+        1.0
+    } else {
+        unit_to_nm(units)?
+    };
+    let nm = value * scale;
+    (nm.is_finite() && nm > 0.0).then_some(nm)
+}
+
+/// Index of the band whose center wavelength is closest to `wavelength_nm`.
+///
+/// Bad bands are only chosen if no good band has a wavelength.
+pub fn nearest_band(bands: &[BandInfo], wavelength_nm: f32) -> Option<usize> {
+    let closest = |allow_bad: bool| {
+        bands
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| allow_bad || !b.bad)
+            .filter_map(|(i, b)| Some((i, (b.wavelength? - wavelength_nm).abs())))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    };
+    closest(false).or_else(|| closest(true))
+}
+
+/// Parse a band table as written next to `.npy` files.
+///
+/// One band per line: `wavelength [fwhm [name]]`, separated by commas or
+/// whitespace. Empty lines, `#` comments and a non-numeric header row are
+/// skipped. Wavelengths are in nanometers.
+pub fn parse_band_table(text: &str) -> Result<Vec<BandInfo>, String> {
+    let mut bands = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = if line.contains(',') {
+            line.split(',').map(str::trim).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+
+        let Ok(wavelength) = fields[0].parse::<f32>() else {
+            if bands.is_empty() {
+                continue; // header row
+            }
+            return Err(format!(
+                "line {}: invalid wavelength '{}'",
+                line_no + 1,
+                fields[0]
+            ));
+        };
+        let fwhm = match fields.get(1).filter(|f| !f.is_empty()) {
+            Some(f) => Some(
+                f.parse::<f32>()
+                    .map_err(|_| format!("line {}: invalid FWHM '{}'", line_no + 1, f))?,
+            ),
+            None => None,
+        };
+        let name = fields
+            .get(2..)
+            .map(|rest| rest.join(" ").trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("Band {}", bands.len() + 1));
+
+        bands.push(BandInfo {
+            name,
+            wavelength: Some(wavelength),
+            fwhm,
+            bad: false,
+        });
+    }
+    Ok(bands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band(wavelength: f32, bad: bool) -> BandInfo {
+        BandInfo {
+            wavelength: Some(wavelength),
+            bad,
+            ..BandInfo::named("b")
+        }
+    }
+
+    #[test]
+    fn test_parse_wavelength() {
+        assert_eq!(parse_wavelength("650nm"), Some(650.0));
+        assert_eq!(parse_wavelength(" 650 nm "), Some(650.0));
+        assert_eq!(parse_wavelength("650"), Some(650.0));
+        assert_eq!(parse_wavelength("0.65um"), Some(650.0));
+        assert_eq!(parse_wavelength("1.2 Micrometers"), Some(1200.0));
+        assert_eq!(parse_wavelength("650 parsecs"), None);
+        assert_eq!(parse_wavelength("red"), None);
+        assert_eq!(parse_wavelength("-5nm"), None);
+    }
+
+    #[test]
+    fn test_nearest_band_skips_bad() {
+        let bands = vec![band(450.0, false), band(640.0, true), band(700.0, false)];
+        assert_eq!(nearest_band(&bands, 460.0), Some(0));
+        assert_eq!(nearest_band(&bands, 650.0), Some(2));
+        assert_eq!(nearest_band(&[band(640.0, true)], 650.0), Some(0));
+        assert_eq!(nearest_band(&default_bands(3), 650.0), None);
+    }
+
+    #[test]
+    fn test_parse_band_table() {
+        let text =
+            "# exported by pipeline\nwavelength,fwhm,name\n450.5, 10, blue edge\n550,,\n650\n";
+        let bands = parse_band_table(text).unwrap();
+        assert_eq!(bands.len(), 3);
+        assert_eq!(bands[0].name, "blue edge");
+        assert_eq!(bands[0].fwhm, Some(10.0));
+        assert_eq!(bands[1].wavelength, Some(550.0));
+        assert_eq!(bands[1].fwhm, None);
+        assert_eq!(bands[1].name, "Band 2");
+        assert_eq!(bands[2].name, "Band 3");
+
+        let bands = parse_band_table("400 5\n500 5 green band\n").unwrap();
+        assert_eq!(bands[1].name, "green band");
+
+        assert!(parse_band_table("400\nfoo\n").is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(BandInfo::named("Band 1").describe(), "Band 1");
+        let info = BandInfo {
+            fwhm: Some(5.0),
+            ..band(650.3, true)
+        };
+        assert_eq!(info.describe(), "b (650.3 nm, FWHM 5.0) [bad]");
+    }
+}
@@ -0,0 +1,438 @@
+//! Radiometric calibration of raw captures to reflectance.
+//!
+//! Lab push-broom captures come with a dark-current cube (shutter closed) and
+//! a white-reference cube (calibration panel). Each reference is reduced to
+//! one value per band and column by averaging its rows, and every sample of
+//! the capture becomes `(raw - dark) / (white - dark) * gain + offset`.
+//!
+//! `CalibrationRule`s in `LoaderOptions` assign references to files by
+//! pattern. The resolved `Calibration` runs as a `DataProcessor` of the
+//! `LoaderRegistry`, after decoding and before the data reaches the GPU, and
+//! records its `CalibrationSource` in the decoded data.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DataProcessor, HyperspectralData};
+
+/// Placeholder in reference paths replaced by the image file stem.
+pub const STEM_PLACEHOLDER: &str = "{stem}";
+
+/// Calibration of files whose path matches `pattern`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRule {
+    /// Glob pattern (`*`, `?`); matched against the file name when it has
+    /// no `/`, otherwise against the full path
+    pub pattern: String,
+    /// Dark-current reference cube (see `resolve`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dark: Option<String>,
+    /// White reference cube (see `resolve`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub white: Option<String>,
+    /// Gain of each loaded band, or one for all bands (empty = 1)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gain: Vec<f32>,
+    /// Offset of each loaded band, or one for all bands (empty = 0)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offset: Vec<f32>,
+}
+
+impl CalibrationRule {
+    /// Reference files and factors for the image at `path`.
+    ///
+    /// `{stem}` in a reference path is replaced by the file stem of the
+    /// image (e.g. `DARKREF_{stem}.hdr`), and relative paths are taken
+    /// relative to the folder of the image.
+    pub fn resolve(&self, path: &Path) -> CalibrationSource {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let folder = path.parent().unwrap_or(Path::new(""));
+        let reference = |reference: &String| folder.join(reference.replace(STEM_PLACEHOLDER, stem));
+        CalibrationSource {
+            dark: self.dark.as_ref().map(reference),
+            white: self.white.as_ref().map(reference),
+            gain: self.gain.clone(),
+            offset: self.offset.clone(),
+        }
+    }
+
+    /// Short description for the settings list.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(dark) = &self.dark {
+            parts.push(format!("dark {}", dark));
+        }
+        if let Some(white) = &self.white {
+            parts.push(format!("white {}", white));
+        }
+        if !self.gain.is_empty() {
+            parts.push(format!("gain {}", format_factors(&self.gain)));
+        }
+        if !self.offset.is_empty() {
+            parts.push(format!("offset {}", format_factors(&self.offset)));
+        }
+        if parts.is_empty() {
+            parts.push("no references".to_string());
+        }
+        format!("{} -> {}", self.pattern, parts.join(", "))
+    }
+}
+
+/// Reference files and factors an image was calibrated with.
+///
+/// Recorded with the image in the project file so the result can be
+/// reproduced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationSource {
+    /// Dark-current reference cube
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dark: Option<PathBuf>,
+    /// White reference cube
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub white: Option<PathBuf>,
+    /// Gain of each loaded band, or one for all bands (empty = 1)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gain: Vec<f32>,
+    /// Offset of each loaded band, or one for all bands (empty = 0)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offset: Vec<f32>,
+}
+
+impl CalibrationSource {
+    /// Short description naming the reference files.
+    pub fn describe(&self) -> String {
+        let name = |path: &PathBuf| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let mut parts = Vec::new();
+        if let Some(dark) = &self.dark {
+            parts.push(format!("dark {}", name(dark)));
+        }
+        if let Some(white) = &self.white {
+            parts.push(format!("white {}", name(white)));
+        }
+        if !self.gain.is_empty() || !self.offset.is_empty() {
+            parts.push("gain/offset".to_string());
+        }
+        if parts.is_empty() {
+            "Calibrated".to_string()
+        } else {
+            format!("Calibrated ({})", parts.join(", "))
+        }
+    }
+}
+
+/// Parse gain or offset factors typed by the user (`"1.5"`, `"0.9, 1.1"`).
+///
+/// An empty string gives no factors (the default of 1 or 0).
+pub fn parse_factors(text: &str) -> Result<Vec<f32>, String> {
+    text.split([',', ' ', ';'])
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("Invalid calibration factor: {}", s))
+        })
+        .collect()
+}
+
+/// Factors as typed, the inverse of `parse_factors`.
+pub fn format_factors(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Mean of each column of a reference cube, per band.
+#[derive(Clone, PartialEq)]
+pub struct ReferenceFrame {
+    /// Per band, the mean over all rows of each column
+    pub bands: Vec<Vec<f32>>,
+    /// Number of columns
+    pub width: usize,
+}
+
+impl ReferenceFrame {
+    /// Average the rows of `cube`, leaving out non-finite samples.
+    ///
+    /// Columns without any finite sample are NaN.
+    pub fn from_cube(cube: &HyperspectralData) -> Self {
+        let width = cube.width as usize;
+        let bands = cube
+            .bands
+            .iter()
+            .map(|band| {
+                let mut sums = vec![0.0f64; width];
+                let mut counts = vec![0u32; width];
+                for row in band.chunks_exact(width.max(1)) {
+                    for (x, &value) in row.iter().enumerate() {
+                        if value.is_finite() {
+                            sums[x] += f64::from(value);
+                            counts[x] += 1;
+                        }
+                    }
+                }
+                sums.iter()
+                    .zip(&counts)
+                    .map(|(&sum, &count)| {
+                        if count > 0 {
+                            (sum / f64::from(count)) as f32
+                        } else {
+                            f32::NAN
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { bands, width }
+    }
+
+    /// Check that the frame fits `data`: same bands, and the same width or
+    /// a single column used for all columns.
+    fn check(&self, name: &str, data: &HyperspectralData) -> Result<(), String> {
+        if self.bands.len() != data.bands.len() {
+            return Err(format!(
+                "{} reference has {} bands, the image {}",
+                name,
+                self.bands.len(),
+                data.bands.len()
+            ));
+        }
+        if self.width != 1 && self.width != data.width as usize {
+            return Err(format!(
+                "{} reference is {} columns wide, the image {}",
+                name, self.width, data.width
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reference value of `band` at column `x`.
+    fn value(&self, band: usize, x: usize) -> f32 {
+        self.bands[band][if self.width == 1 { 0 } else { x }]
+    }
+}
+
+impl std::fmt::Debug for ReferenceFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ReferenceFrame({} bands x {} columns)",
+            self.bands.len(),
+            self.width
+        )
+    }
+}
+
+/// Calibration of one image with its references loaded.
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    /// Reference files and factors (recorded in the calibrated data)
+    pub source: CalibrationSource,
+    /// Dark-current reference (0 without)
+    dark: Option<Arc<ReferenceFrame>>,
+    /// White reference (no normalization without)
+    white: Option<Arc<ReferenceFrame>>,
+}
+
+impl Calibration {
+    /// Create a calibration from its source and the loaded reference frames.
+    pub fn new(
+        source: CalibrationSource,
+        dark: Option<Arc<ReferenceFrame>>,
+        white: Option<Arc<ReferenceFrame>>,
+    ) -> Self {
+        Self {
+            source,
+            dark,
+            white,
+        }
+    }
+
+    /// Calibrate `data` to `(raw - dark) / (white - dark) * gain + offset`.
+    ///
+    /// Samples where white and dark are equal become NaN.
+    pub fn apply(&self, mut data: HyperspectralData) -> Result<HyperspectralData, String> {
+        if let Some(dark) = &self.dark {
+            dark.check("Dark", &data)?;
+        }
+        if let Some(white) = &self.white {
+            white.check("White", &data)?;
+        }
+        let num_bands = data.bands.len();
+        let gains = expand_factors("gains", &self.source.gain, num_bands, 1.0)?;
+        let offsets = expand_factors("offsets", &self.source.offset, num_bands, 0.0)?;
+
+        let width = (data.width as usize).max(1);
+        for (b, band) in data.bands.iter_mut().enumerate() {
+            for (i, value) in band.iter_mut().enumerate() {
+                let x = i % width;
+                let dark = self.dark.as_ref().map_or(0.0, |d| d.value(b, x));
+                let mut calibrated = *value - dark;
+                if let Some(white) = &self.white {
+                    let range = white.value(b, x) - dark;
+                    calibrated = if range == 0.0 {
+                        f32::NAN
+                    } else {
+                        calibrated / range
+                    };
+                }
+                *value = calibrated * gains[b] + offsets[b];
+            }
+        }
+        data.calibration = Some(self.source.clone());
+        Ok(data)
+    }
+}
+
+impl DataProcessor for Calibration {
+    fn id(&self) -> &'static str {
+        "calibration"
+    }
+
+    fn process(&self, data: HyperspectralData) -> Result<HyperspectralData, String> {
+        self.apply(data)
+    }
+}
+
+/// One factor per band from a per-band list or a single shared value.
+fn expand_factors(
+    name: &str,
+    values: &[f32],
+    num_bands: usize,
+    default: f32,
+) -> Result<Vec<f32>, String> {
+    match values {
+        [] => Ok(vec![default; num_bands]),
+        [value] => Ok(vec![*value; num_bands]),
+        values if values.len() == num_bands => Ok(values.to_vec()),
+        values => Err(format!(
+            "Calibration has {} {} for {} bands",
+            values.len(),
+            name,
+            num_bands
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two bands of 2x2 pixels.
+    fn cube(band0: [f32; 4], band1: [f32; 4]) -> HyperspectralData {
+        HyperspectralData::new(
+            vec![band0.to_vec(), band1.to_vec()],
+            2,
+            2,
+            vec!["a".to_string(), "b".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_resolve_references() {
+        let rule = CalibrationRule {
+            pattern: "*.hdr".to_string(),
+            dark: Some("DARKREF_{stem}.hdr".to_string()),
+            white: Some("/lab/white.hdr".to_string()),
+            gain: vec![2.0],
+            offset: Vec::new(),
+        };
+        let source = rule.resolve(Path::new("/data/scans/leaf_01.hdr"));
+        assert_eq!(
+            source.dark,
+            Some(PathBuf::from("/data/scans/DARKREF_leaf_01.hdr"))
+        );
+        assert_eq!(source.white, Some(PathBuf::from("/lab/white.hdr")));
+        assert_eq!(source.gain, vec![2.0]);
+        assert_eq!(
+            source.describe(),
+            "Calibrated (dark DARKREF_leaf_01.hdr, white white.hdr, gain/offset)"
+        );
+        assert_eq!(
+            rule.describe(),
+            "*.hdr -> dark DARKREF_{stem}.hdr, white /lab/white.hdr, gain 2"
+        );
+    }
+
+    #[test]
+    fn test_parse_factors() {
+        assert_eq!(parse_factors(""), Ok(Vec::new()));
+        assert_eq!(parse_factors("1.5"), Ok(vec![1.5]));
+        assert_eq!(parse_factors("0.5, 2 ,3"), Ok(vec![0.5, 2.0, 3.0]));
+        assert!(parse_factors("1, x").is_err());
+        assert!(parse_factors("inf").is_err());
+        assert_eq!(format_factors(&[0.5, 2.0]), "0.5, 2");
+    }
+
+    #[test]
+    fn test_reference_frame_column_means() {
+        let frame = ReferenceFrame::from_cube(&cube([1.0, 2.0, 3.0, f32::NAN], [0.0; 4]));
+        assert_eq!(frame.width, 2);
+        // Column 1 only has one finite sample
+        assert_eq!(frame.bands[0], vec![2.0, 2.0]);
+        assert_eq!(frame.bands[1], vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_apply_dark_white() {
+        let source = CalibrationSource {
+            dark: Some(PathBuf::from("dark.hdr")),
+            white: Some(PathBuf::from("white.hdr")),
+            gain: vec![1.0, 2.0],
+            offset: vec![0.5],
+        };
+        let dark = ReferenceFrame::from_cube(&cube([10.0, 20.0, 10.0, 20.0], [0.0; 4]));
+        let white = ReferenceFrame::from_cube(&cube([110.0, 20.0, 110.0, 20.0], [4.0; 4]));
+        let calibration = Calibration::new(source.clone(), Some(dark.into()), Some(white.into()));
+
+        let raw = cube([60.0, 25.0, 110.0, 20.0], [1.0, 2.0, 3.0, 4.0]);
+        let result = calibration.apply(raw).unwrap();
+        // Band 0: (raw - dark) / (white - dark) + 0.5; column 1 has no range
+        assert_eq!(result.bands[0][0], 1.0);
+        assert_eq!(result.bands[0][2], 1.5);
+        assert!(result.bands[0][1].is_nan());
+        // Band 1: raw / 4 * 2 + 0.5
+        assert_eq!(result.bands[1], vec![1.0, 1.5, 2.0, 2.5]);
+        assert_eq!(result.calibration, Some(source));
+    }
+
+    #[test]
+    fn test_apply_mismatch() {
+        let single_column = ReferenceFrame {
+            bands: vec![vec![1.0], vec![2.0]],
+            width: 1,
+        };
+        let source = CalibrationSource {
+            dark: None,
+            white: None,
+            gain: Vec::new(),
+            offset: Vec::new(),
+        };
+        // A single column applies to all columns
+        let calibration = Calibration::new(source.clone(), Some(single_column.into()), None);
+        let result = calibration.apply(cube([1.0; 4], [3.0; 4])).unwrap();
+        assert_eq!(result.bands, vec![vec![0.0; 4], vec![1.0; 4]]);
+
+        let narrow = ReferenceFrame {
+            bands: vec![vec![0.0; 3]; 2],
+            width: 3,
+        };
+        let calibration = Calibration::new(source.clone(), None, Some(narrow.into()));
+        assert!(calibration.apply(cube([1.0; 4], [1.0; 4])).is_err());
+
+        let gains = CalibrationSource {
+            gain: vec![1.0, 2.0, 3.0],
+            ..source
+        };
+        let calibration = Calibration::new(gains, None, None);
+        assert!(calibration.apply(cube([1.0; 4], [1.0; 4])).is_err());
+    }
+}
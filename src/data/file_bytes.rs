@@ -0,0 +1,345 @@
+//! File contents held in memory or left on disk.
+//!
+//! Raw payloads on disk (native only) are not read up front: loaders that
+//! decode them in place go through a `ByteReader`, which reads the pages
+//! holding the samples it is asked for with positioned reads. Decoding a
+//! band subset of a large raw cube therefore does not pull the whole file
+//! into RAM. Other files are read whole, since their loaders do so anyway.
+
+use std::cell::RefCell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::{HashMap, VecDeque};
+use std::io;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{Read, Seek, SeekFrom};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Size of the blocks a `ByteReader` reads from disk.
+#[cfg(not(target_arch = "wasm32"))]
+const PAGE_SIZE: usize = 16 * 1024;
+
+/// Pages a `ByteReader` keeps (64 MiB). Band-sequential payloads touch one
+/// page per selected band for each run of pixels, so this bounds the bands
+/// that decode without re-reading pages.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_CACHED_PAGES: usize = 4096;
+
+/// Bytes of a file, in memory or still on disk.
+///
+/// Cloning an open file shares its handle; cloning owned bytes copies them.
+#[derive(Clone)]
+pub enum FileBytes {
+    /// Bytes read into memory (uploads, ZIP imports, WASM)
+    Owned(Vec<u8>),
+    /// File on disk, read on demand
+    #[cfg(not(target_arch = "wasm32"))]
+    File(Arc<OpenFile>),
+}
+
+/// Read-only handle to a file on disk and its length when opened.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct OpenFile {
+    /// Locked for each seek + read, so clones can read concurrently
+    file: Mutex<std::fs::File>,
+    len: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl OpenFile {
+    /// Read `buf.len()` bytes starting at `offset`.
+    fn read_exact_at(&self, offset: usize, buf: &mut [u8]) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        file.seek(SeekFrom::Start(offset as u64))?;
+        file.read_exact(buf)
+    }
+}
+
+impl FileBytes {
+    /// Open the file at `path` without reading it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open(path: &std::path::Path) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large"))?;
+        Ok(Self::File(Arc::new(OpenFile {
+            file: Mutex::new(file),
+            len,
+        })))
+    }
+
+    /// Length of the file in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Owned(data) => data.len(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(file) => file.len,
+        }
+    }
+
+    /// Whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the bytes are still on disk rather than in memory.
+    pub fn is_on_disk(&self) -> bool {
+        match self {
+            Self::Owned(_) => false,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(_) => true,
+        }
+    }
+
+    /// The bytes, if they are in memory.
+    pub fn as_slice(&self) -> Option<&[u8]> {
+        match self {
+            Self::Owned(data) => Some(data),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(_) => None,
+        }
+    }
+
+    /// Random access to the bytes, for decoding parts of them.
+    pub fn reader(&self) -> ByteReader<'_> {
+        match self {
+            Self::Owned(data) => ByteReader::new(data),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(file) => ByteReader::with_source(Source::File(file)),
+        }
+    }
+
+    /// Copy of all bytes (reads a file on disk).
+    pub fn read_all(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Owned(data) => Ok(data.clone()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(file) => {
+                let mut data = vec![0; file.len];
+                file.read_exact_at(0, &mut data)?;
+                Ok(data)
+            }
+        }
+    }
+
+    /// Take the bytes as a vector (reads a file on disk).
+    pub fn into_vec(self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Owned(data) => Ok(data),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(_) => self.read_all(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for FileBytes {
+    fn from(data: Vec<u8>) -> Self {
+        Self::Owned(data)
+    }
+}
+
+impl std::fmt::Debug for FileBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.is_on_disk() { "File" } else { "Owned" };
+        write!(f, "FileBytes::{}({} bytes)", kind, self.len())
+    }
+}
+
+/// Where a `ByteReader` reads from.
+enum Source<'a> {
+    Slice(&'a [u8]),
+    #[cfg(not(target_arch = "wasm32"))]
+    File(&'a OpenFile),
+}
+
+/// Random access reader for decoders that pick scattered samples.
+///
+/// Reads of a file on disk go through a cache of recently read pages. A
+/// failed read does not interrupt decoding: its bytes read as zero and the
+/// error is returned by the next `check`.
+pub struct ByteReader<'a> {
+    source: Source<'a>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pages: RefCell<PageCache>,
+    error: RefCell<Option<io::Error>>,
+}
+
+impl<'a> ByteReader<'a> {
+    /// Reader over bytes in memory.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_source(Source::Slice(data))
+    }
+
+    fn with_source(source: Source<'a>) -> Self {
+        Self {
+            source,
+            #[cfg(not(target_arch = "wasm32"))]
+            pages: RefCell::default(),
+            error: RefCell::default(),
+        }
+    }
+
+    /// Total length of the bytes.
+    pub fn len(&self) -> usize {
+        match self.source {
+            Source::Slice(data) => data.len(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Source::File(file) => file.len,
+        }
+    }
+
+    /// Whether there are no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fill `buf` with the bytes starting at `offset`.
+    pub fn read(&self, offset: usize, buf: &mut [u8]) {
+        let result = match self.source {
+            Source::Slice(data) => match data.get(offset..offset + buf.len()) {
+                Some(bytes) => {
+                    buf.copy_from_slice(bytes);
+                    Ok(())
+                }
+                None => Err(io::ErrorKind::UnexpectedEof.into()),
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            Source::File(file) => self.read_pages(file, offset, buf),
+        };
+        if let Err(e) = result {
+            buf.fill(0);
+            self.error.borrow_mut().get_or_insert(e);
+        }
+    }
+
+    /// Copy the bytes at `offset` out of cached pages, reading missing ones.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_pages(&self, file: &OpenFile, offset: usize, buf: &mut [u8]) -> io::Result<()> {
+        if offset + buf.len() > file.len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut pages = self.pages.borrow_mut();
+        let mut done = 0;
+        while done < buf.len() {
+            let position = offset + done;
+            let index = position / PAGE_SIZE;
+            let page = pages.get_or_read(index, |page| {
+                let start = index * PAGE_SIZE;
+                page.resize(PAGE_SIZE.min(file.len - start), 0);
+                file.read_exact_at(start, page)
+            })?;
+            let start = position - index * PAGE_SIZE;
+            let count = (buf.len() - done).min(page.len() - start);
+            buf[done..done + count].copy_from_slice(&page[start..start + count]);
+            done += count;
+        }
+        Ok(())
+    }
+
+    /// Return the first read error since the last check, if any.
+    pub fn check(&self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Pages of a file on disk, evicted in the order they were read.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct PageCache {
+    pages: HashMap<usize, Vec<u8>>,
+    order: VecDeque<usize>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PageCache {
+    /// The page at `index`, filled by `read` if it is not cached.
+    fn get_or_read(
+        &mut self,
+        index: usize,
+        read: impl FnOnce(&mut Vec<u8>) -> io::Result<()>,
+    ) -> io::Result<&[u8]> {
+        if !self.pages.contains_key(&index) {
+            let mut page = if self.order.len() >= MAX_CACHED_PAGES {
+                let oldest = self.order.pop_front().unwrap_or_default();
+                self.pages.remove(&oldest).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            read(&mut page)?;
+            self.pages.insert(index, page);
+            self.order.push_back(index);
+        }
+        Ok(&self.pages[&index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owned_bytes() {
+        let bytes = FileBytes::from(vec![1, 2, 3]);
+        assert!(!bytes.is_on_disk());
+        assert_eq!(bytes.as_slice(), Some(&[1u8, 2, 3][..]));
+
+        let reader = bytes.reader();
+        let mut buf = [0; 2];
+        reader.read(1, &mut buf);
+        assert_eq!(buf, [2, 3]);
+        reader.read(2, &mut buf);
+        assert_eq!(buf, [0, 0]);
+        assert_eq!(
+            reader.check().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        assert_eq!(bytes.into_vec().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_read_file() {
+        let dir = std::env::temp_dir().join(format!("hvat_file_bytes_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cube.raw");
+        let data: Vec<u8> = (0..PAGE_SIZE * 3 + 10).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let bytes = FileBytes::open(&path).unwrap();
+        let missing = FileBytes::open(&dir.join("missing.raw"));
+
+        // Reads within a page, across pages and up to the end of the file
+        let ranges = [(5, 4), (PAGE_SIZE - 2, 4), (PAGE_SIZE * 3 + 6, 4)];
+        let reader = bytes.reader();
+        let reads: Vec<Vec<u8>> = ranges
+            .iter()
+            .map(|&(offset, len)| {
+                let mut buf = vec![0; len];
+                reader.read(offset, &mut buf);
+                buf
+            })
+            .collect();
+        let in_range = reader.check();
+        let reader = bytes.reader();
+        reader.read(data.len() - 1, &mut [0; 2]);
+        let past_end = reader.check();
+        let all = bytes.clone().into_vec();
+        let (on_disk, len) = (bytes.is_on_disk(), bytes.len());
+        drop(bytes);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(on_disk);
+        assert_eq!(len, data.len());
+        assert!(missing.is_err());
+        for ((offset, len), read) in ranges.into_iter().zip(reads) {
+            assert_eq!(read, data[offset..offset + len]);
+        }
+        assert!(in_range.is_ok());
+        assert!(past_end.is_err());
+        assert_eq!(all.unwrap(), data);
+    }
+}
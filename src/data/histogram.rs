@@ -0,0 +1,170 @@
+//! Per-band value histograms.
+//!
+//! Computed once when an image is decoded and used for contrast stretching.
+//! Non-finite values (e.g. NaN no-data pixels) are ignored.
+
+/// Number of bins in a band histogram.
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// Value distribution of one band.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandHistogram {
+    /// Smallest finite value
+    pub min: f32,
+    /// Largest finite value
+    pub max: f32,
+    /// Mean of the finite values
+    pub mean: f32,
+    /// Standard deviation of the finite values
+    pub std_dev: f32,
+    /// Value counts in `HISTOGRAM_BINS` equal-width bins from `min` to `max`
+    pub counts: Vec<u32>,
+}
+
+impl BandHistogram {
+    /// Build the histogram of one band.
+    pub fn from_values(values: &[f32]) -> Self {
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        let mut sum = 0.0f64;
+        let mut sum_sq = 0.0f64;
+        let mut n = 0u64;
+        for &v in values.iter().filter(|v| v.is_finite()) {
+            min = min.min(v);
+            max = max.max(v);
+            sum += f64::from(v);
+            sum_sq += f64::from(v) * f64::from(v);
+            n += 1;
+        }
+
+        let mut counts = vec![0u32; HISTOGRAM_BINS];
+        if n == 0 {
+            return Self {
+                min: 0.0,
+                max: 0.0,
+                mean: 0.0,
+                std_dev: 0.0,
+                counts,
+            };
+        }
+
+        let scale = if max > min {
+            HISTOGRAM_BINS as f32 / (max - min)
+        } else {
+            0.0
+        };
+        for &v in values.iter().filter(|v| v.is_finite()) {
+            let bin = (((v - min) * scale) as usize).min(HISTOGRAM_BINS - 1);
+            counts[bin] += 1;
+        }
+
+        let mean = sum / n as f64;
+        let variance = (sum_sq / n as f64 - mean * mean).max(0.0);
+        Self {
+            min,
+            max,
+            mean: mean as f32,
+            std_dev: variance.sqrt() as f32,
+            counts,
+        }
+    }
+
+    /// Number of values counted.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&c| u64::from(c)).sum()
+    }
+
+    /// Width of one bin.
+    pub fn bin_width(&self) -> f32 {
+        (self.max - self.min) / self.counts.len() as f32
+    }
+
+    /// Value below which `percent` (0-100) of the values fall.
+    ///
+    /// Interpolates linearly within the bin containing the percentile.
+    pub fn percentile(&self, percent: f32) -> f32 {
+        let total = self.total();
+        if total == 0 || self.max <= self.min {
+            return self.min;
+        }
+        let target = (percent.clamp(0.0, 100.0) / 100.0) as f64 * total as f64;
+        let mut cumulative = 0.0f64;
+        for (bin, &count) in self.counts.iter().enumerate() {
+            let next = cumulative + f64::from(count);
+            if next >= target && count > 0 {
+                let fraction = ((target - cumulative) / f64::from(count)) as f32;
+                return self.min + (bin as f32 + fraction) * self.bin_width();
+            }
+            cumulative = next;
+        }
+        self.max
+    }
+
+    /// Fraction of values (0-1) at or below `value`.
+    pub fn cdf(&self, value: f32) -> f32 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        if value >= self.max {
+            return 1.0;
+        }
+        if value < self.min {
+            return 0.0;
+        }
+        let position = (value - self.min) / self.bin_width();
+        let bin = (position as usize).min(self.counts.len() - 1);
+        let below: u64 = self.counts[..bin].iter().map(|&c| u64::from(c)).sum();
+        let partial = f64::from(self.counts[bin]) * f64::from(position - bin as f32);
+        ((below as f64 + partial) / total as f64) as f32
+    }
+}
+
+/// Histograms of all bands of an image.
+pub fn band_histograms(bands: &[Vec<f32>]) -> Vec<BandHistogram> {
+    bands
+        .iter()
+        .map(|band| BandHistogram::from_values(band))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics() {
+        let hist = BandHistogram::from_values(&[1.0, 2.0, 3.0, 4.0, f32::NAN]);
+        assert_eq!(hist.min, 1.0);
+        assert_eq!(hist.max, 4.0);
+        assert_eq!(hist.mean, 2.5);
+        assert!((hist.std_dev - 1.118).abs() < 0.001);
+        assert_eq!(hist.total(), 4);
+        assert_eq!(hist.counts[0], 1);
+        assert_eq!(hist.counts[HISTOGRAM_BINS - 1], 1);
+    }
+
+    #[test]
+    fn test_percentile_and_cdf() {
+        let values: Vec<f32> = (0..1000).map(|v| v as f32 / 999.0).collect();
+        let hist = BandHistogram::from_values(&values);
+        assert!((hist.percentile(2.0) - 0.02).abs() < 0.005);
+        assert!((hist.percentile(98.0) - 0.98).abs() < 0.005);
+        assert_eq!(hist.percentile(0.0), 0.0);
+        assert!((hist.cdf(0.5) - 0.5).abs() < 0.005);
+        assert_eq!(hist.cdf(1.0), 1.0);
+        assert_eq!(hist.cdf(-1.0), 0.0);
+    }
+
+    #[test]
+    fn test_empty_and_constant() {
+        let empty = BandHistogram::from_values(&[f32::NAN]);
+        assert_eq!(empty.total(), 0);
+        assert_eq!(empty.percentile(50.0), 0.0);
+
+        let constant = BandHistogram::from_values(&[0.5; 10]);
+        assert_eq!(constant.counts[0], 10);
+        assert_eq!(constant.percentile(98.0), 0.5);
+        assert_eq!(constant.cdf(0.5), 1.0);
+    }
+}
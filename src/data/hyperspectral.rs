@@ -1,6 +1,30 @@
 //! Hyperspectral image data structure and loading.
 
-use crate::data::LoaderRegistry;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::subset::decode_subset;
+use crate::data::{
+    BandInfo, CalibrationSource, DataProcessor, FileBytes, FileResolver, LoadSubset, LoaderOptions,
+    LoaderRegistry,
+};
+
+/// Affine pixel-to-map transform of a georeferenced image.
+///
+/// Coefficients use the GDAL ordering `[x0, dx, rx, y0, ry, dy]`:
+/// `map_x = x0 + px * dx + py * rx`, `map_y = y0 + px * ry + py * dy`,
+/// where `(px, py)` are pixel coordinates of the top-left pixel corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoTransform(pub [f64; 6]);
+
+impl GeoTransform {
+    /// Convert pixel coordinates to map coordinates.
+    pub fn pixel_to_map(&self, px: f64, py: f64) -> (f64, f64) {
+        let [x0, dx, rx, y0, ry, dy] = self.0;
+        (x0 + px * dx + py * rx, y0 + px * ry + py * dy)
+    }
+}
 
 /// CPU-side hyperspectral data, used for initial upload to GPU.
 #[derive(Clone)]
@@ -11,19 +35,93 @@ pub struct HyperspectralData {
     pub width: u32,
     /// Image height in pixels
     pub height: u32,
-    /// Band labels (e.g., wavelength names)
-    #[allow(dead_code)] // Reserved for future use (band labels in UI)
-    pub labels: Vec<String>,
+    /// Per-band metadata (name, wavelength, FWHM, bad-band flag)
+    pub band_info: Vec<BandInfo>,
+    /// Pixel-to-map transform for georeferenced sources (e.g. GeoTIFF)
+    pub geotransform: Option<GeoTransform>,
+    /// Radiometric calibration applied while loading
+    pub calibration: Option<CalibrationSource>,
+    /// Non-fatal issues found while decoding (shown to the user)
+    pub warnings: Vec<String>,
 }
 
 impl HyperspectralData {
     /// Create a new hyperspectral image with the given dimensions and bands.
+    ///
+    /// `labels` become the band names; loaders add wavelengths afterwards.
     pub fn new(bands: Vec<Vec<f32>>, width: u32, height: u32, labels: Vec<String>) -> Self {
         Self {
             bands,
             width,
             height,
-            labels,
+            band_info: labels.into_iter().map(BandInfo::named).collect(),
+            geotransform: None,
+            calibration: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Replace the band metadata read from `source`.
+    ///
+    /// Metadata whose length does not match the band count is ignored and
+    /// reported as a warning. Returns whether it was applied.
+    pub fn set_band_info(&mut self, band_info: Vec<BandInfo>, source: &str) -> bool {
+        if band_info.len() != self.bands.len() {
+            let warning = format!(
+                "Ignoring band metadata from {} ({} entries for {} bands)",
+                source,
+                band_info.len(),
+                self.bands.len()
+            );
+            log::warn!("{}", warning);
+            self.warnings.push(warning);
+            return false;
+        }
+        self.band_info = band_info;
+        true
+    }
+
+    /// Attach a pixel-to-map transform.
+    pub fn with_geotransform(mut self, geotransform: Option<GeoTransform>) -> Self {
+        self.geotransform = geotransform;
+        self
+    }
+
+    /// Keep only the bands and resolution selected by `subset`.
+    ///
+    /// Used for formats that cannot decode a subset directly. Band metadata
+    /// follows the kept bands and the geotransform is scaled to the binned
+    /// pixel size.
+    pub fn subset(self, subset: &LoadSubset) -> Self {
+        if subset.is_full() {
+            return self;
+        }
+        let width = self.width as usize;
+        let decoded = decode_subset(
+            subset,
+            self.bands.len(),
+            width,
+            self.height as usize,
+            |b, x, y| self.bands[b][y * width + x],
+        );
+        let bin = f64::from(subset.binning.max(1));
+        let geotransform = self
+            .geotransform
+            .map(|GeoTransform([x0, dx, rx, y0, ry, dy])| {
+                GeoTransform([x0, dx * bin, rx * bin, y0, ry * bin, dy * bin])
+            });
+        Self {
+            band_info: decoded
+                .indices
+                .iter()
+                .filter_map(|&b| self.band_info.get(b).cloned())
+                .collect(),
+            bands: decoded.bands,
+            width: decoded.width as u32,
+            height: decoded.height as u32,
+            geotransform,
+            calibration: self.calibration,
+            warnings: self.warnings,
         }
     }
 
@@ -33,12 +131,12 @@ impl HyperspectralData {
     /// and just needs to be wrapped in a `HyperspectralData` struct.
     #[allow(dead_code)]
     pub fn from_raw_bands(bands: Vec<Vec<f32>>, width: u32, height: u32) -> Self {
-        let labels = (0..bands.len())
+        let band_info = (0..bands.len())
             .map(|i| match i {
-                0 => "Red".to_string(),
-                1 => "Green".to_string(),
-                2 => "Blue".to_string(),
-                _ => format!("Band {}", i + 1),
+                0 => BandInfo::named("Red"),
+                1 => BandInfo::named("Green"),
+                2 => BandInfo::named("Blue"),
+                _ => BandInfo::named(format!("Band {}", i + 1)),
             })
             .collect();
 
@@ -46,46 +144,109 @@ impl HyperspectralData {
             bands,
             width,
             height,
-            labels,
+            band_info,
+            geotransform: None,
+            calibration: None,
+            warnings: Vec::new(),
         }
     }
 
     /// Load from an image file (PNG, JPEG, etc).
     /// Converts RGB channels to 3 bands.
     ///
-    /// Note: Prefer `from_bytes()` with `ProjectState::get_image_data()` for unified
+    /// Sidecar files (e.g. ENVI payloads) are read from the same folder.
+    ///
+    /// Note: Prefer `ProjectState::load_hyperspectral()` for unified
     /// cross-platform loading.
     #[cfg(not(target_arch = "wasm32"))]
     #[allow(dead_code)] // Kept for direct native file loading use cases
     pub fn from_image_file(path: &std::path::Path) -> Result<Self, String> {
-        let data = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let data = FileBytes::open(path).map_err(|e| format!("Failed to read file: {}", e))?;
         let filename = path.file_name().and_then(|n| n.to_str());
-        Self::from_bytes_with_hint(&data, filename)
+        let resolver = crate::data::FsResolver::for_file(path);
+        Self::from_bytes_with_resolver(&data, filename, &resolver, &LoaderOptions::default(), &[])
     }
 
-    /// Load from raw bytes, auto-detecting the format.
+    /// Load from file bytes with access to sibling files.
     ///
-    /// This is the preferred method for cross-platform loading.
-    /// Uses the `LoaderRegistry` to detect and load various formats including:
-    /// - Standard images (PNG, JPEG, BMP, TIFF, WebP)
-    /// - NumPy arrays (.npy)
-    ///
-    /// Use with `ProjectState::get_image_data()` for unified access.
-    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
-        Self::from_bytes_with_hint(data, None)
-    }
-
-    /// Load from raw bytes with an optional filename hint for format detection.
-    ///
-    /// The filename hint helps the loader registry choose the right loader
-    /// based on file extension before falling back to magic byte detection.
-    pub fn from_bytes_with_hint(data: &[u8], filename: Option<&str>) -> Result<Self, String> {
-        let registry = LoaderRegistry::new();
-        registry.load(data, filename).map_err(|e| e.to_string())
+    /// Required for multi-file formats such as ENVI, where `data` is the
+    /// header and the payload is read through `resolver`. Raw files left on
+    /// disk are read only where the loader decodes them. `options` carries
+    /// user loader settings (e.g. the `.npz` cube key); `processors` run on
+    /// the decoded data (e.g. the radiometric calibration).
+    pub fn from_bytes_with_resolver(
+        data: &FileBytes,
+        filename: Option<&str>,
+        resolver: &dyn FileResolver,
+        options: &LoaderOptions,
+        processors: &[Arc<dyn DataProcessor>],
+    ) -> Result<Self, String> {
+        let mut registry = LoaderRegistry::with_options(options);
+        for processor in processors {
+            registry.add_processor(processor.clone());
+        }
+        registry
+            .load_file(data, filename, resolver)
+            .map_err(|e| e.to_string())
     }
 
     /// Get the number of bands.
     pub fn num_bands(&self) -> usize {
         self.bands.len()
     }
+
+    /// Values of all bands at pixel (x, y), or `None` outside the image.
+    pub fn pixel_spectrum(&self, x: u32, y: u32) -> Option<Vec<f32>> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = y as usize * self.width as usize + x as usize;
+        self.bands
+            .iter()
+            .map(|band| band.get(index).copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_spectrum() {
+        // 2x2 image, band values encode band * 10 + pixel index
+        let bands = vec![vec![0.0, 1.0, 2.0, 3.0], vec![10.0, 11.0, 12.0, 13.0]];
+        let hyper = HyperspectralData::new(bands, 2, 2, Vec::new());
+        assert_eq!(hyper.pixel_spectrum(1, 0), Some(vec![1.0, 11.0]));
+        assert_eq!(hyper.pixel_spectrum(0, 1), Some(vec![2.0, 12.0]));
+        assert_eq!(hyper.pixel_spectrum(2, 0), None);
+        assert_eq!(hyper.pixel_spectrum(0, 2), None);
+    }
+
+    #[test]
+    fn test_subset() {
+        // 3 bands of 2x2 pixels
+        let bands = vec![
+            vec![0.0, 1.0, 2.0, 3.0],
+            vec![10.0, 11.0, 12.0, 13.0],
+            vec![20.0, 21.0, 22.0, 23.0],
+        ];
+        let labels = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let hyper = HyperspectralData::new(bands, 2, 2, labels)
+            .with_geotransform(Some(GeoTransform([100.0, 0.5, 0.0, 200.0, 0.0, -0.5])));
+
+        let subset = LoadSubset {
+            bands: None,
+            band_stride: 2,
+            binning: 2,
+        };
+        let binned = hyper.subset(&subset);
+        assert_eq!((binned.width, binned.height), (1, 1));
+        assert_eq!(binned.bands, vec![vec![1.5], vec![21.5]]);
+        assert_eq!(binned.band_info[1].name, "c");
+        assert_eq!(
+            binned.geotransform.unwrap().pixel_to_map(1.0, 1.0),
+            (101.0, 199.0)
+        );
+    }
 }
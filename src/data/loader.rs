@@ -7,7 +7,36 @@
 //! ## Supported Formats
 //!
 //! - **Standard Images**: PNG, JPEG, BMP, TIFF, WebP (3-band RGB)
-//! - **NumPy Arrays**: `.npy` files with 2D (grayscale) or 3D (bands × H × W or H × W × bands) arrays
+//! - **TIFF / GeoTIFF**: any number of samples per pixel at native bit depth,
+//!   with the GeoTIFF geotransform when present
+//! - **NumPy Arrays**: `.npy` files with 2D (grayscale) or 3D arrays in a declared axis layout
+//!   (BWH/WHB/HWB/BHW, see `LoaderOptions::npy_layout`), guessed from the shape otherwise
+//! - **NumPy Archives**: `.npz` files; the cube is picked by key or as the largest 3D array
+//! - **ENVI**: `.hdr` header + binary payload (BSQ/BIL/BIP)
+//!
+//! ## Multi-file Formats
+//!
+//! Formats that split metadata and payload across files read their sidecars
+//! through a `FileResolver` passed to `load_with_resolver`. Loaders advertise
+//! which sibling names they may need via `sidecar_candidates`, so callers can
+//! group those files with the primary file (or prefetch them for background decoding).
+//!
+//! Raw payloads on disk are opened rather than read (`FileBytes`). Loaders
+//! listing them in `in_place_extensions` read only the samples they decode;
+//! the payload of a multi-file format comes from `FileResolver::open_sibling`,
+//! a primary file goes through `LoaderRegistry::load_file`.
+//!
+//! ## Band Subsets
+//!
+//! `LoaderOptions::subset` selects a band range, a band stride and a spatial
+//! binning. Loaders of raw layouts decode the subset themselves
+//! (`applies_subset`); the registry applies it to the output of all others.
+//!
+//! ## Processing
+//!
+//! `DataProcessor`s added with `LoaderRegistry::add_processor` transform the
+//! decoded data of every loader, after the band subset and before it is
+//! returned (e.g. radiometric calibration, see `Calibration`).
 //!
 //! ## Usage
 //!
@@ -15,10 +44,16 @@
 //! use hvat::data::{LoaderRegistry, HyperspectralData};
 //!
 //! let registry = LoaderRegistry::new();
-//! let data = registry.load_from_bytes(bytes, Some("image.npy"))?;
+//!
+//! // Multi-file formats resolve their sidecars next to the primary file
+//! let resolver = FsResolver::for_file(Path::new("scans/cube.hdr"));
+//! let data = registry.load_with_resolver(header_bytes, Some("cube.hdr"), &resolver)?;
 //! ```
 
-use crate::data::HyperspectralData;
+use std::sync::Arc;
+
+use crate::data::resolver::FileResolver;
+use crate::data::{FileBytes, HyperspectralData, LoadSubset, LoaderOptions};
 
 /// Error type for loader operations.
 #[derive(Debug, Clone)]
@@ -103,6 +138,76 @@ pub trait HyperspectralLoader: Send + Sync {
     /// * `Err(LoaderError)` - Loading failed with error details
     fn load(&self, data: &[u8]) -> Result<HyperspectralData, LoaderError>;
 
+    /// Load hyperspectral data with access to sibling files.
+    ///
+    /// Multi-file formats override this to read their sidecars through
+    /// `resolver`. The default ignores the resolver and calls `load()`.
+    ///
+    /// # Arguments
+    /// * `data` - Raw bytes of the primary file
+    /// * `filename` - Bare filename of the primary file (e.g. `"scene.hdr"`)
+    /// * `resolver` - Access to files next to the primary file
+    fn load_with_resolver(
+        &self,
+        data: &[u8],
+        filename: &str,
+        resolver: &dyn FileResolver,
+    ) -> Result<HyperspectralData, LoaderError> {
+        let _ = (filename, resolver);
+        self.load(data)
+    }
+
+    /// Load hyperspectral data from a primary file that may still be on disk.
+    ///
+    /// Loaders of files in `in_place_extensions` override this to read only
+    /// the bytes they decode. The default reads the whole file and calls
+    /// `load_with_resolver()`.
+    fn load_file(
+        &self,
+        data: &FileBytes,
+        filename: &str,
+        resolver: &dyn FileResolver,
+    ) -> Result<HyperspectralData, LoaderError> {
+        let data = data
+            .read_all()
+            .map_err(|e| LoaderError::new(format!("Failed to read {}: {}", filename, e)))?;
+        self.load_with_resolver(&data, filename, resolver)
+    }
+
+    /// Sibling filenames this loader may read for the given primary file.
+    ///
+    /// Used to group sidecars with their primary file and to prefetch them
+    /// when decoding off the main thread. Default is none.
+    fn sidecar_candidates(&self, filename: &str) -> Vec<String> {
+        let _ = filename;
+        Vec::new()
+    }
+
+    /// Extensions of files that only serve as sidecars for this format.
+    ///
+    /// These files are kept next to their primary file but never listed as
+    /// images on their own. Default is none.
+    fn sidecar_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Extensions of files this loader decodes in place, touching only the
+    /// bytes it needs (raw payloads).
+    ///
+    /// Such files are left on disk and read on demand; all others are read
+    /// into memory. Default is none.
+    fn in_place_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether this loader decodes the `LoadSubset` it was created with.
+    ///
+    /// The registry applies the subset to the output of loaders that do not.
+    /// Default is `false`.
+    fn applies_subset(&self) -> bool {
+        false
+    }
+
     /// Priority for format detection (higher = checked first).
     ///
     /// Used when multiple loaders claim to handle the same extension.
@@ -112,23 +217,55 @@ pub trait HyperspectralLoader: Send + Sync {
     }
 }
 
+/// Processing step applied to the output of every loader.
+pub trait DataProcessor: Send + Sync {
+    /// Identifier used in logs and errors (e.g., "calibration").
+    fn id(&self) -> &'static str;
+
+    /// Transform decoded data.
+    ///
+    /// An error fails the load; it is not retried with other loaders.
+    fn process(&self, data: HyperspectralData) -> Result<HyperspectralData, String>;
+}
+
 /// Registry of available hyperspectral data loaders.
 ///
 /// Provides format detection and unified loading interface.
 pub struct LoaderRegistry {
     loaders: Vec<Box<dyn HyperspectralLoader>>,
+    /// Bands and resolution to load
+    subset: LoadSubset,
+    /// Applied in order to all decoded data
+    processors: Vec<Arc<dyn DataProcessor>>,
 }
 
 impl LoaderRegistry {
-    /// Create a new registry with all built-in loaders.
+    /// Create a new registry with all built-in loaders and default options.
     pub fn new() -> Self {
+        Self::with_options(&LoaderOptions::default())
+    }
+
+    /// Create a registry with all built-in loaders configured by `options`.
+    pub fn with_options(options: &LoaderOptions) -> Self {
         let mut registry = Self {
             loaders: Vec::new(),
+            subset: options.subset,
+            processors: Vec::new(),
         };
 
         // Register built-in loaders (order matters for priority ties)
         registry.register(Box::new(super::loaders::ImageLoader));
-        registry.register(Box::new(super::loaders::NpyLoader));
+        registry.register(Box::new(
+            super::loaders::NpyLoader::new(options.npy_layout).with_subset(options.subset),
+        ));
+        registry.register(Box::new(super::loaders::NpzLoader::new(
+            options.npz_cube_key.clone(),
+            options.npy_layout,
+        )));
+        registry.register(Box::new(
+            super::loaders::EnviLoader::default().with_subset(options.subset),
+        ));
+        registry.register(Box::new(super::loaders::TiffLoader));
 
         // Sort by priority (highest first)
         registry
@@ -145,6 +282,11 @@ impl LoaderRegistry {
         self.loaders.sort_by(|a, b| b.priority().cmp(&a.priority()));
     }
 
+    /// Add a processing step run on all decoded data, after those added before.
+    pub fn add_processor(&mut self, processor: Arc<dyn DataProcessor>) {
+        self.processors.push(processor);
+    }
+
     /// Get all supported file extensions (for file filtering).
     pub fn supported_extensions(&self) -> Vec<&'static str> {
         let mut extensions: Vec<&'static str> = self
@@ -175,28 +317,54 @@ impl LoaderRegistry {
             .map(|l| l.as_ref())
     }
 
-    /// Load data, auto-detecting the format.
+    /// Apply the subset to data decoded by `loader`, unless it already did,
+    /// then run the processors.
+    fn finish(
+        &self,
+        loader: &dyn HyperspectralLoader,
+        mut data: HyperspectralData,
+    ) -> Result<HyperspectralData, LoaderError> {
+        if !loader.applies_subset() && !self.subset.is_full() {
+            log::debug!("Applying load subset to {} loader output", loader.id());
+            data = data.subset(&self.subset);
+        }
+        for processor in &self.processors {
+            log::debug!(
+                "Applying {} to {} loader output",
+                processor.id(),
+                loader.id()
+            );
+            data = processor
+                .process(data)
+                .map_err(|e| LoaderError::new(e).with_loader(processor.id()))?;
+        }
+        Ok(data)
+    }
+
+    /// Load data with access to sibling files, auto-detecting the format.
     ///
     /// Tries loaders in this order:
     /// 1. By file extension (if filename provided)
     /// 2. By magic byte detection
     /// 3. All loaders as fallback
-    pub fn load(
+    pub fn load_with_resolver(
         &self,
         data: &[u8],
         filename: Option<&str>,
+        resolver: &dyn FileResolver,
     ) -> Result<HyperspectralData, LoaderError> {
         // Extract extension from filename
         let extension = filename.and_then(|f| f.rsplit('.').next().map(|e| e.to_lowercase()));
+        let bare_name = filename.map(super::resolver::bare_filename).unwrap_or("");
 
         // Try loaders matching the extension first
         if let Some(ref ext) = extension {
             let matching_loaders = self.loaders_for_extension(ext);
             for loader in &matching_loaders {
-                match loader.load(data) {
+                match loader.load_with_resolver(data, bare_name, resolver) {
                     Ok(result) => {
                         log::debug!("Loaded with {} loader (by extension)", loader.id());
-                        return Ok(result);
+                        return self.finish(*loader, result);
                     }
                     Err(e) => {
                         log::trace!("Loader {} failed: {}", loader.id(), e);
@@ -207,10 +375,10 @@ impl LoaderRegistry {
 
         // Try magic byte detection
         if let Some(loader) = self.detect_loader(data) {
-            match loader.load(data) {
+            match loader.load_with_resolver(data, bare_name, resolver) {
                 Ok(result) => {
                     log::debug!("Loaded with {} loader (by detection)", loader.id());
-                    return Ok(result);
+                    return self.finish(loader, result);
                 }
                 Err(e) => {
                     log::trace!("Detected loader {} failed: {}", loader.id(), e);
@@ -220,9 +388,9 @@ impl LoaderRegistry {
 
         // Last resort: try all loaders
         for loader in &self.loaders {
-            if let Ok(result) = loader.load(data) {
+            if let Ok(result) = loader.load_with_resolver(data, bare_name, resolver) {
                 log::debug!("Loaded with {} loader (fallback)", loader.id());
-                return Ok(result);
+                return self.finish(loader.as_ref(), result);
             }
         }
 
@@ -234,6 +402,90 @@ impl LoaderRegistry {
         )))
     }
 
+    /// Load a primary file that may still be on disk (see `FileBytes`).
+    ///
+    /// A file on disk goes to the loaders for its extension that decode it in
+    /// place. If none of them can, or the bytes are in memory, the file is
+    /// loaded like `load_with_resolver`.
+    pub fn load_file(
+        &self,
+        data: &FileBytes,
+        filename: Option<&str>,
+        resolver: &dyn FileResolver,
+    ) -> Result<HyperspectralData, LoaderError> {
+        if let Some(bytes) = data.as_slice() {
+            return self.load_with_resolver(bytes, filename, resolver);
+        }
+
+        let bare_name = filename.map(super::resolver::bare_filename).unwrap_or("");
+        if let Some((_, ext)) = bare_name.rsplit_once('.') {
+            let ext = ext.to_lowercase();
+            for loader in self.loaders_for_extension(&ext) {
+                if !loader.in_place_extensions().contains(&ext.as_str()) {
+                    continue;
+                }
+                match loader.load_file(data, bare_name, resolver) {
+                    Ok(result) => {
+                        log::debug!("Loaded with {} loader (in place)", loader.id());
+                        return self.finish(loader, result);
+                    }
+                    Err(e) => {
+                        log::trace!("Loader {} failed: {}", loader.id(), e);
+                    }
+                }
+            }
+        }
+
+        let bytes = data
+            .read_all()
+            .map_err(|e| LoaderError::new(format!("Failed to read file: {}", e)))?;
+        self.load_with_resolver(&bytes, filename, resolver)
+    }
+
+    /// Sibling filenames that may belong to the given primary file.
+    ///
+    /// Collected from all loaders handling the file's extension.
+    pub fn sidecar_candidates(&self, filename: &str) -> Vec<String> {
+        let bare_name = super::resolver::bare_filename(filename);
+        let Some((_, ext)) = bare_name.rsplit_once('.') else {
+            return Vec::new();
+        };
+
+        let mut candidates: Vec<String> = Vec::new();
+        for loader in self.loaders_for_extension(ext) {
+            for name in loader.sidecar_candidates(bare_name) {
+                if !candidates.contains(&name) {
+                    candidates.push(name);
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Check if a filename is a sidecar-only file (e.g. an ENVI payload).
+    ///
+    /// Sidecars should be kept with in-memory file sets but not listed as images.
+    pub fn is_sidecar_file(&self, filename: &str) -> bool {
+        if self.is_supported_file(filename) {
+            return false;
+        }
+        let lower = filename.to_lowercase();
+        self.loaders
+            .iter()
+            .flat_map(|l| l.sidecar_extensions().iter())
+            .any(|ext| lower.ends_with(&format!(".{}", ext)))
+    }
+
+    /// Check if a filename is a raw payload that a loader decodes in place,
+    /// so it is worth leaving on disk rather than reading.
+    pub fn is_in_place_file(&self, filename: &str) -> bool {
+        let lower = filename.to_lowercase();
+        self.loaders
+            .iter()
+            .flat_map(|l| l.in_place_extensions().iter())
+            .any(|ext| lower.ends_with(&format!(".{}", ext)))
+    }
+
     /// Check if a filename has a supported extension.
     pub fn is_supported_file(&self, filename: &str) -> bool {
         let lower = filename.to_lowercase();
@@ -257,6 +509,7 @@ impl Default for LoaderRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::MemoryResolver;
 
     #[test]
     fn test_registry_has_loaders() {
@@ -275,6 +528,11 @@ mod tests {
 
         // Should include npy
         assert!(extensions.contains(&"npy"));
+        assert!(extensions.contains(&"npz"));
+
+        // Should include ENVI headers (payload files are not listed)
+        assert!(extensions.contains(&"hdr"));
+        assert!(!extensions.contains(&"raw"));
     }
 
     #[test]
@@ -286,4 +544,136 @@ mod tests {
         assert!(registry.is_supported_file("IMAGE.PNG")); // case insensitive
         assert!(!registry.is_supported_file("document.pdf"));
     }
+
+    #[test]
+    fn test_sidecar_files() {
+        let registry = LoaderRegistry::new();
+
+        assert!(registry.is_sidecar_file("cube.raw"));
+        assert!(registry.is_sidecar_file("scans/CUBE.BIL"));
+        assert!(!registry.is_sidecar_file("cube.hdr"));
+        assert!(!registry.is_sidecar_file("image.png"));
+
+        let candidates = registry.sidecar_candidates("scans/cube.hdr");
+        assert!(candidates.contains(&"cube.raw".to_string()));
+        assert!(registry.sidecar_candidates("image.png").is_empty());
+    }
+
+    #[test]
+    fn test_in_place_files() {
+        let registry = LoaderRegistry::new();
+
+        // Raw payloads are decoded in place
+        assert!(registry.is_in_place_file("cube.npy"));
+        assert!(registry.is_in_place_file("scans/CUBE.BIL"));
+        // Headers, compressed and text files are read whole
+        assert!(!registry.is_in_place_file("cube.hdr"));
+        assert!(!registry.is_in_place_file("cube.npz"));
+        assert!(!registry.is_in_place_file("image.png"));
+        assert!(!registry.is_in_place_file("cube.wavelengths.txt"));
+    }
+
+    /// Loader returning a fixed 3-band 2x2 image.
+    struct FixedLoader;
+
+    impl HyperspectralLoader for FixedLoader {
+        fn id(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Fixed"
+        }
+
+        fn extensions(&self) -> &'static [&'static str] {
+            &["fixed"]
+        }
+
+        fn can_load(&self, _data: &[u8]) -> bool {
+            false
+        }
+
+        fn load(&self, _data: &[u8]) -> Result<HyperspectralData, LoaderError> {
+            let bands = (0..3).map(|b| vec![b as f32; 4]).collect();
+            Ok(HyperspectralData::new(bands, 2, 2, Vec::new()))
+        }
+    }
+
+    #[test]
+    fn test_subset_applied_to_loader_output() {
+        let options = LoaderOptions {
+            subset: LoadSubset {
+                bands: Some((1, 2)),
+                band_stride: 1,
+                binning: 2,
+            },
+            ..Default::default()
+        };
+        let mut registry = LoaderRegistry::with_options(&options);
+        registry.register(Box::new(FixedLoader));
+
+        let data = registry
+            .load_with_resolver(&[], Some("image.fixed"), &MemoryResolver::default())
+            .unwrap();
+        assert_eq!((data.width, data.height), (1, 1));
+        assert_eq!(data.bands, vec![vec![1.0], vec![2.0]]);
+    }
+
+    /// Processor scaling all values.
+    struct Scale(f32);
+
+    impl DataProcessor for Scale {
+        fn id(&self) -> &'static str {
+            "scale"
+        }
+
+        fn process(&self, mut data: HyperspectralData) -> Result<HyperspectralData, String> {
+            for value in data.bands.iter_mut().flatten() {
+                *value *= self.0;
+            }
+            Ok(data)
+        }
+    }
+
+    /// Processor rejecting all data.
+    struct Reject;
+
+    impl DataProcessor for Reject {
+        fn id(&self) -> &'static str {
+            "reject"
+        }
+
+        fn process(&self, _data: HyperspectralData) -> Result<HyperspectralData, String> {
+            Err("rejected".to_string())
+        }
+    }
+
+    #[test]
+    fn test_processors_run_after_subset() {
+        let options = LoaderOptions {
+            subset: LoadSubset {
+                bands: Some((2, 2)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut registry = LoaderRegistry::with_options(&options);
+        registry.register(Box::new(FixedLoader));
+        registry.add_processor(Arc::new(Scale(2.0)));
+        registry.add_processor(Arc::new(Scale(3.0)));
+
+        let data = registry
+            .load_with_resolver(&[], Some("image.fixed"), &MemoryResolver::default())
+            .unwrap();
+        assert_eq!(data.bands, vec![vec![12.0; 4]]);
+
+        // A failing processor fails the load
+        registry.add_processor(Arc::new(Reject));
+        let Err(error) =
+            registry.load_with_resolver(&[], Some("image.fixed"), &MemoryResolver::default())
+        else {
+            panic!("expected the processor to fail the load");
+        };
+        assert_eq!(error.loader_id, Some("reject"));
+    }
 }
@@ -0,0 +1,865 @@
+//! Loader for ENVI hyperspectral files (`.hdr` + binary payload).
+//!
+//! ENVI stores a cube as two files: a plain-text header describing the
+//! layout (`scene.hdr`) and a headerless binary file with the samples
+//! (`scene`, `scene.raw`, `scene.img`, `scene.bsq`, `scene.bil`, `scene.bip`, ...).
+//! The header alone is not enough to decode the image, so the payload is
+//! read through the `FileResolver` passed to `load_with_resolver`.
+//!
+//! The payload is requested with `FileResolver::open_sibling`, so on disk it
+//! is left unread and only the samples of the `LoadSubset` are read from it.
+
+use crate::data::bands::unit_to_nm;
+use crate::data::file_bytes::ByteReader;
+use crate::data::loader::{HyperspectralLoader, LoaderError};
+use crate::data::resolver::FileResolver;
+use crate::data::subset::decode_subset;
+use crate::data::{BandInfo, HyperspectralData, LoadSubset};
+
+/// Sample data types supported by ENVI (`data type` header field).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnviDataType {
+    /// 1: 8-bit unsigned integer
+    U8,
+    /// 2: 16-bit signed integer
+    I16,
+    /// 3: 32-bit signed integer
+    I32,
+    /// 4: 32-bit float
+    F32,
+    /// 5: 64-bit float
+    F64,
+    /// 12: 16-bit unsigned integer
+    U16,
+    /// 13: 32-bit unsigned integer
+    U32,
+    /// 14: 64-bit signed integer
+    I64,
+    /// 15: 64-bit unsigned integer
+    U64,
+}
+
+impl EnviDataType {
+    /// Parse the numeric ENVI data type code.
+    ///
+    /// Complex types (6, 9) are not supported.
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(Self::U8),
+            2 => Some(Self::I16),
+            3 => Some(Self::I32),
+            4 => Some(Self::F32),
+            5 => Some(Self::F64),
+            12 => Some(Self::U16),
+            13 => Some(Self::U32),
+            14 => Some(Self::I64),
+            15 => Some(Self::U64),
+            _ => None,
+        }
+    }
+
+    /// Size of a single sample in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::I64 | Self::U64 | Self::F64 => 8,
+        }
+    }
+
+    /// Read one raw sample from `bytes` (exactly `size()` bytes long).
+    fn read(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! read_as {
+            ($ty:ty) => {{
+                let arr = bytes.try_into().expect("sample slice has the type's size");
+                if big_endian {
+                    <$ty>::from_be_bytes(arr) as f64
+                } else {
+                    <$ty>::from_le_bytes(arr) as f64
+                }
+            }};
+        }
+
+        match self {
+            Self::U8 => f64::from(bytes[0]),
+            Self::I16 => read_as!(i16),
+            Self::U16 => read_as!(u16),
+            Self::I32 => read_as!(i32),
+            Self::U32 => read_as!(u32),
+            Self::F32 => read_as!(f32),
+            Self::I64 => read_as!(i64),
+            Self::U64 => read_as!(u64),
+            Self::F64 => read_as!(f64),
+        }
+    }
+
+    /// Normalize a raw sample to the 0.0-1.0 range based on the data type.
+    ///
+    /// Uses the same convention as the NumPy loader: integers are mapped
+    /// over their full type range, floats are passed through unchanged.
+    fn normalize(self, value: f64) -> f32 {
+        let normalized = match self {
+            Self::U8 => value / f64::from(u8::MAX),
+            Self::U16 => value / f64::from(u16::MAX),
+            Self::I16 => (value + 32_768.0) / 65_535.0,
+            Self::U32 => value / f64::from(u32::MAX),
+            Self::I32 => (value + 2_147_483_648.0) / 4_294_967_295.0,
+            Self::U64 => value / u64::MAX as f64,
+            Self::I64 => (value - i64::MIN as f64) / (u64::MAX as f64),
+            Self::F32 | Self::F64 => value,
+        };
+        normalized as f32
+    }
+}
+
+/// Interleave (band ordering) of the binary payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnviInterleave {
+    /// Band sequential: `[band][line][sample]`
+    Bsq,
+    /// Band interleaved by line: `[line][band][sample]`
+    Bil,
+    /// Band interleaved by pixel: `[line][sample][band]`
+    Bip,
+}
+
+impl EnviInterleave {
+    /// Parse an interleave name (case-insensitive).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "bsq" => Some(Self::Bsq),
+            "bil" => Some(Self::Bil),
+            "bip" => Some(Self::Bip),
+            _ => None,
+        }
+    }
+
+    /// Sample index in the payload for (band, line, sample).
+    fn index(self, band: usize, line: usize, sample: usize, header: &EnviHeader) -> usize {
+        match self {
+            Self::Bsq => (band * header.lines + line) * header.samples + sample,
+            Self::Bil => (line * header.bands + band) * header.samples + sample,
+            Self::Bip => (line * header.samples + sample) * header.bands + band,
+        }
+    }
+}
+
+/// Parsed ENVI header.
+#[derive(Debug, Clone)]
+pub struct EnviHeader {
+    /// Number of samples per line (image width)
+    pub samples: usize,
+    /// Number of lines (image height)
+    pub lines: usize,
+    /// Number of spectral bands
+    pub bands: usize,
+    /// Bytes to skip at the start of the payload file
+    pub header_offset: usize,
+    /// Sample data type
+    pub data_type: EnviDataType,
+    /// Band ordering of the payload
+    pub interleave: EnviInterleave,
+    /// `true` if the payload is big-endian (`byte order = 1`)
+    pub big_endian: bool,
+    /// Band center wavelengths (empty if not present)
+    pub wavelengths: Vec<f32>,
+    /// Wavelength units as written in the header (e.g. "Nanometers")
+    pub wavelength_units: Option<String>,
+    /// Band names (empty if not present)
+    ///
+    /// ENVI lists have no escaping, so like ENVI and GDAL the names are split
+    /// on every comma: a name containing one becomes two entries.
+    pub band_names: Vec<String>,
+    /// Band widths (FWHM) in wavelength units (empty if not present)
+    pub fwhm: Vec<f32>,
+    /// Bad band list: `1` = good, `0` = bad (empty if not present)
+    pub bbl: Vec<f32>,
+    /// Value marking pixels that should be ignored
+    pub data_ignore_value: Option<f64>,
+}
+
+impl EnviHeader {
+    /// Parse an ENVI header from its text contents.
+    pub fn parse(text: &str) -> Result<Self, LoaderError> {
+        let mut lines = text.lines();
+
+        let first = lines.by_ref().map(str::trim).find(|l| !l.is_empty());
+        if first != Some("ENVI") {
+            return Err(LoaderError::new("Not an ENVI header (missing 'ENVI' line)"));
+        }
+
+        let mut samples = None;
+        let mut num_lines = None;
+        let mut bands = None;
+        let mut header_offset = 0;
+        let mut data_type = None;
+        let mut interleave = EnviInterleave::Bsq;
+        let mut big_endian = false;
+        let mut wavelengths = Vec::new();
+        let mut wavelength_units = None;
+        let mut band_names = Vec::new();
+        let mut fwhm = Vec::new();
+        let mut bbl = Vec::new();
+        let mut data_ignore_value = None;
+
+        for (key, value) in header_fields(lines) {
+            match key.as_str() {
+                "samples" => samples = Some(parse_number::<usize>(&key, &value)?),
+                "lines" => num_lines = Some(parse_number::<usize>(&key, &value)?),
+                "bands" => bands = Some(parse_number::<usize>(&key, &value)?),
+                "header offset" => header_offset = parse_number::<usize>(&key, &value)?,
+                "data type" => {
+                    let code = parse_number::<u32>(&key, &value)?;
+                    data_type = Some(EnviDataType::from_code(code).ok_or_else(|| {
+                        LoaderError::new(format!("Unsupported ENVI data type: {}", code))
+                    })?);
+                }
+                "interleave" => {
+                    interleave = EnviInterleave::parse(&value).ok_or_else(|| {
+                        LoaderError::new(format!("Unsupported ENVI interleave: {}", value))
+                    })?;
+                }
+                "byte order" => big_endian = parse_number::<u8>(&key, &value)? == 1,
+                "wavelength" => {
+                    wavelengths = split_list(&value)
+                        .iter()
+                        .map(|v| parse_number::<f32>(&key, v))
+                        .collect::<Result<_, _>>()?;
+                }
+                "wavelength units" => wavelength_units = Some(value),
+                "band names" => band_names = split_list(&value),
+                "fwhm" => {
+                    fwhm = split_list(&value)
+                        .iter()
+                        .map(|v| parse_number::<f32>(&key, v))
+                        .collect::<Result<_, _>>()?;
+                }
+                "bbl" => {
+                    bbl = split_list(&value)
+                        .iter()
+                        .map(|v| parse_number::<f32>(&key, v))
+                        .collect::<Result<_, _>>()?;
+                }
+                "data ignore value" => {
+                    data_ignore_value = Some(parse_number::<f64>(&key, &value)?);
+                }
+                _ => log::trace!("EnviLoader: ignoring header field '{}'", key),
+            }
+        }
+
+        let samples = samples.ok_or("ENVI header is missing 'samples'")?;
+        let lines = num_lines.ok_or("ENVI header is missing 'lines'")?;
+        let bands = bands.ok_or("ENVI header is missing 'bands'")?;
+        let data_type = data_type.ok_or("ENVI header is missing 'data type'")?;
+
+        if samples == 0 || lines == 0 || bands == 0 {
+            return Err(LoaderError::new(format!(
+                "Invalid ENVI dimensions: {} samples x {} lines x {} bands",
+                samples, lines, bands
+            )));
+        }
+
+        let header = Self {
+            samples,
+            lines,
+            bands,
+            header_offset,
+            data_type,
+            interleave,
+            big_endian,
+            wavelengths,
+            wavelength_units,
+            band_names,
+            fwhm,
+            bbl,
+            data_ignore_value,
+        };
+        // Reject dimensions whose payload size can't be addressed
+        header.payload_size()?;
+        Ok(header)
+    }
+
+    /// Expected payload size in bytes (including the header offset).
+    pub fn payload_size(&self) -> Result<usize, LoaderError> {
+        self.samples
+            .checked_mul(self.lines)
+            .and_then(|n| n.checked_mul(self.bands))
+            .and_then(|n| n.checked_mul(self.data_type.size()))
+            .and_then(|n| n.checked_add(self.header_offset))
+            .ok_or_else(|| {
+                LoaderError::new(format!(
+                    "ENVI dimensions too large: {} samples x {} lines x {} bands",
+                    self.samples, self.lines, self.bands
+                ))
+            })
+    }
+
+    /// Factor converting header wavelengths to nanometers.
+    ///
+    /// Headers without usable units are assumed to be in micrometers when all
+    /// wavelengths are below 100 (typical for 0.4-2.5 um sensors).
+    fn wavelength_scale(&self) -> f32 {
+        if let Some(scale) = self.wavelength_units.as_deref().and_then(unit_to_nm) {
+            return scale;
+        }
+        if !self.wavelengths.is_empty() && self.wavelengths.iter().all(|&w| w < 100.0) {
+            1000.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Whether there are more band names than bands, which happens when
+    /// names contain commas. Such names are not used.
+    pub fn has_split_band_names(&self) -> bool {
+        self.band_names.len() > self.bands
+    }
+
+    /// Metadata for every band from `band names`, `wavelength`, `fwhm` and `bbl`.
+    pub fn band_info(&self) -> Vec<BandInfo> {
+        let scale = self.wavelength_scale();
+        // Which part of a split name belongs to which band is unknown
+        let names: &[String] = if self.has_split_band_names() {
+            &[]
+        } else {
+            &self.band_names
+        };
+        (0..self.bands)
+            .map(|b| BandInfo {
+                name: names
+                    .get(b)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Band {}", b + 1)),
+                wavelength: self.wavelengths.get(b).map(|w| w * scale),
+                fwhm: self.fwhm.get(b).map(|w| w * scale),
+                bad: self.bbl.get(b).is_some_and(|&v| v == 0.0),
+            })
+            .collect()
+    }
+}
+
+/// Iterate over `key = value` pairs, joining `{ ... }` values that span lines.
+fn header_fields<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut pending: Option<(String, String)> = None;
+
+    for line in lines {
+        if let Some((key, mut value)) = pending.take() {
+            value.push(' ');
+            value.push_str(line.trim());
+            if value.contains('}') {
+                fields.push((key, value));
+            } else {
+                pending = Some((key, value));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+
+        if value.starts_with('{') && !value.contains('}') {
+            pending = Some((key, value));
+        } else {
+            fields.push((key, value));
+        }
+    }
+
+    if let Some(field) = pending {
+        log::warn!(
+            "EnviLoader: unterminated value for header field '{}'",
+            field.0
+        );
+        fields.push(field);
+    }
+
+    fields
+}
+
+/// Split a `{ a, b, c }` list value into trimmed items (on every comma; ENVI
+/// has no escaping).
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parse a numeric header value with a descriptive error.
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, LoaderError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| LoaderError::new(format!("Invalid value for '{}': {}", key, value)))
+}
+
+/// Loader for ENVI `.hdr` + binary payload files.
+///
+/// Supported data types: `u8`, `i16`, `u16`, `i32`, `u32`, `i64`, `u64`, `f32`, `f64`.
+/// Supported interleaves: BSQ, BIL, BIP, in either byte order.
+/// Values are normalized to 0.0-1.0 range based on data type, and pixels equal to
+/// the `data ignore value` are set to 0.0.
+#[derive(Default)]
+pub struct EnviLoader {
+    /// Bands and resolution to decode
+    subset: LoadSubset,
+}
+
+impl EnviLoader {
+    /// Extensions used for ENVI payload files, in lookup order.
+    ///
+    /// A payload without any extension (`scene.hdr` + `scene`) is also valid
+    /// and is always tried first.
+    pub const PAYLOAD_EXTENSIONS: &'static [&'static str] =
+        &["raw", "img", "bsq", "bil", "bip", "dat"];
+
+    /// Candidate payload filenames for a header filename, in lookup order.
+    ///
+    /// Handles both `scene.hdr` and `scene.img.hdr` naming styles.
+    pub fn payload_candidates(header_name: &str) -> Vec<String> {
+        let Some(stem) = header_name
+            .len()
+            .checked_sub(4)
+            .filter(|&i| {
+                header_name
+                    .get(i..)
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case(".hdr"))
+            })
+            .map(|i| &header_name[..i])
+        else {
+            return Vec::new();
+        };
+
+        let mut candidates = vec![stem.to_string()];
+        for ext in Self::PAYLOAD_EXTENSIONS {
+            candidates.push(format!("{}.{}", stem, ext));
+            candidates.push(format!("{}.{}", stem, ext.to_uppercase()));
+        }
+        candidates
+    }
+
+    /// Decode only the bands and resolution selected by `subset`.
+    pub fn with_subset(mut self, subset: LoadSubset) -> Self {
+        self.subset = subset;
+        self
+    }
+
+    /// Decode the selected bands of an ENVI cube from its header bytes and
+    /// payload.
+    pub fn load_with_payload(
+        &self,
+        header: &[u8],
+        payload: &ByteReader,
+    ) -> Result<HyperspectralData, LoaderError> {
+        let text = std::str::from_utf8(header)
+            .map_err(|e| LoaderError::new(format!("ENVI header is not valid text: {}", e)))?;
+        let header = EnviHeader::parse(text)?;
+        let expected = header.payload_size()?;
+        if payload.len() < expected {
+            return Err(LoaderError::new(format!(
+                "ENVI payload too small: expected {} bytes, got {}",
+                expected,
+                payload.len()
+            )));
+        }
+
+        let sample_size = header.data_type.size();
+
+        let decoded = decode_subset(
+            &self.subset,
+            header.bands,
+            header.samples,
+            header.lines,
+            |b, sample, line| {
+                let offset = header.interleave.index(b, line, sample, &header) * sample_size;
+                let mut bytes = [0; 8];
+                payload.read(header.header_offset + offset, &mut bytes[..sample_size]);
+                let raw = header
+                    .data_type
+                    .read(&bytes[..sample_size], header.big_endian);
+
+                if header.data_ignore_value == Some(raw) {
+                    0.0
+                } else {
+                    header.data_type.normalize(raw)
+                }
+            },
+        );
+        payload
+            .check()
+            .map_err(|e| LoaderError::new(format!("Failed to read ENVI payload: {}", e)))?;
+
+        log::info!(
+            "EnviLoader: loaded {}x{} with {} of {} bands ({:?}, {:?})",
+            decoded.width,
+            decoded.height,
+            decoded.bands.len(),
+            header.bands,
+            header.interleave,
+            header.data_type
+        );
+
+        let band_info = header.band_info();
+        let mut result = HyperspectralData::new(
+            decoded.bands,
+            decoded.width as u32,
+            decoded.height as u32,
+            Vec::new(),
+        );
+        result.band_info = decoded
+            .indices
+            .iter()
+            .map(|&b| band_info[b].clone())
+            .collect();
+        if header.has_split_band_names() {
+            let warning = format!(
+                "ENVI header lists {} band names for {} bands (names containing commas); using default names",
+                header.band_names.len(),
+                header.bands
+            );
+            log::warn!("EnviLoader: {}", warning);
+            result.warnings.push(warning);
+        }
+        Ok(result)
+    }
+}
+
+impl HyperspectralLoader for EnviLoader {
+    fn id(&self) -> &'static str {
+        "envi"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "ENVI (.hdr + data)"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["hdr"]
+    }
+
+    fn can_load(&self, data: &[u8]) -> bool {
+        data.starts_with(b"ENVI")
+    }
+
+    fn load(&self, data: &[u8]) -> Result<HyperspectralData, LoaderError> {
+        // Validate the header so callers get a useful error either way
+        let text = std::str::from_utf8(data)
+            .map_err(|e| LoaderError::new(format!("ENVI header is not valid text: {}", e)))?;
+        EnviHeader::parse(text)?;
+
+        Err(LoaderError::new("ENVI header requires its binary payload file").with_loader(self.id()))
+    }
+
+    fn load_with_resolver(
+        &self,
+        data: &[u8],
+        filename: &str,
+        resolver: &dyn FileResolver,
+    ) -> Result<HyperspectralData, LoaderError> {
+        let payload = Self::payload_candidates(filename)
+            .iter()
+            .find_map(|name| {
+                let payload = resolver.open_sibling(name)?;
+                log::debug!("EnviLoader: using payload file {}", name);
+                Some(payload)
+            })
+            .ok_or_else(|| {
+                LoaderError::new(format!("No ENVI payload file found for {}", filename))
+                    .with_loader(self.id())
+            })?;
+
+        self.load_with_payload(data, &payload.reader())
+    }
+
+    fn sidecar_candidates(&self, filename: &str) -> Vec<String> {
+        Self::payload_candidates(filename)
+    }
+
+    fn sidecar_extensions(&self) -> &'static [&'static str] {
+        Self::PAYLOAD_EXTENSIONS
+    }
+
+    fn in_place_extensions(&self) -> &'static [&'static str] {
+        Self::PAYLOAD_EXTENSIONS
+    }
+
+    fn applies_subset(&self) -> bool {
+        true
+    }
+
+    fn priority(&self) -> i32 {
+        // ENVI headers are unambiguous, same tier as other scientific formats
+        10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "ENVI
+description = {
+  Test cube}
+samples = 3
+lines = 2
+bands = 2
+header offset = 0
+file type = ENVI Standard
+data type = 1
+interleave = bsq
+byte order = 0
+wavelength units = Nanometers
+wavelength = {
+ 450.0, 550.0 }
+";
+
+    #[test]
+    fn test_loader_metadata() {
+        let loader = EnviLoader::default();
+        assert_eq!(loader.id(), "envi");
+        assert!(loader.extensions().contains(&"hdr"));
+        assert!(loader.can_load(HEADER.as_bytes()));
+        assert!(!loader.can_load(&[0x93, b'N', b'U', b'M', b'P', b'Y']));
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let text = "ENVI\nsamples = 640\nlines = 480\nbands = 3\ndata type = 12\n\
+                    interleave = BIL\nbyte order = 1\nheader offset = 128\n\
+                    band names = { red, green,\n blue }\ndata ignore value = 0\n";
+        let header = EnviHeader::parse(text).unwrap();
+
+        assert_eq!(header.samples, 640);
+        assert_eq!(header.lines, 480);
+        assert_eq!(header.bands, 3);
+        assert_eq!(header.data_type, EnviDataType::U16);
+        assert_eq!(header.interleave, EnviInterleave::Bil);
+        assert!(header.big_endian);
+        assert_eq!(header.header_offset, 128);
+        assert_eq!(header.band_names, vec!["red", "green", "blue"]);
+        assert_eq!(header.data_ignore_value, Some(0.0));
+        assert_eq!(header.payload_size().unwrap(), 128 + 640 * 480 * 3 * 2);
+    }
+
+    #[test]
+    fn test_band_info() {
+        let text = "ENVI\nsamples = 1\nlines = 1\nbands = 3\ndata type = 4\n\
+                    wavelength units = Micrometers\nwavelength = { 0.5, 0.75, 1.5 }\n\
+                    fwhm = { 0.125, 0.125, 0.25 }\nbbl = { 1, 1, 0 }\nband names = { a, b }\n";
+        let info = EnviHeader::parse(text).unwrap().band_info();
+
+        assert_eq!(info.len(), 3);
+        assert_eq!(info[0].name, "a");
+        assert_eq!(info[2].name, "Band 3");
+        assert_eq!(info[1].wavelength, Some(750.0));
+        assert_eq!(info[2].fwhm, Some(250.0));
+        assert_eq!(
+            info.iter().map(|b| b.bad).collect::<Vec<_>>(),
+            [false, false, true]
+        );
+
+        // Unitless micrometer values are detected
+        let text =
+            "ENVI\nsamples = 1\nlines = 1\nbands = 1\ndata type = 4\nwavelength = { 0.625 }\n";
+        assert_eq!(
+            EnviHeader::parse(text).unwrap().band_info()[0].wavelength,
+            Some(625.0)
+        );
+    }
+
+    #[test]
+    fn test_band_names_with_commas() {
+        let text = "ENVI\nsamples = 1\nlines = 1\nbands = 2\ndata type = 1\n\
+                    band names = { Red, 650 nm, NIR, 860 nm }\n";
+        let header = EnviHeader::parse(text).unwrap();
+        assert_eq!(header.band_names.len(), 4);
+        assert!(header.has_split_band_names());
+
+        let data = EnviLoader::default()
+            .load_with_payload(text.as_bytes(), &ByteReader::new(&[0, 255]))
+            .unwrap();
+        assert_eq!(data.band_info[0].name, "Band 1");
+        assert_eq!(data.band_info[1].name, "Band 2");
+        assert_eq!(data.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_header_errors() {
+        assert!(EnviHeader::parse("samples = 3").is_err());
+        assert!(EnviHeader::parse("ENVI\nsamples = 3\nlines = 2\ndata type = 1").is_err());
+        assert!(
+            EnviHeader::parse("ENVI\nsamples = 3\nlines = 2\nbands = 1\ndata type = 6").is_err()
+        );
+    }
+
+    #[test]
+    fn test_load_bsq_u8() {
+        let payload: Vec<u8> = vec![0, 51, 102, 153, 204, 255, 255, 204, 153, 102, 51, 0];
+        let data = EnviLoader::default()
+            .load_with_payload(HEADER.as_bytes(), &ByteReader::new(&payload))
+            .unwrap();
+
+        assert_eq!(data.width, 3);
+        assert_eq!(data.height, 2);
+        assert_eq!(data.bands.len(), 2);
+        assert_eq!(data.band_info[0].name, "Band 1");
+        assert_eq!(data.band_info[1].wavelength, Some(550.0));
+        assert!((data.bands[0][1] - 0.2).abs() < 1e-6);
+        assert!((data.bands[1][0] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_interleaves_agree() {
+        // 2x2 image, 3 bands, value = band * 10 + pixel index
+        let value = |b: usize, line: usize, sample: usize| (b * 10 + line * 2 + sample) as f32;
+        let header = |interleave: &str| {
+            format!(
+                "ENVI\nsamples = 2\nlines = 2\nbands = 3\ndata type = 4\n\
+                 interleave = {}\nbyte order = 1\n",
+                interleave
+            )
+        };
+
+        let mut bsq = Vec::new();
+        let mut bil = Vec::new();
+        let mut bip = Vec::new();
+        for b in 0..3 {
+            for line in 0..2 {
+                for sample in 0..2 {
+                    bsq.extend_from_slice(&value(b, line, sample).to_be_bytes());
+                }
+            }
+        }
+        for line in 0..2 {
+            for b in 0..3 {
+                for sample in 0..2 {
+                    bil.extend_from_slice(&value(b, line, sample).to_be_bytes());
+                }
+            }
+            for sample in 0..2 {
+                for b in 0..3 {
+                    bip.extend_from_slice(&value(b, line, sample).to_be_bytes());
+                }
+            }
+        }
+
+        let from_bsq = EnviLoader::default()
+            .load_with_payload(header("bsq").as_bytes(), &ByteReader::new(&bsq))
+            .unwrap();
+        let from_bil = EnviLoader::default()
+            .load_with_payload(header("bil").as_bytes(), &ByteReader::new(&bil))
+            .unwrap();
+        let from_bip = EnviLoader::default()
+            .load_with_payload(header("bip").as_bytes(), &ByteReader::new(&bip))
+            .unwrap();
+
+        assert_eq!(from_bsq.bands, from_bil.bands);
+        assert_eq!(from_bsq.bands, from_bip.bands);
+        assert_eq!(from_bsq.bands[2][3], 23.0);
+    }
+
+    #[test]
+    fn test_ignore_value_and_offset() {
+        let header = "ENVI\nsamples = 2\nlines = 1\nbands = 1\ndata type = 2\n\
+                      header offset = 4\ndata ignore value = -9999\n";
+        let mut payload = vec![0xAA; 4];
+        payload.extend_from_slice(&(-9999i16).to_le_bytes());
+        payload.extend_from_slice(&32767i16.to_le_bytes());
+
+        let data = EnviLoader::default()
+            .load_with_payload(header.as_bytes(), &ByteReader::new(&payload))
+            .unwrap();
+        assert_eq!(data.bands[0][0], 0.0);
+        assert!((data.bands[0][1] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_subset() {
+        // Same 2-band 3x2 cube as test_load_bsq_u8, second band binned 2x2
+        let payload: Vec<u8> = vec![0, 51, 102, 153, 204, 255, 255, 204, 153, 102, 51, 0];
+        let subset = LoadSubset {
+            bands: Some((1, 1)),
+            band_stride: 1,
+            binning: 2,
+        };
+        let data = EnviLoader::default()
+            .with_subset(subset)
+            .load_with_payload(HEADER.as_bytes(), &ByteReader::new(&payload))
+            .unwrap();
+
+        assert_eq!((data.width, data.height), (2, 1));
+        assert_eq!(data.band_info.len(), 1);
+        assert_eq!(data.band_info[0].wavelength, Some(550.0));
+        assert!((data.bands[0][0] - 0.6).abs() < 1e-6);
+        assert!((data.bands[0][1] - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_payload_too_small() {
+        let result =
+            EnviLoader::default().load_with_payload(HEADER.as_bytes(), &ByteReader::new(&[0u8; 5]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_payload_size_overflow() {
+        let text = format!(
+            "ENVI\nsamples = {}\nlines = 2\nbands = 1\ndata type = 1\n",
+            usize::MAX / 2 + 1
+        );
+        assert!(EnviHeader::parse(&text).is_err());
+
+        let text = format!(
+            "ENVI\nsamples = 1\nlines = 1\nbands = 1\ndata type = 1\nheader offset = {}\n",
+            usize::MAX
+        );
+        assert!(EnviHeader::parse(&text).is_err());
+    }
+
+    #[test]
+    fn test_load_without_payload_fails() {
+        assert!(EnviLoader::default().load(HEADER.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_load_with_resolver() {
+        use crate::data::resolver::MemoryResolver;
+
+        let resolver = MemoryResolver::new(vec![("scans/cube.bsq".to_string(), vec![255u8; 12])]);
+        let data = EnviLoader::default()
+            .load_with_resolver(HEADER.as_bytes(), "cube.hdr", &resolver)
+            .unwrap();
+        assert_eq!(data.bands.len(), 2);
+        assert!(data.bands.iter().flatten().all(|&v| v == 1.0));
+
+        let empty = MemoryResolver::default();
+        assert!(
+            EnviLoader::default()
+                .load_with_resolver(HEADER.as_bytes(), "cube.hdr", &empty)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_payload_candidates() {
+        let candidates = EnviLoader::payload_candidates("dir/scene.hdr");
+        assert_eq!(candidates[0], "dir/scene");
+        assert!(candidates.contains(&"dir/scene.raw".to_string()));
+        assert!(candidates.contains(&"dir/scene.IMG".to_string()));
+
+        // scene.img.hdr style resolves to scene.img first
+        assert_eq!(
+            EnviLoader::payload_candidates("scene.img.hdr")[0],
+            "scene.img"
+        );
+        assert!(EnviLoader::payload_candidates("scene.png").is_empty());
+    }
+}
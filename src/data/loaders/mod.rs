@@ -3,8 +3,14 @@
 //! This module contains implementations of the `HyperspectralLoader` trait
 //! for various file formats.
 
+mod envi_loader;
 mod image_loader;
 mod npy_loader;
+mod npz_loader;
+mod tiff_loader;
 
+pub use envi_loader::EnviLoader;
 pub use image_loader::ImageLoader;
 pub use npy_loader::NpyLoader;
+pub use npz_loader::NpzLoader;
+pub use tiff_loader::TiffLoader;
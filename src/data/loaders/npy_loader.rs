@@ -2,132 +2,389 @@
 //!
 //! Supports loading hyperspectral data stored as NumPy arrays.
 //! Handles various array shapes and data types.
+//!
+//! C-order arrays are decoded straight from the file bytes, visiting only
+//! the bands and pixels of the `LoadSubset`, so a file left on disk is read
+//! only where those samples are. Fortran-order arrays go through
+//! `ndarray-npy` instead.
+//!
+//! Band metadata can be supplied in a sidecar table next to the array
+//! (`cube.wavelengths.txt` or `cube.wavelengths.csv` for `cube.npy`), one
+//! band per line as `wavelength [fwhm [name]]` in nanometers.
 
 use std::io::Cursor;
 
-use ndarray::ArrayD;
+use ndarray::{ArrayD, Axis, IxDyn};
 use ndarray_npy::ReadNpyExt;
 
-use crate::data::HyperspectralData;
+use crate::data::bands::parse_band_table;
+use crate::data::file_bytes::{ByteReader, FileBytes};
 use crate::data::loader::{HyperspectralLoader, LoaderError};
+use crate::data::resolver::FileResolver;
+use crate::data::subset::decode_subset;
+use crate::data::{HyperspectralData, LoadSubset, NpyLayout};
 
 /// Loader for NumPy `.npy` files.
 ///
-/// **Expected array shapes** (width, height convention):
-/// - 2D `(W, H)`: Single-band grayscale
-/// - 3D `(B, W, H)`: Bands-first format (common for hyperspectral)
-/// - 3D `(W, H, B)`: Channels-last format (common in image processing)
+/// **Array shapes** follow the declared `NpyLayout` (B = bands, H = height,
+/// W = width):
+/// - 2D: single-band grayscale, `(H, W)` or `(W, H)` depending on the layout
+/// - 3D: `(B, W, H)`, `(W, H, B)`, `(H, W, B)` or `(B, H, W)`
+///
+/// Without a declared layout, 2D arrays are read as `(H, W)` and 3D arrays
+/// are guessed from the shape (small first axis = `(B, H, W)`, small last
+/// axis = `(H, W, B)`); the guess is reported in `HyperspectralData::warnings`.
 ///
 /// Supported data types: `f32`, `f64`, `u8`, `u16`, `i16`, `i32`.
 /// Values are normalized to 0.0-1.0 range based on data type.
-pub struct NpyLoader;
+#[derive(Default)]
+pub struct NpyLoader {
+    /// Declared axis layout (`None` = guess from shape)
+    layout: Option<NpyLayout>,
+    /// Bands and resolution to decode
+    subset: LoadSubset,
+}
 
 impl NpyLoader {
     /// NumPy magic bytes: \x93NUMPY
     const MAGIC: &'static [u8] = &[0x93, b'N', b'U', b'M', b'P', b'Y'];
 
-    /// Convert array to HyperspectralData, handling different shapes.
+    /// Suffixes of band metadata tables (replacing `.npy`).
+    const BAND_TABLE_SUFFIXES: &'static [&'static str] = &["wavelengths.txt", "wavelengths.csv"];
+
+    /// Create a loader for the given axis layout (`None` = guess from shape).
+    pub fn new(layout: Option<NpyLayout>) -> Self {
+        Self {
+            layout,
+            subset: LoadSubset::default(),
+        }
+    }
+
+    /// Decode only the bands and resolution selected by `subset`.
+    pub fn with_subset(mut self, subset: LoadSubset) -> Self {
+        self.subset = subset;
+        self
+    }
+
+    /// Guess the layout of a 3D array from its shape.
+    ///
+    /// Band counts are usually much smaller than the spatial dimensions, so a
+    /// small first axis means bands-first and a small last axis channels-last.
+    fn guess_layout(shape: &[usize]) -> NpyLayout {
+        if shape[0] <= 100 && shape[2] > shape[0] {
+            NpyLayout::Bhw
+        } else if shape[2] <= 100 && shape[0] > shape[2] {
+            NpyLayout::Hwb
+        } else {
+            // Ambiguous - bands-first is more common for hyperspectral
+            NpyLayout::Bhw
+        }
+    }
+
+    /// Positions of the (bands, height, width) axes of an array with `shape`,
+    /// and the layout if it had to be guessed.
+    ///
+    /// 2D arrays get a band axis of length 1 inserted in front.
+    fn cube_axes(&self, shape: &[usize]) -> Result<([usize; 3], Option<NpyLayout>), LoaderError> {
+        match shape.len() {
+            2 => {
+                // Single band: only the H/W order of the layout matters
+                let axes = match self.layout {
+                    Some(layout) if layout.axes()[2] < layout.axes()[1] => [0, 2, 1],
+                    _ => [0, 1, 2],
+                };
+                Ok((axes, None))
+            }
+            3 => match self.layout {
+                Some(layout) => Ok((layout.axes(), None)),
+                None => {
+                    let layout = Self::guess_layout(shape);
+                    Ok((layout.axes(), Some(layout)))
+                }
+            },
+            n => Err(LoaderError::new(format!(
+                "Unsupported array dimensions: {} (expected 2 or 3)",
+                n
+            ))),
+        }
+    }
+
+    /// Report a guessed layout in the warnings of `data`.
+    fn warn_guessed(data: &mut HyperspectralData, shape: &[usize], guessed: Option<NpyLayout>) {
+        if let Some(layout) = guessed {
+            let warning = format!(
+                "No axis layout declared for shape {:?}; guessed {}. Set the layout if the image looks scrambled.",
+                shape,
+                layout.name()
+            );
+            log::warn!("NpyLoader: {}", warning);
+            data.warnings.push(warning);
+        }
+    }
+
+    /// Attach the band metadata of the table next to `filename`, if there
+    /// is one. An invalid table is reported in the warnings.
+    fn with_band_table(
+        &self,
+        mut result: HyperspectralData,
+        filename: &str,
+        resolver: &dyn FileResolver,
+    ) -> HyperspectralData {
+        let Some((name, bytes)) = self
+            .sidecar_candidates(filename)
+            .into_iter()
+            .find_map(|name| resolver.read_sibling(&name).map(|bytes| (name, bytes)))
+        else {
+            return result;
+        };
+
+        match parse_band_table(&String::from_utf8_lossy(&bytes)) {
+            Ok(band_info) => {
+                // The table lists every band of the file; keep the loaded ones
+                let band_info = self
+                    .subset
+                    .band_indices(band_info.len())
+                    .into_iter()
+                    .map(|b| band_info[b].clone())
+                    .collect();
+                if result.set_band_info(band_info, &name) {
+                    log::debug!("NpyLoader: band metadata from {}", name);
+                }
+            }
+            Err(e) => {
+                let warning = format!("Invalid band table {}: {}", name, e);
+                log::warn!("NpyLoader: {}", warning);
+                result.warnings.push(warning);
+            }
+        }
+        result
+    }
+
+    /// Decode a C-order array directly from the file bytes.
+    ///
+    /// Only the samples of the selected bands are read.
+    fn load_raw(
+        &self,
+        header: &NpyHeader,
+        data: &ByteReader,
+    ) -> Result<HyperspectralData, LoaderError> {
+        log::debug!("NpyLoader: array shape = {:?}", header.shape);
+        let (axes, guessed) = self.cube_axes(&header.shape)?;
+
+        let mut shape = [1; 3];
+        shape[3 - header.shape.len()..].copy_from_slice(&header.shape);
+        let count: usize = shape.iter().product();
+        let size = header.dtype.size();
+        let payload = count.saturating_mul(size);
+        if data.len().saturating_sub(header.data_offset) < payload {
+            return Err(LoaderError::new(format!(
+                "NumPy data too small: expected {} values of {} bytes",
+                count, size
+            )));
+        }
+
+        // Element strides of the C-order array
+        let strides = [shape[1] * shape[2], shape[2], 1];
+        let [b_axis, h_axis, w_axis] = axes;
+        let (num_bands, height, width) = (shape[b_axis], shape[h_axis], shape[w_axis]);
+        let decoded = decode_subset(&self.subset, num_bands, width, height, |b, x, y| {
+            let index = b * strides[b_axis] + y * strides[h_axis] + x * strides[w_axis];
+            let mut bytes = [0; 8];
+            data.read(header.data_offset + index * size, &mut bytes[..size]);
+            header.dtype.read(&bytes[..size], header.big_endian)
+        });
+        data.check()
+            .map_err(|e| LoaderError::new(format!("Failed to read NumPy data: {}", e)))?;
+        let labels = decoded
+            .indices
+            .iter()
+            .map(|b| format!("Band {}", b + 1))
+            .collect();
+
+        log::info!(
+            "NpyLoader: loaded {}x{} with {} of {} bands",
+            decoded.width,
+            decoded.height,
+            decoded.bands.len(),
+            num_bands
+        );
+
+        let mut result = HyperspectralData::new(
+            decoded.bands,
+            decoded.width as u32,
+            decoded.height as u32,
+            labels,
+        );
+        Self::warn_guessed(&mut result, &header.shape, guessed);
+        Ok(result)
+    }
+
+    /// Convert array to HyperspectralData using the declared (or guessed) layout.
     ///
-    /// Uses (width, height) convention throughout:
-    /// - 2D: shape[0] = width, shape[1] = height
-    /// - 3D bands-first: shape[0] = bands, shape[1] = width, shape[2] = height
-    /// - 3D channels-last: shape[0] = width, shape[1] = height, shape[2] = bands
-    fn array_to_hyperspectral<T>(array: ArrayD<T>) -> Result<HyperspectralData, LoaderError>
+    /// The array is permuted to `(B, H, W)` so each band is emitted in
+    /// row-major pixel order. The subset is applied afterwards.
+    fn array_to_hyperspectral<T>(&self, array: ArrayD<T>) -> Result<HyperspectralData, LoaderError>
     where
         T: NumericConvert + Copy,
     {
-        let shape = array.shape();
+        let shape = array.shape().to_vec();
         log::debug!("NpyLoader: array shape = {:?}", shape);
 
-        match shape.len() {
-            2 => {
-                // 2D array: (W, H) - single band grayscale
-                let width = shape[0] as u32;
-                let height = shape[1] as u32;
-
-                let band: Vec<f32> = array.iter().map(|&v| v.to_normalized_f32()).collect();
-
-                log::info!(
-                    "NpyLoader: loaded {}x{} as single-band grayscale",
-                    width,
-                    height
-                );
-
-                Ok(HyperspectralData::new(
-                    vec![band],
-                    width,
-                    height,
-                    vec!["Band 1".to_string()],
-                ))
-            }
-            3 => {
-                // 3D array: need to determine if (B, W, H) or (W, H, B)
-                // Heuristic: if first dimension is small (<= 100) and third is larger,
-                // assume (B, W, H). Otherwise assume (W, H, B).
-                let (num_bands, width, height) = if shape[0] <= 100 && shape[2] > shape[0] {
-                    // (B, W, H) format - bands first
-                    (shape[0], shape[1] as u32, shape[2] as u32)
-                } else if shape[2] <= 100 && shape[0] > shape[2] {
-                    // (W, H, B) format - channels last
-                    (shape[2], shape[0] as u32, shape[1] as u32)
-                } else {
-                    // Ambiguous - default to (B, W, H) as it's more common for hyperspectral
-                    log::warn!(
-                        "NpyLoader: ambiguous 3D shape {:?}, assuming (bands, width, height)",
-                        shape
-                    );
-                    (shape[0], shape[1] as u32, shape[2] as u32)
-                };
+        let (axes, guessed) = self.cube_axes(&shape)?;
+        let cube = if shape.len() == 2 {
+            array.insert_axis(Axis(0))
+        } else {
+            array
+        };
+
+        let cube = cube.permuted_axes(IxDyn(&axes));
+        let (num_bands, height, width) = (cube.shape()[0], cube.shape()[1], cube.shape()[2]);
+
+        let bands: Vec<Vec<f32>> = cube
+            .outer_iter()
+            .map(|band| band.iter().map(|&v| v.to_normalized_f32()).collect())
+            .collect();
+        let labels = (1..=num_bands).map(|b| format!("Band {}", b)).collect();
+
+        log::info!(
+            "NpyLoader: loaded {}x{} with {} bands",
+            width,
+            height,
+            num_bands
+        );
+
+        let mut data =
+            HyperspectralData::new(bands, width as u32, height as u32, labels).subset(&self.subset);
+        Self::warn_guessed(&mut data, &shape, guessed);
+        Ok(data)
+    }
+}
 
-                let pixel_count = (width * height) as usize;
-                let mut bands = Vec::with_capacity(num_bands);
-                let mut labels = Vec::with_capacity(num_bands);
-
-                // Determine layout
-                let is_bands_first = shape[0] <= 100 && shape[2] > shape[0];
-
-                for b in 0..num_bands {
-                    let mut band_data = Vec::with_capacity(pixel_count);
-
-                    if is_bands_first || (shape[2] > 100 && shape[0] <= 100) {
-                        // (B, W, H) - bands first layout
-                        for w in 0..width as usize {
-                            for h in 0..height as usize {
-                                let idx = b * (width as usize * height as usize)
-                                    + w * height as usize
-                                    + h;
-                                band_data.push(array.as_slice().unwrap()[idx].to_normalized_f32());
-                            }
-                        }
-                    } else {
-                        // (W, H, B) - channels last layout
-                        for w in 0..width as usize {
-                            for h in 0..height as usize {
-                                let idx = w * (height as usize * num_bands) + h * num_bands + b;
-                                band_data.push(array.as_slice().unwrap()[idx].to_normalized_f32());
-                            }
-                        }
-                    }
+/// Element types decoded from the raw bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NpyDtype {
+    F32,
+    F64,
+    U8,
+    U16,
+    I16,
+    I32,
+}
+
+impl NpyDtype {
+    /// Parse the type part of a `descr` (e.g. `f4` of `<f4`).
+    fn parse(code: &str) -> Option<Self> {
+        match code {
+            "f4" => Some(Self::F32),
+            "f8" => Some(Self::F64),
+            "u1" => Some(Self::U8),
+            "u2" => Some(Self::U16),
+            "i2" => Some(Self::I16),
+            "i4" => Some(Self::I32),
+            _ => None,
+        }
+    }
+
+    /// Size of one element in bytes.
+    fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::F32 | Self::I32 => 4,
+            Self::F64 => 8,
+        }
+    }
 
-                    bands.push(band_data);
-                    labels.push(format!("Band {}", b + 1));
+    /// Read one element from `bytes` (exactly `size()` bytes) and normalize it.
+    fn read(self, bytes: &[u8], big_endian: bool) -> f32 {
+        macro_rules! read_as {
+            ($ty:ty) => {{
+                let arr = bytes.try_into().expect("element slice has the type's size");
+                if big_endian {
+                    <$ty>::from_be_bytes(arr).to_normalized_f32()
+                } else {
+                    <$ty>::from_le_bytes(arr).to_normalized_f32()
                 }
+            }};
+        }
 
-                log::info!(
-                    "NpyLoader: loaded {}x{} with {} bands",
-                    width,
-                    height,
-                    num_bands
-                );
+        match self {
+            Self::U8 => bytes[0].to_normalized_f32(),
+            Self::U16 => read_as!(u16),
+            Self::I16 => read_as!(i16),
+            Self::I32 => read_as!(i32),
+            Self::F32 => read_as!(f32),
+            Self::F64 => read_as!(f64),
+        }
+    }
+}
 
-                Ok(HyperspectralData::new(bands, width, height, labels))
-            }
-            _ => Err(LoaderError::new(format!(
-                "Unsupported array dimensions: {} (expected 2 or 3)",
-                shape.len()
-            ))),
+/// Header of a `.npy` file with a supported element type.
+#[derive(Debug, Clone, PartialEq)]
+struct NpyHeader {
+    /// Element type
+    dtype: NpyDtype,
+    /// Elements are stored big-endian
+    big_endian: bool,
+    /// Array is stored in column-major order
+    fortran_order: bool,
+    /// Array shape
+    shape: Vec<usize>,
+    /// Byte offset of the first element
+    data_offset: usize,
+}
+
+impl NpyHeader {
+    /// Bytes at the start of the file that hold the header length.
+    const PREAMBLE_LEN: usize = 12;
+
+    /// Start and length of the header text, read from the preamble at the
+    /// start of `data`.
+    fn text_range(data: &[u8]) -> Option<(usize, usize)> {
+        if !data.starts_with(NpyLoader::MAGIC) {
+            return None;
+        }
+        // Version 1.0 stores the header length in 2 bytes, later versions in 4
+        match *data.get(6)? {
+            1 => Some((
+                10,
+                u16::from_le_bytes([*data.get(8)?, *data.get(9)?]) as usize,
+            )),
+            _ => Some((
+                12,
+                u32::from_le_bytes(data.get(8..12)?.try_into().ok()?) as usize,
+            )),
         }
     }
+
+    /// Parse the header at the start of `data`.
+    ///
+    /// Returns `None` for malformed headers and unsupported element types.
+    fn parse(data: &[u8]) -> Option<Self> {
+        let (header_start, header_len) = Self::text_range(data)?;
+        let text = std::str::from_utf8(data.get(header_start..header_start + header_len)?).ok()?;
+
+        let value = |key: &str| {
+            let start = text.find(&format!("'{}'", key))? + key.len() + 2;
+            Some(text[start..].trim_start().strip_prefix(':')?.trim_start())
+        };
+        let descr = value("descr")?.strip_prefix('\'')?.split('\'').next()?;
+        let (byte_order, code) = descr.split_at(1);
+        let shape = value("shape")?.strip_prefix('(')?.split(')').next()?;
+
+        Some(Self {
+            dtype: NpyDtype::parse(code)?,
+            big_endian: byte_order == ">",
+            fortran_order: value("fortran_order")?.starts_with("True"),
+            shape: shape
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse().ok())
+                .collect::<Option<_>>()?,
+            data_offset: header_start + header_len,
+        })
+    }
 }
 
 impl HyperspectralLoader for NpyLoader {
@@ -148,42 +405,46 @@ impl HyperspectralLoader for NpyLoader {
     }
 
     fn load(&self, data: &[u8]) -> Result<HyperspectralData, LoaderError> {
+        if let Some(header) = NpyHeader::parse(data).filter(|h| !h.fortran_order) {
+            return self.load_raw(&header, &ByteReader::new(data));
+        }
+
         let mut cursor = Cursor::new(data);
 
         // Try different numeric types in order of likelihood
         // f32 is most common for scientific data
         if let Ok(array) = ArrayD::<f32>::read_npy(&mut cursor) {
-            return Self::array_to_hyperspectral(array);
+            return self.array_to_hyperspectral(array);
         }
 
         // Reset cursor and try f64
         cursor.set_position(0);
         if let Ok(array) = ArrayD::<f64>::read_npy(&mut cursor) {
-            return Self::array_to_hyperspectral(array);
+            return self.array_to_hyperspectral(array);
         }
 
         // Reset cursor and try u8 (common for image-like data)
         cursor.set_position(0);
         if let Ok(array) = ArrayD::<u8>::read_npy(&mut cursor) {
-            return Self::array_to_hyperspectral(array);
+            return self.array_to_hyperspectral(array);
         }
 
         // Reset cursor and try u16 (common for 16-bit imagery)
         cursor.set_position(0);
         if let Ok(array) = ArrayD::<u16>::read_npy(&mut cursor) {
-            return Self::array_to_hyperspectral(array);
+            return self.array_to_hyperspectral(array);
         }
 
         // Reset cursor and try i16 (signed 16-bit)
         cursor.set_position(0);
         if let Ok(array) = ArrayD::<i16>::read_npy(&mut cursor) {
-            return Self::array_to_hyperspectral(array);
+            return self.array_to_hyperspectral(array);
         }
 
         // Reset cursor and try i32
         cursor.set_position(0);
         if let Ok(array) = ArrayD::<i32>::read_npy(&mut cursor) {
-            return Self::array_to_hyperspectral(array);
+            return self.array_to_hyperspectral(array);
         }
 
         Err(LoaderError::new(
@@ -191,6 +452,64 @@ impl HyperspectralLoader for NpyLoader {
         ))
     }
 
+    fn load_with_resolver(
+        &self,
+        data: &[u8],
+        filename: &str,
+        resolver: &dyn FileResolver,
+    ) -> Result<HyperspectralData, LoaderError> {
+        let result = self.load(data)?;
+        Ok(self.with_band_table(result, filename, resolver))
+    }
+
+    fn load_file(
+        &self,
+        data: &FileBytes,
+        filename: &str,
+        resolver: &dyn FileResolver,
+    ) -> Result<HyperspectralData, LoaderError> {
+        // Read the header alone; the samples are read as they are decoded
+        let reader = data.reader();
+        let read_prefix = |len: usize| {
+            let mut prefix = vec![0; len.min(reader.len())];
+            reader.read(0, &mut prefix);
+            prefix
+        };
+        let header = NpyHeader::text_range(&read_prefix(NpyHeader::PREAMBLE_LEN))
+            .and_then(|(start, len)| NpyHeader::parse(&read_prefix(start.saturating_add(len))))
+            .filter(|h| !h.fortran_order);
+        let result = match header {
+            Some(header) => self.load_raw(&header, &reader)?,
+            None => {
+                let data = data
+                    .read_all()
+                    .map_err(|e| LoaderError::new(format!("Failed to read NumPy file: {}", e)))?;
+                self.load(&data)?
+            }
+        };
+        Ok(self.with_band_table(result, filename, resolver))
+    }
+
+    fn sidecar_candidates(&self, filename: &str) -> Vec<String> {
+        let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+        Self::BAND_TABLE_SUFFIXES
+            .iter()
+            .map(|suffix| format!("{}.{}", stem, suffix))
+            .collect()
+    }
+
+    fn sidecar_extensions(&self) -> &'static [&'static str] {
+        Self::BAND_TABLE_SUFFIXES
+    }
+
+    fn in_place_extensions(&self) -> &'static [&'static str] {
+        &["npy"]
+    }
+
+    fn applies_subset(&self) -> bool {
+        true
+    }
+
     fn priority(&self) -> i32 {
         // NumPy files have higher priority than generic images
         // since they're specifically for scientific data
@@ -249,7 +568,7 @@ mod tests {
 
     #[test]
     fn test_loader_metadata() {
-        let loader = NpyLoader;
+        let loader = NpyLoader::default();
         assert_eq!(loader.id(), "npy");
         assert!(loader.extensions().contains(&"npy"));
         assert_eq!(loader.priority(), 10);
@@ -257,7 +576,7 @@ mod tests {
 
     #[test]
     fn test_magic_detection() {
-        let loader = NpyLoader;
+        let loader = NpyLoader::default();
 
         // Valid NumPy magic
         let valid_magic = [0x93, b'N', b'U', b'M', b'P', b'Y', 0x01, 0x00];
@@ -281,13 +600,214 @@ mod tests {
         assert!((65535u16.to_normalized_f32() - 1.0).abs() < f32::EPSILON);
     }
 
+    /// Encode a little-endian, C-order f32 `.npy` (format version 1.0).
+    fn f32_npy(shape: &[usize], values: &[f32]) -> Vec<u8> {
+        let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}), }}",
+            dims.join(", ")
+        );
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut out = vec![0x93, b'N', b'U', b'M', b'P', b'Y', 1, 0];
+        out.extend((header.len() as u16).to_le_bytes());
+        out.extend(header.as_bytes());
+        out.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+        out
+    }
+
+    /// Value stored for band `b` at pixel `(x, y)`.
+    fn pixel_value(b: usize, x: usize, y: usize) -> f32 {
+        (b * 100 + y * 10 + x) as f32 / 1000.0
+    }
+
+    /// Write a 2-band 4x3 cube in the given layout.
+    fn cube_npy(layout: NpyLayout) -> Vec<u8> {
+        let (bands, width, height) = (2, 4, 3);
+        let mut sizes = [0; 3];
+        let [b_axis, h_axis, w_axis] = layout.axes();
+        sizes[b_axis] = bands;
+        sizes[h_axis] = height;
+        sizes[w_axis] = width;
+
+        let mut values = Vec::new();
+        for i in 0..sizes[0] {
+            for j in 0..sizes[1] {
+                for k in 0..sizes[2] {
+                    let idx = [i, j, k];
+                    values.push(pixel_value(idx[b_axis], idx[w_axis], idx[h_axis]));
+                }
+            }
+        }
+        f32_npy(&sizes, &values)
+    }
+
+    #[test]
+    fn test_declared_layouts() {
+        for &layout in NpyLayout::all() {
+            let data = NpyLoader::new(Some(layout))
+                .load(&cube_npy(layout))
+                .unwrap();
+            assert_eq!(data.bands.len(), 2, "{}", layout.name());
+            assert_eq!((data.width, data.height), (4, 3), "{}", layout.name());
+            assert!(data.warnings.is_empty());
+            for b in 0..2 {
+                for y in 0..3 {
+                    for x in 0..4 {
+                        assert_eq!(
+                            data.bands[b][y * 4 + x],
+                            pixel_value(b, x, y),
+                            "{} band {} at ({}, {})",
+                            layout.name(),
+                            b,
+                            x,
+                            y
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_guessed_layout_warns() {
+        let data = NpyLoader::default()
+            .load(&cube_npy(NpyLayout::Bhw))
+            .unwrap();
+        assert_eq!((data.width, data.height), (4, 3));
+        assert_eq!(data.bands[1][4 + 2], pixel_value(1, 2, 1));
+        assert_eq!(data.warnings.len(), 1);
+        assert!(data.warnings[0].contains("BHW"));
+    }
+
+    #[test]
+    fn test_2d_layouts() {
+        let values = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5];
+
+        // Default is (H, W) without a warning
+        let data = NpyLoader::default()
+            .load(&f32_npy(&[2, 3], &values))
+            .unwrap();
+        assert_eq!((data.width, data.height), (3, 2));
+        assert_eq!(data.bands[0], values);
+        assert!(data.warnings.is_empty());
+
+        // Width-first layouts transpose
+        let data = NpyLoader::new(Some(NpyLayout::Whb))
+            .load(&f32_npy(&[2, 3], &values))
+            .unwrap();
+        assert_eq!((data.width, data.height), (2, 3));
+        assert_eq!(data.bands[0], vec![0.0, 0.3, 0.1, 0.4, 0.2, 0.5]);
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let bytes = f32_npy(&[2, 4, 3], &[0.0; 24]);
+        let header = NpyHeader::parse(&bytes).unwrap();
+        assert_eq!(header.dtype, NpyDtype::F32);
+        assert!(!header.big_endian && !header.fortran_order);
+        assert_eq!(header.shape, vec![2, 4, 3]);
+        assert_eq!(header.data_offset, bytes.len() - 24 * 4);
+
+        // Unsupported types fall back to ndarray-npy
+        let mut complex = f32_npy(&[2, 2], &[0.0; 4]);
+        complex[21..24].copy_from_slice(b"<c8");
+        assert!(NpyHeader::parse(&complex).is_none());
+        assert!(NpyHeader::parse(b"\x89PNG").is_none());
+    }
+
+    #[test]
+    fn test_subset() {
+        let subset = LoadSubset {
+            bands: Some((1, 1)),
+            band_stride: 1,
+            binning: 2,
+        };
+        for &layout in NpyLayout::all() {
+            let data = NpyLoader::new(Some(layout))
+                .with_subset(subset)
+                .load(&cube_npy(layout))
+                .unwrap();
+            assert_eq!((data.width, data.height), (2, 2), "{}", layout.name());
+            assert_eq!(data.band_info[0].name, "Band 2");
+            // Mean of the top-left 2x2 block of band 1
+            let expected = (pixel_value(1, 0, 0) + pixel_value(1, 1, 0))
+                + (pixel_value(1, 0, 1) + pixel_value(1, 1, 1));
+            assert!((data.bands[0][0] - expected / 4.0).abs() < 1e-6);
+            // The last row is a partial block
+            let expected = (pixel_value(1, 2, 2) + pixel_value(1, 3, 2)) / 2.0;
+            assert!((data.bands[0][3] - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_band_table_sidecar() {
+        use crate::data::MemoryResolver;
+
+        let loader = NpyLoader::new(Some(NpyLayout::Bhw));
+        assert_eq!(
+            loader.sidecar_candidates("cube.npy"),
+            vec!["cube.wavelengths.txt", "cube.wavelengths.csv"]
+        );
+
+        let resolver = MemoryResolver::new([(
+            "cube.wavelengths.csv".to_string(),
+            b"wavelength,fwhm\n450,10\n650,12\n".to_vec(),
+        )]);
+        let data = loader
+            .load_with_resolver(&cube_npy(NpyLayout::Bhw), "cube.npy", &resolver)
+            .unwrap();
+        assert_eq!(data.band_info[1].wavelength, Some(650.0));
+        assert_eq!(data.band_info[1].fwhm, Some(12.0));
+
+        // Wrong band count keeps defaults and warns
+        let resolver = MemoryResolver::new([(
+            "cube.wavelengths.txt".to_string(),
+            b"450\n550\n650\n".to_vec(),
+        )]);
+        let data = loader
+            .load_with_resolver(&cube_npy(NpyLayout::Bhw), "cube.npy", &resolver)
+            .unwrap();
+        assert_eq!(data.band_info[0].wavelength, None);
+        assert_eq!(data.warnings.len(), 1);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_load_file_on_disk() {
+        use crate::data::MemoryResolver;
+
+        let dir = std::env::temp_dir().join(format!("hvat_npy_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cube.npy");
+        std::fs::write(&path, cube_npy(NpyLayout::Hwb)).unwrap();
+
+        let loader = NpyLoader::new(Some(NpyLayout::Hwb)).with_subset(LoadSubset {
+            bands: Some((1, 1)),
+            band_stride: 1,
+            binning: 1,
+        });
+        let file = FileBytes::open(&path).unwrap();
+        let result = loader.load_file(&file, "cube.npy", &MemoryResolver::default());
+        drop(file);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let data = result.unwrap();
+        assert_eq!((data.width, data.height), (4, 3));
+        assert_eq!(data.bands.len(), 1);
+        assert_eq!(data.bands[0][2 * 4 + 3], pixel_value(1, 3, 2));
+    }
+
     /// Integration test loading actual .npy files
     #[cfg(not(target_arch = "wasm32"))]
     #[test]
     fn test_load_npy_files() {
         use std::path::Path;
 
-        let loader = NpyLoader;
+        let loader = NpyLoader::default();
         let test_files = [
             ("/tmp/test_hyperspectral_bhw.npy", 4, 100, 100), // (B, H, W)
             ("/tmp/test_hyperspectral_hwb.npy", 4, 100, 100), // (H, W, B)
@@ -0,0 +1,418 @@
+//! Loader for NumPy `.npz` archives.
+//!
+//! `.npz` files (written by `np.savez` / `np.savez_compressed`) are ZIP
+//! archives holding one `.npy` file per array. Pipelines commonly store the
+//! data cube alongside auxiliary arrays such as `wavelengths` or `mask`.
+//!
+//! The cube is chosen by key (see `LoaderOptions::npz_cube_key`) or, by
+//! default, as the largest 3D array. Per-band arrays matching the band count
+//! fill the band metadata: `wavelengths` (nm), `fwhm` (nm) and `bbl`
+//! (ENVI-style bad band list, `0` = bad). All other arrays are reported in
+//! the log.
+
+use std::io::{Cursor, Read};
+
+use ndarray::ArrayD;
+use ndarray_npy::ReadNpyExt;
+use zip::ZipArchive;
+
+use super::NpyLoader;
+use crate::data::loader::{HyperspectralLoader, LoaderError};
+use crate::data::{HyperspectralData, NpyLayout};
+
+/// One array stored in an `.npz` archive.
+struct NpzArray {
+    /// Array key (entry name without `.npy`)
+    name: String,
+    /// Shape parsed from the `.npy` header (empty if unreadable)
+    shape: Vec<usize>,
+    /// Raw `.npy` bytes
+    data: Vec<u8>,
+}
+
+impl NpzArray {
+    fn element_count(&self) -> usize {
+        self.shape.iter().product()
+    }
+}
+
+/// Loader for NumPy `.npz` archives.
+#[derive(Default)]
+pub struct NpzLoader {
+    /// Key of the array to use as the cube (`None` = largest 3D array)
+    cube_key: Option<String>,
+    /// Declared axis layout of the cube (`None` = guess from shape)
+    layout: Option<NpyLayout>,
+}
+
+impl NpzLoader {
+    /// Key of the optional band center wavelength array (nm).
+    const WAVELENGTHS_KEY: &'static str = "wavelengths";
+    /// Key of the optional band width array (nm).
+    const FWHM_KEY: &'static str = "fwhm";
+    /// Key of the optional bad band list (`0` = bad).
+    const BBL_KEY: &'static str = "bbl";
+
+    /// Create a loader using the given cube key and layout (`None` = auto-detect).
+    pub fn new(cube_key: Option<String>, layout: Option<NpyLayout>) -> Self {
+        Self { cube_key, layout }
+    }
+
+    /// Read all `.npy` entries of the archive.
+    fn read_arrays(data: &[u8]) -> Result<Vec<NpzArray>, LoaderError> {
+        let mut archive = ZipArchive::new(Cursor::new(data))
+            .map_err(|e| format!("Failed to read NPZ archive: {}", e))?;
+
+        let mut arrays = Vec::new();
+        for i in 0..archive.len() {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|e| format!("Failed to read NPZ entry {}: {}", i, e))?;
+            let Some(name) = file.name().strip_suffix(".npy").map(str::to_string) else {
+                log::debug!("NpzLoader: skipping non-array entry {}", file.name());
+                continue;
+            };
+
+            let mut bytes = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut bytes)
+                .map_err(|e| format!("Failed to extract NPZ array '{}': {}", name, e))?;
+
+            arrays.push(NpzArray {
+                shape: npy_shape(&bytes).unwrap_or_default(),
+                name,
+                data: bytes,
+            });
+        }
+
+        if arrays.is_empty() {
+            return Err("NPZ archive contains no arrays".into());
+        }
+        Ok(arrays)
+    }
+
+    /// Check whether `name` is one of the per-band metadata keys.
+    fn is_band_key(name: &str) -> bool {
+        [Self::WAVELENGTHS_KEY, Self::FWHM_KEY, Self::BBL_KEY].contains(&name)
+    }
+
+    /// Pick the cube: the configured key, else the largest 3D (then 2D) array.
+    fn select_cube<'a>(&self, arrays: &'a [NpzArray]) -> Result<&'a NpzArray, LoaderError> {
+        if let Some(key) = &self.cube_key {
+            return arrays.iter().find(|a| a.name == *key).ok_or_else(|| {
+                let keys: Vec<&str> = arrays.iter().map(|a| a.name.as_str()).collect();
+                LoaderError::new(format!(
+                    "NPZ archive has no array '{}' (available: {})",
+                    key,
+                    keys.join(", ")
+                ))
+            });
+        }
+
+        [3, 2]
+            .iter()
+            .find_map(|&ndim| {
+                arrays
+                    .iter()
+                    .filter(|a| a.shape.len() == ndim && !Self::is_band_key(&a.name))
+                    .max_by_key(|a| a.element_count())
+            })
+            .ok_or_else(|| LoaderError::new("NPZ archive contains no 2D or 3D array"))
+    }
+}
+
+impl HyperspectralLoader for NpzLoader {
+    fn id(&self) -> &'static str {
+        "npz"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "NumPy Archive (.npz)"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["npz"]
+    }
+
+    fn can_load(&self, data: &[u8]) -> bool {
+        // ZIP local file header whose first entry is a .npy file
+        const ZIP_MAGIC: &[u8] = &[0x50, 0x4B, 0x03, 0x04];
+        if data.len() < 30 || !data.starts_with(ZIP_MAGIC) {
+            return false;
+        }
+        let name_len = u16::from_le_bytes([data[26], data[27]]) as usize;
+        data.get(30..30 + name_len)
+            .is_some_and(|name| name.ends_with(b".npy"))
+    }
+
+    fn load(&self, data: &[u8]) -> Result<HyperspectralData, LoaderError> {
+        let arrays = Self::read_arrays(data).map_err(|e| e.with_loader(self.id()))?;
+        let cube = self
+            .select_cube(&arrays)
+            .map_err(|e| e.with_loader(self.id()))?;
+
+        let mut result = NpyLoader::new(self.layout).load(&cube.data).map_err(|e| {
+            LoaderError::new(format!("Array '{}': {}", cube.name, e.message)).with_loader(self.id())
+        })?;
+
+        // Per-band metadata arrays; mismatched lengths are reported, not fatal
+        let band_count = result.bands.len();
+        let mut warnings = Vec::new();
+        let mut band_values = |key: &str| {
+            let values = arrays
+                .iter()
+                .find(|a| a.name == key && a.name != cube.name)
+                .and_then(|a| read_f64_array(&a.data))?;
+            if values.len() != band_count {
+                warnings.push(format!(
+                    "Ignoring NPZ array '{}' ({} values for {} bands)",
+                    key,
+                    values.len(),
+                    band_count
+                ));
+                return None;
+            }
+            Some(values)
+        };
+        let wavelengths = band_values(Self::WAVELENGTHS_KEY);
+        let fwhm = band_values(Self::FWHM_KEY);
+        let bbl = band_values(Self::BBL_KEY);
+
+        for (b, info) in result.band_info.iter_mut().enumerate() {
+            if let Some(wl) = &wavelengths {
+                info.wavelength = Some(wl[b] as f32);
+            }
+            if let Some(fwhm) = &fwhm {
+                info.fwhm = Some(fwhm[b] as f32);
+            }
+            if let Some(bbl) = &bbl {
+                info.bad = bbl[b] == 0.0;
+            }
+        }
+        for warning in warnings {
+            log::warn!("NpzLoader: {}", warning);
+            result.warnings.push(warning);
+        }
+
+        let others: Vec<String> = arrays
+            .iter()
+            .filter(|a| a.name != cube.name)
+            .map(|a| format!("{} {:?}", a.name, a.shape))
+            .collect();
+        log::info!(
+            "NpzLoader: using array '{}' {:?}; other arrays: {}",
+            cube.name,
+            cube.shape,
+            if others.is_empty() {
+                "none".to_string()
+            } else {
+                others.join(", ")
+            }
+        );
+
+        Ok(result)
+    }
+
+    fn priority(&self) -> i32 {
+        // Same as NumPy arrays; the ZIP magic alone is not specific
+        10
+    }
+}
+
+/// Parse the `shape` tuple from a `.npy` header.
+fn npy_shape(data: &[u8]) -> Option<Vec<usize>> {
+    if !data.starts_with(&[0x93, b'N', b'U', b'M', b'P', b'Y']) || data.len() < 10 {
+        return None;
+    }
+    // Version 1.x uses a u16 header length, 2.x/3.x a u32
+    let (header_len, start) = if data[6] == 1 {
+        (u16::from_le_bytes([data[8], data[9]]) as usize, 10)
+    } else {
+        (
+            u32::from_le_bytes(data.get(8..12)?.try_into().ok()?) as usize,
+            12,
+        )
+    };
+    let header = std::str::from_utf8(data.get(start..start + header_len)?).ok()?;
+
+    let after_key = &header[header.find("'shape'")?..];
+    let open = after_key.find('(')?;
+    let close = after_key.find(')')?;
+    after_key
+        .get(open + 1..close)?
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim_end_matches('L').parse().ok())
+        .collect()
+}
+
+/// Read a numeric `.npy` array as f64 values (flattened).
+fn read_f64_array(data: &[u8]) -> Option<Vec<f64>> {
+    if let Ok(a) = ArrayD::<f64>::read_npy(Cursor::new(data)) {
+        return Some(a.iter().copied().collect());
+    }
+    if let Ok(a) = ArrayD::<f32>::read_npy(Cursor::new(data)) {
+        return Some(a.iter().map(|&v| f64::from(v)).collect());
+    }
+    if let Ok(a) = ArrayD::<i64>::read_npy(Cursor::new(data)) {
+        return Some(a.iter().map(|&v| v as f64).collect());
+    }
+    if let Ok(a) = ArrayD::<i32>::read_npy(Cursor::new(data)) {
+        return Some(a.iter().map(|&v| f64::from(v)).collect());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    /// Encode a little-endian, C-order `.npy` (format version 1.0).
+    fn npy_bytes(descr: &str, shape: &[usize], body: &[u8]) -> Vec<u8> {
+        let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+        let shape_str = if shape.len() == 1 {
+            format!("({},)", dims[0])
+        } else {
+            format!("({})", dims.join(", "))
+        };
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape_str
+        );
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut out = vec![0x93, b'N', b'U', b'M', b'P', b'Y', 1, 0];
+        out.extend((header.len() as u16).to_le_bytes());
+        out.extend(header.as_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn f32_npy(shape: &[usize], values: &[f32]) -> Vec<u8> {
+        let body: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        npy_bytes("<f4", shape, &body)
+    }
+
+    fn npz_bytes(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            writer.start_file(format!("{}.npy", name), options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Cube (3 bands of 4x5), wavelengths and a mask.
+    fn sample_npz() -> Vec<u8> {
+        let cube: Vec<f32> = (0..60).map(|v| v as f32 / 60.0).collect();
+        npz_bytes(&[
+            ("mask", f32_npy(&[2, 2], &[1.0, 0.0, 0.0, 1.0])),
+            ("cube", f32_npy(&[3, 4, 5], &cube)),
+            ("wavelengths", f32_npy(&[3], &[450.0, 550.5, 650.0])),
+        ])
+    }
+
+    #[test]
+    fn test_loader_metadata() {
+        let loader = NpzLoader::default();
+        assert_eq!(loader.id(), "npz");
+        assert!(loader.extensions().contains(&"npz"));
+        assert!(loader.can_load(&sample_npz()));
+
+        // Plain ZIP without .npy entries is not claimed
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("image.png", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"not an array").unwrap();
+        let zip = writer.finish().unwrap().into_inner();
+        assert!(!loader.can_load(&zip));
+    }
+
+    #[test]
+    fn test_npy_shape() {
+        assert_eq!(
+            npy_shape(&f32_npy(&[3, 2, 2], &[0.0; 12])),
+            Some(vec![3, 2, 2])
+        );
+        assert_eq!(npy_shape(&f32_npy(&[5], &[0.0; 5])), Some(vec![5]));
+        assert_eq!(npy_shape(b"not numpy"), None);
+    }
+
+    #[test]
+    fn test_largest_3d_with_wavelengths() {
+        let result = NpzLoader::default().load(&sample_npz()).unwrap();
+        assert_eq!(result.bands.len(), 3);
+        assert_eq!((result.width, result.height), (5, 4));
+        let wavelengths: Vec<_> = result.band_info.iter().map(|b| b.wavelength).collect();
+        assert_eq!(wavelengths, [Some(450.0), Some(550.5), Some(650.0)]);
+    }
+
+    #[test]
+    fn test_configured_key() {
+        let data = sample_npz();
+
+        let mask = NpzLoader::new(Some("mask".to_string()), None)
+            .load(&data)
+            .unwrap();
+        assert_eq!(mask.bands.len(), 1);
+        assert_eq!(mask.bands[0], vec![1.0, 0.0, 0.0, 1.0]);
+
+        let Err(err) = NpzLoader::new(Some("missing".to_string()), None).load(&data) else {
+            panic!("missing key should fail");
+        };
+        assert!(err.message.contains("available: mask, cube, wavelengths"));
+    }
+
+    #[test]
+    fn test_layout_forwarded_to_npy() {
+        let result = NpzLoader::new(None, Some(NpyLayout::Whb))
+            .load(&sample_npz())
+            .unwrap();
+        assert_eq!(result.bands.len(), 5);
+        assert_eq!((result.width, result.height), (3, 4));
+        // Declared layout: no guess, but the 3 wavelengths no longer fit 5 bands
+        assert!(!result.warnings.iter().any(|w| w.contains("guessed")));
+        assert!(result.warnings.iter().any(|w| w.contains("'wavelengths'")));
+    }
+
+    #[test]
+    fn test_wavelength_count_mismatch_keeps_default_labels() {
+        let data = npz_bytes(&[
+            ("cube", f32_npy(&[2, 3, 3], &[0.5; 18])),
+            ("wavelengths", f32_npy(&[3], &[1.0, 2.0, 3.0])),
+        ]);
+        let result = NpzLoader::default().load(&data).unwrap();
+        assert_eq!(result.band_info[0].wavelength, None);
+        assert_eq!(result.band_info[1].name, "Band 2");
+        assert!(result.warnings.iter().any(|w| w.contains("'wavelengths'")));
+    }
+
+    #[test]
+    fn test_fwhm_and_bad_bands() {
+        let data = npz_bytes(&[
+            ("cube", f32_npy(&[2, 3, 3], &[0.5; 18])),
+            ("fwhm", f32_npy(&[2], &[4.0, 6.0])),
+            ("bbl", f32_npy(&[2], &[1.0, 0.0])),
+        ]);
+        let result = NpzLoader::default().load(&data).unwrap();
+        assert_eq!(result.band_info[1].fwhm, Some(6.0));
+        assert!(!result.band_info[0].bad);
+        assert!(result.band_info[1].bad);
+    }
+
+    #[test]
+    fn test_no_cube() {
+        let data = npz_bytes(&[("wavelengths", f32_npy(&[3], &[1.0, 2.0, 3.0]))]);
+        assert!(NpzLoader::default().load(&data).is_err());
+    }
+}
@@ -0,0 +1,534 @@
+//! Loader for multi-band TIFF and GeoTIFF files.
+//!
+//! Unlike `ImageLoader`, which decodes through the `image` crate and collapses
+//! everything to RGB, this loader emits one band per TIFF sample and keeps the
+//! full sample precision. Supported layouts:
+//!
+//! - Any number of samples per pixel, chunky or planar configuration
+//! - Strip or tile organisation
+//! - u8/u16/u32/u64, i8/i16/i32/i64 and f16/f32/f64 sample formats
+//!
+//! Palette, YCbCr and sub-byte images are rejected so the registry falls back
+//! to `ImageLoader` for them.
+//!
+//! When GeoTIFF tags are present (`ModelTransformationTag`, or
+//! `ModelPixelScaleTag` + `ModelTiepointTag`), the pixel-to-map transform is
+//! attached to the loaded data as a `GeoTransform`.
+
+use std::io::Cursor;
+
+use tiff::ColorType;
+use tiff::decoder::{ChunkType, Decoder, DecodingResult, Limits};
+use tiff::tags::Tag;
+
+use crate::data::loader::{HyperspectralLoader, LoaderError};
+use crate::data::{GeoTransform, HyperspectralData};
+
+/// `PlanarConfiguration` tag value for separate sample planes.
+const PLANAR_SEPARATE: u16 = 2;
+
+/// Loader for multi-sample TIFF / GeoTIFF files.
+pub struct TiffLoader;
+
+impl TiffLoader {
+    /// Decode the first image of a TIFF file into bands.
+    fn decode(data: &[u8]) -> Result<HyperspectralData, LoaderError> {
+        let mut decoder = Decoder::new(Cursor::new(data))
+            .map_err(|e| format!("Failed to read TIFF: {}", e))?
+            .with_limits(Limits::unlimited());
+
+        let (width, height) = decoder
+            .dimensions()
+            .map_err(|e| format!("Failed to read TIFF dimensions: {}", e))?;
+
+        match decoder.colortype() {
+            Ok(ColorType::Palette(_) | ColorType::YCbCr(_)) => {
+                return Err("Palette/YCbCr TIFFs are handled by the image loader".into());
+            }
+            Ok(_) => {}
+            Err(e) => return Err(format!("Unsupported TIFF color type: {}", e).into()),
+        }
+
+        let bits = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)
+            .ok()
+            .flatten()
+            .and_then(|b| b.first().copied())
+            .unwrap_or(1);
+        if bits < 8 {
+            return Err(format!("Unsupported TIFF bit depth: {}", bits).into());
+        }
+
+        let samples = decoder
+            .find_tag_unsigned::<u16>(Tag::SamplesPerPixel)
+            .ok()
+            .flatten()
+            .unwrap_or(1) as usize;
+        let planar = decoder
+            .find_tag_unsigned::<u16>(Tag::PlanarConfiguration)
+            .ok()
+            .flatten()
+            == Some(PLANAR_SEPARATE);
+
+        let geotransform = read_geotransform(&mut decoder);
+
+        let chunk_type = decoder.get_chunk_type();
+        let chunk_count = match chunk_type {
+            ChunkType::Strip => decoder.strip_count(),
+            ChunkType::Tile => decoder.tile_count(),
+        }
+        .map_err(|e| format!("Failed to read TIFF layout: {}", e))?;
+
+        let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+        let chunks_across = match chunk_type {
+            ChunkType::Strip => 1,
+            ChunkType::Tile => width.div_ceil(chunk_width),
+        };
+        let chunks_per_plane = if planar {
+            chunk_count / samples as u32
+        } else {
+            chunk_count
+        };
+        if chunks_per_plane == 0 {
+            return Err("TIFF contains no image data".into());
+        }
+        let samples_per_chunk_pixel = if planar { 1 } else { samples };
+
+        let (w, h) = (width as usize, height as usize);
+        let mut bands = vec![vec![0.0f32; w * h]; samples];
+
+        for chunk in 0..chunk_count {
+            let plane = (chunk / chunks_per_plane) as usize;
+            let index = chunk % chunks_per_plane;
+            let x0 = ((index % chunks_across) * chunk_width) as usize;
+            let y0 = ((index / chunks_across) * chunk_height) as usize;
+            if plane >= samples || x0 >= w || y0 >= h {
+                continue;
+            }
+
+            let (data_width, data_height) = decoder.chunk_data_dimensions(chunk);
+            let (data_width, data_height) = (data_width as usize, data_height as usize);
+            let values = normalize(
+                decoder
+                    .read_chunk(chunk)
+                    .map_err(|e| format!("Failed to decode TIFF chunk {}: {}", chunk, e))?,
+            );
+            if values.len() < data_width * data_height * samples_per_chunk_pixel {
+                return Err(format!("TIFF chunk {} is truncated", chunk).into());
+            }
+
+            // Tiles on the right/bottom edge may extend past the image
+            let cols = data_width.min(w - x0);
+            let rows = data_height.min(h - y0);
+            for row in 0..rows {
+                for col in 0..cols {
+                    let src = (row * data_width + col) * samples_per_chunk_pixel;
+                    let dst = (y0 + row) * w + x0 + col;
+                    if planar {
+                        bands[plane][dst] = values[src];
+                    } else {
+                        for (s, band) in bands.iter_mut().enumerate() {
+                            band[dst] = values[src + s];
+                        }
+                    }
+                }
+            }
+        }
+
+        let labels = (1..=samples).map(|i| format!("Band {}", i)).collect();
+
+        log::debug!(
+            "TIFF: {}x{} with {} samples ({}-bit, {}, {:?}){}",
+            width,
+            height,
+            samples,
+            bits,
+            if planar { "planar" } else { "chunky" },
+            chunk_type,
+            if geotransform.is_some() {
+                ", georeferenced"
+            } else {
+                ""
+            }
+        );
+
+        Ok(HyperspectralData::new(bands, width, height, labels).with_geotransform(geotransform))
+    }
+}
+
+/// Normalize decoded samples to f32.
+///
+/// Uses the same convention as the NumPy loader: integers are mapped over
+/// their full type range, floats are passed through unchanged.
+fn normalize(result: DecodingResult) -> Vec<f32> {
+    match result {
+        DecodingResult::U8(v) => v.into_iter().map(|x| f32::from(x) / 255.0).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(|x| f32::from(x) / 65535.0).collect(),
+        DecodingResult::U32(v) => v
+            .into_iter()
+            .map(|x| (f64::from(x) / f64::from(u32::MAX)) as f32)
+            .collect(),
+        DecodingResult::U64(v) => v
+            .into_iter()
+            .map(|x| (x as f64 / u64::MAX as f64) as f32)
+            .collect(),
+        DecodingResult::I8(v) => v
+            .into_iter()
+            .map(|x| (f32::from(x) + 128.0) / 255.0)
+            .collect(),
+        DecodingResult::I16(v) => v
+            .into_iter()
+            .map(|x| (f32::from(x) + 32768.0) / 65535.0)
+            .collect(),
+        DecodingResult::I32(v) => v
+            .into_iter()
+            .map(|x| ((f64::from(x) + 2_147_483_648.0) / 4_294_967_295.0) as f32)
+            .collect(),
+        DecodingResult::I64(v) => v
+            .into_iter()
+            .map(|x| ((x as f64 - i64::MIN as f64) / u64::MAX as f64) as f32)
+            .collect(),
+        DecodingResult::F16(v) => v.into_iter().map(|x| x.to_f32()).collect(),
+        DecodingResult::F32(v) => v,
+        DecodingResult::F64(v) => v.into_iter().map(|x| x as f32).collect(),
+    }
+}
+
+/// Read the GeoTIFF pixel-to-map transform, if present.
+fn read_geotransform(decoder: &mut Decoder<Cursor<&[u8]>>) -> Option<GeoTransform> {
+    // Full affine matrix (row-major 4x4) takes precedence
+    if let Ok(m) = decoder.get_tag_f64_vec(Tag::ModelTransformationTag)
+        && m.len() >= 16
+    {
+        return Some(GeoTransform([m[3], m[0], m[1], m[7], m[4], m[5]]));
+    }
+
+    // Otherwise: pixel scale + a single tiepoint (I, J, K, X, Y, Z)
+    let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag).ok()?;
+    let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag).ok()?;
+    if scale.len() < 2 || tiepoint.len() < 6 {
+        return None;
+    }
+    let (sx, sy) = (scale[0], scale[1]);
+    let (i, j, x, y) = (tiepoint[0], tiepoint[1], tiepoint[3], tiepoint[4]);
+    Some(GeoTransform([x - i * sx, sx, 0.0, y + j * sy, 0.0, -sy]))
+}
+
+impl HyperspectralLoader for TiffLoader {
+    fn id(&self) -> &'static str {
+        "tiff"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "TIFF / GeoTIFF (multi-band)"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["tif", "tiff"]
+    }
+
+    fn can_load(&self, data: &[u8]) -> bool {
+        // Little endian "II*\0", big endian "MM\0*" (classic and BigTIFF)
+        data.starts_with(&[0x49, 0x49, 0x2A, 0x00])
+            || data.starts_with(&[0x4D, 0x4D, 0x00, 0x2A])
+            || data.starts_with(&[0x49, 0x49, 0x2B, 0x00])
+            || data.starts_with(&[0x4D, 0x4D, 0x00, 0x2B])
+    }
+
+    fn load(&self, data: &[u8]) -> Result<HyperspectralData, LoaderError> {
+        Self::decode(data).map_err(|e| e.with_loader(self.id()))
+    }
+
+    fn priority(&self) -> i32 {
+        // Checked before the generic image loader, which handles the
+        // palette/YCbCr TIFFs this loader rejects.
+        5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A TIFF directory entry for the test writer.
+    enum Field {
+        Short(u16, Vec<u16>),
+        Long(u16, Vec<u32>),
+        Double(u16, Vec<f64>),
+    }
+
+    impl Field {
+        fn tag(&self) -> u16 {
+            match self {
+                Self::Short(t, _) | Self::Long(t, _) | Self::Double(t, _) => *t,
+            }
+        }
+
+        fn type_and_count(&self) -> (u16, usize) {
+            match self {
+                Self::Short(_, v) => (3, v.len()),
+                Self::Long(_, v) => (4, v.len()),
+                Self::Double(_, v) => (12, v.len()),
+            }
+        }
+
+        fn bytes(&self) -> Vec<u8> {
+            match self {
+                Self::Short(_, v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+                Self::Long(_, v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+                Self::Double(_, v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            }
+        }
+    }
+
+    /// Write a minimal little-endian TIFF with uncompressed chunks.
+    fn build_tiff(mut fields: Vec<Field>, chunks: &[Vec<u8>], tiled: bool) -> Vec<u8> {
+        let (offsets_tag, counts_tag) = if tiled { (324, 325) } else { (273, 279) };
+        fields.push(Field::Long(offsets_tag, vec![0; chunks.len()]));
+        fields.push(Field::Long(
+            counts_tag,
+            chunks.iter().map(|c| c.len() as u32).collect(),
+        ));
+        fields.sort_by_key(Field::tag);
+
+        let extra_start = 8 + 2 + 12 * fields.len() + 4;
+        let extra_len: usize = fields
+            .iter()
+            .map(|f| f.bytes().len())
+            .filter(|&n| n > 4)
+            .sum();
+        let mut offset = (extra_start + extra_len) as u32;
+        for field in &mut fields {
+            if let Field::Long(tag, values) = field
+                && *tag == offsets_tag
+            {
+                for (value, chunk) in values.iter_mut().zip(chunks) {
+                    *value = offset;
+                    offset += chunk.len() as u32;
+                }
+            }
+        }
+
+        let mut out = vec![0x49, 0x49, 0x2A, 0x00, 8, 0, 0, 0];
+        let mut extra = Vec::new();
+        out.extend((fields.len() as u16).to_le_bytes());
+        for field in &fields {
+            let (ty, count) = field.type_and_count();
+            let bytes = field.bytes();
+            out.extend(field.tag().to_le_bytes());
+            out.extend(ty.to_le_bytes());
+            out.extend((count as u32).to_le_bytes());
+            if bytes.len() <= 4 {
+                let mut inline = bytes;
+                inline.resize(4, 0);
+                out.extend(inline);
+            } else {
+                out.extend(((extra_start + extra.len()) as u32).to_le_bytes());
+                extra.extend(bytes);
+            }
+        }
+        out.extend(0u32.to_le_bytes());
+        out.extend(extra);
+        for chunk in chunks {
+            out.extend(chunk);
+        }
+        out
+    }
+
+    /// Common tags for a `width`x`height` image.
+    fn base_fields(width: u32, height: u32, samples: u16, bits: u16, format: u16) -> Vec<Field> {
+        vec![
+            Field::Long(256, vec![width]),
+            Field::Long(257, vec![height]),
+            Field::Short(258, vec![bits; samples as usize]),
+            Field::Short(259, vec![1]),
+            Field::Short(262, vec![1]),
+            Field::Short(277, vec![samples]),
+            Field::Short(339, vec![format; samples as usize]),
+        ]
+    }
+
+    #[test]
+    fn test_loader_metadata() {
+        let loader = TiffLoader;
+        assert_eq!(loader.id(), "tiff");
+        assert!(loader.extensions().contains(&"tif"));
+        assert!(loader.extensions().contains(&"tiff"));
+        assert!(loader.can_load(b"II*\0rest"));
+        assert!(loader.can_load(b"MM\0*rest"));
+        assert!(!loader.can_load(b"\x89PNG"));
+        assert!(loader.priority() > 0);
+    }
+
+    #[test]
+    fn test_chunky_u16_multiband() {
+        // 3x2 image, 5 samples, value = 1000 * sample + pixel index
+        let (width, height, samples) = (3u32, 2u32, 5u16);
+        let mut data = Vec::new();
+        for p in 0..6u16 {
+            for s in 0..samples {
+                data.extend((1000 * s + p).to_le_bytes());
+            }
+        }
+        let mut fields = base_fields(width, height, samples, 16, 1);
+        fields.push(Field::Long(278, vec![height]));
+        let tiff = build_tiff(fields, &[data], false);
+
+        let result = TiffLoader.load(&tiff).unwrap();
+        assert_eq!((result.width, result.height), (3, 2));
+        assert_eq!(result.bands.len(), 5);
+        assert!(result.geotransform.is_none());
+        for s in 0..5usize {
+            for p in 0..6usize {
+                let expected = (1000 * s + p) as f32 / 65535.0;
+                assert!((result.bands[s][p] - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_planar_f32_multiple_strips() {
+        // 2x3 image, 3 planes, one row per strip
+        let (width, height) = (2u32, 3u32);
+        let value = |s: usize, y: usize, x: usize| (s * 100 + y * 10 + x) as f32;
+        let mut strips = Vec::new();
+        for s in 0..3 {
+            for y in 0..3 {
+                strips.push(
+                    (0..2)
+                        .flat_map(|x| value(s, y, x).to_le_bytes())
+                        .collect::<Vec<u8>>(),
+                );
+            }
+        }
+        let mut fields = base_fields(width, height, 3, 32, 3);
+        fields.push(Field::Long(278, vec![1]));
+        fields.push(Field::Short(284, vec![PLANAR_SEPARATE]));
+        let tiff = build_tiff(fields, &strips, false);
+
+        let result = TiffLoader.load(&tiff).unwrap();
+        assert_eq!(result.bands.len(), 3);
+        for s in 0..3 {
+            for y in 0..3 {
+                for x in 0..2 {
+                    assert_eq!(result.bands[s][y * 2 + x], value(s, y, x));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiled_with_edge_padding() {
+        // 20x18 image in 16x16 tiles: right and bottom tiles are padded
+        let (width, height, tile) = (20u32, 18u32, 16usize);
+        let value = |s: usize, y: usize, x: usize| ((y * 20 + x + s * 7) % 256) as u8;
+        let tile_data = |planes: &[usize], ty: usize, tx: usize| {
+            let mut data = Vec::new();
+            for row in 0..tile {
+                for col in 0..tile {
+                    let (y, x) = (ty * tile + row, tx * tile + col);
+                    for &s in planes {
+                        data.push(if x < 20 && y < 18 { value(s, y, x) } else { 0 });
+                    }
+                }
+            }
+            data
+        };
+
+        for planar in [false, true] {
+            let mut tiles = Vec::new();
+            let plane_sets: Vec<Vec<usize>> = if planar {
+                vec![vec![0], vec![1]]
+            } else {
+                vec![vec![0, 1]]
+            };
+            for planes in &plane_sets {
+                for ty in 0..2 {
+                    for tx in 0..2 {
+                        tiles.push(tile_data(planes, ty, tx));
+                    }
+                }
+            }
+            let mut fields = base_fields(width, height, 2, 8, 1);
+            fields.push(Field::Long(322, vec![tile as u32]));
+            fields.push(Field::Long(323, vec![tile as u32]));
+            if planar {
+                fields.push(Field::Short(284, vec![PLANAR_SEPARATE]));
+            }
+            let tiff = build_tiff(fields, &tiles, true);
+
+            let result = TiffLoader.load(&tiff).unwrap();
+            assert_eq!((result.width, result.height), (20, 18));
+            assert_eq!(result.bands.len(), 2);
+            for s in 0..2 {
+                for y in 0..18 {
+                    for x in 0..20 {
+                        let expected = f32::from(value(s, y, x)) / 255.0;
+                        assert!((result.bands[s][y * 20 + x] - expected).abs() < 1e-6);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_signed_normalization() {
+        let data: Vec<u8> = [-32768i16, 0, 32767]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let mut fields = base_fields(3, 1, 1, 16, 2);
+        fields.push(Field::Long(278, vec![1]));
+        let tiff = build_tiff(fields, &[data], false);
+
+        let result = TiffLoader.load(&tiff).unwrap();
+        assert!(result.bands[0][0].abs() < 1e-6);
+        assert!((result.bands[0][1] - 0.5).abs() < 1e-4);
+        assert!((result.bands[0][2] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_geotransform_from_tiepoint() {
+        let mut fields = base_fields(2, 2, 1, 8, 1);
+        fields.push(Field::Long(278, vec![2]));
+        fields.push(Field::Double(33550, vec![10.0, 5.0, 0.0]));
+        fields.push(Field::Double(
+            33922,
+            vec![1.0, 2.0, 0.0, 500_000.0, 6_000_000.0, 0.0],
+        ));
+        let tiff = build_tiff(fields, &[vec![0, 1, 2, 3]], false);
+
+        let result = TiffLoader.load(&tiff).unwrap();
+        let gt = result.geotransform.unwrap();
+        assert_eq!(gt.0, [499_990.0, 10.0, 0.0, 6_000_010.0, 0.0, -5.0]);
+        assert_eq!(gt.pixel_to_map(1.0, 2.0), (500_000.0, 6_000_000.0));
+    }
+
+    #[test]
+    fn test_geotransform_from_matrix() {
+        let mut fields = base_fields(1, 1, 1, 8, 1);
+        fields.push(Field::Long(278, vec![1]));
+        #[rustfmt::skip]
+        fields.push(Field::Double(34264, vec![
+            2.0, 0.5, 0.0, 100.0,
+            0.25, -3.0, 0.0, 200.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]));
+        let tiff = build_tiff(fields, &[vec![7]], false);
+
+        let gt = TiffLoader.load(&tiff).unwrap().geotransform.unwrap();
+        assert_eq!(gt.0, [100.0, 2.0, 0.5, 200.0, 0.25, -3.0]);
+    }
+
+    #[test]
+    fn test_rejects_palette() {
+        let mut fields = base_fields(1, 1, 1, 8, 1);
+        fields.retain(|f| f.tag() != 262);
+        fields.push(Field::Short(262, vec![3]));
+        fields.push(Field::Long(278, vec![1]));
+        let tiff = build_tiff(fields, &[vec![0]], false);
+
+        assert!(TiffLoader.load(&tiff).is_err());
+    }
+}
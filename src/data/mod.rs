@@ -2,15 +2,40 @@
 //!
 //! This module provides:
 //! - `HyperspectralData`: CPU-side representation of hyperspectral images
+//! - `BandInfo`: Per-band metadata (wavelength, FWHM, name, bad-band flag)
+//! - `BandHistogram` / `ChannelStretch`: Band value distributions and the
+//!   automatic contrast stretch derived from them
+//! - `RegionStats`: Per-band statistics of a set of pixels (e.g. an annotation)
+//! - `BandExpr` / `BandMathProgram`: Band-math expressions (e.g. NDVI) with a
+//!   CPU evaluator and WGSL code generation for display
+//! - `PrincipalComponents`: PCA / MNF components of a cube for composite views
+//! - `TrueColor`: sRGB rendering of the visible spectrum through the CIE 1931
+//!   color matching functions under a D65 or D50 illuminant
+//! - `SimilarityMap`: Spectral angle / Euclidean distance of every pixel to a
+//!   reference spectrum, with thresholded regions as polygons
 //! - `LoaderRegistry`: Extensible system for loading various file formats
-//! - Built-in loaders for images (PNG, JPEG, etc.) and NumPy (.npy) files
+//! - `FileResolver`: Access to sibling files for multi-file formats (e.g. ENVI)
+//! - `FileBytes`: File contents in memory or left on disk and read on demand
+//!   (native)
+//! - `LoaderOptions`: User settings for loaders (e.g. the `.npz` cube key or
+//!   the `.npy` axis layout, per project or file pattern)
+//! - `LoadSubset`: Band subset, band stride and spatial binning applied while
+//!   loading
+//! - `Calibration`: Dark/white reference calibration to reflectance, run as a
+//!   `DataProcessor` on decoded data
+//! - `PreprocessingChain`: Bad-band exclusion, Savitzky–Golay smoothing,
+//!   derivatives, continuum removal and normalization of every spectrum
+//! - Built-in loaders for images (PNG, JPEG, etc.), multi-band TIFF/GeoTIFF,
+//!   NumPy (.npy/.npz) and ENVI (.hdr) files
 //!
 //! ## Adding New Formats
 //!
-//! To add support for a new format (e.g., ENVI, HDF5):
+//! To add support for a new format (e.g., HDF5):
 //!
 //! 1. Create a new loader in `loaders/` implementing `HyperspectralLoader`
 //! 2. Register it in `LoaderRegistry::new()`
+//! 3. If the format spans several files, override `load_with_resolver`,
+//!    `sidecar_candidates` and `sidecar_extensions`
 //!
 //! ```rust,ignore
 //! use hvat::data::{HyperspectralLoader, LoaderError, HyperspectralData};
@@ -26,9 +51,44 @@
 //! }
 //! ```
 
+mod band_math;
+mod bands;
+mod calibration;
+mod file_bytes;
+mod histogram;
 mod hyperspectral;
 mod loader;
 pub mod loaders;
+mod options;
+mod pca;
+mod preprocess;
+mod region_stats;
+mod resolver;
+mod similarity;
+mod stretch;
+mod subset;
+mod true_color;
 
-pub use hyperspectral::HyperspectralData;
-pub use loader::{HyperspectralLoader, LoaderError, LoaderRegistry};
+pub use band_math::{BandExpr, BandMathError, BandMathMode, BandMathPreset, BandMathProgram};
+pub use bands::{BandInfo, default_bands, nearest_band, parse_wavelength};
+pub use calibration::{
+    Calibration, CalibrationRule, CalibrationSource, ReferenceFrame, STEM_PLACEHOLDER,
+    format_factors, parse_factors,
+};
+pub use file_bytes::FileBytes;
+pub use histogram::{BandHistogram, band_histograms};
+pub use hyperspectral::{GeoTransform, HyperspectralData};
+pub use loader::{DataProcessor, HyperspectralLoader, LoaderError, LoaderRegistry};
+pub use options::{LoaderOptions, NpyLayout, project_pattern};
+pub use pca::{ComponentMethod, PrincipalComponents};
+pub use preprocess::{
+    BandExclusion, MAX_SMOOTHING_ORDER, MAX_SMOOTHING_WINDOW, PreprocessStep, PreprocessingChain,
+};
+pub use region_stats::{RegionStats, region_spectra};
+#[cfg(not(target_arch = "wasm32"))]
+pub use resolver::FsResolver;
+pub use resolver::{FileResolver, MemoryResolver};
+pub use similarity::{SimilarityMap, SimilarityMetric};
+pub use stretch::{ChannelStretch, StretchMode};
+pub use subset::LoadSubset;
+pub use true_color::{Illuminant, TrueColor};
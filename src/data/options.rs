@@ -0,0 +1,351 @@
+//! User-configurable loader settings.
+//!
+//! `LoaderOptions` is persisted in the application config and passed to
+//! `LoaderRegistry::with_options` so loaders can honour user choices that
+//! cannot be detected from the file itself.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{CalibrationRule, CalibrationSource, LoadSubset};
+
+/// Axis order of a NumPy cube: B = bands, W = width (columns), H = height (rows).
+///
+/// For 2D arrays the band axis is dropped (e.g. `Hwb` reads `(H, W)`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum NpyLayout {
+    /// `(bands, width, height)`
+    Bwh,
+    /// `(width, height, bands)`
+    Whb,
+    /// `(height, width, bands)` - OpenCV / NumPy image convention
+    Hwb,
+    /// `(bands, height, width)` - channels-first convention
+    Bhw,
+}
+
+impl NpyLayout {
+    /// Get the display name for this layout.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NpyLayout::Bwh => "BWH",
+            NpyLayout::Whb => "WHB",
+            NpyLayout::Hwb => "HWB",
+            NpyLayout::Bhw => "BHW",
+        }
+    }
+
+    /// Get all layouts.
+    pub fn all() -> &'static [NpyLayout] {
+        &[
+            NpyLayout::Bwh,
+            NpyLayout::Whb,
+            NpyLayout::Hwb,
+            NpyLayout::Bhw,
+        ]
+    }
+
+    /// Positions of the (bands, height, width) axes in a 3D shape.
+    pub(crate) fn axes(&self) -> [usize; 3] {
+        match self {
+            NpyLayout::Bwh => [0, 2, 1],
+            NpyLayout::Whb => [2, 1, 0],
+            NpyLayout::Hwb => [2, 0, 1],
+            NpyLayout::Bhw => [0, 1, 2],
+        }
+    }
+}
+
+/// Layout override for files whose path matches `pattern`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NpyLayoutRule {
+    /// Glob pattern (`*`, `?`); matched against the file name when it has
+    /// no `/`, otherwise against the full path
+    pub pattern: String,
+    /// Layout used for matching files
+    pub layout: NpyLayout,
+}
+
+/// Settings that influence how files are decoded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoaderOptions {
+    /// Array key used as the data cube in `.npz` archives.
+    ///
+    /// `None` selects the largest 3D array.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub npz_cube_key: Option<String>,
+
+    /// Declared axis layout of `.npy`/`.npz` cubes.
+    ///
+    /// `None` guesses from the shape and reports a warning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub npy_layout: Option<NpyLayout>,
+
+    /// Per-project / per-pattern layout overrides (first match wins).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub npy_layout_rules: Vec<NpyLayoutRule>,
+
+    /// Band subset, band stride and spatial binning applied while loading.
+    #[serde(default, skip_serializing_if = "LoadSubset::is_full")]
+    pub subset: LoadSubset,
+
+    /// Radiometric calibration per project / per pattern (first match wins).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calibration_rules: Vec<CalibrationRule>,
+}
+
+impl LoaderOptions {
+    /// Layout declared for `path`: the first matching rule, else the default.
+    pub fn npy_layout_for(&self, path: &Path) -> Option<NpyLayout> {
+        self.npy_layout_rules
+            .iter()
+            .find(|rule| pattern_matches(&rule.pattern, path))
+            .map(|rule| rule.layout)
+            .or(self.npy_layout)
+    }
+
+    /// Options with the per-path rules resolved for `path`.
+    ///
+    /// The calibration is resolved separately (see `calibration_for`), as it
+    /// needs its reference files loaded.
+    pub fn for_path(&self, path: &Path) -> Self {
+        Self {
+            npy_layout: self.npy_layout_for(path),
+            npy_layout_rules: Vec::new(),
+            calibration_rules: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Calibration of `path` from the first matching rule, if any.
+    pub fn calibration_for(&self, path: &Path) -> Option<CalibrationSource> {
+        self.calibration_rules
+            .iter()
+            .find(|rule| pattern_matches(&rule.pattern, path))
+            .map(|rule| rule.resolve(path))
+    }
+
+    /// Add a calibration rule, replacing the rule with the same pattern.
+    pub fn set_calibration_rule(&mut self, rule: CalibrationRule) {
+        match self
+            .calibration_rules
+            .iter_mut()
+            .find(|existing| existing.pattern == rule.pattern)
+        {
+            Some(existing) => *existing = rule,
+            None => self.calibration_rules.push(rule),
+        }
+    }
+
+    /// Set (or with `None`, remove) the layout rule for `pattern`.
+    pub fn set_npy_layout_rule(&mut self, pattern: &str, layout: Option<NpyLayout>) {
+        let existing = self
+            .npy_layout_rules
+            .iter()
+            .position(|rule| rule.pattern == pattern);
+        match (existing, layout) {
+            (Some(idx), Some(layout)) => self.npy_layout_rules[idx].layout = layout,
+            (Some(idx), None) => {
+                self.npy_layout_rules.remove(idx);
+            }
+            (None, Some(layout)) => self.npy_layout_rules.push(NpyLayoutRule {
+                pattern: pattern.to_string(),
+                layout,
+            }),
+            (None, None) => {}
+        }
+    }
+
+    /// Layout of the rule with exactly this pattern, if any.
+    pub fn npy_layout_rule(&self, pattern: &str) -> Option<NpyLayout> {
+        self.npy_layout_rules
+            .iter()
+            .find(|rule| rule.pattern == pattern)
+            .map(|rule| rule.layout)
+    }
+}
+
+/// Pattern covering every file of a project folder.
+///
+/// Projects without a folder (e.g. files picked in the browser) match any file.
+pub fn project_pattern(folder: &Path) -> String {
+    let folder = folder.to_string_lossy().replace('\\', "/");
+    let folder = folder.trim_end_matches('/');
+    if folder.is_empty() {
+        "*".to_string()
+    } else {
+        format!("{}/*", folder)
+    }
+}
+
+/// Check whether `path` matches a glob `pattern`.
+///
+/// Patterns without `/` are matched against the file name only.
+pub(crate) fn pattern_matches(pattern: &str, path: &Path) -> bool {
+    let pattern = pattern.replace('\\', "/");
+    let full = path.to_string_lossy().replace('\\', "/");
+    let target = if pattern.contains('/') {
+        full.as_str()
+    } else {
+        full.rsplit('/').next().unwrap_or(&full)
+    };
+    glob_match(pattern.as_bytes(), target.as_bytes())
+}
+
+/// Minimal glob matcher: `*` matches any run of characters, `?` exactly one.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*.npy", b"cube.npy"));
+        assert!(glob_match(b"cube_??.npy", b"cube_01.npy"));
+        assert!(glob_match(b"*", b""));
+        assert!(!glob_match(b"*.npy", b"cube.npz"));
+        assert!(!glob_match(b"cube_?.npy", b"cube_01.npy"));
+    }
+
+    #[test]
+    fn test_pattern_matches_name_or_path() {
+        let path = Path::new("/data/scans/cube_01.npy");
+        assert!(pattern_matches("cube_*.npy", path));
+        assert!(pattern_matches("/data/scans/*", path));
+        assert!(!pattern_matches("/data/other/*", path));
+        assert!(pattern_matches("C:\\data\\*", Path::new("C:/data/x.npy")));
+    }
+
+    #[test]
+    fn test_layout_resolution() {
+        let mut options = LoaderOptions {
+            npy_layout: Some(NpyLayout::Bhw),
+            ..Default::default()
+        };
+        let path = Path::new("/data/scans/cube.npy");
+        assert_eq!(options.npy_layout_for(path), Some(NpyLayout::Bhw));
+
+        options.set_npy_layout_rule(
+            &project_pattern(Path::new("/data/scans/")),
+            Some(NpyLayout::Hwb),
+        );
+        options.set_npy_layout_rule("*.npz", Some(NpyLayout::Whb));
+        assert_eq!(options.npy_layout_for(path), Some(NpyLayout::Hwb));
+        assert_eq!(options.for_path(path).npy_layout, Some(NpyLayout::Hwb));
+        assert_eq!(
+            options.npy_layout_for(Path::new("/elsewhere/a.npz")),
+            Some(NpyLayout::Whb)
+        );
+
+        // Updating and removing rules
+        options.set_npy_layout_rule("/data/scans/*", Some(NpyLayout::Bwh));
+        assert_eq!(options.npy_layout_rules.len(), 2);
+        assert_eq!(
+            options.npy_layout_rule("/data/scans/*"),
+            Some(NpyLayout::Bwh)
+        );
+        options.set_npy_layout_rule("/data/scans/*", None);
+        assert_eq!(options.npy_layout_for(path), Some(NpyLayout::Bhw));
+
+        assert_eq!(project_pattern(Path::new("")), "*");
+    }
+
+    #[test]
+    fn test_calibration_resolution() {
+        let mut options = LoaderOptions::default();
+        let path = Path::new("/data/scans/leaf.hdr");
+        assert_eq!(options.calibration_for(path), None);
+
+        let rule = |pattern: &str, white: &str| CalibrationRule {
+            pattern: pattern.to_string(),
+            dark: None,
+            white: Some(white.to_string()),
+            gain: Vec::new(),
+            offset: Vec::new(),
+        };
+        options.set_calibration_rule(rule("leaf*.hdr", "WHITEREF_{stem}.hdr"));
+        options.set_calibration_rule(rule("*", "/lab/white.hdr"));
+        assert_eq!(
+            options.calibration_for(path).unwrap().white,
+            Some("/data/scans/WHITEREF_leaf.hdr".into())
+        );
+        assert_eq!(
+            options
+                .calibration_for(Path::new("/data/scans/stem.hdr"))
+                .unwrap()
+                .white,
+            Some("/lab/white.hdr".into())
+        );
+
+        // Same pattern replaces the rule
+        options.set_calibration_rule(rule("leaf*.hdr", "panel.hdr"));
+        assert_eq!(options.calibration_rules.len(), 2);
+        assert_eq!(
+            options.calibration_for(path).unwrap().white,
+            Some("/data/scans/panel.hdr".into())
+        );
+        assert!(options.for_path(path).calibration_rules.is_empty());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let options = LoaderOptions {
+            npz_cube_key: None,
+            npy_layout: Some(NpyLayout::Hwb),
+            npy_layout_rules: vec![NpyLayoutRule {
+                pattern: "*.npy".to_string(),
+                layout: NpyLayout::Bwh,
+            }],
+            subset: LoadSubset {
+                bands: Some((4, 40)),
+                band_stride: 2,
+                binning: 1,
+            },
+            calibration_rules: vec![CalibrationRule {
+                pattern: "/data/scans/*".to_string(),
+                dark: Some("DARKREF_{stem}.hdr".to_string()),
+                white: None,
+                gain: Vec::new(),
+                offset: vec![0.1],
+            }],
+        };
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains("\"HWB\""));
+        assert_eq!(
+            serde_json::from_str::<LoaderOptions>(&json).unwrap(),
+            options
+        );
+        assert_eq!(
+            serde_json::from_str::<LoaderOptions>("{}").unwrap(),
+            LoaderOptions::default()
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! Principal components of a band cube (PCA and MNF).
+//!
+//! The band covariance is estimated from an evenly spaced subset of the
+//! pixels so large cubes stay responsive. MNF (minimum noise fraction) first
+//! whitens the noise, estimated from differences between horizontally
+//! adjacent pixels, and orders the components by signal-to-noise ratio
+//! instead of variance.
+//!
+//! Pixels with a non-finite value in any band are left out of the estimate;
+//! their component values are NaN.
+
+/// Dimensionality reduction method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentMethod {
+    /// Principal component analysis (components ordered by variance)
+    Pca,
+    /// Minimum noise fraction (noise-whitened PCA, ordered by SNR)
+    Mnf,
+}
+
+impl ComponentMethod {
+    /// All methods, in display order.
+    pub fn all() -> &'static [ComponentMethod] {
+        &[ComponentMethod::Pca, ComponentMethod::Mnf]
+    }
+
+    /// Get the display name for this method.
+    pub fn name(self) -> &'static str {
+        match self {
+            ComponentMethod::Pca => "PCA",
+            ComponentMethod::Mnf => "MNF",
+        }
+    }
+
+    /// Short prefix of component names ("PC1", "MNF1").
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ComponentMethod::Pca => "PC",
+            ComponentMethod::Mnf => "MNF",
+        }
+    }
+}
+
+/// Maximum number of Jacobi sweeps of the eigen-decomposition.
+const MAX_JACOBI_SWEEPS: usize = 50;
+
+/// Principal components of a band cube.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrincipalComponents {
+    /// Method the components were computed with
+    pub method: ComponentMethod,
+    /// Mean of each band over the sampled pixels
+    pub mean: Vec<f32>,
+    /// Projection vector of each component (component x band), strongest first
+    pub vectors: Vec<Vec<f32>>,
+    /// Variance (PCA) or signal-to-noise ratio (MNF) of each component
+    pub eigenvalues: Vec<f32>,
+    /// Number of pixels the covariance was estimated from
+    pub samples: usize,
+}
+
+impl PrincipalComponents {
+    /// Compute the components of `bands` (row-major, `width` pixels per row)
+    /// from at most `max_samples` pixels.
+    pub fn compute(
+        bands: &[Vec<f32>],
+        width: u32,
+        method: ComponentMethod,
+        max_samples: usize,
+    ) -> Result<Self, String> {
+        let n = bands.len();
+        if n < 2 {
+            return Err(format!("{} needs at least 2 bands", method.name()));
+        }
+        let pixel_count = bands[0].len();
+        let width = width.max(1) as usize;
+        let stride = pixel_count.div_ceil(max_samples.max(1)).max(1);
+        let finite = |pixel: usize| bands.iter().all(|band| band[pixel].is_finite());
+        let samples: Vec<usize> = (0..pixel_count)
+            .step_by(stride)
+            .filter(|&pixel| finite(pixel))
+            .collect();
+        if samples.len() < 2 {
+            return Err("Not enough valid pixels".to_string());
+        }
+
+        // Band means and covariance (f64 to keep precision over many pixels)
+        let mut mean = vec![0.0f64; n];
+        for &pixel in &samples {
+            for (m, band) in mean.iter_mut().zip(bands) {
+                *m += f64::from(band[pixel]);
+            }
+        }
+        for m in &mut mean {
+            *m /= samples.len() as f64;
+        }
+        let covariance = covariance(
+            n,
+            samples.iter().map(|&pixel| {
+                bands
+                    .iter()
+                    .zip(&mean)
+                    .map(|(band, m)| f64::from(band[pixel]) - m)
+                    .collect::<Vec<f64>>()
+            }),
+        );
+
+        let (eigenvalues, vectors) = match method {
+            ComponentMethod::Pca => symmetric_eigen(covariance, n),
+            ComponentMethod::Mnf => {
+                // Noise from horizontal neighbor differences: cov(d) = 2 cov(noise)
+                let differences = samples
+                    .iter()
+                    .filter(|&&pixel| pixel % width + 1 < width && finite(pixel + 1))
+                    .map(|&pixel| {
+                        bands
+                            .iter()
+                            .map(|band| f64::from(band[pixel]) - f64::from(band[pixel + 1]))
+                            .collect::<Vec<f64>>()
+                    });
+                let mut noise = covariance_raw(n, differences)
+                    .ok_or_else(|| "Image is too narrow to estimate noise".to_string())?;
+                for value in &mut noise {
+                    *value /= 2.0;
+                }
+                mnf_eigen(&covariance, noise, n)
+            }
+        };
+
+        Ok(Self {
+            method,
+            mean: mean.iter().map(|&m| m as f32).collect(),
+            vectors: vectors
+                .into_iter()
+                .map(|v| v.into_iter().map(|x| x as f32).collect())
+                .collect(),
+            eigenvalues: eigenvalues.into_iter().map(|e| e as f32).collect(),
+            samples: samples.len(),
+        })
+    }
+
+    /// Number of components.
+    pub fn num_components(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Share of each component in the total of the eigenvalues.
+    pub fn explained(&self) -> Vec<f32> {
+        let total: f32 = self.eigenvalues.iter().map(|e| e.max(0.0)).sum();
+        self.eigenvalues
+            .iter()
+            .map(|e| if total > 0.0 { e.max(0.0) / total } else { 0.0 })
+            .collect()
+    }
+
+    /// Images of the first `count` components (row-major, like the bands).
+    pub fn project(&self, bands: &[Vec<f32>], count: usize) -> Vec<Vec<f32>> {
+        let pixel_count = bands.first().map_or(0, |band| band.len());
+        self.vectors
+            .iter()
+            .take(count)
+            .map(|vector| {
+                (0..pixel_count)
+                    .map(|pixel| {
+                        vector
+                            .iter()
+                            .zip(bands)
+                            .zip(&self.mean)
+                            .map(|((w, band), m)| w * (band[pixel] - m))
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Covariance (row-major n x n) of mean-centered vectors.
+fn covariance(n: usize, centered: impl Iterator<Item = Vec<f64>>) -> Vec<f64> {
+    covariance_raw(n, centered).unwrap_or_else(|| vec![0.0; n * n])
+}
+
+/// Mean outer product (row-major n x n) of vectors; None without vectors.
+fn covariance_raw(n: usize, vectors: impl Iterator<Item = Vec<f64>>) -> Option<Vec<f64>> {
+    let mut sum = vec![0.0f64; n * n];
+    let mut count = 0usize;
+    for v in vectors {
+        for i in 0..n {
+            let vi = v[i];
+            let row = &mut sum[i * n..(i + 1) * n];
+            for (j, value) in row.iter_mut().enumerate().skip(i) {
+                *value += vi * v[j];
+            }
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    for i in 0..n {
+        for j in i..n {
+            let value = sum[i * n + j] / count as f64;
+            sum[i * n + j] = value;
+            sum[j * n + i] = value;
+        }
+    }
+    Some(sum)
+}
+
+/// MNF components: PCA of the signal after whitening the noise.
+///
+/// Returns the SNR eigenvalues and the projection vectors in band space.
+fn mnf_eigen(signal: &[f64], noise: Vec<f64>, n: usize) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let (noise_values, noise_vectors) = symmetric_eigen(noise, n);
+    // Bands without noise would make the whitening blow up
+    let floor = noise_values.first().copied().unwrap_or(0.0).max(0.0) * 1e-9 + f64::MIN_POSITIVE;
+    // Whitening matrix W (n x n, row-major): column k = v_k / sqrt(lambda_k)
+    let mut whiten = vec![0.0f64; n * n];
+    for (k, (value, vector)) in noise_values.iter().zip(&noise_vectors).enumerate() {
+        let scale = 1.0 / value.max(floor).sqrt();
+        for (b, v) in vector.iter().enumerate() {
+            whiten[b * n + k] = v * scale;
+        }
+    }
+
+    // Whitened signal covariance W^T C W
+    let cw = mat_mul(signal, &whiten, n);
+    let mut whitened = vec![0.0f64; n * n];
+    for i in 0..n {
+        for j in 0..n {
+            whitened[i * n + j] = (0..n).map(|b| whiten[b * n + i] * cw[b * n + j]).sum();
+        }
+    }
+    let (values, vectors) = symmetric_eigen(whitened, n);
+
+    // Back to band space: W u
+    let vectors = vectors
+        .iter()
+        .map(|u| {
+            let mut v: Vec<f64> = (0..n)
+                .map(|b| (0..n).map(|k| whiten[b * n + k] * u[k]).sum())
+                .collect();
+            normalize_sign(&mut v);
+            v
+        })
+        .collect();
+    (values, vectors)
+}
+
+/// Product of two row-major n x n matrices.
+fn mat_mul(a: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    let mut out = vec![0.0f64; n * n];
+    for i in 0..n {
+        for k in 0..n {
+            let aik = a[i * n + k];
+            if aik == 0.0 {
+                continue;
+            }
+            for j in 0..n {
+                out[i * n + j] += aik * b[k * n + j];
+            }
+        }
+    }
+    out
+}
+
+/// Make the largest-magnitude entry positive (eigenvector signs are arbitrary).
+fn normalize_sign(v: &mut [f64]) {
+    let largest = v
+        .iter()
+        .copied()
+        .fold(0.0f64, |acc, x| if x.abs() > acc.abs() { x } else { acc });
+    if largest < 0.0 {
+        for x in v.iter_mut() {
+            *x = -*x;
+        }
+    }
+}
+
+/// Eigen-decomposition of a symmetric row-major n x n matrix by cyclic
+/// Jacobi rotations.
+///
+/// Returns the eigenvalues in decreasing order and the matching unit
+/// eigenvectors.
+fn symmetric_eigen(mut a: Vec<f64>, n: usize) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let mut v = vec![0.0f64; n * n];
+    for i in 0..n {
+        v[i * n + i] = 1.0;
+    }
+
+    let scale: f64 = a.iter().map(|x| x * x).sum::<f64>().max(f64::MIN_POSITIVE);
+    for _ in 0..MAX_JACOBI_SWEEPS {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|p| ((p + 1)..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[p * n + q] * a[p * n + q])
+            .sum();
+        if off_diagonal <= scale * 1e-24 {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let apq = a[p * n + q];
+                if apq == 0.0 {
+                    continue;
+                }
+                let theta = (a[q * n + q] - a[p * n + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let akp = a[k * n + p];
+                    let akq = a[k * n + q];
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let apk = a[p * n + k];
+                    let aqk = a[q * n + k];
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let vkp = v[k * n + p];
+                    let vkq = v[k * n + q];
+                    v[k * n + p] = c * vkp - s * vkq;
+                    v[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[j * n + j].total_cmp(&a[i * n + i]));
+    let values = order.iter().map(|&i| a[i * n + i]).collect();
+    let vectors = order
+        .iter()
+        .map(|&i| {
+            let mut vector: Vec<f64> = (0..n).map(|k| v[k * n + i]).collect();
+            normalize_sign(&mut vector);
+            vector
+        })
+        .collect();
+    (values, vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetric_eigen() {
+        // Eigenvalues 3 and 1 with vectors (1, 1) and (1, -1)
+        let (values, vectors) = symmetric_eigen(vec![2.0, 1.0, 1.0, 2.0], 2);
+        assert!((values[0] - 3.0).abs() < 1e-9 && (values[1] - 1.0).abs() < 1e-9);
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        assert!((vectors[0][0] - h).abs() < 1e-9 && (vectors[0][1] - h).abs() < 1e-9);
+        assert!((vectors[1][0].abs() - h).abs() < 1e-9);
+        assert!((vectors[1][0] + vectors[1][1]).abs() < 1e-9);
+
+        // A x = lambda x for a larger matrix
+        let n = 4;
+        let m: Vec<f64> = (0..n * n)
+            .map(|i| {
+                let (r, c) = (i / n, i % n);
+                1.0 / (1.0 + r as f64 + c as f64)
+            })
+            .collect();
+        let (values, vectors) = symmetric_eigen(m.clone(), n);
+        for (value, vector) in values.iter().zip(&vectors) {
+            for r in 0..n {
+                let ax: f64 = (0..n).map(|c| m[r * n + c] * vector[c]).sum();
+                assert!((ax - value * vector[r]).abs() < 1e-9);
+            }
+        }
+    }
+
+    /// Two bands along the diagonal plus a small perpendicular spread.
+    fn diagonal_bands() -> Vec<Vec<f32>> {
+        let (b1, b2): (Vec<f32>, Vec<f32>) = (0..100)
+            .map(|i| {
+                // Pixel pairs at the same position spread to both sides
+                let t = (i / 2) as f32 / 5.0;
+                let e = if i % 2 == 0 { 0.1 } else { -0.1 };
+                (t + e, t - e)
+            })
+            .unzip();
+        vec![b1, b2]
+    }
+
+    #[test]
+    fn test_pca() {
+        let bands = diagonal_bands();
+        let pca = PrincipalComponents::compute(&bands, 10, ComponentMethod::Pca, 1000).unwrap();
+        assert_eq!(pca.num_components(), 2);
+        assert_eq!(pca.samples, 100);
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((pca.vectors[0][0] - h).abs() < 1e-4 && (pca.vectors[0][1] - h).abs() < 1e-4);
+        assert!(pca.eigenvalues[0] > pca.eigenvalues[1]);
+        let explained = pca.explained();
+        assert!(explained[0] > 0.99 && (explained.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+
+        // PC1 of a pixel is its distance along the diagonal from the mean
+        let images = pca.project(&bands, 1);
+        assert_eq!(images.len(), 1);
+        let expected = (bands[0][0] - pca.mean[0] + bands[1][0] - pca.mean[1]) * h;
+        assert!((images[0][0] - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_subsampling_and_invalid_pixels() {
+        let mut bands = diagonal_bands();
+        bands[1][3] = f32::NAN;
+        let pca = PrincipalComponents::compute(&bands, 10, ComponentMethod::Pca, 10).unwrap();
+        assert_eq!(pca.samples, 10);
+        let all = PrincipalComponents::compute(&bands, 10, ComponentMethod::Pca, 1000).unwrap();
+        assert_eq!(all.samples, 99);
+        assert!(all.project(&bands, 2)[0][3].is_nan());
+
+        assert!(PrincipalComponents::compute(&bands[..1], 10, ComponentMethod::Pca, 10).is_err());
+    }
+
+    #[test]
+    fn test_mnf_orders_by_signal_to_noise() {
+        // Band 0: smooth signal with small noise; band 1: large pixel noise only
+        let width = 20;
+        let (smooth, noisy): (Vec<f32>, Vec<f32>) = (0..400)
+            .map(|i| {
+                let x = (i % width) as f32;
+                let y = (i / width) as f32;
+                let noise = if i % 2 == 0 { 1.0 } else { -1.0 };
+                ((x + y) / 4.0 + noise * 0.01, noise * 5.0)
+            })
+            .unzip();
+        let bands = vec![smooth, noisy];
+
+        // PCA picks the noisy band (larger variance), MNF the smooth one
+        let pca = PrincipalComponents::compute(&bands, 20, ComponentMethod::Pca, 1000).unwrap();
+        assert!(pca.vectors[0][1].abs() > pca.vectors[0][0].abs());
+        let mnf = PrincipalComponents::compute(&bands, 20, ComponentMethod::Mnf, 1000).unwrap();
+        assert!(mnf.vectors[0][0].abs() > mnf.vectors[0][1].abs());
+        assert!(mnf.eigenvalues[0] > mnf.eigenvalues[1]);
+    }
+}
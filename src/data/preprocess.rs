@@ -0,0 +1,708 @@
+//! Spectral preprocessing chain applied to decoded data.
+//!
+//! Water-absorption and noisy edge bands ruin composites and statistics. A
+//! `PreprocessingChain` removes them and then transforms every pixel spectrum
+//! in turn: Savitzky–Golay smoothing, derivatives, continuum removal and
+//! normalization. The chain is stored per project (in the project file) and
+//! runs as a `DataProcessor` after the radiometric calibration.
+//!
+//! Stages working along the spectrum use the band wavelengths as abscissa
+//! when every band has one (in increasing order), otherwise the band
+//! position.
+
+use serde::{Deserialize, Serialize};
+
+use super::{BandInfo, DataProcessor, HyperspectralData};
+
+/// Largest Savitzky–Golay window (bands).
+pub const MAX_SMOOTHING_WINDOW: usize = 51;
+
+/// Highest Savitzky–Golay polynomial order.
+pub const MAX_SMOOTHING_ORDER: usize = 5;
+
+/// Bands removed by `PreprocessStep::ExcludeBands`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandExclusion {
+    /// Index ranges of the loaded bands (0-based, inclusive)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indices: Vec<(usize, usize)>,
+    /// Wavelength ranges in nanometers (inclusive)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub wavelengths: Vec<(f32, f32)>,
+    /// Also exclude the bands flagged bad by the loader (e.g. ENVI `bbl`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub flagged: bool,
+}
+
+impl BandExclusion {
+    /// Parse ranges typed by the user: 1-based band numbers (`"1-5"`,
+    /// `"120"`) and wavelength ranges in nanometers (`"1350-1450nm"`),
+    /// separated by commas.
+    pub fn parse(text: &str, flagged: bool) -> Result<Self, String> {
+        let mut exclusion = Self {
+            flagged,
+            ..Default::default()
+        };
+        for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (range, is_wavelength) = match part.strip_suffix("nm") {
+                Some(range) => (range.trim(), true),
+                None => (part, false),
+            };
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+            if is_wavelength {
+                let parse = |s: &str| {
+                    s.trim()
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|v| v.is_finite())
+                        .ok_or_else(|| format!("Invalid wavelength: {}", s.trim()))
+                };
+                let (first, last) = (parse(first)?, parse(last)?);
+                exclusion
+                    .wavelengths
+                    .push((first.min(last), first.max(last)));
+            } else {
+                let parse = |s: &str| {
+                    s.trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("Invalid band number: {}", s.trim()))
+                };
+                let (first, last) = (parse(first)?, parse(last)?);
+                exclusion
+                    .indices
+                    .push((first.min(last) - 1, first.max(last) - 1));
+            }
+        }
+        if exclusion.is_empty() {
+            return Err("No bands to exclude".to_string());
+        }
+        Ok(exclusion)
+    }
+
+    /// Whether nothing is excluded.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty() && self.wavelengths.is_empty() && !self.flagged
+    }
+
+    /// Whether band `index` with metadata `info` is excluded.
+    pub fn excludes(&self, index: usize, info: Option<&BandInfo>) -> bool {
+        self.indices
+            .iter()
+            .any(|&(first, last)| (first..=last).contains(&index))
+            || info.is_some_and(|info| {
+                (self.flagged && info.bad)
+                    || info.wavelength.is_some_and(|w| {
+                        self.wavelengths
+                            .iter()
+                            .any(|&(first, last)| (first..=last).contains(&w))
+                    })
+            })
+    }
+
+    /// Ranges as typed, the inverse of `parse` (without the flag).
+    pub fn ranges_text(&self) -> String {
+        let indices = self.indices.iter().map(|&(first, last)| {
+            if first == last {
+                format!("{}", first + 1)
+            } else {
+                format!("{}-{}", first + 1, last + 1)
+            }
+        });
+        let wavelengths = self
+            .wavelengths
+            .iter()
+            .map(|&(first, last)| format!("{}-{}nm", first, last));
+        indices.chain(wavelengths).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// One stage of a `PreprocessingChain`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    /// Remove bands by index, wavelength or bad-band flag
+    ExcludeBands(BandExclusion),
+    /// Savitzky–Golay smoothing with an odd `window` and polynomial `order`
+    SavitzkyGolay { window: usize, order: usize },
+    /// First (`order` 1) or second (`order` 2) derivative
+    Derivative { order: u8 },
+    /// Division by the upper convex hull of the spectrum
+    ContinuumRemoval,
+    /// Division by the Euclidean norm of the spectrum
+    NormalizeL2,
+    /// Division by the area under the spectrum
+    NormalizeArea,
+}
+
+impl PreprocessStep {
+    /// Short description for the step list.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::ExcludeBands(exclusion) => {
+                let mut parts = Vec::new();
+                let ranges = exclusion.ranges_text();
+                if !ranges.is_empty() {
+                    parts.push(ranges);
+                }
+                if exclusion.flagged {
+                    parts.push("flagged bad".to_string());
+                }
+                format!("Exclude bands {}", parts.join(", "))
+            }
+            Self::SavitzkyGolay { window, order } => {
+                format!("Savitzky-Golay (window {}, order {})", window, order)
+            }
+            Self::Derivative { order: 1 } => "1st derivative".to_string(),
+            Self::Derivative { order } => format!("{}nd derivative", order),
+            Self::ContinuumRemoval => "Continuum removal".to_string(),
+            Self::NormalizeL2 => "L2 normalization".to_string(),
+            Self::NormalizeArea => "Area normalization".to_string(),
+        }
+    }
+
+    /// Check the parameters of the step.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Self::ExcludeBands(ref exclusion) if exclusion.is_empty() => {
+                Err("No bands to exclude".to_string())
+            }
+            Self::SavitzkyGolay { window, order } => {
+                if window < 3 || window.is_multiple_of(2) || window > MAX_SMOOTHING_WINDOW {
+                    Err(format!(
+                        "Smoothing window must be odd, 3 to {}",
+                        MAX_SMOOTHING_WINDOW
+                    ))
+                } else if order >= window || order > MAX_SMOOTHING_ORDER {
+                    Err(format!(
+                        "Smoothing order must be below the window and at most {}",
+                        MAX_SMOOTHING_ORDER
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Derivative { order } if !(1..=2).contains(&order) => {
+                Err("Derivative order must be 1 or 2".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the step to every pixel of `data`.
+    pub fn apply(&self, mut data: HyperspectralData) -> Result<HyperspectralData, String> {
+        self.validate()?;
+        match self {
+            Self::ExcludeBands(exclusion) => return exclude_bands(data, exclusion),
+            Self::SavitzkyGolay { window, order } => {
+                let smoothing = Smoothing::new(*window, *order, data.bands.len());
+                map_spectra(&mut data, |_, spectrum| smoothing.apply(spectrum));
+            }
+            Self::Derivative { order: 1 } => map_spectra(&mut data, first_derivative),
+            Self::Derivative { .. } => map_spectra(&mut data, second_derivative),
+            Self::ContinuumRemoval => map_spectra(&mut data, remove_continuum),
+            Self::NormalizeL2 => map_spectra(&mut data, |_, spectrum| {
+                let norm = spectrum
+                    .iter()
+                    .map(|&v| f64::from(v) * f64::from(v))
+                    .sum::<f64>()
+                    .sqrt();
+                scale(spectrum, norm);
+            }),
+            Self::NormalizeArea => map_spectra(&mut data, |x, spectrum| {
+                let area = if spectrum.len() < 2 {
+                    spectrum.iter().map(|&v| f64::from(v)).sum()
+                } else {
+                    (1..spectrum.len())
+                        .map(|i| 0.5 * f64::from(spectrum[i - 1] + spectrum[i]) * (x[i] - x[i - 1]))
+                        .sum::<f64>()
+                };
+                scale(spectrum, area.abs());
+            }),
+        }
+        Ok(data)
+    }
+}
+
+/// Ordered preprocessing stages of a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PreprocessingChain {
+    /// Stages, applied first to last
+    pub steps: Vec<PreprocessStep>,
+}
+
+impl PreprocessingChain {
+    /// Whether the chain has no stages.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Apply all stages in order.
+    pub fn apply(&self, data: HyperspectralData) -> Result<HyperspectralData, String> {
+        self.steps
+            .iter()
+            .try_fold(data, |data, step| step.apply(data))
+    }
+}
+
+impl DataProcessor for PreprocessingChain {
+    fn id(&self) -> &'static str {
+        "preprocessing"
+    }
+
+    fn process(&self, data: HyperspectralData) -> Result<HyperspectralData, String> {
+        self.apply(data)
+    }
+}
+
+/// Remove the excluded bands and their metadata.
+fn exclude_bands(
+    data: HyperspectralData,
+    exclusion: &BandExclusion,
+) -> Result<HyperspectralData, String> {
+    let HyperspectralData {
+        bands, band_info, ..
+    } = &data;
+    let keep: Vec<usize> = (0..bands.len())
+        .filter(|&b| !exclusion.excludes(b, band_info.get(b)))
+        .collect();
+    if keep.is_empty() {
+        return Err("Band exclusion removes every band".to_string());
+    }
+    if keep.len() == bands.len() {
+        return Ok(data);
+    }
+    log::debug!(
+        "Excluding {} of {} bands",
+        bands.len() - keep.len(),
+        bands.len()
+    );
+
+    let mut data = data;
+    let mut bands = std::mem::take(&mut data.bands);
+    let mut band_info = std::mem::take(&mut data.band_info);
+    data.bands = keep
+        .iter()
+        .map(|&b| std::mem::take(&mut bands[b]))
+        .collect();
+    data.band_info = keep
+        .iter()
+        .filter_map(|&b| band_info.get_mut(b).map(std::mem::take))
+        .collect();
+    Ok(data)
+}
+
+/// Positions of the bands along the spectrum: the wavelengths if every band
+/// has one and they increase, otherwise the band index.
+fn abscissa(band_info: &[BandInfo], num_bands: usize) -> Vec<f64> {
+    let wavelengths: Option<Vec<f64>> = (band_info.len() == num_bands)
+        .then(|| {
+            band_info
+                .iter()
+                .map(|info| info.wavelength.map(f64::from))
+                .collect()
+        })
+        .flatten();
+    match wavelengths {
+        Some(x) if x.windows(2).all(|w| w[1] > w[0]) => x,
+        _ => (0..num_bands).map(|i| i as f64).collect(),
+    }
+}
+
+/// Replace the spectrum of every pixel with `f(abscissa, spectrum)`.
+fn map_spectra(data: &mut HyperspectralData, f: impl Fn(&[f64], &mut [f32])) {
+    let num_bands = data.bands.len();
+    if num_bands == 0 {
+        return;
+    }
+    let x = abscissa(&data.band_info, num_bands);
+    let num_pixels = data.bands[0].len();
+    let mut spectrum = vec![0.0f32; num_bands];
+    for pixel in 0..num_pixels {
+        for (value, band) in spectrum.iter_mut().zip(&data.bands) {
+            *value = band[pixel];
+        }
+        f(&x, &mut spectrum);
+        for (value, band) in spectrum.iter().zip(&mut data.bands) {
+            band[pixel] = *value;
+        }
+    }
+}
+
+/// Divide all values by `divisor` (NaN if it is zero or not finite).
+fn scale(spectrum: &mut [f32], divisor: f64) {
+    for value in spectrum.iter_mut() {
+        *value = if divisor != 0.0 && divisor.is_finite() {
+            (f64::from(*value) / divisor) as f32
+        } else {
+            f32::NAN
+        };
+    }
+}
+
+/// Savitzky–Golay filter weights for spectra of a fixed length.
+struct Smoothing {
+    /// `weights[t][j]`: weight of window sample `j` for the value at window
+    /// position `t` (the center for interior bands, off-center at the edges)
+    weights: Vec<Vec<f64>>,
+}
+
+impl Smoothing {
+    /// Fit polynomials of `order` over `window` bands, shrinking the window
+    /// (and order) for spectra with fewer than `window` bands.
+    fn new(window: usize, order: usize, num_bands: usize) -> Self {
+        let window = window.min(if num_bands.is_multiple_of(2) {
+            num_bands.saturating_sub(1)
+        } else {
+            num_bands
+        });
+        if window < 3 {
+            return Self {
+                weights: Vec::new(),
+            };
+        }
+        let order = order.min(window - 1);
+        let half = (window / 2) as f64;
+        let positions: Vec<f64> = (0..window).map(|j| j as f64 - half).collect();
+        let terms = order + 1;
+
+        // Normal matrix of the least-squares fit, inverted
+        let mut normal = vec![vec![0.0f64; terms]; terms];
+        for &p in &positions {
+            for (a, row) in normal.iter_mut().enumerate() {
+                for (b, value) in row.iter_mut().enumerate() {
+                    *value += p.powi((a + b) as i32);
+                }
+            }
+        }
+        let inverse = invert(normal);
+
+        let weights = positions
+            .iter()
+            .map(|&t| {
+                positions
+                    .iter()
+                    .map(|&p| {
+                        (0..terms)
+                            .flat_map(|a| (0..terms).map(move |b| (a, b)))
+                            .map(|(a, b)| p.powi(a as i32) * inverse[a][b] * t.powi(b as i32))
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { weights }
+    }
+
+    /// Smooth one spectrum in place.
+    fn apply(&self, spectrum: &mut [f32]) {
+        let window = self.weights.len();
+        if window == 0 {
+            return;
+        }
+        let half = window / 2;
+        let n = spectrum.len();
+        let source: Vec<f64> = spectrum.iter().map(|&v| f64::from(v)).collect();
+        for (i, value) in spectrum.iter_mut().enumerate() {
+            let start = i.saturating_sub(half).min(n - window);
+            let weights = &self.weights[i - start];
+            *value = weights
+                .iter()
+                .zip(&source[start..start + window])
+                .map(|(w, v)| w * v)
+                .sum::<f64>() as f32;
+        }
+    }
+}
+
+/// Invert a small symmetric positive definite matrix (Gauss-Jordan).
+fn invert(mut matrix: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    for col in 0..n {
+        let pivot_row = (col..n)
+            .max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))
+            .unwrap_or(col);
+        matrix.swap(col, pivot_row);
+        inverse.swap(col, pivot_row);
+        let pivot = matrix[col][col];
+        for j in 0..n {
+            matrix[col][j] /= pivot;
+            inverse[col][j] /= pivot;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = matrix[row][col];
+                for j in 0..n {
+                    matrix[row][j] -= factor * matrix[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+    inverse
+}
+
+/// First derivative along `x` (central differences, one-sided at the ends).
+fn first_derivative(x: &[f64], spectrum: &mut [f32]) {
+    let n = spectrum.len();
+    if n < 2 {
+        spectrum.fill(0.0);
+        return;
+    }
+    let y: Vec<f64> = spectrum.iter().map(|&v| f64::from(v)).collect();
+    for (i, value) in spectrum.iter_mut().enumerate() {
+        let (a, b) = (i.saturating_sub(1), (i + 1).min(n - 1));
+        *value = ((y[b] - y[a]) / (x[b] - x[a])) as f32;
+    }
+}
+
+/// Second derivative along `x`; the end bands repeat their neighbours.
+fn second_derivative(x: &[f64], spectrum: &mut [f32]) {
+    let n = spectrum.len();
+    if n < 3 {
+        spectrum.fill(0.0);
+        return;
+    }
+    let y: Vec<f64> = spectrum.iter().map(|&v| f64::from(v)).collect();
+    for i in 1..n - 1 {
+        let right = (y[i + 1] - y[i]) / (x[i + 1] - x[i]);
+        let left = (y[i] - y[i - 1]) / (x[i] - x[i - 1]);
+        spectrum[i] = (2.0 * (right - left) / (x[i + 1] - x[i - 1])) as f32;
+    }
+    spectrum[0] = spectrum[1];
+    spectrum[n - 1] = spectrum[n - 2];
+}
+
+/// Divide the spectrum by its upper convex hull (non-finite values are
+/// left out of the hull and stay as they are).
+fn remove_continuum(x: &[f64], spectrum: &mut [f32]) {
+    let mut hull: Vec<(f64, f64)> = Vec::new();
+    for (&xi, &v) in x.iter().zip(spectrum.iter()) {
+        if !v.is_finite() {
+            continue;
+        }
+        let point = (xi, f64::from(v));
+        while let [.., a, b] = hull[..] {
+            // Drop `b` if it lies on or below the line from `a` to `point`
+            let cross = (b.0 - a.0) * (point.1 - a.1) - (b.1 - a.1) * (point.0 - a.0);
+            if cross < 0.0 {
+                break;
+            }
+            hull.pop();
+        }
+        hull.push(point);
+    }
+
+    let mut segment = 0;
+    for (&xi, value) in x.iter().zip(spectrum.iter_mut()) {
+        if !value.is_finite() {
+            continue;
+        }
+        while segment + 2 < hull.len() && hull[segment + 1].0 < xi {
+            segment += 1;
+        }
+        let continuum = match hull.get(segment..segment + 2) {
+            Some(&[(x0, y0), (x1, y1)]) => y0 + (y1 - y0) * (xi - x0) / (x1 - x0),
+            _ => hull[0].1,
+        };
+        *value = if continuum != 0.0 {
+            (f64::from(*value) / continuum) as f32
+        } else {
+            f32::NAN
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One-pixel image of `spectrum` with the given wavelengths.
+    fn pixel(spectrum: &[f32], wavelengths: Option<&[f32]>) -> HyperspectralData {
+        let mut data = HyperspectralData::new(
+            spectrum.iter().map(|&v| vec![v]).collect(),
+            1,
+            1,
+            (1..=spectrum.len())
+                .map(|i| format!("Band {}", i))
+                .collect(),
+        );
+        if let Some(wavelengths) = wavelengths {
+            for (info, &w) in data.band_info.iter_mut().zip(wavelengths) {
+                info.wavelength = Some(w);
+            }
+        }
+        data
+    }
+
+    fn spectrum(data: &HyperspectralData) -> Vec<f32> {
+        data.bands.iter().map(|band| band[0]).collect()
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_exclusion() {
+        let exclusion = BandExclusion::parse("1-3, 10, 1450-1350nm", true).unwrap();
+        assert_eq!(exclusion.indices, vec![(0, 2), (9, 9)]);
+        assert_eq!(exclusion.wavelengths, vec![(1350.0, 1450.0)]);
+        assert!(exclusion.flagged);
+        assert_eq!(exclusion.ranges_text(), "1-3, 10, 1350-1450nm");
+
+        assert!(BandExclusion::parse("", true).unwrap().flagged);
+        assert!(BandExclusion::parse("", false).is_err());
+        assert!(BandExclusion::parse("0-3", false).is_err());
+        assert!(BandExclusion::parse("a-b nm", false).is_err());
+    }
+
+    #[test]
+    fn test_exclude_bands() {
+        let mut data = pixel(
+            &[1.0, 2.0, 3.0, 4.0, 5.0],
+            Some(&[1000.0, 1400.0, 1420.0, 1600.0, 2500.0]),
+        );
+        data.band_info[4].bad = true;
+        let step = PreprocessStep::ExcludeBands(BandExclusion {
+            indices: vec![(0, 0)],
+            wavelengths: vec![(1350.0, 1450.0)],
+            flagged: true,
+        });
+        let result = step.apply(data.clone()).unwrap();
+        assert_eq!(spectrum(&result), vec![4.0]);
+        assert_eq!(result.band_info.len(), 1);
+        assert_eq!(result.band_info[0].name, "Band 4");
+
+        let all = PreprocessStep::ExcludeBands(BandExclusion {
+            indices: vec![(0, 10)],
+            ..Default::default()
+        });
+        assert!(all.apply(data).is_err());
+    }
+
+    #[test]
+    fn test_savitzky_golay() {
+        // A quadratic is reproduced exactly by a 2nd order fit, edges included
+        let quadratic: Vec<f32> = (0..9).map(|i| (i * i) as f32 * 0.5 - i as f32).collect();
+        let step = PreprocessStep::SavitzkyGolay {
+            window: 5,
+            order: 2,
+        };
+        let result = step.apply(pixel(&quadratic, None)).unwrap();
+        assert_close(&spectrum(&result), &quadratic);
+
+        // Classic 5-point quadratic weights (-3, 12, 17, 12, -3) / 35
+        let result = step
+            .apply(pixel(&[0.0, 0.0, 35.0, 0.0, 0.0, 0.0, 0.0], None))
+            .unwrap();
+        assert_close(&spectrum(&result)[2..5], &[17.0, 12.0, -3.0]);
+
+        assert!(
+            PreprocessStep::SavitzkyGolay {
+                window: 4,
+                order: 2
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            PreprocessStep::SavitzkyGolay {
+                window: 5,
+                order: 5
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_derivatives() {
+        // y = x^2 over wavelengths 0, 1, 2, 4
+        let wavelengths = [0.0, 1.0, 2.0, 4.0];
+        let data = pixel(&[0.0, 1.0, 4.0, 16.0], Some(&wavelengths));
+
+        let first = PreprocessStep::Derivative { order: 1 }
+            .apply(data.clone())
+            .unwrap();
+        assert_close(&spectrum(&first), &[1.0, 2.0, 5.0, 6.0]);
+
+        let second = PreprocessStep::Derivative { order: 2 }.apply(data).unwrap();
+        assert_close(&spectrum(&second), &[2.0, 2.0, 2.0, 2.0]);
+
+        assert!(PreprocessStep::Derivative { order: 3 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_continuum_removal() {
+        // Absorption feature between two shoulders on a sloped continuum
+        let data = pixel(&[1.0, 0.5, 2.0, f32::NAN, 3.0], None);
+        let result = PreprocessStep::ContinuumRemoval.apply(data).unwrap();
+        let values = spectrum(&result);
+        assert_close(&values[..3], &[1.0, 0.5 / 1.5, 1.0]);
+        assert!(values[3].is_nan());
+        assert_eq!(values[4], 1.0);
+    }
+
+    #[test]
+    fn test_normalization() {
+        let l2 = PreprocessStep::NormalizeL2
+            .apply(pixel(&[3.0, 4.0], None))
+            .unwrap();
+        assert_close(&spectrum(&l2), &[0.6, 0.8]);
+
+        // Trapezoid area over wavelengths 400, 500: (1 + 3) / 2 * 100 = 200
+        let area = PreprocessStep::NormalizeArea
+            .apply(pixel(&[1.0, 3.0], Some(&[400.0, 500.0])))
+            .unwrap();
+        assert_close(&spectrum(&area), &[0.005, 0.015]);
+
+        let zero = PreprocessStep::NormalizeL2
+            .apply(pixel(&[0.0, 0.0], None))
+            .unwrap();
+        assert!(spectrum(&zero).iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn test_chain_serde_and_order() {
+        let chain = PreprocessingChain {
+            steps: vec![
+                PreprocessStep::ExcludeBands(BandExclusion {
+                    indices: vec![(0, 0)],
+                    ..Default::default()
+                }),
+                PreprocessStep::SavitzkyGolay {
+                    window: 3,
+                    order: 1,
+                },
+                PreprocessStep::NormalizeL2,
+            ],
+        };
+        let json = serde_json::to_string(&chain).unwrap();
+        assert!(json.starts_with("[{\"type\":\"exclude_bands\""));
+        assert_eq!(
+            serde_json::from_str::<PreprocessingChain>(&json).unwrap(),
+            chain
+        );
+
+        // Band 1 is gone before smoothing; a linear spectrum stays linear
+        let result = chain.apply(pixel(&[100.0, 3.0, 4.0, 5.0], None)).unwrap();
+        let norm = (9.0f32 + 16.0 + 25.0).sqrt();
+        assert_close(&spectrum(&result), &[3.0 / norm, 4.0 / norm, 5.0 / norm]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Band statistics of image regions (e.g. the pixels inside an annotation).
+//!
+//! Non-finite values (e.g. NaN no-data pixels) are ignored; bands without a
+//! single finite value in the region get NaN statistics.
+
+/// Per-band statistics over a set of pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionStats {
+    /// Number of pixels in the region
+    pub pixel_count: usize,
+    /// Mean of each band
+    pub mean: Vec<f32>,
+    /// Standard deviation of each band
+    pub std_dev: Vec<f32>,
+    /// Smallest value of each band
+    pub min: Vec<f32>,
+    /// Largest value of each band
+    pub max: Vec<f32>,
+}
+
+impl RegionStats {
+    /// Compute the statistics of `bands` over `pixels` (row-major indices).
+    ///
+    /// Indices outside a band are ignored.
+    pub fn compute(bands: &[Vec<f32>], pixels: &[usize]) -> Self {
+        let num_bands = bands.len();
+        let mut stats = Self {
+            pixel_count: pixels.len(),
+            mean: Vec::with_capacity(num_bands),
+            std_dev: Vec::with_capacity(num_bands),
+            min: Vec::with_capacity(num_bands),
+            max: Vec::with_capacity(num_bands),
+        };
+
+        for band in bands {
+            let mut min = f32::INFINITY;
+            let mut max = f32::NEG_INFINITY;
+            let mut sum = 0.0f64;
+            let mut sum_sq = 0.0f64;
+            let mut n = 0u64;
+            for &v in pixels.iter().filter_map(|&i| band.get(i)) {
+                if !v.is_finite() {
+                    continue;
+                }
+                min = min.min(v);
+                max = max.max(v);
+                sum += f64::from(v);
+                sum_sq += f64::from(v) * f64::from(v);
+                n += 1;
+            }
+
+            if n == 0 {
+                stats.mean.push(f32::NAN);
+                stats.std_dev.push(f32::NAN);
+                stats.min.push(f32::NAN);
+                stats.max.push(f32::NAN);
+                continue;
+            }
+            let mean = sum / n as f64;
+            let variance = (sum_sq / n as f64 - mean * mean).max(0.0);
+            stats.mean.push(mean as f32);
+            stats.std_dev.push(variance.sqrt() as f32);
+            stats.min.push(min);
+            stats.max.push(max);
+        }
+        stats
+    }
+
+    /// Number of bands.
+    pub fn num_bands(&self) -> usize {
+        self.mean.len()
+    }
+}
+
+/// Spectra of `pixels`, one row of band values per pixel (row-major, flat).
+pub fn region_spectra(bands: &[Vec<f32>], pixels: &[usize]) -> Vec<f32> {
+    let mut spectra = Vec::with_capacity(pixels.len() * bands.len());
+    for &pixel in pixels {
+        spectra.extend(
+            bands
+                .iter()
+                .map(|band| band.get(pixel).copied().unwrap_or(f32::NAN)),
+        );
+    }
+    spectra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_stats() {
+        let bands = vec![vec![1.0, 2.0, 3.0, 100.0], vec![0.5, f32::NAN, 0.5, 0.0]];
+        let stats = RegionStats::compute(&bands, &[0, 1, 2]);
+        assert_eq!(stats.pixel_count, 3);
+        assert_eq!(stats.num_bands(), 2);
+        assert_eq!(stats.mean[0], 2.0);
+        assert!((stats.std_dev[0] - 0.8165).abs() < 0.001);
+        assert_eq!((stats.min[0], stats.max[0]), (1.0, 3.0));
+        // NaN pixel is ignored
+        assert_eq!(stats.mean[1], 0.5);
+        assert_eq!(stats.std_dev[1], 0.0);
+    }
+
+    #[test]
+    fn test_empty_region() {
+        let stats = RegionStats::compute(&[vec![1.0, 2.0]], &[]);
+        assert_eq!(stats.pixel_count, 0);
+        assert!(stats.mean[0].is_nan() && stats.max[0].is_nan());
+    }
+
+    #[test]
+    fn test_region_spectra() {
+        let bands = vec![vec![1.0, 2.0, 3.0], vec![10.0, 20.0, 30.0]];
+        assert_eq!(region_spectra(&bands, &[2, 0]), vec![3.0, 30.0, 1.0, 10.0]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Sibling file resolution for multi-file formats.
+//!
+//! Some formats split metadata and payload across files (ENVI `.hdr` + `.raw`,
+//! Analyze `.hdr` + `.img`, world files, calibration sidecars). Loaders get a
+//! `FileResolver` to ask for "the file named X next to this one" without
+//! knowing where the files actually live:
+//!
+//! - `FsResolver`: reads from a directory on disk (native only)
+//! - `MemoryResolver`: looks up files already held in memory (drag-drop,
+//!   folder picker, ZIP import, background decode requests); an empty one
+//!   stands for no siblings
+//!
+//! Large payloads are requested with `open_sibling`, which leaves files on
+//! disk to be read on demand instead of reading them whole.
+
+use std::collections::HashMap;
+
+use super::FileBytes;
+
+/// Provides access to files next to the one being loaded.
+pub trait FileResolver {
+    /// Read the file named `name` in the same folder as the primary file.
+    ///
+    /// `name` is a bare filename (e.g. `"scene.raw"`). Returns `None` if the
+    /// file does not exist or cannot be read.
+    fn read_sibling(&self, name: &str) -> Option<Vec<u8>>;
+
+    /// Access the file named `name` without necessarily reading it.
+    ///
+    /// Used for payloads that may be much larger than what the loader
+    /// decodes. The default reads the file with `read_sibling`.
+    fn open_sibling(&self, name: &str) -> Option<FileBytes> {
+        self.read_sibling(name).map(FileBytes::from)
+    }
+}
+
+/// Resolver reading siblings from a directory on disk.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FsResolver {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FsResolver {
+    /// Create a resolver for files next to `path`.
+    pub fn for_file(path: &std::path::Path) -> Self {
+        Self {
+            dir: path.parent().map(|p| p.to_path_buf()).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileResolver for FsResolver {
+    fn read_sibling(&self, name: &str) -> Option<Vec<u8>> {
+        let path = self.dir.join(name);
+        if !path.is_file() {
+            return None;
+        }
+        match std::fs::read(&path) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                log::warn!("Failed to read sibling file {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    fn open_sibling(&self, name: &str) -> Option<FileBytes> {
+        let path = self.dir.join(name);
+        if !path.is_file() {
+            return None;
+        }
+        FileBytes::open(&path)
+            .map_err(|e| log::warn!("Failed to open sibling file {:?}: {}", path, e))
+            .ok()
+    }
+}
+
+/// Resolver backed by in-memory (or already opened) files, keyed by bare
+/// filename.
+#[derive(Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, FileBytes>,
+}
+
+impl MemoryResolver {
+    /// Create a resolver from `(filename, data)` pairs.
+    ///
+    /// Names may include a folder prefix; only the last path component is
+    /// used for lookup.
+    pub fn new<T: Into<FileBytes>>(files: impl IntoIterator<Item = (String, T)>) -> Self {
+        let files = files
+            .into_iter()
+            .map(|(name, data)| (bare_filename(&name).to_string(), data.into()))
+            .collect();
+        Self { files }
+    }
+}
+
+impl FileResolver for MemoryResolver {
+    fn read_sibling(&self, name: &str) -> Option<Vec<u8>> {
+        self.files
+            .get(name)?
+            .read_all()
+            .map_err(|e| log::warn!("Failed to read sibling file {}: {}", name, e))
+            .ok()
+    }
+
+    fn open_sibling(&self, name: &str) -> Option<FileBytes> {
+        self.files.get(name).cloned()
+    }
+}
+
+/// Last path component of a `/` or `\` separated name.
+pub(crate) fn bare_filename(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_resolver() {
+        let resolver = MemoryResolver::new(vec![
+            ("scans/cube.raw".to_string(), vec![1, 2, 3]),
+            ("other.img".to_string(), vec![4]),
+        ]);
+
+        assert_eq!(resolver.read_sibling("cube.raw"), Some(vec![1, 2, 3]));
+        assert_eq!(resolver.read_sibling("other.img"), Some(vec![4]));
+        assert!(resolver.read_sibling("missing.bsq").is_none());
+        assert!(MemoryResolver::default().read_sibling("cube.raw").is_none());
+        assert_eq!(
+            resolver
+                .open_sibling("cube.raw")
+                .and_then(|data| data.as_slice().map(<[u8]>::to_vec)),
+            Some(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn test_bare_filename() {
+        assert_eq!(bare_filename("a/b/c.hdr"), "c.hdr");
+        assert_eq!(bare_filename("a\\c.hdr"), "c.hdr");
+        assert_eq!(bare_filename("c.hdr"), "c.hdr");
+    }
+}
@@ -0,0 +1,319 @@
+//! Spectral similarity map of a band cube to a reference spectrum.
+//!
+//! Every pixel gets the spectral angle (SAM, radians) or the Euclidean
+//! distance between its spectrum and the reference, so low values mean
+//! similar. Bands flagged as bad, or without a finite reference value, are
+//! left out; pixels with a non-finite value in a used band are NaN.
+//!
+//! Thresholding the map gives a mask whose connected regions are traced into
+//! polygons with the SAM2 contour extraction.
+
+use super::{BandHistogram, BandInfo};
+use crate::sam2::{SAM2Mask, extract_contour};
+
+/// Distance between a pixel spectrum and the reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SimilarityMetric {
+    /// Angle between the spectra as vectors (insensitive to brightness)
+    #[default]
+    SpectralAngle,
+    /// Euclidean distance between the spectra
+    Euclidean,
+}
+
+impl SimilarityMetric {
+    /// All metrics, in display order.
+    pub fn all() -> &'static [SimilarityMetric] {
+        &[SimilarityMetric::SpectralAngle, SimilarityMetric::Euclidean]
+    }
+
+    /// Get the display name for this metric.
+    pub fn name(self) -> &'static str {
+        match self {
+            SimilarityMetric::SpectralAngle => "SAM",
+            SimilarityMetric::Euclidean => "Euclidean",
+        }
+    }
+}
+
+/// Similarity of every pixel of an image to a reference spectrum.
+#[derive(Debug, Clone)]
+pub struct SimilarityMap {
+    /// Metric of the values
+    pub metric: SimilarityMetric,
+    /// Distance of each pixel (row-major, lower = more similar)
+    pub values: Vec<f32>,
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Value distribution (for the display range and threshold slider)
+    pub histogram: BandHistogram,
+}
+
+impl SimilarityMap {
+    /// Compute the map of `bands` (`width` x `height`) to `reference`.
+    pub fn compute(
+        bands: &[Vec<f32>],
+        width: u32,
+        height: u32,
+        band_info: &[BandInfo],
+        reference: &[f32],
+        metric: SimilarityMetric,
+    ) -> Result<Self, String> {
+        if reference.len() != bands.len() {
+            return Err(format!(
+                "Reference has {} bands, the image {}",
+                reference.len(),
+                bands.len()
+            ));
+        }
+        let used: Vec<usize> = (0..bands.len())
+            .filter(|&b| reference[b].is_finite() && !band_info.get(b).is_some_and(|i| i.bad))
+            .collect();
+        if used.is_empty() {
+            return Err("No usable bands in the reference spectrum".to_string());
+        }
+        let reference_norm = used
+            .iter()
+            .map(|&b| f64::from(reference[b]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        if metric == SimilarityMetric::SpectralAngle && reference_norm == 0.0 {
+            return Err("Spectral angle needs a non-zero reference".to_string());
+        }
+
+        let pixel_count = (width as usize * height as usize).min(bands[0].len());
+        let values: Vec<f32> = (0..pixel_count)
+            .map(|pixel| {
+                let mut dot = 0.0f64;
+                let mut norm_sq = 0.0f64;
+                let mut dist_sq = 0.0f64;
+                for &b in &used {
+                    let v = f64::from(bands[b][pixel]);
+                    if !v.is_finite() {
+                        return f32::NAN;
+                    }
+                    let r = f64::from(reference[b]);
+                    dot += v * r;
+                    norm_sq += v * v;
+                    dist_sq += (v - r) * (v - r);
+                }
+                match metric {
+                    SimilarityMetric::SpectralAngle if norm_sq == 0.0 => f32::NAN,
+                    SimilarityMetric::SpectralAngle => {
+                        let cos = dot / (norm_sq.sqrt() * reference_norm);
+                        cos.clamp(-1.0, 1.0).acos() as f32
+                    }
+                    SimilarityMetric::Euclidean => dist_sq.sqrt() as f32,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            metric,
+            histogram: BandHistogram::from_values(&values),
+            values,
+            width,
+            height,
+        })
+    }
+
+    /// Pixels at or below `threshold` (row-major).
+    pub fn mask(&self, threshold: f32) -> Vec<bool> {
+        self.values.iter().map(|&v| v <= threshold).collect()
+    }
+
+    /// Outlines of the connected regions at or below `threshold`, largest
+    /// first.
+    ///
+    /// Regions smaller than `min_area` pixels are skipped, and at most
+    /// `max_regions` are returned. `epsilon` is the contour simplification
+    /// tolerance in pixels.
+    pub fn regions(
+        &self,
+        threshold: f32,
+        min_area: usize,
+        epsilon: f32,
+        max_regions: usize,
+    ) -> Vec<Vec<(f32, f32)>> {
+        let mask = self.mask(threshold);
+        let mut components = connected_components(&mask, self.width, self.height);
+        components.retain(|c| c.pixels.len() >= min_area.max(1));
+        components.sort_by_key(|c| std::cmp::Reverse(c.pixels.len()));
+        components
+            .iter()
+            .take(max_regions)
+            .filter_map(|component| {
+                let contour = component.contour(self.width, epsilon);
+                (contour.len() >= 3).then_some(contour)
+            })
+            .collect()
+    }
+}
+
+/// A 4-connected region of a mask.
+struct Component {
+    /// Row-major pixel indices
+    pixels: Vec<usize>,
+    /// Bounding box (min x, min y, max x, max y), inclusive
+    bounds: (u32, u32, u32, u32),
+}
+
+impl Component {
+    /// Outline in image coordinates.
+    ///
+    /// The region is copied into a mask of its bounding box with a
+    /// one-pixel border, so only this region is traced.
+    fn contour(&self, image_width: u32, epsilon: f32) -> Vec<(f32, f32)> {
+        let (x0, y0, x1, y1) = self.bounds;
+        let width = x1 - x0 + 3;
+        let height = y1 - y0 + 3;
+        let mut data = vec![0u8; width as usize * height as usize];
+        for &pixel in &self.pixels {
+            let x = pixel as u32 % image_width - x0 + 1;
+            let y = pixel as u32 / image_width - y0 + 1;
+            data[(y * width + x) as usize] = 255;
+        }
+        let mask = SAM2Mask::new(data, width, height, 1.0);
+        extract_contour(&mask, epsilon)
+            .into_iter()
+            .map(|(x, y)| (x + x0 as f32 - 1.0, y + y0 as f32 - 1.0))
+            .collect()
+    }
+}
+
+/// Label the 4-connected regions of `mask`.
+fn connected_components(mask: &[bool], width: u32, height: u32) -> Vec<Component> {
+    let (w, h) = (width as usize, height as usize);
+    let mut visited = vec![false; mask.len()];
+    let mut components = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..mask.len().min(w * h) {
+        if !mask[start] || visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start);
+        let mut pixels = Vec::new();
+        let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+        while let Some(pixel) = stack.pop() {
+            pixels.push(pixel);
+            let (x, y) = (pixel % w, pixel / w);
+            x0 = x0.min(x as u32);
+            y0 = y0.min(y as u32);
+            x1 = x1.max(x as u32);
+            y1 = y1.max(y as u32);
+            let neighbors = [
+                (x > 0).then(|| pixel - 1),
+                (x + 1 < w).then(|| pixel + 1),
+                (y > 0).then(|| pixel - w),
+                (y + 1 < h).then(|| pixel + w),
+            ];
+            for next in neighbors.into_iter().flatten() {
+                if mask[next] && !visited[next] {
+                    visited[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        components.push(Component {
+            pixels,
+            bounds: (x0, y0, x1, y1),
+        });
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4x1 image with two bands.
+    fn cube() -> Vec<Vec<f32>> {
+        vec![vec![1.0, 2.0, 0.0, f32::NAN], vec![1.0, 2.0, 1.0, 1.0]]
+    }
+
+    #[test]
+    fn test_spectral_angle() {
+        let map = SimilarityMap::compute(
+            &cube(),
+            4,
+            1,
+            &[],
+            &[1.0, 1.0],
+            SimilarityMetric::SpectralAngle,
+        )
+        .unwrap();
+        // Scaled copies of the reference have angle 0
+        assert!(map.values[0].abs() < 1e-6);
+        assert!(map.values[1].abs() < 1e-6);
+        assert!((map.values[2] - std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+        assert!(map.values[3].is_nan());
+    }
+
+    #[test]
+    fn test_euclidean_skips_bad_bands() {
+        let map =
+            SimilarityMap::compute(&cube(), 4, 1, &[], &[1.0, 1.0], SimilarityMetric::Euclidean)
+                .unwrap();
+        assert_eq!(&map.values[..3], &[0.0, 2f32.sqrt(), 1.0]);
+
+        // Without the first band only the second one counts
+        let mut info = vec![BandInfo::named("a"), BandInfo::named("b")];
+        info[0].bad = true;
+        let map = SimilarityMap::compute(
+            &cube(),
+            4,
+            1,
+            &info,
+            &[1.0, 1.0],
+            SimilarityMetric::Euclidean,
+        )
+        .unwrap();
+        assert_eq!(map.values, vec![0.0, 1.0, 0.0, 0.0]);
+
+        assert!(
+            SimilarityMap::compute(&cube(), 4, 1, &[], &[1.0], SimilarityMetric::Euclidean)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_regions() {
+        // Two separate blocks of low values in a 10x10 image
+        let mut values = vec![1.0f32; 100];
+        for y in 1..4 {
+            for x in 1..4 {
+                values[y * 10 + x] = 0.0;
+            }
+        }
+        for y in 6..10 {
+            for x in 5..10 {
+                values[y * 10 + x] = 0.0;
+            }
+        }
+        values[0] = 0.0; // single pixel, below the minimum area
+        let map = SimilarityMap {
+            metric: SimilarityMetric::Euclidean,
+            histogram: BandHistogram::from_values(&values),
+            values,
+            width: 10,
+            height: 10,
+        };
+
+        let regions = map.regions(0.5, 2, 0.5, 10);
+        assert_eq!(regions.len(), 2);
+        // Largest first, each within its block
+        let inside = |region: &[(f32, f32)], x: (f32, f32), y: (f32, f32)| {
+            region
+                .iter()
+                .all(|p| p.0 >= x.0 && p.0 <= x.1 && p.1 >= y.0 && p.1 <= y.1)
+        };
+        assert!(inside(&regions[0], (5.0, 10.0), (6.0, 10.0)));
+        assert!(inside(&regions[1], (1.0, 4.0), (1.0, 4.0)));
+
+        assert_eq!(map.regions(0.5, 2, 0.5, 1).len(), 1);
+        assert!(map.regions(-1.0, 1, 0.5, 10).is_empty());
+    }
+}
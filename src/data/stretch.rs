@@ -0,0 +1,165 @@
+//! Automatic contrast stretch of display channels.
+//!
+//! A stretch maps the band values of one output channel to 0.0-1.0 before the
+//! manual adjustments (brightness, contrast, ...) are applied. The bounds are
+//! derived from the band histogram, or set per channel with the histogram
+//! markers in `StretchMode::Manual`.
+
+use super::histogram::BandHistogram;
+
+/// Lower percentile used by `StretchMode::Percentile`.
+pub const PERCENTILE_LOW: f32 = 2.0;
+/// Upper percentile used by `StretchMode::Percentile`.
+pub const PERCENTILE_HIGH: f32 = 98.0;
+
+/// How display channels are stretched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StretchMode {
+    /// Band values are shown unchanged
+    #[default]
+    None,
+    /// Linear from band minimum to maximum
+    MinMax,
+    /// Linear between the 2% and 98% percentiles
+    Percentile,
+    /// Linear over mean ± k standard deviations
+    StdDev,
+    /// Histogram equalization
+    Equalize,
+    /// Linear between bounds dragged on the channel histograms (entered by
+    /// dragging, so not part of `all()`)
+    Manual,
+}
+
+impl StretchMode {
+    /// All modes, in display order.
+    pub fn all() -> &'static [StretchMode] {
+        &[
+            StretchMode::None,
+            StretchMode::MinMax,
+            StretchMode::Percentile,
+            StretchMode::StdDev,
+            StretchMode::Equalize,
+        ]
+    }
+
+    /// Get the display name for this mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            StretchMode::None => "Off",
+            StretchMode::MinMax => "Min/Max",
+            StretchMode::Percentile => "2-98%",
+            StretchMode::StdDev => "Std Dev",
+            StretchMode::Equalize => "Equalize",
+            StretchMode::Manual => "Manual",
+        }
+    }
+}
+
+/// Stretch of one display channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelStretch {
+    /// Band value shown as black
+    pub low: f32,
+    /// Band value shown as white
+    pub high: f32,
+    /// Equalization curve: CDF sampled evenly from `low` to `high`
+    /// (empty for linear modes)
+    pub lut: Vec<f32>,
+}
+
+impl ChannelStretch {
+    /// Compute the stretch for `mode` from a band histogram.
+    ///
+    /// `k` is the number of standard deviations for `StdDev`; `lut_size` the
+    /// number of equalization curve samples. `Manual` gives the min/max
+    /// stretch, for channels without dragged bounds. Returns `None` for
+    /// `StretchMode::None` or bands without valid values.
+    pub fn compute(
+        hist: &BandHistogram,
+        mode: StretchMode,
+        k: f32,
+        lut_size: usize,
+    ) -> Option<Self> {
+        if hist.total() == 0 {
+            return None;
+        }
+        let (low, high) = match mode {
+            StretchMode::None => return None,
+            StretchMode::MinMax | StretchMode::Equalize | StretchMode::Manual => {
+                (hist.min, hist.max)
+            }
+            StretchMode::Percentile => (
+                hist.percentile(PERCENTILE_LOW),
+                hist.percentile(PERCENTILE_HIGH),
+            ),
+            StretchMode::StdDev => (
+                (hist.mean - k * hist.std_dev).max(hist.min),
+                (hist.mean + k * hist.std_dev).min(hist.max),
+            ),
+        };
+
+        let lut = if mode == StretchMode::Equalize && lut_size > 1 {
+            let step = (high - low) / (lut_size - 1) as f32;
+            (0..lut_size)
+                .map(|i| hist.cdf(low + i as f32 * step))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Some(Self { low, high, lut })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp() -> BandHistogram {
+        let values: Vec<f32> = (0..=100).map(|v| v as f32).collect();
+        BandHistogram::from_values(&values)
+    }
+
+    #[test]
+    fn test_linear_modes() {
+        let hist = ramp();
+        assert!(ChannelStretch::compute(&hist, StretchMode::None, 2.0, 64).is_none());
+
+        let min_max = ChannelStretch::compute(&hist, StretchMode::MinMax, 2.0, 64).unwrap();
+        assert_eq!((min_max.low, min_max.high), (0.0, 100.0));
+        assert!(min_max.lut.is_empty());
+        let manual = ChannelStretch::compute(&hist, StretchMode::Manual, 2.0, 64).unwrap();
+        assert_eq!(manual, min_max);
+
+        let pct = ChannelStretch::compute(&hist, StretchMode::Percentile, 2.0, 64).unwrap();
+        assert!((pct.low - 2.0).abs() < 1.0);
+        assert!((pct.high - 98.0).abs() < 1.0);
+
+        // Mean 50, sigma ~29: 1 sigma inside the range, 2 sigma clamped to it
+        let sd = ChannelStretch::compute(&hist, StretchMode::StdDev, 1.0, 64).unwrap();
+        assert!((sd.low - 20.8).abs() < 0.5);
+        assert!((sd.high - 79.2).abs() < 0.5);
+        let sd = ChannelStretch::compute(&hist, StretchMode::StdDev, 2.0, 64).unwrap();
+        assert_eq!((sd.low, sd.high), (0.0, 100.0));
+    }
+
+    #[test]
+    fn test_equalize_lut() {
+        // Skewed band: most values near zero
+        let values: Vec<f32> = (0..1000).map(|v| (v as f32 / 1000.0).powi(4)).collect();
+        let hist = BandHistogram::from_values(&values);
+        let eq = ChannelStretch::compute(&hist, StretchMode::Equalize, 2.0, 16).unwrap();
+        assert_eq!(eq.lut.len(), 16);
+        assert_eq!(eq.lut[15], 1.0);
+        assert!(eq.lut.windows(2).all(|w| w[0] <= w[1]));
+        // Dark values are spread out
+        assert!(eq.lut[1] > 0.4);
+    }
+
+    #[test]
+    fn test_empty_band() {
+        let hist = BandHistogram::from_values(&[]);
+        assert!(ChannelStretch::compute(&hist, StretchMode::MinMax, 2.0, 64).is_none());
+    }
+}
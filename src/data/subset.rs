@@ -0,0 +1,261 @@
+//! Band subset, band stride and spatial binning applied at load time.
+//!
+//! Huge cubes rarely need every band at full resolution. `LoadSubset`
+//! selects a band range, every n-th band of it, and an n x n pixel binning.
+//! Loaders reading raw layouts (NumPy, ENVI) decode only the selected
+//! samples; the registry applies the subset to the output of all others.
+
+use serde::{Deserialize, Serialize};
+
+/// Bands and resolution to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadSubset {
+    /// First and last band to load (0-based, inclusive); `None` = all bands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bands: Option<(usize, usize)>,
+    /// Load every n-th band of the range (1 = all)
+    pub band_stride: usize,
+    /// Average n x n pixel blocks into one pixel (1 = full resolution)
+    pub binning: u32,
+}
+
+impl Default for LoadSubset {
+    fn default() -> Self {
+        Self {
+            bands: None,
+            band_stride: 1,
+            binning: 1,
+        }
+    }
+}
+
+impl LoadSubset {
+    /// Whether everything is loaded at full resolution.
+    pub fn is_full(&self) -> bool {
+        self.bands.is_none() && self.band_stride <= 1 && self.binning <= 1
+    }
+
+    /// Source indices of the bands to load from a cube with `num_bands`.
+    pub fn band_indices(&self, num_bands: usize) -> Vec<usize> {
+        if num_bands == 0 {
+            return Vec::new();
+        }
+        let (first, last) = self.bands.unwrap_or((0, num_bands - 1));
+        let last = last.min(num_bands - 1);
+        (first.min(last)..=last)
+            .step_by(self.band_stride.max(1))
+            .collect()
+    }
+
+    /// Image size after binning a `width` x `height` image.
+    ///
+    /// Partial blocks at the right and bottom edge become pixels of their own.
+    pub fn binned_size(&self, width: usize, height: usize) -> (usize, usize) {
+        let bin = self.binning.max(1) as usize;
+        (width.div_ceil(bin), height.div_ceil(bin))
+    }
+
+    /// Parse a 1-based band range as typed by the user (`"10-120"`, `"5"`).
+    ///
+    /// An empty string selects all bands.
+    pub fn parse_band_range(text: &str) -> Result<Option<(usize, usize)>, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Invalid band number: {}", s.trim()))
+        };
+        let (first, last) = match text.split_once(['-', ':']) {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => (parse(text)?, parse(text)?),
+        };
+        if first > last {
+            return Err(format!("Band range {}-{} is reversed", first, last));
+        }
+        Ok(Some((first - 1, last - 1)))
+    }
+
+    /// 1-based band range for display, the inverse of `parse_band_range`.
+    pub fn band_range_text(&self) -> String {
+        match self.bands {
+            Some((first, last)) if first == last => format!("{}", first + 1),
+            Some((first, last)) => format!("{}-{}", first + 1, last + 1),
+            None => String::new(),
+        }
+    }
+}
+
+/// Bands decoded by `decode_subset`.
+pub(crate) struct SubsetBands {
+    /// Selected bands in row-major pixel order
+    pub bands: Vec<Vec<f32>>,
+    /// Width after binning
+    pub width: usize,
+    /// Height after binning
+    pub height: usize,
+    /// Source index of each band
+    pub indices: Vec<usize>,
+}
+
+/// Decode the bands and resolution selected by `subset` from a cube of
+/// `num_bands` bands of `width` x `height` pixels.
+///
+/// `sample(band, x, y)` returns one source value. Pixels are visited row by
+/// row with all selected bands of a pixel together, so interleaved payloads
+/// are read front to back. Binned pixels are the mean of the finite values
+/// of their block (NaN if there are none).
+pub(crate) fn decode_subset(
+    subset: &LoadSubset,
+    num_bands: usize,
+    width: usize,
+    height: usize,
+    sample: impl Fn(usize, usize, usize) -> f32,
+) -> SubsetBands {
+    let indices = subset.band_indices(num_bands);
+    let bin = subset.binning.max(1) as usize;
+    let (out_width, out_height) = subset.binned_size(width, height);
+    let mut bands = vec![Vec::with_capacity(out_width * out_height); indices.len()];
+
+    if bin == 1 {
+        for y in 0..height {
+            for x in 0..width {
+                for (band, &b) in bands.iter_mut().zip(&indices) {
+                    band.push(sample(b, x, y));
+                }
+            }
+        }
+    } else {
+        // Running sums of one output row, per selected band
+        let mut sums = vec![0.0f64; indices.len() * out_width];
+        let mut counts = vec![0u32; indices.len() * out_width];
+        for out_y in 0..out_height {
+            sums.fill(0.0);
+            counts.fill(0);
+            for y in out_y * bin..((out_y + 1) * bin).min(height) {
+                for x in 0..width {
+                    for (i, &b) in indices.iter().enumerate() {
+                        let value = sample(b, x, y);
+                        if value.is_finite() {
+                            let slot = i * out_width + x / bin;
+                            sums[slot] += f64::from(value);
+                            counts[slot] += 1;
+                        }
+                    }
+                }
+            }
+            for (i, band) in bands.iter_mut().enumerate() {
+                let row = i * out_width..(i + 1) * out_width;
+                band.extend(
+                    sums[row.clone()]
+                        .iter()
+                        .zip(&counts[row])
+                        .map(|(&sum, &count)| {
+                            if count > 0 {
+                                (sum / f64::from(count)) as f32
+                            } else {
+                                f32::NAN
+                            }
+                        }),
+                );
+            }
+        }
+    }
+
+    SubsetBands {
+        bands,
+        width: out_width,
+        height: out_height,
+        indices,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_band_indices() {
+        let full = LoadSubset::default();
+        assert!(full.is_full());
+        assert_eq!(full.band_indices(3), vec![0, 1, 2]);
+        assert!(full.band_indices(0).is_empty());
+
+        let subset = LoadSubset {
+            bands: Some((2, 20)),
+            band_stride: 3,
+            binning: 1,
+        };
+        assert!(!subset.is_full());
+        // The range is clipped to the available bands
+        assert_eq!(subset.band_indices(10), vec![2, 5, 8]);
+        assert_eq!(subset.band_indices(2), vec![1]);
+    }
+
+    #[test]
+    fn test_parse_band_range() {
+        assert_eq!(LoadSubset::parse_band_range(""), Ok(None));
+        assert_eq!(LoadSubset::parse_band_range("10-120"), Ok(Some((9, 119))));
+        assert_eq!(LoadSubset::parse_band_range(" 5 "), Ok(Some((4, 4))));
+        assert!(LoadSubset::parse_band_range("0-3").is_err());
+        assert!(LoadSubset::parse_band_range("9-3").is_err());
+        assert!(LoadSubset::parse_band_range("a").is_err());
+
+        let subset = LoadSubset {
+            bands: Some((9, 119)),
+            ..Default::default()
+        };
+        assert_eq!(subset.band_range_text(), "10-120");
+    }
+
+    #[test]
+    fn test_decode_subset_binning() {
+        // 3 bands of 3x2 pixels, value = band * 100 + y * 10 + x
+        let sample = |b: usize, x: usize, y: usize| (b * 100 + y * 10 + x) as f32;
+
+        let full = decode_subset(&LoadSubset::default(), 3, 3, 2, sample);
+        assert_eq!((full.width, full.height), (3, 2));
+        assert_eq!(
+            full.bands[2],
+            vec![200.0, 201.0, 202.0, 210.0, 211.0, 212.0]
+        );
+
+        let subset = LoadSubset {
+            bands: Some((1, 2)),
+            band_stride: 1,
+            binning: 2,
+        };
+        let binned = decode_subset(&subset, 3, 3, 2, sample);
+        assert_eq!(binned.indices, vec![1, 2]);
+        assert_eq!((binned.width, binned.height), (2, 1));
+        // A 2x2 block and the partial block of the last column
+        assert_eq!(binned.bands[0], vec![105.5, 107.0]);
+
+        // Non-finite samples are left out of the mean
+        let with_nan = |b: usize, x: usize, y: usize| {
+            if x == 0 { f32::NAN } else { sample(b, x, y) }
+        };
+        let binned = decode_subset(&subset, 3, 3, 2, with_nan);
+        assert_eq!(binned.bands[0], vec![106.0, 107.0]);
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        assert_eq!(
+            serde_json::from_str::<LoadSubset>("{}").unwrap(),
+            LoadSubset::default()
+        );
+        let subset = LoadSubset {
+            bands: Some((0, 9)),
+            band_stride: 2,
+            binning: 4,
+        };
+        let json = serde_json::to_string(&subset).unwrap();
+        assert_eq!(serde_json::from_str::<LoadSubset>(&json).unwrap(), subset);
+    }
+}
@@ -0,0 +1,390 @@
+//! True-color RGB synthesis from band wavelengths.
+//!
+//! Each pixel spectrum is linearly interpolated onto the CIE 1931 2° color
+//! matching functions (380-780 nm, 10 nm steps), weighted by the illuminant
+//! and integrated to XYZ. XYZ is adapted to the D65 white point (Bradford)
+//! and converted to gamma-encoded sRGB. Since every step up to the sRGB
+//! gamma is linear, the whole projection reduces to three weights per band.
+//!
+//! Values are treated as reflectance: a flat spectrum of 1 is white. Cubes
+//! in other units (e.g. raw counts) are scaled so their brightest pixel has
+//! a luminance of 1.
+
+/// First wavelength of the tables in nanometers.
+const TABLE_START: f32 = 380.0;
+
+/// Wavelength step of the tables in nanometers.
+const TABLE_STEP: f32 = 10.0;
+
+/// Minimum number of bands inside the tables' visible range.
+const MIN_VISIBLE_BANDS: usize = 3;
+
+/// CIE 1931 2° standard observer (x̄, ȳ, z̄), 380-780 nm in 10 nm steps.
+const CIE_1931: [[f64; 3]; 41] = [
+    [0.001368, 0.000039, 0.006450],
+    [0.004243, 0.000120, 0.020050],
+    [0.014310, 0.000396, 0.067850],
+    [0.043510, 0.001210, 0.207400],
+    [0.134380, 0.004000, 0.645600],
+    [0.283900, 0.011600, 1.385600],
+    [0.348280, 0.023000, 1.747060],
+    [0.336200, 0.038000, 1.772110],
+    [0.290800, 0.060000, 1.669200],
+    [0.195360, 0.090980, 1.287640],
+    [0.095640, 0.139020, 0.812950],
+    [0.032010, 0.208020, 0.465180],
+    [0.004900, 0.323000, 0.272000],
+    [0.009300, 0.503000, 0.158200],
+    [0.063270, 0.710000, 0.078250],
+    [0.165500, 0.862000, 0.042160],
+    [0.290400, 0.954000, 0.020300],
+    [0.433450, 0.994950, 0.008750],
+    [0.594500, 0.995000, 0.003900],
+    [0.762100, 0.952000, 0.002100],
+    [0.916300, 0.870000, 0.001650],
+    [1.026300, 0.757000, 0.001100],
+    [1.062200, 0.631000, 0.000800],
+    [1.002600, 0.503000, 0.000340],
+    [0.854450, 0.381000, 0.000190],
+    [0.642400, 0.265000, 0.000050],
+    [0.447900, 0.175000, 0.000020],
+    [0.283500, 0.107000, 0.000000],
+    [0.164900, 0.061000, 0.000000],
+    [0.087400, 0.032000, 0.000000],
+    [0.046770, 0.017000, 0.000000],
+    [0.022700, 0.008210, 0.000000],
+    [0.011359, 0.004102, 0.000000],
+    [0.005790, 0.002091, 0.000000],
+    [0.002899, 0.001047, 0.000000],
+    [0.001440, 0.000520, 0.000000],
+    [0.000690, 0.000249, 0.000000],
+    [0.000332, 0.000120, 0.000000],
+    [0.000166, 0.000060, 0.000000],
+    [0.000083, 0.000030, 0.000000],
+    [0.000042, 0.000015, 0.000000],
+];
+
+/// CIE standard illuminant D65, relative spectral power, 380-780 nm.
+const D65: [f64; 41] = [
+    49.9755, 54.6482, 82.7549, 91.486, 93.4318, 86.6823, 104.865, 117.008, 117.812, 114.861,
+    115.923, 108.811, 109.354, 107.802, 104.79, 107.689, 104.405, 104.046, 100.0, 96.3342, 95.788,
+    88.6856, 90.0062, 89.5991, 87.6987, 83.2886, 83.6992, 80.0268, 80.2146, 82.2778, 78.2842,
+    69.7213, 71.6091, 74.349, 61.604, 69.8856, 75.087, 63.5927, 46.4182, 66.8054, 63.3828,
+];
+
+/// CIE standard illuminant D50, relative spectral power, 380-780 nm.
+const D50: [f64; 41] = [
+    24.49, 29.87, 49.31, 56.51, 60.03, 57.82, 74.82, 87.25, 90.61, 91.37, 95.11, 91.96, 95.72,
+    96.61, 97.13, 102.10, 100.75, 102.32, 100.0, 97.74, 98.92, 93.50, 97.69, 99.27, 99.04, 95.72,
+    98.86, 95.67, 98.19, 103.00, 99.13, 87.38, 91.60, 92.89, 76.85, 86.51, 92.58, 78.23, 57.69,
+    82.92, 78.27,
+];
+
+/// Linear sRGB from XYZ (D65 white).
+const XYZ_TO_SRGB: [[f64; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+/// Bradford cone response from XYZ.
+const BRADFORD: [[f64; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+/// XYZ from Bradford cone response.
+const BRADFORD_INVERSE: [[f64; 3]; 3] = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867],
+];
+
+/// Light source the reflectance is viewed under.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Illuminant {
+    /// Average daylight (6504 K), the sRGB white point
+    #[default]
+    D65,
+    /// Horizon daylight (5003 K), the print industry standard
+    D50,
+}
+
+impl Illuminant {
+    /// All illuminants, in display order.
+    pub fn all() -> &'static [Illuminant] {
+        &[Illuminant::D65, Illuminant::D50]
+    }
+
+    /// Get the display name for this illuminant.
+    pub fn name(self) -> &'static str {
+        match self {
+            Illuminant::D65 => "D65",
+            Illuminant::D50 => "D50",
+        }
+    }
+
+    /// Relative spectral power on the table wavelengths.
+    fn power(self) -> &'static [f64; 41] {
+        match self {
+            Illuminant::D65 => &D65,
+            Illuminant::D50 => &D50,
+        }
+    }
+
+    /// XYZ of a perfect reflector, normalized to Y = 1.
+    fn white(self) -> [f64; 3] {
+        let power = self.power();
+        let mut white = [0.0f64; 3];
+        for (cmf, &s) in CIE_1931.iter().zip(power) {
+            for (w, &c) in white.iter_mut().zip(cmf) {
+                *w += c * s;
+            }
+        }
+        let y = white[1];
+        white.map(|w| w / y)
+    }
+}
+
+/// Per-band weights projecting a spectrum to linear sRGB.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrueColor {
+    /// Illuminant the weights were computed for
+    pub illuminant: Illuminant,
+    /// Linear red, green and blue weight of each band
+    pub weights: [Vec<f32>; 3],
+    /// Luminance (Y) weight of each band
+    luminance: Vec<f32>,
+}
+
+impl TrueColor {
+    /// Compute the weights for bands with the wavelengths of `band_info`.
+    ///
+    /// Bands without a wavelength or flagged bad get no weight. Fails
+    /// unless at least `MIN_VISIBLE_BANDS` bands lie in 380-780 nm.
+    pub fn new(band_info: &[super::BandInfo], illuminant: Illuminant) -> Result<Self, String> {
+        let mut samples: Vec<(f32, usize)> = band_info
+            .iter()
+            .enumerate()
+            .filter(|(_, info)| !info.bad)
+            .filter_map(|(band, info)| Some((info.wavelength.filter(|w| w.is_finite())?, band)))
+            .collect();
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        samples.dedup_by(|a, b| a.0 == b.0);
+        let table_end = TABLE_START + TABLE_STEP * (CIE_1931.len() - 1) as f32;
+        let visible = samples
+            .iter()
+            .filter(|(w, _)| (TABLE_START..=table_end).contains(w))
+            .count();
+        if visible < MIN_VISIBLE_BANDS {
+            return Err(format!(
+                "True color needs at least {} bands with wavelengths in {}-{} nm ({} found)",
+                MIN_VISIBLE_BANDS, TABLE_START, table_end, visible
+            ));
+        }
+
+        // XYZ weight of each band: the color matching functions times the
+        // illuminant, spread over the bands interpolating each wavelength
+        let power = illuminant.power();
+        let normalization: f64 = CIE_1931.iter().zip(power).map(|(cmf, &s)| cmf[1] * s).sum();
+        let mut xyz = vec![[0.0f64; 3]; band_info.len()];
+        for (i, (cmf, &s)) in CIE_1931.iter().zip(power).enumerate() {
+            let wavelength = TABLE_START + TABLE_STEP * i as f32;
+            for (band, share) in interpolation(&samples, wavelength) {
+                for (w, &c) in xyz[band].iter_mut().zip(cmf) {
+                    *w += share * c * s / normalization;
+                }
+            }
+        }
+
+        let to_rgb = multiply(&XYZ_TO_SRGB, &adaptation(illuminant.white()));
+        let mut weights: [Vec<f32>; 3] = std::array::from_fn(|_| vec![0.0; band_info.len()]);
+        for (band, xyz) in xyz.iter().enumerate() {
+            for (channel, row) in to_rgb.iter().enumerate() {
+                weights[channel][band] =
+                    row.iter().zip(xyz).map(|(m, v)| m * v).sum::<f64>() as f32;
+            }
+        }
+        let luminance = xyz.iter().map(|xyz| xyz[1] as f32).collect();
+        Ok(Self {
+            illuminant,
+            weights,
+            luminance,
+        })
+    }
+
+    /// Gamma-encoded sRGB images (red, green, blue; 0 to 1) of `bands`.
+    ///
+    /// Pixels with a non-finite value in a weighted band are NaN.
+    pub fn render(&self, bands: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        let pixel_count = bands.first().map_or(0, Vec::len);
+        let weighted: Vec<usize> = (0..bands.len().min(self.luminance.len()))
+            .filter(|&band| self.weights.iter().any(|w| w[band] != 0.0))
+            .collect();
+        let dot = |weights: &[f32], pixel: usize| -> f32 {
+            weighted
+                .iter()
+                .map(|&band| weights[band] * bands[band][pixel])
+                .sum()
+        };
+
+        // Scale non-reflectance data so the brightest pixel is white
+        let max_luminance = (0..pixel_count)
+            .map(|pixel| dot(&self.luminance, pixel))
+            .filter(|y| y.is_finite())
+            .fold(0.0f32, f32::max);
+        let scale = if max_luminance > 1.0 {
+            1.0 / max_luminance
+        } else {
+            1.0
+        };
+
+        self.weights
+            .iter()
+            .map(|weights| {
+                (0..pixel_count)
+                    .map(|pixel| encode_srgb(dot(weights, pixel) * scale))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Bands (and their shares) whose interpolated spectrum gives the value at
+/// `wavelength`; beyond the outermost bands their value is held.
+fn interpolation(samples: &[(f32, usize)], wavelength: f32) -> Vec<(usize, f64)> {
+    let next = samples.partition_point(|&(w, _)| w < wavelength);
+    match (next.checked_sub(1).map(|i| samples[i]), samples.get(next)) {
+        (Some((w0, b0)), Some(&(w1, b1))) => {
+            let t = f64::from((wavelength - w0) / (w1 - w0));
+            vec![(b0, 1.0 - t), (b1, t)]
+        }
+        (Some((_, band)), None) | (None, Some(&(_, band))) => vec![(band, 1.0)],
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Bradford adaptation from `white` to the D65 white point.
+fn adaptation(white: [f64; 3]) -> [[f64; 3]; 3] {
+    let source = apply(&BRADFORD, white);
+    let target = apply(&BRADFORD, Illuminant::D65.white());
+    let mut scale = [[0.0f64; 3]; 3];
+    for i in 0..3 {
+        scale[i][i] = target[i] / source[i];
+    }
+    multiply(&BRADFORD_INVERSE, &multiply(&scale, &BRADFORD))
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn apply(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| (0..3).map(|k| m[i][k] * v[k]).sum())
+}
+
+/// sRGB transfer function of a linear value, clamped to 0-1.
+fn encode_srgb(linear: f32) -> f32 {
+    if !linear.is_finite() {
+        return f32::NAN;
+    }
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::BandInfo;
+
+    /// Bands every 10 nm over 400-700 nm.
+    fn visible_bands() -> Vec<BandInfo> {
+        (0..31)
+            .map(|i| BandInfo {
+                wavelength: Some(400.0 + 10.0 * i as f32),
+                ..BandInfo::named(format!("Band {}", i + 1))
+            })
+            .collect()
+    }
+
+    /// One-pixel cube with `value(wavelength)` in every band.
+    fn pixel(info: &[BandInfo], value: impl Fn(f32) -> f32) -> Vec<Vec<f32>> {
+        info.iter()
+            .map(|info| vec![value(info.wavelength.unwrap())])
+            .collect()
+    }
+
+    fn rgb(images: &[Vec<f32>]) -> [f32; 3] {
+        std::array::from_fn(|channel| images[channel][0])
+    }
+
+    #[test]
+    fn test_white_and_gray() {
+        let info = visible_bands();
+        for &illuminant in Illuminant::all() {
+            let true_color = TrueColor::new(&info, illuminant).unwrap();
+            let white = rgb(&true_color.render(&pixel(&info, |_| 1.0)));
+            for value in white {
+                assert!((value - 1.0).abs() < 0.02, "{:?}: {:?}", illuminant, white);
+            }
+
+            // 18% gray reflectance encodes to about 0.46
+            let gray = rgb(&true_color.render(&pixel(&info, |_| 0.18)));
+            for value in gray {
+                assert!((value - 0.46).abs() < 0.02, "{:?}: {:?}", illuminant, gray);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hues() {
+        let info = visible_bands();
+        let true_color = TrueColor::new(&info, Illuminant::D65).unwrap();
+        let band =
+            |low: f32, high: f32| move |w: f32| if (low..high).contains(&w) { 0.8 } else { 0.05 };
+
+        let [r, g, b] = rgb(&true_color.render(&pixel(&info, band(600.0, 701.0))));
+        assert!(r > g && r > b, "red: {:?}", [r, g, b]);
+        let [r, g, b] = rgb(&true_color.render(&pixel(&info, band(500.0, 570.0))));
+        assert!(g > r && g > b, "green: {:?}", [r, g, b]);
+        let [r, g, b] = rgb(&true_color.render(&pixel(&info, band(400.0, 480.0))));
+        assert!(b > r && b > g, "blue: {:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn test_scaled_data_and_bad_bands() {
+        let mut info = visible_bands();
+        // Raw counts are scaled so the brightest pixel is white
+        let true_color = TrueColor::new(&info, Illuminant::D65).unwrap();
+        let counts = rgb(&true_color.render(&pixel(&info, |_| 4000.0)));
+        assert!(counts.iter().all(|v| (v - 1.0).abs() < 0.02));
+
+        // Bad bands are interpolated over, so their values are ignored
+        info[10].bad = true;
+        let true_color = TrueColor::new(&info, Illuminant::D65).unwrap();
+        let mut cube = pixel(&info, |_| 0.5);
+        cube[10][0] = f32::NAN;
+        let values = rgb(&true_color.render(&cube));
+        assert!(values.iter().all(|v| v.is_finite()), "{:?}", values);
+        assert!(true_color.weights.iter().all(|w| w[10] == 0.0));
+    }
+
+    #[test]
+    fn test_requires_visible_wavelengths() {
+        let named: Vec<BandInfo> = (0..5).map(|i| BandInfo::named(format!("{}", i))).collect();
+        assert!(TrueColor::new(&named, Illuminant::D65).is_err());
+
+        let infrared: Vec<BandInfo> = (0..5)
+            .map(|i| BandInfo {
+                wavelength: Some(900.0 + 50.0 * i as f32),
+                ..BandInfo::named(format!("{}", i))
+            })
+            .collect();
+        assert!(TrueColor::new(&infrared, Illuminant::D65).is_err());
+    }
+}
@@ -15,18 +15,27 @@ use crate::format::project::{
 use crate::format::traits::{
     AnnotationFormat, ExportOptions, ExportResult, FormatWarning, ImportOptions,
 };
+use crate::model::{
+    AnnotationShape, DEFAULT_POLYLINE_WIDTH, KEYPOINT_NOT_LABELED, KEYPOINT_VISIBLE, Keypoint,
+    MAX_MASK_PIXELS, RleMask, ring_holes, rotated_box_corners,
+};
 
 /// COCO JSON format.
 ///
 /// Supports:
 /// - Bounding boxes (bbox)
 /// - Polygons (segmentation)
+/// - Multipolygons (one segmentation list per part or hole)
 /// - Points (as single-point segmentation)
-/// - Categories with supercategories
+/// - Polylines (as open segmentation paths, marked in `attributes`)
+/// - Masks (as RLE segmentation, with `iscrowd` for crowd regions)
+/// - Keypoints (`keypoints`/`num_keypoints`, with the instance box as bbox)
+/// - Categories with supercategories, keypoint names and skeletons
 ///
 /// Does not support:
 /// - Per-image tags (COCO doesn't have this concept)
 /// - Category colors (not part of standard COCO)
+/// - Marking polygon holes (written as extra lists with a warning)
 pub struct CocoFormat;
 
 impl AnnotationFormat for CocoFormat {
@@ -86,14 +95,21 @@ impl AnnotationFormat for CocoFormat {
         let mut warnings = Vec::new();
         let mut coco = CocoDataset::new();
 
-        // Convert categories
+        // Convert categories (COCO skeletons use 1-based keypoint numbers)
         for cat in &data.categories {
             coco.categories.push(CocoCategory {
                 id: cat.id,
                 name: cat.name.clone(),
                 supercategory: cat.supercategory.clone().unwrap_or_else(|| "none".into()),
+                keypoints: cat.keypoints.clone(),
+                skeleton: cat.skeleton.iter().map(|(a, b)| [a + 1, b + 1]).collect(),
             });
         }
+        let keypoint_counts: HashMap<u32, usize> = data
+            .categories
+            .iter()
+            .map(|cat| (cat.id, cat.keypoints.len()))
+            .collect();
 
         // Convert images and annotations
         let mut annotation_id = 1u64;
@@ -138,7 +154,27 @@ impl AnnotationFormat for CocoFormat {
             });
 
             for ann in &image.annotations {
-                match self.convert_annotation(ann, image_id, annotation_id, &mut warnings) {
+                if let ShapeEntry::Keypoints { keypoints, .. } = &ann.shape {
+                    let expected = keypoint_counts.get(&ann.category_id).copied();
+                    if expected != Some(keypoints.len()) {
+                        warnings.push(
+                            FormatWarning::warning(format!(
+                                "Annotation has {} keypoints but its category template has {}",
+                                keypoints.len(),
+                                expected.unwrap_or(0)
+                            ))
+                            .with_image(&image.path),
+                        );
+                    }
+                }
+                match self.convert_annotation(
+                    ann,
+                    image_id,
+                    annotation_id,
+                    (width, height),
+                    &image.path,
+                    &mut warnings,
+                ) {
                     Ok(coco_ann) => {
                         coco.annotations.push(coco_ann);
                         annotation_id += 1;
@@ -195,6 +231,16 @@ impl AnnotationFormat for CocoFormat {
             if cat.supercategory != "none" {
                 entry = entry.with_supercategory(&cat.supercategory);
             }
+            if !cat.keypoints.is_empty() {
+                let count = cat.keypoints.len();
+                let skeleton = cat
+                    .skeleton
+                    .iter()
+                    .filter(|[a, b]| (1..=count).contains(a) && (1..=count).contains(b))
+                    .map(|[a, b]| (a - 1, b - 1))
+                    .collect();
+                entry = entry.with_keypoints(cat.keypoints.clone(), skeleton);
+            }
             data.categories.push(entry);
         }
 
@@ -219,6 +265,16 @@ impl AnnotationFormat for CocoFormat {
 
         // Convert annotations
         for coco_ann in &coco.annotations {
+            // Refuse masks too large to decode rather than dropping them
+            if let Some(CocoSegmentation::Rle(rle)) = &coco_ann.segmentation
+                && rle.pixel_count() > MAX_MASK_PIXELS
+            {
+                let [height, width] = rle.size;
+                return Err(FormatError::invalid_format(format!(
+                    "RLE mask of annotation {} is {}x{} pixels, more than the limit of {}",
+                    coco_ann.id, width, height, MAX_MASK_PIXELS
+                )));
+            }
             if let Some(&img_idx) = image_map.get(&coco_ann.image_id) {
                 if let Some(shape) = self.convert_coco_annotation(coco_ann) {
                     let entry =
@@ -251,7 +307,9 @@ impl CocoFormat {
         ann: &AnnotationEntry,
         image_id: u64,
         annotation_id: u64,
-        _warnings: &mut Vec<FormatWarning>,
+        image_size: (u32, u32),
+        image_path: &Path,
+        warnings: &mut Vec<FormatWarning>,
     ) -> Result<CocoAnnotation, FormatError> {
         let (bbox, segmentation, area) = match &ann.shape {
             ShapeEntry::BoundingBox {
@@ -267,15 +325,149 @@ impl CocoFormat {
             ShapeEntry::Point { x, y } => {
                 // Represent point as a small polygon for COCO compatibility
                 let seg = vec![vec![*x, *y]];
-                (None, Some(seg), 0.0)
+                (None, Some(CocoSegmentation::Polygons(seg)), 0.0)
             }
             ShapeEntry::Polygon { vertices } => {
                 // Convert vertices to flat array [x1, y1, x2, y2, ...]
                 let flat: Vec<f32> = vertices.iter().flat_map(|(x, y)| [*x, *y]).collect();
                 let area = polygon_area(vertices);
                 let bbox = polygon_bbox(vertices);
-                (bbox, Some(vec![flat]), area)
+                (bbox, Some(CocoSegmentation::Polygons(vec![flat])), area)
+            }
+            ShapeEntry::MultiPolygon { rings } => {
+                // One segmentation list per ring; COCO has no hole marker
+                let holes = ring_holes(rings);
+                if holes.iter().any(|hole| *hole) {
+                    warnings.push(
+                        FormatWarning::warning(
+                            "Exported polygon holes as extra segmentation lists (COCO tools may fill them)",
+                        )
+                        .with_image(image_path),
+                    );
+                }
+                let flat: Vec<Vec<f32>> = rings
+                    .iter()
+                    .map(|ring| ring.iter().flat_map(|(x, y)| [*x, *y]).collect())
+                    .collect();
+                let area = rings
+                    .iter()
+                    .zip(&holes)
+                    .map(|(ring, hole)| {
+                        if *hole {
+                            -polygon_area(ring)
+                        } else {
+                            polygon_area(ring)
+                        }
+                    })
+                    .sum();
+                let all: Vec<(f32, f32)> = rings.iter().flatten().copied().collect();
+                (
+                    polygon_bbox(&all),
+                    Some(CocoSegmentation::Polygons(flat)),
+                    area,
+                )
+            }
+            ShapeEntry::RotatedBox {
+                cx,
+                cy,
+                width,
+                height,
+                angle,
+            } => {
+                // COCO has no oriented boxes; store the corners as a polygon
+                warnings.push(
+                    FormatWarning::warning(
+                        "Exported rotated box as a polygon (COCO does not support rotation)",
+                    )
+                    .with_image(image_path),
+                );
+                let vertices = rotated_box_corners(*cx, *cy, *width, *height, *angle);
+                let flat: Vec<f32> = vertices.iter().flat_map(|(x, y)| [*x, *y]).collect();
+                let bbox = polygon_bbox(&vertices);
+                (
+                    bbox,
+                    Some(CocoSegmentation::Polygons(vec![flat])),
+                    width * height,
+                )
+            }
+            ShapeEntry::Polyline { vertices, width } => {
+                // COCO has no line type; other tools will read the path as a polygon
+                warnings.push(
+                    FormatWarning::warning(
+                        "Exported polyline as an open segmentation path (COCO has no line type)",
+                    )
+                    .with_image(image_path),
+                );
+                let flat: Vec<f32> = vertices.iter().flat_map(|(x, y)| [*x, *y]).collect();
+                let bbox = polygon_bbox(vertices);
+                (
+                    bbox,
+                    Some(CocoSegmentation::Polygons(vec![flat])),
+                    polyline_length(vertices) * width,
+                )
             }
+            ShapeEntry::Mask { .. } => {
+                let mask = ann.shape.rle_mask().unwrap_or_default();
+                // RLE covers the whole image; fall back to the mask's extent
+                let (width, height) = if image_size.0 > 0 && image_size.1 > 0 {
+                    image_size
+                } else {
+                    (mask.x + mask.width, mask.y + mask.height)
+                };
+                let rle = CocoRle {
+                    size: [height, width],
+                    counts: CocoRleCounts::Compressed(encode_rle_counts(&mask_to_coco_counts(
+                        &mask, width, height,
+                    ))),
+                };
+                let bbox = Some([
+                    mask.x as f32,
+                    mask.y as f32,
+                    mask.width as f32,
+                    mask.height as f32,
+                ]);
+                (bbox, Some(CocoSegmentation::Rle(rle)), mask.area() as f32)
+            }
+            ShapeEntry::Keypoints {
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => (Some([*x, *y, *width, *height]), None, width * height),
+        };
+
+        // Flat (x, y, visibility) triplets; unlabeled keypoints are all zero
+        let (keypoints, num_keypoints) = match &ann.shape {
+            ShapeEntry::Keypoints { keypoints, .. } => (
+                Some(
+                    keypoints
+                        .iter()
+                        .flat_map(|(x, y, v)| {
+                            if *v == KEYPOINT_NOT_LABELED {
+                                [0.0; 3]
+                            } else {
+                                [*x, *y, *v as f32]
+                            }
+                        })
+                        .collect(),
+                ),
+                Some(
+                    keypoints
+                        .iter()
+                        .filter(|(_, _, v)| *v != KEYPOINT_NOT_LABELED)
+                        .count() as u32,
+                ),
+            ),
+            _ => (None, None),
+        };
+
+        let attributes = match &ann.shape {
+            ShapeEntry::Polyline { width, .. } => Some(CocoAttributes {
+                shape_type: Some("polyline".into()),
+                line_width: Some(*width),
+            }),
+            _ => None,
         };
 
         Ok(CocoAnnotation {
@@ -285,14 +477,78 @@ impl CocoFormat {
             bbox,
             segmentation,
             area,
-            iscrowd: 0,
+            iscrowd: matches!(ann.shape, ShapeEntry::Mask { crowd: true, .. }) as u8,
+            keypoints,
+            num_keypoints,
+            attributes,
         })
     }
 
     /// Convert a COCO annotation to a shape entry.
     fn convert_coco_annotation(&self, ann: &CocoAnnotation) -> Option<ShapeEntry> {
+        // Keypoint annotations take priority over their segmentation
+        if let Some(flat) = ann.keypoints.as_ref().filter(|k| !k.is_empty()) {
+            let keypoints: Vec<Keypoint> = flat
+                .chunks_exact(3)
+                .map(|chunk| {
+                    Keypoint::new(chunk[0], chunk[1], (chunk[2] as u8).min(KEYPOINT_VISIBLE))
+                })
+                .collect();
+            let shape = match ann.bbox {
+                Some([x, y, width, height]) => AnnotationShape::Keypoints {
+                    keypoints,
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+                None => AnnotationShape::keypoints_from_points(keypoints)?,
+            };
+            return Some(ShapeEntry::from_shape(&shape));
+        }
+
+        // Polylines exported by HVAT are marked in the attributes
+        if let Some(attributes) = ann
+            .attributes
+            .as_ref()
+            .filter(|a| a.shape_type.as_deref() == Some("polyline"))
+        {
+            let vertices: Vec<(f32, f32)> = ann
+                .segmentation
+                .as_ref()?
+                .polygons()?
+                .first()?
+                .chunks_exact(2)
+                .map(|chunk| (chunk[0], chunk[1]))
+                .collect();
+            return Some(ShapeEntry::Polyline {
+                vertices,
+                width: attributes.line_width.unwrap_or(DEFAULT_POLYLINE_WIDTH),
+            });
+        }
+
+        // RLE segmentation becomes a mask
+        if let Some(CocoSegmentation::Rle(rle)) = &ann.segmentation {
+            let counts = match &rle.counts {
+                CocoRleCounts::List(counts) => Some(counts.clone()),
+                CocoRleCounts::Compressed(s) => decode_rle_counts(s),
+            };
+            let [height, width] = rle.size;
+            let mask = coco_counts_to_mask(&counts?, width, height);
+            if !mask.is_empty() {
+                return Some(ShapeEntry::Mask {
+                    x: mask.x,
+                    y: mask.y,
+                    width: mask.width,
+                    height: mask.height,
+                    counts: mask.counts,
+                    crowd: ann.iscrowd != 0,
+                });
+            }
+        }
+
         // Prefer segmentation if available
-        if let Some(ref seg) = ann.segmentation {
+        if let Some(seg) = ann.segmentation.as_ref().and_then(|s| s.polygons()) {
             if let Some(first_seg) = seg.first() {
                 if first_seg.len() == 2 {
                     // Single point
@@ -301,18 +557,23 @@ impl CocoFormat {
                         y: first_seg[1],
                     });
                 } else if first_seg.len() >= 6 {
-                    // Polygon (at least 3 vertices)
-                    let vertices: Vec<(f32, f32)> = first_seg
-                        .chunks(2)
-                        .filter_map(|chunk| {
-                            if chunk.len() == 2 {
-                                Some((chunk[0], chunk[1]))
-                            } else {
-                                None
-                            }
+                    // Polygon (at least 3 vertices); several lists are parts or holes
+                    let mut rings: Vec<Vec<(f32, f32)>> = seg
+                        .iter()
+                        .filter(|ring| ring.len() >= 6)
+                        .map(|ring| {
+                            ring.chunks_exact(2)
+                                .map(|chunk| (chunk[0], chunk[1]))
+                                .collect()
                         })
                         .collect();
-                    return Some(ShapeEntry::Polygon { vertices });
+                    return Some(if rings.len() == 1 {
+                        ShapeEntry::Polygon {
+                            vertices: rings.remove(0),
+                        }
+                    } else {
+                        ShapeEntry::MultiPolygon { rings }
+                    });
                 }
             }
         }
@@ -347,6 +608,116 @@ fn polygon_area(vertices: &[(f32, f32)]) -> f32 {
     (area / 2.0).abs()
 }
 
+/// Calculate the length of an open path.
+fn polyline_length(vertices: &[(f32, f32)]) -> f32 {
+    vertices
+        .windows(2)
+        .map(|pair| ((pair[1].0 - pair[0].0).powi(2) + (pair[1].1 - pair[0].1).powi(2)).sqrt())
+        .sum()
+}
+
+/// Convert a mask to COCO's column-major run lengths over a whole image.
+fn mask_to_coco_counts(mask: &RleMask, width: u32, height: u32) -> Vec<u32> {
+    let bits = mask.to_bitmap();
+    let mut counts = Vec::new();
+    let mut current = false;
+    let mut run = 0u32;
+    for col in 0..width {
+        for row in 0..height {
+            let bit = col >= mask.x
+                && row >= mask.y
+                && col - mask.x < mask.width
+                && row - mask.y < mask.height
+                && bits[((row - mask.y) * mask.width + col - mask.x) as usize];
+            if bit != current {
+                counts.push(run);
+                current = bit;
+                run = 0;
+            }
+            run += 1;
+        }
+    }
+    counts.push(run);
+    counts
+}
+
+/// Convert COCO's column-major run lengths over a whole image to a mask.
+/// Import rejects sizes beyond [`MAX_MASK_PIXELS`] before converting.
+fn coco_counts_to_mask(counts: &[u32], width: u32, height: u32) -> RleMask {
+    let (w, h) = (width as usize, height as usize);
+    let mut bits = vec![false; w * h];
+    let mut pos = 0usize;
+    for (i, &run) in counts.iter().enumerate() {
+        if i % 2 == 1 {
+            for p in pos..(pos + run as usize).min(w * h) {
+                bits[(p % h) * w + p / h] = true;
+            }
+        }
+        pos += run as usize;
+    }
+    RleMask::from_bitmap(0, 0, width, height, &bits)
+}
+
+/// Encode run lengths as a pycocotools compressed RLE string.
+///
+/// Each count (stored as a difference from the count two back, after the
+/// first three) is written as little-endian 5-bit groups offset into
+/// printable ASCII, with bit 0x20 marking continuation.
+fn encode_rle_counts(counts: &[u32]) -> String {
+    let mut out = String::new();
+    for (i, &count) in counts.iter().enumerate() {
+        let mut x = count as i64;
+        if i > 2 {
+            x -= counts[i - 2] as i64;
+        }
+        loop {
+            let mut c = (x & 0x1f) as u8;
+            x >>= 5;
+            let more = if c & 0x10 != 0 { x != -1 } else { x != 0 };
+            if more {
+                c |= 0x20;
+            }
+            out.push((c + 48) as char);
+            if !more {
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// Decode a pycocotools compressed RLE string. None if it is malformed.
+fn decode_rle_counts(s: &str) -> Option<Vec<u32>> {
+    let bytes = s.as_bytes();
+    let mut counts: Vec<u32> = Vec::new();
+    let mut p = 0;
+    while p < bytes.len() {
+        let mut x = 0i64;
+        let mut k = 0;
+        loop {
+            // 12 groups already exceed any valid count
+            if k >= 12 {
+                return None;
+            }
+            let c = bytes.get(p)?.checked_sub(48)? as i64;
+            x |= (c & 0x1f) << (5 * k);
+            p += 1;
+            k += 1;
+            if c & 0x20 == 0 {
+                if c & 0x10 != 0 {
+                    x |= -1i64 << (5 * k);
+                }
+                break;
+            }
+        }
+        if counts.len() > 2 {
+            x += counts[counts.len() - 2] as i64;
+        }
+        counts.push(u32::try_from(x).ok()?);
+    }
+    Some(counts)
+}
+
 /// Calculate the bounding box of a polygon.
 fn polygon_bbox(vertices: &[(f32, f32)]) -> Option<[f32; 4]> {
     if vertices.is_empty() {
@@ -419,9 +790,67 @@ struct CocoAnnotation {
     #[serde(skip_serializing_if = "Option::is_none")]
     bbox: Option<[f32; 4]>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    segmentation: Option<Vec<Vec<f32>>>,
+    segmentation: Option<CocoSegmentation>,
     area: f32,
     iscrowd: u8,
+    /// Keypoints as flat (x, y, visibility) triplets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keypoints: Option<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    num_keypoints: Option<u32>,
+    /// Non-standard extension, used to mark shapes COCO can't represent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attributes: Option<CocoAttributes>,
+}
+
+/// Segmentation as polygon lists or, for masks and crowd regions, RLE.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CocoSegmentation {
+    Polygons(Vec<Vec<f32>>),
+    Rle(CocoRle),
+}
+
+impl CocoSegmentation {
+    fn polygons(&self) -> Option<&Vec<Vec<f32>>> {
+        match self {
+            CocoSegmentation::Polygons(polygons) => Some(polygons),
+            CocoSegmentation::Rle(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CocoRle {
+    /// Image size as [height, width]
+    size: [u32; 2],
+    counts: CocoRleCounts,
+}
+
+impl CocoRle {
+    /// Pixels of the image the mask covers.
+    fn pixel_count(&self) -> u64 {
+        let [height, width] = self.size;
+        height as u64 * width as u64
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CocoRleCounts {
+    /// Uncompressed run lengths (used for `iscrowd` in the COCO dataset)
+    List(Vec<u32>),
+    /// pycocotools compressed string
+    Compressed(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CocoAttributes {
+    /// Original HVAT shape type (e.g. "polyline")
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    shape_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line_width: Option<f32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -429,6 +858,11 @@ struct CocoCategory {
     id: u32,
     name: String,
     supercategory: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keypoints: Vec<String>,
+    /// Skeleton edges as pairs of 1-based keypoint numbers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    skeleton: Vec<[usize; 2]>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -463,6 +897,33 @@ mod tests {
         assert_eq!(bbox[3], 60.0); // height
     }
 
+    #[test]
+    fn test_polyline_length() {
+        let path = vec![(0.0, 0.0), (3.0, 4.0), (3.0, 10.0)];
+        assert!((polyline_length(&path) - 11.0).abs() < 0.001);
+        assert_eq!(polyline_length(&path[..1]), 0.0);
+    }
+
+    #[test]
+    fn test_rle_string_roundtrip() {
+        let counts = vec![0, 5, 120, 3, 70000, 1, 2];
+        let encoded = encode_rle_counts(&counts);
+        assert_eq!(decode_rle_counts(&encoded), Some(counts));
+        // Matches pycocotools for a 2x2 image with its bottom-right pixel set
+        assert_eq!(encode_rle_counts(&[3, 1]), "31");
+        // Truncated in the middle of a value
+        assert_eq!(decode_rle_counts("P"), None);
+    }
+
+    #[test]
+    fn test_mask_column_major_counts() {
+        // 3x2 image, foreground at (1, 0) and (2, 1)
+        let mask = RleMask::from_pixels(&[1, 5], 3);
+        let counts = mask_to_coco_counts(&mask, 3, 2);
+        assert_eq!(counts, vec![2, 1, 2, 1]);
+        assert_eq!(coco_counts_to_mask(&counts, 3, 2), mask);
+    }
+
     #[test]
     fn test_format_metadata() {
         let format = CocoFormat;
@@ -0,0 +1,339 @@
+//! DOTA TXT format implementation.
+//!
+//! Implements the DOTA aerial imagery format, which uses one text file per
+//! image with one oriented box per line in pixel coordinates:
+//! `<x1> <y1> <x2> <y2> <x3> <y3> <x4> <y4> <category> <difficult>`.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use super::yolo::find_image_for_stem;
+use crate::format::error::FormatError;
+use crate::format::project::{
+    AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ProjectMetadata, ShapeEntry,
+};
+use crate::format::traits::{
+    AnnotationFormat, ExportOptions, ExportResult, FormatWarning, ImportOptions,
+};
+use crate::model::AnnotationShape;
+
+/// DOTA TXT format.
+///
+/// Supports:
+/// - Rotated boxes (four pixel corners)
+/// - Bounding boxes (exported as unrotated corners)
+/// - Per-image annotation files
+/// - Category names inline on each line
+///
+/// Does not support:
+/// - Polygons (skipped with warning)
+/// - Points (skipped with warning)
+/// - Per-image tags
+/// - Category colors
+pub struct DotaFormat;
+
+impl AnnotationFormat for DotaFormat {
+    fn id(&self) -> &'static str {
+        "dota"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "DOTA (TXT)"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["txt"]
+    }
+
+    fn supports_polygon(&self) -> bool {
+        false
+    }
+
+    fn supports_point(&self) -> bool {
+        false
+    }
+
+    fn supports_per_image(&self) -> bool {
+        true
+    }
+
+    fn export(
+        &self,
+        data: &ProjectData,
+        path: &Path,
+        _options: &ExportOptions,
+    ) -> Result<ExportResult, FormatError> {
+        log::info!("Exporting DOTA annotations to {:?}", path);
+
+        let output_dir = path;
+        std::fs::create_dir_all(output_dir)?;
+
+        let mut warnings = Vec::new();
+        let mut files_created = Vec::new();
+        let mut annotations_exported = 0;
+
+        let cat_names = category_names(data);
+
+        // Write per-image annotation files
+        for image in &data.images {
+            let lines = image_lines(image, &cat_names, &mut warnings, &mut annotations_exported);
+
+            let stem = Path::new(&image.filename)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown");
+            let txt_path = output_dir.join(format!("{}.txt", stem));
+            std::fs::write(&txt_path, lines.join("\n"))?;
+            files_created.push(txt_path);
+        }
+
+        log::info!(
+            "Exported {} images with {} annotations ({} warnings)",
+            data.images.len(),
+            annotations_exported,
+            warnings.len()
+        );
+
+        Ok(ExportResult {
+            images_exported: data.images.len(),
+            annotations_exported,
+            warnings,
+            files_created,
+        })
+    }
+
+    fn export_to_bytes(
+        &self,
+        data: &ProjectData,
+        _options: &ExportOptions,
+    ) -> Result<(Vec<u8>, ExportResult), FormatError> {
+        log::info!("Exporting DOTA annotations to ZIP");
+
+        let mut warnings = Vec::new();
+        let mut annotations_exported = 0;
+
+        // Create ZIP file in memory
+        let buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(buffer);
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(0o644);
+
+        let cat_names = category_names(data);
+
+        // Write per-image annotation files, preserving folder structure
+        for image in &data.images {
+            let lines = image_lines(image, &cat_names, &mut warnings, &mut annotations_exported);
+
+            // Compute relative path from project folder to preserve structure
+            let relative_path = if !data.folder.as_os_str().is_empty() {
+                image
+                    .path
+                    .strip_prefix(&data.folder)
+                    .ok()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|| image.path.clone())
+            } else {
+                image.path.clone()
+            };
+
+            // Replace image extension with .txt, keeping the folder structure
+            let txt_path = relative_path.with_extension("txt");
+            let txt_filename = txt_path
+                .to_str()
+                .unwrap_or("unknown.txt")
+                .replace('\\', "/"); // Normalize path separators for ZIP
+
+            zip.start_file(&txt_filename, options)
+                .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+            zip.write_all(lines.join("\n").as_bytes())?;
+        }
+
+        let buffer = zip
+            .finish()
+            .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+
+        log::info!(
+            "Exported {} images with {} annotations ({} warnings) to ZIP",
+            data.images.len(),
+            annotations_exported,
+            warnings.len()
+        );
+
+        Ok((
+            buffer.into_inner(),
+            ExportResult {
+                images_exported: data.images.len(),
+                annotations_exported,
+                warnings,
+                files_created: Vec::new(),
+            },
+        ))
+    }
+
+    fn import(&self, path: &Path, _options: &ImportOptions) -> Result<ProjectData, FormatError> {
+        log::info!("Importing DOTA annotations from {:?}", path);
+
+        let input_dir = path;
+        if !input_dir.is_dir() {
+            return Err(FormatError::invalid_format(
+                "DOTA import requires a directory path",
+            ));
+        }
+
+        let mut data = ProjectData::new();
+        data.folder = input_dir.to_path_buf();
+
+        let txt_files: Vec<_> = std::fs::read_dir(input_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+
+        // Categories are named inline; assign IDs in order of first appearance
+        let mut category_map: HashMap<String, u32> = HashMap::new();
+
+        for txt_path in txt_files {
+            let stem = txt_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+
+            let image_path = find_image_for_stem(input_dir, stem);
+            let mut entry = ImageEntry::new(image_path.clone());
+            entry.filename = image_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(stem)
+                .to_string();
+
+            let content = std::fs::read_to_string(&txt_path)?;
+            let mut ann_id = 0u32;
+            for line in content.lines() {
+                // Header lines ("imagesource:...", "gsd:...") don't parse as boxes
+                let Some((corners, name)) = parse_dota_line(line) else {
+                    continue;
+                };
+
+                let next_id = category_map.len() as u32;
+                let cat_id = *category_map.entry(name.to_string()).or_insert_with(|| {
+                    data.categories.push(CategoryEntry::new(next_id, name));
+                    next_id
+                });
+
+                entry.annotations.push(AnnotationEntry::new(
+                    ann_id,
+                    cat_id,
+                    ShapeEntry::from_shape(&AnnotationShape::rotated_box_from_corners(corners)),
+                ));
+                ann_id += 1;
+            }
+
+            data.images.push(entry);
+        }
+
+        data.metadata = ProjectMetadata::new();
+        data.metadata.extra.insert(
+            "imported_from".into(),
+            serde_json::Value::String("dota".into()),
+        );
+
+        log::info!(
+            "Imported {} images with {} annotations",
+            data.images.len(),
+            data.total_annotations()
+        );
+
+        Ok(data)
+    }
+}
+
+/// Map category IDs to DOTA category names (no whitespace allowed).
+fn category_names(data: &ProjectData) -> HashMap<u32, String> {
+    data.categories
+        .iter()
+        .map(|c| {
+            (
+                c.id,
+                c.name.split_whitespace().collect::<Vec<_>>().join("-"),
+            )
+        })
+        .collect()
+}
+
+/// Build the label lines for one image.
+fn image_lines(
+    image: &ImageEntry,
+    cat_names: &HashMap<u32, String>,
+    warnings: &mut Vec<FormatWarning>,
+    annotations_exported: &mut usize,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for ann in &image.annotations {
+        let Some(corners) = ann.shape.box_corners() else {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Skipped {} annotation (DOTA only supports boxes)",
+                    ann.shape.shape_type()
+                ))
+                .with_image(&image.path),
+            );
+            continue;
+        };
+
+        let Some(name) = cat_names.get(&ann.category_id) else {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Unknown category ID {}, skipping annotation",
+                    ann.category_id
+                ))
+                .with_image(&image.path),
+            );
+            continue;
+        };
+
+        let coords: Vec<String> = corners
+            .iter()
+            .flat_map(|(x, y)| [x, y])
+            // Shortest exact form, so coordinates survive repeated round trips
+            .map(|v| v.to_string())
+            .collect();
+        lines.push(format!("{} {} 0", coords.join(" "), name));
+        *annotations_exported += 1;
+    }
+    lines
+}
+
+/// Parse a single DOTA annotation line into its corners and category name.
+fn parse_dota_line(line: &str) -> Option<([(f32, f32); 4], &str)> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 9 {
+        return None;
+    }
+
+    let mut corners = [(0.0, 0.0); 4];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let x: f32 = parts[i * 2].parse().ok()?;
+        let y: f32 = parts[i * 2 + 1].parse().ok()?;
+        *corner = (x, y);
+    }
+
+    Some((corners, parts[8]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dota_line() {
+        let (corners, name) =
+            parse_dota_line("10.0 20.0 30.0 20.0 30.0 25.0 10.0 25.0 small-vehicle 0").unwrap();
+        assert_eq!(name, "small-vehicle");
+        assert_eq!(corners[2], (30.0, 25.0));
+
+        assert!(parse_dota_line("imagesource:GoogleEarth").is_none());
+        assert!(parse_dota_line("gsd:0.146").is_none());
+    }
+}
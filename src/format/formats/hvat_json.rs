@@ -20,7 +20,9 @@ use crate::format::traits::{AnnotationFormat, ExportOptions, ExportResult, Impor
 /// Native HVAT JSON format.
 ///
 /// This format provides full fidelity for all HVAT features:
-/// - All shape types (bounding box, point, polygon)
+/// - All shape types (bounding box, rotated box, point, polygon, multipolygon, polyline, mask,
+///   keypoints)
+/// - Category keypoint templates (names and skeleton)
 /// - Category colors and names
 /// - Per-image tags
 /// - Global tags
@@ -115,6 +117,17 @@ impl AnnotationFormat for HvatJsonFormat {
             });
         }
 
+        for image in &data.images {
+            for ann in &image.annotations {
+                ann.shape.validate().map_err(|e| {
+                    FormatError::invalid_format(format!(
+                        "Invalid annotation {} in '{}': {}",
+                        ann.id, image.filename, e
+                    ))
+                })?;
+            }
+        }
+
         if !ProjectData::is_version_compatible(&data.version) {
             log::warn!(
                 "Project version {} may not be fully compatible with current version {} \
@@ -138,7 +151,7 @@ impl AnnotationFormat for HvatJsonFormat {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::format::project::{CategoryEntry, ImageEntry};
+    use crate::format::project::{AnnotationEntry, CategoryEntry, ImageEntry, ShapeEntry};
     use std::path::PathBuf;
 
     fn create_test_project() -> ProjectData {
@@ -168,6 +181,32 @@ mod tests {
         assert_eq!(original.images.len(), loaded.images.len());
     }
 
+    #[test]
+    fn test_import_rejects_malformed_mask() {
+        let path =
+            std::env::temp_dir().join(format!("hvat_json_bad_mask_{}.json", std::process::id()));
+        let mut data = create_test_project();
+        // Declared box overflows u32 when multiplied out
+        data.images[0].add_annotation(AnnotationEntry::new(
+            1,
+            0,
+            ShapeEntry::Mask {
+                x: 0,
+                y: 0,
+                width: 65536,
+                height: 65536,
+                counts: vec![0, 1],
+                crowd: false,
+            },
+        ));
+        std::fs::write(&path, serde_json::to_string(&data).unwrap()).unwrap();
+
+        let result = HvatJsonFormat.import(&path, &ImportOptions::default());
+        std::fs::remove_file(&path).ok();
+        let err = result.expect_err("malformed mask should be rejected");
+        assert!(err.to_string().contains("annotation 1 in 'test.png'"));
+    }
+
     #[test]
     fn test_version_parsing() {
         assert_eq!(ProjectData::parse_version("0.1.0"), Some((0, 1, 0)));
@@ -0,0 +1,569 @@
+//! LabelMe JSON format implementation.
+//!
+//! Implements the LabelMe format, which uses one JSON file per image with a
+//! list of labeled shapes in pixel coordinates. Each shape has a
+//! `shape_type` (`rectangle`, `polygon`, `point`, `line`, `linestrip`, ...)
+//! and a list of `[x, y]` points.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use crate::format::error::FormatError;
+use crate::format::project::{
+    AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ProjectMetadata, ShapeEntry,
+};
+use crate::format::traits::{
+    AnnotationFormat, ExportOptions, ExportResult, FormatWarning, ImportOptions,
+};
+use crate::model::{DEFAULT_POLYLINE_WIDTH, KEYPOINT_NOT_LABELED, ring_holes, rotated_box_corners};
+use crate::sam2::extract_mask_contours;
+
+/// LabelMe version written to exported files.
+const LABELME_VERSION: &str = "5.4.1";
+
+/// Douglas-Peucker tolerance for mask outlines (pixels).
+const MASK_CONTOUR_EPSILON: f32 = 1.0;
+
+/// LabelMe JSON format.
+///
+/// Supports:
+/// - Bounding boxes (rectangle)
+/// - Polygons (polygon)
+/// - Multipolygons (one polygon per part, sharing a `group_id`)
+/// - Points (point)
+/// - Polylines (linestrip; line on import)
+/// - Per-image annotation files
+/// - Category names inline on each shape
+///
+/// Does not support:
+/// - Rotated boxes (exported as polygons with warning)
+/// - Masks (exported as polygon outlines with warning)
+/// - Polygon holes (dropped with warning)
+/// - Polyline line width (dropped with warning)
+/// - Keypoint names and visibility (labeled keypoints exported as grouped
+///   points with warning)
+/// - Circles and masks (skipped on import)
+/// - Per-image tags
+/// - Category colors
+pub struct LabelMeFormat;
+
+impl AnnotationFormat for LabelMeFormat {
+    fn id(&self) -> &'static str {
+        "labelme"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "LabelMe (JSON)"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["json"]
+    }
+
+    fn supports_polygon(&self) -> bool {
+        true
+    }
+
+    fn supports_point(&self) -> bool {
+        true
+    }
+
+    fn supports_per_image(&self) -> bool {
+        true
+    }
+
+    fn export(
+        &self,
+        data: &ProjectData,
+        path: &Path,
+        _options: &ExportOptions,
+    ) -> Result<ExportResult, FormatError> {
+        log::info!("Exporting LabelMe annotations to {:?}", path);
+
+        let output_dir = path;
+        std::fs::create_dir_all(output_dir)?;
+
+        let mut warnings = Vec::new();
+        let mut files_created = Vec::new();
+        let mut annotations_exported = 0;
+
+        let cat_names = category_names(data);
+
+        // Write per-image annotation files
+        for image in &data.images {
+            let file = image_file(image, &cat_names, &mut warnings, &mut annotations_exported);
+
+            let stem = Path::new(&image.filename)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown");
+            let json_path = output_dir.join(format!("{}.json", stem));
+            std::fs::write(&json_path, serde_json::to_string_pretty(&file)?)?;
+            files_created.push(json_path);
+        }
+
+        log::info!(
+            "Exported {} images with {} annotations ({} warnings)",
+            data.images.len(),
+            annotations_exported,
+            warnings.len()
+        );
+
+        Ok(ExportResult {
+            images_exported: data.images.len(),
+            annotations_exported,
+            warnings,
+            files_created,
+        })
+    }
+
+    fn export_to_bytes(
+        &self,
+        data: &ProjectData,
+        _options: &ExportOptions,
+    ) -> Result<(Vec<u8>, ExportResult), FormatError> {
+        log::info!("Exporting LabelMe annotations to ZIP");
+
+        let mut warnings = Vec::new();
+        let mut annotations_exported = 0;
+
+        // Create ZIP file in memory
+        let buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(buffer);
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(0o644);
+
+        let cat_names = category_names(data);
+
+        // Write per-image annotation files, preserving folder structure
+        for image in &data.images {
+            let file = image_file(image, &cat_names, &mut warnings, &mut annotations_exported);
+
+            // Compute relative path from project folder to preserve structure
+            let relative_path = if !data.folder.as_os_str().is_empty() {
+                image
+                    .path
+                    .strip_prefix(&data.folder)
+                    .ok()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|| image.path.clone())
+            } else {
+                image.path.clone()
+            };
+
+            // Replace image extension with .json, keeping the folder structure
+            let json_path = relative_path.with_extension("json");
+            let json_filename = json_path
+                .to_str()
+                .unwrap_or("unknown.json")
+                .replace('\\', "/"); // Normalize path separators for ZIP
+
+            zip.start_file(&json_filename, options)
+                .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+            zip.write_all(serde_json::to_string_pretty(&file)?.as_bytes())?;
+        }
+
+        let buffer = zip
+            .finish()
+            .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+
+        log::info!(
+            "Exported {} images with {} annotations ({} warnings) to ZIP",
+            data.images.len(),
+            annotations_exported,
+            warnings.len()
+        );
+
+        Ok((
+            buffer.into_inner(),
+            ExportResult {
+                images_exported: data.images.len(),
+                annotations_exported,
+                warnings,
+                files_created: Vec::new(),
+            },
+        ))
+    }
+
+    fn import(&self, path: &Path, _options: &ImportOptions) -> Result<ProjectData, FormatError> {
+        log::info!("Importing LabelMe annotations from {:?}", path);
+
+        let input_dir = path;
+        if !input_dir.is_dir() {
+            return Err(FormatError::invalid_format(
+                "LabelMe import requires a directory path",
+            ));
+        }
+
+        let mut data = ProjectData::new();
+        data.folder = input_dir.to_path_buf();
+
+        let json_files: Vec<_> = std::fs::read_dir(input_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+
+        // Categories are named inline; assign IDs in order of first appearance
+        let mut category_map: HashMap<String, u32> = HashMap::new();
+
+        for json_path in json_files {
+            let content = std::fs::read_to_string(&json_path)?;
+            // Other JSON files (e.g. a COCO export) may share the folder
+            let Ok(file) = serde_json::from_str::<LabelMeFile>(&content) else {
+                log::warn!("Skipping {:?}: not a LabelMe annotation file", json_path);
+                continue;
+            };
+
+            let image_path = input_dir.join(&file.image_path);
+            let mut entry = ImageEntry::new(image_path.clone());
+            entry.filename = image_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(&file.image_path)
+                .to_string();
+            if let (Some(width @ 1..), Some(height @ 1..)) = (file.image_width, file.image_height) {
+                entry = entry.with_dimensions(width, height);
+            }
+
+            let mut ann_id = 0u32;
+            // Polygons sharing a label and group ID are parts of one object
+            let mut groups: HashMap<(String, i64), usize> = HashMap::new();
+            for shape in &file.shapes {
+                let Some(shape_entry) = parse_labelme_shape(shape) else {
+                    log::warn!(
+                        "Skipping unsupported LabelMe {} shape '{}' in {:?}",
+                        shape.shape_type,
+                        shape.label,
+                        json_path
+                    );
+                    continue;
+                };
+
+                if let (ShapeEntry::Polygon { vertices }, Some(group_id)) =
+                    (&shape_entry, shape.group_id)
+                {
+                    let key = (shape.label.clone(), group_id);
+                    if let Some(&index) = groups.get(&key) {
+                        let merged = add_polygon_ring(&entry.annotations[index].shape, vertices);
+                        entry.annotations[index].shape = merged;
+                        continue;
+                    }
+                    groups.insert(key, entry.annotations.len());
+                }
+
+                let next_id = category_map.len() as u32;
+                let cat_id = *category_map.entry(shape.label.clone()).or_insert_with(|| {
+                    data.categories
+                        .push(CategoryEntry::new(next_id, &shape.label));
+                    next_id
+                });
+
+                entry
+                    .annotations
+                    .push(AnnotationEntry::new(ann_id, cat_id, shape_entry));
+                ann_id += 1;
+            }
+
+            data.images.push(entry);
+        }
+
+        data.metadata = ProjectMetadata::new();
+        data.metadata.extra.insert(
+            "imported_from".into(),
+            serde_json::Value::String("labelme".into()),
+        );
+
+        log::info!(
+            "Imported {} images with {} annotations",
+            data.images.len(),
+            data.total_annotations()
+        );
+
+        Ok(data)
+    }
+}
+
+/// Map category IDs to category names.
+fn category_names(data: &ProjectData) -> HashMap<u32, String> {
+    data.categories
+        .iter()
+        .map(|c| (c.id, c.name.clone()))
+        .collect()
+}
+
+/// Build the LabelMe file for one image.
+fn image_file(
+    image: &ImageEntry,
+    cat_names: &HashMap<u32, String>,
+    warnings: &mut Vec<FormatWarning>,
+    annotations_exported: &mut usize,
+) -> LabelMeFile {
+    let mut shapes = Vec::new();
+    for ann in &image.annotations {
+        let Some(label) = cat_names.get(&ann.category_id) else {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Unknown category ID {}, skipping annotation",
+                    ann.category_id
+                ))
+                .with_image(&image.path),
+            );
+            continue;
+        };
+
+        let (shape_type, parts): (&str, Vec<Vec<[f32; 2]>>) = match &ann.shape {
+            ShapeEntry::BoundingBox {
+                x,
+                y,
+                width,
+                height,
+            } => ("rectangle", vec![vec![[*x, *y], [x + width, y + height]]]),
+            ShapeEntry::RotatedBox {
+                cx,
+                cy,
+                width,
+                height,
+                angle,
+            } => {
+                warnings.push(
+                    FormatWarning::warning(
+                        "Exported rotated box as a polygon (LabelMe does not support rotation)",
+                    )
+                    .with_image(&image.path),
+                );
+                let corners = rotated_box_corners(*cx, *cy, *width, *height, *angle);
+                (
+                    "polygon",
+                    vec![corners.iter().map(|(x, y)| [*x, *y]).collect()],
+                )
+            }
+            ShapeEntry::Point { x, y } => ("point", vec![vec![[*x, *y]]]),
+            ShapeEntry::Polygon { vertices } => (
+                "polygon",
+                vec![vertices.iter().map(|(x, y)| [*x, *y]).collect()],
+            ),
+            ShapeEntry::MultiPolygon { rings } => {
+                ("polygon", polygon_parts(rings, &image.path, warnings))
+            }
+            ShapeEntry::Polyline { vertices, width } => {
+                warnings.push(
+                    FormatWarning::warning(format!(
+                        "Dropped polyline line width {:.1} (LabelMe has no line width)",
+                        width
+                    ))
+                    .with_image(&image.path),
+                );
+                (
+                    "linestrip",
+                    vec![vertices.iter().map(|(x, y)| [*x, *y]).collect()],
+                )
+            }
+            ShapeEntry::Mask { .. } => {
+                let mask = ann.shape.rle_mask().unwrap_or_default();
+                let rings = extract_mask_contours(&mask, MASK_CONTOUR_EPSILON);
+                if rings.is_empty() {
+                    warnings.push(
+                        FormatWarning::warning("Skipped mask annotation with no outline")
+                            .with_image(&image.path),
+                    );
+                    continue;
+                }
+                warnings.push(
+                    FormatWarning::warning(
+                        "Exported mask as a polygon outline (LabelMe masks are not supported)",
+                    )
+                    .with_image(&image.path),
+                );
+                ("polygon", polygon_parts(&rings, &image.path, warnings))
+            }
+            ShapeEntry::Keypoints { keypoints, .. } => {
+                let points: Vec<Vec<[f32; 2]>> = keypoints
+                    .iter()
+                    .filter(|(_, _, v)| *v != KEYPOINT_NOT_LABELED)
+                    .map(|(x, y, _)| vec![[*x, *y]])
+                    .collect();
+                if points.is_empty() {
+                    warnings.push(
+                        FormatWarning::warning(
+                            "Skipped keypoints annotation with no labeled keypoints",
+                        )
+                        .with_image(&image.path),
+                    );
+                    continue;
+                }
+                warnings.push(
+                    FormatWarning::warning(
+                        "Exported keypoints as grouped points (LabelMe has no keypoint names or visibility)",
+                    )
+                    .with_image(&image.path),
+                );
+                ("point", points)
+            }
+        };
+
+        // Parts of one object share a group ID, as LabelMe does for occluded objects
+        let group_id = (parts.len() > 1).then_some(ann.id as i64);
+        for points in parts {
+            shapes.push(LabelMeShape {
+                label: label.clone(),
+                points,
+                group_id,
+                description: Some(String::new()),
+                shape_type: shape_type.to_string(),
+                flags: serde_json::Value::Object(Default::default()),
+            });
+        }
+        *annotations_exported += 1;
+    }
+
+    let (width, height) = image.dimensions.unzip();
+    LabelMeFile {
+        version: LABELME_VERSION.to_string(),
+        flags: serde_json::Value::Object(Default::default()),
+        shapes,
+        image_path: image.filename.clone(),
+        image_data: None,
+        image_height: height,
+        image_width: width,
+    }
+}
+
+/// Outer rings of an even-odd polygon as LabelMe point lists. LabelMe
+/// polygons can't have holes, so those are dropped with a warning.
+fn polygon_parts(
+    rings: &[Vec<(f32, f32)>],
+    image_path: &Path,
+    warnings: &mut Vec<FormatWarning>,
+) -> Vec<Vec<[f32; 2]>> {
+    let holes = ring_holes(rings);
+    let dropped = holes.iter().filter(|hole| **hole).count();
+    if dropped > 0 {
+        warnings.push(
+            FormatWarning::warning(format!(
+                "Dropped {} polygon hole(s) (LabelMe polygons cannot have holes)",
+                dropped
+            ))
+            .with_image(image_path),
+        );
+    }
+    rings
+        .iter()
+        .zip(holes)
+        .filter(|(_, hole)| !hole)
+        .map(|(ring, _)| ring.iter().map(|(x, y)| [*x, *y]).collect())
+        .collect()
+}
+
+/// Add a ring to a polygon or multipolygon entry. Other shapes are
+/// returned unchanged.
+fn add_polygon_ring(shape: &ShapeEntry, ring: &[(f32, f32)]) -> ShapeEntry {
+    let mut rings = match shape {
+        ShapeEntry::Polygon { vertices } => vec![vertices.clone()],
+        ShapeEntry::MultiPolygon { rings } => rings.clone(),
+        other => return other.clone(),
+    };
+    rings.push(ring.to_vec());
+    ShapeEntry::MultiPolygon { rings }
+}
+
+/// Convert a LabelMe shape to a shape entry, or None if it isn't supported.
+fn parse_labelme_shape(shape: &LabelMeShape) -> Option<ShapeEntry> {
+    let vertices: Vec<(f32, f32)> = shape.points.iter().map(|[x, y]| (*x, *y)).collect();
+    match (shape.shape_type.as_str(), vertices.as_slice()) {
+        ("rectangle", [(x1, y1), (x2, y2)]) => Some(ShapeEntry::BoundingBox {
+            x: x1.min(*x2),
+            y: y1.min(*y2),
+            width: (x2 - x1).abs(),
+            height: (y2 - y1).abs(),
+        }),
+        ("polygon", _) if vertices.len() >= 3 => Some(ShapeEntry::Polygon { vertices }),
+        ("point", [(x, y)]) => Some(ShapeEntry::Point { x: *x, y: *y }),
+        ("line" | "linestrip", _) if vertices.len() >= 2 => Some(ShapeEntry::Polyline {
+            vertices,
+            width: DEFAULT_POLYLINE_WIDTH,
+        }),
+        _ => None,
+    }
+}
+
+// LabelMe format structures
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelMeFile {
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    flags: serde_json::Value,
+    shapes: Vec<LabelMeShape>,
+    image_path: String,
+    #[serde(default)]
+    image_data: Option<String>,
+    #[serde(default)]
+    image_height: Option<u32>,
+    #[serde(default)]
+    image_width: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LabelMeShape {
+    label: String,
+    points: Vec<[f32; 2]>,
+    #[serde(default)]
+    group_id: Option<i64>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default = "default_shape_type")]
+    shape_type: String,
+    #[serde(default)]
+    flags: serde_json::Value,
+}
+
+/// LabelMe treats shapes without a type as polygons.
+fn default_shape_type() -> String {
+    "polygon".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(shape_type: &str, points: Vec<[f32; 2]>) -> LabelMeShape {
+        LabelMeShape {
+            label: "crack".into(),
+            points,
+            group_id: None,
+            description: None,
+            shape_type: shape_type.into(),
+            flags: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_parse_labelme_shape() {
+        // Rectangles may be drawn from any corner
+        let rect = parse_labelme_shape(&shape("rectangle", vec![[30.0, 40.0], [10.0, 20.0]]));
+        assert!(matches!(
+            rect,
+            Some(ShapeEntry::BoundingBox {
+                x: 10.0,
+                y: 20.0,
+                width: 20.0,
+                height: 20.0
+            })
+        ));
+
+        let line = parse_labelme_shape(&shape("line", vec![[0.0, 0.0], [5.0, 5.0]]));
+        assert!(line.is_some_and(|s| s.is_polyline()));
+
+        assert!(parse_labelme_shape(&shape("polygon", vec![[0.0, 0.0], [5.0, 5.0]])).is_none());
+        assert!(parse_labelme_shape(&shape("circle", vec![[0.0, 0.0], [5.0, 5.0]])).is_none());
+    }
+}
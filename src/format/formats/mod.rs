@@ -1,14 +1,20 @@
 //! Annotation format implementations.
 
 mod coco;
+mod dota;
 mod hvat_json;
+mod labelme;
 mod pascal_voc;
 mod yolo;
+mod yolo_obb;
 
 #[cfg(test)]
 mod tests;
 
 pub use coco::CocoFormat;
+pub use dota::DotaFormat;
 pub use hvat_json::HvatJsonFormat;
+pub use labelme::LabelMeFormat;
 pub use pascal_voc::PascalVocFormat;
 pub use yolo::YoloFormat;
+pub use yolo_obb::YoloObbFormat;
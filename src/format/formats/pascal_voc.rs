@@ -23,6 +23,8 @@ use crate::format::traits::{
 ///
 /// Supports:
 /// - Bounding boxes only
+/// - Rotated boxes (exported as their enclosing axis-aligned box)
+/// - Masks and keypoints (exported as their bounding box)
 /// - Per-image annotation files
 /// - Object names and bndbox coordinates
 ///
@@ -347,62 +349,76 @@ impl PascalVocFormat {
 
         // <object> elements
         for ann in annotations {
-            match &ann.shape {
+            let (x, y, w, h) = match &ann.shape {
                 ShapeEntry::BoundingBox {
                     x,
                     y,
-                    width: w,
-                    height: h,
-                } => {
-                    let name = cat_names
-                        .get(&ann.category_id)
-                        .copied()
-                        .unwrap_or("unknown");
-
-                    writer
-                        .write_event(Event::Start(BytesStart::new("object")))
-                        .map_err(|e| FormatError::Xml(e.into()))?;
-
-                    self.write_text_element(&mut writer, "name", name)?;
-                    self.write_text_element(&mut writer, "pose", "Unspecified")?;
-                    self.write_text_element(&mut writer, "truncated", "0")?;
-                    self.write_text_element(&mut writer, "difficult", "0")?;
-
-                    // <bndbox>
-                    writer
-                        .write_event(Event::Start(BytesStart::new("bndbox")))
-                        .map_err(|e| FormatError::Xml(e.into()))?;
-                    self.write_text_element(&mut writer, "xmin", &(*x as u32).to_string())?;
-                    self.write_text_element(&mut writer, "ymin", &(*y as u32).to_string())?;
-                    self.write_text_element(&mut writer, "xmax", &((x + w) as u32).to_string())?;
-                    self.write_text_element(&mut writer, "ymax", &((y + h) as u32).to_string())?;
-                    writer
-                        .write_event(Event::End(BytesEnd::new("bndbox")))
-                        .map_err(|e| FormatError::Xml(e.into()))?;
-
-                    writer
-                        .write_event(Event::End(BytesEnd::new("object")))
-                        .map_err(|e| FormatError::Xml(e.into()))?;
-
-                    *annotations_exported += 1;
-                }
-                ShapeEntry::Point { .. } => {
+                    width,
+                    height,
+                } => (*x, *y, *width, *height),
+                ShapeEntry::RotatedBox { .. } => {
                     warnings.push(
                         FormatWarning::warning(
-                            "Skipped point annotation (Pascal VOC only supports bounding boxes)",
+                            "Exported rotated box as its enclosing axis-aligned box \
+                             (Pascal VOC does not support rotation)",
                         )
                         .with_image(image_path),
                     );
+                    ann.shape.enclosing_box()
                 }
-                ShapeEntry::Polygon { .. } => {
+                ShapeEntry::Mask { .. } | ShapeEntry::Keypoints { .. } => {
                     warnings.push(
-                        FormatWarning::warning(
-                            "Skipped polygon annotation (Pascal VOC only supports bounding boxes)",
-                        )
+                        FormatWarning::warning(format!(
+                            "Exported {} annotation as its bounding box",
+                            ann.shape.shape_type()
+                        ))
                         .with_image(image_path),
                     );
+                    ann.shape.enclosing_box()
                 }
-            }
+                other => {
+                    warnings.push(
+                        FormatWarning::warning(format!(
+                            "Skipped {} annotation (Pascal VOC only supports bounding boxes)",
+                            other.shape_type()
+                        ))
+                        .with_image(image_path),
+                    );
+                    continue;
+                }
+            };
+
+            let name = cat_names
+                .get(&ann.category_id)
+                .copied()
+                .unwrap_or("unknown");
+
+            writer
+                .write_event(Event::Start(BytesStart::new("object")))
+                .map_err(|e| FormatError::Xml(e.into()))?;
+
+            self.write_text_element(&mut writer, "name", name)?;
+            self.write_text_element(&mut writer, "pose", "Unspecified")?;
+            self.write_text_element(&mut writer, "truncated", "0")?;
+            self.write_text_element(&mut writer, "difficult", "0")?;
+
+            // <bndbox>
+            writer
+                .write_event(Event::Start(BytesStart::new("bndbox")))
+                .map_err(|e| FormatError::Xml(e.into()))?;
+            self.write_text_element(&mut writer, "xmin", &(x as u32).to_string())?;
+            self.write_text_element(&mut writer, "ymin", &(y as u32).to_string())?;
+            self.write_text_element(&mut writer, "xmax", &((x + w) as u32).to_string())?;
+            self.write_text_element(&mut writer, "ymax", &((y + h) as u32).to_string())?;
+            writer
+                .write_event(Event::End(BytesEnd::new("bndbox")))
+                .map_err(|e| FormatError::Xml(e.into()))?;
+
+            writer
+                .write_event(Event::End(BytesEnd::new("object")))
+                .map_err(|e| FormatError::Xml(e.into()))?;
+
+            *annotations_exported += 1;
         }
 
         // </annotation>
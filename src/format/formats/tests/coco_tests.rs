@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use crate::format::formats::CocoFormat;
 use crate::format::project::{AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ShapeEntry};
-use crate::format::traits::AnnotationFormat;
+use crate::format::traits::{AnnotationFormat, ExportOptions, ImportOptions};
 
 /// Create a test project with COCO-compatible data.
 fn create_coco_project() -> ProjectData {
@@ -242,3 +242,234 @@ fn test_coco_dimensions_required() {
     image = image.with_dimensions(1920, 1080);
     assert_eq!(image.dimensions, Some((1920, 1080)));
 }
+
+#[test]
+fn test_coco_polyline_roundtrip() {
+    let path = std::env::temp_dir().join(format!("hvat_coco_polyline_{}.json", std::process::id()));
+    let format = CocoFormat;
+    let mut data = create_coco_project();
+    data.images[0].annotations.push(AnnotationEntry::new(
+        3,
+        1,
+        ShapeEntry::Polyline {
+            vertices: vec![(10.0, 10.0), (20.0, 10.0)],
+            width: 2.0,
+        },
+    ));
+
+    let result = format
+        .export(&data, &path, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.annotations_exported, 3);
+    assert_eq!(result.warnings.len(), 1, "COCO has no line type");
+
+    let imported = format
+        .import(&path, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_file(&path).ok();
+
+    // Two-vertex paths would otherwise be read back as bounding boxes
+    match &imported.images[0].annotations[2].shape {
+        ShapeEntry::Polyline { vertices, width } => {
+            assert_eq!(vertices, &vec![(10.0, 10.0), (20.0, 10.0)]);
+            assert_eq!(*width, 2.0);
+        }
+        other => panic!("Expected polyline, got {}", other.shape_type()),
+    }
+}
+
+#[test]
+fn test_coco_mask_roundtrip() {
+    let path = std::env::temp_dir().join(format!("hvat_coco_mask_{}.json", std::process::id()));
+    let format = CocoFormat;
+    let mut data = create_coco_project();
+    // 2x2 block at (10, 20) plus a stray pixel below it
+    data.images[0].annotations.push(AnnotationEntry::new(
+        3,
+        1,
+        ShapeEntry::Mask {
+            x: 10,
+            y: 20,
+            width: 2,
+            height: 3,
+            counts: vec![0, 4, 1, 1],
+            crowd: true,
+        },
+    ));
+
+    let result = format
+        .export(&data, &path, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.annotations_exported, 3);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let exported = &json["annotations"][2];
+    assert_eq!(exported["iscrowd"], 1);
+    assert_eq!(exported["area"], 5.0);
+    assert!(exported["segmentation"]["counts"].is_string());
+
+    let imported = format
+        .import(&path, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_file(&path).ok();
+
+    match &imported.images[0].annotations[2].shape {
+        ShapeEntry::Mask {
+            x,
+            y,
+            width,
+            height,
+            counts,
+            crowd,
+        } => {
+            assert_eq!((*x, *y, *width, *height), (10, 20, 2, 3));
+            assert_eq!(counts, &vec![0, 4, 1, 1]);
+            assert!(*crowd);
+        }
+        other => panic!("Expected mask, got {}", other.shape_type()),
+    }
+}
+
+#[test]
+fn test_coco_import_rejects_oversized_mask() {
+    let path = std::env::temp_dir().join(format!("hvat_coco_big_mask_{}.json", std::process::id()));
+    let json = serde_json::json!({
+        "info": {},
+        "images": [{"id": 1, "file_name": "image1.jpg", "width": 800, "height": 600}],
+        "annotations": [{
+            "id": 7,
+            "image_id": 1,
+            "category_id": 1,
+            "segmentation": {"size": [65536, 65536], "counts": [0, 1]},
+            "area": 1.0,
+            "iscrowd": 1
+        }],
+        "categories": [{"id": 1, "name": "person", "supercategory": "human"}]
+    });
+    std::fs::write(&path, json.to_string()).unwrap();
+
+    let result = CocoFormat.import(&path, &ImportOptions::default());
+    std::fs::remove_file(&path).ok();
+    let err = result.expect_err("oversized mask should be rejected");
+    assert!(err.to_string().contains("annotation 7"));
+}
+
+#[test]
+fn test_coco_multipolygon_roundtrip() {
+    let path = std::env::temp_dir().join(format!("hvat_coco_multi_{}.json", std::process::id()));
+    let format = CocoFormat;
+    let mut data = create_coco_project();
+    // 10x10 square with a 4x4 hole, plus a separate triangle
+    data.images[0].annotations.push(AnnotationEntry::new(
+        3,
+        1,
+        ShapeEntry::MultiPolygon {
+            rings: vec![
+                vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)],
+                vec![(3.0, 3.0), (7.0, 3.0), (7.0, 7.0), (3.0, 7.0)],
+                vec![(20.0, 0.0), (30.0, 0.0), (25.0, 10.0)],
+            ],
+        },
+    ));
+
+    let result = format
+        .export(&data, &path, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.warnings.len(), 1, "Holes can't be marked in COCO");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let exported = &json["annotations"][2];
+    assert_eq!(exported["segmentation"].as_array().unwrap().len(), 3);
+    // Square minus hole plus triangle
+    assert_eq!(exported["area"], 100.0 - 16.0 + 50.0);
+    assert_eq!(exported["bbox"], serde_json::json!([0.0, 0.0, 30.0, 10.0]));
+
+    let imported = format
+        .import(&path, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_file(&path).ok();
+
+    match &imported.images[0].annotations[2].shape {
+        ShapeEntry::MultiPolygon { rings } => {
+            assert_eq!(rings.len(), 3);
+            assert_eq!(rings[1][0], (3.0, 3.0));
+        }
+        other => panic!("Expected multipolygon, got {}", other.shape_type()),
+    }
+}
+
+#[test]
+fn test_coco_keypoints_roundtrip() {
+    let path =
+        std::env::temp_dir().join(format!("hvat_coco_keypoints_{}.json", std::process::id()));
+    let format = CocoFormat;
+    let mut data = create_coco_project();
+    data.categories[0] = CategoryEntry::new(1, "person")
+        .with_supercategory("human")
+        .with_keypoints(
+            vec!["nose".into(), "left_eye".into(), "right_eye".into()],
+            vec![(0, 1), (0, 2)],
+        );
+    // Visible nose, skipped left eye, occluded right eye
+    data.images[0].annotations.push(AnnotationEntry::new(
+        3,
+        1,
+        ShapeEntry::Keypoints {
+            keypoints: vec![(50.0, 60.0, 2), (0.0, 0.0, 0), (40.0, 55.0, 1)],
+            x: 30.0,
+            y: 45.0,
+            width: 30.0,
+            height: 25.0,
+        },
+    ));
+
+    let result = format
+        .export(&data, &path, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.annotations_exported, 3);
+    assert!(result.warnings.is_empty());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let category = &json["categories"][0];
+    assert_eq!(
+        category["keypoints"],
+        serde_json::json!(["nose", "left_eye", "right_eye"])
+    );
+    assert_eq!(category["skeleton"], serde_json::json!([[1, 2], [1, 3]]));
+    let exported = &json["annotations"][2];
+    assert_eq!(
+        exported["keypoints"],
+        serde_json::json!([50.0, 60.0, 2.0, 0.0, 0.0, 0.0, 40.0, 55.0, 1.0])
+    );
+    assert_eq!(exported["num_keypoints"], 2);
+    assert_eq!(
+        exported["bbox"],
+        serde_json::json!([30.0, 45.0, 30.0, 25.0])
+    );
+
+    let imported = format
+        .import(&path, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(imported.categories[0].keypoints.len(), 3);
+    assert_eq!(imported.categories[0].skeleton, vec![(0, 1), (0, 2)]);
+    match &imported.images[0].annotations[2].shape {
+        ShapeEntry::Keypoints {
+            keypoints,
+            x,
+            y,
+            width,
+            height,
+        } => {
+            assert_eq!(keypoints[0], (50.0, 60.0, 2));
+            assert_eq!(keypoints[1].2, 0);
+            assert_eq!(keypoints[2], (40.0, 55.0, 1));
+            assert_eq!((*x, *y, *width, *height), (30.0, 45.0, 30.0, 25.0));
+        }
+        other => panic!("Expected keypoints, got {}", other.shape_type()),
+    }
+}
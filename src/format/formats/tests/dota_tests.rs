@@ -0,0 +1,88 @@
+//! Tests for the DOTA format.
+
+use std::path::PathBuf;
+
+use crate::format::formats::DotaFormat;
+use crate::format::project::{AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ShapeEntry};
+use crate::format::traits::{AnnotationFormat, ExportOptions, ImportOptions};
+
+/// Create a test project with a rotated box and a point.
+fn create_dota_project() -> ProjectData {
+    let mut data = ProjectData::new();
+    data.categories.push(CategoryEntry::new(7, "small vehicle"));
+
+    let mut image = ImageEntry::new(PathBuf::from("P0001.png")).with_dimensions(1024, 1024);
+    image.annotations.push(AnnotationEntry::new(
+        1,
+        7,
+        ShapeEntry::RotatedBox {
+            cx: 500.0,
+            cy: 300.0,
+            width: 40.0,
+            height: 20.0,
+            angle: 0.3,
+        },
+    ));
+    image.annotations.push(AnnotationEntry::new(
+        2,
+        7,
+        ShapeEntry::Point { x: 5.0, y: 5.0 },
+    ));
+
+    data.images.push(image);
+    data
+}
+
+#[test]
+fn test_dota_format_metadata() {
+    let format = DotaFormat;
+
+    assert_eq!(format.id(), "dota");
+    assert_eq!(format.display_name(), "DOTA (TXT)");
+    assert!(format.extensions().contains(&"txt"));
+    assert!(!format.supports_polygon(), "DOTA only supports boxes");
+    assert!(!format.supports_point(), "DOTA only supports boxes");
+    assert!(format.supports_per_image(), "DOTA uses per-image files");
+}
+
+#[test]
+fn test_dota_export_import_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("hvat_dota_test_{}", std::process::id()));
+    let format = DotaFormat;
+    let data = create_dota_project();
+
+    // Clean up before asserting so a failing run leaves no files behind
+    let exported = format.export(&data, &dir, &ExportOptions::default());
+    let labels = std::fs::read_to_string(dir.join("P0001.txt"));
+    let imported = format.import(&dir, &ImportOptions::default());
+    std::fs::remove_dir_all(&dir).ok();
+
+    let result = exported.expect("Failed to export");
+    assert_eq!(result.annotations_exported, 1);
+    assert_eq!(result.warnings.len(), 1, "Point should be skipped");
+
+    // Category names can't contain whitespace in DOTA
+    let labels = labels.expect("Missing labels");
+    assert!(labels.trim_end().ends_with("small-vehicle 0"));
+
+    let imported = imported.expect("Failed to import");
+    assert_eq!(imported.categories.len(), 1);
+    assert_eq!(imported.categories[0].name, "small-vehicle");
+    let shape = &imported.images[0].annotations[0].shape;
+    match shape {
+        ShapeEntry::RotatedBox {
+            cx,
+            cy,
+            width,
+            height,
+            angle,
+        } => {
+            assert!((cx - 500.0).abs() < 0.1);
+            assert!((cy - 300.0).abs() < 0.1);
+            assert!((width - 40.0).abs() < 0.01);
+            assert!((height - 20.0).abs() < 0.01);
+            assert!((angle - 0.3).abs() < 0.01);
+        }
+        _ => panic!("Expected rotated box"),
+    }
+}
@@ -2,8 +2,10 @@
 
 use std::path::PathBuf;
 
+use crate::data::{BandExclusion, BandInfo, PreprocessStep};
 use crate::format::project::{
-    AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ProjectMetadata, ShapeEntry, TagEntry,
+    AnnotationEntry, CategoryEntry, CompositeBand, CompositeEntry, ImageEntry, ProjectData,
+    ProjectMetadata, ShapeEntry, TagEntry,
 };
 
 /// Create a minimal test project with basic data.
@@ -207,6 +209,29 @@ fn test_shape_entry_bbox() {
     assert!(json.contains("\"type\":\"bbox\""));
 }
 
+#[test]
+fn test_shape_entry_rotated_box() {
+    let shape = ShapeEntry::RotatedBox {
+        cx: 50.0,
+        cy: 40.0,
+        width: 30.0,
+        height: 10.0,
+        angle: 0.5,
+    };
+
+    assert!(shape.is_rotated_box());
+    assert!(!shape.is_bbox());
+    assert_eq!(shape.shape_type(), "rotated_box");
+
+    let json = serde_json::to_string(&shape).expect("Failed to serialize");
+    assert!(json.contains("\"type\":\"rotated_box\""));
+    let loaded: ShapeEntry = serde_json::from_str(&json).expect("Failed to deserialize");
+    assert_eq!(
+        ShapeEntry::from_shape(&loaded.to_shape()).shape_type(),
+        "rotated_box"
+    );
+}
+
 #[test]
 fn test_shape_entry_point() {
     let shape = ShapeEntry::Point { x: 100.0, y: 200.0 };
@@ -235,6 +260,71 @@ fn test_shape_entry_polygon() {
     assert!(json.contains("\"type\":\"polygon\""));
 }
 
+#[test]
+fn test_shape_entry_polyline() {
+    let shape = ShapeEntry::Polyline {
+        vertices: vec![(0.0, 0.0), (50.0, 10.0), (100.0, 0.0)],
+        width: 4.5,
+    };
+
+    assert!(shape.is_polyline());
+    assert!(!shape.is_polygon());
+    assert_eq!(shape.shape_type(), "polyline");
+
+    let json = serde_json::to_string(&shape).expect("Failed to serialize");
+    assert!(json.contains("\"type\":\"polyline\""));
+    let loaded: ShapeEntry = serde_json::from_str(&json).expect("Failed to deserialize");
+    assert!(matches!(loaded, ShapeEntry::Polyline { width: 4.5, .. }));
+
+    // Width is optional in hand-written files
+    let loaded: ShapeEntry =
+        serde_json::from_str(r#"{"type":"polyline","vertices":[[0.0,0.0],[1.0,1.0]]}"#)
+            .expect("Failed to deserialize");
+    assert!(matches!(loaded, ShapeEntry::Polyline { width, .. } if width > 0.0));
+}
+
+#[test]
+fn test_shape_entry_multipolygon() {
+    let shape = ShapeEntry::MultiPolygon {
+        rings: vec![
+            vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)],
+            vec![(3.0, 3.0), (7.0, 3.0), (7.0, 7.0)],
+        ],
+    };
+
+    assert!(shape.is_multipolygon());
+    assert!(!shape.is_polygon());
+    assert_eq!(shape.shape_type(), "multipolygon");
+
+    let json = serde_json::to_string(&shape).expect("Failed to serialize");
+    assert!(json.contains("\"type\":\"multipolygon\""));
+    let loaded: ShapeEntry = serde_json::from_str(&json).expect("Failed to deserialize");
+    assert!(matches!(loaded, ShapeEntry::MultiPolygon { ref rings } if rings.len() == 2));
+}
+
+#[test]
+fn test_shape_entry_mask() {
+    let shape = ShapeEntry::Mask {
+        x: 4,
+        y: 8,
+        width: 3,
+        height: 2,
+        counts: vec![1, 4, 1],
+        crowd: false,
+    };
+
+    assert!(shape.is_mask());
+    assert_eq!(shape.shape_type(), "mask");
+    assert_eq!(shape.rle_mask().map(|m| m.area()), Some(4));
+
+    let json = serde_json::to_string(&shape).expect("Failed to serialize");
+    assert!(json.contains("\"type\":\"mask\""));
+    // Crowd is only written when set
+    assert!(!json.contains("crowd"));
+    let loaded: ShapeEntry = serde_json::from_str(&json).expect("Failed to deserialize");
+    assert!(matches!(loaded, ShapeEntry::Mask { crowd: false, .. }));
+}
+
 #[test]
 fn test_category_entry_builders() {
     let cat = CategoryEntry::new(1, "test")
@@ -322,3 +412,66 @@ fn test_annotation_entry_conversion() {
     assert_eq!(entry.id, entry2.id);
     assert_eq!(entry.category_id, entry2.category_id);
 }
+
+#[test]
+fn test_band_metadata_roundtrip() {
+    let bands = vec![
+        BandInfo {
+            wavelength: Some(450.0),
+            fwhm: Some(10.0),
+            ..BandInfo::named("Band 1")
+        },
+        BandInfo {
+            wavelength: Some(1400.0),
+            bad: true,
+            ..BandInfo::named("Band 2")
+        },
+    ];
+
+    let mut data = create_minimal_project();
+    data.images[0].bands = bands.clone();
+    data.metadata.composite = Some(CompositeEntry {
+        red: CompositeBand::from_band(1, &bands),
+        green: CompositeBand::from_band(0, &bands),
+        blue: CompositeBand::from_band(5, &bands),
+    });
+
+    let json = serde_json::to_string(&data).expect("Failed to serialize");
+    let loaded: ProjectData = serde_json::from_str(&json).expect("Failed to deserialize");
+
+    assert_eq!(loaded.images[0].bands, bands);
+    let composite = loaded.metadata.composite.expect("composite missing");
+    assert_eq!(composite.red.wavelength, Some(1400.0));
+    assert_eq!(composite.green.name, "Band 1");
+    assert_eq!(composite.blue.band, 5);
+    assert_eq!(composite.blue.wavelength, None);
+
+    // Images without band metadata don't serialize the field
+    let json = serde_json::to_string(&create_minimal_project()).unwrap();
+    assert!(!json.contains("\"bands\""));
+}
+
+#[test]
+fn test_preprocessing_roundtrip() {
+    let mut data = create_minimal_project();
+    data.metadata.preprocessing.steps = vec![
+        PreprocessStep::ExcludeBands(BandExclusion {
+            wavelengths: vec![(1350.0, 1450.0)],
+            flagged: true,
+            ..Default::default()
+        }),
+        PreprocessStep::SavitzkyGolay {
+            window: 7,
+            order: 2,
+        },
+        PreprocessStep::Derivative { order: 1 },
+    ];
+
+    let json = serde_json::to_string(&data).expect("Failed to serialize");
+    let loaded: ProjectData = serde_json::from_str(&json).expect("Failed to deserialize");
+    assert_eq!(loaded.metadata.preprocessing, data.metadata.preprocessing);
+
+    // Projects without preprocessing don't serialize the field
+    let json = serde_json::to_string(&create_minimal_project()).unwrap();
+    assert!(!json.contains("\"preprocessing\""));
+}
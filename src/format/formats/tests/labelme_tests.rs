@@ -0,0 +1,141 @@
+//! Tests for the LabelMe format.
+
+use std::path::PathBuf;
+
+use crate::format::formats::LabelMeFormat;
+use crate::format::project::{AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ShapeEntry};
+use crate::format::traits::{AnnotationFormat, ExportOptions, ImportOptions};
+
+/// Create a test project with a polyline, a box and a rotated box.
+fn create_labelme_project() -> ProjectData {
+    let mut data = ProjectData::new();
+    data.categories.push(CategoryEntry::new(3, "crack"));
+    data.categories.push(CategoryEntry::new(4, "defect"));
+
+    let mut image = ImageEntry::new(PathBuf::from("wall.png")).with_dimensions(640, 480);
+    image.annotations.push(AnnotationEntry::new(
+        1,
+        3,
+        ShapeEntry::Polyline {
+            vertices: vec![(10.0, 10.0), (40.0, 25.0), (80.0, 20.0)],
+            width: 6.0,
+        },
+    ));
+    image.annotations.push(AnnotationEntry::new(
+        2,
+        4,
+        ShapeEntry::BoundingBox {
+            x: 100.0,
+            y: 50.0,
+            width: 30.0,
+            height: 20.0,
+        },
+    ));
+    image.annotations.push(AnnotationEntry::new(
+        3,
+        4,
+        ShapeEntry::RotatedBox {
+            cx: 300.0,
+            cy: 200.0,
+            width: 40.0,
+            height: 10.0,
+            angle: 0.4,
+        },
+    ));
+
+    data.images.push(image);
+    data
+}
+
+#[test]
+fn test_labelme_format_metadata() {
+    let format = LabelMeFormat;
+
+    assert_eq!(format.id(), "labelme");
+    assert_eq!(format.display_name(), "LabelMe (JSON)");
+    assert!(format.extensions().contains(&"json"));
+    assert!(format.supports_polygon());
+    assert!(format.supports_point());
+    assert!(format.supports_per_image(), "LabelMe uses per-image files");
+}
+
+#[test]
+fn test_labelme_export_import_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("hvat_labelme_test_{}", std::process::id()));
+    let format = LabelMeFormat;
+    let data = create_labelme_project();
+
+    let result = format
+        .export(&data, &dir, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.annotations_exported, 3);
+    assert_eq!(
+        result.warnings.len(),
+        2,
+        "Line width and rotation can't be represented"
+    );
+
+    let json = std::fs::read_to_string(dir.join("wall.json")).expect("Missing annotation file");
+    assert!(json.contains("\"shape_type\": \"linestrip\""));
+    assert!(json.contains("\"imagePath\": \"wall.png\""));
+
+    let imported = format
+        .import(&dir, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(imported.categories.len(), 2);
+    let image = &imported.images[0];
+    assert_eq!(image.dimensions, Some((640, 480)));
+    assert_eq!(image.annotations.len(), 3);
+    match &image.annotations[0].shape {
+        ShapeEntry::Polyline { vertices, .. } => assert_eq!(vertices.len(), 3),
+        _ => panic!("Expected polyline"),
+    }
+    assert!(image.annotations[1].shape.is_bbox());
+    assert!(image.annotations[2].shape.is_polygon());
+}
+
+#[test]
+fn test_labelme_multipolygon_parts() {
+    let dir = std::env::temp_dir().join(format!("hvat_labelme_parts_{}", std::process::id()));
+    let format = LabelMeFormat;
+    let mut data = ProjectData::new();
+    data.categories.push(CategoryEntry::new(1, "leaf"));
+    let mut image = ImageEntry::new(PathBuf::from("plant.png")).with_dimensions(100, 100);
+    // Two parts, the first with a hole
+    image.annotations.push(AnnotationEntry::new(
+        7,
+        1,
+        ShapeEntry::MultiPolygon {
+            rings: vec![
+                vec![(0.0, 0.0), (20.0, 0.0), (20.0, 20.0), (0.0, 20.0)],
+                vec![(5.0, 5.0), (15.0, 5.0), (15.0, 15.0), (5.0, 15.0)],
+                vec![(50.0, 50.0), (60.0, 50.0), (55.0, 60.0)],
+            ],
+        },
+    ));
+    data.images.push(image);
+
+    let result = format
+        .export(&data, &dir, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.annotations_exported, 1);
+    assert_eq!(result.warnings.len(), 1, "The hole can't be represented");
+
+    let imported = format
+        .import(&dir, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_dir_all(&dir).ok();
+
+    // The parts share a group ID and are merged back into one annotation
+    let annotations = &imported.images[0].annotations;
+    assert_eq!(annotations.len(), 1);
+    match &annotations[0].shape {
+        ShapeEntry::MultiPolygon { rings } => {
+            assert_eq!(rings.len(), 2);
+            assert_eq!(rings[1].len(), 3);
+        }
+        other => panic!("Expected multipolygon, got {}", other.shape_type()),
+    }
+}
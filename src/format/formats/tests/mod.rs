@@ -4,7 +4,10 @@
 //! and round-trip conversions.
 
 mod coco_tests;
+mod dota_tests;
 mod hvat_json_tests;
+mod labelme_tests;
 mod pascal_voc_tests;
 mod roundtrip_tests;
+mod yolo_obb_tests;
 mod yolo_tests;
@@ -0,0 +1,94 @@
+//! Tests for the YOLO-OBB format.
+
+use std::path::PathBuf;
+
+use crate::format::formats::YoloObbFormat;
+use crate::format::project::{AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ShapeEntry};
+use crate::format::traits::{AnnotationFormat, ExportOptions, ImportOptions};
+
+/// Create a test project with one rotated box, one bbox and one polygon.
+fn create_obb_project() -> ProjectData {
+    let mut data = ProjectData::new();
+    data.categories.push(CategoryEntry::new(0, "ship"));
+    data.categories.push(CategoryEntry::new(1, "plane"));
+
+    let mut image = ImageEntry::new(PathBuf::from("tile_001.png")).with_dimensions(200, 100);
+    image.annotations.push(AnnotationEntry::new(
+        1,
+        0,
+        ShapeEntry::RotatedBox {
+            cx: 100.0,
+            cy: 50.0,
+            width: 40.0,
+            height: 20.0,
+            angle: std::f32::consts::FRAC_PI_2,
+        },
+    ));
+    image.annotations.push(AnnotationEntry::new(
+        2,
+        1,
+        ShapeEntry::BoundingBox {
+            x: 10.0,
+            y: 10.0,
+            width: 20.0,
+            height: 10.0,
+        },
+    ));
+    image.annotations.push(AnnotationEntry::new(
+        3,
+        1,
+        ShapeEntry::Polygon {
+            vertices: vec![(0.0, 0.0), (10.0, 0.0), (5.0, 5.0)],
+        },
+    ));
+
+    data.images.push(image);
+    data
+}
+
+#[test]
+fn test_yolo_obb_format_metadata() {
+    let format = YoloObbFormat;
+
+    assert_eq!(format.id(), "yolo_obb");
+    assert_eq!(format.display_name(), "YOLO-OBB (TXT)");
+    assert!(format.extensions().contains(&"txt"));
+    assert!(!format.supports_polygon(), "YOLO-OBB only supports boxes");
+    assert!(!format.supports_point(), "YOLO-OBB only supports boxes");
+    assert!(format.supports_per_image(), "YOLO-OBB uses per-image files");
+}
+
+#[test]
+fn test_yolo_obb_export_import() {
+    let dir = std::env::temp_dir().join(format!("hvat_yolo_obb_test_{}", std::process::id()));
+    let format = YoloObbFormat;
+    let data = create_obb_project();
+
+    let result = format
+        .export(&data, &dir, &ExportOptions::default())
+        .expect("Failed to export");
+    assert_eq!(result.annotations_exported, 2);
+    assert_eq!(result.warnings.len(), 1, "Polygon should be skipped");
+
+    // Quarter-turn box: local top-left corner lands at (110, 30) -> (0.55, 0.3)
+    let labels = std::fs::read_to_string(dir.join("tile_001.txt")).expect("Missing labels");
+    let first = labels.lines().next().unwrap();
+    assert!(first.starts_with("0 0.550000 0.300000 "));
+
+    // Image file is absent, so imported corners stay normalized
+    let imported = format
+        .import(&dir, &ImportOptions::default())
+        .expect("Failed to import");
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(imported.categories.len(), 2);
+    assert_eq!(imported.images.len(), 1);
+    assert_eq!(imported.images[0].annotations.len(), 2);
+    assert!(imported.metadata.extra.contains_key("note"));
+    assert!(
+        imported.images[0]
+            .annotations
+            .iter()
+            .all(|ann| ann.shape.is_rotated_box())
+    );
+}
@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use crate::format::formats::YoloFormat;
 use crate::format::project::{AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ShapeEntry};
-use crate::format::traits::AnnotationFormat;
+use crate::format::traits::{AnnotationFormat, ExportOptions};
 
 /// Create a test project with YOLO-compatible data (bbox only).
 fn create_yolo_project() -> ProjectData {
@@ -284,3 +284,95 @@ fn test_empty_yolo_file() {
 
     assert_eq!(data.images[0].annotations.len(), 0);
 }
+
+#[test]
+fn test_yolo_rotated_box_exports_enclosing_box() {
+    let dir = std::env::temp_dir().join(format!("hvat_yolo_rotated_{}", std::process::id()));
+    let mut data = create_yolo_project();
+    data.images[0].annotations = vec![AnnotationEntry::new(
+        1,
+        0,
+        ShapeEntry::RotatedBox {
+            cx: 320.0,
+            cy: 240.0,
+            width: 100.0,
+            height: 50.0,
+            angle: std::f32::consts::FRAC_PI_2,
+        },
+    )];
+
+    let result = YoloFormat
+        .export(&data, &dir, &ExportOptions::default())
+        .expect("Failed to export");
+    let labels = std::fs::read_to_string(dir.join("photo001.txt")).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(result.annotations_exported, 1);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(
+        result.warnings[0]
+            .message
+            .contains("enclosing axis-aligned box")
+    );
+    // Turned a quarter, the 100x50 box encloses 50x100 pixels
+    assert_eq!(labels, "0 0.500000 0.500000 0.078125 0.208333");
+}
+
+#[test]
+fn test_yolo_mask_exports_bounding_box() {
+    let dir = std::env::temp_dir().join(format!("hvat_yolo_mask_{}", std::process::id()));
+    let mut data = create_yolo_project();
+    data.images[0].annotations = vec![AnnotationEntry::new(
+        1,
+        0,
+        ShapeEntry::Mask {
+            x: 64,
+            y: 48,
+            width: 64,
+            height: 48,
+            counts: vec![0, 64 * 48],
+            crowd: false,
+        },
+    )];
+
+    let result = YoloFormat
+        .export(&data, &dir, &ExportOptions::default())
+        .expect("Failed to export");
+    let labels = std::fs::read_to_string(dir.join("photo001.txt")).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(result.annotations_exported, 1);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].message.contains("mask annotation"));
+    assert_eq!(labels, "0 0.150000 0.150000 0.100000 0.100000");
+}
+
+#[test]
+fn test_yolo_keypoints_export_bounding_box() {
+    let dir = std::env::temp_dir().join(format!("hvat_yolo_keypoints_{}", std::process::id()));
+    let mut data = create_yolo_project();
+    // A labeled keypoint right of the instance box widens it; the unlabeled
+    // one is ignored
+    data.images[0].annotations = vec![AnnotationEntry::new(
+        1,
+        0,
+        ShapeEntry::Keypoints {
+            keypoints: vec![(80.0, 60.0, 2), (160.0, 96.0, 2), (600.0, 400.0, 0)],
+            x: 64.0,
+            y: 48.0,
+            width: 64.0,
+            height: 48.0,
+        },
+    )];
+
+    let result = YoloFormat
+        .export(&data, &dir, &ExportOptions::default())
+        .expect("Failed to export");
+    let labels = std::fs::read_to_string(dir.join("photo001.txt")).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(result.annotations_exported, 1);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].message.contains("keypoints annotation"));
+    assert_eq!(labels, "0 0.175000 0.150000 0.150000 0.100000");
+}
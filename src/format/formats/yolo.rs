@@ -22,6 +22,8 @@ use crate::format::traits::{
 ///
 /// Supports:
 /// - Bounding boxes only (normalized coordinates)
+/// - Rotated boxes (exported as their enclosing axis-aligned box)
+/// - Masks and keypoints (exported as their bounding box)
 /// - Per-image annotation files
 /// - classes.txt for category names
 ///
@@ -115,56 +117,11 @@ impl AnnotationFormat for YoloFormat {
 
             let mut lines = Vec::new();
             for ann in &image.annotations {
-                match &ann.shape {
-                    ShapeEntry::BoundingBox {
-                        x,
-                        y,
-                        width: w,
-                        height: h,
-                    } => {
-                        let class_idx = match cat_to_idx.get(&ann.category_id) {
-                            Some(&idx) => idx,
-                            None => {
-                                warnings.push(
-                                    FormatWarning::warning(format!(
-                                        "Unknown category ID {}, skipping annotation",
-                                        ann.category_id
-                                    ))
-                                    .with_image(&image.path),
-                                );
-                                continue;
-                            }
-                        };
-
-                        // Convert to YOLO normalized format
-                        // YOLO uses center coordinates, normalized to [0, 1]
-                        let cx = (x + w / 2.0) / width;
-                        let cy = (y + h / 2.0) / height;
-                        let nw = w / width;
-                        let nh = h / height;
-
-                        lines.push(format!(
-                            "{} {:.6} {:.6} {:.6} {:.6}",
-                            class_idx, cx, cy, nw, nh
-                        ));
-                        annotations_exported += 1;
-                    }
-                    ShapeEntry::Point { .. } => {
-                        warnings.push(
-                            FormatWarning::warning(
-                                "Skipped point annotation (YOLO only supports bounding boxes)",
-                            )
-                            .with_image(&image.path),
-                        );
-                    }
-                    ShapeEntry::Polygon { .. } => {
-                        warnings.push(
-                            FormatWarning::warning(
-                                "Skipped polygon annotation (YOLO only supports bounding boxes)",
-                            )
-                            .with_image(&image.path),
-                        );
-                    }
+                if let Some(line) =
+                    label_line(ann, image, &cat_to_idx, (width, height), &mut warnings)
+                {
+                    lines.push(line);
+                    annotations_exported += 1;
                 }
             }
 
@@ -260,56 +217,11 @@ impl AnnotationFormat for YoloFormat {
 
             let mut lines = Vec::new();
             for ann in &image.annotations {
-                match &ann.shape {
-                    ShapeEntry::BoundingBox {
-                        x,
-                        y,
-                        width: w,
-                        height: h,
-                    } => {
-                        let class_idx = match cat_to_idx.get(&ann.category_id) {
-                            Some(&idx) => idx,
-                            None => {
-                                warnings.push(
-                                    FormatWarning::warning(format!(
-                                        "Unknown category ID {}, skipping annotation",
-                                        ann.category_id
-                                    ))
-                                    .with_image(&image.path),
-                                );
-                                continue;
-                            }
-                        };
-
-                        // Convert to YOLO normalized format
-                        // YOLO uses center coordinates, normalized to [0, 1]
-                        let cx = (x + w / 2.0) / width;
-                        let cy = (y + h / 2.0) / height;
-                        let nw = w / width;
-                        let nh = h / height;
-
-                        lines.push(format!(
-                            "{} {:.6} {:.6} {:.6} {:.6}",
-                            class_idx, cx, cy, nw, nh
-                        ));
-                        annotations_exported += 1;
-                    }
-                    ShapeEntry::Point { .. } => {
-                        warnings.push(
-                            FormatWarning::warning(
-                                "Skipped point annotation (YOLO only supports bounding boxes)",
-                            )
-                            .with_image(&image.path),
-                        );
-                    }
-                    ShapeEntry::Polygon { .. } => {
-                        warnings.push(
-                            FormatWarning::warning(
-                                "Skipped polygon annotation (YOLO only supports bounding boxes)",
-                            )
-                            .with_image(&image.path),
-                        );
-                    }
+                if let Some(line) =
+                    label_line(ann, image, &cat_to_idx, (width, height), &mut warnings)
+                {
+                    lines.push(line);
+                    annotations_exported += 1;
                 }
             }
 
@@ -428,6 +340,77 @@ impl AnnotationFormat for YoloFormat {
     }
 }
 
+/// Format an annotation as a YOLO label line, or warn why it is left out.
+fn label_line(
+    ann: &AnnotationEntry,
+    image: &ImageEntry,
+    cat_to_idx: &HashMap<u32, usize>,
+    (width, height): (f32, f32),
+    warnings: &mut Vec<FormatWarning>,
+) -> Option<String> {
+    let (x, y, w, h) = match &ann.shape {
+        ShapeEntry::BoundingBox {
+            x,
+            y,
+            width,
+            height,
+        } => (*x, *y, *width, *height),
+        ShapeEntry::RotatedBox { .. } => {
+            warnings.push(
+                FormatWarning::warning(
+                    "Exported rotated box as its enclosing axis-aligned box \
+                     (use YOLO-OBB to keep the rotation)",
+                )
+                .with_image(&image.path),
+            );
+            ann.shape.enclosing_box()
+        }
+        ShapeEntry::Mask { .. } | ShapeEntry::Keypoints { .. } => {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Exported {} annotation as its bounding box",
+                    ann.shape.shape_type()
+                ))
+                .with_image(&image.path),
+            );
+            ann.shape.enclosing_box()
+        }
+        other => {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Skipped {} annotation (YOLO only supports bounding boxes)",
+                    other.shape_type()
+                ))
+                .with_image(&image.path),
+            );
+            return None;
+        }
+    };
+
+    let Some(&class_idx) = cat_to_idx.get(&ann.category_id) else {
+        warnings.push(
+            FormatWarning::warning(format!(
+                "Unknown category ID {}, skipping annotation",
+                ann.category_id
+            ))
+            .with_image(&image.path),
+        );
+        return None;
+    };
+
+    // Convert to YOLO normalized format
+    // YOLO uses center coordinates, normalized to [0, 1]
+    let cx = (x + w / 2.0) / width;
+    let cy = (y + h / 2.0) / height;
+    let nw = w / width;
+    let nh = h / height;
+
+    Some(format!(
+        "{} {:.6} {:.6} {:.6} {:.6}",
+        class_idx, cx, cy, nw, nh
+    ))
+}
+
 /// Parse a single YOLO annotation line.
 fn parse_yolo_line(line: &str, id: u32) -> Option<AnnotationEntry> {
     let parts: Vec<&str> = line.split_whitespace().collect();
@@ -459,7 +442,7 @@ fn parse_yolo_line(line: &str, id: u32) -> Option<AnnotationEntry> {
 }
 
 /// Find an image file matching the given stem in the directory.
-fn find_image_for_stem(dir: &Path, stem: &str) -> std::path::PathBuf {
+pub(super) fn find_image_for_stem(dir: &Path, stem: &str) -> std::path::PathBuf {
     const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "tiff", "tif", "webp"];
 
     for ext in IMAGE_EXTENSIONS {
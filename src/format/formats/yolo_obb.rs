@@ -0,0 +1,430 @@
+//! YOLO-OBB TXT format implementation.
+//!
+//! Implements the oriented bounding box variant of the YOLO format, which uses
+//! one text file per image listing the four box corners in normalized
+//! coordinates: `<class> <x1> <y1> <x2> <y2> <x3> <y3> <x4> <y4>`.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use super::yolo::find_image_for_stem;
+use crate::format::error::FormatError;
+use crate::format::project::{
+    AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ProjectMetadata, ShapeEntry,
+};
+use crate::format::traits::{
+    AnnotationFormat, ExportOptions, ExportResult, FormatWarning, ImportOptions,
+};
+use crate::model::AnnotationShape;
+
+/// YOLO-OBB TXT format.
+///
+/// Supports:
+/// - Rotated boxes (four normalized corners)
+/// - Bounding boxes (exported as unrotated corners)
+/// - Per-image annotation files
+/// - classes.txt for category names
+///
+/// Does not support:
+/// - Polygons (skipped with warning)
+/// - Points (skipped with warning)
+/// - Per-image tags
+/// - Category colors
+pub struct YoloObbFormat;
+
+impl AnnotationFormat for YoloObbFormat {
+    fn id(&self) -> &'static str {
+        "yolo_obb"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "YOLO-OBB (TXT)"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["txt"]
+    }
+
+    fn supports_polygon(&self) -> bool {
+        false
+    }
+
+    fn supports_point(&self) -> bool {
+        false
+    }
+
+    fn supports_per_image(&self) -> bool {
+        true
+    }
+
+    fn export(
+        &self,
+        data: &ProjectData,
+        path: &Path,
+        _options: &ExportOptions,
+    ) -> Result<ExportResult, FormatError> {
+        log::info!("Exporting YOLO-OBB annotations to {:?}", path);
+
+        let output_dir = path;
+        std::fs::create_dir_all(output_dir)?;
+
+        let mut warnings = Vec::new();
+        let mut files_created = Vec::new();
+        let mut annotations_exported = 0;
+
+        // Write classes.txt
+        let classes_path = output_dir.join("classes.txt");
+        std::fs::write(&classes_path, classes_content(data))?;
+        files_created.push(classes_path);
+
+        let cat_to_idx = category_indices(data);
+
+        // Write per-image annotation files
+        for image in &data.images {
+            let Some(lines) =
+                image_lines(image, &cat_to_idx, &mut warnings, &mut annotations_exported)
+            else {
+                continue;
+            };
+
+            let stem = Path::new(&image.filename)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown");
+            let txt_path = output_dir.join(format!("{}.txt", stem));
+            std::fs::write(&txt_path, lines.join("\n"))?;
+            files_created.push(txt_path);
+        }
+
+        log::info!(
+            "Exported {} images with {} annotations ({} warnings)",
+            data.images.len(),
+            annotations_exported,
+            warnings.len()
+        );
+
+        Ok(ExportResult {
+            images_exported: data.images.len(),
+            annotations_exported,
+            warnings,
+            files_created,
+        })
+    }
+
+    fn export_to_bytes(
+        &self,
+        data: &ProjectData,
+        _options: &ExportOptions,
+    ) -> Result<(Vec<u8>, ExportResult), FormatError> {
+        log::info!("Exporting YOLO-OBB annotations to ZIP");
+
+        let mut warnings = Vec::new();
+        let mut annotations_exported = 0;
+
+        // Create ZIP file in memory
+        let buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(buffer);
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(0o644);
+
+        // Write classes.txt at root level
+        zip.start_file("classes.txt", options)
+            .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+        zip.write_all(classes_content(data).as_bytes())?;
+
+        let cat_to_idx = category_indices(data);
+
+        // Write per-image annotation files, preserving folder structure
+        for image in &data.images {
+            let Some(lines) =
+                image_lines(image, &cat_to_idx, &mut warnings, &mut annotations_exported)
+            else {
+                continue;
+            };
+
+            // Compute relative path from project folder to preserve structure
+            let relative_path = if !data.folder.as_os_str().is_empty() {
+                image
+                    .path
+                    .strip_prefix(&data.folder)
+                    .ok()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|| image.path.clone())
+            } else {
+                image.path.clone()
+            };
+
+            // Replace image extension with .txt, keeping the folder structure
+            let txt_path = relative_path.with_extension("txt");
+            let txt_filename = txt_path
+                .to_str()
+                .unwrap_or("unknown.txt")
+                .replace('\\', "/"); // Normalize path separators for ZIP
+
+            zip.start_file(&txt_filename, options)
+                .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+            zip.write_all(lines.join("\n").as_bytes())?;
+        }
+
+        let buffer = zip
+            .finish()
+            .map_err(|e| FormatError::Io(std::io::Error::other(e.to_string())))?;
+
+        log::info!(
+            "Exported {} images with {} annotations ({} warnings) to ZIP",
+            data.images.len(),
+            annotations_exported,
+            warnings.len()
+        );
+
+        Ok((
+            buffer.into_inner(),
+            ExportResult {
+                images_exported: data.images.len(),
+                annotations_exported,
+                warnings,
+                files_created: Vec::new(),
+            },
+        ))
+    }
+
+    fn import(&self, path: &Path, _options: &ImportOptions) -> Result<ProjectData, FormatError> {
+        log::info!("Importing YOLO-OBB annotations from {:?}", path);
+
+        let input_dir = path;
+        if !input_dir.is_dir() {
+            return Err(FormatError::invalid_format(
+                "YOLO-OBB import requires a directory path",
+            ));
+        }
+
+        let mut data = ProjectData::new();
+        data.folder = input_dir.to_path_buf();
+
+        // Read classes.txt
+        let classes_path = input_dir.join("classes.txt");
+        if classes_path.exists() {
+            let content = std::fs::read_to_string(&classes_path)?;
+            for (idx, line) in content.lines().enumerate() {
+                let name = line.trim();
+                if !name.is_empty() {
+                    data.categories.push(CategoryEntry::new(idx as u32, name));
+                }
+            }
+        }
+
+        // Find all .txt files (excluding classes.txt)
+        let txt_files: Vec<_> = std::fs::read_dir(input_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension().is_some_and(|ext| ext == "txt")
+                    && p.file_name().is_some_and(|n| n != "classes.txt")
+            })
+            .collect();
+
+        let mut normalized_images = 0;
+        for txt_path in txt_files {
+            let stem = txt_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+
+            // Try to find corresponding image
+            let image_path = find_image_for_stem(input_dir, stem);
+            let mut entry = ImageEntry::new(image_path.clone());
+            entry.filename = image_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(stem)
+                .to_string();
+
+            // Corners must be in pixels for the box angle to be meaningful,
+            // so scale by the image size when the image can be read
+            let scale = match image::image_dimensions(&image_path) {
+                Ok((width, height)) => {
+                    entry = entry.with_dimensions(width, height);
+                    (width as f32, height as f32)
+                }
+                Err(_) => {
+                    normalized_images += 1;
+                    (1.0, 1.0)
+                }
+            };
+
+            // Parse annotations
+            let content = std::fs::read_to_string(&txt_path)?;
+            let mut ann_id = 0u32;
+            for line in content.lines() {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                if let Some(ann) = parse_obb_line(line, ann_id, scale) {
+                    entry.annotations.push(ann);
+                    ann_id += 1;
+                }
+            }
+
+            data.images.push(entry);
+        }
+
+        data.metadata = ProjectMetadata::new();
+        data.metadata.extra.insert(
+            "imported_from".into(),
+            serde_json::Value::String("yolo_obb".into()),
+        );
+        if normalized_images > 0 {
+            data.metadata.extra.insert(
+                "note".into(),
+                serde_json::Value::String(format!(
+                    "{} images not found; their YOLO-OBB coordinates are still normalized",
+                    normalized_images
+                )),
+            );
+        }
+
+        log::info!(
+            "Imported {} images with {} annotations",
+            data.images.len(),
+            data.total_annotations()
+        );
+
+        Ok(data)
+    }
+}
+
+/// Category names in class index order.
+fn classes_content(data: &ProjectData) -> String {
+    data.categories
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Map category IDs to 0-based class indices.
+fn category_indices(data: &ProjectData) -> HashMap<u32, usize> {
+    data.categories
+        .iter()
+        .enumerate()
+        .map(|(idx, c)| (c.id, idx))
+        .collect()
+}
+
+/// Build the label lines for one image.
+/// Returns None (with an error warning) if the image has no dimensions.
+fn image_lines(
+    image: &ImageEntry,
+    cat_to_idx: &HashMap<u32, usize>,
+    warnings: &mut Vec<FormatWarning>,
+    annotations_exported: &mut usize,
+) -> Option<Vec<String>> {
+    let (width, height) = match image.dimensions {
+        Some((w, h)) if w > 0 && h > 0 => (w as f32, h as f32),
+        _ => {
+            warnings.push(
+                FormatWarning::error(format!(
+                    "Skipping image '{}': dimensions required for YOLO-OBB format",
+                    image.filename
+                ))
+                .with_image(&image.path),
+            );
+            return None;
+        }
+    };
+
+    let mut lines = Vec::new();
+    for ann in &image.annotations {
+        let Some(corners) = ann.shape.box_corners() else {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Skipped {} annotation (YOLO-OBB only supports boxes)",
+                    ann.shape.shape_type()
+                ))
+                .with_image(&image.path),
+            );
+            continue;
+        };
+
+        let Some(&class_idx) = cat_to_idx.get(&ann.category_id) else {
+            warnings.push(
+                FormatWarning::warning(format!(
+                    "Unknown category ID {}, skipping annotation",
+                    ann.category_id
+                ))
+                .with_image(&image.path),
+            );
+            continue;
+        };
+
+        let coords: Vec<String> = corners
+            .iter()
+            .flat_map(|(x, y)| [x / width, y / height])
+            .map(|v| format!("{:.6}", v))
+            .collect();
+        lines.push(format!("{} {}", class_idx, coords.join(" ")));
+        *annotations_exported += 1;
+    }
+
+    Some(lines)
+}
+
+/// Parse a single YOLO-OBB annotation line, scaling normalized corners by
+/// `scale` (image width and height).
+fn parse_obb_line(line: &str, id: u32, scale: (f32, f32)) -> Option<AnnotationEntry> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 9 {
+        return None;
+    }
+
+    let class_id: u32 = parts[0].parse().ok()?;
+    let mut corners = [(0.0, 0.0); 4];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let x: f32 = parts[1 + i * 2].parse().ok()?;
+        let y: f32 = parts[2 + i * 2].parse().ok()?;
+        *corner = (x * scale.0, y * scale.1);
+    }
+
+    Some(AnnotationEntry::new(
+        id,
+        class_id,
+        ShapeEntry::from_shape(&AnnotationShape::rotated_box_from_corners(corners)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_obb_line() {
+        let line = "2 0.1 0.1 0.3 0.1 0.3 0.2 0.1 0.2";
+        let ann = parse_obb_line(line, 4, (100.0, 50.0)).unwrap();
+
+        assert_eq!(ann.id, 4);
+        assert_eq!(ann.category_id, 2);
+
+        match ann.shape {
+            ShapeEntry::RotatedBox {
+                cx,
+                cy,
+                width,
+                height,
+                angle,
+            } => {
+                assert!((cx - 20.0).abs() < 0.001);
+                assert!((cy - 7.5).abs() < 0.001);
+                assert!((width - 20.0).abs() < 0.001);
+                assert!((height - 5.0).abs() < 0.001);
+                assert!(angle.abs() < 0.001);
+            }
+            _ => panic!("Expected rotated box"),
+        }
+
+        assert!(parse_obb_line("0 0.5 0.5 0.2 0.3", 0, (1.0, 1.0)).is_none());
+    }
+}
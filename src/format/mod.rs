@@ -11,6 +11,9 @@
 //! - **YOLO TXT**: Simple per-image format for bounding boxes
 //! - **Pascal VOC XML**: Classic per-image XML format for bounding boxes
 //!
+//! `SpectralDataset` additionally exports the band values inside annotations
+//! (per-annotation statistics as CSV, per-pixel spectra as `.npy`).
+//!
 //! ## Usage
 //!
 //! ```rust,ignore
@@ -29,14 +32,17 @@ mod error;
 pub mod formats;
 mod project;
 mod registry;
+mod spectra;
 mod traits;
 
 pub use auto_save::AutoSaveManager;
 pub use error::FormatError;
 pub use project::{
-    AnnotationEntry, CategoryEntry, ImageEntry, ProjectData, ProjectMetadata, ShapeEntry, TagEntry,
+    AnnotationEntry, CategoryEntry, CompositeBand, CompositeEntry, ImageEntry, ProjectData,
+    ProjectMetadata, ShapeEntry, TagEntry,
 };
 pub use registry::FormatRegistry;
+pub use spectra::SpectralDataset;
 pub use traits::{
     AnnotationFormat, ExportOptions, ExportResult, FormatWarning, ImportOptions, WarningSeverity,
 };
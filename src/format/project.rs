@@ -21,7 +21,10 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-use crate::model::{Annotation, AnnotationShape, Category, Tag};
+use crate::data::{BandInfo, CalibrationSource, GeoTransform, PreprocessingChain};
+use crate::model::{
+    Annotation, AnnotationShape, Category, DEFAULT_POLYLINE_WIDTH, Keypoint, RleMask, Tag,
+};
 use crate::state::ImageData;
 
 /// Complete project data for import/export.
@@ -155,6 +158,18 @@ pub struct ImageEntry {
     #[serde(default)]
     pub dimensions: Option<(u32, u32)>,
 
+    /// Pixel-to-map transform for georeferenced images (e.g. GeoTIFF).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geotransform: Option<GeoTransform>,
+
+    /// Per-band metadata (wavelength, FWHM, name, bad-band flag) if known.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bands: Vec<BandInfo>,
+
+    /// Dark/white references and factors the image was calibrated with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<CalibrationSource>,
+
     /// Annotations on this image.
     pub annotations: Vec<AnnotationEntry>,
 
@@ -176,6 +191,9 @@ impl ImageEntry {
             path,
             filename,
             dimensions: None,
+            geotransform: None,
+            bands: Vec::new(),
+            calibration: None,
             annotations: Vec::new(),
             tag_ids: HashSet::new(),
         }
@@ -250,6 +268,16 @@ pub enum ShapeEntry {
         height: f32,
     },
 
+    /// Oriented bounding box defined by center, size and rotation (radians).
+    #[serde(rename = "rotated_box")]
+    RotatedBox {
+        cx: f32,
+        cy: f32,
+        width: f32,
+        height: f32,
+        angle: f32,
+    },
+
     /// Single point marker.
     #[serde(rename = "point")]
     Point { x: f32, y: f32 },
@@ -257,6 +285,48 @@ pub enum ShapeEntry {
     /// Polygon defined by vertices.
     #[serde(rename = "polygon")]
     Polygon { vertices: Vec<(f32, f32)> },
+
+    /// Polygon made of several rings (parts and holes), filled by the
+    /// even-odd rule.
+    #[serde(rename = "multipolygon")]
+    MultiPolygon { rings: Vec<Vec<(f32, f32)>> },
+
+    /// Open path defined by vertices, with a line width in pixels.
+    #[serde(rename = "polyline")]
+    Polyline {
+        vertices: Vec<(f32, f32)>,
+        #[serde(default = "default_polyline_width")]
+        width: f32,
+    },
+
+    /// Raster mask as row-major run lengths inside a pixel bounding box,
+    /// alternating background and foreground starting with background.
+    #[serde(rename = "mask")]
+    Mask {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        counts: Vec<u32>,
+        /// COCO crowd region (`iscrowd`).
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        crowd: bool,
+    },
+
+    /// Keypoints of one instance as (x, y, visibility), in the order of the
+    /// category's keypoint template, with the instance's bounding box.
+    #[serde(rename = "keypoints")]
+    Keypoints {
+        keypoints: Vec<(f32, f32, u8)>,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+fn default_polyline_width() -> f32 {
+    DEFAULT_POLYLINE_WIDTH
 }
 
 impl ShapeEntry {
@@ -274,10 +344,51 @@ impl ShapeEntry {
                 width: *width,
                 height: *height,
             },
+            AnnotationShape::RotatedBox {
+                cx,
+                cy,
+                w,
+                h,
+                angle,
+            } => ShapeEntry::RotatedBox {
+                cx: *cx,
+                cy: *cy,
+                width: *w,
+                height: *h,
+                angle: *angle,
+            },
             AnnotationShape::Point { x, y } => ShapeEntry::Point { x: *x, y: *y },
             AnnotationShape::Polygon { vertices } => ShapeEntry::Polygon {
                 vertices: vertices.clone(),
             },
+            AnnotationShape::MultiPolygon { rings } => ShapeEntry::MultiPolygon {
+                rings: rings.clone(),
+            },
+            AnnotationShape::Polyline { vertices, width } => ShapeEntry::Polyline {
+                vertices: vertices.clone(),
+                width: *width,
+            },
+            AnnotationShape::Mask { mask, crowd } => ShapeEntry::Mask {
+                x: mask.x,
+                y: mask.y,
+                width: mask.width,
+                height: mask.height,
+                counts: mask.counts.clone(),
+                crowd: *crowd,
+            },
+            AnnotationShape::Keypoints {
+                keypoints,
+                x,
+                y,
+                width,
+                height,
+            } => ShapeEntry::Keypoints {
+                keypoints: keypoints.iter().map(|k| (k.x, k.y, k.visibility)).collect(),
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+            },
         }
     }
 
@@ -295,19 +406,116 @@ impl ShapeEntry {
                 width: *width,
                 height: *height,
             },
+            ShapeEntry::RotatedBox {
+                cx,
+                cy,
+                width,
+                height,
+                angle,
+            } => AnnotationShape::RotatedBox {
+                cx: *cx,
+                cy: *cy,
+                w: *width,
+                h: *height,
+                angle: *angle,
+            },
             ShapeEntry::Point { x, y } => AnnotationShape::Point { x: *x, y: *y },
             ShapeEntry::Polygon { vertices } => AnnotationShape::Polygon {
                 vertices: vertices.clone(),
             },
+            ShapeEntry::MultiPolygon { rings } => AnnotationShape::MultiPolygon {
+                rings: rings.clone(),
+            },
+            ShapeEntry::Polyline { vertices, width } => AnnotationShape::Polyline {
+                vertices: vertices.clone(),
+                width: *width,
+            },
+            // Malformed masks decode as empty; imports reject them via `validate`
+            ShapeEntry::Mask { crowd, .. } => AnnotationShape::Mask {
+                mask: self
+                    .rle_mask()
+                    .filter(|mask| mask.validate().is_ok())
+                    .unwrap_or_default(),
+                crowd: *crowd,
+            },
+            ShapeEntry::Keypoints {
+                keypoints,
+                x,
+                y,
+                width,
+                height,
+            } => AnnotationShape::Keypoints {
+                keypoints: keypoints
+                    .iter()
+                    .map(|(x, y, visibility)| Keypoint::new(*x, *y, *visibility))
+                    .collect(),
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+            },
         }
     }
 
+    /// The run-length encoded bitmap of a mask. None for other shapes.
+    pub fn rle_mask(&self) -> Option<RleMask> {
+        match self {
+            ShapeEntry::Mask {
+                x,
+                y,
+                width,
+                height,
+                counts,
+                ..
+            } => Some(RleMask {
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+                counts: counts.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Check data that `to_shape` can't represent safely. Masks must have a
+    /// bounded box whose runs cover it exactly.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.rle_mask() {
+            Some(mask) => mask.validate(),
+            None => Ok(()),
+        }
+    }
+
+    /// Corners of a bounding box or rotated box in clockwise order, starting
+    /// at the box's (local) top-left. None for other shapes.
+    pub fn box_corners(&self) -> Option<[(f32, f32); 4]> {
+        match self {
+            ShapeEntry::BoundingBox { .. } | ShapeEntry::RotatedBox { .. } => {
+                self.to_shape().to_rotated_box().rotated_box_corners()
+            }
+            _ => None,
+        }
+    }
+
+    /// Axis-aligned box (x, y, width, height) enclosing the shape, for
+    /// bounding-box-only formats.
+    pub fn enclosing_box(&self) -> (f32, f32, f32, f32) {
+        let (min_x, min_y, max_x, max_y) = self.to_shape().bounding_box();
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
     /// Get the shape type as a string (for error messages).
     pub fn shape_type(&self) -> &'static str {
         match self {
             ShapeEntry::BoundingBox { .. } => "bbox",
+            ShapeEntry::RotatedBox { .. } => "rotated_box",
             ShapeEntry::Point { .. } => "point",
             ShapeEntry::Polygon { .. } => "polygon",
+            ShapeEntry::MultiPolygon { .. } => "multipolygon",
+            ShapeEntry::Polyline { .. } => "polyline",
+            ShapeEntry::Mask { .. } => "mask",
+            ShapeEntry::Keypoints { .. } => "keypoints",
         }
     }
 
@@ -316,6 +524,11 @@ impl ShapeEntry {
         matches!(self, ShapeEntry::BoundingBox { .. })
     }
 
+    /// Check if this is a rotated bounding box.
+    pub fn is_rotated_box(&self) -> bool {
+        matches!(self, ShapeEntry::RotatedBox { .. })
+    }
+
     /// Check if this is a point.
     pub fn is_point(&self) -> bool {
         matches!(self, ShapeEntry::Point { .. })
@@ -325,6 +538,26 @@ impl ShapeEntry {
     pub fn is_polygon(&self) -> bool {
         matches!(self, ShapeEntry::Polygon { .. })
     }
+
+    /// Check if this is a multipolygon.
+    pub fn is_multipolygon(&self) -> bool {
+        matches!(self, ShapeEntry::MultiPolygon { .. })
+    }
+
+    /// Check if this is a polyline.
+    pub fn is_polyline(&self) -> bool {
+        matches!(self, ShapeEntry::Polyline { .. })
+    }
+
+    /// Check if this is a raster mask.
+    pub fn is_mask(&self) -> bool {
+        matches!(self, ShapeEntry::Mask { .. })
+    }
+
+    /// Check if this is a keypoints annotation.
+    pub fn is_keypoints(&self) -> bool {
+        matches!(self, ShapeEntry::Keypoints { .. })
+    }
 }
 
 /// Category definition for export/import.
@@ -343,6 +576,14 @@ pub struct CategoryEntry {
     /// Supercategory for COCO compatibility.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub supercategory: Option<String>,
+
+    /// Keypoint names in placement order (empty = no keypoint template).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keypoints: Vec<String>,
+
+    /// Skeleton edges as pairs of 0-based keypoint indices.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skeleton: Vec<(usize, usize)>,
 }
 
 impl CategoryEntry {
@@ -353,6 +594,8 @@ impl CategoryEntry {
             name: name.into(),
             color: None,
             supercategory: None,
+            keypoints: Vec::new(),
+            skeleton: Vec::new(),
         }
     }
 
@@ -363,12 +606,15 @@ impl CategoryEntry {
             name: category.name.clone(),
             color: Some(category.color),
             supercategory: None,
+            keypoints: category.keypoints.clone(),
+            skeleton: category.skeleton.clone(),
         }
     }
 
     /// Convert to an internal Category.
     pub fn to_category(&self) -> Category {
         Category::new(self.id, &self.name, self.color.unwrap_or([200, 200, 200]))
+            .with_keypoints(self.keypoints.clone(), self.skeleton.clone())
     }
 
     /// Set the color.
@@ -382,6 +628,13 @@ impl CategoryEntry {
         self.supercategory = Some(supercategory.into());
         self
     }
+
+    /// Set the keypoint template (names and 0-based skeleton edges).
+    pub fn with_keypoints(mut self, keypoints: Vec<String>, skeleton: Vec<(usize, usize)>) -> Self {
+        self.keypoints = keypoints;
+        self.skeleton = skeleton;
+        self
+    }
 }
 
 /// Tag definition for export/import (image-level tags).
@@ -444,6 +697,14 @@ pub struct ProjectMetadata {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub modified_at: Option<String>,
 
+    /// RGB band composite shown when the project was saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composite: Option<CompositeEntry>,
+
+    /// Spectral preprocessing applied to every image of the project.
+    #[serde(default, skip_serializing_if = "PreprocessingChain::is_empty")]
+    pub preprocessing: PreprocessingChain,
+
     /// Format-specific extra data.
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub extra: HashMap<String, serde_json::Value>,
@@ -456,6 +717,8 @@ impl ProjectMetadata {
             created_by: Some("HVAT".to_string()),
             created_at: Some(Self::current_timestamp()),
             modified_at: Some(Self::current_timestamp()),
+            composite: None,
+            preprocessing: PreprocessingChain::default(),
             extra: HashMap::new(),
         }
     }
@@ -492,6 +755,51 @@ impl ProjectMetadata {
     }
 }
 
+/// Bands displayed as the red, green and blue channels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeEntry {
+    /// Band shown as red.
+    pub red: CompositeBand,
+
+    /// Band shown as green.
+    pub green: CompositeBand,
+
+    /// Band shown as blue.
+    pub blue: CompositeBand,
+}
+
+/// One channel of a band composite.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeBand {
+    /// Band index (0-based).
+    pub band: usize,
+
+    /// Band name.
+    pub name: String,
+
+    /// Band center wavelength in nanometers, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wavelength: Option<f32>,
+}
+
+impl CompositeBand {
+    /// Describe band `band` using its metadata (falls back to "Band N").
+    pub fn from_band(band: usize, band_info: &[BandInfo]) -> Self {
+        match band_info.get(band) {
+            Some(info) => Self {
+                band,
+                name: info.name.clone(),
+                wavelength: info.wavelength,
+            },
+            None => Self {
+                band,
+                name: format!("Band {}", band + 1),
+                wavelength: None,
+            },
+        }
+    }
+}
+
 /// Convert days since Unix epoch to year/month/day.
 fn days_to_ymd(days: u64) -> (u32, u32, u32) {
     // Simplified algorithm - good enough for timestamps
@@ -572,6 +880,9 @@ impl ProjectData {
                 entry = entry.with_dimensions(w, h);
             }
 
+            entry.geotransform = image_data.geotransform;
+            entry.bands = image_data.band_info;
+            entry.calibration = image_data.calibration;
             entry.tag_ids = image_data.selected_tag_ids;
             entry.annotations = image_data
                 .annotations
@@ -2,7 +2,10 @@
 
 use std::collections::HashMap;
 
-use crate::format::formats::{CocoFormat, HvatJsonFormat, PascalVocFormat, YoloFormat};
+use crate::format::formats::{
+    CocoFormat, DotaFormat, HvatJsonFormat, LabelMeFormat, PascalVocFormat, YoloFormat,
+    YoloObbFormat,
+};
 use crate::format::traits::AnnotationFormat;
 
 /// Registry of available annotation formats.
@@ -25,6 +28,9 @@ impl FormatRegistry {
         registry.register(Box::new(CocoFormat));
         registry.register(Box::new(YoloFormat));
         registry.register(Box::new(PascalVocFormat));
+        registry.register(Box::new(YoloObbFormat));
+        registry.register(Box::new(DotaFormat));
+        registry.register(Box::new(LabelMeFormat));
 
         registry
     }
@@ -99,6 +105,9 @@ mod tests {
         assert!(registry.get("coco").is_some());
         assert!(registry.get("yolo").is_some());
         assert!(registry.get("voc").is_some());
+        assert!(registry.get("yolo_obb").is_some());
+        assert!(registry.get("dota").is_some());
+        assert!(registry.get("labelme").is_some());
     }
 
     #[test]
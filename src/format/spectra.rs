@@ -0,0 +1,339 @@
+//! Spectral training data export.
+//!
+//! Builds classifier datasets from annotations: one row of per-band
+//! statistics per annotation (CSV), and the spectra of every annotated pixel
+//! (`.npy` of shape pixels x bands) with a CSV index naming the image,
+//! annotation and position of each row.
+
+use ndarray::Array2;
+use ndarray_npy::WriteNpyExt;
+
+use super::FormatError;
+use crate::data::{BandInfo, HyperspectralData, RegionStats, default_bands, region_spectra};
+use crate::model::{Annotation, AnnotationShape, Category};
+
+/// Spectra of one annotation.
+#[derive(Debug, Clone)]
+pub struct AnnotationSpectra {
+    /// Image path (relative to the project folder)
+    pub image: String,
+    /// Annotation ID
+    pub annotation_id: u32,
+    /// Category ID
+    pub category_id: u32,
+    /// Category name (empty if the category no longer exists)
+    pub category: String,
+    /// Shape type name
+    pub shape: &'static str,
+    /// Per-band statistics over the covered pixels
+    pub stats: RegionStats,
+    /// Positions of the covered pixels
+    pub pixels: Vec<(u32, u32)>,
+    /// Spectra of the covered pixels (pixels x bands, row-major)
+    pub spectra: Vec<f32>,
+}
+
+/// Annotation spectra of a whole project.
+///
+/// All images must have the same band count as the first one added; other
+/// images are skipped with a warning.
+#[derive(Debug, Clone, Default)]
+pub struct SpectralDataset {
+    /// Band metadata of the first image
+    pub bands: Vec<BandInfo>,
+    /// One entry per annotation
+    pub annotations: Vec<AnnotationSpectra>,
+    /// Images or annotations that were left out
+    pub warnings: Vec<String>,
+}
+
+impl SpectralDataset {
+    /// Create an empty dataset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the annotations of one image.
+    pub fn add_image(
+        &mut self,
+        image: &str,
+        hyper: &HyperspectralData,
+        annotations: &[Annotation],
+        categories: &[Category],
+    ) {
+        if self.bands.is_empty() {
+            self.bands = if hyper.band_info.len() == hyper.num_bands() {
+                hyper.band_info.clone()
+            } else {
+                default_bands(hyper.num_bands())
+            };
+        } else if hyper.num_bands() != self.bands.len() {
+            self.warnings.push(format!(
+                "Skipped {}: {} bands, expected {}",
+                image,
+                hyper.num_bands(),
+                self.bands.len()
+            ));
+            return;
+        }
+
+        for annotation in annotations {
+            let pixels = annotation.shape.mask_pixels(hyper.width, hyper.height);
+            if pixels.is_empty() {
+                self.warnings.push(format!(
+                    "Annotation {} in {} covers no pixels",
+                    annotation.id, image
+                ));
+            }
+            let category = categories
+                .iter()
+                .find(|c| c.id == annotation.category_id)
+                .map(|c| c.name.clone())
+                .unwrap_or_default();
+            let width = hyper.width as usize;
+            self.annotations.push(AnnotationSpectra {
+                image: image.to_string(),
+                annotation_id: annotation.id,
+                category_id: annotation.category_id,
+                category,
+                shape: shape_name(&annotation.shape),
+                stats: RegionStats::compute(&hyper.bands, &pixels),
+                pixels: pixels
+                    .iter()
+                    .map(|&i| ((i % width) as u32, (i / width) as u32))
+                    .collect(),
+                spectra: region_spectra(&hyper.bands, &pixels),
+            });
+        }
+    }
+
+    /// Total number of annotated pixels.
+    pub fn pixel_count(&self) -> usize {
+        self.annotations.iter().map(|a| a.pixels.len()).sum()
+    }
+
+    /// Column suffix of each band: the wavelength if known, else "bN".
+    fn band_labels(&self) -> Vec<String> {
+        self.bands
+            .iter()
+            .enumerate()
+            .map(|(i, band)| match band.wavelength {
+                Some(wavelength) => format!("{}nm", wavelength),
+                None => format!("b{}", i + 1),
+            })
+            .collect()
+    }
+
+    /// One CSV row per annotation with mean, std, min and max of every band.
+    pub fn stats_csv(&self) -> String {
+        let mut header = vec![
+            "image".to_string(),
+            "annotation_id".to_string(),
+            "category_id".to_string(),
+            "category".to_string(),
+            "shape".to_string(),
+            "pixel_count".to_string(),
+        ];
+        for label in self.band_labels() {
+            for stat in ["mean", "std", "min", "max"] {
+                header.push(format!("{}_{}", stat, label));
+            }
+        }
+
+        let mut csv = header.join(",");
+        csv.push('\n');
+        for entry in &self.annotations {
+            let mut row = vec![
+                csv_field(&entry.image),
+                entry.annotation_id.to_string(),
+                entry.category_id.to_string(),
+                csv_field(&entry.category),
+                entry.shape.to_string(),
+                entry.stats.pixel_count.to_string(),
+            ];
+            let stats = &entry.stats;
+            for band in 0..stats.num_bands() {
+                for value in [
+                    stats.mean[band],
+                    stats.std_dev[band],
+                    stats.min[band],
+                    stats.max[band],
+                ] {
+                    row.push(csv_number(value));
+                }
+            }
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Spectra of all annotated pixels as a float32 `.npy` (pixels x bands).
+    ///
+    /// Rows are in the order of `spectra_index_csv`.
+    pub fn spectra_npy(&self) -> Result<Vec<u8>, FormatError> {
+        let values: Vec<f32> = self
+            .annotations
+            .iter()
+            .flat_map(|a| a.spectra.iter().copied())
+            .collect();
+        let array = Array2::from_shape_vec((self.pixel_count(), self.bands.len()), values)
+            .map_err(|e| FormatError::InvalidFormat {
+                message: format!("Inconsistent spectra: {}", e),
+            })?;
+        let mut bytes = Vec::new();
+        array
+            .write_npy(&mut bytes)
+            .map_err(|e| FormatError::InvalidFormat {
+                message: format!("Failed to write NPY: {}", e),
+            })?;
+        Ok(bytes)
+    }
+
+    /// CSV naming the image, annotation and pixel of each `.npy` row.
+    pub fn spectra_index_csv(&self) -> String {
+        let mut csv = String::from("row,image,annotation_id,category_id,category,x,y\n");
+        let mut row = 0;
+        for entry in &self.annotations {
+            for &(x, y) in &entry.pixels {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{}\n",
+                    row,
+                    csv_field(&entry.image),
+                    entry.annotation_id,
+                    entry.category_id,
+                    csv_field(&entry.category),
+                    x,
+                    y
+                ));
+                row += 1;
+            }
+        }
+        csv
+    }
+}
+
+/// Shape type name used in the exported tables.
+fn shape_name(shape: &AnnotationShape) -> &'static str {
+    match shape {
+        AnnotationShape::BoundingBox { .. } => "bbox",
+        AnnotationShape::RotatedBox { .. } => "rotated_box",
+        AnnotationShape::Point { .. } => "point",
+        AnnotationShape::Polygon { .. } => "polygon",
+        AnnotationShape::MultiPolygon { .. } => "multipolygon",
+        AnnotationShape::Polyline { .. } => "polyline",
+        AnnotationShape::Mask { .. } => "mask",
+        AnnotationShape::Keypoints { .. } => "keypoints",
+    }
+}
+
+/// Quote a CSV text field if needed.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format a CSV number; NaN becomes an empty field.
+fn csv_number(value: f32) -> String {
+    if value.is_nan() {
+        String::new()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(num_bands: usize) -> HyperspectralData {
+        // 4x2 image, value = band * 100 + pixel index
+        let bands = (0..num_bands)
+            .map(|b| (0..8).map(|i| (b * 100 + i) as f32).collect())
+            .collect();
+        let mut hyper = HyperspectralData::new(bands, 4, 2, vec!["b".to_string(); num_bands]);
+        hyper.band_info[0].wavelength = Some(450.0);
+        hyper
+    }
+
+    fn dataset() -> SpectralDataset {
+        let categories = vec![Category::new(1, "Leaf, healthy", [0, 255, 0])];
+        let bbox = AnnotationShape::BoundingBox {
+            x: 0.0,
+            y: 0.0,
+            width: 2.0,
+            height: 1.0,
+        };
+        let point = AnnotationShape::Point { x: 3.5, y: 1.5 };
+        let mut dataset = SpectralDataset::new();
+        dataset.add_image(
+            "a.npy",
+            &image(2),
+            &[Annotation::new(1, bbox, 1), Annotation::new(2, point, 7)],
+            &categories,
+        );
+        dataset.add_image(
+            "b.npy",
+            &image(3),
+            &[Annotation::new(
+                3,
+                AnnotationShape::Point { x: 0.0, y: 0.0 },
+                1,
+            )],
+            &categories,
+        );
+        dataset
+    }
+
+    #[test]
+    fn test_band_count_mismatch_is_skipped() {
+        let dataset = dataset();
+        assert_eq!(dataset.annotations.len(), 2);
+        assert_eq!(dataset.pixel_count(), 3);
+        assert_eq!(dataset.warnings.len(), 1);
+        assert!(dataset.warnings[0].contains("b.npy"));
+    }
+
+    #[test]
+    fn test_stats_csv() {
+        let csv = dataset().stats_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "image,annotation_id,category_id,category,shape,pixel_count,\
+             mean_450nm,std_450nm,min_450nm,max_450nm,mean_b2,std_b2,min_b2,max_b2"
+        );
+        assert_eq!(
+            lines[1],
+            "a.npy,1,1,\"Leaf, healthy\",bbox,2,0.5,0.5,0,1,100.5,0.5,100,101"
+        );
+        // Unknown category stays empty
+        assert!(lines[2].starts_with("a.npy,2,7,,point,1,7,0,7,7,"));
+    }
+
+    #[test]
+    fn test_spectra_npy_and_index() {
+        let dataset = dataset();
+        let npy = dataset.spectra_npy().unwrap();
+        assert!(npy.starts_with(b"\x93NUMPY"));
+        let header = String::from_utf8_lossy(&npy[..npy.len().min(128)]).to_string();
+        assert!(header.contains("'shape': (3, 2)"));
+        // Values follow the header: 3 pixels x 2 bands of f32
+        let data = &npy[npy.len() - 24..];
+        let values: Vec<f32> = data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(values, vec![0.0, 100.0, 1.0, 101.0, 7.0, 107.0]);
+
+        let index = dataset.spectra_index_csv();
+        let lines: Vec<&str> = index.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "1,a.npy,1,1,\"Leaf, healthy\",1,0");
+        assert_eq!(lines[3], "2,a.npy,2,7,,3,1");
+    }
+}
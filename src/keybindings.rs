@@ -31,10 +31,20 @@ pub struct KeyBindings {
     pub tool_select: KeyCode,
     /// Hotkey for BoundingBox tool
     pub tool_bbox: KeyCode,
+    /// Hotkey for RotatedBox tool
+    pub tool_rotated_box: KeyCode,
     /// Hotkey for Polygon tool
     pub tool_polygon: KeyCode,
+    /// Hotkey for Polyline tool
+    pub tool_polyline: KeyCode,
     /// Hotkey for Point tool
     pub tool_point: KeyCode,
+    /// Hotkey for Brush tool
+    pub tool_brush: KeyCode,
+    /// Hotkey for Eraser tool
+    pub tool_eraser: KeyCode,
+    /// Hotkey for Keypoints tool
+    pub tool_keypoints: KeyCode,
 
     /// Hotkeys for category selection (indices 0-9 map to categories 1-10)
     /// None means no hotkey assigned for that slot
@@ -44,11 +54,17 @@ pub struct KeyBindings {
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
-            // Default tool hotkeys: G for Select, E for BBox, R for Polygon, T for Point
+            // Default tool hotkeys: S for Select, E for BBox, W for Rotated Box, R for Polygon,
+            // Q for Polyline, T for Point, B for Brush, X for Eraser, K for Keypoints
             tool_select: KeyCode::S,
             tool_bbox: KeyCode::E,
+            tool_rotated_box: KeyCode::W,
             tool_polygon: KeyCode::R,
+            tool_polyline: KeyCode::Q,
             tool_point: KeyCode::T,
+            tool_brush: KeyCode::B,
+            tool_eraser: KeyCode::X,
+            tool_keypoints: KeyCode::K,
 
             // Default category hotkeys: 1-9, 0 for categories 1-10
             category_hotkeys: [
@@ -79,10 +95,20 @@ impl KeyBindings {
             Some(AnnotationTool::Select)
         } else if key == self.tool_bbox {
             Some(AnnotationTool::BoundingBox)
+        } else if key == self.tool_rotated_box {
+            Some(AnnotationTool::RotatedBox)
         } else if key == self.tool_polygon {
             Some(AnnotationTool::Polygon)
+        } else if key == self.tool_polyline {
+            Some(AnnotationTool::Polyline)
         } else if key == self.tool_point {
             Some(AnnotationTool::Point)
+        } else if key == self.tool_brush {
+            Some(AnnotationTool::Brush)
+        } else if key == self.tool_eraser {
+            Some(AnnotationTool::Eraser)
+        } else if key == self.tool_keypoints {
+            Some(AnnotationTool::Keypoints)
         } else {
             None
         }
@@ -102,8 +128,13 @@ impl KeyBindings {
         match tool {
             AnnotationTool::Select => self.tool_select,
             AnnotationTool::BoundingBox => self.tool_bbox,
+            AnnotationTool::RotatedBox => self.tool_rotated_box,
             AnnotationTool::Polygon => self.tool_polygon,
+            AnnotationTool::Polyline => self.tool_polyline,
             AnnotationTool::Point => self.tool_point,
+            AnnotationTool::Brush => self.tool_brush,
+            AnnotationTool::Eraser => self.tool_eraser,
+            AnnotationTool::Keypoints => self.tool_keypoints,
             #[cfg(feature = "sam2")]
             AnnotationTool::SAM2Segment => KeyCode::S, // Fixed to S key
         }
@@ -119,8 +150,13 @@ impl KeyBindings {
         match tool {
             AnnotationTool::Select => self.tool_select = key,
             AnnotationTool::BoundingBox => self.tool_bbox = key,
+            AnnotationTool::RotatedBox => self.tool_rotated_box = key,
             AnnotationTool::Polygon => self.tool_polygon = key,
+            AnnotationTool::Polyline => self.tool_polyline = key,
             AnnotationTool::Point => self.tool_point = key,
+            AnnotationTool::Brush => self.tool_brush = key,
+            AnnotationTool::Eraser => self.tool_eraser = key,
+            AnnotationTool::Keypoints => self.tool_keypoints = key,
             #[cfg(feature = "sam2")]
             AnnotationTool::SAM2Segment => {
                 // SAM2Segment key is fixed to S, cannot be changed
@@ -150,12 +186,27 @@ impl KeyBindings {
         if exclude_tool != Some(AnnotationTool::BoundingBox) && key == self.tool_bbox {
             return Some("Bounding Box tool".to_string());
         }
+        if exclude_tool != Some(AnnotationTool::RotatedBox) && key == self.tool_rotated_box {
+            return Some("Rotated Box tool".to_string());
+        }
         if exclude_tool != Some(AnnotationTool::Polygon) && key == self.tool_polygon {
             return Some("Polygon tool".to_string());
         }
+        if exclude_tool != Some(AnnotationTool::Polyline) && key == self.tool_polyline {
+            return Some("Polyline tool".to_string());
+        }
         if exclude_tool != Some(AnnotationTool::Point) && key == self.tool_point {
             return Some("Point tool".to_string());
         }
+        if exclude_tool != Some(AnnotationTool::Brush) && key == self.tool_brush {
+            return Some("Brush tool".to_string());
+        }
+        if exclude_tool != Some(AnnotationTool::Eraser) && key == self.tool_eraser {
+            return Some("Eraser tool".to_string());
+        }
+        if exclude_tool != Some(AnnotationTool::Keypoints) && key == self.tool_keypoints {
+            return Some("Keypoints tool".to_string());
+        }
 
         // Check category bindings
         for (i, hotkey) in self.category_hotkeys.iter().enumerate() {
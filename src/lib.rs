@@ -11,7 +11,8 @@ mod keybindings;
 pub mod licenses;
 mod message;
 mod model;
-#[cfg(feature = "sam2")]
+// Mask types and contour extraction are always built (also used by the
+// similarity map); the model integration requires the `sam2` feature.
 pub mod sam2;
 mod state;
 mod test_image;
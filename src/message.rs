@@ -4,10 +4,15 @@
 
 use std::path::PathBuf;
 
+use hvat_gpu::Colormap;
 use hvat_ui::prelude::*;
 use hvat_ui::{FileTreeState, ImagePointerEvent, TooltipContent};
 
 use crate::config::LogLevel;
+use crate::data::{
+    BandMathMode, ComponentMethod, Illuminant, NpyLayout, PreprocessStep, SimilarityMetric,
+    StretchMode,
+};
 use crate::keybindings::KeybindTarget;
 use crate::model::AnnotationTool;
 use crate::state::{LoadedImage, ProjectState};
@@ -44,6 +49,8 @@ pub enum Message {
     KeybindingsSectionToggled(CollapsibleState),
     /// Folders section toggled
     FoldersSectionToggled(CollapsibleState),
+    /// File loading section toggled
+    LoadingSectionToggled(CollapsibleState),
     /// Performance section toggled
     PerformanceSectionToggled(CollapsibleState),
     /// Theme changed (true = dark, false = light)
@@ -54,6 +61,38 @@ pub enum Message {
     ExportFolderChanged(String, TextInputState),
     /// Import folder path changed
     ImportFolderChanged(String, TextInputState),
+    /// `.npz` cube array key changed (empty = auto-detect)
+    NpzCubeKeyChanged(String, TextInputState),
+    /// Default `.npy` axis layout changed (`None` = guess from shape)
+    NpyLayoutChanged(Option<NpyLayout>),
+    /// `.npy` axis layout for the current project changed (`None` = use default)
+    ProjectNpyLayoutChanged(Option<NpyLayout>),
+    /// Pattern of the `.npy` layout rule being added changed
+    NpyLayoutRulePatternChanged(String, TextInputState),
+    /// Add a `.npy` layout rule for the entered pattern
+    AddNpyLayoutRule(NpyLayout),
+    /// Remove the `.npy` layout rule at index
+    RemoveNpyLayoutRule(usize),
+    /// Band range to load changed (1-based, e.g. "10-120"; empty = all)
+    LoadBandRangeChanged(String, TextInputState),
+    /// Band stride to load changed
+    LoadBandStrideChanged(SliderState),
+    /// Spatial binning factor to load changed
+    LoadBinningChanged(u32),
+    /// Pattern of the calibration rule being added changed
+    CalibrationPatternChanged(String, TextInputState),
+    /// Dark reference of the calibration rule being added changed
+    CalibrationDarkChanged(String, TextInputState),
+    /// White reference of the calibration rule being added changed
+    CalibrationWhiteChanged(String, TextInputState),
+    /// Gain(s) of the calibration rule being added changed
+    CalibrationGainChanged(String, TextInputState),
+    /// Offset(s) of the calibration rule being added changed
+    CalibrationOffsetChanged(String, TextInputState),
+    /// Add the entered calibration rule (`true` = for the current project)
+    AddCalibrationRule(bool),
+    /// Remove the calibration rule at index
+    RemoveCalibrationRule(usize),
     /// Folder was selected and images discovered
     FolderLoaded(ProjectState),
 
@@ -142,6 +181,94 @@ pub enum Message {
     GreenBandChanged(SliderState),
     /// Blue band slider changed
     BlueBandChanged(SliderState),
+    /// Wavelength band picker text changed
+    BandWavelengthChanged(String, TextInputState),
+    /// Select bands closest to the entered wavelength(s)
+    BandWavelengthSubmitted,
+
+    // Right Sidebar - Band Math
+    /// Band math section toggled
+    BandMathToggled(CollapsibleState),
+    /// Band math display mode selected
+    BandMathModeChanged(BandMathMode),
+    /// Expression of an output channel changed (0 = red, 1 = green, 2 = blue)
+    BandMathChannelChanged(usize, String, TextInputState),
+    /// Index expression changed
+    BandMathIndexChanged(String, TextInputState),
+    /// Compile the entered expressions and display the result
+    BandMathApply,
+    /// Name for saving the current expressions changed
+    BandMathNameChanged(String, TextInputState),
+    /// Save the current expressions under the entered name
+    BandMathSave,
+    /// Load saved expressions (by index)
+    BandMathLoad(usize),
+    /// Delete saved expressions (by index)
+    BandMathDelete(usize),
+
+    // Right Sidebar - Colormap
+    /// Colormap section toggled
+    ColormapToggled(CollapsibleState),
+    /// Colormap selected (None = band composite)
+    ColormapChanged(Option<Colormap>),
+    /// Colormap direction reversed
+    ColormapReverseToggled,
+    /// Colormap minimum text changed
+    ColormapMinChanged(String, TextInputState),
+    /// Colormap maximum text changed
+    ColormapMaxChanged(String, TextInputState),
+    /// Use the entered colormap range
+    ColormapRangeSubmitted,
+    /// Fit the colormap range to the data again
+    ColormapAutoRange,
+
+    // Right Sidebar - Principal Components
+    /// Components section toggled
+    ComponentsToggled(CollapsibleState),
+    /// Component method selected (None = band composite)
+    ComponentMethodChanged(Option<ComponentMethod>),
+    /// Component of an output channel changed (0 = red, 1 = green, 2 = blue)
+    ComponentChanged(usize, SliderState),
+
+    // Right Sidebar - True Color
+    /// True color section toggled
+    TrueColorToggled(CollapsibleState),
+    /// True-color illuminant selected (None = band composite)
+    TrueColorIlluminantChanged(Option<Illuminant>),
+
+    // Right Sidebar - Similarity Map
+    /// Similarity section toggled
+    SimilarityToggled(CollapsibleState),
+    /// Similarity metric selected
+    SimilarityMetricChanged(SimilarityMetric),
+    /// Start (or stop) picking the reference pixel on the image
+    SimilarityPickPixel,
+    /// Use the mean spectrum of the selected annotation as the reference
+    SimilarityUseAnnotation,
+    /// Show or hide the map instead of the bands
+    SimilarityShowToggled,
+    /// Threshold slider changed
+    SimilarityThresholdChanged(SliderState),
+    /// Turn the regions below the threshold into polygon annotations
+    SimilarityCreateAnnotations,
+    /// Remove the reference and the map
+    SimilarityClear,
+
+    // Right Sidebar - Preprocessing
+    /// Preprocessing section toggled
+    PreprocessingToggled(CollapsibleState),
+    /// Band exclusion text changed
+    PreprocessExcludeChanged(String, TextInputState),
+    /// Exclude the entered bands
+    PreprocessExcludeSubmitted,
+    /// Smoothing window and order text changed
+    PreprocessSmoothingChanged(String, TextInputState),
+    /// Add smoothing with the entered window and order
+    PreprocessSmoothingSubmitted,
+    /// Append a step to the chain
+    AddPreprocessStep(PreprocessStep),
+    /// Remove a step from the chain (by index)
+    RemovePreprocessStep(usize),
 
     // Right Sidebar - Image Adjustments
     /// Adjustments section toggled
@@ -154,6 +281,12 @@ pub enum Message {
     GammaChanged(SliderState),
     /// Hue slider changed
     HueChanged(SliderState),
+    /// Contrast stretch mode selected
+    StretchModeChanged(StretchMode),
+    /// Stretch standard deviation multiplier slider changed
+    StretchKChanged(SliderState),
+    /// Histogram of an output channel changed (markers dragged or log scale toggled)
+    HistogramChanged(usize, HistogramState),
     /// Reset all adjustments to defaults
     ResetAdjustments,
 
@@ -177,6 +310,18 @@ pub enum Message {
     /// Thumbnail clicked (by index)
     ThumbnailSelect(usize),
 
+    // Right Sidebar - Spectrum
+    /// Spectrum section toggled
+    SpectrumToggled(CollapsibleState),
+    /// Image pixel under the cursor changed (None = cursor left the image)
+    ImageHover(Option<(u32, u32)>),
+    /// Pin the spectrum of a pixel (None = pixel under the cursor)
+    PinSpectrum(Option<(u32, u32)>),
+    /// Remove a pinned spectrum (by index)
+    RemovePinnedSpectrum(usize),
+    /// Remove all pinned spectra
+    ClearPinnedSpectra,
+
     // Right Sidebar - Annotations Panel
     /// Annotations section toggled
     AnnotationsToggled(CollapsibleState),
@@ -186,6 +331,10 @@ pub enum Message {
     ToggleCategoryFilter(u32),
     /// Select an annotation by ID (for highlighting/scrolling to it)
     SelectAnnotation(u32),
+    /// Annotation statistics section toggled
+    AnnotationStatsToggled(CollapsibleState),
+    /// Annotation statistics band list scrolled
+    AnnotationStatsScrolled(ScrollState),
 
     // Right Sidebar Scroll
     /// Right sidebar scrolled
@@ -200,6 +349,18 @@ pub enum Message {
     DeleteAnnotation,
     /// Finish polygon annotation (close the shape)
     FinishPolygon,
+    /// Polyline line width slider changed (new and selected polylines)
+    PolylineWidthChanged(SliderState),
+    /// Brush/eraser radius slider changed
+    BrushRadiusChanged(SliderState),
+    /// Skip the next keypoint while placing keypoints (Tab key)
+    SkipKeypoint,
+    /// Keypoint names of the selected category's template changed
+    KeypointNamesChanged(String, TextInputState),
+    /// Skeleton edges of the selected category's template changed
+    KeypointSkeletonChanged(String, TextInputState),
+    /// Apply the entered keypoint template to the selected category
+    ApplyKeypointTemplate,
     /// Change the category of the selected annotation
     ChangeSelectedAnnotationCategory(u32),
 
@@ -214,6 +375,10 @@ pub enum Message {
     CloseExportDialog,
     /// Export annotations in a specific format (format id)
     ExportAnnotations(String),
+    /// Export per-annotation band statistics as CSV
+    ExportAnnotationStats,
+    /// Export the spectra of all annotated pixels as .npy
+    ExportPixelSpectra,
     /// Import annotations from file
     ImportAnnotations,
     /// Export completed successfully
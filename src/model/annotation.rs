@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::keypoint::{KEYPOINT_BBOX_PADDING, Keypoint};
+use super::mask::RleMask;
+
 /// Unique identifier for an annotation.
 pub type AnnotationId = u32;
 
@@ -11,6 +14,12 @@ pub const MIN_BBOX_SIZE: f32 = 1.0;
 /// Minimum number of vertices required for a valid polygon.
 pub const MIN_POLYGON_VERTICES: usize = 3;
 
+/// Minimum number of vertices required for a valid polyline.
+pub const MIN_POLYLINE_VERTICES: usize = 2;
+
+/// Default line width for new polylines (in image pixels).
+pub const DEFAULT_POLYLINE_WIDTH: f32 = 3.0;
+
 /// Hit radius for point annotation selection (in image pixels).
 pub const POINT_HIT_RADIUS: f32 = 10.0;
 
@@ -21,6 +30,10 @@ pub const POLYGON_CLOSE_THRESHOLD: f32 = 15.0;
 /// This is scaled by zoom level when checking.
 pub const HANDLE_HIT_RADIUS: f32 = 8.0;
 
+/// Distance of a rotated box's rotation handle beyond its top edge, in screen
+/// pixels. Scaled like [`HANDLE_HIT_RADIUS`] when hit testing.
+pub const ROTATE_HANDLE_OFFSET: f32 = 24.0;
+
 /// Handle type for bounding box corners and edges.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BBoxHandle {
@@ -45,6 +58,9 @@ pub enum BBoxHandle {
 }
 
 /// Handle type for polygon vertices and edges.
+///
+/// Also used for polylines, which have no closing edge from the last vertex
+/// back to the first.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PolygonHandle {
     /// A specific vertex index
@@ -56,15 +72,35 @@ pub enum PolygonHandle {
     Center,
 }
 
+/// Handle type for rotated bounding boxes.
+///
+/// Corners are numbered clockwise from the box's local top-left (TL, TR, BR,
+/// BL); edge `i` runs from corner `i` to corner `(i + 1) % 4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotatedBoxHandle {
+    /// A corner, resized with the opposite corner fixed
+    Corner(usize),
+    /// An edge midpoint, resized with the opposite edge fixed
+    Edge(usize),
+    /// Rotation handle above the top edge
+    Rotate,
+    /// Center (for move)
+    Center,
+}
+
 /// Unified handle type for any annotation shape.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnnotationHandle {
     /// Handle for a bounding box
     BBox(BBoxHandle),
-    /// Handle for a polygon vertex or center
+    /// Handle for a polygon or polyline vertex, edge or center
     Polygon(PolygonHandle),
+    /// Handle for a rotated bounding box
+    RotatedBox(RotatedBoxHandle),
     /// Point annotation (move the whole point)
     Point,
+    /// A keypoint of a keypoints annotation, by template index
+    Keypoint(usize),
 }
 
 /// Minimum drag distance (in image pixels) before we consider it a real drag vs a click.
@@ -136,10 +172,20 @@ pub enum AnnotationTool {
     Select,
     /// Bounding box annotation tool
     BoundingBox,
+    /// Rotated (oriented) bounding box annotation tool
+    RotatedBox,
     /// Polygon annotation tool
     Polygon,
+    /// Polyline (open path) annotation tool
+    Polyline,
     /// Point annotation tool
     Point,
+    /// Brush tool for painting masks
+    Brush,
+    /// Eraser tool for removing mask pixels
+    Eraser,
+    /// Keypoint tool, placing a category's keypoints in template order
+    Keypoints,
     /// SAM2 AI-assisted segmentation tool (requires sam2 feature)
     #[cfg(feature = "sam2")]
     SAM2Segment,
@@ -157,8 +203,13 @@ impl AnnotationTool {
         match self {
             AnnotationTool::Select => "Select",
             AnnotationTool::BoundingBox => "Bounding Box",
+            AnnotationTool::RotatedBox => "Rotated Box",
             AnnotationTool::Polygon => "Polygon",
+            AnnotationTool::Polyline => "Polyline",
             AnnotationTool::Point => "Point",
+            AnnotationTool::Brush => "Brush",
+            AnnotationTool::Eraser => "Eraser",
+            AnnotationTool::Keypoints => "Keypoints",
             #[cfg(feature = "sam2")]
             AnnotationTool::SAM2Segment => "SAM2 Segment",
         }
@@ -169,8 +220,13 @@ impl AnnotationTool {
         &[
             AnnotationTool::Select,
             AnnotationTool::BoundingBox,
+            AnnotationTool::RotatedBox,
             AnnotationTool::Polygon,
+            AnnotationTool::Polyline,
             AnnotationTool::Point,
+            AnnotationTool::Brush,
+            AnnotationTool::Eraser,
+            AnnotationTool::Keypoints,
         ]
     }
 
@@ -180,8 +236,13 @@ impl AnnotationTool {
         &[
             AnnotationTool::Select,
             AnnotationTool::BoundingBox,
+            AnnotationTool::RotatedBox,
             AnnotationTool::Polygon,
+            AnnotationTool::Polyline,
             AnnotationTool::Point,
+            AnnotationTool::Brush,
+            AnnotationTool::Eraser,
+            AnnotationTool::Keypoints,
             AnnotationTool::SAM2Segment,
         ]
     }
@@ -198,7 +259,7 @@ impl AnnotationTool {
 }
 
 /// Shape data for an annotation (in image coordinates).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AnnotationShape {
     /// Bounding box defined by top-left corner and size.
     BoundingBox {
@@ -207,10 +268,41 @@ pub enum AnnotationShape {
         width: f32,
         height: f32,
     },
+    /// Oriented bounding box defined by its center, size and rotation.
+    ///
+    /// `angle` is in radians; positive values rotate the box's local x axis
+    /// towards the image's y axis (clockwise on screen).
+    RotatedBox {
+        cx: f32,
+        cy: f32,
+        w: f32,
+        h: f32,
+        angle: f32,
+    },
     /// Single point marker.
     Point { x: f32, y: f32 },
     /// Polygon defined by vertices.
     Polygon { vertices: Vec<(f32, f32)> },
+    /// Polygon made of several rings (separate parts and holes), filled by
+    /// the even-odd rule. Vertex and edge handles are numbered across all
+    /// rings in order.
+    MultiPolygon { rings: Vec<Vec<(f32, f32)>> },
+    /// Open path through vertices, drawn with a line width in image pixels.
+    Polyline {
+        vertices: Vec<(f32, f32)>,
+        width: f32,
+    },
+    /// Raster mask; `crowd` marks COCO crowd regions (`iscrowd`).
+    Mask { mask: RleMask, crowd: bool },
+    /// Keypoints of one instance, one per entry of the category's keypoint
+    /// template, with the instance's bounding box.
+    Keypoints {
+        keypoints: Vec<Keypoint>,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
 }
 
 impl AnnotationShape {
@@ -243,27 +335,48 @@ impl AnnotationShape {
                 width,
                 height,
             } => x >= *bx && x <= bx + width && y >= *by && y <= by + height,
+            AnnotationShape::RotatedBox {
+                cx,
+                cy,
+                w,
+                h,
+                angle,
+            } => {
+                let (lx, ly) = rotate_vector(x - cx, y - cy, -angle);
+                lx.abs() <= w / 2.0 && ly.abs() <= h / 2.0
+            }
             AnnotationShape::Point { x: px, y: py } => {
                 let dx = x - px;
                 let dy = y - py;
                 (dx * dx + dy * dy).sqrt() < POINT_HIT_RADIUS
             }
-            AnnotationShape::Polygon { vertices } => {
-                // Point-in-polygon test using ray casting algorithm
-                if vertices.len() < MIN_POLYGON_VERTICES {
-                    return false;
-                }
-                let mut inside = false;
-                let mut j = vertices.len() - 1;
-                for i in 0..vertices.len() {
-                    let (xi, yi) = vertices[i];
-                    let (xj, yj) = vertices[j];
-                    if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
-                        inside = !inside;
-                    }
-                    j = i;
-                }
-                inside
+            AnnotationShape::Polygon { vertices } => ring_contains(vertices, x, y),
+            AnnotationShape::MultiPolygon { rings } => {
+                // Even-odd rule: holes are rings inside another ring
+                rings
+                    .iter()
+                    .filter(|ring| ring_contains(ring, x, y))
+                    .count()
+                    % 2
+                    == 1
+            }
+            AnnotationShape::Polyline { vertices, width } => {
+                // Thin lines stay selectable within the point hit radius
+                let reach = (width / 2.0).max(POINT_HIT_RADIUS / 2.0);
+                polyline_distance(vertices, x, y).is_some_and(|d| d <= reach)
+            }
+            AnnotationShape::Mask { mask, .. } => mask.contains(x, y),
+            AnnotationShape::Keypoints {
+                keypoints,
+                x: bx,
+                y: by,
+                width,
+                height,
+            } => {
+                (x >= *bx && x <= bx + width && y >= *by && y <= by + height)
+                    || keypoints.iter().any(|k| {
+                        k.is_labeled() && point_distance(x, y, k.x, k.y) < POINT_HIT_RADIUS
+                    })
             }
         }
     }
@@ -326,6 +439,43 @@ impl AnnotationShape {
 
                 None
             }
+            AnnotationShape::RotatedBox {
+                cx,
+                cy,
+                w,
+                h,
+                angle,
+            } => {
+                let corners = rotated_box_corners(*cx, *cy, *w, *h, *angle);
+
+                // Rotation handle sits beyond the top edge midpoint
+                let offset = h / 2.0 + hit_radius * ROTATE_HANDLE_OFFSET / HANDLE_HIT_RADIUS;
+                let (rx, ry) = rotate_vector(0.0, -offset, *angle);
+                if point_distance(x, y, cx + rx, cy + ry) <= hit_radius {
+                    return Some(AnnotationHandle::RotatedBox(RotatedBoxHandle::Rotate));
+                }
+
+                for (i, (hx, hy)) in corners.iter().enumerate() {
+                    if point_distance(x, y, *hx, *hy) <= hit_radius {
+                        return Some(AnnotationHandle::RotatedBox(RotatedBoxHandle::Corner(i)));
+                    }
+                }
+
+                for i in 0..4 {
+                    let (x1, y1) = corners[i];
+                    let (x2, y2) = corners[(i + 1) % 4];
+                    let (mx, my) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+                    if point_distance(x, y, mx, my) <= hit_radius {
+                        return Some(AnnotationHandle::RotatedBox(RotatedBoxHandle::Edge(i)));
+                    }
+                }
+
+                if self.contains_point(x, y) || point_distance(x, y, *cx, *cy) <= hit_radius {
+                    return Some(AnnotationHandle::RotatedBox(RotatedBoxHandle::Center));
+                }
+
+                None
+            }
             AnnotationShape::Point { x: px, y: py } => {
                 if point_distance(x, y, *px, *py) <= hit_radius.max(POINT_HIT_RADIUS) {
                     Some(AnnotationHandle::Point)
@@ -369,6 +519,94 @@ impl AnnotationShape {
 
                 None
             }
+            AnnotationShape::MultiPolygon { rings } => {
+                // Check vertices first (highest priority)
+                for (i, (vx, vy)) in rings.iter().flatten().enumerate() {
+                    if point_distance(x, y, *vx, *vy) <= hit_radius {
+                        return Some(AnnotationHandle::Polygon(PolygonHandle::Vertex(i)));
+                    }
+                }
+
+                // Check edges (second priority), each ring closed on itself
+                let mut offset = 0;
+                for ring in rings {
+                    for i in 0..ring.len() {
+                        let (x1, y1) = ring[i];
+                        let (x2, y2) = ring[(i + 1) % ring.len()];
+                        let (dist, _t) = point_to_segment_distance(x, y, x1, y1, x2, y2);
+                        if dist <= hit_radius {
+                            return Some(AnnotationHandle::Polygon(PolygonHandle::Edge {
+                                index: offset + i,
+                            }));
+                        }
+                    }
+                    offset += ring.len();
+                }
+
+                // Check center (for move), the vertex average as drawn
+                if vertex_average(rings.iter().flatten())
+                    .is_some_and(|(cx, cy)| point_distance(x, y, cx, cy) <= hit_radius)
+                {
+                    return Some(AnnotationHandle::Polygon(PolygonHandle::Center));
+                }
+
+                // Check if inside the filled area (also move)
+                if self.contains_point(x, y) {
+                    return Some(AnnotationHandle::Polygon(PolygonHandle::Center));
+                }
+
+                None
+            }
+            AnnotationShape::Polyline { vertices, .. } => {
+                // Check vertices first (highest priority)
+                for (i, (vx, vy)) in vertices.iter().enumerate() {
+                    if point_distance(x, y, *vx, *vy) <= hit_radius {
+                        return Some(AnnotationHandle::Polygon(PolygonHandle::Vertex(i)));
+                    }
+                }
+
+                // Check center (for move) - the midpoint along the path
+                let midpoint = polyline_midpoint(vertices);
+                if midpoint.is_some_and(|(cx, cy)| point_distance(x, y, cx, cy) <= hit_radius) {
+                    return Some(AnnotationHandle::Polygon(PolygonHandle::Center));
+                }
+
+                // Check segments (for vertex insertion) - no closing edge
+                for (i, pair) in vertices.windows(2).enumerate() {
+                    let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                    let (dist, _t) = point_to_segment_distance(x, y, x1, y1, x2, y2);
+                    if dist <= hit_radius {
+                        return Some(AnnotationHandle::Polygon(PolygonHandle::Edge { index: i }));
+                    }
+                }
+
+                // Check if on the line body (also move)
+                if self.contains_point(x, y) {
+                    return Some(AnnotationHandle::Polygon(PolygonHandle::Center));
+                }
+
+                None
+            }
+            // Masks have no handles; the whole mask moves
+            AnnotationShape::Mask { mask, .. } => mask
+                .contains(x, y)
+                .then_some(AnnotationHandle::Polygon(PolygonHandle::Center)),
+            AnnotationShape::Keypoints { keypoints, .. } => {
+                // Keypoints first (highest priority), then the instance box
+                for (i, keypoint) in keypoints.iter().enumerate() {
+                    if keypoint.is_labeled()
+                        && point_distance(x, y, keypoint.x, keypoint.y) <= hit_radius
+                    {
+                        return Some(AnnotationHandle::Keypoint(i));
+                    }
+                }
+                self.keypoint_box()
+                    .and_then(|bbox| bbox.hit_test_handle(x, y, hit_radius))
+                    .or_else(|| {
+                        self.contains_point(x, y)
+                            .then_some(AnnotationHandle::BBox(BBoxHandle::Center))
+                    })
+            }
         }
     }
 
@@ -379,16 +617,39 @@ impl AnnotationShape {
                 *x += dx;
                 *y += dy;
             }
+            AnnotationShape::RotatedBox { cx, cy, .. } => {
+                *cx += dx;
+                *cy += dy;
+            }
             AnnotationShape::Point { x, y } => {
                 *x += dx;
                 *y += dy;
             }
-            AnnotationShape::Polygon { vertices } => {
+            AnnotationShape::Polygon { vertices } | AnnotationShape::Polyline { vertices, .. } => {
                 for (vx, vy) in vertices.iter_mut() {
                     *vx += dx;
                     *vy += dy;
                 }
             }
+            AnnotationShape::MultiPolygon { rings } => {
+                for (vx, vy) in rings.iter_mut().flatten() {
+                    *vx += dx;
+                    *vy += dy;
+                }
+            }
+            AnnotationShape::Mask { mask, .. } => {
+                mask.translate(dx.round() as i32, dy.round() as i32);
+            }
+            AnnotationShape::Keypoints {
+                keypoints, x, y, ..
+            } => {
+                *x += dx;
+                *y += dy;
+                for keypoint in keypoints.iter_mut().filter(|k| k.is_labeled()) {
+                    keypoint.x += dx;
+                    keypoint.y += dy;
+                }
+            }
         }
     }
 
@@ -451,6 +712,68 @@ impl AnnotationShape {
                     None
                 }
             }
+            (
+                AnnotationShape::RotatedBox {
+                    cx,
+                    cy,
+                    w,
+                    h,
+                    angle,
+                },
+                AnnotationHandle::RotatedBox(rbox_handle),
+            ) => {
+                // Signs of the local axes that grow when the handle moves outward
+                let (sx, sy): (f32, f32) = match rbox_handle {
+                    RotatedBoxHandle::Center => {
+                        return Some(AnnotationShape::RotatedBox {
+                            cx: cx + dx,
+                            cy: cy + dy,
+                            w: *w,
+                            h: *h,
+                            angle: *angle,
+                        });
+                    }
+                    RotatedBoxHandle::Rotate => {
+                        let start = (start_y - cy).atan2(start_x - cx);
+                        let current = (current_y - cy).atan2(current_x - cx);
+                        return Some(AnnotationShape::RotatedBox {
+                            cx: *cx,
+                            cy: *cy,
+                            w: *w,
+                            h: *h,
+                            angle: normalize_angle(angle + current - start),
+                        });
+                    }
+                    RotatedBoxHandle::Corner(0) => (-1.0, -1.0),
+                    RotatedBoxHandle::Corner(1) => (1.0, -1.0),
+                    RotatedBoxHandle::Corner(2) => (1.0, 1.0),
+                    RotatedBoxHandle::Corner(3) => (-1.0, 1.0),
+                    RotatedBoxHandle::Edge(0) => (0.0, -1.0),
+                    RotatedBoxHandle::Edge(1) => (1.0, 0.0),
+                    RotatedBoxHandle::Edge(2) => (0.0, 1.0),
+                    RotatedBoxHandle::Edge(3) => (-1.0, 0.0),
+                    _ => return None,
+                };
+
+                // Resize in the box frame, keeping the opposite side fixed
+                let (ldx, ldy) = rotate_vector(dx, dy, -angle);
+                let (ldx, ldy) = (ldx * sx.abs(), ldy * sy.abs());
+                let new_w = (w + sx * ldx).abs();
+                let new_h = (h + sy * ldy).abs();
+                let (ox, oy) = rotate_vector(ldx / 2.0, ldy / 2.0, *angle);
+
+                if new_w >= MIN_BBOX_SIZE && new_h >= MIN_BBOX_SIZE {
+                    Some(AnnotationShape::RotatedBox {
+                        cx: cx + ox,
+                        cy: cy + oy,
+                        w: new_w,
+                        h: new_h,
+                        angle: *angle,
+                    })
+                } else {
+                    None
+                }
+            }
             (AnnotationShape::Point { x, y }, AnnotationHandle::Point) => {
                 Some(AnnotationShape::Point {
                     x: x + dx,
@@ -482,6 +805,90 @@ impl AnnotationShape {
                     vertices: new_vertices,
                 })
             }
+            (AnnotationShape::MultiPolygon { rings }, AnnotationHandle::Polygon(poly_handle)) => {
+                let mut new_rings = rings.clone();
+                match poly_handle {
+                    PolygonHandle::Vertex(idx) => {
+                        if let Some((vx, vy)) = new_rings.iter_mut().flatten().nth(*idx) {
+                            *vx += dx;
+                            *vy += dy;
+                        }
+                    }
+                    // Edge handles are used for insertion, not dragging
+                    PolygonHandle::Edge { .. } => return None,
+                    PolygonHandle::Center => {
+                        for (vx, vy) in new_rings.iter_mut().flatten() {
+                            *vx += dx;
+                            *vy += dy;
+                        }
+                    }
+                }
+                Some(AnnotationShape::MultiPolygon { rings: new_rings })
+            }
+            (
+                AnnotationShape::Polyline { vertices, width },
+                AnnotationHandle::Polygon(poly_handle),
+            ) => {
+                let mut new_vertices = vertices.clone();
+                match poly_handle {
+                    PolygonHandle::Vertex(idx) => {
+                        if *idx < new_vertices.len() {
+                            new_vertices[*idx].0 += dx;
+                            new_vertices[*idx].1 += dy;
+                        }
+                    }
+                    // Edge handles are used for insertion, not dragging
+                    PolygonHandle::Edge { .. } => return None,
+                    PolygonHandle::Center => {
+                        for (vx, vy) in new_vertices.iter_mut() {
+                            *vx += dx;
+                            *vy += dy;
+                        }
+                    }
+                }
+                Some(AnnotationShape::Polyline {
+                    vertices: new_vertices,
+                    width: *width,
+                })
+            }
+            (AnnotationShape::Mask { .. }, AnnotationHandle::Polygon(PolygonHandle::Center))
+            | (AnnotationShape::Keypoints { .. }, AnnotationHandle::BBox(BBoxHandle::Center)) => {
+                let mut moved = original.clone();
+                moved.translate(dx, dy);
+                Some(moved)
+            }
+            (AnnotationShape::Keypoints { keypoints, .. }, AnnotationHandle::Keypoint(idx)) => {
+                let mut new_keypoints = keypoints.clone();
+                let keypoint = new_keypoints.get_mut(*idx)?;
+                keypoint.x += dx;
+                keypoint.y += dy;
+                let mut moved = original.clone();
+                if let AnnotationShape::Keypoints { keypoints, .. } = &mut moved {
+                    *keypoints = new_keypoints;
+                }
+                Some(moved)
+            }
+            (AnnotationShape::Keypoints { keypoints, .. }, AnnotationHandle::BBox(_)) => {
+                // Resizing the instance box leaves the keypoints in place
+                let bbox = original.keypoint_box()?;
+                match Self::apply_handle_drag(
+                    &bbox, handle, start_x, start_y, current_x, current_y,
+                )? {
+                    AnnotationShape::BoundingBox {
+                        x,
+                        y,
+                        width,
+                        height,
+                    } => Some(AnnotationShape::Keypoints {
+                        keypoints: keypoints.clone(),
+                        x,
+                        y,
+                        width,
+                        height,
+                    }),
+                    _ => None,
+                }
+            }
             _ => None, // Mismatched shape and handle types
         }
     }
@@ -495,33 +902,163 @@ impl AnnotationShape {
                 width,
                 height,
             } => (*x, *y, x + width, y + height),
+            AnnotationShape::RotatedBox { .. } => {
+                let corners = self.rotated_box_corners().unwrap_or_default();
+                let xs = corners.iter().map(|(x, _)| *x);
+                let ys = corners.iter().map(|(_, y)| *y);
+                (
+                    xs.clone().fold(f32::MAX, f32::min),
+                    ys.clone().fold(f32::MAX, f32::min),
+                    xs.fold(f32::MIN, f32::max),
+                    ys.fold(f32::MIN, f32::max),
+                )
+            }
             AnnotationShape::Point { x, y } => (*x, *y, *x, *y),
-            AnnotationShape::Polygon { vertices } => {
-                if vertices.is_empty() {
-                    return (0.0, 0.0, 0.0, 0.0);
-                }
-                let mut min_x = f32::MAX;
-                let mut min_y = f32::MAX;
-                let mut max_x = f32::MIN;
-                let mut max_y = f32::MIN;
-                for (vx, vy) in vertices {
-                    min_x = min_x.min(*vx);
-                    min_y = min_y.min(*vy);
-                    max_x = max_x.max(*vx);
-                    max_y = max_y.max(*vy);
+            AnnotationShape::Polygon { vertices } | AnnotationShape::Polyline { vertices, .. } => {
+                vertex_bounds(vertices.iter())
+            }
+            AnnotationShape::MultiPolygon { rings } => vertex_bounds(rings.iter().flatten()),
+            AnnotationShape::Mask { mask, .. } => (
+                mask.x as f32,
+                mask.y as f32,
+                (mask.x + mask.width) as f32,
+                (mask.y + mask.height) as f32,
+            ),
+            AnnotationShape::Keypoints {
+                keypoints,
+                x,
+                y,
+                width,
+                height,
+            } => keypoints.iter().filter(|k| k.is_labeled()).fold(
+                (*x, *y, x + width, y + height),
+                |(min_x, min_y, max_x, max_y), k| {
+                    (
+                        min_x.min(k.x),
+                        min_y.min(k.y),
+                        max_x.max(k.x),
+                        max_y.max(k.y),
+                    )
+                },
+            ),
+        }
+    }
+
+    /// Indices (`y * width + x`) of the image pixels covered by this shape.
+    ///
+    /// A pixel is covered when its center lies inside the shape; a point
+    /// covers the pixel it lies in, keypoints cover the pixels of their
+    /// labeled points, a polyline covers the pixels within half its line
+    /// width and a mask covers its foreground. Pixels outside the image are
+    /// skipped.
+    pub fn mask_pixels(&self, width: u32, height: u32) -> Vec<usize> {
+        let in_image =
+            |x: f32, y: f32| x >= 0.0 && y >= 0.0 && x < width as f32 && y < height as f32;
+        match self {
+            AnnotationShape::Point { x, y } => {
+                return if in_image(*x, *y) {
+                    vec![*y as usize * width as usize + *x as usize]
+                } else {
+                    Vec::new()
+                };
+            }
+            AnnotationShape::Mask { mask, .. } => return mask.pixels(width, height),
+            AnnotationShape::Keypoints { keypoints, .. } => {
+                let mut pixels: Vec<usize> = keypoints
+                    .iter()
+                    .filter(|k| k.is_labeled() && in_image(k.x, k.y))
+                    .map(|k| k.y as usize * width as usize + k.x as usize)
+                    .collect();
+                pixels.sort_unstable();
+                pixels.dedup();
+                return pixels;
+            }
+            _ => {}
+        }
+
+        // Polylines cover their stroke, which extends past the vertices
+        let pad = match self {
+            AnnotationShape::Polyline { width, .. } => stroke_half_width(*width),
+            _ => 0.0,
+        };
+        let (min_x, min_y, max_x, max_y) = self.bounding_box();
+        let (min_x, min_y, max_x, max_y) = (min_x - pad, min_y - pad, max_x + pad, max_y + pad);
+        let x_range = (min_x.floor().max(0.0) as u32)..(max_x.ceil().max(0.0) as u32).min(width);
+        let y_range = (min_y.floor().max(0.0) as u32)..(max_y.ceil().max(0.0) as u32).min(height);
+        let mut pixels = Vec::new();
+        for y in y_range {
+            for x in x_range.clone() {
+                if self.covers_pixel_center(x as f32 + 0.5, y as f32 + 0.5) {
+                    pixels.push(y as usize * width as usize + x as usize);
                 }
-                (min_x, min_y, max_x, max_y)
             }
         }
+        pixels
     }
 
-    /// Remove a vertex from a polygon at the given index.
+    /// Whether a pixel center counts as covered for [`Self::mask_pixels`].
+    fn covers_pixel_center(&self, x: f32, y: f32) -> bool {
+        match self {
+            AnnotationShape::Polyline { vertices, width } => {
+                polyline_distance(vertices, x, y).is_some_and(|d| d <= stroke_half_width(*width))
+            }
+            _ => self.contains_point(x, y),
+        }
+    }
+
+    /// Remove a vertex from a polygon or polyline at the given index.
     /// Returns the new shape if successful, or None if:
-    /// - The shape is not a polygon
+    /// - The shape is not a polygon or polyline
     /// - The index is out of bounds
     /// - Removing the vertex would leave fewer than MIN_POLYGON_VERTICES
+    ///   (MIN_POLYLINE_VERTICES for polylines)
+    ///
+    /// A multipolygon ring that would drop below MIN_POLYGON_VERTICES is
+    /// removed instead; a multipolygon left with one ring becomes a polygon.
     pub fn remove_polygon_vertex(&self, vertex_index: usize) -> Option<AnnotationShape> {
         match self {
+            AnnotationShape::Polyline { vertices, width } => {
+                if vertex_index >= vertices.len() {
+                    log::warn!(
+                        "Cannot remove vertex {}: index out of bounds (len={})",
+                        vertex_index,
+                        vertices.len()
+                    );
+                    return None;
+                }
+                if vertices.len() <= MIN_POLYLINE_VERTICES {
+                    log::warn!(
+                        "Cannot remove vertex: polyline has minimum {} vertices",
+                        MIN_POLYLINE_VERTICES
+                    );
+                    return None;
+                }
+                let mut new_vertices = vertices.clone();
+                new_vertices.remove(vertex_index);
+                Some(AnnotationShape::Polyline {
+                    vertices: new_vertices,
+                    width: *width,
+                })
+            }
+            AnnotationShape::MultiPolygon { rings } => {
+                let Some((ring, index)) = ring_vertex_index(rings, vertex_index) else {
+                    log::warn!(
+                        "Cannot remove vertex {}: index out of bounds (len={})",
+                        vertex_index,
+                        rings.iter().map(Vec::len).sum::<usize>()
+                    );
+                    return None;
+                };
+                let mut new_rings = rings.clone();
+                if new_rings[ring].len() > MIN_POLYGON_VERTICES {
+                    new_rings[ring].remove(index);
+                } else {
+                    // A ring at its minimum is dropped as a whole
+                    log::info!("Removing ring {} of multipolygon", ring);
+                    new_rings.remove(ring);
+                }
+                AnnotationShape::from_rings(new_rings)
+            }
             AnnotationShape::Polygon { vertices } => {
                 if vertex_index >= vertices.len() {
                     log::warn!(
@@ -545,7 +1082,7 @@ impl AnnotationShape {
                 })
             }
             _ => {
-                log::warn!("Cannot remove vertex: shape is not a polygon");
+                log::warn!("Cannot remove vertex: shape is not a polygon or polyline");
                 None
             }
         }
@@ -554,8 +1091,9 @@ impl AnnotationShape {
     /// Insert a new vertex into a polygon on the edge at the given index.
     /// The vertex is inserted between vertices at `edge_index` and `(edge_index + 1) % len`.
     /// The position (x, y) is the location where the new vertex will be placed.
+    /// Polylines have no closing edge, so their last edge index is `len - 2`.
     /// Returns the new shape if successful, or None if:
-    /// - The shape is not a polygon
+    /// - The shape is not a polygon or polyline
     /// - The edge index is out of bounds
     pub fn insert_polygon_vertex(
         &self,
@@ -580,13 +1118,106 @@ impl AnnotationShape {
                     vertices: new_vertices,
                 })
             }
+            AnnotationShape::MultiPolygon { rings } => {
+                let Some((ring, index)) = ring_vertex_index(rings, edge_index) else {
+                    log::warn!(
+                        "Cannot insert vertex on edge {}: index out of bounds (len={})",
+                        edge_index,
+                        rings.iter().map(Vec::len).sum::<usize>()
+                    );
+                    return None;
+                };
+                let mut new_rings = rings.clone();
+                new_rings[ring].insert(index + 1, (x, y));
+                Some(AnnotationShape::MultiPolygon { rings: new_rings })
+            }
+            AnnotationShape::Polyline { vertices, width } => {
+                if edge_index + 1 >= vertices.len() {
+                    log::warn!(
+                        "Cannot insert vertex on edge {}: index out of bounds (len={})",
+                        edge_index,
+                        vertices.len()
+                    );
+                    return None;
+                }
+                let mut new_vertices = vertices.clone();
+                new_vertices.insert(edge_index + 1, (x, y));
+                Some(AnnotationShape::Polyline {
+                    vertices: new_vertices,
+                    width: *width,
+                })
+            }
             _ => {
-                log::warn!("Cannot insert vertex: shape is not a polygon");
+                log::warn!("Cannot insert vertex: shape is not a polygon or polyline");
                 None
             }
         }
     }
 
+    /// Corners of a rotated box in clockwise order (TL, TR, BR, BL of the
+    /// unrotated box). Returns None for other shapes.
+    pub fn rotated_box_corners(&self) -> Option<[(f32, f32); 4]> {
+        match self {
+            AnnotationShape::RotatedBox {
+                cx,
+                cy,
+                w,
+                h,
+                angle,
+            } => Some(rotated_box_corners(*cx, *cy, *w, *h, *angle)),
+            _ => None,
+        }
+    }
+
+    /// Convert a rotated box into an equivalent four-vertex polygon, for
+    /// formats that cannot represent oriented boxes. Other shapes are
+    /// returned unchanged.
+    pub fn to_polygon(&self) -> AnnotationShape {
+        match self.rotated_box_corners() {
+            Some(corners) => AnnotationShape::Polygon {
+                vertices: corners.to_vec(),
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Create a rotated box from four corners in drawing order, as stored by
+    /// oriented box formats. The first edge sets the width and the angle.
+    pub fn rotated_box_from_corners(corners: [(f32, f32); 4]) -> Self {
+        let cx = corners.iter().map(|(x, _)| x).sum::<f32>() / 4.0;
+        let cy = corners.iter().map(|(_, y)| y).sum::<f32>() / 4.0;
+        let (x0, y0) = corners[0];
+        let (x1, y1) = corners[1];
+        let (x2, y2) = corners[2];
+        AnnotationShape::RotatedBox {
+            cx,
+            cy,
+            w: point_distance(x0, y0, x1, y1),
+            h: point_distance(x1, y1, x2, y2),
+            angle: (y1 - y0).atan2(x1 - x0),
+        }
+    }
+
+    /// Convert an axis-aligned bounding box into an unrotated rotated box.
+    /// Other shapes are returned unchanged.
+    pub fn to_rotated_box(&self) -> AnnotationShape {
+        match self {
+            AnnotationShape::BoundingBox {
+                x,
+                y,
+                width,
+                height,
+            } => AnnotationShape::RotatedBox {
+                cx: x + width / 2.0,
+                cy: y + height / 2.0,
+                w: *width,
+                h: *height,
+                angle: 0.0,
+            },
+            _ => self.clone(),
+        }
+    }
+
     /// Check if this shape is a polygon.
     pub fn is_polygon(&self) -> bool {
         matches!(self, AnnotationShape::Polygon { .. })
@@ -599,6 +1230,197 @@ impl AnnotationShape {
             _ => None,
         }
     }
+
+    /// Check if this shape is a multipolygon.
+    pub fn is_multipolygon(&self) -> bool {
+        matches!(self, AnnotationShape::MultiPolygon { .. })
+    }
+
+    /// Check if this shape is a polyline.
+    pub fn is_polyline(&self) -> bool {
+        matches!(self, AnnotationShape::Polyline { .. })
+    }
+
+    /// Check if this shape has editable vertices (polygon, multipolygon or
+    /// polyline).
+    pub fn has_vertices(&self) -> bool {
+        self.is_polygon() || self.is_multipolygon() || self.is_polyline()
+    }
+
+    /// Number of editable vertices (across all rings of a multipolygon).
+    pub fn vertex_count(&self) -> usize {
+        match self {
+            AnnotationShape::Polygon { vertices } | AnnotationShape::Polyline { vertices, .. } => {
+                vertices.len()
+            }
+            AnnotationShape::MultiPolygon { rings } => rings.iter().map(Vec::len).sum(),
+            _ => 0,
+        }
+    }
+
+    /// Build a polygon from rings, dropping rings with fewer than
+    /// MIN_POLYGON_VERTICES. A single ring gives a plain polygon; None if
+    /// no ring is left.
+    pub fn from_rings(rings: Vec<Vec<(f32, f32)>>) -> Option<AnnotationShape> {
+        let mut rings: Vec<_> = rings
+            .into_iter()
+            .filter(|ring| ring.len() >= MIN_POLYGON_VERTICES)
+            .collect();
+        match rings.len() {
+            0 => None,
+            1 => rings
+                .pop()
+                .map(|vertices| AnnotationShape::Polygon { vertices }),
+            _ => Some(AnnotationShape::MultiPolygon { rings }),
+        }
+    }
+
+    /// Check if this shape is a raster mask.
+    pub fn is_mask(&self) -> bool {
+        matches!(self, AnnotationShape::Mask { .. })
+    }
+
+    /// Rasterize a box, polygon or polyline into a mask of the pixels it
+    /// covers in an image of the given size. Returns None for points,
+    /// keypoints and masks, or if no pixel is covered.
+    pub fn to_mask(&self, image_width: u32, image_height: u32) -> Option<AnnotationShape> {
+        if matches!(
+            self,
+            AnnotationShape::Point { .. }
+                | AnnotationShape::Mask { .. }
+                | AnnotationShape::Keypoints { .. }
+        ) {
+            return None;
+        }
+        let mask = RleMask::from_pixels(&self.mask_pixels(image_width, image_height), image_width);
+        (!mask.is_empty()).then_some(AnnotationShape::Mask { mask, crowd: false })
+    }
+
+    /// Build a keypoints annotation whose instance box encloses the labeled
+    /// keypoints, padded by KEYPOINT_BBOX_PADDING. None if no keypoint is
+    /// labeled.
+    pub fn keypoints_from_points(keypoints: Vec<Keypoint>) -> Option<AnnotationShape> {
+        let labeled: Vec<(f32, f32)> = keypoints
+            .iter()
+            .filter(|k| k.is_labeled())
+            .map(|k| (k.x, k.y))
+            .collect();
+        if labeled.is_empty() {
+            return None;
+        }
+        let (min_x, min_y, max_x, max_y) = vertex_bounds(labeled.iter());
+        Some(AnnotationShape::Keypoints {
+            keypoints,
+            x: min_x - KEYPOINT_BBOX_PADDING,
+            y: min_y - KEYPOINT_BBOX_PADDING,
+            width: max_x - min_x + 2.0 * KEYPOINT_BBOX_PADDING,
+            height: max_y - min_y + 2.0 * KEYPOINT_BBOX_PADDING,
+        })
+    }
+
+    /// Check if this shape is a keypoints annotation.
+    pub fn is_keypoints(&self) -> bool {
+        matches!(self, AnnotationShape::Keypoints { .. })
+    }
+
+    /// The instance box of a keypoints annotation. None for other shapes.
+    fn keypoint_box(&self) -> Option<AnnotationShape> {
+        match self {
+            AnnotationShape::Keypoints {
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => Some(AnnotationShape::BoundingBox {
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Which rings of an even-odd polygon are holes, i.e. lie inside an odd
+/// number of the other rings.
+pub fn ring_holes(rings: &[Vec<(f32, f32)>]) -> Vec<bool> {
+    rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            // Test an edge midpoint: traced rings may share corners, never edges
+            let Some((mx, my)) = ring
+                .first()
+                .zip(ring.get(1))
+                .map(|((x1, y1), (x2, y2))| ((x1 + x2) / 2.0, (y1 + y2) / 2.0))
+            else {
+                return false;
+            };
+            rings
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| *j != i && ring_contains(other, mx, my))
+                .count()
+                % 2
+                == 1
+        })
+        .collect()
+}
+
+/// Point-in-polygon test for a single closed ring using ray casting.
+fn ring_contains(vertices: &[(f32, f32)], x: f32, y: f32) -> bool {
+    if vertices.len() < MIN_POLYGON_VERTICES {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = vertices.len() - 1;
+    for i in 0..vertices.len() {
+        let (xi, yi) = vertices[i];
+        let (xj, yj) = vertices[j];
+        if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Map a vertex (or edge) index counted across all rings to (ring, index).
+fn ring_vertex_index(rings: &[Vec<(f32, f32)>], mut index: usize) -> Option<(usize, usize)> {
+    for (ring, vertices) in rings.iter().enumerate() {
+        if index < vertices.len() {
+            return Some((ring, index));
+        }
+        index -= vertices.len();
+    }
+    None
+}
+
+/// Bounds (min_x, min_y, max_x, max_y) of a set of vertices; all zero if empty.
+fn vertex_bounds<'a>(vertices: impl Iterator<Item = &'a (f32, f32)>) -> (f32, f32, f32, f32) {
+    let mut bounds = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    let mut any = false;
+    for (vx, vy) in vertices {
+        bounds.0 = bounds.0.min(*vx);
+        bounds.1 = bounds.1.min(*vy);
+        bounds.2 = bounds.2.max(*vx);
+        bounds.3 = bounds.3.max(*vy);
+        any = true;
+    }
+    if any { bounds } else { (0.0, 0.0, 0.0, 0.0) }
+}
+
+/// Average of a set of vertices. None if empty.
+fn vertex_average<'a>(vertices: impl Iterator<Item = &'a (f32, f32)>) -> Option<(f32, f32)> {
+    let (mut sum_x, mut sum_y, mut n) = (0.0, 0.0, 0);
+    for (vx, vy) in vertices {
+        sum_x += vx;
+        sum_y += vy;
+        n += 1;
+    }
+    (n > 0).then(|| (sum_x / n as f32, sum_y / n as f32))
 }
 
 /// Calculate distance between two points.
@@ -608,6 +1430,32 @@ fn point_distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
     (dx * dx + dy * dy).sqrt()
 }
 
+/// Rotate a vector by `angle` radians (positive turns +x towards +y).
+fn rotate_vector(x: f32, y: f32, angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+/// Wrap an angle into the range (-PI, PI].
+fn normalize_angle(angle: f32) -> f32 {
+    use std::f32::consts::{PI, TAU};
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    if wrapped <= -PI {
+        wrapped + TAU
+    } else {
+        wrapped
+    }
+}
+
+/// Corners of a rotated box in clockwise order, starting at the local top-left.
+pub fn rotated_box_corners(cx: f32, cy: f32, w: f32, h: f32, angle: f32) -> [(f32, f32); 4] {
+    let (hw, hh) = (w / 2.0, h / 2.0);
+    [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)].map(|(lx, ly)| {
+        let (rx, ry) = rotate_vector(lx, ly, angle);
+        (cx + rx, cy + ry)
+    })
+}
+
 /// Calculate the shortest distance from a point to a line segment.
 /// Returns the distance and the interpolation factor t (0.0-1.0) along the segment.
 fn point_to_segment_distance(px: f32, py: f32, x1: f32, y1: f32, x2: f32, y2: f32) -> (f32, f32) {
@@ -631,6 +1479,48 @@ fn point_to_segment_distance(px: f32, py: f32, x1: f32, y1: f32, x2: f32, y2: f3
     (point_distance(px, py, closest_x, closest_y), t_clamped)
 }
 
+/// Shortest distance from a point to an open path, or None if the path has
+/// no vertices.
+fn polyline_distance(vertices: &[(f32, f32)], x: f32, y: f32) -> Option<f32> {
+    match vertices {
+        [] => None,
+        [(vx, vy)] => Some(point_distance(x, y, *vx, *vy)),
+        _ => vertices
+            .windows(2)
+            .map(|pair| {
+                let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                point_to_segment_distance(x, y, x1, y1, x2, y2).0
+            })
+            .reduce(f32::min),
+    }
+}
+
+/// Point halfway along an open path, measured by arc length.
+fn polyline_midpoint(vertices: &[(f32, f32)]) -> Option<(f32, f32)> {
+    let first = *vertices.first()?;
+    let length: f32 = vertices
+        .windows(2)
+        .map(|p| point_distance(p[0].0, p[0].1, p[1].0, p[1].1))
+        .sum();
+    let mut remaining = length / 2.0;
+    for pair in vertices.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        let segment = point_distance(x1, y1, x2, y2);
+        if segment > 0.0 && remaining <= segment {
+            let t = remaining / segment;
+            return Some((x1 + t * (x2 - x1), y1 + t * (y2 - y1)));
+        }
+        remaining -= segment;
+    }
+    Some(first)
+}
+
+/// Half of a polyline's line width, at least half a pixel so that thin lines
+/// still cover the pixels they pass through.
+fn stroke_half_width(width: f32) -> f32 {
+    (width / 2.0).max(0.5)
+}
+
 /// Calculate the geometric centroid (center of mass) of a polygon.
 /// Uses the shoelace formula to compute the true centroid weighted by area,
 /// not just the average of vertices.
@@ -715,8 +1605,35 @@ pub enum DrawingState {
         current_x: f32,
         current_y: f32,
     },
+    /// Drawing a rotated box - dragged out axis-aligned, rotated afterwards
+    /// with its rotation handle.
+    RotatedBox {
+        start_x: f32,
+        start_y: f32,
+        current_x: f32,
+        current_y: f32,
+    },
     /// Drawing a polygon - stores vertices added so far.
     Polygon { vertices: Vec<(f32, f32)> },
+    /// Drawing a polyline - stores vertices added so far and the line width.
+    Polyline {
+        vertices: Vec<(f32, f32)>,
+        width: f32,
+    },
+    /// Painting or erasing a mask annotation - strokes are applied to the
+    /// annotation as they happen, so this only tracks the stroke.
+    Brush {
+        annotation_id: AnnotationId,
+        last_x: f32,
+        last_y: f32,
+        erase: bool,
+    },
+    /// Placing keypoints in template order - stores the keypoints placed or
+    /// skipped so far and the number of keypoints in the template.
+    Keypoints {
+        keypoints: Vec<Keypoint>,
+        count: usize,
+    },
 }
 
 impl Default for DrawingState {
@@ -743,6 +1660,15 @@ impl DrawingState {
             } => AnnotationShape::bounding_box_from_corners(
                 *start_x, *start_y, *current_x, *current_y,
             ),
+            DrawingState::RotatedBox {
+                start_x,
+                start_y,
+                current_x,
+                current_y,
+            } => AnnotationShape::bounding_box_from_corners(
+                *start_x, *start_y, *current_x, *current_y,
+            )
+            .map(|bbox| bbox.to_rotated_box()),
             DrawingState::Polygon { vertices } => {
                 if vertices.len() >= MIN_POLYGON_VERTICES {
                     Some(AnnotationShape::Polygon {
@@ -752,6 +1678,271 @@ impl DrawingState {
                     None
                 }
             }
+            DrawingState::Polyline { vertices, width } => {
+                if vertices.len() >= MIN_POLYLINE_VERTICES {
+                    Some(AnnotationShape::Polyline {
+                        vertices: vertices.clone(),
+                        width: *width,
+                    })
+                } else {
+                    None
+                }
+            }
+            DrawingState::Brush { .. } => None,
+            DrawingState::Keypoints { keypoints, count } => {
+                // Keypoints not reached yet count as not labeled
+                let mut keypoints = keypoints.clone();
+                keypoints.resize(*count, Keypoint::unlabeled());
+                AnnotationShape::keypoints_from_points(keypoints)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_pixels() {
+        // 2x2 box covering pixels (1..3, 1..3) of a 4x4 image
+        let bbox = AnnotationShape::BoundingBox {
+            x: 1.0,
+            y: 1.0,
+            width: 2.0,
+            height: 2.0,
+        };
+        assert_eq!(bbox.mask_pixels(4, 4), vec![5, 6, 9, 10]);
+        // Clipped to the image
+        assert_eq!(bbox.mask_pixels(2, 2), vec![3]);
+
+        // Triangle covering the lower-left half of a 4x4 image
+        let triangle = AnnotationShape::Polygon {
+            vertices: vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0)],
+        };
+        let pixels = triangle.mask_pixels(4, 4);
+        assert_eq!(pixels.len(), 6);
+        assert!(pixels.contains(&12) && !pixels.contains(&3));
+
+        let point = AnnotationShape::Point { x: 2.7, y: 1.2 };
+        assert_eq!(point.mask_pixels(4, 4), vec![6]);
+        assert!(point.mask_pixels(2, 2).is_empty());
+    }
+
+    #[test]
+    fn test_rotated_box() {
+        use std::f32::consts::FRAC_PI_2;
+
+        // 10x4 box rotated a quarter turn becomes 4 wide and 10 tall
+        let rbox = AnnotationShape::RotatedBox {
+            cx: 10.0,
+            cy: 10.0,
+            w: 10.0,
+            h: 4.0,
+            angle: FRAC_PI_2,
+        };
+        assert!(rbox.contains_point(10.0, 14.0));
+        assert!(!rbox.contains_point(14.0, 10.0));
+        let (min_x, min_y, max_x, max_y) = rbox.bounding_box();
+        assert!((min_x - 8.0).abs() < 1e-4 && (max_x - 12.0).abs() < 1e-4);
+        assert!((min_y - 5.0).abs() < 1e-4 && (max_y - 15.0).abs() < 1e-4);
+        assert_eq!(rbox.to_polygon().polygon_vertices().map(Vec::len), Some(4));
+
+        // Local top-left corner is at image (12, 5)
+        assert_eq!(
+            rbox.hit_test_handle(12.0, 5.0, 1.0),
+            Some(AnnotationHandle::RotatedBox(RotatedBoxHandle::Corner(0)))
+        );
+
+        // Dragging the right edge (image +y) grows the width, opposite edge fixed
+        let handle = AnnotationHandle::RotatedBox(RotatedBoxHandle::Edge(1));
+        let grown = AnnotationShape::apply_handle_drag(&rbox, &handle, 10.0, 15.0, 10.0, 17.0);
+        let Some(AnnotationShape::RotatedBox { cx, cy, w, h, .. }) = grown else {
+            panic!("expected a rotated box");
+        };
+        assert!((w - 12.0).abs() < 1e-4 && (h - 4.0).abs() < 1e-4);
+        assert!((cx - 10.0).abs() < 1e-4 && (cy - 11.0).abs() < 1e-4);
+
+        // Rotating around the center by a quarter turn back to axis-aligned
+        let handle = AnnotationHandle::RotatedBox(RotatedBoxHandle::Rotate);
+        let rotated = AnnotationShape::apply_handle_drag(&rbox, &handle, 10.0, 0.0, 20.0, 10.0);
+        let Some(AnnotationShape::RotatedBox { angle, .. }) = rotated else {
+            panic!("expected a rotated box");
+        };
+        assert!((angle - std::f32::consts::PI).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_polyline() {
+        let line = AnnotationShape::Polyline {
+            vertices: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 20.0)],
+            width: 20.0,
+        };
+        // Within half the width of a segment, but not across the open end
+        assert!(line.contains_point(5.0, 9.0));
+        assert!(!line.contains_point(0.0, 21.0));
+        assert_eq!(
+            line.hit_test_handle(10.0, 0.5, 1.0),
+            Some(AnnotationHandle::Polygon(PolygonHandle::Vertex(1)))
+        );
+        // Midpoint along the path is the move handle
+        assert_eq!(
+            line.hit_test_handle(10.0, 5.0, 1.0),
+            Some(AnnotationHandle::Polygon(PolygonHandle::Center))
+        );
+        assert_eq!(
+            line.hit_test_handle(10.0, 12.0, 1.0),
+            Some(AnnotationHandle::Polygon(PolygonHandle::Edge { index: 1 }))
+        );
+
+        // No closing edge, and at least two vertices remain
+        assert!(line.insert_polygon_vertex(2, 5.0, 5.0).is_none());
+        let inserted = line.insert_polygon_vertex(1, 10.0, 5.0).unwrap();
+        assert_eq!(inserted.bounding_box(), (0.0, 0.0, 10.0, 20.0));
+        let shorter = line.remove_polygon_vertex(0).unwrap();
+        assert!(shorter.remove_polygon_vertex(0).is_none());
+
+        // A 1px horizontal line covers one row of pixels
+        let thin = AnnotationShape::Polyline {
+            vertices: vec![(0.0, 1.5), (4.0, 1.5)],
+            width: 1.0,
+        };
+        assert_eq!(thin.mask_pixels(4, 4), vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_mask_shape() {
+        // A 2x2 box rasterizes into the four pixels it covers
+        let bbox = AnnotationShape::BoundingBox {
+            x: 1.0,
+            y: 1.0,
+            width: 2.0,
+            height: 2.0,
+        };
+        let mask = bbox.to_mask(4, 4).unwrap();
+        assert!(mask.is_mask());
+        assert_eq!(mask.bounding_box(), (1.0, 1.0, 3.0, 3.0));
+        assert_eq!(mask.mask_pixels(4, 4), bbox.mask_pixels(4, 4));
+        assert!(mask.to_mask(4, 4).is_none());
+
+        // The body is the move handle, and moves snap to whole pixels
+        let handle = mask.hit_test_handle(1.5, 1.5, 1.0).unwrap();
+        assert!(mask.hit_test_handle(0.5, 0.5, 1.0).is_none());
+        let moved = AnnotationShape::apply_handle_drag(&mask, &handle, 0.0, 0.0, 1.2, 0.6).unwrap();
+        assert_eq!(moved.bounding_box(), (2.0, 2.0, 4.0, 4.0));
+    }
+
+    #[test]
+    fn test_multipolygon() {
+        // 10x10 square with a 4x4 hole, plus a separate triangle
+        let outer = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let hole = vec![(3.0, 3.0), (7.0, 3.0), (7.0, 7.0), (3.0, 7.0)];
+        let part = vec![(20.0, 0.0), (30.0, 0.0), (25.0, 5.0)];
+        let shape =
+            AnnotationShape::from_rings(vec![outer.clone(), hole.clone(), part.clone()]).unwrap();
+        assert!(shape.is_multipolygon());
+        assert_eq!(ring_holes(&[outer, hole, part]), vec![false, true, false]);
+
+        // Even-odd fill
+        assert!(shape.contains_point(1.0, 1.0));
+        assert!(!shape.contains_point(5.0, 5.0));
+        assert!(shape.contains_point(25.0, 2.0));
+        assert_eq!(shape.bounding_box(), (0.0, 0.0, 30.0, 10.0));
+
+        // Handles are numbered across rings: vertex 4 is the hole's first
+        assert_eq!(
+            shape.hit_test_handle(3.0, 3.0, 1.0),
+            Some(AnnotationHandle::Polygon(PolygonHandle::Vertex(4)))
+        );
+        let handle = AnnotationHandle::Polygon(PolygonHandle::Vertex(4));
+        let dragged = AnnotationShape::apply_handle_drag(&shape, &handle, 0.0, 0.0, 1.0, 1.0);
+        assert!(dragged.unwrap().contains_point(3.5, 3.5));
+
+        // Inserting on the hole's first edge adds vertex 5 to that ring
+        let inserted = shape.insert_polygon_vertex(4, 5.0, 3.0).unwrap();
+        assert_eq!(inserted.vertex_count(), 12);
+        assert!(inserted.contains_point(1.0, 1.0));
+
+        // Removing a vertex from the triangle drops it; one ring left is a polygon
+        let without_part = shape.remove_polygon_vertex(8).unwrap();
+        assert_eq!(without_part.vertex_count(), 8);
+        let without_hole = without_part.remove_polygon_vertex(4).unwrap();
+        assert_eq!(without_hole.vertex_count(), 7);
+        let single = AnnotationShape::from_rings(vec![vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]]);
+        assert!(single.unwrap().is_polygon());
+    }
+
+    #[test]
+    fn test_keypoints_shape() {
+        use crate::model::keypoint::{KEYPOINT_OCCLUDED, KEYPOINT_VISIBLE};
+
+        // Third keypoint skipped; drawing fills the rest of the template
+        let drawing = DrawingState::Keypoints {
+            keypoints: vec![
+                Keypoint::new(20.0, 20.0, KEYPOINT_VISIBLE),
+                Keypoint::new(40.0, 30.0, KEYPOINT_OCCLUDED),
+                Keypoint::unlabeled(),
+            ],
+            count: 4,
+        };
+        let shape = drawing.to_shape().unwrap();
+        let AnnotationShape::Keypoints {
+            keypoints,
+            x,
+            y,
+            width,
+            height,
+        } = &shape
+        else {
+            panic!("expected keypoints, got {:?}", shape);
+        };
+        assert_eq!(keypoints.len(), 4);
+        assert!(!keypoints[3].is_labeled());
+        // Box encloses the labeled keypoints plus padding
+        assert_eq!((*x, *y, *width, *height), (10.0, 10.0, 40.0, 30.0));
+
+        // Keypoints take priority over the box handles
+        assert_eq!(
+            shape.hit_test_handle(40.0, 30.0, 2.0),
+            Some(AnnotationHandle::Keypoint(1))
+        );
+        assert_eq!(
+            shape.hit_test_handle(10.0, 10.0, 2.0),
+            Some(AnnotationHandle::BBox(BBoxHandle::TopLeft))
+        );
+        // Unlabeled keypoints have no handle
+        assert_eq!(shape.hit_test_handle(0.0, 0.0, 2.0), None);
+
+        let dragged = AnnotationShape::apply_handle_drag(
+            &shape,
+            &AnnotationHandle::Keypoint(0),
+            20.0,
+            20.0,
+            25.0,
+            22.0,
+        )
+        .unwrap();
+        assert_eq!(dragged.bounding_box(), (10.0, 10.0, 50.0, 40.0));
+        let resized = AnnotationShape::apply_handle_drag(
+            &shape,
+            &AnnotationHandle::BBox(BBoxHandle::BottomRight),
+            50.0,
+            40.0,
+            60.0,
+            50.0,
+        )
+        .unwrap();
+        assert_eq!(resized.bounding_box(), (10.0, 10.0, 60.0, 50.0));
+
+        // Labeled keypoints cover their pixels; no mask conversion
+        assert_eq!(shape.mask_pixels(64, 64), vec![20 * 64 + 20, 30 * 64 + 40]);
+        assert!(shape.to_mask(64, 64).is_none());
+
+        // Nothing labeled gives no annotation
+        let empty = DrawingState::Keypoints {
+            keypoints: vec![Keypoint::unlabeled()],
+            count: 2,
+        };
+        assert!(empty.to_shape().is_none());
+    }
+}
@@ -3,6 +3,10 @@
 use serde::{Deserialize, Serialize};
 
 /// An annotation category with a name and color.
+///
+/// Categories used for keypoint annotations also carry a keypoint template:
+/// the keypoint names in placement order and the skeleton edges between
+/// them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Category {
     /// Unique identifier for the category
@@ -11,6 +15,12 @@ pub struct Category {
     pub name: String,
     /// RGB color for the category
     pub color: [u8; 3],
+    /// Keypoint names in placement order (empty = no keypoint template)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keypoints: Vec<String>,
+    /// Skeleton edges as pairs of 0-based keypoint indices
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skeleton: Vec<(usize, usize)>,
 }
 
 impl Category {
@@ -20,8 +30,33 @@ impl Category {
             id,
             name: name.to_string(),
             color,
+            keypoints: Vec::new(),
+            skeleton: Vec::new(),
         }
     }
+
+    /// Set the keypoint template, dropping skeleton edges that refer to
+    /// keypoints outside it.
+    pub fn with_keypoints(mut self, keypoints: Vec<String>, skeleton: Vec<(usize, usize)>) -> Self {
+        self.set_keypoints(keypoints, skeleton);
+        self
+    }
+
+    /// Replace the keypoint template, dropping skeleton edges that refer to
+    /// keypoints outside it.
+    pub fn set_keypoints(&mut self, keypoints: Vec<String>, skeleton: Vec<(usize, usize)>) {
+        let count = keypoints.len();
+        self.keypoints = keypoints;
+        self.skeleton = skeleton
+            .into_iter()
+            .filter(|(a, b)| *a < count && *b < count)
+            .collect();
+    }
+
+    /// Whether this category has a keypoint template.
+    pub fn has_keypoints(&self) -> bool {
+        !self.keypoints.is_empty()
+    }
 }
 
 /// Default categories for new projects.
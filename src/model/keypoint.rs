@@ -0,0 +1,111 @@
+//! Keypoint data model for pose-style annotations.
+//!
+//! A category's keypoint template names the points of an instance in
+//! placement order and lists the skeleton edges between them. A keypoints
+//! annotation stores one [`Keypoint`] per template entry, with COCO
+//! visibility flags.
+
+use serde::{Deserialize, Serialize};
+
+/// Keypoint not labeled; its position is meaningless (COCO `v=0`).
+pub const KEYPOINT_NOT_LABELED: u8 = 0;
+
+/// Keypoint labeled but not visible, e.g. occluded (COCO `v=1`).
+pub const KEYPOINT_OCCLUDED: u8 = 1;
+
+/// Keypoint labeled and visible (COCO `v=2`).
+pub const KEYPOINT_VISIBLE: u8 = 2;
+
+/// Padding around the labeled keypoints for the instance box of a newly
+/// placed keypoints annotation, in image pixels.
+pub const KEYPOINT_BBOX_PADDING: f32 = 10.0;
+
+/// A single keypoint of an instance.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keypoint {
+    /// X position in image coordinates
+    pub x: f32,
+    /// Y position in image coordinates
+    pub y: f32,
+    /// Visibility flag: not labeled, occluded or visible
+    pub visibility: u8,
+}
+
+impl Keypoint {
+    /// Create a keypoint at the given position.
+    pub fn new(x: f32, y: f32, visibility: u8) -> Self {
+        Self { x, y, visibility }
+    }
+
+    /// A keypoint that was skipped during placement.
+    pub fn unlabeled() -> Self {
+        Self::new(0.0, 0.0, KEYPOINT_NOT_LABELED)
+    }
+
+    /// Whether the keypoint has a position (occluded or visible).
+    pub fn is_labeled(&self) -> bool {
+        self.visibility != KEYPOINT_NOT_LABELED
+    }
+}
+
+/// Keypoint names as typed, comma-separated (e.g. "nose, left_eye").
+pub fn parse_keypoint_names(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Skeleton edges as typed, 1-based keypoint numbers joined by a dash
+/// (e.g. "1-2, 2-3"). Returns 0-based index pairs.
+pub fn parse_skeleton(text: &str, count: usize) -> Result<Vec<(usize, usize)>, String> {
+    text.split([',', ' ', ';'])
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let index = |n: &str| {
+                n.trim()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| (1..=count).contains(n))
+                    .map(|n| n - 1)
+            };
+            s.split_once('-')
+                .and_then(|(a, b)| index(a).zip(index(b)))
+                .filter(|(a, b)| a != b)
+                .ok_or_else(|| format!("Invalid skeleton edge: {}", s))
+        })
+        .collect()
+}
+
+/// Skeleton edges as typed, the inverse of `parse_skeleton`.
+pub fn format_skeleton(skeleton: &[(usize, usize)]) -> String {
+    skeleton
+        .iter()
+        .map(|(a, b)| format!("{}-{}", a + 1, b + 1))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(
+            parse_keypoint_names(" nose,left_eye , ,right_eye"),
+            vec!["nose", "left_eye", "right_eye"]
+        );
+
+        let skeleton = parse_skeleton("1-2, 2-3", 3).unwrap();
+        assert_eq!(skeleton, vec![(0, 1), (1, 2)]);
+        assert_eq!(format_skeleton(&skeleton), "1-2, 2-3");
+        assert!(parse_skeleton("", 3).unwrap().is_empty());
+
+        // Out of range, self loops and malformed pairs are rejected
+        assert!(parse_skeleton("1-4", 3).is_err());
+        assert!(parse_skeleton("2-2", 3).is_err());
+        assert!(parse_skeleton("1", 3).is_err());
+    }
+}
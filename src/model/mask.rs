@@ -0,0 +1,393 @@
+//! Run-length encoded raster masks.
+//!
+//! Masks store a binary bitmap inside a bounding box placed in the image.
+//! The box is kept tight around the foreground, so a mask costs memory in
+//! proportion to its own size rather than the image's.
+
+use serde::{Deserialize, Serialize};
+
+/// Default brush radius for painting masks (image pixels).
+pub const DEFAULT_BRUSH_RADIUS: f32 = 8.0;
+
+/// Largest mask bounding box (in pixels) accepted from project files.
+pub const MAX_MASK_PIXELS: u64 = 1 << 28;
+
+/// Binary raster mask stored as run lengths inside its bounding box.
+///
+/// `counts` alternates background and foreground runs over the box's pixels
+/// in row-major order, starting with a (possibly empty) background run, the
+/// same convention COCO uses for its (column-major) RLE.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RleMask {
+    /// Left edge of the bounding box in image pixels.
+    pub x: u32,
+    /// Top edge of the bounding box in image pixels.
+    pub y: u32,
+    /// Bounding box width in pixels.
+    pub width: u32,
+    /// Bounding box height in pixels.
+    pub height: u32,
+    /// Alternating background/foreground run lengths.
+    pub counts: Vec<u32>,
+}
+
+impl RleMask {
+    /// Encode a row-major bitmap of `width` x `height` pixels whose top-left
+    /// pixel is at (x, y) in the image. The result is trimmed to the
+    /// foreground; an all-background bitmap gives an empty mask.
+    pub fn from_bitmap(x: u32, y: u32, width: u32, height: u32, bits: &[bool]) -> Self {
+        let w = width as usize;
+        let bits = &bits[..bits.len().min(w * height as usize)];
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+        for (i, _) in bits.iter().enumerate().filter(|(_, set)| **set) {
+            let (bx, by) = (i % w, i / w);
+            min_x = min_x.min(bx);
+            min_y = min_y.min(by);
+            max_x = max_x.max(bx);
+            max_y = max_y.max(by);
+        }
+        if min_x == usize::MAX {
+            return Self::default();
+        }
+
+        let trimmed_width = max_x - min_x + 1;
+        let mut counts = Vec::new();
+        let mut current = false;
+        let mut run = 0u32;
+        for row in min_y..=max_y {
+            let start = row * w + min_x;
+            for &bit in &bits[start..start + trimmed_width] {
+                if bit != current {
+                    counts.push(run);
+                    current = bit;
+                    run = 0;
+                }
+                run += 1;
+            }
+        }
+        counts.push(run);
+
+        Self {
+            x: x + min_x as u32,
+            y: y + min_y as u32,
+            width: trimmed_width as u32,
+            height: (max_y - min_y + 1) as u32,
+            counts,
+        }
+    }
+
+    /// Build a mask from image pixel indices (`y * image_width + x`).
+    pub fn from_pixels(pixels: &[usize], image_width: u32) -> Self {
+        let w = image_width as usize;
+        if w == 0 || pixels.is_empty() {
+            return Self::default();
+        }
+        let min_x = pixels.iter().map(|p| p % w).min().unwrap_or(0);
+        let max_x = pixels.iter().map(|p| p % w).max().unwrap_or(0);
+        let min_y = pixels.iter().map(|p| p / w).min().unwrap_or(0);
+        let max_y = pixels.iter().map(|p| p / w).max().unwrap_or(0);
+
+        let box_width = max_x - min_x + 1;
+        let mut bits = vec![false; box_width * (max_y - min_y + 1)];
+        for p in pixels {
+            bits[(p / w - min_y) * box_width + (p % w - min_x)] = true;
+        }
+        Self::from_bitmap(
+            min_x as u32,
+            min_y as u32,
+            box_width as u32,
+            (max_y - min_y + 1) as u32,
+            &bits,
+        )
+    }
+
+    /// Number of pixels in the bounding box.
+    pub fn box_pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Check a mask read from a file: the box must be at most
+    /// [`MAX_MASK_PIXELS`] and the runs must cover it exactly.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.x.checked_add(self.width).is_none() || self.y.checked_add(self.height).is_none() {
+            return Err(format!(
+                "mask box at ({}, {}) is out of range",
+                self.x, self.y
+            ));
+        }
+        if self.box_pixels() > MAX_MASK_PIXELS {
+            return Err(format!(
+                "mask box {}x{} exceeds {} pixels",
+                self.width, self.height, MAX_MASK_PIXELS
+            ));
+        }
+        let total: u64 = self.counts.iter().map(|&run| run as u64).sum();
+        if total != self.box_pixels() {
+            return Err(format!(
+                "mask runs cover {} pixels but the {}x{} box has {}",
+                total,
+                self.width,
+                self.height,
+                self.box_pixels()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Decode into a row-major bitmap of the bounding box.
+    pub fn to_bitmap(&self) -> Vec<bool> {
+        let len = self.box_pixels() as usize;
+        let mut bits = Vec::with_capacity(len);
+        for (i, &run) in self.counts.iter().enumerate() {
+            let run = (run as usize).min(len - bits.len());
+            bits.extend(std::iter::repeat_n(i % 2 == 1, run));
+        }
+        bits.resize(len, false);
+        bits
+    }
+
+    /// Whether the mask has no foreground pixels.
+    pub fn is_empty(&self) -> bool {
+        self.area() == 0
+    }
+
+    /// Number of foreground pixels.
+    pub fn area(&self) -> u32 {
+        self.counts.iter().skip(1).step_by(2).sum()
+    }
+
+    /// Whether the pixel containing image point (x, y) is foreground.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        if x < self.x as f32 || y < self.y as f32 {
+            return false;
+        }
+        let (bx, by) = (x as u32 - self.x, y as u32 - self.y);
+        if bx >= self.width || by >= self.height {
+            return false;
+        }
+
+        let index = by as u64 * self.width as u64 + bx as u64;
+        let mut end = 0u64;
+        for (i, &run) in self.counts.iter().enumerate() {
+            end += run as u64;
+            if index < end {
+                return i % 2 == 1;
+            }
+        }
+        false
+    }
+
+    /// Foreground runs as `(x, y, length)` in image pixels, split at row ends.
+    pub fn runs(&self) -> Vec<(u32, u32, u32)> {
+        let mut runs = Vec::new();
+        if self.width == 0 {
+            return runs;
+        }
+        let mut pos = 0;
+        for (i, &run) in self.counts.iter().enumerate() {
+            if i % 2 == 1 {
+                let end = pos + run;
+                let mut start = pos;
+                while start < end {
+                    let row = start / self.width;
+                    let col = start % self.width;
+                    let len = (end - start).min(self.width - col);
+                    runs.push((self.x + col, self.y + row, len));
+                    start += len;
+                }
+            }
+            pos += run;
+        }
+        runs
+    }
+
+    /// Indices (`y * width + x`) of the foreground pixels inside an image of
+    /// the given size.
+    pub fn pixels(&self, width: u32, height: u32) -> Vec<usize> {
+        let mut pixels = Vec::new();
+        for (x, y, len) in self.runs() {
+            if y >= height {
+                continue;
+            }
+            for px in x..(x + len).min(width) {
+                pixels.push(y as usize * width as usize + px as usize);
+            }
+        }
+        pixels
+    }
+
+    /// Move the mask by whole pixels, stopping at the image's top-left edge.
+    pub fn translate(&mut self, dx: i32, dy: i32) {
+        self.x = self.x.saturating_add_signed(dx);
+        self.y = self.y.saturating_add_signed(dy);
+    }
+
+    /// Paint (or erase, when `value` is false) a stroke of the given radius
+    /// from `from` to `to`. Pixels whose centers are within `radius` of the
+    /// segment change; pixels at or beyond `limit` (the image size) are never
+    /// painted.
+    pub fn paint_stroke(
+        &mut self,
+        from: (f32, f32),
+        to: (f32, f32),
+        radius: f32,
+        value: bool,
+        limit: (u32, u32),
+    ) {
+        let stroke_min_x = (from.0.min(to.0) - radius).floor().max(0.0) as u32;
+        let stroke_min_y = (from.1.min(to.1) - radius).floor().max(0.0) as u32;
+        let stroke_max_x = ((from.0.max(to.0) + radius).ceil().max(0.0) as u32).min(limit.0);
+        let stroke_max_y = ((from.1.max(to.1) + radius).ceil().max(0.0) as u32).min(limit.1);
+        if stroke_min_x >= stroke_max_x || stroke_min_y >= stroke_max_y {
+            return;
+        }
+
+        // Painting may grow the box; erasing never does
+        let (min_x, min_y, max_x, max_y) = if self.is_empty() {
+            if !value {
+                return;
+            }
+            (stroke_min_x, stroke_min_y, stroke_max_x, stroke_max_y)
+        } else if value {
+            (
+                self.x.min(stroke_min_x),
+                self.y.min(stroke_min_y),
+                (self.x + self.width).max(stroke_max_x),
+                (self.y + self.height).max(stroke_max_y),
+            )
+        } else {
+            (self.x, self.y, self.x + self.width, self.y + self.height)
+        };
+
+        let width = max_x - min_x;
+        let height = max_y - min_y;
+        let mut bits = vec![false; width as usize * height as usize];
+        if !self.is_empty() {
+            let own = self.to_bitmap();
+            for row in 0..self.height {
+                let src = row as usize * self.width as usize;
+                let dst =
+                    (self.y - min_y + row) as usize * width as usize + (self.x - min_x) as usize;
+                bits[dst..dst + self.width as usize]
+                    .copy_from_slice(&own[src..src + self.width as usize]);
+            }
+        }
+
+        let ys = stroke_min_y.max(min_y)..stroke_max_y.min(max_y);
+        let xs = stroke_min_x.max(min_x)..stroke_max_x.min(max_x);
+        for py in ys {
+            for px in xs.clone() {
+                let center = (px as f32 + 0.5, py as f32 + 0.5);
+                if segment_distance(center, from, to) <= radius {
+                    bits[(py - min_y) as usize * width as usize + (px - min_x) as usize] = value;
+                }
+            }
+        }
+
+        *self = Self::from_bitmap(min_x, min_y, width, height, &bits);
+    }
+}
+
+/// Distance from a point to the segment between `a` and `b`.
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitmap_roundtrip() {
+        // 4x3 bitmap at (10, 20) with an L shape; the empty last column is trimmed
+        #[rustfmt::skip]
+        let bits = [
+            true,  false, false, false,
+            true,  false, false, false,
+            true,  true,  true,  false,
+        ];
+        let mask = RleMask::from_bitmap(10, 20, 4, 3, &bits);
+        assert_eq!((mask.x, mask.y, mask.width, mask.height), (10, 20, 3, 3));
+        assert_eq!(mask.counts, vec![0, 1, 2, 1, 2, 3]);
+        assert_eq!(mask.area(), 5);
+        assert!(mask.contains(10.5, 21.5));
+        assert!(!mask.contains(11.5, 21.5));
+        assert_eq!(mask.runs(), vec![(10, 20, 1), (10, 21, 1), (10, 22, 3)]);
+
+        let decoded = mask.to_bitmap();
+        assert_eq!(decoded.iter().filter(|b| **b).count(), 5);
+        assert!(RleMask::from_bitmap(0, 0, 2, 2, &[false; 4]).is_empty());
+    }
+
+    #[test]
+    fn test_from_pixels() {
+        // Pixels (2, 1) and (3, 2) of a 10-wide image
+        let mask = RleMask::from_pixels(&[12, 23], 10);
+        assert_eq!((mask.x, mask.y, mask.width, mask.height), (2, 1, 2, 2));
+        assert_eq!(mask.pixels(10, 10), vec![12, 23]);
+        // Pixels outside a narrower image are dropped; indices use its width
+        assert_eq!(mask.pixels(3, 10), vec![5]);
+    }
+
+    #[test]
+    fn test_paint_and_erase() {
+        let mut mask = RleMask::default();
+        mask.paint_stroke((5.0, 5.0), (15.0, 5.0), 2.0, true, (100, 100));
+        assert!(mask.contains(5.5, 5.5));
+        assert!(mask.contains(14.5, 4.5));
+        assert!(!mask.contains(5.5, 8.5));
+
+        // Erase the middle, splitting the stroke
+        mask.paint_stroke((10.0, 5.0), (10.0, 5.0), 2.0, false, (100, 100));
+        assert!(!mask.contains(10.5, 5.5));
+        assert!(mask.contains(5.5, 5.5));
+
+        // Painting never crosses the image limit
+        mask.paint_stroke((19.0, 5.0), (19.0, 5.0), 3.0, true, (20, 20));
+        assert!(mask.x + mask.width <= 20);
+
+        // Erasing everything leaves an empty mask
+        mask.paint_stroke((0.0, 5.0), (25.0, 5.0), 5.0, false, (100, 100));
+        assert!(mask.is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mask = RleMask::from_bitmap(0, 0, 2, 2, &[true, false, false, true]);
+        assert!(mask.validate().is_ok());
+        assert!(RleMask::default().validate().is_ok());
+
+        // Runs that don't cover the box exactly
+        let short = RleMask {
+            counts: vec![0, 1],
+            ..mask.clone()
+        };
+        assert!(short.validate().is_err());
+        let long = RleMask {
+            counts: vec![0, 1, u32::MAX],
+            ..mask
+        };
+        assert!(long.validate().is_err());
+        // A box whose pixel count overflows u32
+        let huge = RleMask {
+            x: 0,
+            y: 0,
+            width: u32::MAX,
+            height: u32::MAX,
+            counts: vec![u32::MAX; 4],
+        };
+        assert!(huge.validate().unwrap_err().contains("exceeds"));
+        let far = RleMask {
+            x: u32::MAX,
+            ..RleMask::from_bitmap(0, 0, 1, 1, &[true])
+        };
+        assert!(far.validate().is_err());
+    }
+}
@@ -2,12 +2,20 @@
 
 mod annotation;
 mod category;
+mod keypoint;
+mod mask;
 mod tag;
 
 pub use annotation::{
-    Annotation, AnnotationHandle, AnnotationId, AnnotationShape, AnnotationTool, DrawingState,
-    EditState, HANDLE_HIT_RADIUS, MIN_DRAG_DISTANCE, MIN_POLYGON_VERTICES, POLYGON_CLOSE_THRESHOLD,
-    PolygonHandle,
+    Annotation, AnnotationHandle, AnnotationId, AnnotationShape, AnnotationTool,
+    DEFAULT_POLYLINE_WIDTH, DrawingState, EditState, HANDLE_HIT_RADIUS, MIN_DRAG_DISTANCE,
+    MIN_POLYGON_VERTICES, MIN_POLYLINE_VERTICES, POLYGON_CLOSE_THRESHOLD, PolygonHandle,
+    RotatedBoxHandle, ring_holes, rotated_box_corners,
 };
 pub use category::{Category, default_categories};
+pub use keypoint::{
+    KEYPOINT_NOT_LABELED, KEYPOINT_OCCLUDED, KEYPOINT_VISIBLE, Keypoint, format_skeleton,
+    parse_keypoint_names, parse_skeleton,
+};
+pub use mask::{DEFAULT_BRUSH_RADIUS, MAX_MASK_PIXELS, RleMask};
 pub use tag::{Tag, default_tags};
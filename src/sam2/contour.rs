@@ -1,9 +1,15 @@
 //! Contour extraction from binary masks using marching squares algorithm.
 //!
 //! This module provides functions to extract polygon contours from binary masks,
-//! which is needed to convert SAM2 masks into annotation polygons.
+//! which is needed to convert SAM2 masks and mask annotations into
+//! annotation polygons.
+//!
+//! [`extract_contour`] follows the outer edge of one region. [`extract_contours`]
+//! traces every region and hole along the pixel edges, giving rings that
+//! reproduce the mask when filled with the even-odd rule.
 
 use super::SAM2Mask;
+use crate::model::RleMask;
 
 /// Extracts the outer contour of a binary mask using marching squares.
 ///
@@ -37,6 +43,120 @@ pub fn extract_contour(mask: &SAM2Mask, epsilon: f32) -> Vec<(f32, f32)> {
     }
 }
 
+/// Extracts the boundaries of all regions and holes of a binary mask.
+///
+/// Each ring runs along pixel edges (clockwise on screen around regions,
+/// counter-clockwise around holes) and is simplified with the
+/// Douglas-Peucker algorithm. Diagonally touching pixels belong to the same
+/// region. Rings left with fewer than three vertices are dropped.
+pub fn extract_contours(mask: &SAM2Mask, epsilon: f32) -> Vec<Vec<(f32, f32)>> {
+    trace_rings(mask.width, mask.height, |x, y| mask.get(x, y) > 127)
+        .into_iter()
+        .filter_map(|ring| simplify_ring(&ring, epsilon))
+        .collect()
+}
+
+/// Extracts the boundaries of all regions and holes of a mask annotation,
+/// in image coordinates. See [`extract_contours`].
+pub fn extract_mask_contours(mask: &RleMask, epsilon: f32) -> Vec<Vec<(f32, f32)>> {
+    let bits = mask.to_bitmap();
+    let width = mask.width;
+    let rings = trace_rings(mask.width, mask.height, |x, y| {
+        bits[(y * width + x) as usize]
+    });
+    rings
+        .into_iter()
+        .filter_map(|ring| simplify_ring(&ring, epsilon))
+        .map(|ring| {
+            ring.into_iter()
+                .map(|(x, y)| (x + mask.x as f32, y + mask.y as f32))
+                .collect()
+        })
+        .collect()
+}
+
+/// Traces all boundary rings of a bitmap along pixel edges.
+///
+/// Every foreground pixel side facing background becomes a directed edge
+/// between pixel corners, with the foreground on its right. Following the
+/// edges (preferring left turns where two regions touch diagonally) closes
+/// them into rings. Only the corners where the ring turns are returned.
+fn trace_rings(width: u32, height: u32, is_set: impl Fn(u32, u32) -> bool) -> Vec<Vec<(f32, f32)>> {
+    let corners_wide = width as usize + 1;
+    let corner = |x: u32, y: u32| y as usize * corners_wide + x as usize;
+    let set = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < width as i64 && y < height as i64 && is_set(x as u32, y as u32)
+    };
+
+    // Outgoing edge directions per corner, as bits of 1 << dir
+    let mut edges = vec![0u8; corners_wide * (height as usize + 1)];
+    for y in 0..height {
+        for x in 0..width {
+            if !is_set(x, y) {
+                continue;
+            }
+            let (px, py) = (x as i64, y as i64);
+            if !set(px, py - 1) {
+                edges[corner(x, y)] |= 1 << 0; // top side, rightwards
+            }
+            if !set(px + 1, py) {
+                edges[corner(x + 1, y)] |= 1 << 1; // right side, downwards
+            }
+            if !set(px, py + 1) {
+                edges[corner(x + 1, y + 1)] |= 1 << 2; // bottom side, leftwards
+            }
+            if !set(px - 1, py) {
+                edges[corner(x, y + 1)] |= 1 << 3; // left side, upwards
+            }
+        }
+    }
+
+    // The edge taken after arriving at a corner: left turn, straight, right turn
+    let all_edges = edges.clone();
+    let next_dir = |c: usize, dir: u8| {
+        [(dir + 3) % 4, dir, (dir + 1) % 4]
+            .into_iter()
+            .find(|d| all_edges[c] & (1 << d) != 0)
+    };
+
+    let mut rings = Vec::new();
+    for start in 0..edges.len() {
+        while edges[start] != 0 {
+            let start_dir = edges[start].trailing_zeros() as u8;
+            let mut ring = Vec::new();
+            let (mut c, mut dir) = (start, start_dir);
+            loop {
+                edges[c] &= !(1 << dir);
+                let (dx, dy) = dir_to_delta(dir);
+                let x = (c % corners_wide) as i32 + dx;
+                let y = (c / corners_wide) as i32 + dy;
+                c = y as usize * corners_wide + x as usize;
+                let Some(next) = next_dir(c, dir) else {
+                    break;
+                };
+                if next != dir {
+                    ring.push((x as f32, y as f32));
+                }
+                if c == start && next == start_dir {
+                    break;
+                }
+                dir = next;
+            }
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+/// Simplifies a closed ring; None if fewer than three vertices remain.
+fn simplify_ring(ring: &[(f32, f32)], epsilon: f32) -> Option<Vec<(f32, f32)>> {
+    let mut closed = ring.to_vec();
+    closed.push(*ring.first()?);
+    let mut simplified = douglas_peucker(&closed, epsilon);
+    simplified.pop();
+    (simplified.len() >= 3).then_some(simplified)
+}
+
 /// Finds the starting point for contour tracing.
 ///
 /// Returns the first foreground pixel that has at least one background neighbor.
@@ -289,6 +409,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extract_contours_holes_and_parts() {
+        // 5x5 ring with a 1px hole, plus a separate 2x2 square
+        let mut data = vec![0u8; 100];
+        for y in 1..6 {
+            for x in 1..6 {
+                data[y * 10 + x] = 255;
+            }
+        }
+        data[3 * 10 + 3] = 0;
+        for y in 7..9 {
+            for x in 7..9 {
+                data[y * 10 + x] = 255;
+            }
+        }
+
+        let mask = SAM2Mask::new(data, 10, 10, 0.9);
+        let mut rings = extract_contours(&mask, 0.1);
+        rings.sort_by_key(|ring| ring.len());
+        assert_eq!(rings.len(), 3);
+        for ring in &rings {
+            assert_eq!(ring.len(), 4);
+        }
+        let xs: Vec<f32> = rings.iter().flatten().map(|(x, _)| *x).collect();
+        assert!(xs.contains(&1.0) && xs.contains(&6.0));
+        assert!(xs.contains(&3.0) && xs.contains(&4.0));
+        assert!(xs.contains(&7.0) && xs.contains(&9.0));
+    }
+
+    #[test]
+    fn test_extract_contours_diagonal_pixels() {
+        // Diagonally touching pixels form one region
+        let mask = SAM2Mask::new(vec![255, 0, 0, 255], 2, 2, 0.9);
+        let rings = extract_contours(&mask, 0.1);
+        assert_eq!(rings.len(), 1);
+        assert_eq!(rings[0].len(), 8);
+    }
+
+    #[test]
+    fn test_extract_mask_contours() {
+        // 4x4 square at (20, 30)
+        let mask = RleMask::from_bitmap(20, 30, 4, 4, &[true; 16]);
+        let rings = extract_mask_contours(&mask, 0.5);
+        assert_eq!(rings.len(), 1);
+        assert_eq!(rings[0].len(), 4);
+        for (x, y) in &rings[0] {
+            assert!(*x == 20.0 || *x == 24.0);
+            assert!(*y == 30.0 || *y == 34.0);
+        }
+    }
+
     #[test]
     fn test_douglas_peucker() {
         let points = vec![(0.0, 0.0), (1.0, 0.1), (2.0, 0.0), (3.0, 0.1), (4.0, 0.0)];
@@ -6,7 +6,7 @@
 //! The decoder is lightweight (~20MB) and runs on the main thread
 //! for real-time preview during interactive segmentation.
 
-use super::{ImageEmbeddings, SAM2Mask, extract_contour};
+use super::{ImageEmbeddings, SAM2Mask, extract_contours};
 use ndarray::{Array1, Array2, Array3, Array4};
 use ort::session::{Session, builder::GraphOptimizationLevel};
 use ort::value::TensorRef;
@@ -157,14 +157,14 @@ impl SAM2Decoder {
 
         // Create mask and extract contour
         let mut mask = SAM2Mask::new(mask_data, img_w, img_h, score);
-        mask.contour = extract_contour(&mask, CONTOUR_EPSILON);
+        mask.contours = extract_contours(&mask, CONTOUR_EPSILON);
 
         log::debug!(
-            "SAM2 mask: {}x{}, score={:.2}, {} contour vertices",
+            "SAM2 mask: {}x{}, score={:.2}, {} contour rings",
             img_w,
             img_h,
             score,
-            mask.contour.len()
+            mask.contours.len()
         );
 
         Ok(mask)
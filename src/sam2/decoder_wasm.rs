@@ -6,7 +6,7 @@
 //! The decoder is lightweight (~16.5MB) and runs on the main thread
 //! for real-time preview during interactive segmentation.
 
-use super::{ImageEmbeddings, SAM2Mask, extract_contour};
+use super::{ImageEmbeddings, SAM2Mask, extract_contours};
 use wasm_bindgen::prelude::*;
 
 /// Simplification epsilon for contour extraction (in pixels).
@@ -265,9 +265,9 @@ impl SAM2Decoder {
 
         // Create mask and extract contour
         let mut mask = SAM2Mask::new(data, width, height, score);
-        mask.contour = extract_contour(&mask, CONTOUR_EPSILON);
+        mask.contours = extract_contours(&mask, CONTOUR_EPSILON);
 
-        log::debug!("SAM2 mask contour: {} vertices", mask.contour.len());
+        log::debug!("SAM2 mask contours: {} rings", mask.contours.len());
 
         Ok(mask)
     }
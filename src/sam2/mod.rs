@@ -17,31 +17,37 @@
 //! 1. **BBox to Segment**: Draw bbox -> auto-segment -> refine with points
 //! 2. **SAM2 Tool**: Select tool -> draw bbox/add points -> accept mask
 //! 3. **Points Only**: Add positive/negative points until satisfied
+//!
+//! # Feature gating
+//!
+//! The state types and contour extraction are built without the `sam2`
+//! feature, since thresholded masks (e.g. the spectral similarity map) are
+//! turned into polygons the same way. The encoder and decoder require it.
 
 mod contour;
 mod state;
 
 // Platform-specific encoder/decoder implementations
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "sam2", not(target_arch = "wasm32")))]
 mod decoder;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "sam2", not(target_arch = "wasm32")))]
 mod encoder;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "sam2", target_arch = "wasm32"))]
 mod decoder_wasm;
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "sam2", target_arch = "wasm32"))]
 mod encoder_wasm;
 
-pub use contour::extract_contour;
+pub use contour::{extract_contour, extract_contours, extract_mask_contours};
 pub use state::{ImageEmbeddings, SAM2Mask, SAM2Message, SAM2Prompts, SAM2Session, SAM2State};
 
 // Re-export platform-specific types with the same names
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "sam2", not(target_arch = "wasm32")))]
 pub use decoder::{SAM2Decoder, SAM2DecoderError};
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "sam2", not(target_arch = "wasm32")))]
 pub use encoder::{ENCODER_INPUT_SIZE, SAM2Encoder, SAM2EncoderError};
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "sam2", target_arch = "wasm32"))]
 pub use decoder_wasm::{SAM2Decoder, SAM2DecoderError};
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "sam2", target_arch = "wasm32"))]
 pub use encoder_wasm::{ENCODER_INPUT_SIZE, SAM2Encoder, SAM2EncoderError};
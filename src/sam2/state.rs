@@ -10,10 +10,14 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::model::RleMask;
+
 /// Current state of SAM2 integration.
 ///
 /// This is a state machine that tracks the SAM2 lifecycle from disabled
 /// through downloading, loading, encoding, and active segmentation.
+// Held once by the app, so the size of the `Active` variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Default)]
 pub enum SAM2State {
     /// SAM2 not loaded (feature disabled or user preference off).
@@ -326,9 +330,10 @@ pub struct SAM2Mask {
     /// Confidence score (0.0 - 1.0).
     pub score: f32,
 
-    /// Extracted polygon contour (for annotation).
-    /// This is computed from the binary mask using marching squares.
-    pub contour: Vec<(f32, f32)>,
+    /// Extracted polygon rings (for annotation): the outline of every
+    /// region and hole, filled by the even-odd rule.
+    /// This is traced from the binary mask along pixel edges.
+    pub contours: Vec<Vec<(f32, f32)>>,
 }
 
 impl SAM2Mask {
@@ -339,24 +344,24 @@ impl SAM2Mask {
             width,
             height,
             score,
-            contour: Vec::new(),
+            contours: Vec::new(),
         }
     }
 
-    /// Creates a mask with a pre-computed contour.
-    pub fn with_contour(
+    /// Creates a mask with pre-computed contours.
+    pub fn with_contours(
         data: Vec<u8>,
         width: u32,
         height: u32,
         score: f32,
-        contour: Vec<(f32, f32)>,
+        contours: Vec<Vec<(f32, f32)>>,
     ) -> Self {
         Self {
             data,
             width,
             height,
             score,
-            contour,
+            contours,
         }
     }
 
@@ -376,6 +381,12 @@ impl SAM2Mask {
         let yi = y as u32;
         self.get(xi, yi) > 127
     }
+
+    /// Converts the foreground into a run-length encoded mask annotation.
+    pub fn to_rle(&self) -> RleMask {
+        let bits: Vec<bool> = self.data.iter().map(|v| *v > 127).collect();
+        RleMask::from_bitmap(0, 0, self.width, self.height, &bits)
+    }
 }
 
 /// Image embeddings from SAM2 encoder.
@@ -511,6 +522,9 @@ pub enum SAM2Message {
     /// Accept current mask as polygon annotation.
     AcceptMask,
 
+    /// Accept current mask as a raster mask annotation.
+    AcceptMaskAsRaster,
+
     /// Cancel SAM2 session without creating annotation.
     CancelSession,
 }
@@ -555,6 +569,7 @@ impl std::fmt::Debug for SAM2Message {
             SAM2Message::UndoPrompts => write!(f, "SAM2Message::UndoPrompts"),
             SAM2Message::RedoPrompts => write!(f, "SAM2Message::RedoPrompts"),
             SAM2Message::AcceptMask => write!(f, "SAM2Message::AcceptMask"),
+            SAM2Message::AcceptMaskAsRaster => write!(f, "SAM2Message::AcceptMaskAsRaster"),
             SAM2Message::CancelSession => write!(f, "SAM2Message::CancelSession"),
         }
     }
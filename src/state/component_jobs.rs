@@ -0,0 +1,165 @@
+//! Component computations on worker threads (native only).
+//!
+//! Computing principal components is slow, so component mode runs it on a
+//! worker thread and keeps showing the previous textures until the result is
+//! uploaded. Only the latest computation counts: results of computations
+//! started for another image or method are dropped when they arrive.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use super::gpu::ComponentImages;
+use crate::data::ComponentMethod;
+
+/// Component images computed on a worker thread, with their image and method.
+pub type ComponentJobResult = (PathBuf, ComponentMethod, Result<ComponentImages, String>);
+
+/// The running component computation and its result waiting for upload.
+pub struct ComponentJobs {
+    /// Computation running (image, method)
+    running: Option<(PathBuf, ComponentMethod)>,
+    /// Finished computation waiting for upload
+    result: Option<ComponentJobResult>,
+    /// Workers send their results here
+    sender: Sender<ComponentJobResult>,
+    receiver: Receiver<ComponentJobResult>,
+}
+
+impl ComponentJobs {
+    /// Create with no computation running.
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            running: None,
+            result: None,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Whether a computation is running.
+    pub fn is_busy(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Whether the computation of `method` for `path` is running.
+    pub fn is_running(&self, path: &Path, method: ComponentMethod) -> bool {
+        matches!(&self.running, Some((p, m)) if p == path && *m == method)
+    }
+
+    /// Record a computation of `method` for `path`, replacing the running
+    /// one, and return the sender its worker reports the result to.
+    pub fn start(&mut self, path: PathBuf, method: ComponentMethod) -> Sender<ComponentJobResult> {
+        self.running = Some((path, method));
+        self.sender.clone()
+    }
+
+    /// Collect results sent by workers. Only the result of the running
+    /// computation is kept, and only while its method is still `selected`.
+    ///
+    /// Returns whether a result is ready for `take_result`.
+    pub fn poll(&mut self, selected: Option<ComponentMethod>) -> bool {
+        let mut ready = false;
+        while let Ok((path, method, images)) = self.receiver.try_recv() {
+            if !self.is_running(&path, method) {
+                continue;
+            }
+            self.running = None;
+            if selected == Some(method) {
+                self.result = Some((path, method, images));
+                ready = true;
+            }
+        }
+        ready
+    }
+
+    /// Take the collected result for `path` and `method`. A result for
+    /// anything else is dropped.
+    pub fn take_result(
+        &mut self,
+        path: &Path,
+        method: ComponentMethod,
+    ) -> Option<Result<ComponentImages, String>> {
+        match self.result.take() {
+            Some((p, m, images)) if p == path && m == method => Some(images),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ComponentJobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finish(sender: &Sender<ComponentJobResult>, path: &str, method: ComponentMethod) {
+        let error = format!("{} of {}", method.name(), path);
+        sender
+            .send((PathBuf::from(path), method, Err(error)))
+            .unwrap();
+    }
+
+    fn take_error(jobs: &mut ComponentJobs, path: &str, method: ComponentMethod) -> Option<String> {
+        jobs.take_result(Path::new(path), method)
+            .map(|images| images.err().unwrap())
+    }
+
+    #[test]
+    fn test_result_waits_for_worker() {
+        let mut jobs = ComponentJobs::new();
+        let sender = jobs.start(PathBuf::from("a.npy"), ComponentMethod::Pca);
+
+        // Nothing to upload while the worker runs, so the previous textures stay
+        assert!(!jobs.poll(Some(ComponentMethod::Pca)));
+        assert!(jobs.is_running(Path::new("a.npy"), ComponentMethod::Pca));
+        assert!(take_error(&mut jobs, "a.npy", ComponentMethod::Pca).is_none());
+
+        finish(&sender, "a.npy", ComponentMethod::Pca);
+        assert!(jobs.poll(Some(ComponentMethod::Pca)));
+        assert!(!jobs.is_busy());
+        assert_eq!(
+            take_error(&mut jobs, "a.npy", ComponentMethod::Pca).as_deref(),
+            Some("PCA of a.npy")
+        );
+        assert!(take_error(&mut jobs, "a.npy", ComponentMethod::Pca).is_none());
+    }
+
+    #[test]
+    fn test_stale_results_dropped() {
+        let mut jobs = ComponentJobs::new();
+        let old = jobs.start(PathBuf::from("a.npy"), ComponentMethod::Pca);
+        let new = jobs.start(PathBuf::from("a.npy"), ComponentMethod::Mnf);
+
+        // The abandoned computation finishing does not end the running one
+        finish(&old, "a.npy", ComponentMethod::Pca);
+        assert!(!jobs.poll(Some(ComponentMethod::Mnf)));
+        assert!(jobs.is_running(Path::new("a.npy"), ComponentMethod::Mnf));
+        assert!(take_error(&mut jobs, "a.npy", ComponentMethod::Pca).is_none());
+
+        finish(&new, "a.npy", ComponentMethod::Mnf);
+        assert!(jobs.poll(Some(ComponentMethod::Mnf)));
+        assert_eq!(
+            take_error(&mut jobs, "a.npy", ComponentMethod::Mnf).as_deref(),
+            Some("MNF of a.npy")
+        );
+
+        // A result for a method no longer selected is not kept
+        let sender = jobs.start(PathBuf::from("b.npy"), ComponentMethod::Pca);
+        finish(&sender, "b.npy", ComponentMethod::Pca);
+        assert!(!jobs.poll(None));
+        assert!(!jobs.is_busy());
+        assert!(take_error(&mut jobs, "b.npy", ComponentMethod::Pca).is_none());
+
+        // A result for another image is dropped when taken
+        let sender = jobs.start(PathBuf::from("c.npy"), ComponentMethod::Pca);
+        finish(&sender, "c.npy", ComponentMethod::Pca);
+        assert!(jobs.poll(Some(ComponentMethod::Pca)));
+        assert!(take_error(&mut jobs, "d.npy", ComponentMethod::Pca).is_none());
+        assert!(take_error(&mut jobs, "c.npy", ComponentMethod::Pca).is_none());
+    }
+}
@@ -3,14 +3,28 @@
 //! This module separates GPU resources into:
 //! - `SharedGpuPipeline`: The stateless rendering pipeline (created once, shared)
 //! - `GpuRenderState`: Per-image GPU data (band textures + render target)
+//! - `ComponentTextures`: Principal component images of one image, shown in
+//!   place of the bands in component mode
+//! - `TrueColorTextures`: sRGB images synthesized from the visible spectrum,
+//!   shown in place of the bands in true-color mode
+//!
+//! Images too large for one texture array are stored as a tiled pyramid
+//! (`GpuBands::Tiled`). Their render target only covers the tiles of the
+//! visible area, at the pyramid level matching the zoom (see
+//! `GpuRenderState::update_tiles`).
 
 use hvat_gpu::{
-    BandSelectionUniform, GpuContext, GpuError, HyperspectralGpuData, HyperspectralPipeline,
-    ImageAdjustments, Texture,
+    BandEncoding, BandMathUniform, BandSelectionUniform, Colormap, ColormapUniform, GpuContext,
+    GpuError, HyperspectralGpuData, HyperspectralPipeline, ImageAdjustments, StretchUniform,
+    TILE_SIZE, Texture, TileQuad, TileView, TiledGpuData,
 };
 
 use super::CachedGpuTexture;
-use crate::data::HyperspectralData;
+use crate::constants::{TILE_CACHE_BYTES, TILE_UPLOADS_PER_FRAME, TILED_IMAGE_BYTES};
+use crate::data::{
+    BandHistogram, BandInfo, BandMathProgram, ComponentMethod, HyperspectralData, Illuminant,
+    PrincipalComponents, TrueColor, band_histograms,
+};
 
 /// Shared GPU pipeline for hyperspectral rendering.
 ///
@@ -26,7 +40,10 @@ impl SharedGpuPipeline {
     /// Create a new shared GPU pipeline.
     pub fn new(gpu_ctx: &GpuContext) -> Self {
         let pipeline = HyperspectralPipeline::new(gpu_ctx);
-        log::info!("Created shared HyperspectralPipeline");
+        log::info!(
+            "Created shared HyperspectralPipeline (band textures: {})",
+            gpu_ctx.band_format.name()
+        );
         Self { pipeline }
     }
 
@@ -51,6 +68,46 @@ impl SharedGpuPipeline {
         self.pipeline.update_adjustments(gpu_ctx, adjustments);
     }
 
+    /// Update the contrast stretch uniform.
+    pub fn update_stretch(&self, gpu_ctx: &GpuContext, stretch: StretchUniform) {
+        self.pipeline.update_stretch(gpu_ctx, stretch);
+    }
+
+    /// Switch to the shader of a band-math program, or back to the band
+    /// composite with `None`. A shader the device rejects leaves the
+    /// composite in place.
+    pub fn set_band_math(
+        &mut self,
+        gpu_ctx: &GpuContext,
+        program: Option<&BandMathProgram>,
+    ) -> Result<(), GpuError> {
+        self.pipeline
+            .set_band_math_shader(gpu_ctx, program.map(|p| p.wgsl.as_str()))?;
+        if let Some(program) = program {
+            log::info!(
+                "Compiled band-math shader ({} channels, {} band samples)",
+                program.channels(),
+                program.bands.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Update the band value ranges used by the band-math shader.
+    pub fn update_band_math(&self, gpu_ctx: &GpuContext, band_math: BandMathUniform) {
+        self.pipeline.update_band_math(gpu_ctx, band_math);
+    }
+
+    /// Upload the lookup texture of a colormap.
+    pub fn set_colormap(&self, gpu_ctx: &GpuContext, colormap: Colormap) {
+        self.pipeline.set_colormap(gpu_ctx, colormap);
+    }
+
+    /// Update the colormap range and direction (disabled = band composite).
+    pub fn update_colormap(&self, gpu_ctx: &GpuContext, colormap: ColormapUniform) {
+        self.pipeline.update_colormap(gpu_ctx, colormap);
+    }
+
     /// Render using the given band data and render target.
     pub fn render(
         &self,
@@ -69,6 +126,226 @@ impl SharedGpuPipeline {
 
         gpu_ctx.queue.submit(std::iter::once(encoder.finish()));
     }
+
+    /// Render tiles of a tiled image, each into its area of the render target.
+    pub fn render_tiles(
+        &self,
+        gpu_ctx: &GpuContext,
+        render_target: &Texture,
+        quads: &[TileQuad<'_>],
+    ) {
+        let mut encoder = gpu_ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Hyperspectral Tile Render Encoder"),
+            });
+
+        self.pipeline
+            .render_tiles(gpu_ctx, &mut encoder, &render_target.view, quads);
+
+        gpu_ctx.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// Band textures of an image: one texture array, or a tiled pyramid for
+/// images too large for one.
+pub enum GpuBands {
+    /// All bands in one texture array
+    Single(HyperspectralGpuData),
+    /// Tiles uploaded as they become visible
+    Tiled(TiledGpuData),
+}
+
+impl GpuBands {
+    /// Upload `bands` (`width` x `height`), tiled if they exceed the texture
+    /// size limit or `TILED_IMAGE_BYTES`.
+    pub fn upload(
+        gpu_ctx: &GpuContext,
+        pipeline: &SharedGpuPipeline,
+        bands: &[Vec<f32>],
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let layout = pipeline.band_texture_layout();
+        if TiledGpuData::required(gpu_ctx, width, height, bands.len(), TILED_IMAGE_BYTES) {
+            let tiled = TiledGpuData::new(
+                gpu_ctx,
+                bands,
+                width,
+                height,
+                TILE_SIZE,
+                TILE_CACHE_BYTES,
+                layout,
+            );
+            log::info!(
+                "Tiled {}x{} image with {} bands ({} pyramid levels)",
+                width,
+                height,
+                bands.len(),
+                tiled.layout.levels
+            );
+            Self::Tiled(tiled)
+        } else {
+            Self::Single(HyperspectralGpuData::from_bands(
+                gpu_ctx, bands, width, height, layout,
+            ))
+        }
+    }
+
+    /// Texel format and value range of each band.
+    pub fn encoding(&self) -> &BandEncoding {
+        match self {
+            Self::Single(data) => &data.encoding,
+            Self::Tiled(tiled) => &tiled.encoding,
+        }
+    }
+
+    /// Whether the bands are stored as tiles.
+    pub fn is_tiled(&self) -> bool {
+        matches!(self, Self::Tiled(_))
+    }
+}
+
+/// Result of `GpuRenderState::update_tiles`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TileUpdate {
+    /// The render target was recreated (it must be registered again)
+    pub target_resized: bool,
+    /// Visible tiles are still missing (update again next frame)
+    pub pending: bool,
+}
+
+/// Principal component images of one image, uploaded like a band cube.
+///
+/// Component `i` is band `i` of the texture array, so the band composite path
+/// displays any three components as RGB.
+pub struct ComponentTextures {
+    /// Components the images were projected with
+    pub components: PrincipalComponents,
+    /// Component images on the GPU
+    pub gpu_data: GpuBands,
+    /// Component images, kept to upload tiles (empty unless tiled)
+    pub images: Vec<Vec<f32>>,
+    /// Value distribution of each component image (for the stretch)
+    pub histograms: Vec<BandHistogram>,
+}
+
+/// Component images computed on the CPU, ready to upload.
+///
+/// Computing them is the slow part of component mode, so it runs on a worker
+/// thread where threads are available (see `ComponentTextures::upload`).
+pub struct ComponentImages {
+    /// Components the images were projected with
+    pub components: PrincipalComponents,
+    /// Component images, strongest first
+    pub images: Vec<Vec<f32>>,
+    /// Value distribution of each component image (for the stretch)
+    pub histograms: Vec<BandHistogram>,
+}
+
+impl ComponentImages {
+    /// Compute the strongest `count` components of `bands` (an image `width`
+    /// pixels wide) and project the image onto them.
+    pub fn compute(
+        bands: &[Vec<f32>],
+        width: u32,
+        method: ComponentMethod,
+        count: usize,
+        max_samples: usize,
+    ) -> Result<Self, String> {
+        let components = PrincipalComponents::compute(bands, width, method, max_samples)?;
+        let images = components.project(bands, count);
+        Ok(Self {
+            histograms: band_histograms(&images),
+            components,
+            images,
+        })
+    }
+}
+
+impl ComponentTextures {
+    /// Upload the computed component images of a `width` x `height` image.
+    pub fn upload(
+        gpu_ctx: &GpuContext,
+        pipeline: &SharedGpuPipeline,
+        computed: ComponentImages,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let ComponentImages {
+            components,
+            images,
+            histograms,
+        } = computed;
+        let gpu_data = GpuBands::upload(gpu_ctx, pipeline, &images, width, height);
+        log::info!(
+            "Uploaded {} {} components ({}x{}, {} samples)",
+            images.len(),
+            components.method.name(),
+            width,
+            height,
+            components.samples
+        );
+        Self {
+            histograms,
+            components,
+            images: if gpu_data.is_tiled() {
+                images
+            } else {
+                Vec::new()
+            },
+            gpu_data,
+        }
+    }
+
+    /// Number of uploaded component images.
+    pub fn num_components(&self) -> usize {
+        self.histograms.len()
+    }
+}
+
+/// True-color images of one image: red, green and blue are bands 0 to 2.
+pub struct TrueColorTextures {
+    /// Band weights the images were computed with
+    pub true_color: TrueColor,
+    /// sRGB images on the GPU
+    pub gpu_data: GpuBands,
+    /// sRGB images, kept to upload tiles (empty unless tiled)
+    pub images: Vec<Vec<f32>>,
+    /// Value distribution of each channel (for the stretch)
+    pub histograms: Vec<BandHistogram>,
+}
+
+impl TrueColorTextures {
+    /// Synthesize the sRGB images of `hyper` (with the wavelengths of
+    /// `band_info`) under `illuminant` and upload them.
+    pub fn compute(
+        gpu_ctx: &GpuContext,
+        pipeline: &SharedGpuPipeline,
+        hyper: &HyperspectralData,
+        band_info: &[BandInfo],
+        illuminant: Illuminant,
+    ) -> Result<Self, String> {
+        let true_color = TrueColor::new(band_info, illuminant)?;
+        let images = true_color.render(&hyper.bands);
+        let gpu_data = GpuBands::upload(gpu_ctx, pipeline, &images, hyper.width, hyper.height);
+        log::info!(
+            "Uploaded true-color image ({}x{}, {})",
+            hyper.width,
+            hyper.height,
+            illuminant.name()
+        );
+        Ok(Self {
+            histograms: band_histograms(&images),
+            true_color,
+            images: if gpu_data.is_tiled() {
+                images
+            } else {
+                Vec::new()
+            },
+            gpu_data,
+        })
+    }
 }
 
 /// Per-image GPU render state.
@@ -76,15 +353,23 @@ impl SharedGpuPipeline {
 /// Contains GPU resources specific to one image: band textures and render target.
 /// The band textures can come from fresh upload or from the GPU cache.
 pub struct GpuRenderState {
-    /// Band data uploaded to GPU texture array
-    pub band_data: HyperspectralGpuData,
-    /// Render target texture for compositing
+    /// Band data uploaded to the GPU
+    pub band_data: GpuBands,
+    /// Render target texture for compositing (the visible tiles when tiled)
     pub render_target: Texture,
     /// Image dimensions
     pub width: u32,
     pub height: u32,
     /// Number of bands
     pub num_bands: usize,
+    /// Component images shown instead of the bands (component mode)
+    pub components: Option<ComponentTextures>,
+    /// True-color images shown instead of the bands (true-color mode)
+    pub true_color: Option<TrueColorTextures>,
+    /// Similarity map shown instead of the bands (not cached)
+    pub similarity: Option<GpuBands>,
+    /// Tiles covered by the render target (tiled images only)
+    pub tile_view: Option<TileView>,
 }
 
 impl GpuRenderState {
@@ -96,36 +381,41 @@ impl GpuRenderState {
         band_selection: (usize, usize, usize),
         adjustments: ImageAdjustments,
     ) -> Result<Self, GpuError> {
-        let band_data = HyperspectralGpuData::from_bands(
-            gpu_ctx,
-            &hyper.bands,
-            hyper.width,
-            hyper.height,
-            pipeline.band_texture_layout(),
-        );
+        let band_data =
+            GpuBands::upload(gpu_ctx, pipeline, &hyper.bands, hyper.width, hyper.height);
         log::info!(
-            "Uploaded {} bands ({}x{}) to GPU texture array",
+            "Uploaded {} bands ({}x{}) to GPU texture array ({})",
             hyper.bands.len(),
             hyper.width,
-            hyper.height
+            hyper.height,
+            band_data.encoding().format.name()
         );
 
-        let render_target = Texture::render_target(gpu_ctx, hyper.width, hyper.height)?;
+        // A tiled image starts out with its overview until `update_tiles`
+        let (target_width, target_height) = match &band_data {
+            GpuBands::Single(_) => (hyper.width, hyper.height),
+            GpuBands::Tiled(tiled) => {
+                let (_, _, w, h) = tiled.layout.view_rect(&tiled.layout.overview());
+                (w, h)
+            }
+        };
+        let render_target = Texture::render_target(gpu_ctx, target_width, target_height)?;
         log::info!(
             "Created render target texture ({}x{})",
-            hyper.width,
-            hyper.height
+            target_width,
+            target_height
         );
 
         // Set initial uniforms
         pipeline.update_band_selection(
             gpu_ctx,
-            BandSelectionUniform {
-                red_band: band_selection.0 as u32,
-                green_band: band_selection.1 as u32,
-                blue_band: band_selection.2 as u32,
-                num_bands: hyper.bands.len() as u32,
-            },
+            BandSelectionUniform::new(
+                band_selection.0,
+                band_selection.1,
+                band_selection.2,
+                hyper.bands.len(),
+            )
+            .with_ranges(band_data.encoding()),
         );
         pipeline.update_adjustments(gpu_ctx, adjustments);
 
@@ -135,6 +425,10 @@ impl GpuRenderState {
             width: hyper.width,
             height: hyper.height,
             num_bands: hyper.bands.len(),
+            components: None,
+            true_color: None,
+            similarity: None,
+            tile_view: None,
         })
     }
 
@@ -160,50 +454,172 @@ impl GpuRenderState {
         // Set initial uniforms
         pipeline.update_band_selection(
             gpu_ctx,
-            BandSelectionUniform {
-                red_band: band_selection.0 as u32,
-                green_band: band_selection.1 as u32,
-                blue_band: band_selection.2 as u32,
-                num_bands: cached.num_bands as u32,
-            },
+            BandSelectionUniform::new(
+                band_selection.0,
+                band_selection.1,
+                band_selection.2,
+                cached.num_bands,
+            )
+            .with_ranges(&cached.gpu_data.encoding),
         );
         pipeline.update_adjustments(gpu_ctx, adjustments);
 
         Ok(Self {
-            band_data: cached.gpu_data,
+            band_data: GpuBands::Single(cached.gpu_data),
             render_target,
             width: cached.width,
             height: cached.height,
             num_bands: cached.num_bands,
+            components: cached.components,
+            true_color: cached.true_color,
+            similarity: None,
+            tile_view: None,
         })
     }
 
+    /// Whether the image is stored as tiles.
+    pub fn is_tiled(&self) -> bool {
+        self.band_data.is_tiled()
+    }
+
+    /// Data currently displayed: the similarity map, the component images,
+    /// the true-color images or the bands.
+    fn displayed(&self) -> &GpuBands {
+        self.similarity
+            .as_ref()
+            .or(self.components.as_ref().map(|c| &c.gpu_data))
+            .or(self.true_color.as_ref().map(|t| &t.gpu_data))
+            .unwrap_or(&self.band_data)
+    }
+
+    /// Upload the tiles showing `rect` (x, y, width, height in image pixels)
+    /// at `zoom`, and fit the render target to them.
+    ///
+    /// `bands` and `similarity` are the CPU values of the bands and of the
+    /// similarity map, whichever is displayed. Does nothing unless the
+    /// displayed data is tiled. At most `TILE_UPLOADS_PER_FRAME` tiles are
+    /// uploaded per call; coarser levels are drawn in place of the rest.
+    pub fn update_tiles(
+        &mut self,
+        gpu_ctx: &GpuContext,
+        pipeline: &SharedGpuPipeline,
+        rect: (f32, f32, f32, f32),
+        zoom: f32,
+        bands: &[Vec<f32>],
+        similarity: Option<&Vec<f32>>,
+    ) -> Result<TileUpdate, GpuError> {
+        let (data, source) = if let Some(data) = self.similarity.as_mut() {
+            let source = similarity.map(std::slice::from_ref).unwrap_or_default();
+            (data, source)
+        } else if let Some(textures) = self.components.as_mut() {
+            (&mut textures.gpu_data, textures.images.as_slice())
+        } else if let Some(textures) = self.true_color.as_mut() {
+            (&mut textures.gpu_data, textures.images.as_slice())
+        } else {
+            (&mut self.band_data, bands)
+        };
+        let GpuBands::Tiled(tiled) = data else {
+            self.tile_view = None;
+            return Ok(TileUpdate::default());
+        };
+
+        let max_dimension = gpu_ctx.device.limits().max_texture_dimension_2d;
+        let view = tiled.view(rect, zoom, max_dimension);
+        let missing = tiled.upload(
+            gpu_ctx,
+            source,
+            view,
+            TILE_UPLOADS_PER_FRAME,
+            pipeline.band_texture_layout(),
+        );
+        let (_, _, width, height) = tiled.layout.view_rect(&view);
+        let target_resized =
+            (self.render_target.width, self.render_target.height) != (width, height);
+        if target_resized {
+            self.render_target = Texture::render_target(gpu_ctx, width, height)?;
+        }
+        if self.tile_view != Some(view) {
+            log::debug!(
+                "Tile view: level {}, tiles {}..{} x {}..{} ({} missing)",
+                view.level,
+                view.x0,
+                view.x1,
+                view.y0,
+                view.y1,
+                missing
+            );
+        }
+        self.tile_view = Some(view);
+        Ok(TileUpdate {
+            target_resized,
+            pending: missing > 0,
+        })
+    }
+
+    /// Image area covered by the render target (x, y, width, height in image
+    /// pixels), `None` when it covers the whole image.
+    pub fn target_region(&self) -> Option<(f32, f32, f32, f32)> {
+        let GpuBands::Tiled(tiled) = self.displayed() else {
+            return None;
+        };
+        let view = self.tile_view.unwrap_or_else(|| tiled.layout.overview());
+        Some(tiled.layout.view_image_rect(&view))
+    }
+
     /// Render to the render target texture using the shared pipeline.
+    ///
+    /// The value ranges of the selected bands, and of the bands sampled by
+    /// the active band-math program, are filled in from the band data. With
+    /// a similarity map, component or true-color images present,
+    /// `band_selection` indexes those instead (in that order).
     pub fn render(
         &self,
         gpu_ctx: &GpuContext,
         pipeline: &SharedGpuPipeline,
         band_selection: BandSelectionUniform,
         adjustments: ImageAdjustments,
+        stretch: StretchUniform,
+        band_math: Option<&BandMathProgram>,
     ) {
+        let displayed = self.displayed();
+
         // Update uniforms
-        pipeline.update_band_selection(gpu_ctx, band_selection);
+        pipeline.update_band_selection(gpu_ctx, band_selection.with_ranges(displayed.encoding()));
         pipeline.update_adjustments(gpu_ctx, adjustments);
+        pipeline.update_stretch(gpu_ctx, stretch);
+        if let Some(program) = band_math {
+            pipeline.update_band_math(
+                gpu_ctx,
+                BandMathUniform::new(&program.bands, self.band_data.encoding()),
+            );
+        }
 
         // Render using shared pipeline
-        pipeline.render(gpu_ctx, &self.render_target, &self.band_data);
+        match displayed {
+            GpuBands::Single(data) => pipeline.render(gpu_ctx, &self.render_target, data),
+            GpuBands::Tiled(tiled) => {
+                let view = self.tile_view.unwrap_or_else(|| tiled.layout.overview());
+                pipeline.render_tiles(gpu_ctx, &self.render_target, &tiled.quads(view));
+            }
+        }
     }
 
     /// Convert this render state back into a cached texture.
     ///
     /// Consumes self and returns the band data as a `CachedGpuTexture`.
-    /// The render target is dropped, but band textures are preserved.
-    pub fn into_cached(self) -> CachedGpuTexture {
-        CachedGpuTexture {
-            gpu_data: self.band_data,
+    /// The render target is dropped, but band textures, component and
+    /// true-color images are preserved. Tiled images are not cached (`None`).
+    pub fn into_cached(self) -> Option<CachedGpuTexture> {
+        let GpuBands::Single(gpu_data) = self.band_data else {
+            return None;
+        };
+        Some(CachedGpuTexture {
+            gpu_data,
             width: self.width,
             height: self.height,
             num_bands: self.num_bands,
-        }
+            components: self.components,
+            true_color: self.true_color,
+        })
     }
 }
@@ -6,8 +6,10 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
-use hvat_gpu::{GpuContext, HyperspectralGpuData};
+use hvat_gpu::{BandEncoding, GpuContext, HyperspectralGpuData, TiledGpuData};
 
+use super::gpu::{ComponentTextures, TrueColorTextures};
+use crate::constants::TILED_IMAGE_BYTES;
 use crate::data::HyperspectralData;
 
 /// Calculate backward index with wraparound, avoiding underflow.
@@ -32,6 +34,10 @@ pub struct CachedGpuTexture {
     pub height: u32,
     /// Number of spectral bands
     pub num_bands: usize,
+    /// Principal component images, kept once computed
+    pub components: Option<ComponentTextures>,
+    /// True-color images, kept once computed
+    pub true_color: Option<TrueColorTextures>,
 }
 
 /// Cache for preloaded GPU textures.
@@ -43,6 +49,8 @@ pub struct GpuTextureCache {
     entries: HashMap<PathBuf, CachedGpuTexture>,
     /// Maximum number of images to preload in each direction
     preload_count: usize,
+    /// Images too large to preload (shown as tiles, see `GpuBands`)
+    skipped: HashSet<PathBuf>,
 }
 
 impl GpuTextureCache {
@@ -51,6 +59,7 @@ impl GpuTextureCache {
         Self {
             entries: HashMap::new(),
             preload_count,
+            skipped: HashSet::new(),
         }
     }
 
@@ -65,6 +74,13 @@ impl GpuTextureCache {
         self.entries.contains_key(path)
     }
 
+    /// Never preload an image again, e.g. because it is too large for one
+    /// texture array and is uploaded tile by tile while shown.
+    pub fn skip(&mut self, path: PathBuf) {
+        log::info!("Not preloading {:?}: it is shown tiled", path);
+        self.skipped.insert(path);
+    }
+
     /// Take cached GPU data, removing it from cache.
     /// Used when transferring ownership to GpuRenderState.
     pub fn take(&mut self, path: &PathBuf) -> Option<CachedGpuTexture> {
@@ -89,7 +105,7 @@ impl GpuTextureCache {
 
     /// Upload hyperspectral data to GPU and cache it.
     ///
-    /// Returns Ok(()) on success, logs warning on failure.
+    /// Images that would be shown tiled are skipped instead.
     pub fn upload_and_cache(
         &mut self,
         gpu_ctx: &GpuContext,
@@ -97,6 +113,16 @@ impl GpuTextureCache {
         hyper: &HyperspectralData,
         bind_group_layout: &wgpu::BindGroupLayout,
     ) {
+        if TiledGpuData::required(
+            gpu_ctx,
+            hyper.width,
+            hyper.height,
+            hyper.bands.len(),
+            TILED_IMAGE_BYTES,
+        ) {
+            self.skip(path);
+            return;
+        }
         log::info!(
             "Caching GPU texture for: {:?} ({}x{}, {} bands)",
             path,
@@ -118,6 +144,8 @@ impl GpuTextureCache {
             width: hyper.width,
             height: hyper.height,
             num_bands: hyper.bands.len(),
+            components: None,
+            true_color: None,
         };
 
         self.insert(path.clone(), cached);
@@ -131,6 +159,7 @@ impl GpuTextureCache {
     /// Insert a pre-uploaded texture into the cache (for chunked upload workflow).
     ///
     /// Creates the `HyperspectralGpuData` from an already-uploaded texture and caches it.
+    /// `encoding` describes how the layers were packed (one range per band).
     #[allow(dead_code)] // Used only in WASM builds
     pub fn insert_from_texture(
         &mut self,
@@ -139,10 +168,11 @@ impl GpuTextureCache {
         texture: wgpu::Texture,
         width: u32,
         height: u32,
-        num_bands: usize,
         num_layers: u32,
+        encoding: BandEncoding,
         bind_group_layout: &wgpu::BindGroupLayout,
     ) {
+        let num_bands = encoding.num_bands();
         log::info!(
             "Caching pre-uploaded GPU texture for: {:?} ({}x{}, {} bands, {} layers)",
             path,
@@ -157,8 +187,8 @@ impl GpuTextureCache {
             texture,
             width,
             height,
-            num_bands,
             num_layers,
+            encoding,
             bind_group_layout,
         );
 
@@ -167,6 +197,8 @@ impl GpuTextureCache {
             width,
             height,
             num_bands,
+            components: None,
+            true_color: None,
         };
 
         self.insert(path.clone(), cached);
@@ -181,6 +213,7 @@ impl GpuTextureCache {
     pub fn clear(&mut self) {
         let count = self.entries.len();
         self.entries.clear();
+        self.skipped.clear();
         if count > 0 {
             log::info!("Cleared GPU texture cache ({} entries)", count);
         }
@@ -203,8 +236,9 @@ impl GpuTextureCache {
 
     /// Get paths that should be preloaded based on current index.
     ///
-    /// Returns paths that are within preload range and not yet cached.
-    /// The current image is excluded since it's already being displayed.
+    /// Returns paths that are within preload range, not yet cached and not
+    /// skipped. The current image is excluded since it's already being
+    /// displayed.
     pub fn paths_to_preload(&self, images: &[PathBuf], current_index: usize) -> Vec<PathBuf> {
         if self.preload_count == 0 || images.is_empty() {
             return Vec::new();
@@ -220,7 +254,7 @@ impl GpuTextureCache {
             let forward_idx = (current_index + offset) % len;
             let forward_path = &images[forward_idx];
             // Skip if it's the current image (wraparound case) or already cached
-            if forward_path != current_path && !self.contains(forward_path) {
+            if forward_path != current_path && self.wants(forward_path) {
                 to_preload.push(forward_path.clone());
             }
 
@@ -228,7 +262,7 @@ impl GpuTextureCache {
             let backward_idx = wrap_backward(current_index, offset, len);
             let backward_path = &images[backward_idx];
             // Skip if it's the current image (wraparound case) or already cached
-            if backward_path != current_path && !self.contains(backward_path) {
+            if backward_path != current_path && self.wants(backward_path) {
                 to_preload.push(backward_path.clone());
             }
         }
@@ -236,6 +270,11 @@ impl GpuTextureCache {
         to_preload
     }
 
+    /// Whether an image still needs preloading.
+    fn wants(&self, path: &PathBuf) -> bool {
+        !self.contains(path) && !self.skipped.contains(path)
+    }
+
     /// Get the set of paths that should be kept in cache.
     ///
     /// Includes current image and N images before/after.
@@ -285,6 +324,23 @@ mod tests {
         assert!(to_preload.contains(&PathBuf::from("img3.png")));
     }
 
+    #[test]
+    fn test_skipped_paths_not_preloaded() {
+        let mut cache = GpuTextureCache::new(1);
+        let images: Vec<PathBuf> = (0..5)
+            .map(|i| PathBuf::from(format!("img{}.png", i)))
+            .collect();
+
+        cache.skip(PathBuf::from("img1.png"));
+        assert_eq!(
+            cache.paths_to_preload(&images, 0),
+            vec![PathBuf::from("img4.png")]
+        );
+        // A folder change forgets them
+        cache.clear();
+        assert_eq!(cache.paths_to_preload(&images, 0).len(), 2);
+    }
+
     #[test]
     fn test_paths_to_keep() {
         let cache = GpuTextureCache::new(1);
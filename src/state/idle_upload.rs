@@ -5,6 +5,7 @@
 //!
 //! Architecture:
 //! 1. Background decoder (Worker on WASM, thread on native) decodes + packs into RGBA layers
+//!    (in the `BandEncoding` chosen for the image, e.g. 16-bit with per-band ranges)
 //! 2. Main thread receives pre-packed layers
 //! 3. Main thread creates GPU texture (allocation only, fast)
 //! 4. Each tick: upload CHUNK of rows to GPU (configurable blocking duration)
@@ -15,7 +16,7 @@
 use std::collections::VecDeque;
 use std::path::PathBuf;
 
-use hvat_gpu::GpuContext;
+use hvat_gpu::{BandEncoding, BandTextureFormat, GpuContext};
 
 use super::preload_types::PackedLayer;
 use crate::constants::GPU_UPLOAD_ROWS_PER_TICK;
@@ -28,6 +29,7 @@ fn create_band_texture(
     width: u32,
     height: u32,
     num_layers: u32,
+    format: BandTextureFormat,
 ) -> wgpu::Texture {
     device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Band Texture"),
@@ -39,7 +41,7 @@ fn create_band_texture(
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba8Unorm,
+        format: format.texture_format(),
         usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
         view_formats: &[],
     })
@@ -47,7 +49,7 @@ fn create_band_texture(
 
 /// A single texture layer with row-based chunking support.
 pub struct PendingLayer {
-    /// RGBA pixel data (width * height * 4 bytes)
+    /// RGBA texel data (width * height texels in the upload's format)
     pub rgba_data: Vec<u8>,
     /// Layer index in the texture array
     pub layer_index: u32,
@@ -70,6 +72,8 @@ pub struct ChunkedUpload {
     pub pending_layers: VecDeque<PendingLayer>,
     /// GPU texture (created upfront, layers uploaded incrementally)
     pub texture: wgpu::Texture,
+    /// Texel format and per-band value ranges of the packed layers
+    pub encoding: BandEncoding,
 }
 
 /// Result of a completed chunked upload, ready for cache insertion.
@@ -80,6 +84,7 @@ pub struct CompletedChunkedUpload {
     pub num_bands: usize,
     pub num_layers: u32,
     pub texture: wgpu::Texture,
+    pub encoding: BandEncoding,
 }
 
 /// Queue for chunked GPU uploads.
@@ -104,26 +109,28 @@ impl ChunkedUploadQueue {
 
     /// Queue pre-packed layers for chunked upload.
     ///
-    /// The RGBA packing has already been done by the worker thread.
-    /// This just creates the GPU texture and queues the layers for upload.
-    /// Actual layer uploads happen via `process_one_layer`.
+    /// The RGBA packing has already been done by the worker thread, using
+    /// `encoding`. This just creates the GPU texture and queues the layers for
+    /// upload. Actual layer uploads happen via `process_one_layer`.
     pub fn queue_prepacked(
         &mut self,
         path: PathBuf,
         width: u32,
         height: u32,
-        num_bands: usize,
         num_layers: u32,
         layers: Vec<PackedLayer>,
+        encoding: BandEncoding,
         device: &wgpu::Device,
     ) {
+        let num_bands = encoding.num_bands();
         log::info!(
-            "Queueing pre-packed upload for {:?}: {}x{}, {} bands, {} layers",
+            "Queueing pre-packed upload for {:?}: {}x{}, {} bands, {} layers ({})",
             path,
             width,
             height,
             num_bands,
-            num_layers
+            num_layers,
+            encoding.format.name()
         );
 
         // Convert pre-packed layers to pending layers
@@ -137,7 +144,7 @@ impl ChunkedUploadQueue {
             .collect();
 
         // Create the texture upfront (fast, just allocation)
-        let texture = create_band_texture(device, width, height, num_layers);
+        let texture = create_band_texture(device, width, height, num_layers, encoding.format);
 
         self.uploads.push_back(ChunkedUpload {
             path,
@@ -147,6 +154,7 @@ impl ChunkedUploadQueue {
             num_layers,
             pending_layers,
             texture,
+            encoding,
         });
     }
 
@@ -200,13 +208,14 @@ impl ChunkedUploadQueue {
                     num_bands: finished.num_bands,
                     num_layers: finished.num_layers,
                     texture: finished.texture,
+                    encoding: finished.encoding,
                 });
             }
             return true;
         }
 
         // Calculate byte offsets for the row range
-        let bytes_per_row = 4 * width;
+        let bytes_per_row = upload.encoding.format.bytes_per_texel() * width;
         let start_byte = (start_row * bytes_per_row) as usize;
         let end_byte = ((start_row + rows_to_upload) * bytes_per_row) as usize;
 
@@ -268,6 +277,7 @@ impl ChunkedUploadQueue {
                     num_bands: finished.num_bands,
                     num_layers: finished.num_layers,
                     texture: finished.texture,
+                    encoding: finished.encoding,
                 });
             }
         }
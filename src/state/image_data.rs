@@ -2,9 +2,11 @@
 
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+use crate::data::{BandHistogram, BandInfo, CalibrationSource, GeoTransform};
 use crate::model::{Annotation, AnnotationId, DrawingState, EditState};
 
 /// Data associated with a specific image (tags, annotations, etc.)
@@ -17,6 +19,21 @@ pub struct ImageData {
     /// Image dimensions (width, height) - stored when image is loaded
     #[serde(default)]
     pub dimensions: Option<(u32, u32)>,
+    /// Pixel-to-map transform for georeferenced images - stored when image is loaded
+    #[serde(default)]
+    pub geotransform: Option<GeoTransform>,
+    /// Per-band metadata (wavelengths etc.) - stored when image is loaded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub band_info: Vec<BandInfo>,
+    /// Radiometric calibration of the loaded data - stored when image is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<CalibrationSource>,
+    /// Value histogram of each band (transient, computed on every decode)
+    #[serde(skip)]
+    pub band_histograms: Arc<Vec<BandHistogram>>,
+    /// Warnings reported by the loader (transient, refreshed on every decode)
+    #[serde(skip)]
+    pub load_warnings: Vec<String>,
     /// Current drawing state for this image (transient, not serialized)
     #[serde(skip)]
     pub drawing_state: DrawingState,
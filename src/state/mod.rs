@@ -1,5 +1,7 @@
 //! Application state management modules.
 
+#[cfg(not(target_arch = "wasm32"))]
+mod component_jobs;
 mod gpu;
 mod gpu_cache;
 mod idle_upload;
@@ -11,9 +13,15 @@ mod preload_types;
 mod preload_worker;
 mod project;
 mod snapshot;
+mod spectrum;
 mod zip_import;
 
-pub use gpu::{GpuRenderState, SharedGpuPipeline};
+#[cfg(not(target_arch = "wasm32"))]
+pub use component_jobs::ComponentJobs;
+pub use gpu::{
+    ComponentImages, ComponentTextures, GpuBands, GpuRenderState, SharedGpuPipeline,
+    TrueColorTextures,
+};
 pub use gpu_cache::{CachedGpuTexture, GpuTextureCache};
 pub use idle_upload::ChunkedUploadQueue;
 #[allow(unused_imports)]
@@ -25,9 +33,12 @@ pub use preload_types::DecodeResult;
 #[cfg(target_arch = "wasm32")]
 pub use preload_worker::ImageDecoderWorker;
 #[cfg(target_arch = "wasm32")]
-pub use project::is_image_filename;
+pub use project::{is_image_filename, is_sidecar_filename};
 pub use project::{LoadedImage, ProjectState};
 pub use snapshot::{AnnotationState, AppSnapshot};
+pub use spectrum::{
+    AnnotationStats, PinnedSpectrum, has_wavelength_axis, next_pin_color, spectrum_points,
+};
 #[cfg(target_arch = "wasm32")]
 pub use zip_import::{extract_images_from_zip_bytes, is_zip_file};
 #[cfg(not(target_arch = "wasm32"))]
@@ -6,13 +6,18 @@
 
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
 use std::thread::{self, JoinHandle};
 
+use hvat_gpu::{BandEncoding, BandTextureFormat};
+
 use super::preload_types::{
     DecodeError, DecodeResult, DecodedImage, calculate_num_layers, pack_bands_to_layers,
 };
-use crate::data::HyperspectralData;
+use crate::data::{
+    DataProcessor, FileBytes, HyperspectralData, LoaderOptions, MemoryResolver, band_histograms,
+};
 
 /// Request to decode an image, sent to the background thread.
 struct DecodeRequest {
@@ -21,14 +26,22 @@ struct DecodeRequest {
     id: u32,
     /// Path for cache key
     path: PathBuf,
-    /// Raw image bytes
-    data: Vec<u8>,
+    /// Raw image bytes (left on disk for raw payloads)
+    data: FileBytes,
+    /// Sidecar files needed to decode multi-file formats (e.g. ENVI payloads)
+    sidecars: Vec<(String, FileBytes)>,
+    /// User loader settings
+    options: LoaderOptions,
+    /// Processing steps run on the decoded data (e.g. the calibration)
+    processors: Vec<Arc<dyn DataProcessor>>,
+    /// Texel format to pack the band layers in
+    band_format: BandTextureFormat,
 }
 
 /// Message sent to the decoder thread.
 enum ThreadMessage {
-    /// Decode an image
-    Decode(DecodeRequest),
+    /// Decode an image (boxed, it is much larger than the other messages)
+    Decode(Box<DecodeRequest>),
     /// Shutdown the thread
     Shutdown,
 }
@@ -84,7 +97,7 @@ impl NativeDecoderThread {
         loop {
             match request_rx.recv() {
                 Ok(ThreadMessage::Decode(request)) => {
-                    let result = Self::decode_image(request.path, &request.data);
+                    let result = Self::decode_image(*request);
                     if result_tx.send(result).is_err() {
                         log::warn!("Result channel closed, decoder thread exiting");
                         break;
@@ -104,19 +117,41 @@ impl NativeDecoderThread {
     }
 
     /// Decode an image and pack it into RGBA layers.
-    fn decode_image(path: PathBuf, data: &[u8]) -> DecodeResult {
+    fn decode_image(request: DecodeRequest) -> DecodeResult {
+        let DecodeRequest {
+            path,
+            data,
+            sidecars,
+            options,
+            processors,
+            band_format,
+            ..
+        } = request;
         log::debug!("Decoding image: {:?} ({} bytes)", path, data.len());
 
-        // Try to load as hyperspectral first
-        match HyperspectralData::from_bytes(data) {
+        let filename = path.file_name().and_then(|n| n.to_str());
+        let resolver = MemoryResolver::new(sidecars);
+        let options = options.for_path(&path);
+        let hyper_result = HyperspectralData::from_bytes_with_resolver(
+            &data,
+            filename,
+            &resolver,
+            &options,
+            &processors,
+        );
+
+        match hyper_result {
             Ok(hyper) => {
                 let width = hyper.width;
                 let height = hyper.height;
                 let num_bands = hyper.bands.len();
                 let num_layers = calculate_num_layers(num_bands);
 
-                // Pack bands into RGBA layers
-                let layers = pack_bands_to_layers(&hyper.bands, width, height, num_layers);
+                // Pack bands into RGBA layers, keeping each band's value range
+                let encoding = BandEncoding::for_bands(&hyper.bands, band_format);
+                let layers =
+                    pack_bands_to_layers(&hyper.bands, width, height, num_layers, &encoding);
+                let histograms = band_histograms(&hyper.bands);
 
                 log::debug!(
                     "Decoded {:?}: {}x{} with {} bands, {} layers",
@@ -127,14 +162,19 @@ impl NativeDecoderThread {
                     num_layers
                 );
 
-                DecodeResult::Decoded(DecodedImage {
+                DecodeResult::Decoded(Box::new(DecodedImage {
                     path,
                     width,
                     height,
                     num_bands,
                     num_layers,
                     layers,
-                })
+                    encoding,
+                    warnings: hyper.warnings,
+                    band_info: hyper.band_info,
+                    histograms,
+                    calibration: hyper.calibration,
+                }))
             }
             Err(e) => {
                 log::debug!("Failed to decode {:?}: {}", path, e);
@@ -149,17 +189,36 @@ impl NativeDecoderThread {
     /// Request decode of an image.
     ///
     /// The request is sent to the background thread asynchronously.
-    pub fn request_decode(&mut self, path: PathBuf, data: Vec<u8>) {
+    /// `sidecars` carries the extra files needed by multi-file formats,
+    /// `processors` run on the decoded data (see `ProjectState::processors`),
+    /// and the bands are packed in `band_format` (see `GpuContext::band_format`).
+    pub fn request_decode(
+        &mut self,
+        path: PathBuf,
+        data: FileBytes,
+        sidecars: Vec<(String, FileBytes)>,
+        options: LoaderOptions,
+        processors: Vec<Arc<dyn DataProcessor>>,
+        band_format: BandTextureFormat,
+    ) {
         let id = self.next_id;
         self.next_id += 1;
 
         self.pending_paths.insert(path.clone());
 
-        let request = DecodeRequest { id, path, data };
+        let request = DecodeRequest {
+            id,
+            path,
+            data,
+            sidecars,
+            options,
+            processors,
+            band_format,
+        };
 
         if self
             .request_tx
-            .send(ThreadMessage::Decode(request))
+            .send(ThreadMessage::Decode(Box::new(request)))
             .is_err()
         {
             log::error!("Failed to send decode request: channel closed");
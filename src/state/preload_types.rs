@@ -5,8 +5,11 @@
 
 use std::path::PathBuf;
 
+use hvat_gpu::BandEncoding;
+
 #[cfg(not(target_arch = "wasm32"))]
 use crate::constants::{BANDS_PER_LAYER, MIN_TEXTURE_LAYERS};
+use crate::data::{BandHistogram, BandInfo, CalibrationSource};
 
 /// Calculate the number of texture layers needed for a given number of bands.
 ///
@@ -19,14 +22,15 @@ pub fn calculate_num_layers(num_bands: usize) -> u32 {
 
 /// Pack band data into RGBA texture layers.
 ///
-/// Takes spectral band data (f32 normalized to 0.0-1.0) and packs it into
-/// RGBA u8 layers suitable for GPU texture upload.
+/// Takes spectral band data and packs it into RGBA layers suitable for GPU
+/// texture upload, in the texel format and per-band value ranges of `encoding`.
 ///
 /// # Arguments
 /// * `bands` - Slice of band data, each band is a Vec<f32> of pixel values
 /// * `width` - Image width in pixels
 /// * `height` - Image height in pixels
 /// * `num_layers` - Number of texture layers to create
+/// * `encoding` - Texel format and value range of each band
 ///
 /// # Returns
 /// Vector of `PackedLayer` ready for GPU upload.
@@ -36,43 +40,25 @@ pub fn pack_bands_to_layers(
     width: u32,
     height: u32,
     num_layers: u32,
+    encoding: &BandEncoding,
 ) -> Vec<PackedLayer> {
     let pixel_count = (width * height) as usize;
-    let num_bands = bands.len();
-
-    (0..num_layers)
-        .map(|layer_idx| {
-            let base_band = (layer_idx as usize) * BANDS_PER_LAYER;
-            let mut rgba_data = vec![0u8; pixel_count * 4];
 
-            // Pack up to BANDS_PER_LAYER bands into RGBA channels
-            for channel_idx in 0..BANDS_PER_LAYER {
-                let band_idx = base_band + channel_idx;
-                if band_idx >= num_bands {
-                    break;
-                }
+    for (band_idx, band) in bands.iter().enumerate() {
+        if band.len() != pixel_count {
+            log::warn!(
+                "Band {} has wrong size: {} vs expected {}",
+                band_idx,
+                band.len(),
+                pixel_count
+            );
+        }
+    }
 
-                let band = &bands[band_idx];
-                if band.len() != pixel_count {
-                    log::warn!(
-                        "Band {} has wrong size: {} vs expected {}",
-                        band_idx,
-                        band.len(),
-                        pixel_count
-                    );
-                    continue;
-                }
-
-                for (pixel_idx, &value) in band.iter().enumerate() {
-                    let byte_value = (value.clamp(0.0, 1.0) * 255.0) as u8;
-                    rgba_data[pixel_idx * 4 + channel_idx] = byte_value;
-                }
-            }
-
-            PackedLayer {
-                rgba_data,
-                layer_index: layer_idx,
-            }
+    (0..num_layers)
+        .map(|layer_idx| PackedLayer {
+            rgba_data: encoding.pack_layer(bands, layer_idx, pixel_count),
+            layer_index: layer_idx,
         })
         .collect()
 }
@@ -82,7 +68,7 @@ pub fn pack_bands_to_layers(
 /// Contains pixel data for one texture layer, with bands packed into RGBA channels.
 #[derive(Debug)]
 pub struct PackedLayer {
-    /// RGBA pixel data (width * height * 4 bytes)
+    /// RGBA texel data (width * height texels, in the image's `BandEncoding` format)
     pub rgba_data: Vec<u8>,
     /// Layer index in the texture array
     pub layer_index: u32,
@@ -106,6 +92,16 @@ pub struct DecodedImage {
     pub num_layers: u32,
     /// Pre-packed RGBA layers ready for GPU upload
     pub layers: Vec<PackedLayer>,
+    /// Texel format and original value range of each band in `layers`
+    pub encoding: BandEncoding,
+    /// Non-fatal decode issues to show to the user
+    pub warnings: Vec<String>,
+    /// Per-band metadata reported by the loader
+    pub band_info: Vec<BandInfo>,
+    /// Value histogram of each band (empty if not computed)
+    pub histograms: Vec<BandHistogram>,
+    /// Radiometric calibration applied while decoding
+    pub calibration: Option<CalibrationSource>,
 }
 
 /// Error result from a decode attempt.
@@ -119,8 +115,8 @@ pub struct DecodeError {
 
 /// Result from background decoding - either decoded data or an error.
 pub enum DecodeResult {
-    /// Successfully decoded image
-    Decoded(DecodedImage),
+    /// Successfully decoded image (boxed, it is much larger than an error)
+    Decoded(Box<DecodedImage>),
     /// Decode failed with error
     Error(DecodeError),
 }
@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+use hvat_gpu::{BandEncoding, BandTextureFormat};
 use js_sys::{Object, Reflect, Uint8Array};
 use wasm_bindgen::prelude::*;
 use web_sys::{MessageEvent, Worker, WorkerOptions, WorkerType};
@@ -150,14 +151,20 @@ impl ImageDecoderWorker {
 
             results_clone
                 .borrow_mut()
-                .push(DecodeResult::Decoded(DecodedImage {
+                .push(DecodeResult::Decoded(Box::new(DecodedImage {
                     path,
                     width,
                     height,
                     num_bands,
                     num_layers,
                     layers,
-                }));
+                    // The worker packs 8-bit RGB images as-is
+                    encoding: BandEncoding::unit(num_bands, BandTextureFormat::Rgba8Unorm),
+                    warnings: Vec::new(),
+                    band_info: Vec::new(),
+                    histograms: Vec::new(),
+                    calibration: None,
+                })));
         }) as Box<dyn Fn(MessageEvent)>);
 
         worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
@@ -1,16 +1,22 @@
 //! Project state management for loaded folders and images.
 
-use std::collections::BTreeMap;
-use std::path::PathBuf;
-use std::sync::LazyLock;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
 
 use hvat_ui::FileTreeNode;
 
-use crate::data::LoaderRegistry;
+use crate::data::{
+    Calibration, DataProcessor, FileBytes, HyperspectralData, LoadSubset, LoaderOptions,
+    LoaderRegistry, MemoryResolver, PreprocessingChain, ReferenceFrame,
+};
 
 /// Lazily initialized loader registry for format detection.
 static LOADER_REGISTRY: LazyLock<LoaderRegistry> = LazyLock::new(LoaderRegistry::new);
 
+/// Calibration reference frames by reference path and load subset.
+type ReferenceCache = Arc<Mutex<HashMap<(PathBuf, LoadSubset), Arc<ReferenceFrame>>>>;
+
 /// Get the list of supported file extensions from the loader registry.
 pub fn supported_extensions() -> Vec<&'static str> {
     LOADER_REGISTRY.supported_extensions()
@@ -22,6 +28,14 @@ pub fn is_supported_filename(name: &str) -> bool {
     LOADER_REGISTRY.is_supported_file(name)
 }
 
+/// Check if a filename is a sidecar of a multi-file format (e.g. an ENVI `.raw` payload).
+///
+/// Sidecar files are kept alongside in-memory images so that their primary
+/// file can be decoded, but they are not listed as images themselves.
+pub fn is_sidecar_filename(name: &str) -> bool {
+    LOADER_REGISTRY.is_sidecar_file(name)
+}
+
 /// Legacy alias for backwards compatibility.
 #[cfg(target_arch = "wasm32")]
 pub fn is_image_filename(name: &str) -> bool {
@@ -37,6 +51,18 @@ fn is_supported_file(path: &PathBuf) -> bool {
         .unwrap_or(false)
 }
 
+/// Read a file from disk. Raw payloads that loaders decode in place are only
+/// opened, to be read on demand; everything else is read into memory.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: &Path) -> std::io::Result<FileBytes> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if LOADER_REGISTRY.is_in_place_file(name) {
+        FileBytes::open(path)
+    } else {
+        std::fs::read(path).map(FileBytes::from)
+    }
+}
+
 /// Image data loaded from a file (used for WASM where we can't access filesystem).
 #[derive(Clone, Debug)]
 pub struct LoadedImage {
@@ -57,9 +83,40 @@ pub struct ProjectState {
     pub current_index: usize,
     /// In-memory image data for WASM (where we can't access filesystem)
     pub loaded_images: Vec<LoadedImage>,
+    /// Sidecar filenames grouped with each image (e.g. ENVI payloads), by image path
+    pub sidecars: BTreeMap<PathBuf, Vec<String>>,
+    /// Calibration references loaded so far (shared by clones of the project)
+    references: ReferenceCache,
 }
 
 impl ProjectState {
+    /// Create a project from discovered images, grouping their sidecar files.
+    fn new(folder: PathBuf, images: Vec<PathBuf>, loaded_images: Vec<LoadedImage>) -> Self {
+        let mut project = Self {
+            folder,
+            images,
+            current_index: 0,
+            loaded_images,
+            sidecars: BTreeMap::new(),
+            references: ReferenceCache::default(),
+        };
+
+        let sidecars: BTreeMap<PathBuf, Vec<String>> = project
+            .images
+            .iter()
+            .filter_map(|path| {
+                let names = project.find_sidecars(path);
+                (!names.is_empty()).then(|| (path.clone(), names))
+            })
+            .collect();
+
+        if !sidecars.is_empty() {
+            log::info!("Grouped sidecar files for {} images", sidecars.len());
+        }
+        project.sidecars = sidecars;
+        project
+    }
+
     /// Discover image files in a folder, non-recursively (native only).
     #[cfg(not(target_arch = "wasm32"))]
     pub fn from_folder(folder: PathBuf) -> Result<Self, String> {
@@ -77,12 +134,7 @@ impl ProjectState {
         // Sort by filename for consistent ordering
         images.sort();
 
-        Ok(Self {
-            folder,
-            images,
-            current_index: 0,
-            loaded_images: Vec::new(),
-        })
+        Ok(Self::new(folder, images, Vec::new()))
     }
 
     /// Discover image files in a folder recursively (native only).
@@ -105,12 +157,7 @@ impl ProjectState {
             images.len()
         );
 
-        Ok(Self {
-            folder,
-            images,
-            current_index: 0,
-            loaded_images: Vec::new(),
-        })
+        Ok(Self::new(folder, images, Vec::new()))
     }
 
     /// Recursively scan a folder for image files (native only).
@@ -181,12 +228,7 @@ impl ProjectState {
             folder
         );
 
-        Ok(Self {
-            folder,
-            images,
-            current_index: 0,
-            loaded_images: Vec::new(),
-        })
+        Ok(Self::new(folder, images, Vec::new()))
     }
 
     /// Create project from loaded image data.
@@ -194,6 +236,9 @@ impl ProjectState {
     /// Used for:
     /// - WASM: Images loaded from browser file picker or drag-drop
     /// - Native: Images extracted from ZIP archives
+    ///
+    /// Files without a supported extension (e.g. ENVI payloads) are kept in
+    /// memory for sibling lookup but are not listed as images.
     pub fn from_loaded_images(loaded_images: Vec<LoadedImage>) -> Result<Self, String> {
         if loaded_images.is_empty() {
             return Err("No images loaded".to_string());
@@ -207,20 +252,20 @@ impl ProjectState {
         // Create virtual paths from image names
         let images: Vec<PathBuf> = loaded_images
             .iter()
+            .filter(|img| is_supported_filename(&img.name))
             .map(|img| PathBuf::from(&img.name))
             .collect();
 
+        if images.is_empty() {
+            return Err("No supported images in loaded files".to_string());
+        }
+
         log::info!("from_loaded_images: created {} image paths", images.len());
         for (i, img) in images.iter().enumerate() {
             log::debug!("  Image {}: {:?}", i, img);
         }
 
-        Ok(Self {
-            folder: PathBuf::from(""),
-            images,
-            current_index: 0,
-            loaded_images,
-        })
+        Ok(Self::new(PathBuf::from(""), images, loaded_images))
     }
 
     /// Get the current image path.
@@ -278,6 +323,7 @@ impl ProjectState {
     ///
     /// Creates a hierarchical tree structure from the flat list of image paths,
     /// organized by folder. Files at the root level are included as root nodes.
+    /// Sidecar files are not listed; they are shown as part of their image's name.
     ///
     /// Returns a list of root-level nodes (can be folders or files).
     pub fn build_file_tree(&self) -> Vec<FileTreeNode> {
@@ -332,7 +378,7 @@ impl ProjectState {
                     let file_path = filename.clone();
                     root_nodes.push(FileTreeNode::File {
                         path: file_path,
-                        name: filename.clone(),
+                        name: self.display_name(*idx, filename),
                         index: Some(*idx),
                     });
                 }
@@ -370,7 +416,7 @@ impl ProjectState {
                         let file_path = format!("{}/{}", folder_path, filename);
                         children.push(FileTreeNode::File {
                             path: file_path,
-                            name: filename.clone(),
+                            name: self.display_name(*idx, filename),
                             index: Some(*idx),
                         });
                     }
@@ -437,11 +483,12 @@ impl ProjectState {
     ///
     /// This provides a unified interface for image loading across platforms:
     /// - WASM: Returns data from in-memory `loaded_images` storage
-    /// - Native: Falls back to filesystem read if not in `loaded_images` (e.g., ZIP imports)
+    /// - Native: Falls back to reading the file if not in `loaded_images` (e.g., ZIP imports)
     ///
     /// The in-memory storage is checked first on both platforms, allowing ZIP-extracted
-    /// images to work uniformly.
-    pub fn get_image_data(&self, path: &PathBuf) -> Result<Vec<u8>, String> {
+    /// images to work uniformly. Raw payloads on disk are only opened, so they are read
+    /// where loaders decode them.
+    pub fn get_image_data(&self, path: &PathBuf) -> Result<FileBytes, String> {
         // First, check in-memory storage (works for both WASM and native ZIP imports)
         if let Some(img) = self.loaded_images.iter().find(|img| {
             // Match by full path or just filename
@@ -453,14 +500,14 @@ impl ProjectState {
                     .unwrap_or(false)
         }) {
             log::trace!("get_image_data: found {:?} in loaded_images", path);
-            return Ok(img.data.clone());
+            return Ok(img.data.clone().into());
         }
 
-        // Native: fall back to filesystem read
+        // Native: fall back to the filesystem
         #[cfg(not(target_arch = "wasm32"))]
         {
             log::trace!("get_image_data: reading {:?} from filesystem", path);
-            std::fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))
+            read_file(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))
         }
 
         // WASM: no filesystem fallback, image must be in loaded_images
@@ -469,4 +516,368 @@ impl ProjectState {
             Err(format!("Image not found in loaded data: {:?}", path))
         }
     }
+
+    /// Find sidecar files that exist next to an image (in memory or on disk).
+    fn find_sidecars(&self, path: &Path) -> Vec<String> {
+        let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+            return Vec::new();
+        };
+
+        LOADER_REGISTRY
+            .sidecar_candidates(filename)
+            .into_iter()
+            .filter(|name| self.sibling_exists(&path.with_file_name(name)))
+            .collect()
+    }
+
+    /// Check if a sibling file exists in memory or (native) on disk.
+    fn sibling_exists(&self, path: &Path) -> bool {
+        if self
+            .loaded_images
+            .iter()
+            .any(|f| Path::new(&f.name) == path)
+        {
+            return true;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            path.is_file()
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            false
+        }
+    }
+
+    /// Read a sibling file from memory or (native) from disk.
+    fn read_sibling(&self, path: &Path) -> Option<FileBytes> {
+        if let Some(file) = self
+            .loaded_images
+            .iter()
+            .find(|f| Path::new(&f.name) == path)
+        {
+            return Some(file.data.clone().into());
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            read_file(path)
+                .map_err(|e| log::warn!("Failed to read sidecar {:?}: {}", path, e))
+                .ok()
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            None
+        }
+    }
+
+    /// Get the sidecar files grouped with an image (bare filename + bytes).
+    ///
+    /// Used to resolve multi-file formats, including for background decoding
+    /// where the project itself is not available. Raw payloads on disk are
+    /// only opened (native).
+    pub fn sidecar_files(&self, path: &Path) -> Vec<(String, FileBytes)> {
+        let Some(names) = self.sidecars.get(path) else {
+            return Vec::new();
+        };
+        self.read_sidecars(path, names)
+    }
+
+    /// Read the named sidecar files next to `path`, skipping missing ones.
+    fn read_sidecars(&self, path: &Path, names: &[String]) -> Vec<(String, FileBytes)> {
+        names
+            .iter()
+            .filter_map(|name| {
+                let data = self.read_sibling(&path.with_file_name(name))?;
+                Some((name.clone(), data))
+            })
+            .collect()
+    }
+
+    /// Load and decode an image into hyperspectral data.
+    ///
+    /// Uses the filename as a format hint. Multi-file formats (e.g. ENVI)
+    /// read their sidecars from `sidecar_files`. Per-file loader rules
+    /// (e.g. the `.npy` layout) are resolved against `path`, and the
+    /// `processors` for the image run on the decoded data.
+    pub fn load_hyperspectral(
+        &self,
+        path: &PathBuf,
+        options: &LoaderOptions,
+        preprocessing: &PreprocessingChain,
+    ) -> Result<HyperspectralData, String> {
+        let processors = self.processors(path, options, preprocessing)?;
+        let data = self.get_image_data(path)?;
+        let filename = path.file_name().and_then(|n| n.to_str());
+        let resolver = MemoryResolver::new(self.sidecar_files(path));
+        HyperspectralData::from_bytes_with_resolver(
+            &data,
+            filename,
+            &resolver,
+            &options.for_path(path),
+            &processors,
+        )
+    }
+
+    /// Processing steps for the decoded data of an image: the radiometric
+    /// calibration of the first matching rule, if any, followed by the
+    /// project's `preprocessing` chain.
+    ///
+    /// Reference cubes are loaded with the same options (so bands and
+    /// binning match) and cached. The references themselves are never
+    /// calibrated.
+    pub fn processors(
+        &self,
+        path: &Path,
+        options: &LoaderOptions,
+        preprocessing: &PreprocessingChain,
+    ) -> Result<Vec<Arc<dyn DataProcessor>>, String> {
+        let mut processors: Vec<Arc<dyn DataProcessor>> = Vec::new();
+        if let Some(calibration) = self.calibration(path, options)? {
+            processors.push(Arc::new(calibration));
+        }
+        if !preprocessing.is_empty() {
+            processors.push(Arc::new(preprocessing.clone()));
+        }
+        Ok(processors)
+    }
+
+    /// Radiometric calibration of the first rule matching `path`.
+    fn calibration(
+        &self,
+        path: &Path,
+        options: &LoaderOptions,
+    ) -> Result<Option<Calibration>, String> {
+        let Some(source) = options.calibration_for(path) else {
+            return Ok(None);
+        };
+        if [&source.dark, &source.white]
+            .into_iter()
+            .any(|reference| reference.as_deref() == Some(path))
+        {
+            return Ok(None);
+        }
+
+        let dark = source
+            .dark
+            .as_deref()
+            .map(|reference| self.reference_frame(reference, options))
+            .transpose()?;
+        let white = source
+            .white
+            .as_deref()
+            .map(|reference| self.reference_frame(reference, options))
+            .transpose()?;
+        Ok(Some(Calibration::new(source, dark, white)))
+    }
+
+    /// Load a calibration reference cube and average its rows (cached).
+    fn reference_frame(
+        &self,
+        path: &Path,
+        options: &LoaderOptions,
+    ) -> Result<Arc<ReferenceFrame>, String> {
+        let key = (path.to_path_buf(), options.subset);
+        let cached = self
+            .references
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .cloned();
+        if let Some(frame) = cached {
+            return Ok(frame);
+        }
+
+        let load = || {
+            let data = self.get_image_data(&key.0)?;
+            let filename = path.file_name().and_then(|n| n.to_str());
+            let resolver = MemoryResolver::new(self.read_sidecars(path, &self.find_sidecars(path)));
+            HyperspectralData::from_bytes_with_resolver(
+                &data,
+                filename,
+                &resolver,
+                &options.for_path(path),
+                &[],
+            )
+        };
+        let cube =
+            load().map_err(|e| format!("Calibration reference {}: {}", path.display(), e))?;
+        let frame = Arc::new(ReferenceFrame::from_cube(&cube));
+        log::info!(
+            "Loaded calibration reference {:?} ({} bands, {} columns)",
+            path,
+            frame.bands.len(),
+            frame.width
+        );
+        self.references
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, frame.clone());
+        Ok(frame)
+    }
+
+    /// Display name for an image in the file tree, listing grouped sidecars.
+    ///
+    /// For example `cube.hdr [+raw]` for an ENVI header with a `.raw` payload.
+    fn display_name(&self, idx: usize, filename: &str) -> String {
+        let Some(names) = self.images.get(idx).and_then(|p| self.sidecars.get(p)) else {
+            return filename.to_string();
+        };
+
+        let exts: Vec<&str> = names
+            .iter()
+            .map(|name| name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("data"))
+            .collect();
+        format!("{} [+{}]", filename, exts.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{BandExclusion, PreprocessStep};
+
+    fn loaded(name: &str, data: &[u8]) -> LoadedImage {
+        LoadedImage {
+            name: name.to_string(),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_envi_payload_not_listed_as_image() {
+        let project = ProjectState::from_loaded_images(vec![
+            loaded("cube.hdr", b"ENVI"),
+            loaded("cube.raw", &[0u8; 4]),
+        ])
+        .unwrap();
+
+        assert_eq!(project.images, vec![PathBuf::from("cube.hdr")]);
+        assert_eq!(project.loaded_images.len(), 2);
+    }
+
+    #[test]
+    fn test_load_envi_from_loaded_images() {
+        let none = PreprocessingChain::default();
+        let header = b"ENVI\nsamples = 2\nlines = 1\nbands = 2\ndata type = 1\ninterleave = bip\n";
+        let project = ProjectState::from_loaded_images(vec![
+            loaded("scans/cube.hdr", header),
+            loaded("scans/cube.img", &[0, 255, 255, 0]),
+        ])
+        .unwrap();
+
+        let path = PathBuf::from("scans/cube.hdr");
+        let hyper = project
+            .load_hyperspectral(&path, &LoaderOptions::default(), &none)
+            .unwrap();
+        assert_eq!(hyper.num_bands(), 2);
+        assert_eq!(hyper.bands[0], vec![0.0, 1.0]);
+        assert_eq!(hyper.bands[1], vec![1.0, 0.0]);
+
+        // The project's preprocessing chain runs on the decoded data
+        let chain = PreprocessingChain {
+            steps: vec![PreprocessStep::ExcludeBands(BandExclusion {
+                indices: vec![(0, 0)],
+                ..Default::default()
+            })],
+        };
+        let hyper = project
+            .load_hyperspectral(&path, &LoaderOptions::default(), &chain)
+            .unwrap();
+        assert_eq!(hyper.bands, vec![vec![1.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_load_calibrated() {
+        let none = PreprocessingChain::default();
+        // Two columns, one line, one band of 8-bit samples
+        let header = b"ENVI\nsamples = 2\nlines = 1\nbands = 1\ndata type = 1\ninterleave = bsq\n";
+        let project = ProjectState::from_loaded_images(vec![
+            loaded("scans/leaf.hdr", header),
+            loaded("scans/leaf.raw", &[60, 30]),
+            loaded("scans/DARKREF_leaf.hdr", header),
+            loaded("scans/DARKREF_leaf.raw", &[10, 20]),
+            loaded("scans/white.hdr", header),
+            loaded("scans/white.raw", &[110, 60]),
+        ])
+        .unwrap();
+
+        let mut options = LoaderOptions::default();
+        options.set_calibration_rule(crate::data::CalibrationRule {
+            pattern: "scans/*".to_string(),
+            dark: Some("DARKREF_{stem}.hdr".to_string()),
+            white: Some("white.hdr".to_string()),
+            gain: Vec::new(),
+            offset: Vec::new(),
+        });
+
+        let path = PathBuf::from("scans/leaf.hdr");
+        let hyper = project.load_hyperspectral(&path, &options, &none).unwrap();
+        // (raw - dark) / (white - dark) per column
+        let expected = [0.5, 0.25];
+        for (value, expected) in hyper.bands[0].iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+        }
+        let source = hyper.calibration.unwrap();
+        assert_eq!(source.dark, Some(PathBuf::from("scans/DARKREF_leaf.hdr")));
+
+        // References are cached and not calibrated themselves
+        assert_eq!(project.references.lock().unwrap().len(), 2);
+        let white = project
+            .load_hyperspectral(&PathBuf::from("scans/white.hdr"), &options, &none)
+            .unwrap();
+        assert!(white.calibration.is_none());
+
+        // A missing reference fails the load
+        options.calibration_rules[0].white = Some("missing.hdr".to_string());
+        assert!(project.load_hyperspectral(&path, &options, &none).is_err());
+    }
+
+    #[test]
+    fn test_envi_missing_payload() {
+        let project =
+            ProjectState::from_loaded_images(vec![loaded("cube.hdr", b"ENVI\nsamples = 1")])
+                .unwrap();
+        assert!(project.sidecars.is_empty());
+        assert!(
+            project
+                .load_hyperspectral(
+                    &PathBuf::from("cube.hdr"),
+                    &LoaderOptions::default(),
+                    &PreprocessingChain::default()
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_file_tree_groups_sidecars() {
+        let project = ProjectState::from_loaded_images(vec![
+            loaded("scans/cube.hdr", b"ENVI"),
+            loaded("scans/cube.raw", &[0u8; 4]),
+            loaded("scans/other.raw", &[0u8; 4]),
+            loaded("photo.png", &[0u8; 4]),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            project.sidecars.get(&PathBuf::from("scans/cube.hdr")),
+            Some(&vec!["cube.raw".to_string()])
+        );
+
+        let tree = project.build_file_tree();
+        assert_eq!(tree.len(), 2);
+        let FileTreeNode::Folder { children, .. } = &tree[0] else {
+            panic!("expected folder first");
+        };
+        assert_eq!(children.len(), 1);
+        let FileTreeNode::File { path, name, .. } = &children[0] else {
+            panic!("expected file");
+        };
+        assert_eq!(path, "scans/cube.hdr");
+        assert_eq!(name, "cube.hdr [+raw]");
+    }
 }
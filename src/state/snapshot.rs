@@ -2,11 +2,13 @@
 
 use std::path::PathBuf;
 
+use crate::data::StretchMode;
 use crate::model::Annotation;
 
 /// Snapshot of application state for undo/redo.
 ///
-/// Contains the current band selection, image adjustments, and optionally
+/// Contains the current band selection, image adjustments (including the
+/// contrast stretch), and optionally
 /// annotation state that can be restored when undoing or redoing actions.
 #[derive(Debug, Clone)]
 pub struct AppSnapshot {
@@ -24,6 +26,12 @@ pub struct AppSnapshot {
     pub gamma: f32,
     /// Hue shift adjustment value
     pub hue: f32,
+    /// Contrast stretch mode
+    pub stretch_mode: StretchMode,
+    /// Dragged (low, high) bounds of each output channel for `StretchMode::Manual`
+    pub manual_stretch: [Option<(f32, f32)>; 3],
+    /// Standard deviation multiplier for `StretchMode::StdDev`
+    pub stretch_k: f32,
     /// Optional annotation state (only present for annotation changes)
     pub annotations: Option<AnnotationState>,
 }
@@ -0,0 +1,138 @@
+//! Pixel spectra for the spectrum plot and annotation statistics.
+//!
+//! Spectra are plotted against wavelength when every band has one, otherwise
+//! against the band number. Bands flagged as bad are left out of the line.
+
+use std::path::PathBuf;
+
+use crate::constants::PINNED_SPECTRUM_COLORS;
+use crate::data::{BandInfo, RegionStats};
+use crate::model::AnnotationShape;
+
+/// A spectrum pinned at an image location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinnedSpectrum {
+    /// Image the pixel belongs to
+    pub image_path: PathBuf,
+    /// Pixel column
+    pub x: u32,
+    /// Pixel row
+    pub y: u32,
+    /// Band values at the pixel
+    pub values: Vec<f32>,
+    /// Line color (RGB)
+    pub color: [u8; 3],
+}
+
+impl PinnedSpectrum {
+    /// Short label for the legend.
+    pub fn label(&self) -> String {
+        let file = self
+            .image_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        format!("({}, {}) {}", self.x, self.y, file)
+    }
+}
+
+/// Band statistics of an annotation, with the image and shape they were
+/// computed for (recomputed when either changes).
+#[derive(Debug, Clone)]
+pub struct AnnotationStats {
+    /// Image the annotation belongs to
+    pub image_path: PathBuf,
+    /// Annotation shape at the time of computation
+    pub shape: AnnotationShape,
+    /// Statistics over the pixels inside the shape
+    pub stats: RegionStats,
+}
+
+/// First palette color not used by `pinned`, cycling when all are taken.
+pub fn next_pin_color(pinned: &[PinnedSpectrum]) -> [u8; 3] {
+    PINNED_SPECTRUM_COLORS
+        .iter()
+        .copied()
+        .find(|color| pinned.iter().all(|p| p.color != *color))
+        .unwrap_or(PINNED_SPECTRUM_COLORS[pinned.len() % PINNED_SPECTRUM_COLORS.len()])
+}
+
+/// Whether spectra of these bands are plotted against wavelength.
+pub fn has_wavelength_axis(band_info: &[BandInfo]) -> bool {
+    !band_info.is_empty() && band_info.iter().all(|b| b.wavelength.is_some())
+}
+
+/// Chart points (x, value) of a spectrum.
+///
+/// X is the wavelength when every band has one, otherwise the 1-based band
+/// number. Bad bands become NaN so the plotted line skips them.
+pub fn spectrum_points(values: &[f32], band_info: &[BandInfo]) -> Vec<(f32, f32)> {
+    let use_wavelength = band_info.len() == values.len() && has_wavelength_axis(band_info);
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let info = band_info.get(i);
+            let x = match info.and_then(|b| b.wavelength) {
+                Some(wavelength) if use_wavelength => wavelength,
+                _ => (i + 1) as f32,
+            };
+            let bad = info.is_some_and(|b| b.bad);
+            (x, if bad { f32::NAN } else { value })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band(wavelength: Option<f32>) -> BandInfo {
+        BandInfo {
+            wavelength,
+            ..BandInfo::named("b")
+        }
+    }
+
+    #[test]
+    fn test_spectrum_points_axis() {
+        let values = [0.1, 0.2, 0.3];
+        let with_wl = [band(Some(450.0)), band(Some(550.0)), band(Some(650.0))];
+        assert_eq!(
+            spectrum_points(&values, &with_wl),
+            vec![(450.0, 0.1), (550.0, 0.2), (650.0, 0.3)]
+        );
+
+        // One band without wavelength falls back to band numbers
+        let partial = [band(Some(450.0)), band(None), band(Some(650.0))];
+        assert_eq!(
+            spectrum_points(&values, &partial),
+            vec![(1.0, 0.1), (2.0, 0.2), (3.0, 0.3)]
+        );
+        assert_eq!(spectrum_points(&values, &[]).len(), 3);
+    }
+
+    #[test]
+    fn test_spectrum_points_skip_bad_bands() {
+        let mut info = vec![band(Some(450.0)), band(Some(550.0))];
+        info[1].bad = true;
+        let points = spectrum_points(&[0.1, 0.2], &info);
+        assert_eq!(points[0], (450.0, 0.1));
+        assert!(points[1].1.is_nan());
+    }
+
+    #[test]
+    fn test_next_pin_color() {
+        let pin = |color| PinnedSpectrum {
+            image_path: PathBuf::from("a.png"),
+            x: 0,
+            y: 0,
+            values: Vec::new(),
+            color,
+        };
+        assert_eq!(next_pin_color(&[]), PINNED_SPECTRUM_COLORS[0]);
+        // Colors freed by removed pins are reused
+        let pinned = [pin(PINNED_SPECTRUM_COLORS[1])];
+        assert_eq!(next_pin_color(&pinned), PINNED_SPECTRUM_COLORS[0]);
+    }
+}
@@ -12,7 +12,7 @@ use std::path::Path;
 use zip::ZipArchive;
 
 use super::LoadedImage;
-use super::project::is_supported_filename;
+use super::project::{is_sidecar_filename, is_supported_filename};
 
 /// Check if a filename has a ZIP extension.
 #[cfg(target_arch = "wasm32")]
@@ -30,13 +30,15 @@ pub fn is_zip_path(path: &Path) -> bool {
 }
 
 /// Check if a filename has a supported extension (for ZIP entry filtering).
+///
+/// Sidecar files (e.g. ENVI payloads) are extracted too so their primary files can be decoded.
 fn is_supported_entry(name: &str) -> bool {
     let lower = name.to_lowercase();
     // Skip hidden files and macOS metadata
     if lower.contains("__macosx") || lower.contains("/.") || lower.starts_with('.') {
         return false;
     }
-    is_supported_filename(name)
+    is_supported_filename(name) || is_sidecar_filename(name)
 }
 
 /// Extract images from a ZIP archive reader.
@@ -170,6 +172,8 @@ mod tests {
         assert!(is_supported_entry("folder/image.jpg"));
         assert!(is_supported_entry("deep/folder/image.JPEG"));
         assert!(is_supported_entry("data.npy")); // NumPy files are now supported
+        assert!(is_supported_entry("cube.hdr")); // ENVI header
+        assert!(is_supported_entry("cube.raw")); // ENVI payload
         assert!(!is_supported_entry("__MACOSX/._image.png"));
         assert!(!is_supported_entry(".hidden.png"));
         assert!(!is_supported_entry("folder/.hidden.jpg"));
@@ -7,6 +7,7 @@ use hvat_ui::{ConfirmDialog, ContextMenu, Element, MenuItem};
 
 use crate::app::{ConfirmTarget, HvatApp};
 use crate::message::Message;
+use crate::model::AnnotationShape;
 
 impl HvatApp {
     /// Build the context menu widget.
@@ -28,6 +29,27 @@ impl HvatApp {
                 let item = MenuItem::new(item_id, &category.name).with_color(category.color);
                 items.push(item);
             }
+
+            // Offer converting between raster masks and outlines
+            let path = self.current_image_path();
+            let shape = self
+                .image_data_store
+                .get(&path)
+                .annotations
+                .into_iter()
+                .find(|a| Some(a.id) == self.context_menu_annotation_id)
+                .map(|a| a.shape);
+            match shape {
+                Some(AnnotationShape::Mask { .. }) => {
+                    items.push(MenuItem::separator());
+                    items.push(MenuItem::new("convert_polygon", "Convert to Polygon"));
+                }
+                Some(AnnotationShape::Point { .. } | AnnotationShape::Keypoints { .. }) | None => {}
+                Some(_) => {
+                    items.push(MenuItem::separator());
+                    items.push(MenuItem::new("convert_mask", "Convert to Mask"));
+                }
+            }
         } else {
             // No annotation - show category selection for new annotations
             items.push(MenuItem::new("header", "Select Category:").disabled());
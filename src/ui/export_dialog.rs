@@ -1,6 +1,6 @@
 //! Export dialog UI component.
 //!
-//! A modal dialog for selecting export format.
+//! A modal dialog for selecting export format, or the spectral data export.
 
 use hvat_ui::constants::BUTTON_PADDING_COMPACT;
 use hvat_ui::prelude::*;
@@ -53,6 +53,28 @@ impl HvatApp {
             ctx.text("");
         }
 
+        // Band values inside the annotations, for training classifiers
+        ctx.text("Spectral data:");
+        ctx.text("");
+        ctx.row(|r| {
+            r.button("Annotation Statistics")
+                .padding(BUTTON_PADDING_COMPACT)
+                .width(Length::Fixed(180.0))
+                .on_click(Message::ExportAnnotationStats);
+            r.text("(CSV, per-band mean/std/min/max per annotation)")
+                .size(FONT_SIZE_SMALL);
+        });
+        ctx.text("");
+        ctx.row(|r| {
+            r.button("Pixel Spectra")
+                .padding(BUTTON_PADDING_COMPACT)
+                .width(Length::Fixed(180.0))
+                .on_click(Message::ExportPixelSpectra);
+            r.text("(.npy pixels x bands, with a CSV row index)")
+                .size(FONT_SIZE_SMALL);
+        });
+        ctx.text("");
+
         ctx.text("");
 
         // Cancel button
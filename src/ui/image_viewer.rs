@@ -1,9 +1,10 @@
 //! Image viewer UI component.
 
 use hvat_ui::prelude::*;
-use hvat_ui::{AnnotationOverlay, Column, Context, Element, OverlayShape};
+use hvat_ui::{AnnotationOverlay, Color, ColorbarLegend, Column, Context, Element, OverlayShape};
 
 use crate::app::HvatApp;
+use crate::constants::{COLORBAR_STEPS, SIMILARITY_REGION_RGBA};
 use crate::message::Message;
 use crate::model::{AnnotationShape, AnnotationTool, DrawingState};
 
@@ -21,11 +22,44 @@ impl From<&AnnotationShape> for OverlayShape {
                 width: *width,
                 height: *height,
             },
+            AnnotationShape::RotatedBox {
+                cx,
+                cy,
+                w,
+                h,
+                angle,
+            } => OverlayShape::RotatedBox {
+                cx: *cx,
+                cy: *cy,
+                width: *w,
+                height: *h,
+                angle: *angle,
+            },
             AnnotationShape::Point { x, y } => OverlayShape::Point { x: *x, y: *y },
             AnnotationShape::Polygon { vertices } => OverlayShape::Polygon {
                 vertices: vertices.clone(),
                 closed: true,
             },
+            AnnotationShape::MultiPolygon { rings } => OverlayShape::MultiPolygon {
+                rings: rings.clone(),
+            },
+            AnnotationShape::Polyline { vertices, width } => OverlayShape::Polyline {
+                vertices: vertices.clone(),
+                width: *width,
+            },
+            AnnotationShape::Mask { mask, .. } => OverlayShape::Mask { runs: mask.runs() },
+            // The skeleton comes from the category template (see `build_overlays`)
+            AnnotationShape::Keypoints {
+                keypoints,
+                x,
+                y,
+                width,
+                height,
+            } => OverlayShape::Keypoints {
+                points: keypoints.iter().map(|k| (k.x, k.y, k.visibility)).collect(),
+                skeleton: Vec::new(),
+                bbox: Some((*x, *y, *width, *height)),
+            },
         }
     }
 }
@@ -49,7 +83,8 @@ impl HvatApp {
         let mut ctx = Context::new();
 
         if let Some(tex_id) = texture_id {
-            ctx.image_viewer(tex_id, texture_size.0, texture_size.1)
+            let mut viewer = ctx
+                .image_viewer(tex_id, texture_size.0, texture_size.1)
                 .state(&viewer_state)
                 .show_controls(true)
                 .width(Length::Fill(1.0))
@@ -57,8 +92,21 @@ impl HvatApp {
                 .on_change(Message::ViewerChanged)
                 .on_pointer(Message::ImagePointer)
                 .interaction_mode(interaction_mode)
-                .overlays(overlays)
-                .build();
+                .overlays(overlays);
+            // Only track the cursor pixel while the spectrum plot is visible
+            if self.spectrum_collapsed.is_expanded {
+                viewer = viewer
+                    .on_hover(Message::ImageHover)
+                    .hover_pixel(self.hover_pixel);
+            }
+            if let Some(legend) = self.colorbar_legend() {
+                viewer = viewer.colorbar(legend);
+            }
+            // Tiled images render only the tiles around the visible area
+            if let Some((x, y, w, h)) = self.render_target_region() {
+                viewer = viewer.texture_region(x, y, w, h);
+            }
+            viewer.build();
         } else {
             ctx.image_viewer_empty()
                 .state(&viewer_state)
@@ -71,6 +119,20 @@ impl HvatApp {
         Element::new(Column::new(ctx.take()))
     }
 
+    /// Colorbar legend of the active colormap.
+    fn colorbar_legend(&self) -> Option<ColorbarLegend> {
+        let colormap = self.displayed_colormap()?;
+        let (min, max) = self.colormap_value_range().unwrap_or((0.0, 1.0));
+        let colors = (0..COLORBAR_STEPS)
+            .map(|i| {
+                let t = i as f32 / (COLORBAR_STEPS - 1) as f32;
+                let [r, g, b] = colormap.sample(if self.colormap_reversed { 1.0 - t } else { t });
+                Color::rgb(r, g, b)
+            })
+            .collect();
+        Some(ColorbarLegend::new(colors, min, max).label(self.colormap_source()))
+    }
+
     /// Build annotation overlays from current annotations and drawing state.
     /// Annotations with hidden categories are filtered out from rendering.
     fn build_overlays(&self) -> Vec<AnnotationOverlay> {
@@ -82,11 +144,17 @@ impl HvatApp {
             .annotations
             .iter()
             .filter(|ann| !self.hidden_categories.contains(&ann.category_id))
-            .map(|ann| AnnotationOverlay {
-                shape: (&ann.shape).into(),
-                color: self.get_category_color(ann.category_id),
-                line_width: 2.0,
-                selected: ann.selected,
+            .map(|ann| {
+                let mut shape: OverlayShape = (&ann.shape).into();
+                if let OverlayShape::Keypoints { skeleton, .. } = &mut shape {
+                    *skeleton = self.get_category_skeleton(ann.category_id);
+                }
+                AnnotationOverlay {
+                    shape,
+                    color: self.get_category_color(ann.category_id),
+                    line_width: 2.0,
+                    selected: ann.selected,
+                }
             })
             .collect();
 
@@ -101,6 +169,39 @@ impl HvatApp {
             });
         }
 
+        // Mark pinned spectrum locations of this image (pixel centers)
+        for pin in self.pinned_spectra.iter().filter(|p| p.image_path == path) {
+            overlays.push(AnnotationOverlay {
+                shape: OverlayShape::Point {
+                    x: pin.x as f32 + 0.5,
+                    y: pin.y as f32 + 0.5,
+                },
+                color: [
+                    pin.color[0] as f32 / 255.0,
+                    pin.color[1] as f32 / 255.0,
+                    pin.color[2] as f32 / 255.0,
+                    1.0,
+                ],
+                line_width: 3.0,
+                selected: false,
+            });
+        }
+
+        // Preview the similarity map regions below the threshold
+        if self.current_similarity_map().is_some() {
+            for region in &self.similarity_regions {
+                overlays.push(AnnotationOverlay {
+                    shape: OverlayShape::Polygon {
+                        vertices: region.clone(),
+                        closed: true,
+                    },
+                    color: SIMILARITY_REGION_RGBA,
+                    line_width: 2.0,
+                    selected: false,
+                });
+            }
+        }
+
         // Add SAM2 point overlays if SAM2 is active
         #[cfg(feature = "sam2")]
         self.add_sam2_overlays(&mut overlays);
@@ -157,19 +258,18 @@ impl HvatApp {
                 });
             }
 
-            // Render mask contour if available
+            // Render mask contours if available
             if let Some(mask) = &session.mask {
-                if !mask.contour.is_empty() {
+                if !mask.contours.is_empty() {
                     log::debug!(
-                        "SAM2: Rendering mask contour with {} vertices, score={:.2}",
-                        mask.contour.len(),
+                        "SAM2: Rendering mask contours with {} rings, score={:.2}",
+                        mask.contours.len(),
                         mask.score
                     );
-                    // Render the mask contour as a semi-transparent filled polygon
+                    // Render the mask's parts and holes as semi-transparent outlines
                     overlays.push(AnnotationOverlay {
-                        shape: OverlayShape::Polygon {
-                            vertices: mask.contour.clone(),
-                            closed: true,
+                        shape: OverlayShape::MultiPolygon {
+                            rings: mask.contours.clone(),
                         },
                         color: [0.2, 0.6, 1.0, 0.4], // Blue semi-transparent
                         line_width: 2.0,
@@ -201,6 +301,12 @@ impl HvatApp {
                 start_y,
                 current_x,
                 current_y,
+            }
+            | DrawingState::RotatedBox {
+                start_x,
+                start_y,
+                current_x,
+                current_y,
             } => Some(OverlayShape::BoundingBox {
                 x: start_x.min(*current_x),
                 y: start_y.min(*current_y),
@@ -214,9 +320,35 @@ impl HvatApp {
                 })
             }
             DrawingState::Polygon { .. } => None,
+            DrawingState::Polyline { vertices, width } if !vertices.is_empty() => {
+                Some(OverlayShape::Polyline {
+                    vertices: vertices.clone(),
+                    width: *width,
+                })
+            }
+            DrawingState::Polyline { .. } => None,
+            DrawingState::Keypoints { keypoints, .. } if !keypoints.is_empty() => {
+                Some(OverlayShape::Keypoints {
+                    points: keypoints.iter().map(|k| (k.x, k.y, k.visibility)).collect(),
+                    skeleton: self.get_category_skeleton(self.selected_category),
+                    bbox: None,
+                })
+            }
+            DrawingState::Keypoints { .. } => None,
+            // Brush strokes are painted straight into their mask
+            DrawingState::Brush { .. } => None,
         }
     }
 
+    /// Get the keypoint skeleton edges of a category.
+    fn get_category_skeleton(&self, category_id: u32) -> Vec<(usize, usize)> {
+        self.categories
+            .iter()
+            .find(|c| c.id == category_id)
+            .map(|c| c.skeleton.clone())
+            .unwrap_or_default()
+    }
+
     /// Get the color for a category as RGBA floats.
     fn get_category_color(&self, category_id: u32) -> [f32; 4] {
         self.categories
@@ -1,5 +1,7 @@
 //! Left sidebar UI component.
 
+use std::rc::Rc;
+
 use hvat_ui::constants::{BUTTON_PADDING_COMPACT, COLOR_PICKER_SWATCH_OFFSET, ROW_ITEM_HEIGHT};
 use hvat_ui::prelude::*;
 use hvat_ui::theme::current_theme;
@@ -9,10 +11,14 @@ use hvat_ui::{
 };
 
 use crate::app::HvatApp;
-use crate::constants::{FILE_LIST_MAX_HEIGHT, SIDEBAR_WIDTH};
+use crate::constants::{
+    BRUSH_RADIUS_MAX, BRUSH_RADIUS_MIN, BRUSH_RADIUS_STEP, FILE_LIST_MAX_HEIGHT,
+    POLYLINE_WIDTH_MAX, POLYLINE_WIDTH_MIN, POLYLINE_WIDTH_STEP, SIDEBAR_CONTENT_WIDTH,
+    SIDEBAR_WIDTH,
+};
 use crate::keybindings::{key_to_string, optional_key_to_string};
 use crate::message::Message;
-use crate::model::AnnotationTool;
+use crate::model::{AnnotationTool, DrawingState};
 
 /// Fixed width for Edit/OK button to prevent layout flicker
 const ACTION_BUTTON_WIDTH: f32 = 40.0;
@@ -133,6 +139,15 @@ fn tool_tooltip(tool: AnnotationTool, hotkey: &str) -> TooltipContent {
                 hotkey
             ),
         ),
+        AnnotationTool::RotatedBox => TooltipContent::rich(
+            "Rotated Box Tool",
+            format!(
+                "Hotkey: {}\n\nDraw oriented box annotations.\n\
+                Click and drag to create a box, then select it and\n\
+                drag the handle above its top edge to rotate.",
+                hotkey
+            ),
+        ),
         AnnotationTool::Polygon => TooltipContent::rich(
             "Polygon Tool",
             format!(
@@ -143,6 +158,16 @@ fn tool_tooltip(tool: AnnotationTool, hotkey: &str) -> TooltipContent {
                 hotkey
             ),
         ),
+        AnnotationTool::Polyline => TooltipContent::rich(
+            "Polyline Tool",
+            format!(
+                "Hotkey: {}\n\nDraw open paths (cracks, roots, wires).\n\
+                Left click to add points, click on last point or press Enter to finish.\n\
+                If annotation is selected, left click on a segment to add point and \n\
+                right-click on point to remove.",
+                hotkey
+            ),
+        ),
         AnnotationTool::Point => TooltipContent::rich(
             "Point Tool",
             format!(
@@ -151,6 +176,34 @@ fn tool_tooltip(tool: AnnotationTool, hotkey: &str) -> TooltipContent {
                 hotkey
             ),
         ),
+        AnnotationTool::Keypoints => TooltipContent::rich(
+            "Keypoints Tool",
+            format!(
+                "Hotkey: {}\n\nPlace the selected category's keypoints in order.\n\
+                Click to place the next keypoint, Shift+click if occluded,\n\
+                Tab to skip it. Enter finishes early.\n\
+                If annotation is selected, right-click on a keypoint to\n\
+                toggle visible/occluded.",
+                hotkey
+            ),
+        ),
+        AnnotationTool::Brush => TooltipContent::rich(
+            "Brush Tool",
+            format!(
+                "Hotkey: {}\n\nPaint pixel mask annotations.\n\
+                Drag to paint into the selected mask, or a new one\n\
+                if no mask is selected.",
+                hotkey
+            ),
+        ),
+        AnnotationTool::Eraser => TooltipContent::rich(
+            "Eraser Tool",
+            format!(
+                "Hotkey: {}\n\nErase pixels from mask annotations.\n\
+                Drag over the selected mask, or the mask under the cursor.",
+                hotkey
+            ),
+        ),
         #[cfg(feature = "sam2")]
         AnnotationTool::SAM2Segment => TooltipContent::rich(
             "SAM2 Segment Tool",
@@ -160,7 +213,8 @@ fn tool_tooltip(tool: AnnotationTool, hotkey: &str) -> TooltipContent {
                 Shift+click for negative points (exclude).\n\
                 Right-click to remove points.\n\
                 Drag to draw bounding box.\n\
-                Enter to accept, Escape to cancel.",
+                Enter to accept as polygon, Shift+Enter as mask,\n\
+                Escape to cancel.",
                 hotkey
             ),
         ),
@@ -214,6 +268,46 @@ impl HvatApp {
         let tag_name_input_state = self.tag_name_input_state;
         let color_picker_tag = self.color_picker_tag;
         let keybindings = self.keybindings.clone();
+        let polyline_width_slider = self.polyline_width_slider.clone();
+        // Width slider is shown while drawing polylines or with one selected;
+        // only snapshot for its undo point while it is shown
+        let show_polyline_width = selected_tool == AnnotationTool::Polyline
+            || current_image_data
+                .annotations
+                .iter()
+                .any(|a| a.selected && a.shape.is_polyline());
+        let polyline_undo_ctx = show_polyline_width.then(|| {
+            UndoContext::new(
+                Rc::clone(&self.undo_stack),
+                self.snapshot_with_annotations(),
+            )
+        });
+        let brush_radius_slider = self.brush_radius_slider.clone();
+        let show_brush_radius = matches!(
+            selected_tool,
+            AnnotationTool::Brush | AnnotationTool::Eraser
+        );
+        // Keypoint template of the default category, with the next keypoint to place
+        let show_keypoint_template = selected_tool == AnnotationTool::Keypoints;
+        let keypoint_names_input = self.keypoint_names_input.clone();
+        let keypoint_names_input_state = self.keypoint_names_input_state;
+        let keypoint_skeleton_input = self.keypoint_skeleton_input.clone();
+        let keypoint_skeleton_input_state = self.keypoint_skeleton_input_state;
+        let keypoint_guide = {
+            let template = categories
+                .iter()
+                .find(|c| c.id == selected_category)
+                .map(|c| c.keypoints.clone())
+                .unwrap_or_default();
+            let placed = match &current_image_data.drawing_state {
+                DrawingState::Keypoints { keypoints, .. } => keypoints.len(),
+                _ => 0,
+            };
+            match template.get(placed) {
+                Some(name) => format!("Next: {} ({}/{})", name, placed + 1, template.len()),
+                None => "No keypoints for this category".to_string(),
+            }
+        };
 
         let mut sidebar_ctx = Context::new();
 
@@ -307,6 +401,58 @@ impl HvatApp {
                         .on_click(Message::ToolSelected(tool_copy));
                 }
 
+                // Line width for new polylines and the selected polyline
+                if let Some(undo_ctx) = &polyline_undo_ctx {
+                    c.text(format!("Line Width: {:.1} px", polyline_width_slider.value))
+                        .size(FONT_SIZE_SECONDARY);
+                    c.slider(POLYLINE_WIDTH_MIN, POLYLINE_WIDTH_MAX)
+                        .state(&polyline_width_slider)
+                        .step(POLYLINE_WIDTH_STEP)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::PolylineWidthChanged)
+                        .on_undo_point(undo_ctx.callback_with_label("polyline_width"))
+                        .build();
+                }
+
+                // Radius for brush and eraser strokes
+                if show_brush_radius {
+                    c.text(format!("Brush Radius: {:.0} px", brush_radius_slider.value))
+                        .size(FONT_SIZE_SECONDARY);
+                    c.slider(BRUSH_RADIUS_MIN, BRUSH_RADIUS_MAX)
+                        .state(&brush_radius_slider)
+                        .step(BRUSH_RADIUS_STEP)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::BrushRadiusChanged)
+                        .build();
+                }
+
+                // Keypoint template editing for the default category
+                if show_keypoint_template {
+                    c.text(&keypoint_guide).size(FONT_SIZE_SECONDARY);
+                    c.text("Keypoints").size(FONT_SIZE_SMALL);
+                    c.text_input()
+                        .placeholder("e.g. nose, left_eye, right_eye")
+                        .value(&keypoint_names_input)
+                        .state(&keypoint_names_input_state)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::KeypointNamesChanged)
+                        .on_submit(|_| Message::ApplyKeypointTemplate)
+                        .build();
+                    c.text("Skeleton").size(FONT_SIZE_SMALL);
+                    c.text_input()
+                        .placeholder("e.g. 1-2, 1-3")
+                        .value(&keypoint_skeleton_input)
+                        .state(&keypoint_skeleton_input_state)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::KeypointSkeletonChanged)
+                        .on_submit(|_| Message::ApplyKeypointTemplate)
+                        .build();
+                    c.button("Apply Template")
+                        .width(Length::Fill(1.0))
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::ApplyKeypointTemplate);
+                }
+
                 // SAM2 AI-Assisted Segmentation section (feature-gated)
                 #[cfg(feature = "sam2")]
                 {
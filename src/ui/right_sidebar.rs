@@ -2,22 +2,32 @@
 
 use std::rc::Rc;
 
+use hvat_gpu::Colormap;
 use hvat_ui::Color;
+use hvat_ui::constants::BUTTON_PADDING_COMPACT;
 use hvat_ui::prelude::*;
 use hvat_ui::theme::current_theme;
 use hvat_ui::{
-    Alignment, BorderSides, Collapsible, Column, Context, Element, Padding, Panel, ScrollDirection,
-    Scrollable, ScrollbarVisibility,
+    Alignment, BorderSides, ChartSeries, Collapsible, ColorSwatch, Column, Context, Element,
+    Histogram, LineChart, Padding, Panel, ScrollDirection, Scrollable, ScrollbarVisibility,
 };
 
 use crate::app::HvatApp;
 use crate::constants::{
     ANNOTATIONS_MAX_HEIGHT, BRIGHTNESS_MAX, BRIGHTNESS_MIN, BRIGHTNESS_STEP, CONTRAST_MAX,
-    CONTRAST_MIN, CONTRAST_STEP, GAMMA_MAX, GAMMA_MIN, GAMMA_STEP, HUE_MAX, HUE_MIN, HUE_STEP,
-    SIDEBAR_CONTENT_WIDTH, SIDEBAR_WIDTH, THUMBNAIL_SIZE, THUMBNAIL_SPACING, THUMBNAILS_MAX_HEIGHT,
+    CONTRAST_MIN, CONTRAST_STEP, GAMMA_MAX, GAMMA_MIN, GAMMA_STEP, HISTOGRAM_CHANNEL_RGB,
+    HISTOGRAM_HEIGHT, HUE_MAX, HUE_MIN, HUE_STEP, LOAD_WARNING_RGB, SIDEBAR_CONTENT_WIDTH,
+    SIDEBAR_WIDTH, SIMILARITY_THRESHOLD_STEP, SPECTRUM_CHART_HEIGHT, STATS_BANDS_MAX_HEIGHT,
+    STRETCH_K_MAX, STRETCH_K_MIN, STRETCH_K_STEP, THUMBNAIL_SIZE, THUMBNAIL_SPACING,
+    THUMBNAILS_MAX_HEIGHT,
+};
+use crate::data::{
+    BandExclusion, BandMathMode, ComponentMethod, Illuminant, NpyLayout, PreprocessStep,
+    SimilarityMetric, StretchMode,
 };
 use crate::message::Message;
-use crate::model::AnnotationShape;
+use crate::model::{AnnotationShape, ring_holes};
+use crate::state::{has_wavelength_axis, spectrum_points};
 
 impl HvatApp {
     /// Build the right sidebar with band selection and image adjustments.
@@ -37,6 +47,8 @@ impl HvatApp {
         let contrast_slider = self.contrast_slider.clone();
         let gamma_slider = self.gamma_slider.clone();
         let hue_slider = self.hue_slider.clone();
+        let stretch_mode = self.stretch_mode;
+        let stretch_k_slider = self.stretch_k_slider.clone();
 
         let max_band = (self.num_bands - 1) as f32;
 
@@ -161,6 +173,10 @@ impl HvatApp {
                                     let cy = y + height / 2.0;
                                     ("Box", format!("({:.0},{:.0})", cx, cy))
                                 }
+                                AnnotationShape::RotatedBox { cx, cy, angle, .. } => (
+                                    "RBox",
+                                    format!("({:.0},{:.0}) {:.0}deg", cx, cy, angle.to_degrees()),
+                                ),
                                 AnnotationShape::Point { x, y } => {
                                     ("Pt", format!("({:.0},{:.0})", x, y))
                                 }
@@ -174,6 +190,29 @@ impl HvatApp {
                                     let cy = sum_y / n;
                                     ("Poly", format!("{}v ({:.0},{:.0})", vertices.len(), cx, cy))
                                 }
+                                AnnotationShape::MultiPolygon { rings } => {
+                                    let holes = ring_holes(rings).iter().filter(|h| **h).count();
+                                    (
+                                        "MPoly",
+                                        format!("{} parts, {} holes", rings.len() - holes, holes),
+                                    )
+                                }
+                                AnnotationShape::Polyline { vertices, width } => {
+                                    ("Line", format!("{}v {:.1}px", vertices.len(), width))
+                                }
+                                AnnotationShape::Mask { mask, crowd } => (
+                                    "Mask",
+                                    format!(
+                                        "{}px{}",
+                                        mask.area(),
+                                        if *crowd { " crowd" } else { "" }
+                                    ),
+                                ),
+                                AnnotationShape::Keypoints { keypoints, .. } => {
+                                    let labeled =
+                                        keypoints.iter().filter(|k| k.is_labeled()).count();
+                                    ("KP", format!("{}/{} pts", labeled, keypoints.len()))
+                                }
                             };
 
                             // Format: [sel] ShapeIcon ID Category Size
@@ -199,6 +238,40 @@ impl HvatApp {
             });
         sidebar_ctx.add(Element::new(collapsible_annotations));
 
+        sidebar_ctx.add(self.build_annotation_stats_panel());
+
+        // Loader feedback for the current image
+        let load_warnings = current_image_data.load_warnings.clone();
+        let calibration = current_image_data
+            .calibration
+            .as_ref()
+            .map(|source| source.describe());
+        let current_path = self.current_image_path();
+        let is_numpy_image = current_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("npy") || e.eq_ignore_ascii_case("npz"));
+        let project_layout = self
+            .project_layout_pattern()
+            .filter(|_| is_numpy_image)
+            .map(|pattern| self.loader_options.npy_layout_rule(&pattern));
+
+        // Band metadata shown under each channel slider
+        let band_info = self.current_band_info();
+        let describe_band = |band: usize| {
+            band_info
+                .get(band)
+                .map(|info| info.describe())
+                .unwrap_or_default()
+        };
+        let red_info = describe_band(self.band_selection.0);
+        let green_info = describe_band(self.band_selection.1);
+        let blue_info = describe_band(self.band_selection.2);
+        let has_wavelengths = band_info.iter().any(|info| info.wavelength.is_some());
+        let wavelength_input = self.band_wavelength_input.clone();
+        let wavelength_state = self.band_wavelength_state.clone();
+        let theme_for_bands = current_theme();
+
         // Band Selection Collapsible
         let band_s = band_state.clone();
         let collapsible_bands = Collapsible::new("Band Selection")
@@ -206,6 +279,45 @@ impl HvatApp {
             .width(Length::Fill(1.0))
             .on_toggle(Message::BandSelectionToggled)
             .content(|c| {
+                let warning_color = Color::from_rgb_bytes(
+                    LOAD_WARNING_RGB[0],
+                    LOAD_WARNING_RGB[1],
+                    LOAD_WARNING_RGB[2],
+                );
+                if let Some(calibration) = &calibration {
+                    c.text(calibration)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true);
+                }
+                for warning in &load_warnings {
+                    c.text(warning)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(warning_color);
+                }
+
+                // Axis layout of .npy/.npz cubes, stored as a rule for the project folder
+                if let Some(selected) = project_layout {
+                    c.text("Axis layout (project)").size(FONT_SIZE_BODY);
+                    c.row(|r| {
+                        let options =
+                            std::iter::once(None).chain(NpyLayout::all().iter().copied().map(Some));
+                        for layout in options {
+                            let name = layout.map_or("Default", |l| l.name());
+                            let label = if layout == selected {
+                                format!("[{}]", name)
+                            } else {
+                                name.to_string()
+                            };
+                            r.button(label)
+                                .padding(BUTTON_PADDING_COMPACT)
+                                .on_click(Message::ProjectNpyLayoutChanged(layout));
+                        }
+                    });
+                }
+
                 c.text("Red Channel").size(FONT_SIZE_BODY);
                 c.slider(0.0, max_band)
                     .state(&red_slider)
@@ -215,6 +327,9 @@ impl HvatApp {
                     .on_change(Message::RedBandChanged)
                     .on_undo_point(undo_ctx.callback_with_label("red_band"))
                     .build();
+                c.text(&red_info)
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme_for_bands.text_secondary);
 
                 c.text("Green Channel").size(FONT_SIZE_BODY);
                 c.slider(0.0, max_band)
@@ -225,6 +340,9 @@ impl HvatApp {
                     .on_change(Message::GreenBandChanged)
                     .on_undo_point(undo_ctx.callback_with_label("green_band"))
                     .build();
+                c.text(&green_info)
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme_for_bands.text_secondary);
 
                 c.text("Blue Channel").size(FONT_SIZE_BODY);
                 c.slider(0.0, max_band)
@@ -235,8 +353,69 @@ impl HvatApp {
                     .on_change(Message::BlueBandChanged)
                     .on_undo_point(undo_ctx.callback_with_label("blue_band"))
                     .build();
+                c.text(&blue_info)
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme_for_bands.text_secondary);
+
+                if has_wavelengths {
+                    c.text("Pick by wavelength").size(FONT_SIZE_BODY);
+                    c.text_input()
+                        .placeholder("650nm or 650, 550, 450 nm")
+                        .value(&wavelength_input)
+                        .state(&wavelength_state)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::BandWavelengthChanged)
+                        .on_submit(|_| Message::BandWavelengthSubmitted)
+                        .build();
+                    c.text("Press Enter to select the closest bands")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme_for_bands.text_placeholder);
+                }
             });
         sidebar_ctx.add(Element::new(collapsible_bands));
+        sidebar_ctx.add(self.build_preprocessing_panel());
+        sidebar_ctx.add(self.build_band_math_panel());
+        sidebar_ctx.add(self.build_colormap_panel());
+        sidebar_ctx.add(self.build_components_panel());
+        sidebar_ctx.add(self.build_true_color_panel());
+        sidebar_ctx.add(self.build_similarity_panel());
+
+        // Histogram with stretch markers of each display channel (one for a
+        // band-math index or the similarity map); bounds as text without one
+        let channel_names: &[&str] = if self.displayed_similarity().is_some() {
+            &["Similarity"]
+        } else if self.single_channel_display() {
+            &["Index"]
+        } else {
+            &["R", "G", "B"]
+        };
+        let histograms = self.channel_histograms();
+        let clips = self.histogram_clips();
+        let mut channel_histograms = Vec::new();
+        let mut stretch_info = Vec::new();
+        for (channel, name) in channel_names.iter().enumerate() {
+            let (Some(hist), Some((low, high))) = (&histograms[channel], clips[channel]) else {
+                if let Some((low, high)) = clips[channel] {
+                    stretch_info.push(format!("{}: {:.4} - {:.4}", name, low, high));
+                }
+                continue;
+            };
+            let mut state = self.histogram_states[channel].clone();
+            state.set_clips(low, high);
+            let mut histogram = Histogram::new(hist.counts.clone(), hist.min, hist.max)
+                .state(&state)
+                .gamma(gamma_slider.value)
+                .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                .height(Length::Fixed(HISTOGRAM_HEIGHT))
+                .on_change(move |state| Message::HistogramChanged(channel, state))
+                .on_undo_point(undo_ctx.callback_with_label("histogram"));
+            if channel_names.len() > 1 {
+                let [r, g, b] = HISTOGRAM_CHANNEL_RGB[channel];
+                histogram = histogram.bar_color(Color::from_rgb_bytes(r, g, b));
+            }
+            channel_histograms.push((name.to_string(), histogram));
+        }
+        let theme_for_adjustments = current_theme();
 
         // Image Adjustments Collapsible
         let adj_s = adj_state.clone();
@@ -245,6 +424,48 @@ impl HvatApp {
             .width(Length::Fill(1.0))
             .on_toggle(Message::AdjustmentsToggled)
             .content(|c| {
+                c.text("Stretch").size(FONT_SIZE_BODY);
+                c.row(|r| {
+                    for &mode in StretchMode::all() {
+                        let label = if mode == stretch_mode {
+                            format!("[{}]", mode.name())
+                        } else {
+                            mode.name().to_string()
+                        };
+                        r.button(label)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::StretchModeChanged(mode));
+                    }
+                });
+                if stretch_mode == StretchMode::StdDev {
+                    c.text(format!("Std devs (k): {:.1}", stretch_k_slider.value))
+                        .size(FONT_SIZE_BODY);
+                    c.slider(STRETCH_K_MIN, STRETCH_K_MAX)
+                        .state(&stretch_k_slider)
+                        .step(STRETCH_K_STEP)
+                        .show_input(true)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::StretchKChanged)
+                        .on_undo_point(undo_ctx.callback_with_label("stretch_k"))
+                        .build();
+                }
+                if stretch_mode == StretchMode::Manual {
+                    c.text("[Manual] stretch from the histogram markers")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme_for_adjustments.text_secondary);
+                }
+                for (name, histogram) in channel_histograms {
+                    c.text(name)
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme_for_adjustments.text_secondary);
+                    c.add(Element::new(histogram));
+                }
+                for line in &stretch_info {
+                    c.text(line)
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme_for_adjustments.text_secondary);
+                }
+
                 c.text(format!("Brightness: {:.2}", brightness_slider.value))
                     .size(FONT_SIZE_BODY);
                 c.slider(BRIGHTNESS_MIN, BRIGHTNESS_MAX)
@@ -295,6 +516,8 @@ impl HvatApp {
             });
         sidebar_ctx.add(Element::new(collapsible_adj));
 
+        sidebar_ctx.add(self.build_spectrum_panel());
+
         // Thumbnails Collapsible (placeholder - actual thumbnails need texture loading)
         let thumbnails_state = self.thumbnails_collapsed.clone();
         let thumbnails_scroll = self.thumbnails_scroll_state.clone();
@@ -496,4 +719,794 @@ impl HvatApp {
 
         Element::new(panel)
     }
+    /// Build the spectrum plot for the pixel under the cursor and pinned pixels.
+    fn build_spectrum_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let band_info = self.current_band_info();
+        let x_label = if has_wavelength_axis(&band_info) {
+            "nm"
+        } else {
+            "band"
+        };
+
+        let cursor = self
+            .hover_pixel
+            .and_then(|(x, y)| self.pixel_spectrum(x, y).map(|values| (x, y, values)));
+        let geotransform = self
+            .image_data_store
+            .get(&self.current_image_path())
+            .geotransform;
+        let cursor_info = match (&cursor, geotransform) {
+            (Some((x, y, _)), Some(geotransform)) => {
+                // Map coordinates of the pixel center, with enough decimals
+                // to tell neighbouring pixels apart
+                let (map_x, map_y) =
+                    geotransform.pixel_to_map(f64::from(*x) + 0.5, f64::from(*y) + 0.5);
+                let decimals = (-geotransform.0[1].abs().log10()).ceil().clamp(0.0, 9.0) as usize;
+                format!(
+                    "Cursor: ({}, {})  Map: ({:.*}, {:.*})",
+                    x, y, decimals, map_x, decimals, map_y
+                )
+            }
+            (Some((x, y, _)), None) => format!("Cursor: ({}, {})", x, y),
+            (None, _) => "Hover the image to plot a spectrum".to_string(),
+        };
+
+        let mut chart = LineChart::new()
+            .x_label(x_label)
+            .empty_text("No spectrum")
+            .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+            .height(Length::Fixed(SPECTRUM_CHART_HEIGHT));
+        for pin in &self.pinned_spectra {
+            let color = Color::from_rgb_bytes(pin.color[0], pin.color[1], pin.color[2]);
+            chart = chart.series(ChartSeries::new(
+                spectrum_points(&pin.values, &band_info),
+                color,
+            ));
+        }
+        if let Some((_, _, values)) = &cursor {
+            chart = chart.series(
+                ChartSeries::new(spectrum_points(values, &band_info), theme.text_primary)
+                    .thickness(2.0),
+            );
+        }
+
+        let pins: Vec<(String, [u8; 3])> = self
+            .pinned_spectra
+            .iter()
+            .map(|pin| (pin.label(), pin.color))
+            .collect();
+
+        let collapsible = Collapsible::new("Spectrum")
+            .state(&self.spectrum_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::SpectrumToggled)
+            .content(|c| {
+                c.add(Element::new(chart));
+                c.text(&cursor_info)
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme.text_secondary);
+                c.text("P or Alt+click to pin a spectrum")
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme.text_placeholder);
+
+                for (index, (label, color)) in pins.iter().enumerate() {
+                    c.row(|r| {
+                        r.add(Element::new(
+                            ColorSwatch::new(*color)
+                                .width(Length::Fixed(12.0))
+                                .height(Length::Fixed(12.0)),
+                        ));
+                        r.text(label).size(FONT_SIZE_SMALL);
+                        r.button("x")
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::RemovePinnedSpectrum(index));
+                    });
+                }
+                if !pins.is_empty() {
+                    c.button("Clear Pinned")
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_click(Message::ClearPinnedSpectra);
+                }
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the preprocessing section: the project's chain of spectral
+    /// stages and inputs to append new ones.
+    fn build_preprocessing_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let steps: Vec<String> = self
+            .preprocessing
+            .steps
+            .iter()
+            .map(PreprocessStep::describe)
+            .collect();
+
+        let collapsible = Collapsible::new("Preprocessing")
+            .state(&self.preprocessing_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::PreprocessingToggled)
+            .content(|c| {
+                if steps.is_empty() {
+                    c.text("No preprocessing, bands are shown as loaded")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_placeholder);
+                }
+                for (index, step) in steps.iter().enumerate() {
+                    c.row(|r| {
+                        r.text(format!("{}. {}", index + 1, step))
+                            .size(FONT_SIZE_SMALL)
+                            .color(theme.text_secondary);
+                        r.button("x")
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::RemovePreprocessStep(index));
+                    });
+                }
+
+                c.text("Exclude bands").size(FONT_SIZE_BODY);
+                c.text_input()
+                    .placeholder("e.g. 1-5, 1350-1450nm")
+                    .value(&self.preprocess_exclude)
+                    .state(&self.preprocess_exclude_state)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_change(Message::PreprocessExcludeChanged)
+                    .on_submit(|_| Message::PreprocessExcludeSubmitted)
+                    .build();
+                c.button("Exclude Flagged Bad Bands")
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_click(Message::AddPreprocessStep(PreprocessStep::ExcludeBands(
+                        BandExclusion {
+                            flagged: true,
+                            ..Default::default()
+                        },
+                    )));
+
+                c.text("Savitzky-Golay window, order").size(FONT_SIZE_BODY);
+                c.text_input()
+                    .placeholder("e.g. 7, 2")
+                    .value(&self.preprocess_smoothing)
+                    .state(&self.preprocess_smoothing_state)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_change(Message::PreprocessSmoothingChanged)
+                    .on_submit(|_| Message::PreprocessSmoothingSubmitted)
+                    .build();
+
+                c.row(|r| {
+                    r.button("1st Deriv.")
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::AddPreprocessStep(PreprocessStep::Derivative {
+                            order: 1,
+                        }));
+                    r.button("2nd Deriv.")
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::AddPreprocessStep(PreprocessStep::Derivative {
+                            order: 2,
+                        }));
+                    r.button("Continuum")
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::AddPreprocessStep(PreprocessStep::ContinuumRemoval));
+                });
+                c.row(|r| {
+                    r.button("L2 Norm")
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::AddPreprocessStep(PreprocessStep::NormalizeL2));
+                    r.button("Area Norm")
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::AddPreprocessStep(PreprocessStep::NormalizeArea));
+                });
+
+                if let Some(error) = &self.preprocess_error {
+                    c.text(error)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(Color::from_rgb_bytes(
+                            LOAD_WARNING_RGB[0],
+                            LOAD_WARNING_RGB[1],
+                            LOAD_WARNING_RGB[2],
+                        ));
+                }
+                c.text("Stages run in order on every image of the project")
+                    .size(FONT_SIZE_SMALL)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .wrap(true)
+                    .color(theme.text_placeholder);
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the band-math section (expressions, saved expressions).
+    fn build_band_math_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let mode = self.band_math_mode;
+        let error = self.band_math_error.clone();
+        let presets: Vec<(String, String)> = self
+            .band_math_presets
+            .iter()
+            .map(|p| (p.name.clone(), p.expressions.join(" | ")))
+            .collect();
+
+        // Value range of each output channel on the displayed image
+        let path = self.current_image_path();
+        let value_info: Vec<String> = match (&self.band_math_program, &self.band_math_histograms) {
+            (Some(program), Some((hist_path, histograms))) if *hist_path == path => {
+                let names: &[&str] = if program.channels() == 1 {
+                    &["Index"]
+                } else {
+                    &["R", "G", "B"]
+                };
+                names
+                    .iter()
+                    .zip(histograms)
+                    .map(|(name, hist)| {
+                        format!(
+                            "{}: {:.4} - {:.4} (mean {:.4})",
+                            name, hist.min, hist.max, hist.mean
+                        )
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let collapsible = Collapsible::new("Band Math")
+            .state(&self.band_math_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::BandMathToggled)
+            .content(|c| {
+                c.row(|r| {
+                    for &option in BandMathMode::all() {
+                        let label = if option == mode {
+                            format!("[{}]", option.name())
+                        } else {
+                            option.name().to_string()
+                        };
+                        r.button(label)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::BandMathModeChanged(option));
+                    }
+                });
+
+                match mode {
+                    BandMathMode::Off => {
+                        c.text("Bands are shown as selected above")
+                            .size(FONT_SIZE_SMALL)
+                            .color(theme.text_secondary);
+                    }
+                    BandMathMode::Rgb => {
+                        for (channel, name) in ["Red", "Green", "Blue"].into_iter().enumerate() {
+                            c.text(name).size(FONT_SIZE_BODY);
+                            c.text_input()
+                                .placeholder("e.g. b60 / b30")
+                                .value(&self.band_math_channels[channel])
+                                .state(&self.band_math_channel_states[channel])
+                                .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                                .on_change(move |text, state| {
+                                    Message::BandMathChannelChanged(channel, text, state)
+                                })
+                                .on_submit(|_| Message::BandMathApply)
+                                .build();
+                        }
+                    }
+                    BandMathMode::Index => {
+                        c.text("Expression").size(FONT_SIZE_BODY);
+                        c.text_input()
+                            .placeholder("e.g. (b80-b40)/(b80+b40)")
+                            .value(&self.band_math_index)
+                            .state(&self.band_math_index_state)
+                            .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                            .on_change(Message::BandMathIndexChanged)
+                            .on_submit(|_| Message::BandMathApply)
+                            .build();
+                    }
+                }
+
+                if mode != BandMathMode::Off {
+                    c.button("Apply")
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_click(Message::BandMathApply);
+                    c.text(
+                        "b1..bN bands, + - * / ^, abs sqrt ln exp, mean/sum/min/max(b10..b20, ...)",
+                    )
+                    .size(FONT_SIZE_SMALL)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .wrap(true)
+                    .color(theme.text_placeholder);
+                }
+                if let Some(error) = &error {
+                    c.text(error)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(Color::from_rgb_bytes(
+                            LOAD_WARNING_RGB[0],
+                            LOAD_WARNING_RGB[1],
+                            LOAD_WARNING_RGB[2],
+                        ));
+                }
+                for line in &value_info {
+                    c.text(line)
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_secondary);
+                }
+
+                c.text("Saved").size(FONT_SIZE_BODY);
+                if presets.is_empty() {
+                    c.text("No saved expressions")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_placeholder);
+                }
+                for (index, (name, expressions)) in presets.iter().enumerate() {
+                    c.row(|r| {
+                        r.button(name)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::BandMathLoad(index));
+                        r.button("x")
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::BandMathDelete(index));
+                    });
+                    c.text(expressions)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(theme.text_secondary);
+                }
+                if mode != BandMathMode::Off {
+                    c.text_input()
+                        .placeholder("Name, e.g. NDVI")
+                        .value(&self.band_math_name)
+                        .state(&self.band_math_name_state)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(Message::BandMathNameChanged)
+                        .on_submit(|_| Message::BandMathSave)
+                        .build();
+                    c.button("Save Expressions")
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_click(Message::BandMathSave);
+                }
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the colormap panel: pseudo-color for the red band or index.
+    fn build_colormap_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let selected = self.colormap;
+        let source = self.colormap_source();
+        let (fitted_min, fitted_max) = match self.colormap_value_range() {
+            Some((min, max)) => (format!("{}", min), format!("{}", max)),
+            None => (String::new(), String::new()),
+        };
+
+        let collapsible = Collapsible::new("Colormap")
+            .state(&self.colormap_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::ColormapToggled)
+            .content(|c| {
+                c.row(|r| {
+                    let options =
+                        std::iter::once(None).chain(Colormap::all().iter().copied().map(Some));
+                    for colormap in options {
+                        let name = colormap.map_or("Off", |m| m.name());
+                        let label = if colormap == selected {
+                            format!("[{}]", name)
+                        } else {
+                            name.to_string()
+                        };
+                        r.button(label)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::ColormapChanged(colormap));
+                    }
+                });
+
+                if selected.is_none() {
+                    c.text("Shows the red band or index in pseudo-color")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_secondary);
+                    return;
+                }
+                c.text(format!("Showing {}", source))
+                    .size(FONT_SIZE_SMALL)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .wrap(true)
+                    .color(theme.text_secondary);
+                let reverse_label = if self.colormap_reversed {
+                    "[Reversed]"
+                } else {
+                    "Reversed"
+                };
+                c.button(reverse_label)
+                    .padding(BUTTON_PADDING_COMPACT)
+                    .on_click(Message::ColormapReverseToggled);
+
+                c.text("Minimum").size(FONT_SIZE_BODY);
+                c.text_input()
+                    .placeholder(&fitted_min)
+                    .value(&self.colormap_min)
+                    .state(&self.colormap_min_state)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_change(Message::ColormapMinChanged)
+                    .on_submit(|_| Message::ColormapRangeSubmitted)
+                    .build();
+                c.text("Maximum").size(FONT_SIZE_BODY);
+                c.text_input()
+                    .placeholder(&fitted_max)
+                    .value(&self.colormap_max)
+                    .state(&self.colormap_max_state)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_change(Message::ColormapMaxChanged)
+                    .on_submit(|_| Message::ColormapRangeSubmitted)
+                    .build();
+                c.text(if self.colormap_range.is_some() {
+                    "Press Enter to apply"
+                } else {
+                    "Fitted to the data (or the stretch); press Enter to apply a range"
+                })
+                .size(FONT_SIZE_SMALL)
+                .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                .wrap(true)
+                .color(theme.text_placeholder);
+                c.button("Fit to Data")
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_click(Message::ColormapAutoRange);
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the principal components panel: PCA/MNF composite instead of bands.
+    fn build_components_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let selected = self.component_method;
+        let path = self.current_image_path();
+        let error = match &self.component_error {
+            Some((p, method, e)) if *p == path && Some(*method) == selected => Some(e.clone()),
+            _ => None,
+        };
+        // The previous components stay displayed while new ones compute
+        #[cfg(not(target_arch = "wasm32"))]
+        let computing = selected.is_some_and(|m| self.component_jobs.is_running(&path, m));
+        #[cfg(target_arch = "wasm32")]
+        let computing = false;
+        let textures = self.displayed_components();
+        let channels = ["Red", "Green", "Blue"];
+
+        let collapsible = Collapsible::new("Principal Components")
+            .state(&self.components_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::ComponentsToggled)
+            .content(|c| {
+                c.row(|r| {
+                    let options = std::iter::once(None)
+                        .chain(ComponentMethod::all().iter().copied().map(Some));
+                    for method in options {
+                        let name = method.map_or("Off", |m| m.name());
+                        let label = if method == selected {
+                            format!("[{}]", name)
+                        } else {
+                            name.to_string()
+                        };
+                        r.button(label)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::ComponentMethodChanged(method));
+                    }
+                });
+
+                if selected.is_none() {
+                    c.text("Shows principal components as RGB instead of bands")
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(theme.text_secondary);
+                    return;
+                }
+                if let Some(error) = &error {
+                    c.text(error)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(Color::from_rgb_bytes(
+                            LOAD_WARNING_RGB[0],
+                            LOAD_WARNING_RGB[1],
+                            LOAD_WARNING_RGB[2],
+                        ));
+                    return;
+                }
+                let Some(textures) = textures.filter(|_| !computing) else {
+                    c.text("Computing components...")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_secondary);
+                    return;
+                };
+
+                let components = &textures.components;
+                let explained = components.explained();
+                // Variance share for PCA, signal-to-noise ratio for MNF
+                let describe = |component: usize| {
+                    let name = format!("{}{}", components.method.prefix(), component + 1);
+                    match components.method {
+                        ComponentMethod::Pca => explained
+                            .get(component)
+                            .map(|share| format!("{} ({:.1}% of variance)", name, share * 100.0)),
+                        ComponentMethod::Mnf => components
+                            .eigenvalues
+                            .get(component)
+                            .map(|snr| format!("{} (SNR {:.2})", name, snr)),
+                    }
+                    .unwrap_or(name)
+                };
+                c.text(format!(
+                    "{} of {} components, from {} pixels",
+                    textures.num_components(),
+                    components.num_components(),
+                    components.samples
+                ))
+                .size(FONT_SIZE_SMALL)
+                .color(theme.text_secondary);
+
+                let selection = self.component_selection(textures);
+                let max_component = textures.num_components().saturating_sub(1) as f32;
+                for (channel, name) in channels.iter().enumerate() {
+                    c.text(format!("{} Channel", name)).size(FONT_SIZE_BODY);
+                    c.slider(0.0, max_component)
+                        .state(&self.component_sliders[channel])
+                        .step(1.0)
+                        .show_input(true)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .on_change(move |state| Message::ComponentChanged(channel, state))
+                        .build();
+                    c.text(describe(selection[channel]))
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_secondary);
+                }
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the true-color panel: sRGB from the visible spectrum instead of
+    /// bands.
+    fn build_true_color_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let selected = self.true_color_illuminant;
+        let path = self.current_image_path();
+        let error = match &self.true_color_error {
+            Some((p, illuminant, e)) if *p == path && Some(*illuminant) == selected => {
+                Some(e.clone())
+            }
+            _ => None,
+        };
+        let shown = self.displayed_true_color().is_some();
+
+        let collapsible = Collapsible::new("True Color")
+            .state(&self.true_color_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::TrueColorToggled)
+            .content(|c| {
+                c.row(|r| {
+                    let options =
+                        std::iter::once(None).chain(Illuminant::all().iter().copied().map(Some));
+                    for illuminant in options {
+                        let name = illuminant.map_or("Off", |i| i.name());
+                        let label = if illuminant == selected {
+                            format!("[{}]", name)
+                        } else {
+                            name.to_string()
+                        };
+                        r.button(label)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::TrueColorIlluminantChanged(illuminant));
+                    }
+                });
+
+                if let Some(error) = &error {
+                    c.text(error)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(Color::from_rgb_bytes(
+                            LOAD_WARNING_RGB[0],
+                            LOAD_WARNING_RGB[1],
+                            LOAD_WARNING_RGB[2],
+                        ));
+                    return;
+                }
+                let description = match selected {
+                    None => "Shows the visible spectrum as sRGB (CIE 1931) instead of bands",
+                    Some(_) if !shown => "Computing true color...",
+                    Some(_) => "sRGB of the 380-780 nm spectrum, read as reflectance",
+                };
+                c.text(description)
+                    .size(FONT_SIZE_SMALL)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .wrap(true)
+                    .color(theme.text_secondary);
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the similarity map panel: distance to a reference spectrum.
+    fn build_similarity_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let metric = self.similarity_metric;
+        let has_map = self.current_similarity_map().is_some();
+        let threshold = self.similarity_threshold();
+        let region_count = self.similarity_regions.len();
+
+        let collapsible = Collapsible::new("Similarity Map")
+            .state(&self.similarity_collapsed)
+            .width(Length::Fill(1.0))
+            .on_toggle(Message::SimilarityToggled)
+            .content(|c| {
+                c.row(|r| {
+                    for &option in SimilarityMetric::all() {
+                        let label = if option == metric {
+                            format!("[{}]", option.name())
+                        } else {
+                            option.name().to_string()
+                        };
+                        r.button(label)
+                            .padding(BUTTON_PADDING_COMPACT)
+                            .on_click(Message::SimilarityMetricChanged(option));
+                    }
+                });
+
+                let reference = match &self.similarity_reference {
+                    Some((source, _)) => format!("Reference: {}", source),
+                    None => "No reference spectrum".to_string(),
+                };
+                c.text(reference)
+                    .size(FONT_SIZE_SMALL)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .wrap(true)
+                    .color(theme.text_secondary);
+                c.row(|r| {
+                    let pick_label = if self.similarity_picking {
+                        "[Pick Pixel]"
+                    } else {
+                        "Pick Pixel"
+                    };
+                    r.button(pick_label)
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::SimilarityPickPixel);
+                    r.button("Annotation Mean")
+                        .padding(BUTTON_PADDING_COMPACT)
+                        .on_click(Message::SimilarityUseAnnotation);
+                });
+                if self.similarity_picking {
+                    c.text("Click a pixel on the image")
+                        .size(FONT_SIZE_SMALL)
+                        .color(theme.text_placeholder);
+                }
+                if let Some((path, error)) = &self.similarity_error
+                    && *path == self.current_image_path()
+                {
+                    c.text(error)
+                        .size(FONT_SIZE_SMALL)
+                        .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                        .wrap(true)
+                        .color(Color::from_rgb_bytes(
+                            LOAD_WARNING_RGB[0],
+                            LOAD_WARNING_RGB[1],
+                            LOAD_WARNING_RGB[2],
+                        ));
+                }
+                if !has_map {
+                    return;
+                }
+
+                let show_label = if self.similarity_shown {
+                    "[Show Map]"
+                } else {
+                    "Show Map"
+                };
+                c.button(show_label)
+                    .padding(BUTTON_PADDING_COMPACT)
+                    .on_click(Message::SimilarityShowToggled);
+
+                let threshold_text = match threshold {
+                    Some(value) => format!("Threshold: {:.4}", value),
+                    None => "Threshold".to_string(),
+                };
+                c.text(threshold_text).size(FONT_SIZE_BODY);
+                c.slider(0.0, 1.0)
+                    .state(&self.similarity_threshold_slider)
+                    .step(SIMILARITY_THRESHOLD_STEP)
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_change(Message::SimilarityThresholdChanged)
+                    .build();
+                c.text(format!("{} matching regions", region_count))
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme.text_secondary);
+                c.button(format!("Create {} Polygons", region_count))
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_click(Message::SimilarityCreateAnnotations);
+                c.button("Clear Reference")
+                    .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+                    .on_click(Message::SimilarityClear);
+            });
+        Element::new(collapsible)
+    }
+
+    /// Build the band statistics of the selected annotation.
+    fn build_annotation_stats_panel(&self) -> Element<Message> {
+        let theme = current_theme();
+        let band_info = self.current_band_info();
+        let path = self.current_image_path();
+        let stats = self
+            .annotation_stats
+            .as_ref()
+            .filter(|s| s.image_path == path)
+            .map(|s| &s.stats);
+
+        let mut chart = LineChart::new()
+            .x_label(if has_wavelength_axis(&band_info) {
+                "nm"
+            } else {
+                "band"
+            })
+            .empty_text("No annotation selected")
+            .width(Length::Fixed(SIDEBAR_CONTENT_WIDTH))
+            .height(Length::Fixed(SPECTRUM_CHART_HEIGHT));
+        let mut summary = "Select an annotation".to_string();
+        let mut band_lines = Vec::new();
+        if let Some(stats) = stats {
+            let offset = |sign: f32| -> Vec<f32> {
+                stats
+                    .mean
+                    .iter()
+                    .zip(&stats.std_dev)
+                    .map(|(m, s)| m + sign * s)
+                    .collect()
+            };
+            let series = [
+                (stats.min.clone(), theme.border, 1.0),
+                (stats.max.clone(), theme.border, 1.0),
+                (offset(-1.0), theme.text_secondary, 1.0),
+                (offset(1.0), theme.text_secondary, 1.0),
+                (stats.mean.clone(), theme.accent, 2.0),
+            ];
+            for (values, color, thickness) in series {
+                chart = chart.series(
+                    ChartSeries::new(spectrum_points(&values, &band_info), color)
+                        .thickness(thickness),
+                );
+            }
+
+            summary = format!("{} pixels (mean, ±1 std, min/max)", stats.pixel_count);
+            band_lines = (0..stats.num_bands())
+                .map(|band| {
+                    let label = match band_info.get(band).and_then(|b| b.wavelength) {
+                        Some(wavelength) => format!("{:.1} nm", wavelength),
+                        None => format!("Band {}", band + 1),
+                    };
+                    format!(
+                        "{}: {:.4} ± {:.4} [{:.4}, {:.4}]",
+                        label,
+                        stats.mean[band],
+                        stats.std_dev[band],
+                        stats.min[band],
+                        stats.max[band]
+                    )
+                })
+                .collect();
+        }
+
+        let collapsible = Collapsible::new("Annotation Statistics")
+            .state(&self.annotation_stats_collapsed)
+            .scroll_state(&self.annotation_stats_scroll_state)
+            .width(Length::Fill(1.0))
+            .max_height(STATS_BANDS_MAX_HEIGHT)
+            .on_toggle(Message::AnnotationStatsToggled)
+            .on_scroll(Message::AnnotationStatsScrolled)
+            .content(|c| {
+                c.add(Element::new(chart));
+                c.text(&summary)
+                    .size(FONT_SIZE_SMALL)
+                    .color(theme.text_secondary);
+                for line in &band_lines {
+                    c.text(line).size(FONT_SIZE_SMALL);
+                }
+            });
+        Element::new(collapsible)
+    }
 }
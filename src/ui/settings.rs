@@ -10,7 +10,8 @@ use hvat_ui::{
 
 use crate::app::HvatApp;
 use crate::config::LogLevel;
-use crate::constants::MAX_GPU_PRELOAD_COUNT;
+use crate::constants::{LOAD_BINNING_FACTORS, MAX_GPU_PRELOAD_COUNT, MAX_LOAD_BAND_STRIDE};
+use crate::data::NpyLayout;
 use crate::keybindings::{KeybindTarget, key_to_string};
 use crate::licenses::{DEPENDENCIES, DependencyInfo};
 use crate::message::Message;
@@ -46,6 +47,7 @@ impl HvatApp {
         let appearance_section_collapsed = self.appearance_section_collapsed;
         let keybindings_section_collapsed = self.keybindings_section_collapsed;
         let folders_section_collapsed = self.folders_section_collapsed;
+        let loading_section_collapsed = self.loading_section_collapsed;
         let performance_section_collapsed = self.performance_section_collapsed;
         let dependencies_collapsed = self.dependencies_collapsed;
         let license_collapsed = self.license_collapsed.clone();
@@ -57,6 +59,29 @@ impl HvatApp {
         let export_folder_state = self.export_folder_state.clone();
         let import_folder = self.import_folder.clone();
         let import_folder_state = self.import_folder_state.clone();
+        let npz_cube_key = self.loader_options.npz_cube_key.clone().unwrap_or_default();
+        let npz_cube_key_state = self.npz_cube_key_state.clone();
+        let npy_layout = self.loader_options.npy_layout;
+        let npy_layout_rules = self.loader_options.npy_layout_rules.clone();
+        let npy_rule_pattern = self.npy_rule_pattern.clone();
+        let npy_rule_pattern_state = self.npy_rule_pattern_state.clone();
+        let load_band_range = self.load_band_range.clone();
+        let load_band_range_state = self.load_band_range_state.clone();
+        let load_band_stride = self.loader_options.subset.band_stride;
+        let load_band_stride_slider = self.load_band_stride_slider.clone();
+        let load_binning = self.loader_options.subset.binning;
+        let calibration_rules = self.loader_options.calibration_rules.clone();
+        let has_project = self.project.is_some();
+        let calibration_pattern = self.calibration_pattern.clone();
+        let calibration_pattern_state = self.calibration_pattern_state.clone();
+        let calibration_dark = self.calibration_dark.clone();
+        let calibration_dark_state = self.calibration_dark_state.clone();
+        let calibration_white = self.calibration_white.clone();
+        let calibration_white_state = self.calibration_white_state.clone();
+        let calibration_gain = self.calibration_gain.clone();
+        let calibration_gain_state = self.calibration_gain_state.clone();
+        let calibration_offset = self.calibration_offset.clone();
+        let calibration_offset_state = self.calibration_offset_state.clone();
         let gpu_preload_count = self.gpu_preload_count;
         let gpu_preload_slider = self.gpu_preload_slider.clone();
 
@@ -162,6 +187,193 @@ impl HvatApp {
 
                 c.text("");
 
+                // --------------------------------
+                // File Loading subsection (collapsible)
+                // --------------------------------
+                let loading_collapsible = Collapsible::new("File Loading")
+                    .state(&loading_section_collapsed)
+                    .on_toggle(Message::LoadingSectionToggled)
+                    .content(|lc| {
+                        lc.row(|r| {
+                            r.text("NPZ cube key:");
+                            r.text_input()
+                                .placeholder("auto (largest 3D)")
+                                .value(&npz_cube_key)
+                                .state(&npz_cube_key_state)
+                                .width(Length::Fixed(200.0))
+                                .on_change(Message::NpzCubeKeyChanged)
+                                .build();
+                        });
+                        lc.text("Array used as the data cube in .npz files (applies to newly loaded images)")
+                            .size(FONT_SIZE_SMALL);
+
+                        lc.text("");
+
+                        // Default .npy/.npz axis layout
+                        lc.row(|r| {
+                            r.text("NPY layout:");
+                            let options = std::iter::once(None)
+                                .chain(NpyLayout::all().iter().copied().map(Some));
+                            for layout in options {
+                                let name = layout.map_or("Auto", |l| l.name());
+                                let label = if layout == npy_layout {
+                                    format!("[{}]", name)
+                                } else {
+                                    name.to_string()
+                                };
+                                r.button(label)
+                                    .padding(BUTTON_PADDING_COMPACT)
+                                    .on_click(Message::NpyLayoutChanged(layout));
+                            }
+                        });
+                        lc.text("Axis order of .npy/.npz cubes (B = bands, H = height, W = width). Auto guesses from the shape and warns")
+                            .size(FONT_SIZE_SMALL);
+
+                        // Per-pattern overrides (first match wins)
+                        for (idx, rule) in npy_layout_rules.iter().enumerate() {
+                            lc.row(|r| {
+                                r.text(format!("{} -> {}", rule.pattern, rule.layout.name()));
+                                r.button("x")
+                                    .width(Length::Fixed(20.0))
+                                    .padding(BUTTON_PADDING_COMPACT)
+                                    .on_click(Message::RemoveNpyLayoutRule(idx));
+                            });
+                        }
+                        lc.row(|r| {
+                            r.text("Rule:");
+                            r.text_input()
+                                .placeholder("*.npy or /path/to/folder/*")
+                                .value(&npy_rule_pattern)
+                                .state(&npy_rule_pattern_state)
+                                .width(Length::Fixed(200.0))
+                                .on_change(Message::NpyLayoutRulePatternChanged)
+                                .build();
+                            for layout in NpyLayout::all() {
+                                r.button(layout.name())
+                                    .padding(BUTTON_PADDING_COMPACT)
+                                    .on_click(Message::AddNpyLayoutRule(*layout));
+                            }
+                        });
+                        lc.text("Layout for files matching a pattern (file name, or full path if it contains /)")
+                            .size(FONT_SIZE_SMALL);
+
+                        lc.text("");
+
+                        // Band subset and binning
+                        lc.row(|r| {
+                            r.text("Bands:");
+                            r.text_input()
+                                .placeholder("all (e.g. 10-120)")
+                                .value(&load_band_range)
+                                .state(&load_band_range_state)
+                                .width(Length::Fixed(120.0))
+                                .on_change(Message::LoadBandRangeChanged)
+                                .build();
+                        });
+                        lc.row(|r| {
+                            r.text("Band stride:");
+                            r.slider(1.0, MAX_LOAD_BAND_STRIDE as f32)
+                                .step(1.0)
+                                .state(&load_band_stride_slider)
+                                .width(Length::Fixed(120.0))
+                                .on_change(Message::LoadBandStrideChanged)
+                                .build();
+                            r.text(format!("{}", load_band_stride));
+                        });
+                        lc.row(|r| {
+                            r.text("Binning:");
+                            for &factor in LOAD_BINNING_FACTORS {
+                                let name = format!("{}x{}", factor, factor);
+                                let label = if factor == load_binning {
+                                    format!("[{}]", name)
+                                } else {
+                                    name
+                                };
+                                r.button(label)
+                                    .padding(BUTTON_PADDING_COMPACT)
+                                    .on_click(Message::LoadBinningChanged(factor));
+                            }
+                        });
+                        lc.text("Load a band range, every n-th band of it, or n x n averaged pixels. ENVI and NumPy files on disk are read on demand and only these samples are read. Annotations use the pixel grid of the loaded image")
+                            .size(FONT_SIZE_SMALL);
+
+                        lc.text("");
+
+                        // Radiometric calibration rules (first match wins)
+                        for (idx, rule) in calibration_rules.iter().enumerate() {
+                            lc.row(|r| {
+                                r.text(rule.describe());
+                                r.button("x")
+                                    .width(Length::Fixed(20.0))
+                                    .padding(BUTTON_PADDING_COMPACT)
+                                    .on_click(Message::RemoveCalibrationRule(idx));
+                            });
+                        }
+                        lc.row(|r| {
+                            r.text("Calibrate:");
+                            r.text_input()
+                                .placeholder("*.hdr or /path/to/folder/*")
+                                .value(&calibration_pattern)
+                                .state(&calibration_pattern_state)
+                                .width(Length::Fixed(200.0))
+                                .on_change(Message::CalibrationPatternChanged)
+                                .build();
+                        });
+                        lc.row(|r| {
+                            r.text("Dark:");
+                            r.text_input()
+                                .placeholder("DARKREF_{stem}.hdr")
+                                .value(&calibration_dark)
+                                .state(&calibration_dark_state)
+                                .width(Length::Fixed(200.0))
+                                .on_change(Message::CalibrationDarkChanged)
+                                .build();
+                        });
+                        lc.row(|r| {
+                            r.text("White:");
+                            r.text_input()
+                                .placeholder("WHITEREF_{stem}.hdr")
+                                .value(&calibration_white)
+                                .state(&calibration_white_state)
+                                .width(Length::Fixed(200.0))
+                                .on_change(Message::CalibrationWhiteChanged)
+                                .build();
+                        });
+                        lc.row(|r| {
+                            r.text("Gain:");
+                            r.text_input()
+                                .placeholder("1")
+                                .value(&calibration_gain)
+                                .state(&calibration_gain_state)
+                                .width(Length::Fixed(80.0))
+                                .on_change(Message::CalibrationGainChanged)
+                                .build();
+                            r.text("Offset:");
+                            r.text_input()
+                                .placeholder("0")
+                                .value(&calibration_offset)
+                                .state(&calibration_offset_state)
+                                .width(Length::Fixed(80.0))
+                                .on_change(Message::CalibrationOffsetChanged)
+                                .build();
+                        });
+                        lc.row(|r| {
+                            r.button("Add rule")
+                                .padding(BUTTON_PADDING_COMPACT)
+                                .on_click(Message::AddCalibrationRule(false));
+                            if has_project {
+                                r.button("Add for project")
+                                    .padding(BUTTON_PADDING_COMPACT)
+                                    .on_click(Message::AddCalibrationRule(true));
+                            }
+                        });
+                        lc.text("Calibrated value = (raw - dark) / (white - dark) * gain + offset, per band and column (reference rows are averaged). {stem} is the image file name without extension; relative paths are next to the image. Gain and offset take one value or one per loaded band")
+                            .size(FONT_SIZE_SMALL);
+                    });
+                c.add(Element::new(loading_collapsible));
+
+                c.text("");
+
                 // --------------------------------
                 // Performance subsection (collapsible)
                 // --------------------------------